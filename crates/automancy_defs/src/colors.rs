@@ -59,3 +59,15 @@ pub const TEXT_INACTIVE: Color = hex_color!("#9a9a9a");
 
 pub const INPUT: Color = hex_color!("#44c8ff");
 pub const OUTPUT: Color = hex_color!("#ff9844");
+
+/// Colors used to tell tiles apart in the map overlays.
+pub const OVERLAY_PALETTE: [Color; 8] = [
+    hex_color!("#e6194b"),
+    hex_color!("#3cb44b"),
+    hex_color!("#ffe119"),
+    hex_color!("#4363d8"),
+    hex_color!("#f58231"),
+    hex_color!("#911eb4"),
+    hex_color!("#42d4f4"),
+    hex_color!("#f032e6"),
+];
@@ -1,7 +1,7 @@
 use crate::math::{Matrix4, FAR, HEX_GRID_LAYOUT};
 use hexx::{EdgeDirection, Hex, HexBounds};
 use serde::{Deserialize, Serialize};
-use std::cmp::{max, min};
+use std::cmp::{max, min, Ordering};
 use std::fmt::{Display, Formatter};
 use std::ops::{Add, Deref, Div, Mul, Neg, Sub};
 
@@ -77,6 +77,21 @@ impl TileCoord {
     }
 }
 
+/// Coordinates are ordered row by row: by r first, then by q.
+///
+/// Anything that walks tiles in a way that affects the game, or ends up on disk, goes in this order.
+impl Ord for TileCoord {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.y, self.x).cmp(&(other.y, other.x))
+    }
+}
+
+impl PartialOrd for TileCoord {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Display for TileCoord {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!("[{}, {}]", self.0.x, self.0.y))
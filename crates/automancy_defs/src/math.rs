@@ -59,17 +59,20 @@ fn projection(aspect: Float) -> Matrix4 {
     Matrix4::perspective_lh(fov(), aspect, z_near(), z_far())
 }
 
-fn camera_view(pos: Vec3) -> Matrix4 {
+/// The view from the camera, tilted by its zoom and turned by the rotation around the Z axis, in radians.
+fn camera_view(pos: Vec3, rotation: Float) -> Matrix4 {
+    let rotation = Quaternion::from_rotation_z(rotation);
+
     Matrix4::look_to_rh(
         pos,
-        Quaternion::from_rotation_x(camera_angle(pos.z)) * vec3(0.0, 0.0, 1.0),
-        vec3(0.0, 1.0, 0.0),
+        rotation * Quaternion::from_rotation_x(camera_angle(pos.z)) * vec3(0.0, 0.0, 1.0),
+        rotation * vec3(0.0, 1.0, 0.0),
     )
 }
 
-pub fn camera_matrix(pos: Vec3, aspect: Float) -> Matrix4 {
+pub fn camera_matrix(pos: Vec3, rotation: Float, aspect: Float) -> Matrix4 {
     let projection = projection(aspect);
-    let view = camera_view(pos);
+    let view = camera_view(pos, rotation);
 
     projection * view
 }
@@ -102,18 +105,40 @@ pub fn main_pos_to_fract_hex(
     (width, height): (Float, Float),
     main_pos: Vec2,
     camera_pos: Vec3,
+    rotation: Float,
 ) -> Vec2 {
-    let p = screen_to_world((width, height), main_pos, camera_pos);
+    let p = screen_to_world((width, height), main_pos, camera_pos, rotation);
 
     HEX_GRID_LAYOUT.world_pos_to_fract_hex(vec2(p.x as Float, p.y as Float))
 }
 
 /// Converts screen coordinates to world coordinates.
 #[inline]
-pub fn screen_to_world((width, height): (Float, Float), pos: Vec2, camera_pos: Vec3) -> Vec3 {
+pub fn screen_to_world(
+    (width, height): (Float, Float),
+    pos: Vec2,
+    camera_pos: Vec3,
+    rotation: Float,
+) -> Vec3 {
     let pos = screen_to_normalized((width, height), pos);
 
-    normalized_to_world((width, height), pos, camera_pos)
+    normalized_to_world((width, height), pos, camera_pos, rotation)
+}
+
+/// Converts world coordinates to screen coordinates, the other way around from [screen_to_world].
+#[inline]
+pub fn world_to_screen(
+    (width, height): (Float, Float),
+    pos: Vec3,
+    camera_pos: Vec3,
+    rotation: Float,
+) -> Vec2 {
+    let clip = camera_matrix(camera_pos, rotation, width / height) * pos.extend(1.0);
+    let normalized = clip.truncate().truncate() / clip.w;
+
+    let size = vec2(width, height) * 0.5;
+
+    normalized * size + size
 }
 
 /// Converts normalized screen coordinates to world coordinates.
@@ -122,10 +147,12 @@ pub fn normalized_to_world(
     (width, height): (Float, Float),
     normalized: Vec2,
     camera_pos: Vec3,
+    rotation: Float,
 ) -> Vec3 {
     let aspect = width / height;
 
-    let matrix = camera_view(vec3(0.0, 0.0, camera_pos.z)).inverse() * projection(aspect).inverse();
+    let matrix = camera_view(vec3(0.0, 0.0, camera_pos.z), rotation).inverse()
+        * projection(aspect).inverse();
 
     let pos = vec4(normalized.x, normalized.y, -1.0, 1.0);
     let pos = matrix * pos;
@@ -143,11 +170,15 @@ pub fn normalized_to_world(
     p + camera_pos
 }
 
-pub fn get_screen_world_bounding_vec(size: (Float, Float), camera_pos: Vec3) -> (Vec2, Vec2) {
-    let a = normalized_to_world(size, vec2(-1.0, -1.0), camera_pos).truncate();
-    let b = normalized_to_world(size, vec2(-1.0, 1.0), camera_pos).truncate();
-    let c = normalized_to_world(size, vec2(1.0, -1.0), camera_pos).truncate();
-    let d = normalized_to_world(size, vec2(1.0, 1.0), camera_pos).truncate();
+pub fn get_screen_world_bounding_vec(
+    size: (Float, Float),
+    camera_pos: Vec3,
+    rotation: Float,
+) -> (Vec2, Vec2) {
+    let a = normalized_to_world(size, vec2(-1.0, -1.0), camera_pos, rotation).truncate();
+    let b = normalized_to_world(size, vec2(-1.0, 1.0), camera_pos, rotation).truncate();
+    let c = normalized_to_world(size, vec2(1.0, -1.0), camera_pos, rotation).truncate();
+    let d = normalized_to_world(size, vec2(1.0, 1.0), camera_pos, rotation).truncate();
 
     let min = a.min(b).min(c.min(d));
     let max = a.max(b).max(c.max(d));
@@ -155,18 +186,31 @@ pub fn get_screen_world_bounding_vec(size: (Float, Float), camera_pos: Vec3) ->
     (min, max)
 }
 
-/// Gets the culling range from the camera's position
-pub fn get_culling_range(size: (Float, Float), camera_pos: Vec3) -> TileBounds {
-    let (bound_min, bound_max) = get_screen_world_bounding_vec(size, camera_pos);
+/// The fewest tiles the culling range reaches past the edge of the screen.
+const MIN_CULLING_MARGIN: Float = 2.0;
+/// How many more tiles the culling range reaches past the edge of the screen per unit of camera height,
+/// so that panning quickly while zoomed far out doesn't show the tiles popping in.
+const CULLING_MARGIN_PER_HEIGHT: Float = 0.25;
+
+/// Gets how many tiles past the edge of the screen the culling range reaches, at the camera's height.
+#[inline]
+pub fn culling_margin(camera_pos: Vec3) -> Float {
+    MIN_CULLING_MARGIN + camera_pos.z.max(0.0) * CULLING_MARGIN_PER_HEIGHT
+}
+
+/// Gets the culling range from the camera's position, reaching [culling_margin] tiles past the edge of the screen.
+pub fn get_culling_range(size: (Float, Float), camera_pos: Vec3, rotation: Float) -> TileBounds {
+    let (bound_min, bound_max) = get_screen_world_bounding_vec(size, camera_pos, rotation);
 
     let size = bound_max - bound_min;
     let bound_center = size / 2.0 + bound_min;
 
     let size = HEX_GRID_LAYOUT.world_pos_to_hex((size / 2.0).ceil());
+    let margin = culling_margin(camera_pos).ceil() as u32;
 
     TileBounds::new(
         HEX_GRID_LAYOUT.world_pos_to_hex(bound_center).into(),
-        size.ulength(),
+        size.ulength() + margin,
     )
 }
 
@@ -80,6 +80,11 @@ pub fn derive_id_reg(item: TokenStream) -> TokenStream {
 
     let name = ast.ident;
 
+    let fields = namespaces
+        .iter()
+        .map(|(field, _)| quote! { self.#field, })
+        .collect::<TokenStream>();
+
     let items = namespaces
         .into_iter()
         .zip(names)
@@ -97,6 +102,11 @@ pub fn derive_id_reg(item: TokenStream) -> TokenStream {
                     #items
                 }
             }
+
+            /// Every ID in the registry, in the order the fields are declared.
+            pub fn all(&self) -> Vec<automancy_defs::id::Id> {
+                vec![#fields]
+            }
         }
     }
 }
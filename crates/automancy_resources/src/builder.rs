@@ -0,0 +1,47 @@
+use crate::types::tile::TileDef;
+use crate::ResourceManager;
+use automancy_defs::id::{Id, TileId};
+use automancy_defs::kira::track::TrackHandle;
+
+/// Builds a resource manager from definitions given in code rather than loaded from resource packs,
+/// for tools and tests that need a registry of their own.
+pub struct ResourceManagerBuilder {
+    resource_man: ResourceManager,
+}
+
+impl ResourceManagerBuilder {
+    /// Creates a builder of an empty resource manager.
+    pub fn new(track: TrackHandle) -> Self {
+        Self {
+            resource_man: ResourceManager::new(track),
+        }
+    }
+
+    /// Adds a tile without a function, category or data. The ID is read in the namespace if it has none of its own.
+    pub fn tile(mut self, id: &str, namespace: &str) -> Self {
+        let id = TileId(Id::parse(id, &mut self.resource_man.interner, Some(namespace)).unwrap());
+
+        self.resource_man.registry.tiles.insert(
+            id,
+            TileDef {
+                id,
+                function: None,
+                category: None,
+                data: Default::default(),
+                actions: Default::default(),
+                footprint: Default::default(),
+                connects_to: Default::default(),
+                variants: Default::default(),
+            },
+        );
+
+        self
+    }
+
+    /// Builds the resource manager, with the tiles ordered as they are after loading.
+    pub fn build(mut self) -> ResourceManager {
+        self.resource_man.ordered_tiles();
+
+        self.resource_man
+    }
+}
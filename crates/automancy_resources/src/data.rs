@@ -6,7 +6,7 @@ use automancy_defs::{
     try_parse_ids,
 };
 use automancy_defs::{glam::IVec2, try_parse_map_id_of};
-use automancy_defs::{hex, try_parse_map_v_id};
+use automancy_defs::{hex, math::Float, try_parse_map_v_id};
 use automancy_defs::{
     hexx::{Hex, OffsetHexMode},
     parse_ids, parse_map_id_of, parse_map_v_id,
@@ -46,9 +46,49 @@ pub enum Data {
     Bool(bool),
     TileMap(HashMap<TileCoord, Id>),
     MapSetId(HashMap<Id, HashSet<Id>>),
+    VecFloat(Vec<Float>),
+    VecString(Vec<String>),
+}
+
+/// The variant of a [Data], without its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DataKind {
+    Inventory,
+    Coord,
+    VecCoord,
+    TileBounds,
+    Id,
+    Color,
+    VecId,
+    SetId,
+    Amount,
+    Bool,
+    TileMap,
+    MapSetId,
+    VecFloat,
+    VecString,
 }
 
 impl Data {
+    pub const fn kind(&self) -> DataKind {
+        match self {
+            Data::Inventory(_) => DataKind::Inventory,
+            Data::Coord(_) => DataKind::Coord,
+            Data::VecCoord(_) => DataKind::VecCoord,
+            Data::TileBounds(_) => DataKind::TileBounds,
+            Data::Id(_) => DataKind::Id,
+            Data::Color(_) => DataKind::Color,
+            Data::VecId(_) => DataKind::VecId,
+            Data::SetId(_) => DataKind::SetId,
+            Data::Amount(_) => DataKind::Amount,
+            Data::Bool(_) => DataKind::Bool,
+            Data::TileMap(_) => DataKind::TileMap,
+            Data::MapSetId(_) => DataKind::MapSetId,
+            Data::VecFloat(_) => DataKind::VecFloat,
+            Data::VecString(_) => DataKind::VecString,
+        }
+    }
+
     pub fn into_coord(self) -> Option<TileCoord> {
         if let Self::Coord(v) = self {
             return Some(v);
@@ -91,6 +131,8 @@ impl Data {
             Data::Bool(v) => Dynamic::from_bool(v),
             Data::TileMap(v) => Dynamic::from(v),
             Data::MapSetId(v) => Dynamic::from(v),
+            Data::VecFloat(v) => Dynamic::from(v),
+            Data::VecString(v) => Dynamic::from(v),
         }
     }
 
@@ -119,6 +161,10 @@ impl Data {
             Data::TileMap(v.cast())
         } else if id == TypeId::of::<HashMap<Id, HashSet<Id>>>() {
             Data::MapSetId(v.cast())
+        } else if id == TypeId::of::<Vec<Float>>() {
+            Data::VecFloat(v.cast())
+        } else if id == TypeId::of::<Vec<String>>() {
+            Data::VecString(v.cast())
         } else {
             return None;
         })
@@ -126,30 +172,52 @@ impl Data {
 }
 
 impl Data {
+    /// Converts the data to its raw form. Entries of hashed collections come out sorted, so the same data always serializes to the same bytes.
     fn try_to_raw(&self, interner: &Interner) -> Option<DataRaw> {
         Some(match self {
             Data::Id(v) => DataRaw::Id(interner.resolve(*v)?.to_string()),
             Data::VecId(v) => DataRaw::VecId(resolve_ids(v.iter().cloned(), interner)),
-            Data::SetId(v) => DataRaw::SetId(resolve_ids(v.iter().cloned(), interner)),
+            Data::SetId(v) => {
+                let mut ids: Vec<String> = resolve_ids(v.iter().cloned(), interner);
+                ids.sort();
+
+                DataRaw::SetId(ids)
+            }
             Data::Amount(v) => DataRaw::Amount(*v),
             Data::Bool(v) => DataRaw::Bool(*v),
             Data::Color(v) => DataRaw::Color(hex::encode([v.r, v.g, v.b, v.a])),
             Data::TileBounds(v) => DataRaw::TileBounds(*v),
             Data::TileMap(v) => {
-                DataRaw::TileMap(resolve_map_v_id(v.iter().map(|(a, b)| (*a, *b)), interner))
+                let mut map: Vec<(TileCoord, String)> =
+                    resolve_map_v_id(v.iter().map(|(a, b)| (*a, *b)), interner);
+                map.sort_by_key(|(coord, _)| *coord);
+
+                DataRaw::TileMap(map)
             }
             Data::Inventory(v) => DataRaw::Inventory(v.to_raw(interner)),
             Data::Coord(v) => DataRaw::Coord(*v),
             Data::VecCoord(v) => DataRaw::VecCoord(v.clone()),
-            Data::MapSetId(v) => DataRaw::MapSetId(resolve_map_id_of(
-                v.iter()
-                    .map(|(id, set)| (*id, resolve_ids(set.iter().cloned(), interner))),
-                interner,
-            )),
+            Data::MapSetId(v) => {
+                let mut map: Vec<(String, Vec<String>)> = resolve_map_id_of(
+                    v.iter().map(|(id, set)| {
+                        let mut set: Vec<String> = resolve_ids(set.iter().cloned(), interner);
+                        set.sort();
+
+                        (*id, set)
+                    }),
+                    interner,
+                );
+                map.sort();
+
+                DataRaw::MapSetId(map)
+            }
+            Data::VecFloat(v) => DataRaw::VecFloat(v.clone()),
+            Data::VecString(v) => DataRaw::VecString(v.clone()),
         })
     }
 }
 
+/// The data of a tile or a map. Iterates in key order.
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct DataMap(BTreeMap<Id, Data>);
 
@@ -206,6 +274,10 @@ impl DataMap {
         self.0.remove(&id)
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     pub fn entry(&mut self, id: Id) -> Entry<'_, Id, Data> {
         self.0.entry(id)
     }
@@ -262,9 +334,36 @@ pub enum DataRaw {
     TileMap(Vec<(TileCoord, String)>),
     TileMapOffsetCoord(Vec<(IVec2, String)>),
     MapSetId(Vec<(String, Vec<String>)>),
+    VecFloat(Vec<Float>),
+    VecString(Vec<String>),
 }
 
 impl DataRaw {
+    /// Returns the IDs this entry references, as they are written.
+    pub fn ids(&self) -> Vec<&String> {
+        match self {
+            DataRaw::Id(v) => vec![v],
+            DataRaw::VecId(v) | DataRaw::SetId(v) => v.iter().collect(),
+            DataRaw::TileMap(v) => v.iter().map(|(_, id)| id).collect(),
+            DataRaw::TileMapOffsetCoord(v) => v.iter().map(|(_, id)| id).collect(),
+            DataRaw::MapSetId(v) => v
+                .iter()
+                .flat_map(|(id, set)| Some(id).into_iter().chain(set.iter()))
+                .collect(),
+            DataRaw::Inventory(v) => v.ids().collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Returns the IDs this entry references that cannot be resolved, which would be dropped when converting it.
+    pub fn unresolved_ids(&self, interner: &Interner) -> Vec<String> {
+        self.ids()
+            .into_iter()
+            .filter(|id| Id::try_parse(id, interner).is_none())
+            .cloned()
+            .collect()
+    }
+
     pub fn to_data(&self, interner: &Interner) -> Option<Data> {
         Some(match self {
             DataRaw::Id(v) => Data::Id(Id::try_parse(v, interner)?),
@@ -303,6 +402,8 @@ impl DataRaw {
                 Data::VecCoord(v.iter().map(|v| offset_to_tile(v.to_array())).collect())
             }
             DataRaw::TileBounds(v) => Data::TileBounds(*v),
+            DataRaw::VecFloat(v) => Data::VecFloat(v.clone()),
+            DataRaw::VecString(v) => Data::VecString(v.clone()),
         })
     }
 
@@ -354,6 +455,17 @@ impl DataMapRaw {
         self.0
     }
 
+    pub fn get(&self, key: &str) -> Option<&DataRaw> {
+        self.0.get(key)
+    }
+
+    /// Returns the keys of the data, and the IDs its entries reference, as they are written.
+    pub fn ids(&self) -> impl Iterator<Item = &String> {
+        self.0
+            .iter()
+            .flat_map(|(key, value)| Some(key).into_iter().chain(value.ids()))
+    }
+
     pub fn to_data(&self, interner: &Interner) -> DataMap {
         DataMap(
             self.0
@@ -366,6 +478,45 @@ impl DataMapRaw {
         )
     }
 
+    /// Converts the data like [DataMapRaw::to_data], except that the entries referencing IDs that cannot be resolved are kept back as they are.
+    ///
+    /// Returns the converted data, the entries that were kept back, and the key and unresolved ID of every reference that could not be resolved.
+    pub fn to_data_quarantined(
+        &self,
+        interner: &Interner,
+    ) -> (DataMap, DataMapRaw, Vec<(String, String)>) {
+        let mut data = DataMap::default();
+        let mut quarantined = DataMapRaw::default();
+        let mut unresolved = vec![];
+
+        for (key, value) in &self.0 {
+            let mut missing = value.unresolved_ids(interner);
+
+            let converted = Id::try_parse(key, interner)
+                .and_then(|id| value.to_data(interner).map(|v| (id, v)));
+
+            if missing.is_empty() {
+                if let Some((id, value)) = converted {
+                    data.set(id, value);
+
+                    continue;
+                }
+
+                // the key itself could not be resolved
+                missing.push(key.clone());
+            }
+
+            quarantined.0.insert(key.clone(), value.clone());
+            unresolved.extend(missing.into_iter().map(|id| (key.clone(), id)));
+        }
+
+        (data, quarantined, unresolved)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     pub fn intern_to_data(&self, interner: &mut Interner, namespace: Option<&str>) -> DataMap {
         DataMap(
             self.0
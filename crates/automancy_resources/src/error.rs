@@ -1,6 +1,14 @@
-use crate::{format::FormatContext, ResourceManager};
+use crate::format::{format_context, FormatContext};
+use crate::ResourceManager;
 use automancy_defs::id::Id;
+use interpolator::Formattable;
 use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// How many of the latest errors the error log keeps.
+pub const ERROR_LOG_SIZE: usize = 100;
 
 /// An ErrorManager contains a queue of errors to be displayed.
 #[derive(Default)]
@@ -12,21 +20,122 @@ thread_local! {
     static ERROR_MAN: RefCell<ErrorManager> = RefCell::new(ErrorManager::default());
 }
 
+/// The errors to be shown as toasts rather than popups. Unlike the popup queue, these can be pushed from any thread.
+static TOAST_ERRORS: Mutex<Vec<(Id, String)>> = Mutex::new(Vec::new());
+
+/// An error as it was recorded in the error log, with what it was formatted with.
+#[derive(Debug, Clone)]
+pub struct LoggedError {
+    /// counts up with every error recorded, so that an entry can be told apart from the others
+    pub number: usize,
+    pub id: Id,
+    /// the translated text the error was formatted into
+    pub text: String,
+    /// the arguments the error was pushed with, each formatted on its own
+    pub args: Vec<(String, String)>,
+    pub time: SystemTime,
+}
+
+#[derive(Default)]
+struct ErrorLog {
+    entries: VecDeque<LoggedError>,
+    recorded: usize,
+}
+
+/// The latest errors, whether they were shown as popups or as toasts, so that they can still be looked at once dismissed.
+static ERROR_LOG: Mutex<ErrorLog> = Mutex::new(ErrorLog {
+    entries: VecDeque::new(),
+    recorded: 0,
+});
+
+/// Formats an argument on its own, the same way it would be in an error's text.
+fn format_arg(value: &Formattable) -> String {
+    let fmt = FormatContext::from([("v", value.clone())].into_iter());
+
+    interpolator::format("{v}", &fmt)
+        .or_else(|_| interpolator::format("{v:?}", &fmt))
+        .unwrap_or_else(|_| "<?>".to_string())
+}
+
+fn log_err(id: Id, text: &str, fmt: &FormatContext) {
+    let mut args = fmt
+        .iter()
+        .map(|(key, value)| (key.to_string(), format_arg(value)))
+        .collect::<Vec<_>>();
+    args.sort();
+
+    if let Ok(mut log) = ERROR_LOG.lock() {
+        if log.entries.len() >= ERROR_LOG_SIZE {
+            log.entries.pop_front();
+        }
+
+        let number = log.recorded;
+        log.recorded += 1;
+
+        log.entries.push_back(LoggedError {
+            number,
+            id,
+            text: text.to_string(),
+            args,
+            time: SystemTime::now(),
+        });
+    }
+}
+
+/// Copies the errors in the error log, the oldest first.
+pub fn error_log() -> Vec<LoggedError> {
+    ERROR_LOG
+        .lock()
+        .map(|log| log.entries.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Removes every error from the error log.
+pub fn clear_error_log() {
+    if let Ok(mut log) = ERROR_LOG.lock() {
+        log.entries.clear();
+    }
+}
+
 /// Gets the unlocalized key of an error's ID.
 pub fn error_to_key(id: Id, resource_man: &ResourceManager) -> &str {
     resource_man.interner.resolve(id).unwrap_or("")
 }
 
-/// Adds a new error to the queue.
-pub fn push_err(id: Id, fmt: &FormatContext, resource_man: &ResourceManager) {
+fn format_err(id: Id, fmt: &FormatContext, resource_man: &ResourceManager) -> String {
     log::error!("Recording game error: {}", error_to_key(id, resource_man));
 
-    let string = interpolator::format(&resource_man.translates.error[&id], fmt)
-        .expect("could not format error!");
+    let string = format_context(&resource_man.translates.error[&id], fmt);
+
+    log_err(id, &string, fmt);
+
+    string
+}
+
+/// Adds a new error to the queue.
+pub fn push_err(id: Id, fmt: &FormatContext, resource_man: &ResourceManager) {
+    let string = format_err(id, fmt, resource_man);
 
     ERROR_MAN.with_borrow_mut(|error_man| error_man.queue.push((id, string)))
 }
 
+/// Adds a new error to be shown as a toast, for the errors that the player doesn't have to stop for.
+pub fn push_err_toast(id: Id, fmt: &FormatContext, resource_man: &ResourceManager) {
+    let string = format_err(id, fmt, resource_man);
+
+    if let Ok(mut errors) = TOAST_ERRORS.lock() {
+        errors.push((id, string));
+    }
+}
+
+/// Takes the errors pushed to be shown as toasts since the last time.
+pub fn take_err_toasts() -> Vec<(Id, String)> {
+    TOAST_ERRORS
+        .lock()
+        .map(|mut v| std::mem::take(&mut *v))
+        .unwrap_or_default()
+}
+
 /// Removes the top error off of the stack and returns it or None if the queue is empty.
 pub fn pop_err() -> Option<(Id, String)> {
     ERROR_MAN.with_borrow_mut(|error_man| error_man.queue.pop())
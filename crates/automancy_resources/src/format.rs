@@ -1,6 +1,8 @@
 use hashbrown::HashMap;
 use interpolator::Context;
+use std::collections::BTreeSet;
 use std::ops::Deref;
+use std::sync::Mutex;
 
 pub use interpolator::Formattable;
 
@@ -27,11 +29,188 @@ impl<'a, T: Iterator<Item = (&'a str, Formattable<'a>)>> From<T> for FormatConte
     }
 }
 
+/// The strings that failed to be formatted, so that each is only logged once rather than every frame it is drawn.
+static MALFORMED: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Gets the integer an argument is formatted as, if it is one.
+fn integer_arg(fmt: &FormatContext, name: &str) -> Option<i64> {
+    let value = fmt.get(name)?.clone();
+
+    interpolator::format("{v}", &FormatContext::from([("v", value)].into_iter()))
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Picks the plural form of `{count, one: item, other: items}` by the count, which is `one` if it is 1 or -1, and `other` otherwise.
+/// The `other` form is the one used if the chosen form is missing.
+fn select_plural(content: &str, fmt: &FormatContext) -> Result<String, String> {
+    let (name, forms) = content
+        .split_once(',')
+        .ok_or_else(|| format!("{{{content}}} has no plural forms"))?;
+    let name = name.trim();
+
+    let forms = forms
+        .split(',')
+        .map(|form| {
+            form.split_once(':')
+                .map(|(key, text)| (key.trim(), text.trim()))
+                .ok_or_else(|| format!("the plural form {form:?} of {name} has no ':'"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let count = integer_arg(fmt, name).ok_or_else(|| {
+        format!("{name} is not an integer argument, so it can't pick a plural form")
+    })?;
+    let key = if count.abs() == 1 { "one" } else { "other" };
+
+    forms
+        .iter()
+        .find(|(form, _)| *form == key)
+        .or_else(|| forms.iter().find(|(form, _)| *form == "other"))
+        .map(|(_, text)| text.to_string())
+        .ok_or_else(|| format!("{name} has no {key} or other plural form"))
+}
+
+/// Replaces the plural placeholders with the forms picked for their counts, leaving the other placeholders to interpolator.
+fn expand_plurals(s: &str, fmt: &FormatContext) -> Result<String, String> {
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if rest.starts_with("{{") {
+            result.push_str("{{");
+            rest = &rest[2..];
+            continue;
+        }
+
+        // interpolator's placeholders can have braces in them, as in `{list:i({})}`
+        let mut depth = 0;
+        let end = rest
+            .char_indices()
+            .find(|(_, c)| {
+                match c {
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    _ => {}
+                }
+
+                depth == 0
+            })
+            .map(|(index, _)| index)
+            .ok_or_else(|| "a placeholder is never closed".to_string())?;
+        let content = &rest[1..end];
+
+        let is_plural = content
+            .split_once(',')
+            .is_some_and(|(name, _)| name.trim().chars().all(|c| c.is_alphanumeric() || c == '_'));
+
+        if is_plural {
+            result.push_str(&select_plural(content, fmt)?);
+        } else {
+            result.push_str(&rest[..=end]);
+        }
+
+        rest = &rest[end + 1..];
+    }
+
+    result.push_str(rest);
+
+    Ok(result)
+}
+
+/// Formats the string with the named arguments, as in `{item}`. As well as interpolator's placeholders,
+/// the string can pick between plural forms by an integer argument, as in `{count, one: item, other: items}`.
+///
+/// A string that can't be formatted, whether it is malformed or uses an argument it wasn't given, comes out as it is.
+/// This is logged the first time it happens to each string.
+pub fn format_context(s: &str, fmt: &FormatContext) -> String {
+    let result = expand_plurals(s, fmt).and_then(|expanded| {
+        interpolator::format(&expanded, fmt).map_err(|err| format!("{err:?}"))
+    });
+
+    match result {
+        Ok(v) => v,
+        Err(err) => {
+            if let Ok(mut malformed) = MALFORMED.lock() {
+                if malformed.insert(s.to_string()) {
+                    let mut args = fmt.keys().collect::<Vec<_>>();
+                    args.sort();
+
+                    log::error!(
+                        "Could not format string! Format string: {s}, error: {err}. Available variables: {args:?}"
+                    );
+                }
+            }
+
+            s.to_string()
+        }
+    }
+}
+
 pub fn format_str<const LEN: usize>(s: &str, fmt: [(&str, Formattable); LEN]) -> String {
-    interpolator::format(s, &FormatContext::from(fmt.into_iter())).unwrap_or_else(|err| {
-        panic!(
-            "Could not format string! Format string: {s}, error: {err:?}. Available variables: {:?}",
-            fmt,
-        )
-    })
+    format_context(s, &FormatContext::from(fmt.into_iter()))
+}
+
+const COMPACT_SUFFIXES: [&str; 6] = ["k", "M", "G", "T", "P", "E"];
+
+/// Formats an amount with all of its digits, putting the separator between each group of three digits.
+///
+/// The amount is taken as an `i128` so that both signed item amounts and unsigned counts up to `u64::MAX` fit.
+pub fn format_amount_full(amount: i128, separator: &str) -> String {
+    let digits = amount.unsigned_abs().to_string();
+    let mut result = String::with_capacity(digits.len() + digits.len() / 3 * separator.len() + 1);
+
+    if amount < 0 {
+        result.push('-');
+    }
+
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            result.push_str(separator);
+        }
+
+        result.push(c);
+    }
+
+    result
+}
+
+/// Formats an amount in compact notation (1.2k, 3.4M, ...) if it is at least the threshold, and in full otherwise.
+///
+/// The compact form is truncated, never rounded up, so it never shows more than there actually is.
+/// Amounts below 1000 are always shown in full, whatever the threshold, as there is no suffix for them,
+/// and a threshold below that would only turn them into fractions of a thousand like 0.5k.
+/// Amounts past the last suffix keep it, with more digits before the point.
+pub fn format_amount_compact(amount: i128, threshold: u64, separator: &str) -> String {
+    let abs = amount.unsigned_abs();
+
+    if abs < u128::from(threshold.max(1000)) {
+        return format_amount_full(amount, separator);
+    }
+
+    let mut unit: u128 = 1000;
+    let mut suffix = COMPACT_SUFFIXES[0];
+
+    for next in &COMPACT_SUFFIXES[1..] {
+        if abs / 1000 < unit {
+            break;
+        }
+
+        unit *= 1000;
+        suffix = next;
+    }
+
+    let tenths = abs / (unit / 10);
+    let (whole, fraction) = (tenths / 10, tenths % 10);
+    let sign = if amount < 0 { "-" } else { "" };
+
+    if fraction == 0 {
+        format!("{sign}{whole}{suffix}")
+    } else {
+        format!("{sign}{whole}.{fraction}{suffix}")
+    }
 }
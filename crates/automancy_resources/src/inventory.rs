@@ -1,5 +1,8 @@
+use crate::data::{Data, DataMap};
+use crate::ResourceManager;
 use automancy_defs::{
-    id::{Id, Interner},
+    id::{Id, Interner, TileId},
+    math::Float,
     parse_map_id_of, resolve_map_id_of,
     stack::{ItemAmount, ItemStack},
     try_parse_map_id_of,
@@ -8,6 +11,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::ops::{Deref, DerefMut};
 
+/// A set of item amounts. Iterates in ID order, so transfers and displays always go through the items the same way.
 #[derive(Debug, Default, Clone, Ord, PartialOrd, Eq, PartialEq)]
 pub struct Inventory(BTreeMap<Id, ItemAmount>);
 
@@ -25,11 +29,144 @@ impl DerefMut for Inventory {
     }
 }
 
+/// How much an inventory can hold: at most `slots` different items, at most `capacity` items in total,
+/// and at most `stack_size` of the item being added. Any of them can be left out for no limit.
+///
+/// The limits only block adding more. Contents that are already over them, like those of older saves, are kept as they are.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InventoryLimits {
+    pub slots: Option<usize>,
+    pub capacity: Option<ItemAmount>,
+    pub stack_size: Option<ItemAmount>,
+}
+
+impl InventoryLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.slots.is_none() && self.capacity.is_none() && self.stack_size.is_none()
+    }
+
+    /// Adds the stack size of the item to the limits, for adding that item.
+    pub fn for_item(self, resource_man: &ResourceManager, id: Id) -> Self {
+        let stack_size = match (self.stack_size, resource_man.item_stack_size(id)) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+
+        Self { stack_size, ..self }
+    }
+
+    /// Adds the tile's threshold for the item to the limits, from its `item_thresholds` data,
+    /// so that no more of the item is taken in once the tile holds that much of it.
+    pub fn with_threshold(self, resource_man: &ResourceManager, data: &DataMap, id: Id) -> Self {
+        let threshold = match data.get(resource_man.registry.data_ids.item_thresholds) {
+            Some(Data::Inventory(thresholds)) => thresholds.0.get(&id).copied().filter(|v| *v > 0),
+            _ => None,
+        };
+
+        let stack_size = match (self.stack_size, threshold) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+
+        Self { stack_size, ..self }
+    }
+
+    /// Reads the limits of a tile from its `slots` and `capacity` data, falling back to the ones in its definition.
+    pub fn of_tile(resource_man: &ResourceManager, id: TileId, data: &DataMap) -> Self {
+        let data_ids = &resource_man.registry.data_ids;
+        let setup = resource_man.registry.tiles.get(&id).map(|def| &def.data);
+
+        let read = |key| {
+            [Some(data), setup]
+                .into_iter()
+                .flatten()
+                .find_map(|data| match data.get(key) {
+                    Some(Data::Amount(amount)) if *amount > 0 => Some(*amount),
+                    _ => None,
+                })
+        };
+
+        Self {
+            slots: read(data_ids.slots).map(|slots| slots as usize),
+            capacity: read(data_ids.capacity),
+            stack_size: None,
+        }
+    }
+}
+
 impl Inventory {
     pub fn into_inner(self) -> BTreeMap<Id, ItemAmount> {
         self.0
     }
 
+    /// Gets how many different items are in the inventory.
+    pub fn used_slots(&self) -> usize {
+        self.0.values().filter(|amount| **amount > 0).count()
+    }
+
+    /// Gets how many items are in the inventory in total.
+    pub fn total(&self) -> ItemAmount {
+        self.0
+            .values()
+            .filter(|amount| **amount > 0)
+            .fold(0, |total, amount| total.saturating_add(*amount))
+    }
+
+    /// Gets how much more of the item fits in the inventory by the limits.
+    pub fn room_for(&self, id: Id, limits: InventoryLimits) -> ItemAmount {
+        let stored = self.0.get(&id).copied().unwrap_or(0).max(0);
+
+        if let Some(slots) = limits.slots {
+            if stored == 0 && self.used_slots() >= slots {
+                return 0;
+            }
+        }
+
+        let capacity = match limits.capacity {
+            Some(capacity) => capacity.saturating_sub(self.total()).max(0),
+            None => ItemAmount::MAX,
+        };
+        let stack = match limits.stack_size {
+            Some(stack_size) => stack_size.saturating_sub(stored).max(0),
+            None => ItemAmount::MAX,
+        };
+
+        capacity.min(stack)
+    }
+
+    /// Adds as much of the item as fits by the limits, and returns the overflow that did not fit.
+    pub fn add_limited(
+        &mut self,
+        id: Id,
+        amount: ItemAmount,
+        limits: InventoryLimits,
+    ) -> ItemAmount {
+        let adding = amount.min(self.room_for(id, limits)).max(0);
+
+        if adding > 0 {
+            self.add(id, adding);
+        }
+
+        amount - adding
+    }
+
+    /// Gets how full the inventory is by the limits, from 0 to 1, or None if it has no limits.
+    /// Whichever of the slots and the capacity is the fullest counts.
+    pub fn fullness(&self, limits: InventoryLimits) -> Option<Float> {
+        let slots = limits
+            .slots
+            .map(|slots| self.used_slots() as Float / slots.max(1) as Float);
+        let capacity = limits
+            .capacity
+            .map(|capacity| self.total() as Float / capacity.max(1) as Float);
+
+        slots
+            .into_iter()
+            .chain(capacity)
+            .reduce(Float::max)
+            .map(|v| v.clamp(0.0, 1.0))
+    }
+
     pub fn get(&mut self, id: Id) -> ItemAmount {
         *self.0.entry(id).or_insert(0)
     }
@@ -75,7 +212,18 @@ impl Inventory {
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct InventoryRaw(Vec<(String, ItemAmount)>);
 
+impl From<Vec<(String, ItemAmount)>> for InventoryRaw {
+    fn from(value: Vec<(String, ItemAmount)>) -> Self {
+        Self(value)
+    }
+}
+
 impl InventoryRaw {
+    /// Returns the item IDs in the inventory, as they are written.
+    pub fn ids(&self) -> impl Iterator<Item = &String> {
+        self.0.iter().map(|(id, _)| id)
+    }
+
     pub fn try_to_inventory(&self, interner: &Interner) -> Inventory {
         Inventory(try_parse_map_id_of(
             self.0.iter().map(|(a, b)| (a, *b)),
@@ -1,14 +1,18 @@
+use crate::error::push_err;
+use crate::format::{FormatContext, Formattable};
 use crate::registry::{DataIds, ErrorIds, GuiIds, KeyIds, ModelIds, Registry};
+use crate::types::audio::Sound;
 use crate::types::font::Font;
 use crate::types::model::IndexRange;
 use crate::types::translate::TranslateDef;
+use automancy_defs::id::ModelId;
+use automancy_defs::math::Float;
 use automancy_defs::rendering::{Animation, Mesh};
 use automancy_defs::{
     chrono::{DateTime, Local},
     id::SharedStr,
 };
 use automancy_defs::{coord::TileCoord, log};
-use automancy_defs::{id::ModelId, kira::sound::static_sound::StaticSoundData};
 use automancy_defs::{id::TileId, kira::track::TrackHandle};
 use automancy_defs::{
     id::{Id, IdRaw, Interner},
@@ -20,16 +24,19 @@ use std::collections::BTreeMap;
 use std::ffi::OsStr;
 use std::fmt;
 use std::fmt::{Debug, Formatter};
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 use std::time::SystemTime;
 use thiserror::Error;
 use types::function::FunctionMetadata;
 use types::item::ItemDef;
+use types::pack::ResourcePack;
 use walkdir::WalkDir;
 
 pub use petgraph;
 
+pub mod builder;
 pub mod data;
 pub mod error;
 pub mod inventory;
@@ -67,6 +74,25 @@ pub fn format_time(time: SystemTime, fmt: &str) -> String {
     time.format(fmt).to_string()
 }
 
+/// Finds the resource packs, one per directory in [RESOURCES_PATH], sorted by their names.
+/// They are loaded in the order [types::pack::resolve_load_order] puts them in.
+///
+/// Returns an empty list if the resources folder is missing or has no packs in it.
+pub fn find_resource_packs() -> Vec<PathBuf> {
+    let Ok(dir) = fs::read_dir(RESOURCES_PATH) else {
+        return vec![];
+    };
+
+    let mut packs = dir
+        .flatten()
+        .map(|v| v.path())
+        .filter(|v| v.is_dir())
+        .collect::<Vec<_>>();
+    packs.sort();
+
+    packs
+}
+
 pub(crate) fn load_recursively(path: &Path, extension: &OsStr) -> Vec<PathBuf> {
     WalkDir::new(path)
         .follow_links(false)
@@ -85,6 +111,17 @@ pub enum LoadResourceError {
     OsStringError(PathBuf),
     #[error("could not get font name from {0}")]
     CouldNotGetFontName(PathBuf),
+    #[error("{0} is not a valid ID")]
+    InvalidId(String),
+}
+
+/// A resource file that failed to load, and was skipped.
+#[derive(Debug, Clone)]
+pub struct LoadFailure {
+    /// the namespace of the pack the file is in
+    pub pack: String,
+    pub file: PathBuf,
+    pub error: String,
 }
 
 #[derive(Error, Debug)]
@@ -104,16 +141,26 @@ pub struct ResourceManager {
     pub registry: Registry,
 
     pub translates: TranslateDef,
-    pub audio: HashMap<String, StaticSoundData>,
+    pub audio: HashMap<String, Sound>,
+    /// The names of the sounds, by the IDs they get from the pack they are in, as in `core:click`.
+    pub audio_ids: HashMap<Id, String>,
     pub shaders: HashMap<String, SharedStr>,
     pub functions: HashMap<Id, FunctionInfo>,
     pub fonts: BTreeMap<String, Font>, // yes this does need to be a BTreeMap
+    /// The resource packs, in the order they were loaded.
+    pub packs: Vec<ResourcePack>,
+    /// whether a resource file failing to load fails the whole loading, rather than being skipped
+    pub strict: bool,
+    /// The resource files that were skipped, in the order they failed.
+    pub load_failures: Vec<LoadFailure>,
 
     pub ordered_tiles: Vec<TileId>,
     pub ordered_items: Vec<Id>,
     pub ordered_categories: Vec<Id>,
     pub all_meshes_anims: HashMap<ModelId, (Vec<Option<Mesh>>, Vec<Animation>)>,
     pub all_index_ranges: HashMap<ModelId, HashMap<usize, IndexRange>>,
+    /// The lower-detail versions of the models, each with the camera distance from which it is drawn instead, nearest first.
+    pub model_lods: HashMap<ModelId, Vec<(Float, ModelId)>>,
 }
 
 impl Debug for ResourceManager {
@@ -124,9 +171,14 @@ impl Debug for ResourceManager {
 
 impl ResourceManager {
     pub fn new(track: TrackHandle) -> Self {
-        let mut interner = Interner::new();
+        Self::with_interner(track, Interner::new())
+    }
+
+    /// Creates an empty resource manager that interns into an existing interner, so that the IDs already handed out stay the same.
+    pub fn with_interner(track: TrackHandle, mut interner: Interner) -> Self {
         let none = IdRaw::new("core", "none").to_id(&mut interner);
         let any = IdRaw::new("core", "#any").to_id(&mut interner);
+        let default_terrain = IdRaw::new("core", "ground").to_id(&mut interner);
 
         let mut engine = Engine::new();
         engine.set_max_expr_depths(0, 0);
@@ -159,12 +211,19 @@ impl ResourceManager {
                 categories: Default::default(),
                 categories_tiles_map: Default::default(),
                 items: Default::default(),
+                item_producers_map: Default::default(),
                 researches: Default::default(),
                 researches_id_map: Default::default(),
                 researches_unlock_map: Default::default(),
+                research_layout: Default::default(),
+                data_schemas: data_ids.schemas().into_iter().collect(),
+                terrains: Default::default(),
+                start: Default::default(),
+                start_raw: Default::default(),
 
                 none,
                 any,
+                default_terrain,
 
                 data_ids,
                 model_ids,
@@ -175,15 +234,81 @@ impl ResourceManager {
 
             translates: Default::default(),
             audio: Default::default(),
+            audio_ids: Default::default(),
             shaders: Default::default(),
             functions: Default::default(),
             fonts: Default::default(),
+            packs: vec![],
+            strict: false,
+            load_failures: vec![],
 
             ordered_tiles: vec![],
             ordered_items: vec![],
             ordered_categories: vec![],
             all_index_ranges: Default::default(),
             all_meshes_anims: Default::default(),
+            model_lods: Default::default(),
+        }
+    }
+
+    /// Parses an ID read from a resource file, in the namespace of its pack.
+    pub(crate) fn parse_file_id(&mut self, id: &str, namespace: &str) -> anyhow::Result<Id> {
+        Id::parse(id, &mut self.interner, Some(namespace))
+            .ok_or_else(|| LoadResourceError::InvalidId(id.to_string()).into())
+    }
+
+    /// Skips a resource file that failed to load, recording why, so that the rest of the files still load.
+    /// In strict mode, the error is returned instead.
+    pub(crate) fn skip_failed<T>(
+        &mut self,
+        namespace: &str,
+        file: &Path,
+        result: anyhow::Result<T>,
+    ) -> anyhow::Result<Option<T>> {
+        match result {
+            Ok(v) => Ok(Some(v)),
+            Err(err) if self.strict => Err(err.context(format!("Error loading {file:?}"))),
+            Err(err) => {
+                log::error!(
+                    "Skipping {file:?} of {namespace}, as it could not be loaded! Error: {err:?}"
+                );
+
+                self.load_failures.push(LoadFailure {
+                    pack: namespace.to_string(),
+                    file: file.to_path_buf(),
+                    error: format!("{err:#}"),
+                });
+
+                Ok(None)
+            }
+        }
+    }
+
+    /// Reports the resource files that were skipped. This needs the translations, so it goes after the packs are loaded.
+    ///
+    /// If the translation of the error itself failed to load, the failures are only in the log.
+    pub fn report_load_failures(&self) {
+        let id = self.registry.err_ids.invalid_resource_file;
+
+        if !self.translates.error.contains_key(&id) {
+            return;
+        }
+
+        for failure in &self.load_failures {
+            let file = failure.file.display().to_string();
+
+            push_err(
+                id,
+                &FormatContext::from(
+                    [
+                        ("pack", Formattable::display(&failure.pack)),
+                        ("file", Formattable::display(&file)),
+                        ("error", Formattable::display(&failure.error)),
+                    ]
+                    .into_iter(),
+                ),
+                self,
+            );
         }
     }
 }
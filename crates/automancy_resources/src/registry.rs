@@ -1,6 +1,9 @@
-use crate::types::research::ResearchDef;
+use crate::types::research::{ResearchDef, ResearchLayout};
+use crate::types::schema::DataSchema;
 use crate::types::script::ScriptDef;
+use crate::types::start::{StartDef, StartRaw};
 use crate::types::tag::TagDef;
+use crate::types::terrain::TerrainDef;
 use crate::types::tile::TileDef;
 use crate::types::{category::CategoryDef, item::ItemDef};
 use automancy_defs::id::{Id, TileId};
@@ -17,12 +20,23 @@ pub struct Registry {
     pub categories: HashMap<Id, CategoryDef>,
     pub(crate) categories_tiles_map: HashMap<Id, Vec<TileId>>,
     pub items: HashMap<Id, ItemDef>,
+    /// the scripts putting out each item. This is computed once all the packs are loaded
+    pub(crate) item_producers_map: HashMap<Id, Vec<Id>>,
     pub researches: StableDiGraph<ResearchDef, ()>,
     pub(crate) researches_id_map: HashMap<Id, NodeIndex>,
     pub(crate) researches_unlock_map: HashMap<TileId, NodeIndex>,
+    /// The researches laid out for the research tree. This is computed once the researches are compiled.
+    pub research_layout: ResearchLayout,
+    pub data_schemas: HashMap<Id, DataSchema>,
+    pub terrains: HashMap<Id, TerrainDef>,
+    /// What new maps start with.
+    pub start: StartDef,
+    pub(crate) start_raw: Vec<(String, StartRaw)>,
 
     pub none: Id,
     pub any: Id,
+    /// The terrain of every coordinate nothing else was generated or set on.
+    pub default_terrain: Id,
 
     pub data_ids: DataIds,
     pub model_ids: ModelIds,
@@ -34,27 +48,78 @@ pub struct Registry {
 #[derive(Copy, Clone, IdReg)]
 pub struct DataIds {
     pub script: Id,
+    /// the scripts a tile can run, as listed in its definition
+    pub scripts: Id,
     pub buffer: Id,
+    pub fluid_buffer: Id,
+    pub fluid_capable: Id,
     pub item: Id,
+    /// the most of each item the tile takes in, as set along with its item filter
+    pub item_thresholds: Id,
     pub capacity: Id,
+    pub slots: Id,
     pub direction: Id,
     pub link: Id,
 
     pub player_inventory: Id,
     pub research_items_filled: Id,
     pub research_puzzle_completed: Id,
+    /// the researches lined up after the current one, in order
+    pub research_queue: Id,
 
     pub tiles: Id,
 
     #[namespace("core")]
     pub unlocked_researches: Id,
+    #[namespace("core")]
+    pub camera_path: Id,
+    #[namespace("core")]
+    pub camera_path_timings: Id,
+    #[namespace("core")]
+    pub camera_path_looping: Id,
+    #[namespace("core")]
+    pub camera_bookmarks: Id,
+    #[namespace("core")]
+    pub camera_bookmark_slots: Id,
+    #[namespace("core")]
+    pub camera_bookmark_names: Id,
+    #[namespace("core")]
+    pub hotbar: Id,
+    #[namespace("core")]
+    pub favorite_tiles: Id,
+    #[namespace("core")]
+    pub logic_tick_rate: Id,
+    #[namespace("core")]
+    pub transfer_tick_rate: Id,
+    #[namespace("core")]
+    pub tick_speed: Id,
+    #[namespace("core")]
+    pub autosave_interval: Id,
+    /// the sound looped under the music while the map is loaded
+    #[namespace("core")]
+    pub ambience: Id,
+    /// whether the map is a sandbox, where everything can be placed and researched without items
+    #[namespace("core")]
+    pub sandbox: Id,
+    /// whether the machines of a sandbox run without using up their inputs
+    #[namespace("core")]
+    pub sandbox_free_inputs: Id,
 
     #[namespace("core")]
     pub direction_color: Id,
     #[namespace("core")]
     pub inactive_model: Id,
+    /// the model the tile is drawn with, picked from its variants by the neighbors it connects to
+    #[namespace("core")]
+    pub variant_model: Id,
     #[namespace("core")]
     pub default_tile: Id,
+    #[namespace("core")]
+    pub protected: Id,
+    #[namespace("core")]
+    pub terrains: Id,
+    #[namespace("core")]
+    pub terrain_edge: Id,
 
     #[namespace("core")]
     #[name("$none_tile")]
@@ -85,6 +150,7 @@ pub struct GuiIds {
     pub info: Id,
     pub player_menu: Id,
     pub error_popup: Id,
+    pub error_log: Id,
     pub debug_menu: Id,
     pub load_map: Id,
     pub delete_map: Id,
@@ -92,20 +158,144 @@ pub struct GuiIds {
     pub invalid_name: Id,
     pub options: Id,
     pub tile_config: Id,
+    pub missing_ids: Id,
+    pub regions: Id,
+    pub map_folder_issues: Id,
+    pub map_repair_confirm: Id,
+    pub camera_path: Id,
+    pub changelog: Id,
+    pub flow_graph: Id,
+    pub production_stats: Id,
+    pub recipes: Id,
+    pub research_tree: Id,
+    pub inventory: Id,
+    pub trim_map: Id,
+    pub bookmarks: Id,
+    pub import_map: Id,
+    pub map_settings: Id,
 
     pub options_graphics: Id,
     pub options_graphics_ui_scale: Id,
     pub options_graphics_ui_scale_small: Id,
     pub options_graphics_ui_scale_normal: Id,
     pub options_graphics_ui_scale_large: Id,
+    pub options_graphics_detail: Id,
+    pub options_graphics_detail_low: Id,
+    pub options_graphics_detail_medium: Id,
+    pub options_graphics_detail_high: Id,
     pub options_audio: Id,
     pub options_gui: Id,
     pub options_controls: Id,
+    pub options_camera_min_zoom: Id,
+    pub options_camera_max_zoom: Id,
+    pub options_camera_rotation: Id,
 
     pub lbl_maps_loaded: Id,
+    pub lbl_map_tile_count: Id,
+    pub lbl_map_playtime: Id,
+    pub lbl_map_description: Id,
+    pub lbl_sandbox: Id,
+    pub lbl_sandbox_free_inputs: Id,
+    pub lbl_map_delete_failed: Id,
+    pub lbl_map_exported: Id,
+    pub lbl_map_export_failed: Id,
+    pub lbl_map_imported: Id,
+    pub lbl_map_import_failed: Id,
+    pub lbl_import_missing_packs: Id,
+    pub lbl_search_tiles: Id,
     pub lbl_pick_another_name: Id,
     pub lbl_delete_map_confirm: Id,
     pub lbl_cannot_place_missing_item: Id,
+    pub lbl_tile_locked: Id,
+    pub lbl_protected: Id,
+    pub lbl_tile_occupied: Id,
+    pub lbl_tile_protected: Id,
+    pub lbl_placement_failed: Id,
+    pub lbl_invalid_terrain: Id,
+    pub lbl_storage_slots: Id,
+    pub lbl_storage_capacity: Id,
+    pub lbl_nothing_to_configure: Id,
+    pub lbl_nothing_to_pick: Id,
+    pub lbl_unbound_key: Id,
+    pub lbl_press_key: Id,
+    pub lbl_binding_conflict: Id,
+    pub lbl_crash_report_saved: Id,
+    pub lbl_take_amount: Id,
+    pub lbl_item_threshold: Id,
+    pub lbl_put_in: Id,
+    pub lbl_missing_ids: Id,
+    pub lbl_show_regions: Id,
+    pub lbl_new_region: Id,
+    pub lbl_no_regions: Id,
+    pub lbl_map_folder_issues: Id,
+    pub lbl_map_missing_info: Id,
+    pub lbl_map_missing_tiles: Id,
+    pub lbl_map_corrupted: Id,
+    pub lbl_map_unknown: Id,
+    pub lbl_map_temp_valid: Id,
+    pub lbl_map_temp_invalid: Id,
+    pub lbl_map_autosave_newer: Id,
+    pub lbl_map_error_recovery: Id,
+    pub lbl_map_folder_summary: Id,
+    pub lbl_promote_temps_confirm: Id,
+    pub lbl_remove_temps_confirm: Id,
+    pub lbl_no_waypoints: Id,
+    pub lbl_waypoint: Id,
+    pub lbl_dwell: Id,
+    pub lbl_transition: Id,
+    pub lbl_loop: Id,
+    pub lbl_collecting_flows: Id,
+    pub lbl_no_flows: Id,
+    pub lbl_flow_node: Id,
+    pub lbl_no_production: Id,
+    pub lbl_production_windows: Id,
+    pub lbl_production_rates: Id,
+    pub lbl_tile_rates: Id,
+    pub lbl_recipes_search: Id,
+    pub lbl_recipes_consuming: Id,
+    pub lbl_recipes_producing: Id,
+    pub lbl_recipes_locked: Id,
+    pub lbl_recipes_other: Id,
+    pub lbl_no_recipes: Id,
+    pub lbl_inventory_filter: Id,
+    pub lbl_inventory_count: Id,
+    pub lbl_inventory_sort: Id,
+    pub lbl_no_items: Id,
+    pub lbl_item_tags: Id,
+    pub lbl_item_produced_by: Id,
+    pub lbl_research_unlocked: Id,
+    pub lbl_research_available: Id,
+    pub lbl_research_locked: Id,
+    pub lbl_research_current: Id,
+    pub lbl_research_cost: Id,
+    pub lbl_research_unlocks: Id,
+    pub lbl_research_dropped: Id,
+    pub lbl_research_queue: Id,
+    pub lbl_research_queued: Id,
+    pub lbl_no_research: Id,
+    pub lbl_no_bookmarks: Id,
+    pub lbl_bookmark: Id,
+    pub lbl_bookmark_set: Id,
+    pub lbl_trim_radius: Id,
+    pub lbl_trim_preview: Id,
+    pub lbl_trim_confirm: Id,
+    pub lbl_trim_backing_up: Id,
+    pub lbl_trimming: Id,
+    pub lbl_trim_done: Id,
+    pub lbl_trim_backup_failed: Id,
+    pub lbl_autosave_fallback: Id,
+    pub lbl_restored_from_backup: Id,
+    pub lbl_map_saved: Id,
+    pub lbl_save_failed: Id,
+    pub lbl_autosave_failed: Id,
+    pub lbl_no_errors: Id,
+    pub lbl_ticks_paused: Id,
+    pub lbl_ticks_resumed: Id,
+    pub lbl_tick_speed: Id,
+    pub lbl_screenshot_saved: Id,
+    pub lbl_screenshot_failed: Id,
+    pub lbl_resources_reloaded: Id,
+    pub lbl_resources_reload_failed: Id,
 
     pub btn_confirm: Id,
     pub btn_exit: Id,
@@ -119,11 +309,58 @@ pub struct GuiIds {
     pub btn_load: Id,
     pub btn_delete: Id,
     pub btn_new_map: Id,
+    pub btn_export: Id,
+    pub btn_import: Id,
+    pub btn_create_region: Id,
+    pub btn_rename: Id,
+    pub btn_recolor: Id,
+    pub btn_repair: Id,
+    pub btn_promote_temps: Id,
+    pub btn_remove_temps: Id,
+    pub btn_open_folder: Id,
+    pub btn_add_waypoint: Id,
+    pub btn_start_playback: Id,
+    pub btn_changelog: Id,
+    pub btn_open_config: Id,
+    pub btn_copy_settings: Id,
+    pub btn_protect: Id,
+    pub btn_unprotect: Id,
+    pub btn_remove_tile: Id,
+    pub btn_refresh: Id,
+    pub btn_go_to: Id,
+    pub btn_trim_map: Id,
+    pub btn_trim: Id,
+    pub btn_map_settings: Id,
+    pub btn_load_autosave: Id,
+    pub btn_reset_defaults: Id,
+    pub btn_error_log: Id,
+    pub btn_clear: Id,
+    pub btn_copy: Id,
+    pub btn_recipes: Id,
+    pub btn_recipes_consuming: Id,
+    pub btn_recipes_producing: Id,
+    pub btn_inventory: Id,
+    pub btn_sort_name: Id,
+    pub btn_sort_amount: Id,
+    pub btn_sort_category: Id,
+    pub btn_research_tree: Id,
+    pub btn_start_research: Id,
+    pub btn_reset_view: Id,
+    pub btn_queue_research: Id,
+    pub btn_unlock_research: Id,
 
     pub research_menu_title: Id,
     pub player_inventory_title: Id,
     pub research_submit_items: Id,
 
+    pub overlay_category: Id,
+    pub overlay_status: Id,
+    pub overlay_throughput: Id,
+    pub lbl_status_working: Id,
+    pub lbl_status_idle: Id,
+    pub lbl_status_error: Id,
+    pub overlay_fullness: Id,
+
     pub time_fmt: Id,
 }
 
@@ -141,6 +378,36 @@ pub struct KeyIds {
     pub cut: Id,
     pub copy: Id,
     pub paste: Id,
+    pub cycle_overlay: Id,
+    pub open_config: Id,
+    pub regions: Id,
+    pub camera_path: Id,
+    pub record_waypoint: Id,
+    pub flow_graph: Id,
+    pub production_stats: Id,
+    pub recipes: Id,
+    pub inventory: Id,
+    pub skip_track: Id,
+    pub minimap: Id,
+    pub bookmarks: Id,
+    pub set_bookmark: Id,
+    pub go_to_bookmark: Id,
+    pub pause_ticks: Id,
+    pub step_tick: Id,
+    pub slow_down: Id,
+    pub speed_up: Id,
+    pub hotbar_slot: Id,
+    pub pick_tile: Id,
+    pub rotate_tile: Id,
+    pub select_tile: Id,
+    pub previous_category: Id,
+    pub next_category: Id,
+    pub menu_up: Id,
+    pub menu_down: Id,
+    pub menu_confirm: Id,
+    pub move_cursor: Id,
+    pub rotate_left: Id,
+    pub rotate_right: Id,
 }
 
 #[derive(Clone, Copy, IdReg)]
@@ -151,4 +418,25 @@ pub struct ErrorIds {
     /// This error is displayed when the options cannot be written.
     #[namespace("core")]
     pub unwritable_options: Id,
+    /// This error is displayed when the map is compressed with a dictionary that cannot be read.
+    #[namespace("core")]
+    pub missing_map_dictionary: Id,
+    /// This error is displayed when an entry of a pack's start definition references something that isn't loaded.
+    #[namespace("core")]
+    pub unresolved_start_entry: Id,
+    /// This error is displayed when the map was saved in a newer format than can be read.
+    #[namespace("core")]
+    pub unsupported_map_version: Id,
+    /// This error is displayed when a resource pack is skipped, for its manifest or its dependencies.
+    #[namespace("core")]
+    pub invalid_resource_pack: Id,
+    /// This error is displayed when a resource file fails to load, and is skipped.
+    #[namespace("core")]
+    pub invalid_resource_file: Id,
+    /// This error is displayed when a tag includes or excludes itself through other tags.
+    #[namespace("core")]
+    pub tag_include_cycle: Id,
+    /// This error is displayed when a research depends on itself through other researches.
+    #[namespace("core")]
+    pub research_cycle: Id,
 }
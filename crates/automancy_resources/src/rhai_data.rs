@@ -1,10 +1,14 @@
 use crate::types::tag::TagDef;
 use crate::types::tile::TileDef;
 use crate::types::{
-    item::ItemDef,
+    item::{ItemClass, ItemDef},
     script::{InstructionsDef, ScriptDef},
 };
-use crate::{data::DataMap, inventory::Inventory};
+use crate::{
+    data::DataMap,
+    inventory::{Inventory, InventoryLimits},
+    RESOURCE_MAN,
+};
 use automancy_defs::{
     coord::TileCoord,
     id::{ModelId, TileId},
@@ -14,6 +18,13 @@ use automancy_defs::{id::Id, stack::ItemAmount};
 use hashbrown::{HashMap, HashSet};
 use rhai::{Dynamic, Engine};
 
+fn item_limits(limits: InventoryLimits, id: Id) -> InventoryLimits {
+    match RESOURCE_MAN.read().unwrap().as_ref() {
+        Some(resource_man) => limits.for_item(resource_man, id),
+        None => limits,
+    }
+}
+
 pub(crate) fn register_data_stuff(engine: &mut Engine) {
     engine
         .register_type_with_name::<DataMap>("DataMap")
@@ -24,8 +35,37 @@ pub(crate) fn register_data_stuff(engine: &mut Engine) {
         .register_type_with_name::<Inventory>("Inventory")
         .register_fn("take", Inventory::take)
         .register_fn("add", Inventory::add)
+        // the item's stack size always counts, so that the scripts can't go over it
+        .register_fn(
+            "add_limited",
+            |v: &mut Inventory, id: Id, amount: ItemAmount, limits: InventoryLimits| {
+                v.add_limited(id, amount, item_limits(limits, id))
+            },
+        )
+        .register_fn(
+            "room_for",
+            |v: &mut Inventory, id: Id, limits: InventoryLimits| {
+                v.room_for(id, item_limits(limits, id))
+            },
+        )
+        .register_fn("total", |v: &mut Inventory| v.total())
+        .register_fn("used_slots", |v: &mut Inventory| {
+            v.used_slots() as ItemAmount
+        })
         .register_indexer_get_set(Inventory::get, Inventory::insert);
 
+    engine
+        .register_type_with_name::<InventoryLimits>("InventoryLimits")
+        // 0 for no limit
+        .register_fn(
+            "InventoryLimits",
+            |slots: ItemAmount, capacity: ItemAmount| InventoryLimits {
+                slots: (slots > 0).then_some(slots as usize),
+                capacity: (capacity > 0).then_some(capacity),
+                stack_size: None,
+            },
+        );
+
     engine
         .register_type_with_name::<Id>("Id")
         .register_fn("==", |a: Id, b: Id| a == b)
@@ -96,6 +136,13 @@ pub(crate) fn register_data_stuff(engine: &mut Engine) {
     engine
         .register_type_with_name::<ItemDef>("ItemDef")
         .register_get("id", |v: &mut ItemDef| -> Id { v.id })
+        // 0 for no limit
+        .register_get("stack_size", |v: &mut ItemDef| -> ItemAmount {
+            v.stack_size.unwrap_or(0)
+        })
+        .register_get("is_fluid", |v: &mut ItemDef| -> bool {
+            v.class == ItemClass::Fluid
+        })
         .register_fn("==", |a: ItemDef, b: ItemDef| a == b)
         .register_fn("!=", |a: ItemDef, b: ItemDef| a != b);
     engine
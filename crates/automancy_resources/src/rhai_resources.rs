@@ -63,4 +63,8 @@ pub(crate) fn register_resources(engine: &mut Engine) {
             None => Dynamic::UNIT,
         }
     });
+    // the data key of the buffer an item goes in, which is the fluid buffer for fluids
+    engine.register_fn("buffer_of", |id: Id| {
+        RESOURCE_MAN.read().unwrap().as_ref().unwrap().buffer_of(id)
+    });
 }
@@ -1,12 +1,100 @@
-use crate::{LoadResourceError, ResourceManager, AUDIO_EXT, COULD_NOT_GET_FILE_STEM};
+use crate::{LoadResourceError, ResourceManager, AUDIO_EXT, COULD_NOT_GET_FILE_STEM, RON_EXT};
+use automancy_defs::id::IdRaw;
 use automancy_defs::kira::sound::static_sound::StaticSoundData;
+use serde::Deserialize;
 use std::ffi::OsStr;
-use std::fs::read_dir;
+use std::fs::{read_dir, read_to_string};
 use std::path::Path;
 
+/// The channel a sound is played through, which has its own volume.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum SoundCategory {
+    Music,
+    #[default]
+    Sfx,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+/// What the optional file next to a sound says about it, as in `click.ron` next to `click.ogg`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SoundRaw {
+    #[serde(default)]
+    pub category: SoundCategory,
+    /// where the music fits, as in `menu` or `game`. music without one plays anywhere
+    #[serde(default)]
+    pub mood: Option<String>,
+    /// how often the music comes up in the playlist, relative to the others. 0 keeps it out, for sounds only used as ambience
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+}
+
+impl Default for SoundRaw {
+    fn default() -> Self {
+        Self {
+            category: Default::default(),
+            mood: None,
+            weight: default_weight(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Sound {
+    pub data: StaticSoundData,
+    pub category: SoundCategory,
+    pub mood: Option<String>,
+    pub weight: f64,
+}
+
 impl ResourceManager {
+    fn load_sound(&mut self, namespace: &str, file: &Path) -> anyhow::Result<()> {
+        log::info!("Loading audio at {file:?}");
+
+        let data = StaticSoundData::from_file(file)?;
+        let name = file
+            .file_stem()
+            .ok_or_else(|| {
+                LoadResourceError::InvalidFileError(file.to_path_buf(), COULD_NOT_GET_FILE_STEM)
+            })?
+            .to_str()
+            .ok_or_else(|| LoadResourceError::OsStringError(file.to_path_buf()))?;
+
+        // sounds without a definition are sound effects
+        let def = file.with_extension(RON_EXT);
+        let raw = if def.exists() {
+            ron::from_str::<SoundRaw>(&read_to_string(&def)?)?
+        } else {
+            SoundRaw::default()
+        };
+
+        self.audio.insert(
+            name.into(),
+            Sound {
+                data,
+                category: raw.category,
+                mood: raw.mood,
+                weight: raw.weight,
+            },
+        );
+
+        let id = IdRaw::new(namespace, name).to_id(&mut self.interner);
+        self.audio_ids.insert(id, name.into());
+
+        log::info!("Registered audio with name {name}, as {:?}", raw.category);
+
+        Ok(())
+    }
+
     pub fn load_audio(&mut self, dir: &Path) -> anyhow::Result<()> {
         let audio = dir.join("audio");
+        let namespace = dir
+            .file_name()
+            .and_then(OsStr::to_str)
+            .unwrap_or_default()
+            .to_string();
 
         if let Ok(audio) = read_dir(audio) {
             for file in audio
@@ -15,24 +103,8 @@ impl ResourceManager {
                 .map(|v| v.path())
                 .filter(|v| v.extension() == Some(OsStr::new(AUDIO_EXT)))
             {
-                log::info!("Loading audio at {file:?}");
-
-                if let Ok(audio) = StaticSoundData::from_file(&file) {
-                    let name = file
-                        .file_stem()
-                        .ok_or_else(|| {
-                            LoadResourceError::InvalidFileError(
-                                file.clone(),
-                                COULD_NOT_GET_FILE_STEM,
-                            )
-                        })?
-                        .to_str()
-                        .ok_or_else(|| LoadResourceError::OsStringError(file.clone()))?;
-
-                    self.audio.insert(name.into(), audio);
-
-                    log::info!("Registered audio with name {name}");
-                }
+                let result = self.load_sound(&namespace, &file);
+                self.skip_failed(&namespace, &file, result)?;
             }
         }
 
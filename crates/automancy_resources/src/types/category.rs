@@ -1,10 +1,13 @@
 use crate::{load_recursively, ResourceManager, RON_EXT};
 use automancy_defs::id::{Id, ModelId, TileId};
+use automancy_defs::{colors, hex};
 use hashbrown::HashMap;
 use serde::Deserialize;
 use std::ffi::OsStr;
 use std::fs::read_to_string;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::path::Path;
+use yakui::Color;
 
 use super::IconMode;
 
@@ -15,6 +18,8 @@ pub struct CategoryDef {
     pub icon: Id,
     pub icon_mode: IconMode,
     pub item: Option<Id>,
+    /// the color the category's tiles are drawn with on the minimap
+    pub color: Option<Color>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -24,6 +29,19 @@ struct Raw {
     pub icon: String,
     pub icon_mode: IconMode,
     pub item: Option<String>,
+    pub color: Option<String>,
+}
+
+/// Parses a color written in hex, like `"3cb44b"` or `"#3cb44bff"`.
+fn parse_color(v: &str) -> Option<Color> {
+    let mut color = hex::decode(v.trim_start_matches('#')).ok()?.into_iter();
+
+    Some(Color {
+        r: color.next()?,
+        g: color.next()?,
+        b: color.next()?,
+        a: color.next().unwrap_or(255),
+    })
 }
 
 impl ResourceManager {
@@ -32,13 +50,23 @@ impl ResourceManager {
 
         let v = ron::from_str::<Raw>(&read_to_string(file)?)?;
 
-        let id = Id::parse(&v.id, &mut self.interner, Some(namespace)).unwrap();
+        let id = self.parse_file_id(&v.id, namespace)?;
         let ord = v.ord;
-        let icon = Id::parse(&v.icon, &mut self.interner, Some(namespace)).unwrap();
+        let icon = self.parse_file_id(&v.icon, namespace)?;
         let icon_mode = v.icon_mode;
         let item = v
             .item
-            .map(|v| Id::parse(&v, &mut self.interner, Some(namespace)).unwrap());
+            .map(|v| self.parse_file_id(&v, namespace))
+            .transpose()?;
+        let color = v.color.and_then(|color| {
+            let parsed = parse_color(&color);
+
+            if parsed.is_none() {
+                log::warn!("Invalid color {color:?} for category {}", v.id);
+            }
+
+            parsed
+        });
 
         self.registry.categories.insert(
             id,
@@ -48,6 +76,7 @@ impl ResourceManager {
                 icon,
                 icon_mode,
                 item,
+                color,
             },
         );
 
@@ -58,7 +87,8 @@ impl ResourceManager {
         let categories = dir.join("categories");
 
         for file in load_recursively(&categories, OsStr::new(RON_EXT)) {
-            self.load_category(&file, namespace)?;
+            let result = self.load_category(&file, namespace);
+            self.skip_failed(namespace, &file, result)?;
         }
 
         Ok(())
@@ -88,6 +118,20 @@ impl ResourceManager {
         self.registry.categories_tiles_map.get(&id)
     }
 
+    /// Gets the color the category's tiles are drawn with on the minimap.
+    ///
+    /// A category without a color of its own gets one from the overlay palette, by hashing its ID's name so that it stays the same between sessions.
+    pub fn category_color(&self, id: Id) -> Color {
+        if let Some(color) = self.registry.categories.get(&id).and_then(|v| v.color) {
+            return color;
+        }
+
+        let mut hasher = DefaultHasher::new();
+        self.interner.resolve(id).hash(&mut hasher);
+
+        colors::OVERLAY_PALETTE[hasher.finish() as usize % colors::OVERLAY_PALETTE.len()]
+    }
+
     pub fn get_researches_by_category(&self, id: Id) -> Option<Vec<Id>> {
         self.registry.categories_tiles_map.get(&id).map(|tiles| {
             tiles
@@ -59,8 +59,30 @@ fn parse_name(names: Names) -> Option<String> {
 }
 
 impl ResourceManager {
+    fn load_font(&mut self, file: &Path) -> anyhow::Result<()> {
+        log::info!("Loading font {file:?}");
+
+        let mut data: Vec<u8> = Vec::new();
+        File::open(file)?.read_to_end(&mut data)?;
+        let data = Arc::new(data);
+
+        let name = parse_name(Face::parse(&data, 0)?.names())
+            .ok_or_else(|| LoadResourceError::CouldNotGetFontName(file.to_path_buf()))?;
+
+        log::info!("Loaded font '{name}'!");
+
+        self.fonts.insert(name.clone(), Font { name, data });
+
+        Ok(())
+    }
+
     pub fn load_fonts(&mut self, dir: &Path) -> anyhow::Result<()> {
         let fonts = dir.join("fonts");
+        let namespace = dir
+            .file_name()
+            .and_then(OsStr::to_str)
+            .unwrap_or_default()
+            .to_string();
 
         if let Ok(fonts) = read_dir(fonts) {
             for file in fonts.into_iter().flatten().map(|v| v.path()).filter(|v| {
@@ -68,18 +90,8 @@ impl ResourceManager {
                     .and_then(OsStr::to_str)
                     .is_some_and(|v| FONT_EXT.contains(&v))
             }) {
-                log::info!("Loading font {file:?}");
-
-                let mut data: Vec<u8> = Vec::new();
-                File::open(&file)?.read_to_end(&mut data)?;
-                let data = Arc::new(data);
-
-                let name = parse_name(Face::parse(&data, 0)?.names())
-                    .ok_or_else(|| LoadResourceError::CouldNotGetFontName(file.clone()))?;
-
-                log::info!("Loaded font '{name}'!");
-
-                self.fonts.insert(name.clone(), Font { name, data });
+                let result = self.load_font(&file);
+                self.skip_failed(&namespace, &file, result)?;
             }
         }
 
@@ -1,20 +1,41 @@
 use crate::{load_recursively, ResourceManager, RON_EXT};
 use automancy_defs::id::{Id, ModelId};
+use automancy_defs::stack::ItemAmount;
 use serde::Deserialize;
 use std::ffi::OsStr;
 use std::fs::read_to_string;
 use std::path::Path;
 
+/// What kind of thing an item is, which decides the buffer it is kept in.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Deserialize)]
+pub enum ItemClass {
+    #[default]
+    Item,
+    /// kept in the fluid buffer, and only moved through tiles that are fluid capable
+    Fluid,
+}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
 pub struct ItemDef {
     pub id: Id,
     pub model: ModelId,
+    /// the most of the item one inventory can hold, or None for no limit
+    pub stack_size: Option<ItemAmount>,
+    pub class: ItemClass,
+    /// the most of the item that moves into a tile in one transaction, or None for no limit
+    pub transfer_rate: Option<ItemAmount>,
 }
 
 #[derive(Debug, Deserialize)]
 struct Raw {
     id: String,
     model: String,
+    #[serde(default)]
+    stack_size: Option<ItemAmount>,
+    #[serde(default)]
+    class: ItemClass,
+    #[serde(default)]
+    transfer_rate: Option<ItemAmount>,
 }
 
 impl ResourceManager {
@@ -23,14 +44,17 @@ impl ResourceManager {
 
         let v = ron::from_str::<Raw>(&read_to_string(file)?)?;
 
-        let id = Id::parse(&v.id, &mut self.interner, Some(namespace)).unwrap();
-        let model = Id::parse(&v.model, &mut self.interner, Some(namespace)).unwrap();
+        let id = self.parse_file_id(&v.id, namespace)?;
+        let model = self.parse_file_id(&v.model, namespace)?;
 
         self.registry.items.insert(
             id,
             ItemDef {
                 id,
                 model: ModelId(model),
+                stack_size: v.stack_size.filter(|v| *v > 0),
+                class: v.class,
+                transfer_rate: v.transfer_rate.filter(|v| *v > 0),
             },
         );
 
@@ -41,12 +65,37 @@ impl ResourceManager {
         let items = dir.join("items");
 
         for file in load_recursively(&items, OsStr::new(RON_EXT)) {
-            self.load_item(&file, namespace)?;
+            let result = self.load_item(&file, namespace);
+            self.skip_failed(namespace, &file, result)?;
         }
 
         Ok(())
     }
 
+    /// Gets the most of the item one inventory can hold, or None for no limit.
+    pub fn item_stack_size(&self, id: Id) -> Option<ItemAmount> {
+        self.registry
+            .items
+            .get(&id)
+            .and_then(|item| item.stack_size)
+    }
+
+    pub fn item_class(&self, id: Id) -> ItemClass {
+        self.registry
+            .items
+            .get(&id)
+            .map(|item| item.class)
+            .unwrap_or_default()
+    }
+
+    /// Gets the data key of the buffer the item is kept in, by its class.
+    pub fn buffer_of(&self, id: Id) -> Id {
+        match self.item_class(id) {
+            ItemClass::Item => self.registry.data_ids.buffer,
+            ItemClass::Fluid => self.registry.data_ids.fluid_buffer,
+        }
+    }
+
     pub fn ordered_items(&mut self) {
         let mut ids = self.registry.items.keys().cloned().collect::<Vec<_>>();
 
@@ -9,10 +9,14 @@ pub mod font;
 pub mod function;
 pub mod item;
 pub mod model;
+pub mod pack;
 pub mod research;
+pub mod schema;
 pub mod script;
 pub mod shader;
+pub mod start;
 pub mod tag;
+pub mod terrain;
 pub mod tile;
 pub mod translate;
 
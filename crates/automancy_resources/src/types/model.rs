@@ -4,6 +4,7 @@ use automancy_defs::rendering::{load_gltf_model, Animation};
 use automancy_defs::{gltf, log};
 use automancy_defs::{
     id::{Id, ModelId},
+    math::Float,
     rendering::Mesh,
 };
 use hashbrown::HashMap;
@@ -17,12 +18,17 @@ pub struct IndexRange {
     pub pos: u32,
     pub count: u32,
     pub base_vertex: i32,
+    pub vertex_count: u32,
 }
 
 #[derive(Debug, Deserialize)]
 struct Raw {
     pub id: String,
     pub file: String,
+    /// the lower-detail files of the model, each with the camera distance from which it is drawn instead.
+    /// their meshes are matched to the model's by index, and the ones they leave out are not drawn
+    #[serde(default)]
+    pub lod: Vec<(Float, String)>,
 }
 
 impl ResourceManager {
@@ -77,22 +83,76 @@ impl ResourceManager {
             })
     }
 
+    /// Gets the version of the model to draw from the camera distance, along with its level of detail, 0 being the model itself.
+    pub fn model_lod(&self, id: ModelId, distance: Float) -> (ModelId, usize) {
+        self.model_lods
+            .get(&id)
+            .and_then(|lods| {
+                lods.iter()
+                    .enumerate()
+                    .rev()
+                    .find(|(_, (from, _))| distance >= *from)
+                    .map(|(level, (_, lod))| (*lod, level + 1))
+            })
+            .unwrap_or((id, 0))
+    }
+
+    /// Returns how many bytes of vertex and index data the model takes up on the GPU.
+    pub fn model_mesh_bytes(&self, id: &ModelId) -> u64 {
+        self.all_index_ranges
+            .get(id)
+            .map(|ranges| {
+                ranges
+                    .values()
+                    .map(|range| {
+                        range.vertex_count as u64 * mem::size_of::<Vertex>() as u64
+                            + range.count as u64 * mem::size_of::<u16>() as u64
+                    })
+                    .sum()
+            })
+            .unwrap_or(0)
+    }
+
     fn load_model(&mut self, file: &Path, namespace: &str) -> anyhow::Result<()> {
         log::info!("Loading model at: {file:?}");
 
         let v = ron::from_str::<Raw>(&read_to_string(file)?)?;
 
-        let file = file.parent().unwrap().join("files").join(v.file.as_str());
+        let files = file.parent().unwrap().join("files");
+        let file = files.join(v.file.as_str());
 
         log::info!("Loading model file at: {file:?}");
 
         let (document, buffers, _images) = gltf::import(file)?;
 
-        let id = Id::parse(&v.id, &mut self.interner, Some(namespace)).unwrap();
+        let id = self.parse_file_id(&v.id, namespace)?;
 
         self.all_meshes_anims
             .insert(ModelId(id), load_gltf_model(document, buffers));
 
+        let mut lods = Vec::with_capacity(v.lod.len());
+        for (level, (distance, file)) in v.lod.iter().enumerate() {
+            let file = files.join(file.as_str());
+
+            log::info!("Loading level of detail {} at: {file:?}", level + 1);
+
+            let (document, buffers, _images) = gltf::import(file)?;
+
+            let lod =
+                ModelId(self.parse_file_id(&format!("{}_lod{}", v.id, level + 1), namespace)?);
+
+            self.all_meshes_anims
+                .insert(lod, load_gltf_model(document, buffers));
+            lods.push((*distance, lod));
+        }
+        lods.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        if lods.is_empty() {
+            self.model_lods.remove(&ModelId(id));
+        } else {
+            self.model_lods.insert(ModelId(id), lods);
+        }
+
         Ok(())
     }
 
@@ -100,7 +160,8 @@ impl ResourceManager {
         let models = dir.join("models");
 
         for file in load_recursively(&models, OsStr::new(RON_EXT)) {
-            self.load_model(&file, namespace)?;
+            let result = self.load_model(&file, namespace);
+            self.skip_failed(namespace, &file, result)?;
         }
 
         Ok(())
@@ -119,6 +180,7 @@ impl ResourceManager {
                         mesh.index,
                         mem::take(&mut mesh.indices),
                         base_vertex_count,
+                        mesh.vertices.len() as u32,
                     ));
 
                     base_vertex_count += mesh.vertices.len() as i32;
@@ -134,13 +196,14 @@ impl ResourceManager {
             .map(|(id, indices)| {
                 let ranges = indices
                     .iter()
-                    .map(|(index, v, base_vertex)| {
+                    .map(|(index, v, base_vertex, vertex_count)| {
                         let size = v.len() as u32;
 
                         let range = IndexRange {
                             pos: offset_count,
                             count: size,
                             base_vertex: *base_vertex,
+                            vertex_count: *vertex_count,
                         };
 
                         offset_count += size;
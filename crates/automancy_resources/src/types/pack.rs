@@ -0,0 +1,175 @@
+use crate::error::push_err;
+use crate::format::{FormatContext, Formattable};
+use crate::ResourceManager;
+use hashbrown::{HashMap, HashSet};
+use serde::Deserialize;
+use std::fs::read_to_string;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// The file a resource pack describes itself in, at the root of its directory.
+pub static PACK_MANIFEST: &str = "pack.ron";
+
+/// What a resource pack says about itself. Packs without one are loaded as if they had no dependencies.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PackManifest {
+    pub name: String,
+    pub version: String,
+    /// The namespaces of the packs that have to be loaded before this one, which are the names of their directories.
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+}
+
+/// A resource pack that was found, and will be loaded.
+#[derive(Debug, Clone)]
+pub struct ResourcePack {
+    pub namespace: String,
+    pub dir: PathBuf,
+    pub manifest: Option<PackManifest>,
+}
+
+#[derive(Error, Debug)]
+pub enum PackError {
+    #[error("the manifest of {0} could not be read: {1}")]
+    InvalidManifest(String, String),
+    #[error("{0} depends on {1}, which is not loaded")]
+    MissingDependency(String, String),
+    #[error("{0} depends on itself through {1}")]
+    DependencyCycle(String, String),
+}
+
+impl PackError {
+    /// Gets the namespace of the pack that was skipped.
+    pub fn pack(&self) -> &str {
+        match self {
+            PackError::InvalidManifest(pack, _) => pack,
+            PackError::MissingDependency(pack, _) => pack,
+            PackError::DependencyCycle(pack, _) => pack,
+        }
+    }
+}
+
+fn read_pack(dir: PathBuf) -> Result<ResourcePack, PackError> {
+    let namespace = dir
+        .file_name()
+        .and_then(|v| v.to_str())
+        .map(|v| v.trim().to_string())
+        .unwrap_or_default();
+
+    let file = dir.join(PACK_MANIFEST);
+
+    let manifest = if file.exists() {
+        let manifest = read_to_string(&file)
+            .map_err(|err| err.to_string())
+            .and_then(|v| ron::from_str::<PackManifest>(&v).map_err(|err| err.to_string()))
+            .map_err(|err| PackError::InvalidManifest(namespace.clone(), err))?;
+
+        Some(manifest)
+    } else {
+        None
+    };
+
+    Ok(ResourcePack {
+        namespace,
+        dir,
+        manifest,
+    })
+}
+
+/// Reads the manifests of the packs, and orders them so that every pack comes after the packs it depends on.
+/// Whatever a pack defines then overrides what its dependencies define with the same ID.
+///
+/// Packs with no order between them keep the order they were found in. Packs with malformed manifests,
+/// missing dependencies, or dependencies that lead back to themselves are skipped, along with the packs depending on them.
+pub fn resolve_load_order(dirs: Vec<PathBuf>) -> (Vec<ResourcePack>, Vec<PackError>) {
+    let mut errors = vec![];
+    let mut packs = vec![];
+
+    for dir in dirs {
+        match read_pack(dir) {
+            Ok(pack) => packs.push(pack),
+            Err(err) => errors.push(err),
+        }
+    }
+
+    let dependencies = |pack: &ResourcePack| {
+        pack.manifest
+            .as_ref()
+            .map(|v| v.dependencies.clone())
+            .unwrap_or_default()
+    };
+
+    // dropping a pack can leave the packs depending on it with a missing dependency, so this goes on until none are dropped
+    loop {
+        let found = packs
+            .iter()
+            .map(|pack| pack.namespace.clone())
+            .collect::<HashSet<_>>();
+
+        let missing = packs.iter().enumerate().find_map(|(index, pack)| {
+            dependencies(pack)
+                .into_iter()
+                .find(|dependency| !found.contains(dependency))
+                .map(|dependency| (index, dependency))
+        });
+
+        let Some((index, dependency)) = missing else {
+            break;
+        };
+
+        let pack = packs.remove(index);
+        errors.push(PackError::MissingDependency(pack.namespace, dependency));
+    }
+
+    let mut left = packs
+        .iter()
+        .map(|pack| (pack.namespace.clone(), dependencies(pack)))
+        .collect::<HashMap<_, _>>();
+    let mut ordered = vec![];
+
+    while !packs.is_empty() {
+        let ready = packs.iter().position(|pack| {
+            left[&pack.namespace]
+                .iter()
+                .all(|dependency| !left.contains_key(dependency))
+        });
+
+        match ready {
+            Some(index) => {
+                let pack = packs.remove(index);
+                left.remove(&pack.namespace);
+
+                ordered.push(pack);
+            }
+            None => {
+                // everything left depends on something else left, so they are all in or behind a cycle
+                for pack in packs.drain(..) {
+                    let through = left[&pack.namespace].join(", ");
+
+                    errors.push(PackError::DependencyCycle(pack.namespace, through));
+                }
+            }
+        }
+    }
+
+    (ordered, errors)
+}
+
+impl ResourceManager {
+    /// Reports the packs that were skipped. This needs the translations, so it goes after the packs are loaded.
+    pub fn report_pack_errors(&self, errors: &[PackError]) {
+        for err in errors {
+            push_err(
+                self.registry.err_ids.invalid_resource_pack,
+                &FormatContext::from(
+                    [
+                        ("pack", Formattable::display(&err.pack())),
+                        ("error", Formattable::display(err)),
+                    ]
+                    .into_iter(),
+                ),
+                self,
+            );
+        }
+    }
+}
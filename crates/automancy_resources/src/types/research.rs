@@ -1,4 +1,6 @@
 use crate::data::{DataMap, DataMapRaw};
+use crate::error::push_err;
+use crate::format::{FormatContext, Formattable};
 use crate::types::IconMode;
 use crate::{load_recursively, ResourceManager, RON_EXT};
 use automancy_defs::{
@@ -9,7 +11,10 @@ use automancy_defs::{
     parse_ids,
     stack::{ItemAmount, ItemStack},
 };
-use petgraph::visit::IntoNodeReferences;
+use hashbrown::HashMap;
+use petgraph::algo::tarjan_scc;
+use petgraph::visit::{IntoNodeReferences, Topo};
+use petgraph::Direction;
 use serde::Deserialize;
 use std::ffi::OsStr;
 use std::fs::read_to_string;
@@ -29,6 +34,18 @@ pub struct ResearchDef {
     pub attached_puzzle: Option<(Id, DataMap)>,
 }
 
+/// The researches laid out in layers for the research tree, where every research is one layer after the one it depends on.
+///
+/// Each layer is ordered by the order of the researches its researches depend on, so the edges between the layers don't cross.
+/// The researches in or behind a dependency cycle are left out.
+#[derive(Debug, Clone, Default)]
+pub struct ResearchLayout {
+    /// the researches of each layer, in order
+    pub layers: Vec<Vec<Id>>,
+    /// the layer of each research, and its index in that layer
+    pub positions: HashMap<Id, (usize, usize)>,
+}
+
 #[derive(Debug, Deserialize)]
 struct Raw {
     id: String,
@@ -49,38 +66,35 @@ impl ResourceManager {
 
         let v = ron::from_str::<Raw>(&read_to_string(file)?)?;
 
-        let id = Id::parse(&v.id, &mut self.interner, Some(namespace)).unwrap();
+        let id = self.parse_file_id(&v.id, namespace)?;
 
         let unlocks: Vec<Id> =
             parse_ids(v.unlocks.into_iter(), &mut self.interner, Some(namespace));
 
-        let icon = Id::parse(&v.icon, &mut self.interner, Some(namespace)).unwrap();
+        let icon = self.parse_file_id(&v.icon, namespace)?;
 
         let depends_on = v
             .depends_on
-            .map(|v| Id::parse(&v, &mut self.interner, Some(namespace)).unwrap());
+            .map(|v| self.parse_file_id(&v, namespace))
+            .transpose()?;
 
-        let name = Id::parse(&v.name, &mut self.interner, Some(namespace)).unwrap();
+        let name = self.parse_file_id(&v.name, namespace)?;
 
-        let description = Id::parse(&v.description, &mut self.interner, Some(namespace)).unwrap();
+        let description = self.parse_file_id(&v.description, namespace)?;
 
-        let completed_description = Id::parse(
-            &v.completed_description,
-            &mut self.interner,
-            Some(namespace),
-        )
-        .unwrap();
+        let completed_description = self.parse_file_id(&v.completed_description, namespace)?;
 
         let required_items = v
             .required_items
             .map(|v| parse_item_stacks(v.into_iter(), &mut self.interner, Some(namespace)));
 
-        let attached_puzzle = v.attached_puzzle.map(|(id, data)| {
-            (
-                Id::parse(&id, &mut self.interner, Some(namespace)).unwrap(),
+        let attached_puzzle = match v.attached_puzzle {
+            Some((id, data)) => Some((
+                self.parse_file_id(&id, namespace)?,
                 data.intern_to_data(&mut self.interner, Some(namespace)),
-            )
-        });
+            )),
+            None => None,
+        };
         let icon_mode = v.icon_mode;
 
         let index = self.registry.researches.add_node(ResearchDef {
@@ -120,7 +134,8 @@ impl ResourceManager {
         let items = dir.join("researches");
 
         for file in load_recursively(&items, OsStr::new(RON_EXT)) {
-            self.load_research(&file, namespace)?;
+            let result = self.load_research(&file, namespace);
+            self.skip_failed(namespace, &file, result)?;
         }
 
         Ok(())
@@ -148,5 +163,91 @@ impl ResourceManager {
                 }
             }
         }
+
+        self.report_research_cycles();
+        self.registry.research_layout = self.layout_researches();
+    }
+
+    /// Reports the researches that depend on themselves, through the researches they depend on.
+    fn report_research_cycles(&self) {
+        let graph = &self.registry.researches;
+
+        for cycle in tarjan_scc(graph) {
+            if cycle.len() == 1 && !graph.contains_edge(cycle[0], cycle[0]) {
+                continue;
+            }
+
+            let research = self
+                .interner
+                .resolve(graph[cycle[0]].id)
+                .unwrap_or_default();
+            let through = cycle
+                .iter()
+                .map(|index| self.interner.resolve(graph[*index].id).unwrap_or_default())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+
+            push_err(
+                self.registry.err_ids.research_cycle,
+                &FormatContext::from(
+                    [
+                        ("research", Formattable::display(&research)),
+                        ("through", Formattable::display(&through)),
+                    ]
+                    .into_iter(),
+                ),
+                self,
+            );
+        }
+    }
+
+    fn layout_researches(&self) -> ResearchLayout {
+        let graph = &self.registry.researches;
+
+        let mut depths = HashMap::new();
+        let mut layers = Vec::<Vec<_>>::new();
+
+        // the researches in a cycle are never visited, nor are the ones depending on them
+        let mut visitor = Topo::new(graph);
+        while let Some(index) = visitor.next(graph) {
+            let depth = graph
+                .neighbors_directed(index, Direction::Incoming)
+                .filter_map(|prev| depths.get(&prev))
+                .max()
+                .map_or(0, |depth| depth + 1);
+            depths.insert(index, depth);
+
+            if layers.len() <= depth {
+                layers.resize_with(depth + 1, Vec::new);
+            }
+            layers[depth].push(index);
+        }
+
+        let mut layout = ResearchLayout::default();
+
+        for (depth, mut layer) in layers.into_iter().enumerate() {
+            layer.sort_by_cached_key(|index| {
+                let prev = graph
+                    .neighbors_directed(*index, Direction::Incoming)
+                    .filter_map(|prev| layout.positions.get(&graph[prev].id))
+                    .map(|(_, order)| *order)
+                    .min();
+                let name = self.interner.resolve(graph[*index].id).unwrap_or_default();
+
+                (prev, name.to_string())
+            });
+
+            let layer = layer
+                .into_iter()
+                .map(|index| graph[index].id)
+                .collect::<Vec<_>>();
+
+            for (order, id) in layer.iter().enumerate() {
+                layout.positions.insert(*id, (depth, order));
+            }
+            layout.layers.push(layer);
+        }
+
+        layout
     }
 }
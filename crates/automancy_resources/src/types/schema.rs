@@ -0,0 +1,197 @@
+use crate::data::{Data, DataKind};
+use crate::registry::{DataIds, Registry};
+use crate::{load_recursively, ResourceManager, RON_EXT};
+use automancy_defs::id::Id;
+use serde::Deserialize;
+use std::ffi::OsStr;
+use std::fs::read_to_string;
+use std::path::Path;
+
+/// What a data key is expected to hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataSchema {
+    pub kind: DataKind,
+    /// Whether the value is written to the map when it is saved.
+    pub persisted: bool,
+}
+
+impl DataSchema {
+    pub const fn persisted(kind: DataKind) -> Self {
+        Self {
+            kind,
+            persisted: true,
+        }
+    }
+}
+
+impl DataIds {
+    /// The schemas of the built-in data keys.
+    pub fn schemas(&self) -> Vec<(Id, DataSchema)> {
+        vec![
+            (self.script, DataSchema::persisted(DataKind::Id)),
+            (self.scripts, DataSchema::persisted(DataKind::VecId)),
+            (self.buffer, DataSchema::persisted(DataKind::Inventory)),
+            (
+                self.fluid_buffer,
+                DataSchema::persisted(DataKind::Inventory),
+            ),
+            (self.fluid_capable, DataSchema::persisted(DataKind::Bool)),
+            (self.item, DataSchema::persisted(DataKind::Id)),
+            (
+                self.item_thresholds,
+                DataSchema::persisted(DataKind::Inventory),
+            ),
+            (self.capacity, DataSchema::persisted(DataKind::Amount)),
+            (self.slots, DataSchema::persisted(DataKind::Amount)),
+            (self.direction, DataSchema::persisted(DataKind::Coord)),
+            (self.link, DataSchema::persisted(DataKind::Coord)),
+            (
+                self.player_inventory,
+                DataSchema::persisted(DataKind::Inventory),
+            ),
+            (
+                self.research_items_filled,
+                DataSchema::persisted(DataKind::SetId),
+            ),
+            (
+                self.research_puzzle_completed,
+                DataSchema::persisted(DataKind::SetId),
+            ),
+            (self.research_queue, DataSchema::persisted(DataKind::VecId)),
+            (self.tiles, DataSchema::persisted(DataKind::TileMap)),
+            (
+                self.unlocked_researches,
+                DataSchema::persisted(DataKind::SetId),
+            ),
+            (self.camera_path, DataSchema::persisted(DataKind::VecCoord)),
+            (
+                self.camera_path_timings,
+                DataSchema::persisted(DataKind::VecFloat),
+            ),
+            (
+                self.camera_path_looping,
+                DataSchema::persisted(DataKind::Bool),
+            ),
+            (
+                self.camera_bookmarks,
+                DataSchema::persisted(DataKind::VecCoord),
+            ),
+            (
+                self.camera_bookmark_slots,
+                DataSchema::persisted(DataKind::VecFloat),
+            ),
+            (
+                self.camera_bookmark_names,
+                DataSchema::persisted(DataKind::VecString),
+            ),
+            (self.hotbar, DataSchema::persisted(DataKind::VecId)),
+            (self.favorite_tiles, DataSchema::persisted(DataKind::VecId)),
+            (
+                self.logic_tick_rate,
+                DataSchema::persisted(DataKind::Amount),
+            ),
+            (
+                self.transfer_tick_rate,
+                DataSchema::persisted(DataKind::Amount),
+            ),
+            (self.tick_speed, DataSchema::persisted(DataKind::Amount)),
+            (
+                self.autosave_interval,
+                DataSchema::persisted(DataKind::Amount),
+            ),
+            (self.ambience, DataSchema::persisted(DataKind::Id)),
+            (self.sandbox, DataSchema::persisted(DataKind::Bool)),
+            (
+                self.sandbox_free_inputs,
+                DataSchema::persisted(DataKind::Bool),
+            ),
+            (self.direction_color, DataSchema::persisted(DataKind::Color)),
+            (self.inactive_model, DataSchema::persisted(DataKind::Id)),
+            // worked out again from the neighbors when the map is loaded
+            (
+                self.variant_model,
+                DataSchema {
+                    kind: DataKind::Id,
+                    persisted: false,
+                },
+            ),
+            (self.default_tile, DataSchema::persisted(DataKind::Bool)),
+            (self.protected, DataSchema::persisted(DataKind::Bool)),
+            (self.terrains, DataSchema::persisted(DataKind::SetId)),
+            (self.terrain_edge, DataSchema::persisted(DataKind::SetId)),
+        ]
+    }
+}
+
+impl Registry {
+    /// Checks the value against the schema of the key. Keys without a schema accept anything.
+    ///
+    /// Returns the expected kind if it does not match.
+    pub fn check_data(&self, key: Id, value: &Data) -> Result<(), DataKind> {
+        match self.data_schemas.get(&key) {
+            Some(schema) if schema.kind != value.kind() => Err(schema.kind),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the value of the key is written to the map. Keys without a schema are.
+    pub fn is_persisted(&self, key: Id) -> bool {
+        self.data_schemas
+            .get(&key)
+            .map_or(true, |schema| schema.persisted)
+    }
+}
+
+fn default_persisted() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+struct Raw {
+    pub id: String,
+    pub kind: DataKind,
+    #[serde(default = "default_persisted")]
+    pub persisted: bool,
+}
+
+impl ResourceManager {
+    fn load_schema(&mut self, file: &Path, namespace: &str) -> anyhow::Result<()> {
+        log::info!("Loading data schema at: {file:?}");
+
+        let v = ron::from_str::<Raw>(&read_to_string(file)?)?;
+
+        let id = self.parse_file_id(&v.id, namespace)?;
+
+        if let Some(existing) = self.registry.data_schemas.get(&id) {
+            if existing.kind != v.kind {
+                log::warn!(
+                    "Data schema at {file:?} redefines {} as {:?}, which was {:?}",
+                    v.id,
+                    v.kind,
+                    existing.kind
+                );
+            }
+        }
+
+        self.registry.data_schemas.insert(
+            id,
+            DataSchema {
+                kind: v.kind,
+                persisted: v.persisted,
+            },
+        );
+
+        Ok(())
+    }
+
+    pub fn load_schemas(&mut self, dir: &Path, namespace: &str) -> anyhow::Result<()> {
+        let schemas = dir.join("schemas");
+
+        for file in load_recursively(&schemas, OsStr::new(RON_EXT)) {
+            let result = self.load_schema(&file, namespace);
+            self.skip_failed(namespace, &file, result)?;
+        }
+
+        Ok(())
+    }
+}
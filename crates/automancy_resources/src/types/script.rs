@@ -4,6 +4,7 @@ use automancy_defs::{
     parse_item_stacks,
     stack::{ItemAmount, ItemStack},
 };
+use hashbrown::HashMap;
 use serde::Deserialize;
 use std::ffi::OsStr;
 use std::fs::read_to_string;
@@ -39,7 +40,7 @@ impl ResourceManager {
 
         let v = ron::from_str::<Raw>(&read_to_string(file)?)?;
 
-        let id = Id::parse(&v.id, &mut self.interner, Some(namespace)).unwrap();
+        let id = self.parse_file_id(&v.id, namespace)?;
 
         let instructions = InstructionsDef {
             inputs: v
@@ -60,11 +61,39 @@ impl ResourceManager {
         Ok(())
     }
 
+    /// Indexes the scripts by the items they put out, each item's scripts sorted by name.
+    /// This goes after all the packs are loaded, as a pack can add scripts putting out the items of another.
+    pub fn compile_scripts(&mut self) {
+        let mut item_producers_map = HashMap::<Id, Vec<Id>>::new();
+
+        for script in self.registry.scripts.values() {
+            for output in &script.instructions.outputs {
+                let producers = item_producers_map.entry(output.id).or_default();
+
+                if !producers.contains(&script.id) {
+                    producers.push(script.id);
+                }
+            }
+        }
+
+        for producers in item_producers_map.values_mut() {
+            producers.sort_by_cached_key(|id| (self.script_name(*id), *id));
+        }
+
+        self.registry.item_producers_map = item_producers_map;
+    }
+
+    /// Gets the scripts putting out the item, sorted by name.
+    pub fn get_scripts_producing(&self, item: Id) -> Option<&Vec<Id>> {
+        self.registry.item_producers_map.get(&item)
+    }
+
     pub fn load_scripts(&mut self, dir: &Path, namespace: &str) -> anyhow::Result<()> {
         let scripts = dir.join("scripts");
 
         for file in load_recursively(&scripts, OsStr::new(RON_EXT)) {
-            self.load_script(&file, namespace)?;
+            let result = self.load_script(&file, namespace);
+            self.skip_failed(namespace, &file, result)?;
         }
 
         Ok(())
@@ -0,0 +1,154 @@
+use crate::data::{DataMap, DataMapRaw};
+use crate::error::push_err;
+use crate::format::{FormatContext, Formattable};
+use crate::ResourceManager;
+use automancy_defs::coord::{TileCoord, TileUnit};
+use automancy_defs::id::{Id, IdRaw, TileId};
+use automancy_defs::stack::{ItemAmount, ItemStack};
+use serde::Deserialize;
+use std::fs::read_to_string;
+use std::path::Path;
+
+/// What a new map starts with, put together from the start definitions of every pack.
+#[derive(Debug, Clone, Default)]
+pub struct StartDef {
+    /// The items in the player's inventory.
+    pub inventory: Vec<ItemStack>,
+    /// The researches that are unlocked.
+    pub researches: Vec<Id>,
+    /// The tiles placed around the origin, with the data set on them.
+    pub tiles: Vec<(TileCoord, TileId, DataMap)>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TileRaw {
+    /// the q and r of the coordinate
+    coord: (TileUnit, TileUnit),
+    id: String,
+    /// the entries are given with their full IDs, like in maps
+    #[serde(default)]
+    data: DataMapRaw,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct StartRaw {
+    #[serde(default)]
+    inventory: Vec<(String, ItemAmount)>,
+    #[serde(default)]
+    researches: Vec<String>,
+    #[serde(default)]
+    tiles: Vec<TileRaw>,
+}
+
+impl ResourceManager {
+    /// Reads the start definition of the pack. Its IDs are only resolved in [ResourceManager::compile_start], once every pack has been loaded.
+    pub fn load_start(&mut self, dir: &Path, namespace: &str) -> anyhow::Result<()> {
+        let file = dir.join("start.ron");
+
+        if !file.exists() {
+            return Ok(());
+        }
+
+        log::info!("Loading start definition at: {file:?}");
+
+        let result = read_to_string(&file)
+            .map_err(anyhow::Error::from)
+            .and_then(|v| Ok(ron::from_str::<StartRaw>(&v)?));
+
+        if let Some(v) = self.skip_failed(namespace, &file, result)? {
+            self.registry.start_raw.push((namespace.to_string(), v));
+        }
+
+        Ok(())
+    }
+
+    fn report_unresolved_start_entry(&self, namespace: &str, entry: &str) {
+        push_err(
+            self.registry.err_ids.unresolved_start_entry,
+            &FormatContext::from(
+                [
+                    ("pack", Formattable::display(&namespace)),
+                    ("entry", Formattable::display(&entry)),
+                ]
+                .into_iter(),
+            ),
+            self,
+        );
+    }
+
+    /// Resolves the ID of an entry, which also has to be of something that's loaded.
+    fn resolve_start_id(
+        &self,
+        namespace: &str,
+        id: &str,
+        loaded: impl FnOnce(Id) -> bool,
+    ) -> Option<Id> {
+        let resolved = IdRaw::parse(id, Some(namespace))
+            .and_then(|v| v.try_to_id(&self.interner))
+            .filter(|id| loaded(*id));
+
+        if resolved.is_none() {
+            self.report_unresolved_start_entry(namespace, id);
+        }
+
+        resolved
+    }
+
+    /// Puts the start definitions of the packs together, in the order the packs were loaded.
+    ///
+    /// The items and researches of every pack are added up, and a tile of a later pack takes the place of an earlier one at the same coordinate.
+    /// Entries with IDs that aren't loaded are reported and skipped.
+    pub fn compile_start(&mut self) {
+        let mut start = StartDef::default();
+
+        for (namespace, raw) in &self.registry.start_raw {
+            for (id, amount) in &raw.inventory {
+                let Some(id) = self
+                    .resolve_start_id(namespace, id, |id| self.registry.items.contains_key(&id))
+                else {
+                    continue;
+                };
+
+                match start.inventory.iter_mut().find(|stack| stack.id == id) {
+                    Some(stack) => stack.amount += amount,
+                    None => start.inventory.push(ItemStack {
+                        id,
+                        amount: *amount,
+                    }),
+                }
+            }
+
+            for id in &raw.researches {
+                let Some(id) = self.resolve_start_id(namespace, id, |id| {
+                    self.registry.researches_id_map.contains_key(&id)
+                }) else {
+                    continue;
+                };
+
+                if !start.researches.contains(&id) {
+                    start.researches.push(id);
+                }
+            }
+
+            for tile in &raw.tiles {
+                let Some(id) = self.resolve_start_id(namespace, &tile.id, |id| {
+                    self.registry.tiles.contains_key(&TileId(id))
+                }) else {
+                    continue;
+                };
+
+                let (data, _, unresolved) = tile.data.to_data_quarantined(&self.interner);
+                for (_, entry) in unresolved {
+                    self.report_unresolved_start_entry(namespace, &entry);
+                }
+
+                let coord = TileCoord::new(tile.coord.0, tile.coord.1);
+
+                start.tiles.retain(|(other, ..)| *other != coord);
+                start.tiles.push((coord, TileId(id), data));
+            }
+        }
+
+        self.registry.start = start;
+    }
+}
@@ -1,7 +1,9 @@
+use crate::error::push_err;
+use crate::format::{FormatContext, Formattable};
 use crate::registry::Registry;
 use crate::{load_recursively, ResourceManager, RON_EXT};
 use automancy_defs::{id::Id, parse_ids};
-use hashbrown::HashSet;
+use hashbrown::{HashMap, HashSet};
 use serde::Deserialize;
 use std::ffi::OsStr;
 use std::fs::read_to_string;
@@ -10,7 +12,12 @@ use std::path::Path;
 #[derive(Debug, Clone)]
 pub struct TagDef {
     pub id: Id,
+    /// the IDs the tag matches. Once the tags are compiled, this has the entries of the included tags, without the excluded ones
     pub entries: HashSet<Id>,
+    /// the tags whose entries are added to this tag's
+    pub includes: Vec<Id>,
+    /// the IDs, or tags of IDs, the tag never matches, even if an included tag does
+    pub excludes: Vec<Id>,
 }
 
 impl TagDef {
@@ -27,6 +34,63 @@ impl TagDef {
 struct Raw {
     pub id: String,
     pub entries: Vec<String>,
+    #[serde(default)]
+    pub includes: Vec<String>,
+    #[serde(default)]
+    pub excludes: Vec<String>,
+}
+
+/// Gets the entries of the tag with those of its included tags, without the excluded ones.
+///
+/// `path` is the tags being resolved that led to this one. A tag that leads back to itself is recorded in `cycles`,
+/// and the include or exclude closing the cycle adds or removes nothing.
+fn resolve_tag(
+    tags: &HashMap<Id, TagDef>,
+    id: Id,
+    path: &mut Vec<Id>,
+    resolved: &mut HashMap<Id, HashSet<Id>>,
+    cycles: &mut Vec<Vec<Id>>,
+) -> HashSet<Id> {
+    if let Some(entries) = resolved.get(&id) {
+        return entries.clone();
+    }
+
+    if let Some(start) = path.iter().position(|v| *v == id) {
+        let mut cycle = path[start..].to_vec();
+        cycle.push(id);
+        cycles.push(cycle);
+
+        return HashSet::new();
+    }
+
+    let Some(tag) = tags.get(&id) else {
+        return HashSet::new();
+    };
+
+    path.push(id);
+
+    let mut entries = tag.entries.clone();
+
+    for include in &tag.includes {
+        if tags.contains_key(include) {
+            entries.extend(resolve_tag(tags, *include, path, resolved, cycles));
+        }
+    }
+
+    for exclude in &tag.excludes {
+        if tags.contains_key(exclude) {
+            for excluded in resolve_tag(tags, *exclude, path, resolved, cycles) {
+                entries.remove(&excluded);
+            }
+        } else {
+            entries.remove(exclude);
+        }
+    }
+
+    path.pop();
+    resolved.insert(id, entries.clone());
+
+    entries
 }
 
 impl ResourceManager {
@@ -35,13 +99,15 @@ impl ResourceManager {
 
         let v = ron::from_str::<Raw>(&read_to_string(file)?)?;
 
-        let id = Id::parse(&v.id, &mut self.interner, Some(namespace)).unwrap();
+        let id = self.parse_file_id(&v.id, namespace)?;
 
         self.registry.tags.insert(
             id,
             TagDef {
                 id,
                 entries: parse_ids(v.entries.into_iter(), &mut self.interner, Some(namespace)),
+                includes: parse_ids(v.includes.into_iter(), &mut self.interner, Some(namespace)),
+                excludes: parse_ids(v.excludes.into_iter(), &mut self.interner, Some(namespace)),
             },
         );
 
@@ -52,9 +118,66 @@ impl ResourceManager {
         let tags = dir.join("tags");
 
         for file in load_recursively(&tags, OsStr::new(RON_EXT)) {
-            self.load_tag(&file, namespace)?;
+            let result = self.load_tag(&file, namespace);
+            self.skip_failed(namespace, &file, result)?;
         }
 
         Ok(())
     }
+
+    /// Expands the includes and excludes of the tags into their entries.
+    /// This goes after all the packs are loaded, as a tag can include the tags of other packs.
+    pub fn compile_tags(&mut self) {
+        for tag in self.registry.tags.values() {
+            for include in &tag.includes {
+                if !self.registry.tags.contains_key(include) {
+                    log::warn!(
+                        "Tag {} includes {}, which is not a tag, so it adds nothing",
+                        self.interner.resolve(tag.id).unwrap_or_default(),
+                        self.interner.resolve(*include).unwrap_or_default(),
+                    );
+                }
+            }
+        }
+
+        let mut resolved = HashMap::new();
+        let mut cycles = vec![];
+
+        for id in self.registry.tags.keys() {
+            resolve_tag(
+                &self.registry.tags,
+                *id,
+                &mut vec![],
+                &mut resolved,
+                &mut cycles,
+            );
+        }
+
+        for (id, entries) in resolved {
+            if let Some(tag) = self.registry.tags.get_mut(&id) {
+                tag.entries = entries;
+            }
+        }
+
+        for cycle in cycles {
+            let tag = self.interner.resolve(cycle[0]).unwrap_or_default();
+            let through = cycle
+                .iter()
+                .map(|id| self.interner.resolve(*id).unwrap_or_default())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+
+            push_err(
+                self.registry.err_ids.tag_include_cycle,
+                &FormatContext::from(
+                    [
+                        ("tag", Formattable::display(&tag)),
+                        ("through", Formattable::display(&through)),
+                    ]
+                    .into_iter(),
+                ),
+                self,
+            );
+        }
+    }
 }
@@ -0,0 +1,87 @@
+use crate::{load_recursively, ResourceManager, RON_EXT};
+use automancy_defs::id::{Id, ModelId};
+use serde::Deserialize;
+use std::ffi::OsStr;
+use std::fs::read_to_string;
+use std::path::Path;
+
+/// A kind of ground a map coordinate can have.
+#[derive(Debug, Clone, Copy)]
+pub struct TerrainDef {
+    pub id: Id,
+    /// The model drawn on empty coordinates with this terrain. Uses the empty tile model if there is none.
+    pub model: Option<ModelId>,
+    /// Whether tiles that don't ask for specific terrains can be placed on it.
+    pub buildable: bool,
+    /// The range of the generation noise, from 0 to 100, this terrain is generated in.
+    pub generated: Option<(i32, i32)>,
+}
+
+fn default_buildable() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+struct Raw {
+    pub id: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default = "default_buildable")]
+    pub buildable: bool,
+    #[serde(default)]
+    pub generated: Option<(i32, i32)>,
+}
+
+impl ResourceManager {
+    fn load_terrain(&mut self, file: &Path, namespace: &str) -> anyhow::Result<()> {
+        log::info!("Loading terrain at: {file:?}");
+
+        let v = ron::from_str::<Raw>(&read_to_string(file)?)?;
+
+        let id = self.parse_file_id(&v.id, namespace)?;
+        let model = v
+            .model
+            .map(|v| self.parse_file_id(&v, namespace).map(ModelId))
+            .transpose()?;
+
+        self.registry.terrains.insert(
+            id,
+            TerrainDef {
+                id,
+                model,
+                buildable: v.buildable,
+                generated: v.generated,
+            },
+        );
+
+        Ok(())
+    }
+
+    pub fn load_terrains(&mut self, dir: &Path, namespace: &str) -> anyhow::Result<()> {
+        let terrains = dir.join("terrains");
+
+        for file in load_recursively(&terrains, OsStr::new(RON_EXT)) {
+            let result = self.load_terrain(&file, namespace);
+            self.skip_failed(namespace, &file, result)?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether tiles without terrain requirements can go on the terrain. Terrains no pack defines are buildable.
+    pub fn terrain_buildable(&self, id: Id) -> bool {
+        self.registry
+            .terrains
+            .get(&id)
+            .map_or(true, |def| def.buildable)
+    }
+
+    /// Gets the model empty coordinates with the terrain are drawn with.
+    pub fn terrain_model(&self, id: Id) -> ModelId {
+        self.registry
+            .terrains
+            .get(&id)
+            .and_then(|def| def.model)
+            .unwrap_or(ModelId(self.registry.model_ids.tile_none))
+    }
+}
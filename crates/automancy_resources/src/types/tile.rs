@@ -1,17 +1,74 @@
 use crate::data::{DataMap, DataMapRaw};
 use crate::{load_recursively, ResourceManager, RON_EXT};
-use automancy_defs::id::{Id, TileId};
+use automancy_defs::coord::{TileCoord, TileUnit};
+use automancy_defs::id::{Id, ModelId, TileId};
 use serde::Deserialize;
 use std::ffi::OsStr;
 use std::fs::read_to_string;
 use std::path::Path;
 
+/// The neighbors of a tile, by the bit of the neighbor mask of a [TileDef::variants] entry they are at.
+/// Counter-clockwise from the right.
+pub const VARIANT_NEIGHBORS: [TileCoord; 6] = [
+    TileCoord::RIGHT,
+    TileCoord::TOP_RIGHT,
+    TileCoord::TOP_LEFT,
+    TileCoord::LEFT,
+    TileCoord::BOTTOM_LEFT,
+    TileCoord::BOTTOM_RIGHT,
+];
+
+/// An entry a tile adds to its context menu, which sends the event to the tile's script when chosen.
+#[derive(Debug, Clone, Copy)]
+pub struct TileAction {
+    /// The GUI translation the entry is shown with.
+    pub name: Id,
+    pub event: Id,
+}
+
 #[derive(Debug, Clone)]
 pub struct TileDef {
     pub id: TileId,
     pub function: Option<Id>,
     pub category: Option<Id>,
     pub data: DataMap,
+    pub actions: Vec<TileAction>,
+    /// The offsets of the other coordinates the tile covers from the one it is placed at, facing [TileCoord::RIGHT].
+    pub footprint: Vec<TileCoord>,
+    /// The tile, or tag of tiles, the neighbors need to be for the tile to connect to them. The tile itself if not set.
+    pub connects_to: Option<Id>,
+    /// The models the tile can be drawn with, by the neighbors it connects to, as in [VARIANT_NEIGHBORS].
+    pub variants: Vec<(u8, ModelId)>,
+}
+
+impl TileDef {
+    /// Gets the other coordinates the tile covers when placed at the coordinate, with the footprint turned to face the direction.
+    pub fn footprint_at(&self, coord: TileCoord, direction: Option<TileCoord>) -> Vec<TileCoord> {
+        let turns = direction
+            .and_then(|direction| {
+                (0..6).find(|turns| TileCoord::RIGHT.rotate_cw(*turns) == *direction)
+            })
+            .unwrap_or(0);
+
+        self.footprint
+            .iter()
+            .map(|offset| coord + TileCoord::from(offset.rotate_cw(turns)))
+            .collect()
+    }
+
+    /// Gets the model of the first variant whose neighbors are all connected to in the mask.
+    pub fn variant(&self, mask: u8) -> Option<ModelId> {
+        self.variants
+            .iter()
+            .find(|(neighbors, _)| mask & neighbors == *neighbors)
+            .map(|(_, model)| *model)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ActionRaw {
+    pub name: String,
+    pub event: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -21,6 +78,16 @@ struct Raw {
     #[serde(default)]
     pub category: Option<String>,
     pub data: DataMapRaw,
+    #[serde(default)]
+    pub actions: Vec<ActionRaw>,
+    /// as (q, r) offsets
+    #[serde(default)]
+    pub footprint: Vec<(TileUnit, TileUnit)>,
+    #[serde(default)]
+    pub connects_to: Option<String>,
+    /// as (neighbor mask, model) entries, tried in order
+    #[serde(default)]
+    pub variants: Vec<(u8, String)>,
 }
 
 impl ResourceManager {
@@ -29,15 +96,43 @@ impl ResourceManager {
 
         let v = ron::from_str::<Raw>(&read_to_string(file)?)?;
 
-        let id = TileId(Id::parse(&v.id, &mut self.interner, Some(namespace)).unwrap());
+        let id = TileId(self.parse_file_id(&v.id, namespace)?);
         let function = v
             .function
-            .map(|v| Id::parse(&v, &mut self.interner, Some(namespace)).unwrap());
+            .map(|v| self.parse_file_id(&v, namespace))
+            .transpose()?;
         let category = v
             .category
-            .map(|v| Id::parse(&v, &mut self.interner, Some(namespace)).unwrap());
+            .map(|v| self.parse_file_id(&v, namespace))
+            .transpose()?;
 
         let data = v.data.intern_to_data(&mut self.interner, Some(namespace));
+        let actions = v
+            .actions
+            .into_iter()
+            .map(|v| {
+                Ok(TileAction {
+                    name: self.parse_file_id(&v.name, namespace)?,
+                    event: self.parse_file_id(&v.event, namespace)?,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        let footprint = v
+            .footprint
+            .into_iter()
+            .map(|(q, r)| TileCoord::new(q, r))
+            // the tile is always at the coordinate it is placed at
+            .filter(|offset| *offset != TileCoord::ZERO)
+            .collect();
+        let connects_to = v
+            .connects_to
+            .map(|v| self.parse_file_id(&v, namespace))
+            .transpose()?;
+        let variants = v
+            .variants
+            .into_iter()
+            .map(|(mask, model)| Ok((mask, ModelId(self.parse_file_id(&model, namespace)?))))
+            .collect::<anyhow::Result<_>>()?;
 
         self.registry.tiles.insert(
             id,
@@ -46,6 +141,10 @@ impl ResourceManager {
                 function,
                 category,
                 data,
+                actions,
+                footprint,
+                connects_to,
+                variants,
             },
         );
 
@@ -56,7 +155,8 @@ impl ResourceManager {
         let tiles = dir.join("tiles");
 
         for file in load_recursively(&tiles, OsStr::new(RON_EXT)) {
-            self.load_tile(&file, namespace)?;
+            let result = self.load_tile(&file, namespace);
+            self.skip_failed(namespace, &file, result)?;
         }
 
         Ok(())
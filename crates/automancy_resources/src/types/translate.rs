@@ -1,19 +1,29 @@
-use crate::{format::FormatContext, ResourceManager, RON_EXT};
+use crate::{
+    format::{format_amount_compact, format_amount_full, format_context, FormatContext},
+    ResourceManager, RON_EXT,
+};
 use automancy_defs::{
     id::{Id, SharedStr, TileId},
     parse_map_id_str,
 };
-use hashbrown::HashMap;
+use hashbrown::hash_map::Entry;
+use hashbrown::{HashMap, HashSet};
 use interpolator::Formattable;
 use serde::Deserialize;
+use std::collections::BTreeSet;
 use std::fs::{read_dir, read_to_string};
 use std::path::Path;
 use std::{ffi::OsStr, fmt::Debug};
 
+/// The language whose translations are used for whatever the selected language has no translation of.
+pub static FALLBACK_LANGUAGE: &str = "en_US";
+
 #[derive(Debug, Default, Clone)]
 pub struct TranslateDef {
     pub none: SharedStr,
     pub unnamed: SharedStr,
+    /// The separator between each group of three digits in full amounts.
+    pub group_separator: SharedStr,
 
     pub(crate) items: HashMap<Id, SharedStr>,
     pub(crate) tiles: HashMap<Id, SharedStr>,
@@ -24,6 +34,9 @@ pub struct TranslateDef {
     pub(crate) error: HashMap<Id, SharedStr>,
     pub(crate) research: HashMap<Id, SharedStr>,
     pub keys: HashMap<Id, SharedStr>,
+
+    /// the translations that only the fallback language has, by the kind of translation and their IDs
+    pub fallen_back: HashSet<(&'static str, Id)>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -32,6 +45,8 @@ struct Raw {
     none: Option<String>,
     #[serde(default)]
     unnamed: Option<String>,
+    #[serde(default)]
+    group_separator: Option<String>,
 
     #[serde(default)]
     items: HashMap<String, String>,
@@ -53,7 +68,14 @@ struct Raw {
 }
 
 impl ResourceManager {
-    fn load_translate(&mut self, file: &Path, namespace: &str) -> anyhow::Result<()> {
+    /// Loads the translations of the file. The ones of the fallback language are only added where there is no translation yet,
+    /// while the ones of the selected language replace what is there.
+    fn load_translate(
+        &mut self,
+        file: &Path,
+        namespace: &str,
+        fallback: bool,
+    ) -> anyhow::Result<()> {
         log::info!("Loading translate at: {file:?}");
 
         let v = ron::from_str::<Raw>(&read_to_string(file)?)?;
@@ -61,6 +83,8 @@ impl ResourceManager {
         let mut new = TranslateDef {
             none: SharedStr::default(),
             unnamed: SharedStr::default(),
+            group_separator: SharedStr::default(),
+            fallen_back: Default::default(),
             items: parse_map_id_str(v.items.into_iter(), &mut self.interner, Some(namespace)),
             tiles: parse_map_id_str(v.tiles.into_iter(), &mut self.interner, Some(namespace)),
             categories: parse_map_id_str(
@@ -80,43 +104,84 @@ impl ResourceManager {
         if let Some(v) = v.unnamed {
             new.unnamed = v.into();
         }
+        if let Some(v) = v.group_separator {
+            new.group_separator = v.into();
+        }
         if self.translates.none.is_empty() {
             self.translates.none = new.none;
         }
         if self.translates.unnamed.is_empty() {
             self.translates.unnamed = new.unnamed;
         }
+        if self.translates.group_separator.is_empty() {
+            self.translates.group_separator = new.group_separator;
+        }
 
-        self.translates.items.extend(new.items);
-        self.translates.tiles.extend(new.tiles);
-        self.translates.categories.extend(new.categories);
-        self.translates.scripts.extend(new.scripts);
-        self.translates.gui.extend(new.gui);
-        self.translates.keys.extend(new.keys);
-        self.translates.error.extend(new.error);
-        self.translates.research.extend(new.research);
+        let translates = &mut self.translates;
+        for (kind, current, new) in [
+            ("items", &mut translates.items, new.items),
+            ("tiles", &mut translates.tiles, new.tiles),
+            ("categories", &mut translates.categories, new.categories),
+            ("scripts", &mut translates.scripts, new.scripts),
+            ("gui", &mut translates.gui, new.gui),
+            ("keys", &mut translates.keys, new.keys),
+            ("error", &mut translates.error, new.error),
+            ("research", &mut translates.research, new.research),
+        ] {
+            for (id, text) in new {
+                if !fallback {
+                    current.insert(id, text);
+                    translates.fallen_back.remove(&(kind, id));
+                } else if let Entry::Vacant(entry) = current.entry(id) {
+                    entry.insert(text);
+                    translates.fallen_back.insert((kind, id));
+                }
+            }
+        }
 
         Ok(())
     }
 
+    /// Lists the languages that any of the loaded packs has translations in.
+    pub fn available_languages(&self) -> Vec<String> {
+        let mut languages = BTreeSet::new();
+
+        for pack in &self.packs {
+            let Ok(dir) = read_dir(pack.dir.join("translates")) else {
+                continue;
+            };
+
+            languages.extend(
+                dir.flatten()
+                    .map(|v| v.path())
+                    .filter(|v| v.extension() == Some(OsStr::new(RON_EXT)))
+                    .flat_map(|v| v.file_stem().and_then(OsStr::to_str).map(str::to_string)),
+            );
+        }
+
+        languages.into_iter().collect()
+    }
+
     pub fn load_translates(
         &mut self,
         dir: &Path,
         namespace: &str,
         selected_language: &str,
     ) -> anyhow::Result<()> {
-        let lang = OsStr::new(selected_language);
-
-        if let Ok(dir) = read_dir(dir.join("translates")) {
-            for file in dir
-                .into_iter()
-                .flatten()
-                .map(|v| v.path())
-                .filter(|v| v.extension() == Some(OsStr::new(RON_EXT)))
-            {
-                if file.file_stem() == Some(lang) {
-                    self.load_translate(&file, namespace)?;
-                }
+        let dir = dir.join("translates");
+
+        let mut languages = vec![(selected_language, false)];
+        // the fallback goes after, so that it only fills in what the selected language doesn't have
+        if selected_language != FALLBACK_LANGUAGE {
+            languages.push((FALLBACK_LANGUAGE, true));
+        }
+
+        for (language, fallback) in languages {
+            let file = dir.join(language).with_extension(RON_EXT);
+
+            if file.exists() {
+                let result = self.load_translate(&file, namespace, fallback);
+                self.skip_failed(namespace, &file, result)?;
             }
         }
 
@@ -192,18 +257,25 @@ impl ResourceManager {
 
     pub fn gui_fmt<const LEN: usize>(&self, id: Id, fmt: [(&str, Formattable); LEN]) -> String {
         match self.translates.gui.get(&id) {
-            Some(v) => interpolator::format(v, &FormatContext::from(fmt.into_iter()))
-                .unwrap_or_else(|err| {
-                    panic!(
-                        "Could not format gui translation of ID {:?}. Error: {err:?}. Available variables: {:?}",
-                        self.interner.resolve(id),
-                        fmt,
-                    )
-                }),
+            Some(v) => format_context(v, &FormatContext::from(fmt.into_iter())),
             None => self.translates.unnamed.to_string(),
         }
     }
 
+    /// Formats an amount for display, compacting it if a threshold is given and the amount reaches it.
+    pub fn format_amount(&self, amount: i128, compact_threshold: Option<u64>) -> String {
+        let separator = if self.translates.group_separator.is_empty() {
+            ","
+        } else {
+            self.translates.group_separator.as_ref()
+        };
+
+        match compact_threshold {
+            Some(threshold) => format_amount_compact(amount, threshold, separator),
+            None => format_amount_full(amount, separator),
+        }
+    }
+
     pub fn research_str(&self, id: Id) -> SharedStr {
         match self.translates.research.get(&id) {
             Some(v) => v.clone(),
@@ -0,0 +1,97 @@
+use automancy_defs::kira::manager::backend::mock::MockBackend;
+use automancy_defs::kira::manager::{AudioManager, AudioManagerSettings};
+use automancy_defs::kira::track::TrackBuilder;
+use automancy_resources::format::{format_amount_compact, format_amount_full};
+use automancy_resources::ResourceManager;
+
+fn compact(amount: i128) -> String {
+    format_amount_compact(amount, 1000, ",")
+}
+
+#[test]
+fn full_groups_digits() {
+    assert_eq!(format_amount_full(0, ","), "0");
+    assert_eq!(format_amount_full(999, ","), "999");
+    assert_eq!(format_amount_full(1000, ","), "1,000");
+    assert_eq!(format_amount_full(-1234567, ","), "-1,234,567");
+    assert_eq!(
+        format_amount_full(1234567, "\u{202f}"),
+        "1\u{202f}234\u{202f}567"
+    );
+    assert_eq!(
+        format_amount_full(u64::MAX.into(), ","),
+        "18,446,744,073,709,551,615"
+    );
+    assert_eq!(
+        format_amount_full(i64::MIN.into(), ","),
+        "-9,223,372,036,854,775,808"
+    );
+}
+
+#[test]
+fn compact_boundaries() {
+    assert_eq!(compact(999), "999");
+    assert_eq!(compact(1000), "1k");
+    assert_eq!(compact(1099), "1k");
+    assert_eq!(compact(1100), "1.1k");
+    assert_eq!(compact(1999), "1.9k");
+    assert_eq!(compact(-999), "-999");
+    assert_eq!(compact(-1000), "-1k");
+    assert_eq!(compact(-3_456_789), "-3.4M");
+}
+
+#[test]
+fn compact_rolls_over_suffixes() {
+    assert_eq!(compact(999_999), "999.9k");
+    assert_eq!(compact(1_000_000), "1M");
+    assert_eq!(compact(999_999_999), "999.9M");
+    assert_eq!(compact(1_000_000_000), "1G");
+    assert_eq!(compact(5_600_000_000), "5.6G");
+    assert_eq!(compact(1_000_000_000_000), "1T");
+    assert_eq!(compact(1_000_000_000_000_000), "1P");
+    assert_eq!(compact(1_000_000_000_000_000_000), "1E");
+}
+
+#[test]
+fn compact_very_large() {
+    assert_eq!(compact(i64::MAX.into()), "9.2E");
+    assert_eq!(compact(i64::MIN.into()), "-9.2E");
+    assert_eq!(compact(u64::MAX.into()), "18.4E");
+    // past the last suffix, it is kept with more digits
+    assert_eq!(compact(1_000_000_000_000_000_000_000), "1000E");
+}
+
+#[test]
+fn compact_threshold() {
+    assert_eq!(format_amount_compact(9_999, 10_000, ","), "9,999");
+    assert_eq!(format_amount_compact(10_000, 10_000, ","), "10k");
+    // there is nothing to compact below a thousand, whatever the threshold
+    assert_eq!(format_amount_compact(500, 0, ","), "500");
+    assert_eq!(format_amount_compact(1000, 0, ","), "1k");
+}
+
+#[test]
+fn tooltip_shows_exact_value() {
+    let mut audio_man = AudioManager::<MockBackend>::new(AudioManagerSettings::default()).unwrap();
+    let resource_man = ResourceManager::new(audio_man.add_sub_track(TrackBuilder::new()).unwrap());
+
+    for amount in [
+        0,
+        999,
+        1000,
+        1234,
+        -98_765,
+        1_234_567_890,
+        i64::MAX.into(),
+        u64::MAX.into(),
+    ] {
+        let shown = resource_man.format_amount(amount, Some(1000));
+        let tooltip = resource_man.format_amount(amount, None);
+
+        assert_eq!(tooltip.replace(',', "").parse::<i128>().unwrap(), amount);
+        // the tooltip is only shown when the amount was compacted, which it must be past the threshold
+        assert_eq!(shown != tooltip, amount.unsigned_abs() >= 1000);
+    }
+
+    assert_eq!(resource_man.format_amount(1_234_567, None), "1,234,567");
+}
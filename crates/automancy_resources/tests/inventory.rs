@@ -0,0 +1,161 @@
+use automancy_defs::kira::manager::backend::mock::MockBackend;
+use automancy_defs::kira::manager::{AudioManager, AudioManagerSettings};
+use automancy_defs::kira::track::TrackBuilder;
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::inventory::{Inventory, InventoryLimits};
+use automancy_resources::ResourceManager;
+
+fn resource_man() -> ResourceManager {
+    let mut audio_man = AudioManager::<MockBackend>::new(AudioManagerSettings::default()).unwrap();
+
+    ResourceManager::new(audio_man.add_sub_track(TrackBuilder::new()).unwrap())
+}
+
+#[test]
+fn thresholds_stop_taking_in_the_item() {
+    let mut resource_man = resource_man();
+    let iron = resource_man.interner.get_or_intern("test:iron");
+    let copper = resource_man.interner.get_or_intern("test:copper");
+
+    let mut thresholds = Inventory::default();
+    thresholds.insert(iron, 10);
+    // not a threshold, as with no amount set
+    thresholds.insert(copper, 0);
+
+    let mut data = DataMap::default();
+    data.set(
+        resource_man.registry.data_ids.item_thresholds,
+        Data::Inventory(thresholds),
+    );
+
+    let limits = |id| InventoryLimits::default().with_threshold(&resource_man, &data, id);
+
+    let mut buffer = Inventory::default();
+    assert_eq!(buffer.room_for(iron, limits(iron)), 10);
+    assert!(limits(copper).is_unlimited());
+
+    assert_eq!(buffer.add_limited(iron, 7, limits(iron)), 0);
+    assert_eq!(buffer.room_for(iron, limits(iron)), 3);
+
+    // the rest is left with the sender once the tile holds the threshold
+    assert_eq!(buffer.add_limited(iron, 7, limits(iron)), 4);
+    assert_eq!(buffer.get(iron), 10);
+    assert_eq!(buffer.room_for(iron, limits(iron)), 0);
+
+    // the lower of the stack size and the threshold counts
+    let stacked = InventoryLimits {
+        stack_size: Some(5),
+        ..Default::default()
+    }
+    .with_threshold(&resource_man, &data, iron);
+    assert_eq!(stacked.stack_size, Some(5));
+
+    // without thresholds, nothing changes
+    assert!(InventoryLimits::default()
+        .with_threshold(&resource_man, &DataMap::default(), iron)
+        .is_unlimited());
+}
+
+#[test]
+fn slots_run_out_before_the_capacity() {
+    let mut resource_man = resource_man();
+    let [iron, copper, gold] =
+        ["test:iron", "test:copper", "test:gold"].map(|v| resource_man.interner.get_or_intern(v));
+    let limits = InventoryLimits {
+        slots: Some(2),
+        capacity: Some(100),
+        stack_size: None,
+    };
+
+    let mut buffer = Inventory::default();
+    assert_eq!(buffer.add_limited(iron, 10, limits), 0);
+    assert_eq!(buffer.add_limited(copper, 10, limits), 0);
+
+    // a third item has no slot, while the ones already held still have room
+    assert_eq!(buffer.room_for(gold, limits), 0);
+    assert_eq!(buffer.add_limited(gold, 10, limits), 10);
+    assert_eq!(buffer.get(gold), 0);
+    assert_eq!(buffer.room_for(iron, limits), 80);
+
+    // an emptied slot can be used again
+    buffer.take(copper, 10);
+    assert_eq!(buffer.used_slots(), 1);
+    assert_eq!(buffer.add_limited(gold, 10, limits), 0);
+    assert_eq!(buffer.fullness(limits), Some(1.0));
+}
+
+#[test]
+fn capacity_runs_out_before_the_slots() {
+    let mut resource_man = resource_man();
+    let [iron, copper] =
+        ["test:iron", "test:copper"].map(|v| resource_man.interner.get_or_intern(v));
+    let limits = InventoryLimits {
+        slots: Some(10),
+        capacity: Some(20),
+        stack_size: None,
+    };
+
+    let mut buffer = Inventory::default();
+    assert_eq!(buffer.add_limited(iron, 15, limits), 0);
+
+    // the capacity is shared, so the next item only has what is left of it
+    assert_eq!(buffer.room_for(copper, limits), 5);
+    assert_eq!(buffer.add_limited(copper, 8, limits), 3);
+    assert_eq!(buffer.total(), 20);
+    assert_eq!(buffer.room_for(iron, limits), 0);
+    assert_eq!(buffer.room_for(copper, limits), 0);
+
+    // the capacity is the fuller of the two here
+    assert_eq!(buffer.fullness(limits), Some(1.0));
+    assert_eq!(
+        buffer.fullness(InventoryLimits {
+            capacity: None,
+            ..limits
+        }),
+        Some(0.2)
+    );
+}
+
+#[test]
+fn contents_over_the_limits_are_kept() {
+    let mut resource_man = resource_man();
+    let [iron, copper, gold] =
+        ["test:iron", "test:copper", "test:gold"].map(|v| resource_man.interner.get_or_intern(v));
+
+    // as loaded from a save made before the limits were set
+    let mut buffer = Inventory::default();
+    buffer.insert(iron, 50);
+    buffer.insert(copper, 50);
+    buffer.insert(gold, 50);
+
+    let limits = InventoryLimits {
+        slots: Some(2),
+        capacity: Some(100),
+        stack_size: None,
+    };
+
+    for id in [iron, copper, gold] {
+        assert_eq!(buffer.room_for(id, limits), 0);
+        assert_eq!(buffer.add_limited(id, 1, limits), 1);
+    }
+    assert_eq!(buffer.total(), 150);
+    assert_eq!(buffer.fullness(limits), Some(1.0));
+
+    // taking out still works, and only once under the limits does it take in again
+    buffer.take(gold, 50);
+    assert_eq!(buffer.room_for(iron, limits), 0);
+    buffer.take(copper, 10);
+    assert_eq!(buffer.room_for(iron, limits), 10);
+}
+
+#[test]
+fn no_limits_take_everything() {
+    let mut resource_man = resource_man();
+    let iron = resource_man.interner.get_or_intern("test:iron");
+    let limits = InventoryLimits::default();
+
+    let mut buffer = Inventory::default();
+    assert!(limits.is_unlimited());
+    assert_eq!(buffer.add_limited(iron, 1_000_000, limits), 0);
+    assert_eq!(buffer.fullness(limits), None);
+}
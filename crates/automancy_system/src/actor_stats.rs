@@ -0,0 +1,365 @@
+use automancy_defs::coord::TileCoord;
+use hashbrown::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+/// The number of handling time buckets. The last one ends at about 8 seconds.
+const BUCKETS: usize = 24;
+
+/// The tile entities on every this many columns and rows have their messages counted, one in 16 of them.
+pub const TILE_SAMPLE_STRIDE: i32 = 4;
+
+/// How many of the busiest sampled tiles are reported.
+pub const BUSIEST_TILES: usize = 5;
+
+/// How often the counters are aggregated into a report.
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The counters of the game actor and the sampled tile entities.
+pub static ACTOR_STATS: ActorStats = ActorStats::new();
+
+/// Counts the messages an actor handles and how long they take.
+///
+/// Handling a message only adds to a few atomics. Turning them into rates and estimates is left to [`ActorStats::flush`].
+#[derive(Debug)]
+pub struct MessageCounter {
+    handled: AtomicU64,
+    busy_nanos: AtomicU64,
+    /// the handling times, where bucket `i` counts the ones under 2^i microseconds
+    buckets: [AtomicU64; BUCKETS],
+}
+
+impl Default for MessageCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MessageCounter {
+    pub const fn new() -> Self {
+        Self {
+            handled: AtomicU64::new(0),
+            busy_nanos: AtomicU64::new(0),
+            buckets: [const { AtomicU64::new(0) }; BUCKETS],
+        }
+    }
+
+    /// Counts a handled message that took the duration.
+    pub fn record(&self, elapsed: Duration) {
+        let micros = elapsed.as_micros() as u64;
+        // the smallest i with micros < 2^i
+        let bucket = (u64::BITS - micros.leading_zeros()) as usize;
+
+        self.handled.fetch_add(1, Ordering::Relaxed);
+        self.busy_nanos
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+        self.buckets[bucket.min(BUCKETS - 1)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Starts timing a message, counting it when the returned timer is dropped.
+    pub fn time(&self) -> HandlingTimer<'_> {
+        HandlingTimer {
+            counter: self,
+            start: Instant::now(),
+        }
+    }
+
+    pub fn snapshot(&self) -> CounterSnapshot {
+        CounterSnapshot {
+            handled: self.handled.load(Ordering::Relaxed),
+            busy_nanos: self.busy_nanos.load(Ordering::Relaxed),
+            buckets: self
+                .buckets
+                .each_ref()
+                .map(|bucket| bucket.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Counts the message it is timing when dropped, so that every way out of a handler is counted.
+pub struct HandlingTimer<'a> {
+    counter: &'a MessageCounter,
+    start: Instant,
+}
+
+impl Drop for HandlingTimer<'_> {
+    fn drop(&mut self) {
+        self.counter.record(self.start.elapsed());
+    }
+}
+
+/// The values of a [`MessageCounter`] at one point in time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CounterSnapshot {
+    pub handled: u64,
+    pub busy_nanos: u64,
+    pub buckets: [u64; BUCKETS],
+}
+
+impl CounterSnapshot {
+    fn add(&mut self, other: &CounterSnapshot) {
+        self.handled += other.handled;
+        self.busy_nanos += other.busy_nanos;
+
+        for (bucket, count) in self.buckets.iter_mut().zip(other.buckets) {
+            *bucket += count;
+        }
+    }
+}
+
+/// What an actor did over a while.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MessageStats {
+    /// the messages handled per second
+    pub rate: f64,
+    /// the time 95% of the messages were handled within, rounded up to the bucket's end
+    pub p95: Duration,
+    /// the estimated number of messages waiting in the mailbox
+    pub backlog: f64,
+}
+
+impl MessageStats {
+    /// Works out the stats between two snapshots of the same counter, taken the window apart.
+    ///
+    /// The mailbox of a ractor actor can't be looked into, so the backlog is estimated from how busy the actor was,
+    /// as the mean queue length of an M/M/1 queue: `busy / (1 - busy)`, where `busy` is the fraction of the window spent handling.
+    pub fn between(earlier: &CounterSnapshot, later: &CounterSnapshot, window: Duration) -> Self {
+        let window = window.as_secs_f64();
+        if window <= 0.0 {
+            return Self::default();
+        }
+
+        let handled = later.handled.saturating_sub(earlier.handled);
+        let busy_nanos = later.busy_nanos.saturating_sub(earlier.busy_nanos);
+        let buckets: [u64; BUCKETS] =
+            std::array::from_fn(|i| later.buckets[i].saturating_sub(earlier.buckets[i]));
+
+        let p95 = {
+            let threshold = (handled * 95).div_ceil(100);
+            let mut seen = 0;
+
+            buckets
+                .iter()
+                .position(|count| {
+                    seen += count;
+                    seen >= threshold
+                })
+                .filter(|_| handled > 0)
+                .map(|bucket| Duration::from_micros(1 << bucket))
+                .unwrap_or_default()
+        };
+
+        let busy = (busy_nanos as f64 / 1_000_000_000.0 / window).min(0.999);
+
+        Self {
+            rate: handled as f64 / window,
+            p95,
+            backlog: busy / (1.0 - busy),
+        }
+    }
+}
+
+/// The aggregated stats of the last flush.
+#[derive(Debug, Clone, Default)]
+pub struct ActorStatsReport {
+    pub game: MessageStats,
+    /// the sampled tile entities put together
+    pub tiles: MessageStats,
+    /// how many tile entities were sampled
+    pub sampled_tiles: usize,
+    /// the busiest sampled tile entities, busiest first
+    pub busiest_tiles: Vec<(TileCoord, MessageStats)>,
+}
+
+#[derive(Debug, Default)]
+struct FlushState {
+    at: Option<Instant>,
+    game: CounterSnapshot,
+    tiles: HashMap<TileCoord, CounterSnapshot>,
+    report: ActorStatsReport,
+}
+
+/// The message counters of the game actor and of the sampled tile entities, along with the last report made from them.
+#[derive(Debug)]
+pub struct ActorStats {
+    pub game: MessageCounter,
+    /// the counters are owned by the tile entities, so the ones of removed tiles go away by themselves
+    tiles: Mutex<Option<HashMap<TileCoord, Weak<MessageCounter>>>>,
+    flushed: Mutex<Option<FlushState>>,
+    /// whether the messages of the game actor are counted by kind, which takes a lock per message
+    counting_kinds: AtomicBool,
+    kinds: Mutex<Option<HashMap<&'static str, u64>>>,
+}
+
+impl Default for ActorStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ActorStats {
+    pub const fn new() -> Self {
+        Self {
+            game: MessageCounter::new(),
+            tiles: Mutex::new(None),
+            flushed: Mutex::new(None),
+            counting_kinds: AtomicBool::new(false),
+            kinds: Mutex::new(None),
+        }
+    }
+
+    /// Returns whether the messages of the tile entity at the coordinate are counted.
+    pub fn is_sampled(coord: TileCoord) -> bool {
+        coord.x.rem_euclid(TILE_SAMPLE_STRIDE) == 0 && coord.y.rem_euclid(TILE_SAMPLE_STRIDE) == 0
+    }
+
+    /// Gives the tile entity at the coordinate a counter if it is sampled. The tile entity should keep it for as long as it lives.
+    pub fn tile_counter(&self, coord: TileCoord) -> Option<Arc<MessageCounter>> {
+        if !Self::is_sampled(coord) {
+            return None;
+        }
+
+        let counter = Arc::new(MessageCounter::new());
+
+        self.tiles
+            .lock()
+            .unwrap()
+            .get_or_insert_with(Default::default)
+            .insert(coord, Arc::downgrade(&counter));
+
+        Some(counter)
+    }
+
+    /// Aggregates what was counted since the last flush into a new report.
+    ///
+    /// This is meant to be called every once in a while from a task of its own, see [`spawn_flush_task`].
+    pub fn flush(&self) {
+        let now = Instant::now();
+
+        let tiles = {
+            let mut tiles = self.tiles.lock().unwrap();
+            let tiles = tiles.get_or_insert_with(Default::default);
+
+            tiles.retain(|_, counter| counter.strong_count() > 0);
+
+            tiles
+                .iter()
+                .flat_map(|(coord, counter)| Some((*coord, counter.upgrade()?.snapshot())))
+                .collect::<HashMap<_, _>>()
+        };
+        let game = self.game.snapshot();
+
+        let mut flushed = self.flushed.lock().unwrap();
+        let flushed = flushed.get_or_insert_with(Default::default);
+
+        if let Some(at) = flushed.at {
+            let window = now - at;
+
+            let mut tiles_total = CounterSnapshot::default();
+            let mut tiles_earlier = CounterSnapshot::default();
+            let mut busiest_tiles = Vec::new();
+
+            for (coord, later) in &tiles {
+                // tiles that came up since the last flush start from nothing
+                let earlier = flushed.tiles.get(coord).copied().unwrap_or_default();
+
+                tiles_total.add(later);
+                tiles_earlier.add(&earlier);
+
+                busiest_tiles.push((*coord, MessageStats::between(&earlier, later, window)));
+            }
+
+            busiest_tiles.sort_by(|(_, a), (_, b)| b.rate.total_cmp(&a.rate));
+            busiest_tiles.truncate(BUSIEST_TILES);
+
+            flushed.report = ActorStatsReport {
+                game: MessageStats::between(&flushed.game, &game, window),
+                tiles: MessageStats::between(&tiles_earlier, &tiles_total, window),
+                sampled_tiles: tiles.len(),
+                busiest_tiles,
+            };
+        }
+
+        flushed.at = Some(now);
+        flushed.game = game;
+        flushed.tiles = tiles;
+    }
+
+    /// Starts counting the messages of the game actor by kind. This is off by default, as it is only wanted when benchmarking.
+    pub fn count_kinds(&self) {
+        self.counting_kinds.store(true, Ordering::Relaxed);
+    }
+
+    /// Counts a message of the game actor of the kind, if they are counted by kind.
+    pub fn record_kind(&self, kind: &'static str) {
+        if !self.counting_kinds.load(Ordering::Relaxed) {
+            return;
+        }
+
+        *self
+            .kinds
+            .lock()
+            .unwrap()
+            .get_or_insert_with(Default::default)
+            .entry(kind)
+            .or_insert(0) += 1;
+    }
+
+    /// Gets how many messages of each kind the game actor handled since they started being counted, the most first.
+    pub fn kind_counts(&self) -> Vec<(&'static str, u64)> {
+        let mut counts = self
+            .kinds
+            .lock()
+            .unwrap()
+            .iter()
+            .flatten()
+            .map(|(kind, count)| (*kind, *count))
+            .collect::<Vec<_>>();
+        counts.sort_by(|(a_kind, a), (b_kind, b)| b.cmp(a).then_with(|| a_kind.cmp(b_kind)));
+
+        counts
+    }
+
+    /// Gets the report of the last flush.
+    pub fn report(&self) -> ActorStatsReport {
+        self.flushed
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|flushed| flushed.report.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Spawns the task that flushes [`ACTOR_STATS`] every [`FLUSH_INTERVAL`].
+pub fn spawn_flush_task(tokio: &tokio::runtime::Runtime) {
+    tokio.spawn(async {
+        let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            ACTOR_STATS.flush();
+        }
+    });
+}
+
+/// Formats a message rate shortly, like "1.2k".
+pub fn format_rate(rate: f64) -> String {
+    if rate >= 1000.0 {
+        format!("{:.1}k", rate / 1000.0)
+    } else {
+        format!("{rate:.0}")
+    }
+}
+
+/// Formats the stats like "1.2k msg/s, p95 handling 0.4ms, est. backlog 12".
+pub fn format_stats(stats: &MessageStats) -> String {
+    format!(
+        "{} msg/s, p95 handling {:.1}ms, est. backlog {:.0}",
+        format_rate(stats.rate),
+        stats.p95.as_secs_f64() * 1000.0,
+        stats.backlog
+    )
+}
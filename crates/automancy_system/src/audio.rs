@@ -0,0 +1,402 @@
+use crate::options::AudioOptions;
+use anyhow::anyhow;
+use automancy_defs::{
+    coord::TileCoord,
+    glam::{vec3, Vec3},
+    id::Id,
+    kira::{
+        manager::AudioManager,
+        sound::{static_sound::StaticSoundHandle, PlaybackState},
+        track::{TrackBuilder, TrackHandle},
+        tween::Tween,
+    },
+    math::{Float, HEX_GRID_LAYOUT},
+};
+use automancy_resources::{
+    types::audio::{Sound, SoundCategory},
+    ResourceManager,
+};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::thread_rng;
+use std::time::Duration;
+
+/// How far from the camera a sound coming from the world can be heard, in world units.
+pub const SFX_CUTOFF_RADIUS: Float = 24.0;
+/// How many sounds coming from the world can play at once. The quietest ones are dropped for louder ones past this.
+pub const MAX_SFX_VOICES: usize = 16;
+/// How long the sounds coming from the world take to follow the camera as it moves.
+const ATTENUATION_TWEEN: Duration = Duration::from_millis(50);
+/// How much the loudness of a sound coming from the world has to change before its volume is set again.
+const GAIN_EPSILON: f64 = 0.01;
+/// How long one piece of music takes to fade into the next, and the ambience into another.
+pub const CROSSFADE: Duration = Duration::from_millis(2500);
+/// How loud the music is while the game is paused or an error is up, relative to its volume.
+const DUCKED_VOLUME: f64 = 0.3;
+/// How long the music takes to duck, and to come back.
+const DUCK_TWEEN: Duration = Duration::from_millis(500);
+
+/// A sound coming from a tile that is playing.
+struct WorldVoice {
+    handle: StaticSoundHandle,
+    coord: TileCoord,
+    /// how loud it is where the camera is, from 0 to 1
+    gain: f64,
+}
+
+/// A looping or playlist sound that is playing.
+struct Playing {
+    name: String,
+    handle: StaticSoundHandle,
+    duration: Duration,
+}
+
+impl Playing {
+    fn stop(mut self) {
+        self.handle.stop(Tween {
+            duration: CROSSFADE,
+            ..Default::default()
+        });
+    }
+}
+
+/// The tracks the sounds are played through, one per [SoundCategory], along with the sounds coming from the world,
+/// the music playlist, and the ambience of the map.
+pub struct AudioChannels {
+    music_track: TrackHandle,
+    sfx_track: TrackHandle,
+    voices: Vec<WorldVoice>,
+
+    /// the music volume from the options, before ducking
+    music_volume: f64,
+    ducked: bool,
+    /// the mood the music is picked for
+    mood: Option<String>,
+    music: Option<Playing>,
+    /// the ID of the ambience, along with the sound that is looping
+    ambience: Option<(Id, Option<Playing>)>,
+}
+
+/// Gets how loud a sound coming from the coordinate is where the camera is, or None if it's past [SFX_CUTOFF_RADIUS].
+pub fn attenuation(coord: TileCoord, listener: Vec3) -> Option<f64> {
+    let pos = HEX_GRID_LAYOUT.hex_to_world_pos(*coord);
+    let distance = listener.distance(vec3(pos.x, pos.y, 0.0));
+
+    (distance < SFX_CUTOFF_RADIUS).then(|| (1.0 - (distance / SFX_CUTOFF_RADIUS) as f64).powi(2))
+}
+
+fn get_sound<'a>(resource_man: &'a ResourceManager, name: &str) -> anyhow::Result<&'a Sound> {
+    resource_man
+        .audio
+        .get(name)
+        .ok_or_else(|| anyhow!("There is no sound named {name}"))
+}
+
+impl AudioChannels {
+    pub fn new(audio_man: &mut AudioManager, options: &AudioOptions) -> anyhow::Result<Self> {
+        let music_track =
+            audio_man.add_sub_track(TrackBuilder::new().volume(options.music_volume))?;
+        let sfx_track = audio_man.add_sub_track(TrackBuilder::new().volume(options.sfx_volume))?;
+
+        audio_man
+            .main_track()
+            .set_volume(options.master_volume, Tween::default());
+
+        Ok(Self {
+            music_track,
+            sfx_track,
+            voices: vec![],
+
+            music_volume: options.music_volume,
+            ducked: false,
+            mood: None,
+            music: None,
+            ambience: None,
+        })
+    }
+
+    fn apply_music_volume(&mut self, tween: Tween) {
+        let volume = if self.ducked {
+            self.music_volume * DUCKED_VOLUME
+        } else {
+            self.music_volume
+        };
+
+        self.music_track.set_volume(volume, tween);
+    }
+
+    /// Sets the volumes of the channels. This also changes the volume of the sounds that are already playing.
+    pub fn apply_options(&mut self, audio_man: &mut AudioManager, options: &AudioOptions) {
+        audio_man
+            .main_track()
+            .set_volume(options.master_volume, Tween::default());
+        self.sfx_track
+            .set_volume(options.sfx_volume, Tween::default());
+
+        self.music_volume = options.music_volume;
+        self.apply_music_volume(Tween::default());
+    }
+
+    fn track(&self, category: SoundCategory) -> &TrackHandle {
+        match category {
+            SoundCategory::Music => &self.music_track,
+            SoundCategory::Sfx => &self.sfx_track,
+        }
+    }
+
+    /// Plays the sound through the channel of its category.
+    pub fn play(
+        &mut self,
+        audio_man: &mut AudioManager,
+        resource_man: &ResourceManager,
+        name: &str,
+    ) -> anyhow::Result<()> {
+        let sound = get_sound(resource_man, name)?;
+
+        audio_man.play(sound.data.output_destination(self.track(sound.category)))?;
+
+        Ok(())
+    }
+
+    /// Plays the sound as coming from the tile, quieter the further it is from the camera.
+    ///
+    /// Sounds past [SFX_CUTOFF_RADIUS] aren't played. If [MAX_SFX_VOICES] are already playing,
+    /// the quietest one is stopped for this one, unless this one would be the quietest.
+    pub fn play_at(
+        &mut self,
+        audio_man: &mut AudioManager,
+        resource_man: &ResourceManager,
+        name: &str,
+        coord: TileCoord,
+        listener: Vec3,
+    ) -> anyhow::Result<()> {
+        let sound = get_sound(resource_man, name)?;
+
+        let Some(gain) = attenuation(coord, listener) else {
+            return Ok(());
+        };
+
+        self.voices
+            .retain(|voice| voice.handle.state() != PlaybackState::Stopped);
+
+        if self.voices.len() >= MAX_SFX_VOICES {
+            let quietest = self
+                .voices
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| a.gain.total_cmp(&b.gain))
+                .map(|(index, voice)| (index, voice.gain));
+
+            match quietest {
+                Some((index, quietest)) if quietest < gain => {
+                    let mut voice = self.voices.swap_remove(index);
+                    voice.handle.stop(Tween::default());
+                }
+                _ => return Ok(()),
+            }
+        }
+
+        let handle = audio_man.play(
+            sound
+                .data
+                .output_destination(self.track(sound.category))
+                .volume(gain),
+        )?;
+
+        self.voices.push(WorldVoice {
+            handle,
+            coord,
+            gain,
+        });
+
+        Ok(())
+    }
+
+    /// Makes the sounds coming from the world follow the camera, and forgets the ones that are done.
+    pub fn update(&mut self, listener: Vec3) {
+        self.voices
+            .retain(|voice| voice.handle.state() != PlaybackState::Stopped);
+
+        for voice in &mut self.voices {
+            // past the cutoff they go quiet rather than stop, so they are the first to be dropped for new ones
+            let gain = attenuation(voice.coord, listener).unwrap_or(0.0);
+
+            if (gain - voice.gain).abs() > GAIN_EPSILON {
+                voice.gain = gain;
+                voice.handle.set_volume(
+                    gain,
+                    Tween {
+                        duration: ATTENUATION_TWEEN,
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+    }
+
+    /// Gets the name of the music that is playing.
+    pub fn now_playing(&self) -> Option<&str> {
+        self.music.as_ref().map(|music| music.name.as_str())
+    }
+
+    /// Gets the name of the ambience that is looping.
+    pub fn ambience(&self) -> Option<&str> {
+        self.ambience
+            .as_ref()
+            .and_then(|(_, playing)| playing.as_ref())
+            .map(|ambience| ambience.name.as_str())
+    }
+
+    /// Starts a sound that fades in over [CROSSFADE].
+    fn fade_in(
+        &self,
+        audio_man: &mut AudioManager,
+        resource_man: &ResourceManager,
+        name: &str,
+        looping: bool,
+    ) -> anyhow::Result<Playing> {
+        let sound = get_sound(resource_man, name)?;
+
+        let mut data = sound
+            .data
+            .output_destination(self.track(sound.category))
+            .fade_in_tween(Tween {
+                duration: CROSSFADE,
+                ..Default::default()
+            });
+        if looping {
+            data = data.loop_region(..);
+        }
+
+        Ok(Playing {
+            name: name.to_string(),
+            duration: sound.data.duration(),
+            handle: audio_man.play(data)?,
+        })
+    }
+
+    /// Checks if the music is in the playlist for the mood.
+    fn fits_mood(&self, resource_man: &ResourceManager, name: &str) -> bool {
+        resource_man.audio.get(name).is_some_and(|sound| {
+            sound.category == SoundCategory::Music
+                && sound.weight > 0.0
+                && sound
+                    .mood
+                    .as_ref()
+                    .map_or(true, |mood| Some(mood) == self.mood.as_ref())
+        })
+    }
+
+    /// Picks the next music by the weights of the music that fits the mood. The music that is playing is only picked again if it's the only one.
+    fn pick_music(&self, resource_man: &ResourceManager) -> Option<String> {
+        let mut pool = resource_man
+            .audio
+            .iter()
+            .filter(|(name, _)| self.fits_mood(resource_man, name))
+            .map(|(name, sound)| (name.clone(), sound.weight))
+            .collect::<Vec<_>>();
+
+        if pool.len() > 1 {
+            pool.retain(|(name, _)| Some(name.as_str()) != self.now_playing());
+        }
+
+        let index = WeightedIndex::new(pool.iter().map(|(_, weight)| *weight))
+            .ok()?
+            .sample(&mut thread_rng());
+
+        Some(pool.swap_remove(index).0)
+    }
+
+    /// Fades the music that is playing out, and the next one in. The music stops if there is none left for the mood.
+    pub fn skip_music(&mut self, audio_man: &mut AudioManager, resource_man: &ResourceManager) {
+        let next = self.pick_music(resource_man);
+
+        if let Some(music) = self.music.take() {
+            music.stop();
+        }
+
+        let Some(name) = next else {
+            return;
+        };
+
+        match self.fade_in(audio_man, resource_man, &name, false) {
+            Ok(music) => self.music = Some(music),
+            Err(err) => log::warn!("Could not play the music {name}! Error: {err}"),
+        }
+    }
+
+    /// Sets the mood the music is picked for. The music that is playing fades out if it doesn't fit the new one.
+    pub fn set_mood(&mut self, mood: Option<String>) {
+        self.mood = mood;
+    }
+
+    /// Loops the ambience under the music, fading it in over the one that was looping. None stops the ambience.
+    pub fn set_ambience(
+        &mut self,
+        audio_man: &mut AudioManager,
+        resource_man: &ResourceManager,
+        id: Option<Id>,
+    ) {
+        if self.ambience.as_ref().map(|(id, _)| *id) == id {
+            return;
+        }
+
+        if let Some(playing) = self.ambience.take().and_then(|(_, playing)| playing) {
+            playing.stop();
+        }
+
+        let Some(id) = id else {
+            return;
+        };
+
+        let playing = match resource_man.audio_ids.get(&id) {
+            Some(name) => match self.fade_in(audio_man, resource_man, name, true) {
+                Ok(playing) => Some(playing),
+                Err(err) => {
+                    log::warn!("Could not play the ambience {name}! Error: {err}");
+
+                    None
+                }
+            },
+            None => {
+                log::warn!("There is no sound with the ID {id:?} to use as the ambience");
+
+                None
+            }
+        };
+
+        // kept even if it can't play, so that it isn't tried again every time
+        self.ambience = Some((id, playing));
+    }
+
+    /// Moves the playlist on once the music is about to end or doesn't fit the mood anymore, crossfading into the next one.
+    /// The music is ducked while `ducked` is set.
+    pub fn update_music(
+        &mut self,
+        audio_man: &mut AudioManager,
+        resource_man: &ResourceManager,
+        ducked: bool,
+    ) {
+        if ducked != self.ducked {
+            self.ducked = ducked;
+            self.apply_music_volume(Tween {
+                duration: DUCK_TWEEN,
+                ..Default::default()
+            });
+        }
+
+        let next = match &self.music {
+            Some(music) => {
+                music.handle.state() == PlaybackState::Stopped
+                    || Duration::from_secs_f64(music.handle.position()) + CROSSFADE
+                        >= music.duration
+                    || !self.fits_mood(resource_man, &music.name)
+            }
+            None => true,
+        };
+
+        // without anything that fits, this is a lookup through the sounds, so it's fine to try every frame
+        if next {
+            self.skip_music(audio_man, resource_man);
+        }
+    }
+}
@@ -1,4 +1,5 @@
-use crate::input::InputHandler;
+use crate::input::{ActionType, InputHandler, Modifier};
+use crate::options::CameraOptions;
 use automancy_defs::glam::{vec2, vec3, Vec2, Vec3};
 use automancy_defs::hexx::Hex;
 use automancy_defs::math;
@@ -7,13 +8,47 @@ use automancy_defs::{
     coord::{TileBounds, TileCoord},
     math::Matrix4,
 };
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::registry::DataIds;
+use std::f32::consts::TAU;
 use std::ops::Mul;
 
+/// The zoom level past which the models start fading into the strategic view.
+pub const STRATEGIC_ZOOM_START: Float = 4.0;
+/// How much further the camera has to zoom out for the strategic view to fully replace the models.
+pub const STRATEGIC_ZOOM_BAND: Float = 0.5;
+/// How far one step of the scroll wheel zooms.
+const ZOOM_STEP: Float = 1.0 / 3.0;
+/// How quickly the zoom catches up with where it was scrolled to. Higher is quicker.
+const ZOOM_SMOOTHING: Float = 12.0;
+/// How fast the camera turns while a rotation key is held, in radians per second.
+const KEY_ROTATION_SPEED: Float = 1.5;
+/// How far the camera turns per pixel the mouse is dragged, in radians.
+const DRAG_ROTATION_SPEED: Float = 0.005;
+/// How quickly the camera catches up with where it is panning to. Higher is quicker.
+const PAN_SMOOTHING: Float = 6.0;
+/// The number of camera bookmarks a map can have, bound to the number keys from 1 to 9.
+pub const BOOKMARK_SLOTS: usize = 9;
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct GameCamera {
     pos: Vec3,
     move_vel: Vec2,
-    scroll_vel: Float,
+    /// the zoom level the camera is easing towards
+    target_zoom: Float,
+    /// the rotation around the Z axis, in radians
+    rotation: Float,
+    /// how fast the camera is turning from the rotation keys, in radians per second
+    rotation_vel: Float,
+    /// where the camera is easing towards, if it was sent somewhere
+    pan_target: Option<Vec2>,
+
+    /// how far in the camera can zoom, with the zoom level as in [GameCamera::get_raw_pos]
+    pub min_zoom: Float,
+    /// how far out the camera can zoom
+    pub max_zoom: Float,
+    /// whether the camera can be turned
+    pub rotation_enabled: bool,
 
     pub culling_range: TileBounds,
     pub pointing_at: TileCoord,
@@ -39,14 +74,22 @@ pub fn fit_pos(Vec3 { x, y, z }: Vec3) -> Vec3 {
 impl GameCamera {
     pub fn new((width, height): (Float, Float)) -> Self {
         let pos = vec3(0.0, 0.0, 2.0);
-        let matrix = camera_matrix(fit_pos(pos), width / height);
+        let matrix = camera_matrix(fit_pos(pos), 0.0, width / height);
+        let options = CameraOptions::default();
 
         Self {
             pos,
             move_vel: vec2(0.0, 0.0),
-            scroll_vel: 0.0,
+            target_zoom: pos.z,
+            rotation: 0.0,
+            rotation_vel: 0.0,
+            pan_target: None,
 
-            culling_range: math::get_culling_range((width, height), fit_pos(pos)),
+            min_zoom: options.min_zoom,
+            max_zoom: options.max_zoom,
+            rotation_enabled: options.rotation,
+
+            culling_range: math::get_culling_range((width, height), fit_pos(pos), 0.0),
             pointing_at: TileCoord::new(0, 0),
             matrix,
         }
@@ -57,6 +100,49 @@ impl GameCamera {
         fit_pos(self.pos)
     }
 
+    /// Returns where the camera is, with the zoom level as the z coordinate.
+    pub fn get_raw_pos(&self) -> Vec3 {
+        self.pos
+    }
+
+    /// Moves the camera to the position, with the zoom level as the z coordinate, dropping any movement it was carrying.
+    pub fn set_raw_pos(&mut self, pos: Vec3) {
+        self.pos = vec3(pos.x, pos.y, pos.z.clamp(self.min_zoom, self.max_zoom));
+        self.stop();
+    }
+
+    /// Pans the camera smoothly to the position, with the zoom level as the z coordinate. Moving the camera by hand stops the pan.
+    pub fn pan_to(&mut self, pos: Vec3) {
+        self.move_vel = vec2(0.0, 0.0);
+        self.pan_target = Some(pos.truncate());
+        self.target_zoom = pos.z.clamp(self.min_zoom, self.max_zoom);
+    }
+
+    /// Returns how far the camera is turned around the Z axis, in radians.
+    pub fn get_rotation(&self) -> Float {
+        self.rotation
+    }
+
+    /// Uses the zoom limits and the rotation setting of the options, turning the camera back if it can no longer be turned.
+    pub fn apply_options(&mut self, options: &CameraOptions) {
+        self.min_zoom = options.min_zoom.min(options.max_zoom);
+        self.max_zoom = options.max_zoom.max(options.min_zoom);
+        self.rotation_enabled = options.rotation;
+
+        if !self.rotation_enabled {
+            self.rotation = 0.0;
+            self.rotation_vel = 0.0;
+        }
+
+        self.pos.z = self.pos.z.clamp(self.min_zoom, self.max_zoom);
+        self.target_zoom = self.target_zoom.clamp(self.min_zoom, self.max_zoom);
+    }
+
+    /// Returns how far the strategic view has faded in, from 0 (only models) to 1 (only flat hexagons).
+    pub fn strategic_blend(&self) -> Float {
+        ((self.pos.z - STRATEGIC_ZOOM_START) / STRATEGIC_ZOOM_BAND).clamp(0.0, 1.0)
+    }
+
     pub fn get_matrix(&self) -> Matrix4 {
         self.matrix
     }
@@ -66,7 +152,8 @@ impl GameCamera {
     /// Sets the position the camera is centered on.
     pub fn update_pointing_at(&mut self, main_pos: Vec2, (width, height): (Float, Float)) {
         let p = Hex::round(
-            math::main_pos_to_fract_hex((width, height), main_pos, self.get_pos()).to_array(),
+            math::main_pos_to_fract_hex((width, height), main_pos, self.get_pos(), self.rotation)
+                .to_array(),
         );
 
         self.pointing_at = p.into();
@@ -81,9 +168,25 @@ impl GameCamera {
 
     /// Updates the movement state of the camera based on input.
     pub fn handle_input(&mut self, input: &InputHandler) {
+        let rotating = self.rotation_enabled && input.modifier_held(Modifier::Alt);
+
         if input.tertiary_held {
             if let Some(delta) = input.main_move {
-                self.on_moving_main(delta);
+                if rotating {
+                    self.rotation += delta.x * DRAG_ROTATION_SPEED;
+                } else {
+                    self.on_moving_main(delta);
+                }
+            }
+        }
+
+        self.rotation_vel = 0.0;
+        if self.rotation_enabled {
+            if input.key_active(ActionType::RotateLeft) {
+                self.rotation_vel += KEY_ROTATION_SPEED;
+            }
+            if input.key_active(ActionType::RotateRight) {
+                self.rotation_vel -= KEY_ROTATION_SPEED;
             }
         }
 
@@ -92,6 +195,26 @@ impl GameCamera {
         }
     }
 
+    /// Pans the camera along with the stick of a gamepad, faster the further out it is zoomed.
+    pub fn pan(&mut self, stick: Vec2, elapsed: Float) {
+        const STICK_PAN_SPEED: Float = 200.0;
+
+        if stick == Vec2::ZERO {
+            return;
+        }
+
+        // dragging moves the map with the cursor, while the stick moves the view
+        self.on_moving_main(-stick * STICK_PAN_SPEED * self.pos.z.max(0.5) * elapsed);
+    }
+
+    /// Stops any movement the camera is still carrying.
+    pub fn stop(&mut self) {
+        self.move_vel = vec2(0.0, 0.0);
+        self.target_zoom = self.pos.z;
+        self.rotation_vel = 0.0;
+        self.pan_target = None;
+    }
+
     /// Updates the camera's position.
     pub fn update_pos(&mut self, (width, height): (Float, Float), elapsed: Float) {
         let m = elapsed * 100.0;
@@ -103,28 +226,41 @@ impl GameCamera {
             self.move_vel -= self.move_vel * elapsed.mul(4.0).min(0.9);
         }
 
-        if self.scroll_vel.abs() > 0.00005 {
-            self.pos.z += self.scroll_vel * m;
-            self.pos.z = self.pos.z.clamp(0.05, 4.0);
+        if let Some(target) = self.pan_target {
+            let left = target - self.pos.truncate();
 
-            self.scroll_vel -= self.scroll_vel * elapsed.mul(15.0).min(0.9);
+            if left.length_squared() > 0.0001 {
+                let step = left * (1.0 - (-elapsed * PAN_SMOOTHING).exp());
+                self.pos.x += step.x;
+                self.pos.y += step.y;
+            } else {
+                self.pos.x = target.x;
+                self.pos.y = target.y;
+                self.pan_target = None;
+            }
+        }
+
+        let zoom_left = self.target_zoom - self.pos.z;
+        if zoom_left.abs() > 0.00005 {
+            // eases in the same way at any frame rate
+            self.pos.z += zoom_left * (1.0 - (-elapsed * ZOOM_SMOOTHING).exp());
+            self.pos.z = self.pos.z.clamp(self.min_zoom, self.max_zoom);
         }
 
-        self.matrix = camera_matrix(self.get_pos(), width / height);
-        self.culling_range = math::get_culling_range((width, height), self.get_pos());
+        self.rotation = (self.rotation + self.rotation_vel * elapsed).rem_euclid(TAU);
+
+        self.matrix = camera_matrix(self.get_pos(), self.rotation, width / height);
+        self.culling_range =
+            math::get_culling_range((width, height), self.get_pos(), self.rotation);
     }
 
     /// Called when the camera is scrolled.
     fn on_scroll(&mut self, delta: Vec2) {
-        const MAX_SCROLL_VEL: Float = 0.2;
-
         let y = delta.y;
 
         if y != 0.0 {
-            let change = -y;
-
-            self.scroll_vel += change / 20.0;
-            self.scroll_vel = self.scroll_vel.clamp(-MAX_SCROLL_VEL, MAX_SCROLL_VEL);
+            self.target_zoom =
+                (self.target_zoom - y * ZOOM_STEP).clamp(self.min_zoom, self.max_zoom);
         }
     }
 
@@ -132,10 +268,294 @@ impl GameCamera {
     fn on_moving_main(&mut self, delta: Vec2) {
         const MAX_MOVE_VEL: Float = 2.0;
 
-        self.move_vel += delta / 500.0;
+        self.pan_target = None;
+
+        // the drag is on the screen, which is turned along with the camera
+        self.move_vel += Vec2::from_angle(self.rotation).rotate(delta) / 500.0;
         self.move_vel = self.move_vel.clamp(
             vec2(-MAX_MOVE_VEL, -MAX_MOVE_VEL),
             vec2(MAX_MOVE_VEL, MAX_MOVE_VEL),
         );
     }
 }
+
+/// A viewpoint along a [CameraPath].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraWaypoint {
+    pub coord: TileCoord,
+    pub zoom: Float,
+    /// How long, in seconds, the camera stays at the waypoint.
+    pub dwell: Float,
+    /// How long, in seconds, the camera takes to fly from the waypoint to the next one.
+    pub transition: Float,
+}
+
+impl CameraWaypoint {
+    pub const DEFAULT_DWELL: Float = 1.0;
+    pub const DEFAULT_TRANSITION: Float = 3.0;
+
+    pub fn new(coord: TileCoord, zoom: Float) -> Self {
+        Self {
+            coord,
+            zoom,
+            dwell: Self::DEFAULT_DWELL,
+            transition: Self::DEFAULT_TRANSITION,
+        }
+    }
+
+    /// Returns the camera position of the waypoint, with the zoom level as the z coordinate.
+    pub fn pos(&self) -> Vec3 {
+        HEX_GRID_LAYOUT
+            .hex_to_world_pos(*self.coord)
+            .extend(self.zoom)
+    }
+}
+
+/// A place on the map the camera can be sent back to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraBookmark {
+    pub coord: TileCoord,
+    /// the zoom level, as in [GameCamera::get_raw_pos]
+    pub zoom: Float,
+    pub name: String,
+}
+
+impl CameraBookmark {
+    /// Returns the camera position of the bookmark, with the zoom level as the z coordinate.
+    pub fn pos(&self) -> Vec3 {
+        HEX_GRID_LAYOUT
+            .hex_to_world_pos(*self.coord)
+            .extend(self.zoom)
+    }
+}
+
+/// The map's camera bookmarks, by slot.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CameraBookmarks {
+    pub slots: [Option<CameraBookmark>; BOOKMARK_SLOTS],
+}
+
+impl CameraBookmarks {
+    /// Reads the bookmarks stored in the map data.
+    pub fn from_data(data: &DataMap, data_ids: &DataIds) -> Self {
+        let coords = match data.get(data_ids.camera_bookmarks) {
+            Some(Data::VecCoord(v)) => v.as_slice(),
+            _ => &[],
+        };
+        let slots = match data.get(data_ids.camera_bookmark_slots) {
+            Some(Data::VecFloat(v)) => v.as_slice(),
+            _ => &[],
+        };
+        let names = match data.get(data_ids.camera_bookmark_names) {
+            Some(Data::VecString(v)) => v.as_slice(),
+            _ => &[],
+        };
+
+        let mut bookmarks = Self::default();
+
+        for (index, coord) in coords.iter().enumerate() {
+            let Some(&[slot, zoom]) = slots.get(index * 2..index * 2 + 2) else {
+                continue;
+            };
+
+            if let Some(bookmark) = bookmarks.slots.get_mut(slot as usize) {
+                *bookmark = Some(CameraBookmark {
+                    coord: *coord,
+                    zoom,
+                    name: names.get(index).cloned().unwrap_or_default(),
+                });
+            }
+        }
+
+        bookmarks
+    }
+
+    /// Writes the bookmarks into the map data, where the coordinates, the slot and zoom, and the names of the set slots are kept as lists.
+    pub fn write_data(&self, data: &mut DataMap, data_ids: &DataIds) {
+        let set = self
+            .slots
+            .iter()
+            .enumerate()
+            .flat_map(|(slot, bookmark)| bookmark.as_ref().map(|v| (slot, v)))
+            .collect::<Vec<_>>();
+
+        if set.is_empty() {
+            data.remove(data_ids.camera_bookmarks);
+            data.remove(data_ids.camera_bookmark_slots);
+            data.remove(data_ids.camera_bookmark_names);
+
+            return;
+        }
+
+        data.set(
+            data_ids.camera_bookmarks,
+            Data::VecCoord(set.iter().map(|(_, v)| v.coord).collect()),
+        );
+        data.set(
+            data_ids.camera_bookmark_slots,
+            Data::VecFloat(
+                set.iter()
+                    .flat_map(|(slot, v)| [*slot as Float, v.zoom])
+                    .collect(),
+            ),
+        );
+        data.set(
+            data_ids.camera_bookmark_names,
+            Data::VecString(set.iter().map(|(_, v)| v.name.clone()).collect()),
+        );
+    }
+}
+
+/// Evaluates the uniform Catmull-Rom spline through p1 and p2 at t, from 0 at p1 to 1 at p2.
+pub fn catmull_rom(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: Float) -> Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+
+    0.5 * ((2.0 * p1)
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+/// Eases t from 0 to 1 such that it starts and ends at rest.
+pub fn smoothstep(t: Float) -> Float {
+    let t = t.clamp(0.0, 1.0);
+
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// A sequence of waypoints for the camera to fly along, such as for presenting a map.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CameraPath {
+    pub waypoints: Vec<CameraWaypoint>,
+    /// Whether the camera flies back to the first waypoint after the last one, and starts over.
+    pub looping: bool,
+}
+
+impl CameraPath {
+    /// Reads the path stored in the map data.
+    pub fn from_data(data: &DataMap, data_ids: &DataIds) -> Self {
+        let coords = match data.get(data_ids.camera_path) {
+            Some(Data::VecCoord(v)) => v.as_slice(),
+            _ => &[],
+        };
+        let timings = match data.get(data_ids.camera_path_timings) {
+            Some(Data::VecFloat(v)) => v.as_slice(),
+            _ => &[],
+        };
+
+        let waypoints = coords
+            .iter()
+            .enumerate()
+            .map(|(index, coord)| {
+                let timing = timings.get(index * 3..index * 3 + 3);
+
+                CameraWaypoint {
+                    coord: *coord,
+                    zoom: timing.map_or(2.0, |v| v[0]),
+                    dwell: timing.map_or(CameraWaypoint::DEFAULT_DWELL, |v| v[1]),
+                    transition: timing.map_or(CameraWaypoint::DEFAULT_TRANSITION, |v| v[2]),
+                }
+            })
+            .collect();
+
+        Self {
+            waypoints,
+            looping: data.get(data_ids.camera_path_looping) == Some(&Data::Bool(true)),
+        }
+    }
+
+    /// Writes the path into the map data, where the coordinates and the zoom, dwell, and transition of each waypoint are kept as lists.
+    pub fn write_data(&self, data: &mut DataMap, data_ids: &DataIds) {
+        if self.waypoints.is_empty() {
+            data.remove(data_ids.camera_path);
+            data.remove(data_ids.camera_path_timings);
+        } else {
+            data.set(
+                data_ids.camera_path,
+                Data::VecCoord(self.waypoints.iter().map(|v| v.coord).collect()),
+            );
+            data.set(
+                data_ids.camera_path_timings,
+                Data::VecFloat(
+                    self.waypoints
+                        .iter()
+                        .flat_map(|v| [v.zoom, v.dwell, v.transition])
+                        .collect(),
+                ),
+            );
+        }
+
+        if self.looping {
+            data.set(data_ids.camera_path_looping, Data::Bool(true));
+        } else {
+            data.remove(data_ids.camera_path_looping);
+        }
+    }
+
+    fn waypoint_pos(&self, index: isize) -> Vec3 {
+        let len = self.waypoints.len() as isize;
+
+        let index = if self.looping {
+            index.rem_euclid(len)
+        } else {
+            index.clamp(0, len - 1)
+        };
+
+        self.waypoints[index as usize].pos()
+    }
+
+    /// Returns the camera position at the time, in seconds since the playback started, or None once the playback is over.
+    ///
+    /// The camera comes to a stop at every waypoint, so that there is no sudden change of speed going into or out of a dwell.
+    pub fn sample(&self, time: Float) -> Option<Vec3> {
+        let len = self.waypoints.len();
+        if len == 0 {
+            return None;
+        }
+
+        let segments = if self.looping { len } else { len - 1 };
+        let total = self.waypoints[..segments]
+            .iter()
+            .map(|v| v.dwell.max(0.0) + v.transition.max(0.0))
+            .sum::<Float>()
+            + if self.looping {
+                0.0
+            } else {
+                self.waypoints[len - 1].dwell.max(0.0)
+            };
+
+        let mut time = if self.looping && total > 0.0 {
+            time.rem_euclid(total)
+        } else if time > total {
+            return None;
+        } else {
+            time
+        };
+
+        for (index, waypoint) in self.waypoints[..segments].iter().enumerate() {
+            let (dwell, transition) = (waypoint.dwell.max(0.0), waypoint.transition.max(0.0));
+
+            if time < dwell {
+                return Some(waypoint.pos());
+            }
+            time -= dwell;
+
+            if time < transition {
+                let index = index as isize;
+                let t = smoothstep(time / transition);
+
+                return Some(catmull_rom(
+                    self.waypoint_pos(index - 1),
+                    self.waypoint_pos(index),
+                    self.waypoint_pos(index + 1),
+                    self.waypoint_pos(index + 2),
+                    t,
+                ));
+            }
+            time -= transition;
+        }
+
+        Some(self.waypoint_pos(segments as isize))
+    }
+}
@@ -0,0 +1,119 @@
+use automancy_resources::ResourceManager;
+use serde::Deserialize;
+use std::cmp::Ordering;
+
+/// The changelog bundled with the game.
+static CHANGELOG: &str = include_str!("assets/changelog.ron");
+
+/// A piece of text in the changelog, either written out or looked up in the GUI translations.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub enum ChangelogText {
+    Plain(String),
+    Translated(String),
+}
+
+impl ChangelogText {
+    pub fn resolve(&self, resource_man: &ResourceManager) -> String {
+        match self {
+            ChangelogText::Plain(text) => text.clone(),
+            ChangelogText::Translated(id) => match resource_man.interner.get(id) {
+                Some(id) => resource_man.gui_str(id).to_string(),
+                None => id.clone(),
+            },
+        }
+    }
+}
+
+/// A titled list of changes in a version.
+///
+/// Unknown fields are ignored, so that changelogs with new fields still parse.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ChangelogSection {
+    #[serde(default)]
+    pub title: Option<ChangelogText>,
+    #[serde(default)]
+    pub bullets: Vec<ChangelogText>,
+}
+
+/// The changes in a version of the game.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ChangelogEntry {
+    pub version: String,
+    #[serde(default)]
+    pub date: Option<String>,
+    #[serde(default)]
+    pub sections: Vec<ChangelogSection>,
+}
+
+/// Compares two dotted version numbers part by part. Missing parts count as 0, and parts that aren't numbers as 0 too.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |v: &str| {
+        v.trim_start_matches('v')
+            .split('.')
+            .map(|part| {
+                part.chars()
+                    .take_while(char::is_ascii_digit)
+                    .collect::<String>()
+                    .parse::<u32>()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let (a, b) = (parts(a), parts(b));
+    let len = a.len().max(b.len());
+
+    (0..len)
+        .map(|i| {
+            a.get(i)
+                .copied()
+                .unwrap_or(0)
+                .cmp(&b.get(i).copied().unwrap_or(0))
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Reads the bundled changelog, sorted from the newest version to the oldest.
+pub fn read_changelog() -> Vec<ChangelogEntry> {
+    let mut entries = ron::from_str::<Vec<ChangelogEntry>>(CHANGELOG)
+        .inspect_err(|err| log::error!("Error parsing the changelog! Error: {err}"))
+        .unwrap_or_default();
+
+    entries.sort_by(|a, b| compare_versions(&b.version, &a.version));
+
+    entries
+}
+
+/// Picks the entries of every version up to the current one, leaving out the unreleased ones.
+pub fn released_entries(changelog: &[ChangelogEntry], current: &str) -> Vec<ChangelogEntry> {
+    changelog
+        .iter()
+        .filter(|entry| compare_versions(&entry.version, current).is_le())
+        .cloned()
+        .collect()
+}
+
+/// Picks the entries to show in the "what's new" window, in the order of the changelog.
+///
+/// Those are the versions newer than the last seen one, up to the current one.
+/// If no version was seen before, only the newest entry up to the current version is shown, not the whole history.
+pub fn entries_since(
+    changelog: &[ChangelogEntry],
+    last_seen: Option<&str>,
+    current: &str,
+) -> Vec<ChangelogEntry> {
+    let released = released_entries(changelog, current);
+
+    match last_seen {
+        Some(last_seen) => released
+            .into_iter()
+            .filter(|entry| compare_versions(&entry.version, last_seen).is_gt())
+            .collect(),
+        None => released
+            .into_iter()
+            .max_by(|a, b| compare_versions(&a.version, &b.version))
+            .into_iter()
+            .collect(),
+    }
+}
@@ -0,0 +1,145 @@
+use crate::options::OPTIONS_PATH;
+use log::{Log, Metadata, Record};
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, fmt};
+
+pub static CRASHES_PATH: &str = "crashes";
+/// Left behind by a crash, pointing at its report bundle, so the next launch can tell the player about it.
+static CRASH_MARKER_PATH: &str = "crashes/last_crash";
+
+/// How many of the latest log entries are kept around for crash reports.
+pub const LOG_HISTORY_SIZE: usize = 500;
+
+static LOG_HISTORY: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// What a crash report needs to know about the running game.
+///
+/// This is kept up to date as the game runs, so that the panic hook never has to ask the GPU or the actors for it.
+#[derive(Debug)]
+pub struct CrashContext {
+    /// The graphics adapter info, formatted.
+    pub adapter_info: Option<String>,
+    /// The namespaces of the loaded resource packs.
+    pub packs: Vec<String>,
+    /// The name of the loaded map, and its info formatted.
+    pub map: Option<(String, String)>,
+}
+
+pub static CRASH_CONTEXT: Mutex<CrashContext> = Mutex::new(CrashContext {
+    adapter_info: None,
+    packs: Vec::new(),
+    map: None,
+});
+
+/// A logger that keeps the latest [LOG_HISTORY_SIZE] entries in memory, on top of passing them on to the inner logger.
+pub struct HistoryLogger<L: Log> {
+    pub inner: L,
+}
+
+impl<L: Log> Log for HistoryLogger<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.inner.enabled(record.metadata()) {
+            return;
+        }
+
+        // never block on the history, the panic hook might be holding it
+        if let Ok(mut history) = LOG_HISTORY.try_lock() {
+            if history.len() >= LOG_HISTORY_SIZE {
+                history.pop_front();
+            }
+
+            history.push_back(format!(
+                "[{} {}] {}",
+                record.level(),
+                record.target(),
+                record.args()
+            ));
+        }
+
+        self.inner.log(record);
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Replaces the user's home directory in the text, so that reports do not leak it.
+fn strip_home(text: &str) -> String {
+    match env::var("HOME").or_else(|_| env::var("USERPROFILE")) {
+        Ok(home) if !home.is_empty() => text.replace(&home, "~"),
+        _ => text.to_string(),
+    }
+}
+
+fn write_file(dir: &Path, name: &str, content: impl fmt::Display) {
+    if let Ok(mut file) = File::create(dir.join(name)) {
+        let _ = write!(file, "{}", strip_home(&content.to_string()));
+    }
+}
+
+/// Writes a crash report bundle to `crashes/<timestamp>/`, and returns the path of the bundle if it could be created.
+///
+/// Every step is allowed to fail on its own, in which case its part of the bundle is skipped.
+/// This must not panic, as it is called from the panic hook.
+pub fn write_crash_bundle(report: &str) -> Option<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|v| v.as_secs())
+        .unwrap_or_default();
+
+    let dir = Path::new(CRASHES_PATH).join(timestamp.to_string());
+    fs::create_dir_all(&dir).ok()?;
+
+    write_file(&dir, "report.txt", report);
+
+    if let Ok(history) = LOG_HISTORY.try_lock() {
+        let log = history.iter().fold(String::new(), |mut acc, entry| {
+            acc.push_str(entry);
+            acc.push('\n');
+            acc
+        });
+
+        write_file(&dir, "log.txt", log);
+    }
+
+    if let Ok(context) = CRASH_CONTEXT.try_lock() {
+        if let Some(adapter_info) = &context.adapter_info {
+            write_file(&dir, "adapter_info.ron", adapter_info);
+        }
+
+        write_file(&dir, "packs.txt", context.packs.join("\n"));
+
+        if let Some((name, info)) = &context.map {
+            write_file(&dir, "map.txt", format!("{name}\n\n{info}"));
+        }
+    }
+
+    if let Ok(options) = fs::read_to_string(OPTIONS_PATH) {
+        write_file(&dir, "options.ron", options);
+    }
+
+    if let Ok(mut marker) = File::create(CRASH_MARKER_PATH) {
+        let _ = write!(marker, "{}", dir.display());
+    }
+
+    Some(dir)
+}
+
+/// Returns the path of the crash bundle left by the last session if it crashed, and clears it so it is only reported once.
+pub fn take_last_crash() -> Option<String> {
+    let path = fs::read_to_string(CRASH_MARKER_PATH).ok()?;
+
+    let _ = fs::remove_file(CRASH_MARKER_PATH);
+
+    Some(path)
+}
@@ -0,0 +1,89 @@
+use automancy_defs::coord::TileCoord;
+use automancy_defs::math::{Float, Vec3, FAR, HEX_GRID_LAYOUT};
+use serde::{Deserialize, Serialize};
+
+/// How much cosmetic detail a tile is drawn with. Ordered from the least to the most.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DetailLevel {
+    Low,
+    Medium,
+    High,
+}
+
+impl DetailLevel {
+    pub const ALL: [DetailLevel; 3] = [DetailLevel::Low, DetailLevel::Medium, DetailLevel::High];
+
+    /// Every how many frames the moving parts of a tile at this level are updated.
+    pub const fn update_interval(self) -> u64 {
+        match self {
+            DetailLevel::Low => 8,
+            DetailLevel::Medium => 2,
+            DetailLevel::High => 1,
+        }
+    }
+
+    /// Whether the moving parts of a tile at this level are updated on the frame.
+    pub const fn updates_on(self, frame: u64) -> bool {
+        frame % self.update_interval() == 0
+    }
+
+    /// Whether one-off effects, such as the placement animation, are shown for a tile at this level.
+    pub fn shows_effects(self) -> bool {
+        self >= DetailLevel::Medium
+    }
+}
+
+/// The distances from the camera, in world units, up to which tiles get each level of detail. Anything further is [DetailLevel::Low].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DetailThresholds {
+    pub high: Float,
+    pub medium: Float,
+}
+
+/// The detail presets in the graphics options, each mapping to a set of thresholds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DetailPreset {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl DetailPreset {
+    pub const fn thresholds(self) -> DetailThresholds {
+        match self {
+            DetailPreset::Low => DetailThresholds {
+                high: 6.0,
+                medium: 12.0,
+            },
+            DetailPreset::Medium => DetailThresholds {
+                high: 12.0,
+                medium: 20.0,
+            },
+            DetailPreset::High => DetailThresholds {
+                high: 20.0,
+                medium: 32.0,
+            },
+        }
+    }
+}
+
+/// Gets the level of detail the tile at the coordinate is drawn with, as seen from the camera position.
+///
+/// This is what every cosmetic system should consult, instead of deciding on its own.
+/// Only the distance to the camera counts, so a tile never gets more detail than a nearer one.
+pub fn detail_level(
+    camera_pos: Vec3,
+    coord: TileCoord,
+    thresholds: DetailThresholds,
+) -> DetailLevel {
+    let distance = camera_pos.distance(HEX_GRID_LAYOUT.hex_to_world_pos(*coord).extend(FAR));
+
+    if distance <= thresholds.high {
+        DetailLevel::High
+    } else if distance <= thresholds.medium.max(thresholds.high) {
+        DetailLevel::Medium
+    } else {
+        DetailLevel::Low
+    }
+}
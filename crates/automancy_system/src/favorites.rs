@@ -0,0 +1,36 @@
+use automancy_defs::id::TileId;
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::ResourceManager;
+
+/// Reads the favorite tiles stored in the map data, in the order they were added. Tiles that no longer exist are left out.
+pub fn read_favorites(resource_man: &ResourceManager, game_data: &DataMap) -> Vec<TileId> {
+    let Some(Data::VecId(ids)) = game_data.get(resource_man.registry.data_ids.favorite_tiles)
+    else {
+        return vec![];
+    };
+
+    ids.iter()
+        .map(|id| TileId(*id))
+        .filter(|id| resource_man.registry.tiles.contains_key(id))
+        .collect()
+}
+
+/// Adds the tile to the favorites, or removes it if it is already one.
+pub fn toggle_favorite(resource_man: &ResourceManager, game_data: &mut DataMap, id: TileId) {
+    let mut favorites = read_favorites(resource_man, game_data);
+
+    if let Some(index) = favorites.iter().position(|v| *v == id) {
+        favorites.remove(index);
+    } else {
+        favorites.push(id);
+    }
+
+    if favorites.is_empty() {
+        game_data.remove(resource_man.registry.data_ids.favorite_tiles);
+    } else {
+        game_data.set(
+            resource_man.registry.data_ids.favorite_tiles,
+            Data::VecId(favorites.into_iter().map(|id| *id).collect()),
+        );
+    }
+}
@@ -0,0 +1,286 @@
+use crate::game::GameSystemMessage;
+use crate::tile_entity::TileEntityMsg;
+use crate::util::{actor::multi_call_iter_tolerant, layout::layered};
+use automancy_defs::{
+    coord::TileCoord,
+    id::{Id, TileId},
+    math::{Float, Vec2},
+    stack::ItemAmount,
+};
+use hashbrown::HashMap;
+use ractor::{rpc::CallResult, ActorRef};
+use std::time::{Duration, Instant};
+
+/// How far back the item flows are kept.
+pub const FLOW_WINDOW: Duration = Duration::from_secs(60);
+
+/// The number of buckets the window is kept in. The oldest one is dropped as a whole, so the window is this much coarse.
+const FLOW_BUCKETS: usize = 6;
+
+#[derive(Debug, Clone, Default)]
+struct FlowBucket {
+    /// how many items came from each machine, by the coordinate of the machine that sent them out
+    inflow: HashMap<TileCoord, ItemAmount>,
+    ticks: u64,
+    /// the ticks the script did something in
+    active: u64,
+}
+
+/// Keeps what flowed into a tile entity and how busy it was over the last [`FLOW_WINDOW`].
+#[derive(Debug, Clone)]
+pub struct FlowRecorder {
+    start: Instant,
+    /// the index of the latest bucket, counted from the start
+    current: usize,
+    buckets: [FlowBucket; FLOW_BUCKETS],
+}
+
+impl Default for FlowRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FlowRecorder {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            current: 0,
+            buckets: Default::default(),
+        }
+    }
+
+    /// Gets the bucket of now, clearing the ones that were skipped since the last time.
+    fn bucket(&mut self) -> &mut FlowBucket {
+        let bucket_length = FLOW_WINDOW / FLOW_BUCKETS as u32;
+        let index = (self.start.elapsed().as_nanos() / bucket_length.as_nanos()) as usize;
+
+        for skipped in (self.current + 1)..=index.min(self.current + FLOW_BUCKETS) {
+            self.buckets[skipped % FLOW_BUCKETS] = FlowBucket::default();
+        }
+        self.current = self.current.max(index);
+
+        &mut self.buckets[self.current % FLOW_BUCKETS]
+    }
+
+    /// Counts items taken in, from the machine at the coordinate.
+    pub fn record_inflow(&mut self, from: TileCoord, amount: ItemAmount) {
+        *self.bucket().inflow.entry(from).or_insert(0) += amount;
+    }
+
+    /// Counts a logic tick, and whether the script did something in it.
+    pub fn record_tick(&mut self, active: bool) {
+        let bucket = self.bucket();
+
+        bucket.ticks += 1;
+        if active {
+            bucket.active += 1;
+        }
+    }
+
+    /// Sums up how many items were taken in over the window, from any machine.
+    pub fn total_inflow(&mut self) -> ItemAmount {
+        self.bucket();
+
+        self.buckets
+            .iter()
+            .flat_map(|bucket| bucket.inflow.values())
+            .sum()
+    }
+
+    /// Sums up the window.
+    pub fn sample(&mut self, script: Option<Id>) -> FlowSample {
+        self.bucket();
+
+        let mut inflow = HashMap::<TileCoord, ItemAmount>::new();
+        let mut ticks = 0;
+        let mut active = 0;
+
+        for bucket in &self.buckets {
+            for (from, amount) in &bucket.inflow {
+                *inflow.entry(*from).or_insert(0) += amount;
+            }
+
+            ticks += bucket.ticks;
+            active += bucket.active;
+        }
+
+        FlowSample {
+            script,
+            inflow: inflow.into_iter().collect(),
+            ticks,
+            active,
+        }
+    }
+}
+
+/// What flowed into a tile entity over the last [`FLOW_WINDOW`].
+#[derive(Debug, Clone, Default)]
+pub struct FlowSample {
+    /// the script the tile is configured with
+    pub script: Option<Id>,
+    /// how many items came from each machine
+    pub inflow: Vec<(TileCoord, ItemAmount)>,
+    pub ticks: u64,
+    pub active: u64,
+}
+
+/// The machines of the same tile and script, put together.
+#[derive(Debug, Clone)]
+pub struct FlowNode {
+    pub tile: TileId,
+    pub script: Option<Id>,
+    pub coords: Vec<TileCoord>,
+    pub ticks: u64,
+    pub active: u64,
+}
+
+impl FlowNode {
+    /// The fraction of the ticks the machines did something in, or None if they don't tick.
+    pub fn utilization(&self) -> Option<Float> {
+        (self.ticks > 0).then(|| self.active as Float / self.ticks as Float)
+    }
+}
+
+/// The items that went from the machines of one node to the ones of another, over the last [`FLOW_WINDOW`].
+#[derive(Debug, Clone, Copy)]
+pub struct FlowEdge {
+    pub from: usize,
+    pub to: usize,
+    pub amount: ItemAmount,
+}
+
+/// The logical graph of how items flow between the machines of the map, laid out for drawing.
+#[derive(Debug, Clone, Default)]
+pub struct FlowGraph {
+    pub nodes: Vec<FlowNode>,
+    pub edges: Vec<FlowEdge>,
+    /// the position of each node, see [`layered`]
+    pub positions: Vec<Vec2>,
+    /// when the samples were taken
+    pub taken: Option<Instant>,
+}
+
+impl FlowGraph {
+    /// Builds the graph from the samples of the tile entities, grouping them by their tile and script.
+    ///
+    /// Only the machines that tick or have items flowing into or out of them are kept. Flows within a node are left out.
+    pub fn build(
+        tiles: &HashMap<TileCoord, TileId>,
+        samples: &HashMap<TileCoord, FlowSample>,
+    ) -> Self {
+        let mut nodes = Vec::<FlowNode>::new();
+        let mut groups = HashMap::<(TileId, Option<Id>), usize>::new();
+
+        let mut group_of = |coord: TileCoord, nodes: &mut Vec<FlowNode>| -> Option<usize> {
+            let tile = *tiles.get(&coord)?;
+            let script = samples.get(&coord)?.script;
+
+            Some(*groups.entry((tile, script)).or_insert_with(|| {
+                nodes.push(FlowNode {
+                    tile,
+                    script,
+                    coords: vec![],
+                    ticks: 0,
+                    active: 0,
+                });
+
+                nodes.len() - 1
+            }))
+        };
+
+        let mut amounts = HashMap::<(usize, usize), ItemAmount>::new();
+        let mut members = HashMap::<TileCoord, usize>::new();
+
+        for (coord, sample) in samples {
+            let mut involved = sample.ticks > 0;
+
+            for (from, amount) in &sample.inflow {
+                let (Some(from_node), Some(to_node)) =
+                    (group_of(*from, &mut nodes), group_of(*coord, &mut nodes))
+                else {
+                    continue;
+                };
+
+                members.insert(*from, from_node);
+                involved = true;
+
+                if from_node != to_node {
+                    *amounts.entry((from_node, to_node)).or_insert(0) += amount;
+                }
+            }
+
+            if involved {
+                if let Some(node) = group_of(*coord, &mut nodes) {
+                    members.insert(*coord, node);
+                }
+            }
+        }
+
+        for (coord, node) in members {
+            let node = &mut nodes[node];
+            node.coords.push(coord);
+
+            if let Some(sample) = samples.get(&coord) {
+                node.ticks += sample.ticks;
+                node.active += sample.active;
+            }
+        }
+
+        for node in &mut nodes {
+            node.coords.sort_by_key(|coord| (coord.x, coord.y));
+        }
+
+        let mut edges = amounts
+            .into_iter()
+            .map(|((from, to), amount)| FlowEdge { from, to, amount })
+            .collect::<Vec<_>>();
+        edges.sort_by_key(|edge| (edge.from, edge.to));
+
+        let positions = layered(
+            nodes.len(),
+            &edges
+                .iter()
+                .map(|edge| (edge.from, edge.to))
+                .collect::<Vec<_>>(),
+        );
+
+        Self {
+            nodes,
+            edges,
+            positions,
+            taken: Some(Instant::now()),
+        }
+    }
+
+    /// The largest amount of an edge, to scale the others by.
+    pub fn max_amount(&self) -> ItemAmount {
+        self.edges.iter().map(|edge| edge.amount).max().unwrap_or(0)
+    }
+}
+
+/// Samples every tile entity of the map and builds the flow graph from them.
+///
+/// This calls every tile entity, so it is meant to be run as a task of its own rather than waited on.
+pub async fn collect_flow_graph(game: ActorRef<GameSystemMessage>) -> Option<FlowGraph> {
+    let Ok(CallResult::Success((tiles, tile_entities))) =
+        game.call(GameSystemMessage::GetAllTiles, None).await
+    else {
+        return None;
+    };
+
+    match multi_call_iter_tolerant(
+        &tile_entities,
+        |reply, _| TileEntityMsg::GetFlowSample(reply),
+        None,
+    )
+    .await
+    {
+        Ok(samples) => Some(FlowGraph::build(&tiles, &samples)),
+        Err(err) => {
+            log::error!("Could not collect the item flows! Error: {err:?}");
+
+            None
+        }
+    }
+}
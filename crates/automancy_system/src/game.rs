@@ -1,29 +1,53 @@
+use crate::actor_stats::ACTOR_STATS;
 use crate::map::{GameMap, MapInfo, TileEntities};
-use crate::tile_entity::{TileEntity, TileEntityMsg};
+use crate::metrics::{self, MetricsSnapshot};
+use crate::options::SaveOptions;
+use crate::sandbox::{is_sandbox, Sandbox};
+use crate::terrain::{seed_from_name, Terrain};
+use crate::tile_entity::{TileEntity, TileEntityMsg, TileStatus};
+use crate::ui_state::{queue_toast, ToastLevel};
+use crate::util::{placement_requirements, pop_research_queue, tile_direction};
 use crate::{game::GameSystemMessage::*, map::LoadMapOption};
-use crate::{tile_entity::TileEntityError, util::actor::multi_call_iter};
+use crate::{
+    tile_entity::TileEntityError,
+    util::actor::{multi_call_iter, multi_call_iter_tolerant},
+};
 use arraydeque::{ArrayDeque, Wrapping};
 use automancy_defs::id::{Id, ModelId, RenderTagId};
+use automancy_defs::math::Float;
+use automancy_defs::stack::ItemAmount;
 use automancy_defs::{
     coord::{TileBounds, TileCoord},
     id::TileId,
 };
+use automancy_resources::format::Formattable;
+use automancy_resources::inventory::InventoryLimits;
+use automancy_resources::registry::DataIds;
 use automancy_resources::types::function::OnFailAction;
-use automancy_resources::ResourceManager;
+use automancy_resources::types::tile::VARIANT_NEIGHBORS;
 use automancy_resources::{
     data::{Data, DataMap},
     rhai_render::RenderCommand,
 };
-use hashbrown::HashMap;
+use automancy_resources::{item_match, ResourceManager};
+use hashbrown::{HashMap, HashSet};
 use ractor::rpc::CallResult;
-use ractor::{Actor, ActorProcessingErr, ActorRef, RpcReplyPort, SupervisionEvent};
+use ractor::{Actor, ActorId, ActorProcessingErr, ActorRef, RpcReplyPort, SupervisionEvent};
+use std::collections::BTreeMap;
 use std::time::{Duration, Instant};
 use std::{mem, sync::Arc};
 use tokio::sync::Mutex;
 
 /// Game ticks per second
 pub const TPS: u64 = 60;
+/// The highest rate either tick stream can run at, per second.
+pub const MAX_TICK_RATE: u64 = TPS * 4;
 pub const TICK_INTERVAL: Duration = Duration::from_nanos(1_000_000_000 / TPS);
+
+/// The speed the game runs at normally, in percent.
+pub const NORMAL_TICK_SPEED: u64 = 100;
+/// The speeds the game can be set to run at, in percent, the slowest first.
+pub const TICK_SPEEDS: [u64; 4] = [50, NORMAL_TICK_SPEED, 200, 500];
 pub const MAX_ALLOWED_TICK_INTERVAL: Duration = TICK_INTERVAL.saturating_mul(5);
 
 pub const TRANSACTION_ANIMATION_SPEED: Duration = Duration::from_nanos(800_000_000);
@@ -32,54 +56,301 @@ pub const TAKE_ITEM_ANIMATION_SPEED: Duration = Duration::from_nanos(300_000_000
 
 const UNDO_CACHE_SIZE: usize = 256;
 
-pub type TickUnit = u16;
+/// How often the game checks whether the map is due to be autosaved.
+pub const AUTOSAVE_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+/// How many minutes pass between autosaves, for maps that don't set it.
+pub const DEFAULT_AUTOSAVE_MINUTES: u64 = 5;
+
+/// How long a removed tile entity gets to answer and stop before it is killed.
+const TILE_STOP_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub type TickUnit = u64;
 
 pub type FlatTiles = Vec<(TileCoord, TileId, Option<DataMap>)>;
 
+/// What the renderer gets from the game every frame.
+#[derive(Debug, Default)]
+pub struct RenderFrame {
+    /// the render commands, those of the tiles cleaned up since the last frame first
+    pub commands: [HashMap<TileCoord, Vec<RenderCommand>>; 2],
+    /// the statuses of the tiles in view, if they were asked for
+    pub statuses: HashMap<TileCoord, TileStatus>,
+}
+
+/// How many logic and transfer ticks happen per second in a map, and how fast the map runs.
+///
+/// Logic ticks run the `handle_tick` function of the tiles' scripts, and transfer ticks their `handle_transfer_tick`,
+/// so that how fast items move can be set apart from how fast machines work.
+///
+/// The speed scales how many base ticks run per [TICK_INTERVAL], so everything counted in ticks speeds up together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickRates {
+    pub logic: u64,
+    pub transfer: u64,
+    /// in percent, one of [TICK_SPEEDS]
+    pub speed: u64,
+}
+
+impl Default for TickRates {
+    fn default() -> Self {
+        Self {
+            logic: TPS,
+            transfer: TPS,
+            speed: NORMAL_TICK_SPEED,
+        }
+    }
+}
+
+/// Gets the speed closest to the given one, out of [TICK_SPEEDS].
+fn closest_speed(speed: u64) -> u64 {
+    TICK_SPEEDS
+        .into_iter()
+        .min_by_key(|v| v.abs_diff(speed))
+        .unwrap_or(NORMAL_TICK_SPEED)
+}
+
+impl TickRates {
+    /// Reads the rates from the map data, using the defaults for the ones it doesn't have.
+    pub fn from_data(data: &DataMap, data_ids: &DataIds) -> Self {
+        let read = |key, default| match data.get(key) {
+            Some(Data::Amount(rate)) => (*rate as u64).clamp(1, MAX_TICK_RATE),
+            _ => default,
+        };
+
+        let speed = match data.get(data_ids.tick_speed) {
+            Some(Data::Amount(speed)) => closest_speed((*speed).max(0) as u64),
+            _ => NORMAL_TICK_SPEED,
+        };
+
+        Self {
+            logic: read(data_ids.logic_tick_rate, TPS),
+            transfer: read(data_ids.transfer_tick_rate, TPS),
+            speed,
+        }
+    }
+
+    pub fn write_data(&self, data: &mut DataMap, data_ids: &DataIds) {
+        data.set(
+            data_ids.logic_tick_rate,
+            Data::Amount(self.logic as ItemAmount),
+        );
+        data.set(
+            data_ids.transfer_tick_rate,
+            Data::Amount(self.transfer as ItemAmount),
+        );
+        data.set(data_ids.tick_speed, Data::Amount(self.speed as ItemAmount));
+    }
+
+    /// How many times faster than normal the map runs.
+    pub fn speed_factor(&self) -> Float {
+        self.speed as Float / NORMAL_TICK_SPEED as Float
+    }
+
+    /// Gets the rates with the next speed of [TICK_SPEEDS], faster or slower, staying at the fastest or the slowest.
+    pub fn next_speed(self, faster: bool) -> Self {
+        let index = TICK_SPEEDS
+            .iter()
+            .position(|v| *v == self.speed)
+            .unwrap_or(1);
+
+        let index = if faster {
+            (index + 1).min(TICK_SPEEDS.len() - 1)
+        } else {
+            index.saturating_sub(1)
+        };
+
+        Self {
+            speed: TICK_SPEEDS[index],
+            ..self
+        }
+    }
+}
+
+/// Reads how long to wait between autosaves from the map data, in minutes. None if it is set to 0 or less, which turns autosaving off.
+pub fn autosave_interval(data: &DataMap, data_ids: &DataIds) -> Option<Duration> {
+    let minutes = match data.get(data_ids.autosave_interval) {
+        Some(Data::Amount(minutes)) if *minutes <= 0 => return None,
+        Some(Data::Amount(minutes)) => *minutes as u64,
+        _ => DEFAULT_AUTOSAVE_MINUTES,
+    };
+
+    Some(Duration::from_secs(minutes * 60))
+}
+
+/// The tick rates of the map, along with how many ticks of each stream actually happened per second lately.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TickStats {
+    pub rates: TickRates,
+    pub logic_frequency: Float,
+    pub transfer_frequency: Float,
+    /// whether the tiles are paused, see [GameSystemMessage::SetTickPaused]
+    pub paused: bool,
+    /// the ticks left to step before pausing again
+    pub steps: u32,
+}
+
+/// How much each script is used in the current map.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptUsage {
+    /// the number of tiles currently configured with each script
+    pub configured: HashMap<Id, u64>,
+    /// the number of crafts completed with each script this session
+    pub crafted: HashMap<Id, u64>,
+}
+
+/// The steps that undo and redo the user events, as the messages that do them.
+#[derive(Debug, Default)]
+struct History {
+    /// what to do to undo the last UNDO_CACHE_SIZE user events
+    undo: ArrayDeque<Vec<GameSystemMessage>, UNDO_CACHE_SIZE, Wrapping>,
+    /// what to do to redo the last undone steps, which a new user event clears
+    redo: ArrayDeque<Vec<GameSystemMessage>, UNDO_CACHE_SIZE, Wrapping>,
+    /// what reverses the step that is being undone or redone, gathered as its messages run
+    replayed: Option<Vec<GameSystemMessage>>,
+}
+
+impl History {
+    /// Records the message that undoes a user event. While a step is undone or redone, it goes into the step that reverses that instead.
+    fn record(&mut self, msg: GameSystemMessage) {
+        if let Some(replayed) = &mut self.replayed {
+            replayed.push(msg);
+        } else {
+            self.undo.push_back(vec![msg]);
+            self.redo.clear();
+        }
+    }
+
+    fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.replayed = None;
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct GameSystemState {
-    /// a count of all the ticks that have happened
+    /// a count of all the ticks that have happened in the map, carried across sessions
     tick_count: TickUnit,
+    /// the rates of the logic and transfer ticks, from the map data
+    tick_rates: TickRates,
+    /// the sandbox settings, from the map data
+    sandbox: Sandbox,
+    /// the part of a logic and of a transfer tick that has built up, in 1/TPS of a tick
+    tick_remainders: (u64, u64),
+    /// the count of the next logic and of the next transfer tick, so that every tick a script gets has a count of its own
+    stream_ticks: (TickUnit, TickUnit),
+    /// the part of a base tick that has built up at the current speed, in percent of a tick
+    speed_remainder: u64,
+    /// when the current frequency measurement started, and the logic and transfer ticks counted since
+    tick_measurement: Option<(Instant, u64, u64)>,
+    /// the last measured frequencies of the logic and transfer ticks
+    tick_frequencies: (Float, Float),
     /// is the game stopped
     stopped: bool,
+    /// whether the tiles are paused, while the rest of the game goes on
+    paused: bool,
+    /// the ticks left to run while paused, one per base tick
+    steps: u32,
 
     /// the tile entities
     tile_entities: TileEntities,
     /// the map
     map: Option<GameMap>,
+    /// how the map is saved
+    save_options: SaveOptions,
 
-    /// what to do to undo the last UNDO_CACHE_SIZE user events
-    undo_steps: ArrayDeque<Vec<GameSystemMessage>, UNDO_CACHE_SIZE, Wrapping>,
+    /// what undoes and redoes the user events
+    history: History,
 
     cleanup_render_commands: HashMap<TileCoord, Vec<RenderCommand>>,
     last_culling_range: TileBounds,
+
+    /// the number of crafts completed with each script, not saved with the map
+    crafted: HashMap<Id, u64>,
+    /// the script each tile entity is configured with, as they last told the game
+    scripts: HashMap<ActorId, Id>,
+    /// the number of tile entities configured with each script, kept along with [GameSystemState::scripts]
+    configured: HashMap<Id, u64>,
+
+    /// the base ticks run this session
+    ticks_processed: u64,
+    /// how long the last save took
+    last_save_duration: Option<Duration>,
+    /// when the metrics were last published
+    metrics_published: Option<Instant>,
+
+    /// the tiles removed by the parts of the trim in progress, undone all together once it finishes
+    trimmed: FlatTiles,
+
+    /// when the map was last saved or autosaved, or loaded
+    last_save: Option<Instant>,
+}
+
+impl GameSystemState {
+    /// Sets the script the tile entity is configured with, counting it in place of the one it had.
+    fn set_configured_script(&mut self, actor: ActorId, script: Option<Id>) {
+        let old = match script {
+            Some(script) => self.scripts.insert(actor, script),
+            None => self.scripts.remove(&actor),
+        };
+
+        if let Some(old) = old {
+            if let Some(count) = self.configured.get_mut(&old) {
+                *count -= 1;
+
+                if *count == 0 {
+                    self.configured.remove(&old);
+                }
+            }
+        }
+
+        if let Some(script) = script {
+            *self.configured.entry(script).or_insert(0) += 1;
+        }
+    }
 }
 
 pub static COULD_NOT_LOAD_ANYTHING: &str = "??? main menu is corrupted and couldn't be emptied!";
 
-fn track_none(resource_man: &ResourceManager, coord: TileCoord) -> [RenderCommand; 2] {
+/// Gets the model an empty coordinate is drawn with, which is the one of its terrain.
+fn ground_model(resource_man: &ResourceManager, terrain: &Terrain, coord: TileCoord) -> ModelId {
+    resource_man.terrain_model(terrain.get(resource_man, coord))
+}
+
+fn track_none(
+    resource_man: &ResourceManager,
+    terrain: &Terrain,
+    coord: TileCoord,
+) -> [RenderCommand; 2] {
+    let model = ground_model(resource_man, terrain, coord);
+
     [
         RenderCommand::Track {
             tag: RenderTagId(resource_man.registry.data_ids.none_tile_render_tag),
-            model: ModelId(resource_man.registry.model_ids.tile_none),
+            model,
         },
         RenderCommand::Transform {
             tag: RenderTagId(resource_man.registry.data_ids.none_tile_render_tag),
-            model: ModelId(resource_man.registry.model_ids.tile_none),
+            model,
             model_matrix: coord.as_translation(),
         },
     ]
 }
 
-fn untrack_none(resource_man: &ResourceManager) -> [RenderCommand; 1] {
+fn untrack_none(
+    resource_man: &ResourceManager,
+    terrain: &Terrain,
+    coord: TileCoord,
+) -> [RenderCommand; 1] {
     [RenderCommand::Untrack {
         tag: RenderTagId(resource_man.registry.data_ids.none_tile_render_tag),
-        model: ModelId(resource_man.registry.model_ids.tile_none),
+        model: ground_model(resource_man, terrain, coord),
     }]
 }
 
 fn fill_map_with_none(
     resource_man: &ResourceManager,
+    terrain: &Terrain,
     culling_range: TileBounds,
     last_culling_range: TileBounds,
     commands: &mut HashMap<TileCoord, Vec<RenderCommand>>,
@@ -90,22 +361,69 @@ fn fill_map_with_none(
 
     for coord in culling_range.into_iter() {
         if !commands.contains_key(&coord) && !last_culling_range.contains(coord) {
-            commands.insert(coord, track_none(resource_man, coord).to_vec());
+            commands.insert(coord, track_none(resource_man, terrain, coord).to_vec());
         }
     }
 
     for coord in last_culling_range.into_iter() {
         if !commands.contains_key(&coord) && !culling_range.contains(coord) {
-            commands.insert(coord, untrack_none(resource_man).to_vec());
+            commands.insert(coord, untrack_none(resource_man, terrain, coord).to_vec());
         }
     }
 }
 
-#[derive(Debug, Copy, Clone)]
-pub enum PlaceTileResponse {
+/// What came of trying to place a tile.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PlacementResult {
     Placed,
     Removed,
+    /// there was nothing to do, as the tile is there already
     Ignored,
+    Failed(PlacementFailure),
+}
+
+/// Why a tile couldn't be placed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PlacementFailure {
+    /// there is a tile at the position already, and it wasn't to be placed over
+    Occupied,
+    /// the tile at the position is protected, and the placement wasn't forced
+    Protected,
+    /// the terrain at the position doesn't allow the tile
+    InvalidTerrain,
+    /// the research that unlocks the tile isn't done yet
+    Locked,
+    /// the player has none of the item the tile's category needs
+    MissingItem(Id),
+}
+
+/// The tiles of a bulk placement that couldn't be placed, and why.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlacementFailures(pub Vec<(TileCoord, PlacementFailure)>);
+
+impl PlacementFailures {
+    pub fn push(&mut self, coord: TileCoord, failure: PlacementFailure) {
+        self.0.push((coord, failure));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Counts the failures of each reason.
+    pub fn counts(&self) -> BTreeMap<PlacementFailure, usize> {
+        let mut counts = BTreeMap::new();
+
+        for (_, failure) in &self.0 {
+            *counts.entry(*failure).or_insert(0) += 1;
+        }
+
+        counts
+    }
 }
 
 /// Represents a message the game receives
@@ -119,7 +437,29 @@ pub enum GameSystemMessage {
     LoadMap(LoadMapOption, RpcReplyPort<bool>),
     /// save the map
     SaveMap(RpcReplyPort<()>),
+    /// save the map and copy it to its backup, replying with whether both worked
+    BackupMap(RpcReplyPort<bool>),
+    /// save the map to its autosave, if the autosave interval of the map passed since it was last saved
+    Autosave,
+    /// set how the map is saved
+    SetSaveOptions(SaveOptions),
+    /// train a compression dictionary on the map's tiles and save the map with it, replying with its size if it worked
+    TrainMapDictionary(RpcReplyPort<Option<usize>>),
+    /// get the number of ticks that have happened in the map
+    GetTick(RpcReplyPort<TickUnit>),
+    /// set the rates of the logic and transfer ticks, saving them in the map data
+    SetTickRates(TickRates),
+    /// set the sandbox settings of the map, saving them in the map data, and reply once they are there
+    SetSandbox(Sandbox, RpcReplyPort<()>),
+    /// get the tick rates and their measured frequencies
+    GetTickStats(RpcReplyPort<TickStats>),
+    /// pause or resume ticking the tiles. rendering, the UI, and saving go on while paused
+    SetTickPaused(bool),
+    /// run the given number of base ticks, one at the usual pace, and pause once they are done
+    StepTick(u32),
     GetMapInfoAndName(RpcReplyPort<Option<(Arc<Mutex<MapInfo>>, LoadMapOption)>>),
+    /// get how many references to each unloaded ID were found when loading the map, clearing them so they are only reported once
+    TakeMissingIds(RpcReplyPort<BTreeMap<String, usize>>),
 
     /// send a message to a tile entity
     ForwardMsgToTile {
@@ -135,34 +475,144 @@ pub enum GameSystemMessage {
         id: TileId,
         data: Option<DataMap>,
         record: bool,
-        reply: Option<RpcReplyPort<PlaceTileResponse>>,
+        /// replace the tile even if it is protected, and skip the research and item requirements
+        force: bool,
+        reply: Option<RpcReplyPort<PlacementResult>>,
     },
+    /// place many tiles at once, replying with the tiles that were replaced and the ones that couldn't be placed
     PlaceTiles {
         tiles: FlatTiles,
-        reply: Option<RpcReplyPort<FlatTiles>>,
+        reply: Option<RpcReplyPort<(FlatTiles, PlacementFailures)>>,
         place_over: bool,
         record: bool,
+        /// replace the tiles even if they are protected, and skip the research and item requirements
+        force: bool,
     },
     MoveTiles(Vec<TileCoord>, TileCoord, bool),
+    /// remove the tiles as one part of trimming the map, without giving anything back, replying with how many were removed.
+    /// the tiles of every part are recorded as a single undo step when the last part is done
+    TrimTiles {
+        coords: Vec<TileCoord>,
+        last: bool,
+        reply: RpcReplyPort<usize>,
+    },
 
+    /// undo the last user event that changed the tiles
     Undo,
+    /// redo the last undone step, unless a user event happened since
+    Redo,
 
     /// get the tile at the given position
     GetTile(TileCoord, RpcReplyPort<Option<TileId>>),
+    /// get the tile at the given position along with the config that placing more of it copies, which leaves out its buffers
+    PickTile(TileCoord, RpcReplyPort<Option<(TileId, DataMap)>>),
     /// get the tile entity at the given position
     GetTileEntity(TileCoord, RpcReplyPort<Option<ActorRef<TileEntityMsg>>>),
     GetTiles(Vec<TileCoord>, RpcReplyPort<FlatTiles>),
-    /// get all the tiles' render commands
+    /// get the ids of all the tiles within the given bounds
+    GetTilesInBounds(TileBounds, RpcReplyPort<HashMap<TileCoord, TileId>>),
+    /// get the latest tick the tiles in the bounds did something at when ticked, for the ones with tick handlers
+    GetTickActivity(
+        TileBounds,
+        RpcReplyPort<HashMap<TileCoord, Option<TickUnit>>>,
+    ),
+    /// get every tile and tile entity of the map, to go through them off the game actor
+    GetAllTiles(RpcReplyPort<(Tiles, TileEntities)>),
+    /// get all the tiles' render commands, and the statuses of the ones in the culling range if asked for, for the overlays
     GetAllRenderCommands {
         culling_range: TileBounds,
-        reply: RpcReplyPort<[HashMap<TileCoord, Vec<RenderCommand>>; 2]>,
+        statuses: bool,
+        reply: RpcReplyPort<RenderFrame>,
+    },
+
+    /// count the given number of completed crafts of the script
+    RecordCraft(Id, u64),
+    /// set the script the tile entity at the position is configured with, as it tells the game whenever it changes
+    SetConfiguredScript {
+        coord: TileCoord,
+        actor: ActorId,
+        script: Option<Id>,
     },
+    /// get how much each script is used
+    GetScriptUsage(RpcReplyPort<ScriptUsage>),
+    /// take the next research that can be started off the research queue, paying for its items, and reply with it
+    AdvanceResearchQueue(RpcReplyPort<Option<Id>>),
+}
+
+impl GameSystemMessage {
+    /// The name of the message's variant, for counting them by kind.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Tick => "Tick",
+            StopTicking => "StopTicking",
+            LoadMap(..) => "LoadMap",
+            SaveMap(..) => "SaveMap",
+            BackupMap(..) => "BackupMap",
+            Autosave => "Autosave",
+            SetSaveOptions(..) => "SetSaveOptions",
+            TrainMapDictionary(..) => "TrainMapDictionary",
+            GetTick(..) => "GetTick",
+            SetTickRates(..) => "SetTickRates",
+            SetSandbox(..) => "SetSandbox",
+            GetTickStats(..) => "GetTickStats",
+            SetTickPaused(..) => "SetTickPaused",
+            StepTick(..) => "StepTick",
+            GetMapInfoAndName(..) => "GetMapInfoAndName",
+            TakeMissingIds(..) => "TakeMissingIds",
+            ForwardMsgToTile { .. } => "ForwardMsgToTile",
+            PlaceTile { .. } => "PlaceTile",
+            PlaceTiles { .. } => "PlaceTiles",
+            MoveTiles(..) => "MoveTiles",
+            TrimTiles { .. } => "TrimTiles",
+            Undo => "Undo",
+            Redo => "Redo",
+            GetTile(..) => "GetTile",
+            PickTile(..) => "PickTile",
+            GetTileEntity(..) => "GetTileEntity",
+            GetTiles(..) => "GetTiles",
+            GetTilesInBounds(..) => "GetTilesInBounds",
+            GetTickActivity(..) => "GetTickActivity",
+            GetAllTiles(..) => "GetAllTiles",
+            GetAllRenderCommands { .. } => "GetAllRenderCommands",
+            RecordCraft(..) => "RecordCraft",
+            SetConfiguredScript { .. } => "SetConfiguredScript",
+            GetScriptUsage(..) => "GetScriptUsage",
+            AdvanceResearchQueue(..) => "AdvanceResearchQueue",
+        }
+    }
 }
 
 pub struct GameSystem {
     pub resource_man: Arc<ResourceManager>,
 }
 
+impl GameSystem {
+    /// Runs the messages of an undo or redo step right away, returning the step that reverses it.
+    async fn replay(
+        &self,
+        myself: ActorRef<GameSystemMessage>,
+        state: &mut GameSystemState,
+        step: Vec<GameSystemMessage>,
+    ) -> Result<Vec<GameSystemMessage>, ActorProcessingErr> {
+        state.history.replayed = Some(vec![]);
+
+        let mut result = Ok(());
+        for msg in step {
+            result = self.handle(myself.clone(), msg, state).await;
+
+            if result.is_err() {
+                break;
+            }
+        }
+
+        // taken out even if a message failed, so that the next user events are recorded as usual
+        let reversed = state.history.replayed.take().unwrap_or_default();
+        result?;
+
+        Ok(reversed)
+    }
+}
+
 #[async_trait::async_trait]
 impl Actor for GameSystem {
     type Msg = GameSystemMessage;
@@ -183,6 +633,9 @@ impl Actor for GameSystem {
         message: Self::Msg,
         state: &mut Self::State,
     ) -> Result<(), ActorProcessingErr> {
+        let _timer = ACTOR_STATS.game.time();
+        ACTOR_STATS.record_kind(message.kind());
+
         match message {
             LoadMap(opt, reply) => {
                 let last_culling_range = state.last_culling_range;
@@ -194,6 +647,7 @@ impl Actor for GameSystem {
                         reply,
                         loading: false,
                         unloading: last_culling_range.contains(coord),
+                        status: false,
                     },
                     None,
                 )
@@ -201,14 +655,26 @@ impl Actor for GameSystem {
                 .map(|commands| {
                     commands
                         .into_iter()
-                        .flat_map(|(k, v)| Some(k).zip(v))
+                        .flat_map(|(k, (v, _))| Some(k).zip(v))
                         .collect()
                 });
 
+                let terrain = match &state.map {
+                    Some(map) => map.info.lock().await.terrain.clone(),
+                    None => Default::default(),
+                };
+
                 match commands {
                     Ok(mut commands) => {
+                        if let Some(map) = &state.map {
+                            for covered in map.footprints.keys() {
+                                commands.entry(*covered).or_default();
+                            }
+                        }
+
                         fill_map_with_none(
                             &self.resource_man,
+                            &terrain,
                             TileBounds::Empty,
                             last_culling_range,
                             &mut commands,
@@ -228,7 +694,10 @@ impl Actor for GameSystem {
                 }
 
                 state.map = None;
-                state.undo_steps.clear();
+                state.history.clear();
+                state.trimmed.clear();
+                state.scripts.clear();
+                state.configured.clear();
 
                 let (map, tile_entities) =
                     match GameMap::load(myself.clone(), self.resource_man.clone(), &opt).await {
@@ -238,24 +707,189 @@ impl Actor for GameSystem {
                                 reply.send(false)?;
                                 return Ok(());
                             } else {
-                                (GameMap::new_empty(opt.clone()), HashMap::new())
+                                let mut map = GameMap::new_empty(opt.clone());
+                                let mut tile_entities = HashMap::new();
+
+                                if let LoadMapOption::FromSave(name) = &opt {
+                                    map.info.lock().await.terrain = Arc::new(Terrain::generate(
+                                        &self.resource_man,
+                                        seed_from_name(name),
+                                    ));
+
+                                    apply_start(
+                                        &self.resource_man,
+                                        myself.clone(),
+                                        &mut map,
+                                        &mut tile_entities,
+                                    )
+                                    .await;
+                                }
+
+                                (map, tile_entities)
                             }
                         }
                     };
 
+                {
+                    let info = map.info.lock().await;
+
+                    state.tick_count = info.tick_count;
+                    state.tick_rates =
+                        TickRates::from_data(&info.data, &self.resource_man.registry.data_ids);
+                    state.sandbox =
+                        Sandbox::from_data(&info.data, &self.resource_man.registry.data_ids);
+                }
+                state.tick_remainders = (0, 0);
+                state.stream_ticks = (state.tick_count, state.tick_count);
+                state.speed_remainder = 0;
+                state.tick_measurement = None;
+                state.paused = false;
+                state.steps = 0;
+                // the variants aren't saved, and every tile has to be there to pick them
+                refresh_variants(
+                    &self.resource_man,
+                    &map,
+                    &tile_entities,
+                    tile_entities.keys().copied().collect::<Vec<_>>(),
+                );
+
                 state.map = Some(map);
                 state.tile_entities = tile_entities;
+                state.last_save = Some(Instant::now());
 
                 log::info!("Successfully loaded map {opt}!");
                 reply.send(true)?;
             }
             SaveMap(reply) => {
                 if let Some(map) = &state.map {
-                    map.save(&self.resource_man.interner, &state.tile_entities)
-                        .await?;
+                    let start = Instant::now();
+
+                    map.info.lock().await.tick_count = state.tick_count;
+                    let result = map
+                        .save(
+                            &self.resource_man,
+                            &state.tile_entities,
+                            &state.save_options,
+                        )
+                        .await;
+
+                    if let LoadMapOption::FromSave(name) = &map.opt {
+                        let gui_ids = &self.resource_man.registry.gui_ids;
+                        let (level, id) = if result.is_ok() {
+                            (ToastLevel::Info, gui_ids.lbl_map_saved)
+                        } else {
+                            (ToastLevel::Error, gui_ids.lbl_save_failed)
+                        };
+
+                        queue_toast(
+                            level,
+                            self.resource_man
+                                .gui_fmt(id, [("name", Formattable::display(name))]),
+                        );
+                    }
+
+                    result?;
+
+                    state.last_save_duration = Some(start.elapsed());
+                    state.last_save = Some(Instant::now());
                 }
                 reply.send(())?;
             }
+            BackupMap(reply) => {
+                let mut backed_up = false;
+
+                if let Some(map) = &state.map {
+                    map.info.lock().await.tick_count = state.tick_count;
+                    map.save(
+                        &self.resource_man,
+                        &state.tile_entities,
+                        &state.save_options,
+                    )
+                    .await?;
+
+                    match map.write_backup() {
+                        Ok(()) => backed_up = true,
+                        Err(err) => log::error!("Could not back up {}! Error: {err}", map.opt),
+                    }
+                }
+
+                reply.send(backed_up)?;
+            }
+            Autosave => {
+                if let Some(map) = &state.map {
+                    let interval = {
+                        let mut info = map.info.lock().await;
+                        info.tick_count = state.tick_count;
+
+                        autosave_interval(&info.data, &self.resource_man.registry.data_ids)
+                    };
+
+                    if interval.is_some_and(|interval| {
+                        state.last_save.map_or(true, |v| v.elapsed() >= interval)
+                    }) {
+                        // an autosave that fails is tried again at the next check, without stopping the game
+                        match map
+                            .save_autosave(
+                                &self.resource_man,
+                                &state.tile_entities,
+                                &state.save_options,
+                            )
+                            .await
+                        {
+                            Ok(()) => state.last_save = Some(Instant::now()),
+                            Err(err) => {
+                                log::error!("Could not autosave {}! Error: {err}", map.opt);
+
+                                queue_toast(
+                                    ToastLevel::Warning,
+                                    self.resource_man
+                                        .gui_str(
+                                            self.resource_man.registry.gui_ids.lbl_autosave_failed,
+                                        )
+                                        .to_string(),
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+            SetSaveOptions(options) => {
+                state.save_options = options;
+            }
+            TrainMapDictionary(reply) => {
+                let mut size = None;
+
+                if let Some(map) = &mut state.map {
+                    match map
+                        .train_dictionary(&self.resource_man, &state.tile_entities)
+                        .await
+                    {
+                        Ok(trained) => {
+                            map.info.lock().await.tick_count = state.tick_count;
+                            map.save(
+                                &self.resource_man,
+                                &state.tile_entities,
+                                &state.save_options,
+                            )
+                            .await?;
+
+                            size = Some(trained);
+                        }
+                        Err(err) => log::warn!("Could not train a map dictionary! Error: {err}"),
+                    }
+                }
+
+                reply.send(size)?;
+            }
+            TakeMissingIds(reply) => {
+                reply.send(
+                    state
+                        .map
+                        .as_mut()
+                        .map(|map| mem::take(&mut map.missing_ids))
+                        .unwrap_or_default(),
+                )?;
+            }
             GetMapInfoAndName(reply) => {
                 if let Some(map) = &state.map {
                     reply.send(Some((map.info.clone(), map.opt.clone())))?;
@@ -264,12 +898,138 @@ impl Actor for GameSystem {
                 }
             }
 
+            GetTick(reply) => {
+                reply.send(state.tick_count)?;
+            }
+            SetTickRates(rates) => {
+                state.tick_rates = TickRates {
+                    logic: rates.logic.clamp(1, MAX_TICK_RATE),
+                    transfer: rates.transfer.clamp(1, MAX_TICK_RATE),
+                    speed: closest_speed(rates.speed),
+                };
+
+                if let Some(map) = &state.map {
+                    state.tick_rates.write_data(
+                        &mut map.info.lock().await.data,
+                        &self.resource_man.registry.data_ids,
+                    );
+                }
+            }
+            SetSandbox(sandbox, reply) => {
+                state.sandbox = sandbox;
+
+                if let Some(map) = &state.map {
+                    sandbox.write_data(
+                        &mut map.info.lock().await.data,
+                        &self.resource_man.registry.data_ids,
+                    );
+                }
+
+                reply.send(())?;
+            }
+            GetTickStats(reply) => {
+                reply.send(TickStats {
+                    rates: state.tick_rates,
+                    logic_frequency: state.tick_frequencies.0,
+                    transfer_frequency: state.tick_frequencies.1,
+                    paused: state.paused,
+                    steps: state.steps,
+                })?;
+            }
+            SetTickPaused(paused) => {
+                if state.paused && !paused {
+                    // the time spent paused isn't counted against the frequencies
+                    state.tick_measurement = None;
+                }
+
+                state.paused = paused;
+                state.steps = 0;
+            }
+            StepTick(steps) => {
+                state.paused = true;
+                state.steps = state.steps.saturating_add(steps);
+            }
+
+            RecordCraft(script, crafts) => {
+                *state.crafted.entry(script).or_insert(0) += crafts;
+            }
+            SetConfiguredScript {
+                coord,
+                actor,
+                script,
+            } => {
+                // a tile entity that was removed, or is from the last map, is already left out
+                let current = state
+                    .tile_entities
+                    .get(&coord)
+                    .is_some_and(|tile_entity| tile_entity.get_id() == actor);
+
+                match script {
+                    Some(script) if current => state.set_configured_script(actor, Some(script)),
+                    Some(_) => {}
+                    None => state.set_configured_script(actor, None),
+                }
+            }
+            GetScriptUsage(reply) => {
+                reply.send(ScriptUsage {
+                    configured: state.configured.clone(),
+                    crafted: state.crafted.clone(),
+                })?;
+            }
+            AdvanceResearchQueue(reply) => {
+                let next = match &state.map {
+                    Some(map) => {
+                        pop_research_queue(&self.resource_man, &mut map.info.lock().await.data)
+                    }
+                    None => None,
+                };
+
+                reply.send(next)?;
+            }
             Tick => {
-                tick(state);
+                // the ticks missed while paused aren't made up for, so resuming goes on from where it stopped
+                if !state.paused {
+                    state.speed_remainder += state.tick_rates.speed;
+
+                    for _ in 0..state.speed_remainder / NORMAL_TICK_SPEED {
+                        tick(&self.resource_man, state);
+                    }
+                    state.speed_remainder %= NORMAL_TICK_SPEED;
+                } else if state.steps > 0 {
+                    state.steps -= 1;
+                    tick(&self.resource_man, state);
+                }
+
+                if metrics::enabled()
+                    && state
+                        .metrics_published
+                        .map_or(true, |v| v.elapsed() >= metrics::PUBLISH_INTERVAL)
+                {
+                    metrics::publish(metrics_snapshot(&self.resource_man, state));
+                    state.metrics_published = Some(Instant::now());
+                }
             }
             StopTicking => {
                 state.stopped = true;
             }
+            Undo => {
+                if let Some(step) = state.history.undo.pop_back() {
+                    let redo = self.replay(myself, state, step).await?;
+
+                    if !redo.is_empty() {
+                        state.history.redo.push_back(redo);
+                    }
+                }
+            }
+            Redo => {
+                if let Some(step) = state.history.redo.pop_back() {
+                    let undo = self.replay(myself, state, step).await?;
+
+                    if !undo.is_empty() {
+                        state.history.undo.push_back(undo);
+                    }
+                }
+            }
 
             rest => {
                 if state.stopped {
@@ -283,12 +1043,13 @@ impl Actor for GameSystem {
                 match rest {
                     GetAllRenderCommands {
                         culling_range,
+                        statuses,
                         reply,
                     } => {
                         let last_culling_range = state.last_culling_range;
                         state.last_culling_range = culling_range;
 
-                        let commands = multi_call_iter(
+                        let replies = multi_call_iter(
                             &state.tile_entities,
                             |reply, coord| {
                                 let loading = culling_range.contains(coord)
@@ -300,31 +1061,52 @@ impl Actor for GameSystem {
                                     reply,
                                     loading,
                                     unloading,
+                                    status: statuses && culling_range.contains(coord),
                                 }
                             },
                             None,
                         )
                         .await
-                        .map(|commands| {
-                            commands
-                                .into_iter()
-                                .flat_map(|(k, v)| Some(k).zip(v))
-                                .collect()
+                        .map(|replies| {
+                            let mut commands = HashMap::new();
+                            let mut tile_statuses = HashMap::new();
+
+                            for (coord, (tile_commands, status)) in replies {
+                                if let Some(tile_commands) = tile_commands {
+                                    commands.insert(coord, tile_commands);
+                                }
+                                if let Some(status) = status {
+                                    tile_statuses.insert(coord, status);
+                                }
+                            }
+
+                            (commands, tile_statuses)
                         });
 
-                        match commands {
-                            Ok(mut commands) => {
+                        let terrain = map.info.lock().await.terrain.clone();
+
+                        match replies {
+                            Ok((mut commands, statuses)) => {
+                                // the ground under bigger tiles is covered by them
+                                for covered in map.footprints.keys() {
+                                    commands.entry(*covered).or_default();
+                                }
+
                                 fill_map_with_none(
                                     &self.resource_man,
+                                    &terrain,
                                     culling_range,
                                     last_culling_range,
                                     &mut commands,
                                 );
 
-                                reply.send([
-                                    mem::take(&mut state.cleanup_render_commands),
-                                    commands,
-                                ])?;
+                                reply.send(RenderFrame {
+                                    commands: [
+                                        mem::take(&mut state.cleanup_render_commands),
+                                        commands,
+                                    ],
+                                    statuses,
+                                })?;
                             }
                             Err(err) => {
                                 log::error!("Could not collect render commands! Error: {err:?}")
@@ -336,23 +1118,58 @@ impl Actor for GameSystem {
                         id,
                         data,
                         record,
+                        force,
                         reply,
                     } => {
-                        if let Some(old_id) = map.tiles.get(&coord) {
-                            if *old_id == id {
+                        let none = TileId(self.resource_man.registry.none);
+
+                        // a bigger tile is removed from any coordinate it covers, but nothing else goes over them
+                        let coord = if id == none { map.anchor(coord) } else { coord };
+
+                        if let Some(old_id) = map.get_tile(coord) {
+                            if old_id == id {
                                 if let Some(reply) = reply {
-                                    reply.send(PlaceTileResponse::Ignored)?;
+                                    reply.send(PlacementResult::Ignored)?;
                                 }
 
                                 return Ok(());
                             }
                         }
 
-                        if id == TileId(self.resource_man.registry.none)
-                            && !map.tiles.contains_key(&coord)
-                        {
+                        if id == none && !map.has_tile(coord) {
+                            if let Some(reply) = reply {
+                                reply.send(PlacementResult::Ignored)?;
+                            }
+
+                            return Ok(());
+                        }
+
+                        if map.footprints.contains_key(&coord) {
                             if let Some(reply) = reply {
-                                reply.send(PlaceTileResponse::Ignored)?;
+                                reply.send(PlacementResult::Failed(PlacementFailure::Occupied))?;
+                            }
+
+                            return Ok(());
+                        }
+
+                        let failure = {
+                            let mut info = map.info.lock().await;
+
+                            placement_failure(
+                                &self.resource_man,
+                                &state.tile_entities,
+                                map,
+                                &mut info,
+                                coord,
+                                id,
+                                data.as_ref(),
+                                force,
+                            )
+                            .await
+                        };
+                        if let Some(failure) = failure {
+                            if let Some(reply) = reply {
+                                reply.send(PlacementResult::Failed(failure))?;
                             }
 
                             return Ok(());
@@ -372,33 +1189,84 @@ impl Actor for GameSystem {
 
                         if let Some(reply) = reply {
                             if let (Some(_), ..) = &old_tile {
-                                if id == TileId(self.resource_man.registry.none) {
-                                    reply.send(PlaceTileResponse::Removed)?;
+                                if id == none {
+                                    reply.send(PlacementResult::Removed)?;
                                 } else {
-                                    reply.send(PlaceTileResponse::Placed)?;
+                                    reply.send(PlacementResult::Placed)?;
                                 }
                             } else {
-                                reply.send(PlaceTileResponse::Placed)?;
+                                reply.send(PlacementResult::Placed)?;
                             }
                         }
 
-                        if let (Some(id), data) = old_tile {
-                            if record {
-                                state.undo_steps.push_back(vec![PlaceTile {
+                        if record {
+                            match old_tile {
+                                (Some(id), data) => state.history.record(PlaceTile {
                                     coord,
                                     id,
-                                    record: false,
+                                    record: true,
+                                    force: true,
                                     reply: None,
                                     data,
-                                }]);
+                                }),
+                                // placed where there was nothing, so it is undone by removing it
+                                (None, _) if map.get_tile(coord) == Some(id) => {
+                                    state.history.record(PlaceTile {
+                                        coord,
+                                        id: TileId(self.resource_man.registry.none),
+                                        record: true,
+                                        force: true,
+                                        reply: None,
+                                        data: None,
+                                    })
+                                }
+                                _ => {}
                             }
                         }
                     }
                     GetTile(coord, reply) => {
-                        reply.send(map.tiles.get(&coord).cloned())?;
+                        reply.send(map.get_tile(coord))?;
+                    }
+                    PickTile(coord, reply) => {
+                        let coord = map.anchor(coord);
+
+                        let Some(id) = map.get_tile(coord) else {
+                            reply.send(None)?;
+
+                            return Ok(());
+                        };
+
+                        let mut data = DataMap::default();
+
+                        if let Some(entity) = state.tile_entities.get(&coord) {
+                            if let Ok(CallResult::Success(mut entity_data)) =
+                                entity.call(TileEntityMsg::GetData, None).await
+                            {
+                                data = copy_auxiliary_data(&self.resource_man, &mut entity_data);
+                            }
+                        }
+
+                        reply.send(Some((id, data)))?;
                     }
                     GetTileEntity(coord, reply) => {
-                        reply.send(state.tile_entities.get(&coord).cloned())?;
+                        reply.send(state.tile_entities.get(&map.anchor(coord)).cloned())?;
+                    }
+                    GetTilesInBounds(bounds, reply) => {
+                        reply.send(
+                            bounds
+                                .into_iter()
+                                .flat_map(|coord| map.get_tile(coord).map(|id| (coord, id)))
+                                .collect(),
+                        )?;
+                    }
+                    GetTickActivity(bounds, reply) => {
+                        reply.send(
+                            tick_activity(&self.resource_man, map, &state.tile_entities, bounds)
+                                .await,
+                        )?;
+                    }
+                    GetAllTiles(reply) => {
+                        reply.send((map.all_tiles(), state.tile_entities.clone()))?;
                     }
                     ForwardMsgToTile {
                         source,
@@ -406,7 +1274,7 @@ impl Actor for GameSystem {
                         msg,
                         on_fail,
                     } => {
-                        if let Some(tile_entity) = state.tile_entities.get(&to) {
+                        if let Some(tile_entity) = state.tile_entities.get(&map.anchor(to)) {
                             tile_entity.send_message(msg)?;
                         } else {
                             match on_fail {
@@ -416,6 +1284,7 @@ impl Actor for GameSystem {
                                         &self.resource_man,
                                         map,
                                         &mut state.tile_entities,
+                                        &mut state.cleanup_render_commands,
                                         source,
                                     )
                                     .await;
@@ -435,19 +1304,14 @@ impl Actor for GameSystem {
                             }
                         }
                     }
-                    Undo => {
-                        if let Some(step) = state.undo_steps.pop_back() {
-                            for msg in step {
-                                myself.send_message(msg)?;
-                            }
-                        }
-                    }
                     GetTiles(coords, reply) => {
                         let mut tiles = vec![];
 
+                        // the coordinates covered by bigger tiles come with the tiles
                         for (id, coord) in coords
                             .into_iter()
-                            .flat_map(|coord| map.tiles.get(&coord).zip(Some(coord)))
+                            .filter(|coord| !map.footprints.contains_key(coord))
+                            .flat_map(|coord| map.get_tile(coord).zip(Some(coord)))
                         {
                             if let Some(entity) = state.tile_entities.get(&coord) {
                                 if let Ok(CallResult::Success(mut data)) =
@@ -455,12 +1319,12 @@ impl Actor for GameSystem {
                                 {
                                     tiles.push((
                                         coord,
-                                        *id,
+                                        id,
                                         Some(copy_auxiliary_data(&self.resource_man, &mut data)),
                                     ));
                                 }
                             } else {
-                                tiles.push((coord, *id, None));
+                                tiles.push((coord, id, None));
                             }
                         }
                         reply.send(tiles)?;
@@ -470,51 +1334,106 @@ impl Actor for GameSystem {
                         reply,
                         place_over,
                         record,
+                        force,
                     } => {
                         let mut old = vec![];
+                        let mut placed = vec![];
+                        let mut failures = PlacementFailures::default();
+
+                        let none = TileId(self.resource_man.registry.none);
 
                         for (coord, id, data) in tiles {
-                            if place_over || map.tiles.get(&coord).is_none() {
-                                if let (Some(old_id), old_data) = insert_new_tile(
-                                    self.resource_man.clone(),
-                                    myself.clone(),
+                            let coord = if id == none { map.anchor(coord) } else { coord };
+
+                            if (!place_over && map.has_tile(coord))
+                                || (id != none && map.footprints.contains_key(&coord))
+                            {
+                                failures.push(coord, PlacementFailure::Occupied);
+                                continue;
+                            }
+
+                            // there is nothing to remove
+                            if id == none && !map.has_tile(coord) {
+                                continue;
+                            }
+
+                            let failure = {
+                                let mut info = map.info.lock().await;
+
+                                placement_failure(
+                                    &self.resource_man,
+                                    &state.tile_entities,
                                     map,
-                                    &mut state.tile_entities,
-                                    &mut state.cleanup_render_commands,
+                                    &mut info,
                                     coord,
                                     id,
-                                    data,
+                                    data.as_ref(),
+                                    force,
                                 )
                                 .await
-                                {
-                                    if let Some(mut old_data) = old_data {
-                                        old.push((
-                                            coord,
-                                            old_id,
-                                            Some(copy_auxiliary_data(
-                                                &self.resource_man,
-                                                &mut old_data,
-                                            )),
-                                        ));
-                                    } else {
-                                        old.push((coord, old_id, None));
-                                    }
+                            };
+                            if let Some(failure) = failure {
+                                failures.push(coord, failure);
+                                continue;
+                            }
+
+                            if let (Some(old_id), old_data) = insert_new_tile(
+                                self.resource_man.clone(),
+                                myself.clone(),
+                                map,
+                                &mut state.tile_entities,
+                                &mut state.cleanup_render_commands,
+                                coord,
+                                id,
+                                data,
+                            )
+                            .await
+                            {
+                                if let Some(mut old_data) = old_data {
+                                    old.push((
+                                        coord,
+                                        old_id,
+                                        Some(copy_auxiliary_data(
+                                            &self.resource_man,
+                                            &mut old_data,
+                                        )),
+                                    ));
+                                } else {
+                                    old.push((coord, old_id, None));
                                 }
+                            } else if map.get_tile(coord) == Some(id) {
+                                placed.push((coord, none, None));
                             }
                         }
 
-                        if let Some(reply) = reply {
-                            reply.send(old)?;
-                        } else if record {
-                            state.undo_steps.push_back(vec![PlaceTiles {
-                                tiles: old,
+                        if record {
+                            // the tiles placed where there was nothing are removed again, and the replaced ones put back over the new ones
+                            state.history.record(PlaceTiles {
+                                tiles: old.iter().cloned().chain(placed).collect(),
                                 reply: None,
-                                place_over: false,
-                                record: false,
-                            }]);
+                                place_over: true,
+                                record: true,
+                                force: true,
+                            });
+                        }
+                        if let Some(reply) = reply {
+                            reply.send((old, failures))?;
                         }
                     }
                     MoveTiles(tiles, direction, record) => {
+                        let terrain = map.info.lock().await.terrain.clone();
+
+                        // the tiles are moved all together or not at all
+                        if let Some(coord) = tiles.iter().find(|coord| {
+                            map.get_tile(**coord).is_some_and(|id| {
+                                !terrain.can_place(&self.resource_man, id, **coord + direction)
+                            })
+                        }) {
+                            log::debug!("Not moving the tiles, as the terrain doesn't allow the one at {coord} to go there");
+
+                            return Ok(());
+                        }
+
                         let mut undo = vec![];
 
                         let mut removed = Vec::new();
@@ -524,6 +1443,7 @@ impl Actor for GameSystem {
                                 &self.resource_man,
                                 map,
                                 &mut state.tile_entities,
+                                &mut state.cleanup_render_commands,
                                 coord,
                             )
                             .await
@@ -557,11 +1477,50 @@ impl Actor for GameSystem {
                         }
 
                         if record {
-                            state
-                                .undo_steps
-                                .push_back(vec![MoveTiles(undo, -direction, false)]);
+                            state.history.record(MoveTiles(undo, -direction, true));
                         }
                     }
+                    TrimTiles {
+                        coords,
+                        last,
+                        reply,
+                    } => {
+                        let mut removed = 0;
+
+                        for coord in coords {
+                            if let Some((id, data, mut cleanup)) = take_tile(
+                                &self.resource_man,
+                                map,
+                                &mut state.tile_entities,
+                                &mut state.cleanup_render_commands,
+                                coord,
+                            )
+                            .await
+                            {
+                                state
+                                    .cleanup_render_commands
+                                    .entry(coord)
+                                    .or_default()
+                                    .append(&mut cleanup);
+
+                                // kept whole, so that undoing gives the tiles back as they were
+                                state.trimmed.push((coord, id, data));
+                                removed += 1;
+                            }
+                        }
+
+                        if last && !state.trimmed.is_empty() {
+                            state.history.record(PlaceTiles {
+                                tiles: mem::take(&mut state.trimmed),
+                                reply: None,
+                                place_over: true,
+                                record: true,
+                                force: true,
+                            });
+                        }
+
+                        reply.send(removed)?;
+                    }
                     _ => {}
                 }
             }
@@ -582,6 +1541,8 @@ impl Actor for GameSystem {
                     "Tile entity {dead_actor:?} panicked, trying to remove. Error: {error}"
                 );
 
+                state.set_configured_script(dead_actor.get_id(), None);
+
                 if let Ok(tile_error) = error.downcast::<Box<TileEntityError>>() {
                     match **tile_error {
                         TileEntityError::NonExistent(coord) => {
@@ -590,6 +1551,7 @@ impl Actor for GameSystem {
                                     &self.resource_man,
                                     map,
                                     &mut state.tile_entities,
+                                    &mut state.cleanup_render_commands,
                                     coord,
                                 )
                                 .await;
@@ -600,6 +1562,8 @@ impl Actor for GameSystem {
             }
             SupervisionEvent::ActorTerminated(dead_actor, _tile_state, reason) => {
                 log::debug!("Tile entity {dead_actor:?} has been removed. Reason: {reason:?}");
+
+                state.set_configured_script(dead_actor.get_id(), None);
             }
             other => {
                 log::debug!("Supervision event: {other}")
@@ -641,6 +1605,7 @@ pub async fn new_tile(
     game: ActorRef<GameSystemMessage>,
     coord: TileCoord,
     id: TileId,
+    seed: u64,
 ) -> ActorRef<TileEntityMsg> {
     let (actor, _handle) = Actor::spawn_linked(
         Some(coord.to_minimal_string()),
@@ -648,8 +1613,9 @@ pub async fn new_tile(
             id,
             coord,
             resource_man,
+            counter: ACTOR_STATS.tile_counter(coord),
         },
-        (game.clone(),),
+        (game.clone(), seed),
         game.get_cell(),
     )
     .await
@@ -658,59 +1624,299 @@ pub async fn new_tile(
     actor
 }
 
-/// Stops a tile and removes it from the game
+/// Gives a new map what the packs' start definitions say it starts with.
+///
+/// The start tiles are placed for free, but the ones the terrain doesn't allow are left out.
+async fn apply_start(
+    resource_man: &Arc<ResourceManager>,
+    game: ActorRef<GameSystemMessage>,
+    map: &mut GameMap,
+    tile_entities: &mut TileEntities,
+) {
+    let start = &resource_man.registry.start;
+
+    let (terrain, seed) = {
+        let mut info = map.info.lock().await;
+
+        if let Data::Inventory(inventory) = info
+            .data
+            .entry(resource_man.registry.data_ids.player_inventory)
+            .or_insert_with(|| Data::Inventory(Default::default()))
+        {
+            for stack in &start.inventory {
+                inventory.add_limited(
+                    stack.id,
+                    stack.amount,
+                    InventoryLimits::default().for_item(resource_man, stack.id),
+                );
+            }
+        }
+
+        if let Data::SetId(unlocked) = info
+            .data
+            .entry(resource_man.registry.data_ids.unlocked_researches)
+            .or_insert_with(|| Data::SetId(HashSet::new()))
+        {
+            unlocked.extend(start.researches.iter().copied());
+        }
+
+        (info.terrain.clone(), info.seed)
+    };
+
+    for (coord, id, data) in &start.tiles {
+        if !terrain.can_place(resource_man, *id, *coord) {
+            log::warn!("The start tile {id:?} at {coord} can't be placed on the terrain, skipping");
+
+            continue;
+        }
+
+        let tile_entity = new_tile(resource_man.clone(), game.clone(), *coord, *id, seed).await;
+
+        if !data.is_empty() {
+            tile_entity
+                .send_message(TileEntityMsg::SetData(data.clone()))
+                .unwrap();
+        }
+
+        map.set_tile(*coord, *id);
+        tile_entities.insert(*coord, tile_entity);
+
+        for covered in covered_coords(resource_man, *coord, *id, Some(data)) {
+            if !map.has_tile(covered) {
+                map.set_tile(covered, *id);
+                map.footprints.insert(covered, *coord);
+            }
+        }
+    }
+}
+
+/// Gets the latest tick each tile in the bounds did something at when ticked. Tiles whose scripts have no tick handlers are left out.
+async fn tick_activity(
+    resource_man: &ResourceManager,
+    map: &GameMap,
+    tile_entities: &TileEntities,
+    bounds: TileBounds,
+) -> HashMap<TileCoord, Option<TickUnit>> {
+    let ticked = tile_entities
+        .iter()
+        .filter(|(coord, _)| bounds.contains(**coord))
+        .filter(|(coord, _)| {
+            map.get_tile(**coord)
+                .and_then(|id| resource_man.registry.tiles.get(&id))
+                .and_then(|tile| tile.function.as_ref())
+                .and_then(|function| resource_man.functions.get(function))
+                .is_some_and(|(_, metadata)| {
+                    metadata.handles_tick || metadata.handles_transfer_tick
+                })
+        })
+        .map(|(coord, entity)| (*coord, entity.clone()))
+        .collect::<TileEntities>();
+
+    match multi_call_iter_tolerant(
+        &ticked,
+        |reply, _| TileEntityMsg::GetActiveTick(reply),
+        None,
+    )
+    .await
+    {
+        Ok(activity) => activity,
+        Err(err) => {
+            log::error!("Could not collect the tick activity! Error: {err:?}");
+
+            HashMap::new()
+        }
+    }
+}
+
+/// Checks whether the tile can be placed at the position, and why not if it can't.
+///
+/// The protection and the requirements of the tile are only checked when the placement isn't forced.
+/// A tile covering several coordinates needs all of them to be free of other tiles, and placeable on.
+async fn placement_failure(
+    resource_man: &ResourceManager,
+    tile_entities: &TileEntities,
+    map: &GameMap,
+    info: &mut MapInfo,
+    coord: TileCoord,
+    id: TileId,
+    data: Option<&DataMap>,
+    force: bool,
+) -> Option<PlacementFailure> {
+    if !force && is_protected(resource_man, tile_entities, coord).await {
+        return Some(PlacementFailure::Protected);
+    }
+
+    if !info.terrain.can_place(resource_man, id, coord) {
+        return Some(PlacementFailure::InvalidTerrain);
+    }
+
+    for covered in covered_coords(resource_man, coord, id, data) {
+        if map.has_tile(covered) && map.anchor(covered) != coord {
+            return Some(PlacementFailure::Occupied);
+        }
+
+        if !info.terrain.can_place(resource_man, id, covered) {
+            return Some(PlacementFailure::InvalidTerrain);
+        }
+    }
+
+    if !force {
+        placement_requirements(resource_man, &mut info.data, id).err()
+    } else {
+        None
+    }
+}
+
+/// Gets the other coordinates the tile covers when placed at the coordinate, facing the direction in the data, or else in its definition.
+fn covered_coords(
+    resource_man: &ResourceManager,
+    coord: TileCoord,
+    id: TileId,
+    data: Option<&DataMap>,
+) -> Vec<TileCoord> {
+    let Some(tile) = resource_man.registry.tiles.get(&id) else {
+        return vec![];
+    };
+
+    if tile.footprint.is_empty() {
+        return vec![];
+    }
+
+    tile.footprint_at(coord, tile_direction(resource_man, id, data))
+}
+
+/// Checks if the tile at the given position is marked as protected from removal.
+async fn is_protected(
+    resource_man: &ResourceManager,
+    tile_entities: &TileEntities,
+    coord: TileCoord,
+) -> bool {
+    let Some(tile_entity) = tile_entities.get(&coord) else {
+        return false;
+    };
+
+    matches!(
+        tile_entity
+            .call(
+                |reply| TileEntityMsg::GetDataValue(
+                    resource_man.registry.data_ids.protected,
+                    reply
+                ),
+                None,
+            )
+            .await,
+        Ok(CallResult::Success(Some(Data::Bool(true))))
+    )
+}
+
+/// Removes the tile and stops its tile entity, without giving anything back.
+///
+/// A tile entity that doesn't answer or stop within [`TILE_STOP_TIMEOUT`] is killed, losing its data, so that one stuck tile can't hold up the game.
+///
+/// Only takes tiles from the coordinates they are placed at. The other coordinates they cover are freed along with them.
+async fn take_tile(
+    resource_man: &ResourceManager,
+    map: &mut GameMap,
+    tile_entities: &mut TileEntities,
+    cleanup_render_commands: &mut HashMap<TileCoord, Vec<RenderCommand>>,
+    coord: TileCoord,
+) -> Option<(TileId, Option<DataMap>, Vec<RenderCommand>)> {
+    if map.footprints.contains_key(&coord) {
+        return None;
+    }
+
+    let (tile, tile_entity) = map.clear_tile(coord).zip(tile_entities.remove(&coord))?;
+
+    let data = tile_entity
+        .call(TileEntityMsg::TakeData, Some(TILE_STOP_TIMEOUT))
+        .await
+        .ok()
+        .and_then(|v| v.success_or(()).ok());
+
+    let mut commands = tile_entity
+        .call(
+            |reply| TileEntityMsg::CollectRenderCommands {
+                reply,
+                loading: false,
+                unloading: true,
+                status: false,
+            },
+            Some(TILE_STOP_TIMEOUT),
+        )
+        .await
+        .ok()
+        .and_then(|v| v.success_or(()).ok())
+        .and_then(|(commands, _)| commands)
+        .unwrap_or_default();
+
+    let terrain = map.info.lock().await.terrain.clone();
+    commands.extend_from_slice(&track_none(resource_man, &terrain, coord));
+
+    let covered = map.take_footprint(coord);
+    for covered in &covered {
+        cleanup_render_commands
+            .entry(*covered)
+            .or_default()
+            .extend_from_slice(&track_none(resource_man, &terrain, *covered));
+    }
+
+    refresh_variants(
+        resource_man,
+        map,
+        tile_entities,
+        with_neighbors([coord].into_iter().chain(covered)),
+    );
+
+    if let Err(err) = tile_entity
+        .stop_and_wait(
+            Some("Removed from game".to_string()),
+            Some(TILE_STOP_TIMEOUT),
+        )
+        .await
+    {
+        log::warn!("Tile entity at {coord} did not stop in time, killing it. Error: {err}");
+        tile_entity.kill();
+    }
+
+    Some((tile, data, commands))
+}
+
+/// Removes the tile, giving the item of its category back to the player.
 async fn remove_tile(
     resource_man: &ResourceManager,
     map: &mut GameMap,
     tile_entities: &mut TileEntities,
+    cleanup_render_commands: &mut HashMap<TileCoord, Vec<RenderCommand>>,
     coord: TileCoord,
 ) -> Option<(TileId, Option<DataMap>, Vec<RenderCommand>)> {
-    if let Some((tile, tile_entity)) = map.tiles.remove(&coord).zip(tile_entities.remove(&coord)) {
-        {
-            let lock = &mut map.info.lock().await;
+    let removed = take_tile(
+        resource_man,
+        map,
+        tile_entities,
+        cleanup_render_commands,
+        coord,
+    )
+    .await?;
+
+    {
+        let lock = &mut map.info.lock().await;
 
-            try_category(resource_man, tile, |item| {
+        // nothing was taken to place it
+        if !is_sandbox(resource_man, &lock.data) {
+            try_category(resource_man, removed.0, |item| {
                 if let Data::Inventory(inventory) = lock
                     .data
                     .entry(resource_man.registry.data_ids.player_inventory)
                     .or_insert_with(|| Data::Inventory(Default::default()))
                 {
+                    // the item was taken when the tile was placed, so giving it back ignores the stack size rather than losing it
                     inventory.add(item, 1);
                 }
             });
         }
-
-        let data = tile_entity
-            .call(TileEntityMsg::TakeData, None)
-            .await
-            .ok()
-            .and_then(|v| v.success_or(()).ok());
-
-        let mut commands = tile_entity
-            .call(
-                |reply| TileEntityMsg::CollectRenderCommands {
-                    reply,
-                    loading: false,
-                    unloading: true,
-                },
-                None,
-            )
-            .await
-            .unwrap()
-            .unwrap()
-            .unwrap_or_default();
-
-        commands.extend_from_slice(&track_none(resource_man, coord));
-
-        tile_entity
-            .stop_and_wait(Some("Removed from game".to_string()), None)
-            .await
-            .unwrap();
-
-        Some((tile, data, commands))
-    } else {
-        None
     }
+
+    Some(removed)
 }
 
 /// Makes a new tile and add it into both the map and the game
@@ -724,25 +1930,34 @@ async fn insert_new_tile(
     tile_id: TileId,
     data: Option<DataMap>,
 ) -> (Option<TileId>, Option<DataMap>) {
+    // covered by a bigger tile, which has to be removed from where it is placed
+    if map.footprints.contains_key(&coord) {
+        return (None, None);
+    }
+
     let mut skip = false;
 
-    {
+    let seed = {
         let lock = &mut map.info.lock().await;
 
-        try_category(&resource_man, tile_id, |item| {
-            if let Data::Inventory(inventory) = lock
-                .data
-                .entry(resource_man.registry.data_ids.player_inventory)
-                .or_insert_with(|| Data::Inventory(Default::default()))
-            {
-                if inventory.get(item) < 1 {
-                    skip = true;
+        if !is_sandbox(&resource_man, &lock.data) {
+            try_category(&resource_man, tile_id, |item| {
+                if let Data::Inventory(inventory) = lock
+                    .data
+                    .entry(resource_man.registry.data_ids.player_inventory)
+                    .or_insert_with(|| Data::Inventory(Default::default()))
+                {
+                    if inventory.get(item) < 1 {
+                        skip = true;
+                    }
+
+                    inventory.take(item, 1);
                 }
+            });
+        }
 
-                inventory.take(item, 1);
-            }
-        });
-    }
+        lock.seed
+    };
 
     if skip {
         return (None, None);
@@ -751,8 +1966,14 @@ async fn insert_new_tile(
     let mut old_id = None;
     let mut old_data = None;
 
-    if let Some((id, data, mut cleanup)) =
-        remove_tile(&resource_man, map, tile_entities, coord).await
+    if let Some((id, data, mut cleanup)) = remove_tile(
+        &resource_man,
+        map,
+        tile_entities,
+        cleanup_render_commands,
+        coord,
+    )
+    .await
     {
         cleanup_render_commands
             .entry(coord)
@@ -767,7 +1988,9 @@ async fn insert_new_tile(
         old_data = data;
     }
 
-    let tile_entity = new_tile(resource_man.clone(), game, coord, tile_id).await;
+    let covered = covered_coords(&resource_man, coord, tile_id, data.as_ref());
+
+    let tile_entity = new_tile(resource_man.clone(), game, coord, tile_id, seed).await;
 
     if let Some(data) = data {
         tile_entity
@@ -775,10 +1998,11 @@ async fn insert_new_tile(
             .unwrap();
     }
 
+    let terrain = map.info.lock().await.terrain.clone();
     cleanup_render_commands
         .entry(coord)
         .or_default()
-        .extend_from_slice(&untrack_none(&resource_man));
+        .extend_from_slice(&untrack_none(&resource_man, &terrain, coord));
 
     let mut new_tile_render = tile_entity
         .call(
@@ -786,12 +2010,14 @@ async fn insert_new_tile(
                 reply,
                 loading: true,
                 unloading: false,
+                status: false,
             },
             None,
         )
         .await
         .unwrap()
         .unwrap()
+        .0
         .unwrap_or_default();
 
     cleanup_render_commands
@@ -800,27 +2026,236 @@ async fn insert_new_tile(
         .append(&mut new_tile_render);
 
     tile_entities.insert(coord, tile_entity);
-    map.tiles.insert(coord, tile_id);
+    map.set_tile(coord, tile_id);
+
+    let mut claimed = vec![coord];
+    for covered in covered {
+        // the placements that aren't checked beforehand, like moves and undos, only get the coordinates that are free
+        if map.has_tile(covered) {
+            log::debug!("Tile {tile_id:?} at {coord} can't cover {covered}, as it is taken");
+
+            continue;
+        }
+
+        map.set_tile(covered, tile_id);
+        map.footprints.insert(covered, coord);
+        cleanup_render_commands
+            .entry(covered)
+            .or_default()
+            .extend_from_slice(&untrack_none(&resource_man, &terrain, covered));
+        claimed.push(covered);
+    }
+
+    refresh_variants(&resource_man, map, tile_entities, with_neighbors(claimed));
 
     (old_id, old_data)
 }
 
-fn inner_tick(state: &mut GameSystemState) {
-    state.tile_entities.iter().for_each(|(_, tile_entity)| {
-        if let Err(e) = tile_entity.send_message(TileEntityMsg::Tick {
-            tick_count: state.tick_count,
-        }) {
-            log::error!("{e:?}");
+/// Gets the coordinates along with their neighbors, without repeats.
+fn with_neighbors(coords: impl IntoIterator<Item = TileCoord>) -> HashSet<TileCoord> {
+    coords
+        .into_iter()
+        .flat_map(|coord| [coord].into_iter().chain(coord.neighbors()))
+        .collect()
+}
+
+/// Picks the model variant of each tile at the coordinates that has variants, by which of its neighbors it connects to.
+///
+/// The model is told to the tile as data, which isn't saved, so the variants are picked again whenever the neighbors change.
+fn refresh_variants(
+    resource_man: &ResourceManager,
+    map: &GameMap,
+    tile_entities: &TileEntities,
+    coords: impl IntoIterator<Item = TileCoord>,
+) {
+    let key = resource_man.registry.data_ids.variant_model;
+
+    for coord in coords {
+        // the coordinates covered by bigger tiles have no entity of their own
+        let Some((id, entity)) = map.get_tile(coord).zip(tile_entities.get(&coord)) else {
+            continue;
+        };
+        let Some(tile) = resource_man.registry.tiles.get(&id) else {
+            continue;
+        };
+        if tile.variants.is_empty() {
+            continue;
+        }
+
+        let connects_to = tile.connects_to.unwrap_or(*id);
+        let mask = VARIANT_NEIGHBORS
+            .iter()
+            .enumerate()
+            .filter(|(_, offset)| {
+                map.get_tile(coord + **offset)
+                    .is_some_and(|other| item_match(resource_man, *other, connects_to))
+            })
+            .fold(0, |mask, (bit, _)| mask | 1 << bit);
+
+        let msg = match tile.variant(mask) {
+            Some(model) => TileEntityMsg::SetDataValue(key, Data::Id(*model)),
+            None => TileEntityMsg::RemoveData(key),
+        };
+
+        if let Err(err) = entity.send_message(msg) {
+            log::warn!("Could not tell the tile at {coord} its variant. Error: {err}");
+        }
+    }
+}
+
+/// Works out how many ticks of a stream at the rate happen in this base tick, keeping the part of a tick left over.
+fn due_ticks(rate: u64, remainder: &mut u64) -> u64 {
+    *remainder += rate;
+    let due = *remainder / TPS;
+    *remainder %= TPS;
+
+    due
+}
+
+/// Counts the ticks sent, updating the measured frequencies about once a second.
+fn measure_ticks(state: &mut GameSystemState, logic: u64, transfer: u64) {
+    let now = Instant::now();
+    let (start, logic_count, transfer_count) = state.tick_measurement.get_or_insert((now, 0, 0));
+
+    *logic_count += logic;
+    *transfer_count += transfer;
+
+    let elapsed = (now - *start).as_secs_f32();
+    if elapsed >= 1.0 {
+        state.tick_frequencies = (
+            *logic_count as Float / elapsed,
+            *transfer_count as Float / elapsed,
+        );
+        state.tick_measurement = Some((now, 0, 0));
+    }
+}
+
+/// Gathers the aggregates the game actor already keeps into a snapshot for the metrics server.
+///
+/// The produced items are worked out from the crafts of each script and the outputs of the script.
+fn metrics_snapshot(resource_man: &ResourceManager, state: &GameSystemState) -> MetricsSnapshot {
+    let resolve = |id: Id| {
+        resource_man
+            .interner
+            .resolve(id)
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    let mut categories = HashMap::<Option<Id>, u64>::new();
+    if let Some(map) = &state.map {
+        for (_, id) in map.tiles() {
+            let category = resource_man
+                .registry
+                .tiles
+                .get(&id)
+                .and_then(|tile| tile.category);
+
+            *categories.entry(category).or_insert(0) += 1;
+        }
+    }
+    let mut tiles_by_category = categories
+        .into_iter()
+        .map(|(category, count)| (category.map_or_else(|| "none".to_string(), resolve), count))
+        .collect::<Vec<_>>();
+    tiles_by_category.sort();
+
+    let mut produced = HashMap::<Id, u64>::new();
+    for (script, crafts) in &state.crafted {
+        let Some(script) = resource_man.registry.scripts.get(script) else {
+            continue;
+        };
+
+        for output in &script.instructions.outputs {
+            *produced.entry(output.id).or_insert(0) += crafts * output.amount.max(0) as u64;
         }
-    });
+    }
+    let mut produced = produced
+        .into_iter()
+        .map(|(id, amount)| (resolve(id), amount))
+        .collect::<Vec<_>>();
+    produced.sort_by(|(a_id, a), (b_id, b)| b.cmp(a).then_with(|| a_id.cmp(b_id)));
+    produced.truncate(metrics::TOP_PRODUCED);
+
+    MetricsSnapshot {
+        map_ticks: state.tick_count,
+        ticks_processed: state.ticks_processed,
+        logic_frequency: state.tick_frequencies.0,
+        transfer_frequency: state.tick_frequencies.1,
+        entity_count: state.tile_entities.len(),
+        tiles_by_category,
+        produced,
+        last_save_duration: state.last_save_duration,
+    }
+}
+
+/// Sends the ticks that are due to the tile entities, in coordinate order.
+/// Transfer ticks only go to the tiles whose scripts handle them.
+///
+/// Each tile entity is an actor of its own, so the tiles handle their ticks alongside each other, in no set order between them,
+/// and what one tile sends another can arrive before or after the other's tick. Only the ticks of each tile are handled in the order they were sent.
+/// Each base tick, every tile entity gets all of its logic ticks before any of its transfer ticks.
+/// How many of each are due only depends on the rates and the ticks before.
+///
+/// Each tick of a stream has its own count, going up by one per tick of that stream, which is what the scripts get.
+/// At the default rates, these are the same as the count of base ticks.
+fn inner_tick(resource_man: &ResourceManager, state: &mut GameSystemState) {
+    let logic = due_ticks(state.tick_rates.logic, &mut state.tick_remainders.0);
+    let transfer = due_ticks(state.tick_rates.transfer, &mut state.tick_remainders.1);
+
+    let mut tile_entities = state.tile_entities.iter().collect::<Vec<_>>();
+    tile_entities.sort_unstable_by_key(|(coord, _)| **coord);
+
+    for _ in 0..logic {
+        tile_entities.iter().for_each(|(_, tile_entity)| {
+            if let Err(e) = tile_entity.send_message(TileEntityMsg::Tick {
+                tick_count: state.tick_count,
+                stream_tick: state.stream_ticks.0,
+                free_inputs: state.sandbox.has_free_inputs(),
+            }) {
+                log::error!("{e:?}");
+            }
+        });
+
+        state.stream_ticks.0 = state.stream_ticks.0.wrapping_add(1);
+    }
+
+    if transfer > 0 {
+        tile_entities.retain(|(coord, _)| {
+            state
+                .map
+                .as_ref()
+                .and_then(|map| map.get_tile(**coord))
+                .and_then(|id| resource_man.registry.tiles.get(&id))
+                .and_then(|tile| tile.function.as_ref())
+                .and_then(|function| resource_man.functions.get(function))
+                .is_some_and(|(_, metadata)| metadata.handles_transfer_tick)
+        });
+    }
+
+    for _ in 0..transfer {
+        tile_entities.iter().for_each(|(_, tile_entity)| {
+            if let Err(e) = tile_entity.send_message(TileEntityMsg::TransferTick {
+                tick_count: state.tick_count,
+                stream_tick: state.stream_ticks.1,
+            }) {
+                log::error!("{e:?}");
+            }
+        });
+
+        state.stream_ticks.1 = state.stream_ticks.1.wrapping_add(1);
+    }
+
+    measure_ticks(state, logic, transfer);
 
     state.tick_count = state.tick_count.wrapping_add(1);
+    state.ticks_processed += 1;
 }
 
 /// Runs the game for one tick, logging if the tick is too long.
-pub fn tick(state: &mut GameSystemState) {
+pub fn tick(resource_man: &ResourceManager, state: &mut GameSystemState) {
     let start = Instant::now();
-    inner_tick(state);
+    inner_tick(resource_man, state);
     let finish = Instant::now();
 
     let tick_time = finish - start;
@@ -853,6 +2288,9 @@ pub fn copy_auxiliary_data(resource_man: &ResourceManager, data: &mut DataMap) -
     if let Some(v) = data.remove(resource_man.registry.data_ids.item) {
         copied.set(resource_man.registry.data_ids.item, v);
     }
+    if let Some(v) = data.remove(resource_man.registry.data_ids.protected) {
+        copied.set(resource_man.registry.data_ids.protected, v);
+    }
 
     copied
 }
@@ -0,0 +1,141 @@
+use crate::input::{get_default_keymap, ActionType, GameInputEvent, KeyAction, PressType};
+use automancy_resources::ResourceManager;
+use gilrs::{Button, Event, EventType, Gilrs};
+use winit::event::ElementState;
+
+/// The button bindings of the gamepad. An action can be bound to buttons here as well as to keys in the key map.
+pub type GamepadMap = Vec<(Button, KeyAction)>;
+
+/// The button that places tiles and clicks, like the main mouse button does.
+pub const PLACE_BUTTON: Button = Button::South;
+
+/// Gets the default gamepad bindings. The actions that also have keys take their press type and name from the default key map.
+pub fn get_default_gamepad_map(resource_man: &ResourceManager) -> GamepadMap {
+    let default = get_default_keymap(resource_man);
+
+    let cursor = |action| KeyAction {
+        action,
+        press_type: PressType::Tap,
+        name: Some(resource_man.registry.key_ids.move_cursor),
+    };
+
+    [
+        (Button::East, ActionType::Cancel),
+        (Button::Start, ActionType::Cancel),
+        (Button::West, ActionType::Delete),
+        (Button::North, ActionType::MenuConfirm),
+        (Button::Select, ActionType::Player),
+        (Button::LeftTrigger, ActionType::PreviousCategory),
+        (Button::RightTrigger, ActionType::NextCategory),
+        (Button::LeftTrigger2, ActionType::SelectMode),
+        (Button::RightTrigger2, ActionType::PickTile),
+    ]
+    .into_iter()
+    .flat_map(|(button, action)| {
+        default
+            .iter()
+            .find(|(_, v)| v.action == action)
+            .map(|(_, v)| (button, *v))
+    })
+    .chain([
+        (Button::DPadUp, cursor(ActionType::CursorUp)),
+        (Button::DPadDown, cursor(ActionType::CursorDown)),
+        (Button::DPadLeft, cursor(ActionType::CursorLeft)),
+        (Button::DPadRight, cursor(ActionType::CursorRight)),
+    ])
+    .collect()
+}
+
+/// Gets the name of the button as it is shown to the player, after the layout of the common controllers.
+pub fn button_name(button: Button) -> &'static str {
+    match button {
+        Button::South => "A",
+        Button::East => "B",
+        Button::West => "X",
+        Button::North => "Y",
+        Button::LeftTrigger => "LB",
+        Button::RightTrigger => "RB",
+        Button::LeftTrigger2 => "LT",
+        Button::RightTrigger2 => "RT",
+        Button::Select => "Back",
+        Button::Start => "Start",
+        Button::Mode => "Guide",
+        Button::LeftThumb => "LS",
+        Button::RightThumb => "RS",
+        Button::DPadUp => "D-Pad Up",
+        Button::DPadDown => "D-Pad Down",
+        Button::DPadLeft => "D-Pad Left",
+        Button::DPadRight => "D-Pad Right",
+        _ => "<?>",
+    }
+}
+
+/// The connected gamepads, if the platform supports them.
+pub struct Gamepads {
+    gilrs: Option<Gilrs>,
+}
+
+impl Default for Gamepads {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Gamepads {
+    pub fn new() -> Self {
+        let gilrs = Gilrs::new()
+            .inspect_err(|err| {
+                log::warn!("Could not set up gamepad input! Controllers won't work. Error: {err}")
+            })
+            .ok();
+
+        Self { gilrs }
+    }
+
+    /// Takes the events of the gamepads since the last poll, as input events.
+    ///
+    /// Gamepads can be plugged in and out at any time. Unplugging one releases whatever it was holding.
+    pub fn poll(&mut self) -> Vec<GameInputEvent> {
+        let Some(gilrs) = &mut self.gilrs else {
+            return vec![];
+        };
+
+        let mut events = vec![];
+
+        while let Some(Event { id, event, .. }) = gilrs.next_event() {
+            match event {
+                EventType::ButtonPressed(button, _) => {
+                    events.push(GameInputEvent::GamepadButton {
+                        button,
+                        state: ElementState::Pressed,
+                    });
+                }
+                EventType::ButtonReleased(button, _) => {
+                    events.push(GameInputEvent::GamepadButton {
+                        button,
+                        state: ElementState::Released,
+                    });
+                }
+                EventType::AxisChanged(axis, value, _) => {
+                    events.push(GameInputEvent::GamepadAxis { axis, value });
+                }
+                EventType::Connected => {
+                    let name = gilrs
+                        .connected_gamepad(id)
+                        .map(|gamepad| gamepad.name().to_string())
+                        .unwrap_or_default();
+
+                    log::info!("Gamepad connected: {name}");
+                }
+                EventType::Disconnected => {
+                    log::info!("Gamepad disconnected");
+
+                    events.push(GameInputEvent::GamepadDisconnected);
+                }
+                _ => {}
+            }
+        }
+
+        events
+    }
+}
@@ -0,0 +1,42 @@
+use automancy_defs::id::TileId;
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::ResourceManager;
+
+/// The number of slots on the hotbar, bound to the number keys from 1 to 0.
+pub const HOTBAR_SIZE: usize = 10;
+
+pub type Hotbar = [Option<TileId>; HOTBAR_SIZE];
+
+/// Reads the hotbar stored in the map data, where empty slots are kept as the none tile.
+pub fn read_hotbar(resource_man: &ResourceManager, game_data: &DataMap) -> Hotbar {
+    let mut hotbar = [None; HOTBAR_SIZE];
+
+    if let Some(Data::VecId(slots)) = game_data.get(resource_man.registry.data_ids.hotbar) {
+        for (slot, id) in hotbar.iter_mut().zip(slots) {
+            if resource_man.registry.tiles.contains_key(&TileId(*id))
+                && *id != resource_man.registry.none
+            {
+                *slot = Some(TileId(*id));
+            }
+        }
+    }
+
+    hotbar
+}
+
+/// Writes the hotbar into the map data.
+pub fn write_hotbar(resource_man: &ResourceManager, game_data: &mut DataMap, hotbar: &Hotbar) {
+    if hotbar.iter().all(Option::is_none) {
+        game_data.remove(resource_man.registry.data_ids.hotbar);
+    } else {
+        game_data.set(
+            resource_man.registry.data_ids.hotbar,
+            Data::VecId(
+                hotbar
+                    .iter()
+                    .map(|slot| slot.map_or(resource_man.registry.none, |id| *id))
+                    .collect(),
+            ),
+        );
+    }
+}
@@ -1,3 +1,6 @@
+use crate::camera::BOOKMARK_SLOTS;
+use crate::gamepad::{GamepadMap, PLACE_BUTTON};
+use crate::hotbar::HOTBAR_SIZE;
 use crate::options::GameOptions;
 use automancy_defs::id::Id;
 use automancy_defs::{
@@ -5,23 +8,31 @@ use automancy_defs::{
     math::{Float, Vec2},
 };
 use automancy_resources::ResourceManager;
+use gilrs::{Axis, Button};
 use hashbrown::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
 use std::{cell::Cell, mem};
 use winit::event::{
     DeviceEvent, ElementState, KeyEvent, Modifiers, MouseButton, MouseScrollDelta, WindowEvent,
 };
-use winit::keyboard::{Key, NamedKey, SmolStr};
+use winit::keyboard::{Key, ModifiersState, NamedKey, SmolStr};
 use winit::{
     event::ElementState::{Pressed, Released},
     platform::modifier_supplement::KeyEventExtModifierSupplement,
 };
 
+pub type KeyMap = Vec<(KeyBinding, KeyAction)>;
+
+/// How many of the tiles shown in the tile selection can be picked with a key, from the first one on.
+pub const TILE_SELECTION_KEYS: usize = 9;
+
 thread_local! {
-    static DEFAULT_KEYMAP: Cell<Option<HashMap<Key, KeyAction>>> = Cell::default();
+    static DEFAULT_KEYMAP: Cell<Option<KeyMap>> = Cell::default();
 }
 
-pub fn get_default_keymap(resource_man: &ResourceManager) -> HashMap<Key, KeyAction> {
+pub fn get_default_keymap(resource_man: &ResourceManager) -> KeyMap {
     let taken = DEFAULT_KEYMAP.take();
 
     if let Some(taken) = taken {
@@ -66,6 +77,21 @@ fn set_default_keymap(resource_man: &ResourceManager) {
         press_type: PressType::Tap,
         name: None,
     };
+    let save_screenshot: KeyAction = KeyAction {
+        action: ActionType::SaveScreenshot,
+        press_type: PressType::Tap,
+        name: None,
+    };
+    let save_screenshot_without_ui: KeyAction = KeyAction {
+        action: ActionType::SaveScreenshotWithoutUi,
+        press_type: PressType::Tap,
+        name: None,
+    };
+    let reload_resources: KeyAction = KeyAction {
+        action: ActionType::ReloadResources,
+        press_type: PressType::Tap,
+        name: None,
+    };
     let toggle_gui: KeyAction = KeyAction {
         action: ActionType::ToggleGui,
         press_type: PressType::Toggle,
@@ -106,23 +132,334 @@ fn set_default_keymap(resource_man: &ResourceManager) {
         press_type: PressType::Tap,
         name: Some(resource_man.registry.key_ids.paste),
     };
+    let cycle_overlay: KeyAction = KeyAction {
+        action: ActionType::CycleOverlay,
+        press_type: PressType::Tap,
+        name: Some(resource_man.registry.key_ids.cycle_overlay),
+    };
+    let open_config: KeyAction = KeyAction {
+        action: ActionType::OpenConfig,
+        press_type: PressType::Tap,
+        name: Some(resource_man.registry.key_ids.open_config),
+    };
 
-    DEFAULT_KEYMAP.set(Some(HashMap::from_iter([
-        (Key::Character(SmolStr::new_inline("z")), undo),
-        (Key::Character(SmolStr::new_inline("r")), redo),
-        (Key::Character(SmolStr::new_inline("e")), player),
-        (Key::Character(SmolStr::new_inline("x")), cut),
-        (Key::Character(SmolStr::new_inline("c")), copy),
-        (Key::Character(SmolStr::new_inline("v")), paste),
-        (Key::Named(NamedKey::Escape), cancel),
-        (Key::Named(NamedKey::F1), toggle_gui),
-        (Key::Named(NamedKey::F2), screenshot),
-        (Key::Named(NamedKey::F3), debug),
-        (Key::Named(NamedKey::F11), fullscreen),
-        (Key::Named(NamedKey::Backspace), delete),
-        (Key::Named(NamedKey::Shift), select_mode),
-        (Key::Named(NamedKey::Control), hotkey),
-    ])));
+    let regions: KeyAction = KeyAction {
+        action: ActionType::Regions,
+        press_type: PressType::Tap,
+        name: Some(resource_man.registry.key_ids.regions),
+    };
+    let camera_path: KeyAction = KeyAction {
+        action: ActionType::CameraPath,
+        press_type: PressType::Tap,
+        name: Some(resource_man.registry.key_ids.camera_path),
+    };
+    let record_waypoint: KeyAction = KeyAction {
+        action: ActionType::RecordWaypoint,
+        press_type: PressType::Tap,
+        name: Some(resource_man.registry.key_ids.record_waypoint),
+    };
+    let flow_graph: KeyAction = KeyAction {
+        action: ActionType::FlowGraph,
+        press_type: PressType::Tap,
+        name: Some(resource_man.registry.key_ids.flow_graph),
+    };
+    let bookmarks: KeyAction = KeyAction {
+        action: ActionType::Bookmarks,
+        press_type: PressType::Tap,
+        name: Some(resource_man.registry.key_ids.bookmarks),
+    };
+    let production_stats: KeyAction = KeyAction {
+        action: ActionType::ProductionStats,
+        press_type: PressType::Tap,
+        name: Some(resource_man.registry.key_ids.production_stats),
+    };
+    let recipes: KeyAction = KeyAction {
+        action: ActionType::Recipes,
+        press_type: PressType::Tap,
+        name: Some(resource_man.registry.key_ids.recipes),
+    };
+    let inventory: KeyAction = KeyAction {
+        action: ActionType::Inventory,
+        press_type: PressType::Tap,
+        name: Some(resource_man.registry.key_ids.inventory),
+    };
+    let skip_track: KeyAction = KeyAction {
+        action: ActionType::SkipTrack,
+        press_type: PressType::Tap,
+        name: Some(resource_man.registry.key_ids.skip_track),
+    };
+    let minimap: KeyAction = KeyAction {
+        action: ActionType::Minimap,
+        press_type: PressType::Toggle,
+        name: Some(resource_man.registry.key_ids.minimap),
+    };
+    let pause_ticks: KeyAction = KeyAction {
+        action: ActionType::PauseTicks,
+        press_type: PressType::Tap,
+        name: Some(resource_man.registry.key_ids.pause_ticks),
+    };
+    let step_tick: KeyAction = KeyAction {
+        action: ActionType::StepTick,
+        press_type: PressType::Tap,
+        name: Some(resource_man.registry.key_ids.step_tick),
+    };
+    let slow_down: KeyAction = KeyAction {
+        action: ActionType::SlowDown,
+        press_type: PressType::Tap,
+        name: Some(resource_man.registry.key_ids.slow_down),
+    };
+    let speed_up: KeyAction = KeyAction {
+        action: ActionType::SpeedUp,
+        press_type: PressType::Tap,
+        name: Some(resource_man.registry.key_ids.speed_up),
+    };
+    let pick_tile: KeyAction = KeyAction {
+        action: ActionType::PickTile,
+        press_type: PressType::Tap,
+        name: Some(resource_man.registry.key_ids.pick_tile),
+    };
+    let rotate_tile: KeyAction = KeyAction {
+        action: ActionType::RotateTile,
+        press_type: PressType::Tap,
+        name: Some(resource_man.registry.key_ids.rotate_tile),
+    };
+    let menu_up: KeyAction = KeyAction {
+        action: ActionType::MenuUp,
+        press_type: PressType::Tap,
+        name: Some(resource_man.registry.key_ids.menu_up),
+    };
+    let menu_down: KeyAction = KeyAction {
+        action: ActionType::MenuDown,
+        press_type: PressType::Tap,
+        name: Some(resource_man.registry.key_ids.menu_down),
+    };
+    let menu_confirm: KeyAction = KeyAction {
+        action: ActionType::MenuConfirm,
+        press_type: PressType::Tap,
+        name: Some(resource_man.registry.key_ids.menu_confirm),
+    };
+
+    let rotate_left: KeyAction = KeyAction {
+        action: ActionType::RotateLeft,
+        press_type: PressType::Hold,
+        name: Some(resource_man.registry.key_ids.rotate_left),
+    };
+    let rotate_right: KeyAction = KeyAction {
+        action: ActionType::RotateRight,
+        press_type: PressType::Hold,
+        name: Some(resource_man.registry.key_ids.rotate_right),
+    };
+
+    let previous_category: KeyAction = KeyAction {
+        action: ActionType::PreviousCategory,
+        press_type: PressType::Tap,
+        name: Some(resource_man.registry.key_ids.previous_category),
+    };
+    let next_category: KeyAction = KeyAction {
+        action: ActionType::NextCategory,
+        press_type: PressType::Tap,
+        name: Some(resource_man.registry.key_ids.next_category),
+    };
+
+    let hotbar = (0..HOTBAR_SIZE as u8).map(|slot| {
+        (
+            // the last slot is on the 0 key
+            Key::Character(SmolStr::new(((slot + 1) % 10).to_string())),
+            KeyAction {
+                action: ActionType::Hotbar(slot),
+                press_type: PressType::Tap,
+                name: Some(resource_man.registry.key_ids.hotbar_slot),
+            },
+        )
+    });
+
+    // with Alt held, so that the number keys stay on the hotbar
+    let select_tile = (0..TILE_SELECTION_KEYS as u8).map(|index| {
+        (
+            KeyBinding::new(Key::Character(SmolStr::new((index + 1).to_string())))
+                .with(Modifier::Alt),
+            KeyAction {
+                action: ActionType::SelectTile(index),
+                press_type: PressType::Tap,
+                name: Some(resource_man.registry.key_ids.select_tile),
+            },
+        )
+    });
+
+    // with Ctrl or Shift held, so that the number keys stay on the hotbar
+    let bookmark_keys = (0..BOOKMARK_SLOTS as u8).flat_map(|slot| {
+        let key = Key::Character(SmolStr::new((slot + 1).to_string()));
+
+        [
+            (
+                KeyBinding::new(key.clone()).with(Modifier::Control),
+                KeyAction {
+                    action: ActionType::SetBookmark(slot),
+                    press_type: PressType::Tap,
+                    name: Some(resource_man.registry.key_ids.set_bookmark),
+                },
+            ),
+            (
+                KeyBinding::new(key).with(Modifier::Shift),
+                KeyAction {
+                    action: ActionType::GoToBookmark(slot),
+                    press_type: PressType::Tap,
+                    name: Some(resource_man.registry.key_ids.go_to_bookmark),
+                },
+            ),
+        ]
+    });
+
+    DEFAULT_KEYMAP.set(Some(
+        [
+            (Key::Character(SmolStr::new_inline("z")), undo),
+            (Key::Character(SmolStr::new_inline("y")), redo),
+            (Key::Character(SmolStr::new_inline("e")), player),
+            (Key::Character(SmolStr::new_inline("x")), cut),
+            (Key::Character(SmolStr::new_inline("c")), copy),
+            (Key::Character(SmolStr::new_inline("v")), paste),
+            (Key::Character(SmolStr::new_inline("o")), cycle_overlay),
+            (Key::Character(SmolStr::new_inline("f")), open_config),
+            (Key::Character(SmolStr::new_inline("g")), regions),
+            (Key::Character(SmolStr::new_inline("p")), camera_path),
+            (Key::Character(SmolStr::new_inline("k")), record_waypoint),
+            (Key::Character(SmolStr::new_inline("b")), flow_graph),
+            (Key::Character(SmolStr::new_inline("t")), production_stats),
+            (Key::Character(SmolStr::new_inline("r")), recipes),
+            (Key::Character(SmolStr::new_inline("i")), inventory),
+            (Key::Character(SmolStr::new_inline("m")), minimap),
+            (Key::Character(SmolStr::new_inline("n")), bookmarks),
+            (Key::Character(SmolStr::new_inline("q")), pick_tile),
+            (Key::Character(SmolStr::new_inline("h")), rotate_tile),
+            (Key::Character(SmolStr::new_inline(",")), rotate_left),
+            (Key::Character(SmolStr::new_inline(".")), rotate_right),
+            (Key::Character(SmolStr::new_inline("[")), slow_down),
+            (Key::Character(SmolStr::new_inline("]")), speed_up),
+            (Key::Named(NamedKey::Escape), cancel),
+            (Key::Named(NamedKey::ArrowUp), menu_up),
+            (Key::Named(NamedKey::ArrowDown), menu_down),
+            (Key::Named(NamedKey::Enter), menu_confirm),
+            (Key::Named(NamedKey::ArrowLeft), previous_category),
+            (Key::Named(NamedKey::ArrowRight), next_category),
+            (Key::Named(NamedKey::F1), toggle_gui),
+            (Key::Named(NamedKey::F2), screenshot),
+            (Key::Named(NamedKey::F3), debug),
+            (Key::Named(NamedKey::F5), reload_resources),
+            (Key::Named(NamedKey::F8), skip_track),
+            (Key::Named(NamedKey::MediaTrackNext), skip_track),
+            (Key::Named(NamedKey::F11), fullscreen),
+            (Key::Named(NamedKey::F12), save_screenshot),
+            (Key::Named(NamedKey::Pause), pause_ticks),
+            (Key::Named(NamedKey::Backspace), delete),
+            (Key::Named(NamedKey::Delete), delete),
+            (Key::Named(NamedKey::Shift), select_mode),
+            (Key::Named(NamedKey::Control), hotkey),
+        ]
+        .into_iter()
+        .chain(hotbar)
+        .map(|(key, action)| (KeyBinding::new(key), action))
+        .chain(select_tile)
+        .chain(bookmark_keys)
+        // with Control held, as the period alone turns the camera
+        .chain([
+            (
+                KeyBinding::new(Key::Character(SmolStr::new_inline("."))).with(Modifier::Control),
+                step_tick,
+            ),
+            (
+                KeyBinding::new(Key::Named(NamedKey::F12)).with(Modifier::Shift),
+                save_screenshot_without_ui,
+            ),
+        ])
+        .collect(),
+    ));
+}
+
+/// A modifier key that can be held as part of a [KeyBinding].
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum Modifier {
+    Shift,
+    Control,
+    Alt,
+    Super,
+}
+
+impl Modifier {
+    /// Gets the modifiers held in the state.
+    pub fn held(state: ModifiersState) -> BTreeSet<Modifier> {
+        [
+            (Modifier::Shift, state.shift_key()),
+            (Modifier::Control, state.control_key()),
+            (Modifier::Alt, state.alt_key()),
+            (Modifier::Super, state.super_key()),
+        ]
+        .into_iter()
+        .filter(|(_, held)| *held)
+        .map(|(modifier, _)| modifier)
+        .collect()
+    }
+}
+
+/// A key combination bound to an action: the primary key, pressed while all the modifiers are held.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct KeyBinding {
+    pub key: Key,
+    #[serde(default)]
+    pub modifiers: BTreeSet<Modifier>,
+}
+
+impl KeyBinding {
+    pub fn new(key: Key) -> Self {
+        Self {
+            key,
+            modifiers: Default::default(),
+        }
+    }
+
+    pub fn with(mut self, modifier: Modifier) -> Self {
+        self.modifiers.insert(modifier);
+
+        self
+    }
+
+    /// Returns whether pressing the key while holding the modifiers satisfies the binding.
+    pub fn matches(&self, key: &Key, held: &BTreeSet<Modifier>) -> bool {
+        self.key == *key && self.modifiers.is_subset(held)
+    }
+}
+
+/// Finds the binding that pressing the key while holding the modifiers triggers.
+///
+/// When several are satisfied, the most specific one wins, which is the one with the most modifiers,
+/// so that Ctrl+Z does not also trigger what plain Z is bound to. Among equally specific ones, the first listed wins.
+pub fn match_binding<'a>(
+    key_map: &'a [(KeyBinding, KeyAction)],
+    key: &Key,
+    held: &BTreeSet<Modifier>,
+) -> Option<&'a (KeyBinding, KeyAction)> {
+    key_map
+        .iter()
+        .filter(|(binding, _)| binding.matches(key, held))
+        .min_by_key(|(binding, _)| Reverse(binding.modifiers.len()))
+}
+
+/// Finds a binding that is bound to more than one action, along with the two actions.
+///
+/// Bindings with the same key but different modifiers do not conflict, as the most specific one wins.
+pub fn find_conflict(
+    key_map: &[(KeyBinding, KeyAction)],
+) -> Option<(&KeyBinding, ActionType, ActionType)> {
+    key_map
+        .iter()
+        .enumerate()
+        .find_map(|(i, (binding, action))| {
+            key_map[i + 1..]
+                .iter()
+                .find(|(other, other_action)| {
+                    other == binding && other_action.action != action.action
+                })
+                .map(|(_, other_action)| (binding, action.action, other_action.action))
+        })
 }
 
 #[derive(Serialize, Deserialize, Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq, Hash)]
@@ -133,6 +470,12 @@ pub enum ActionType {
     Debug,
     Fullscreen,
     Screenshot,
+    /// Saves a screenshot into the screenshots folder.
+    SaveScreenshot,
+    /// Saves a screenshot into the screenshots folder, without the UI drawn over the world.
+    SaveScreenshotWithoutUi,
+    /// Loads the resource packs again, without leaving the map.
+    ReloadResources,
     ToggleGui,
     Player,
     Delete,
@@ -141,6 +484,75 @@ pub enum ActionType {
     Cut,
     Copy,
     Paste,
+    CycleOverlay,
+    OpenConfig,
+    Regions,
+    CameraPath,
+    RecordWaypoint,
+    /// Opens or closes the graph of how items flow between the machines.
+    FlowGraph,
+    /// Opens or closes the production statistics of the map.
+    ProductionStats,
+    /// Opens or closes the recipe browser.
+    Recipes,
+    /// Opens or closes the player inventory window.
+    Inventory,
+    /// Fades the music that is playing into the next one.
+    SkipTrack,
+    /// Shows or hides the minimap.
+    Minimap,
+    /// Opens or closes the list of the camera bookmarks.
+    Bookmarks,
+    /// Stores the camera position in the bookmark slot, by index.
+    SetBookmark(u8),
+    /// Pans the camera to the position stored in the bookmark slot, by index.
+    GoToBookmark(u8),
+    /// Pauses or resumes ticking the tiles.
+    PauseTicks,
+    /// Runs a single tick of the tiles, and pauses them after.
+    StepTick,
+    /// Runs the game at the next slower speed.
+    SlowDown,
+    /// Runs the game at the next faster speed.
+    SpeedUp,
+    /// Selects the pointed-at tile for placing, along with its config.
+    PickTile,
+    /// Turns the selected tile clockwise, for the next placements.
+    RotateTile,
+    /// Moves up in the open context menu.
+    MenuUp,
+    /// Moves down in the open context menu.
+    MenuDown,
+    /// Chooses the highlighted entry of the open context menu.
+    MenuConfirm,
+    /// Places the tile in the hotbar slot, by index.
+    Hotbar(u8),
+    /// Picks the tile shown in the tile selection, by index.
+    SelectTile(u8),
+    /// Shows the category before the current one in the tile selection.
+    PreviousCategory,
+    /// Shows the category after the current one in the tile selection.
+    NextCategory,
+    /// Turns the camera to the left while held.
+    RotateLeft,
+    /// Turns the camera to the right while held.
+    RotateRight,
+    /// Moves the pointing cursor to the tile above.
+    CursorUp,
+    /// Moves the pointing cursor to the tile below.
+    CursorDown,
+    /// Moves the pointing cursor to the tile on the left.
+    CursorLeft,
+    /// Moves the pointing cursor to the tile on the right.
+    CursorRight,
+}
+
+/// The kind of device the player last used.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum InputDevice {
+    #[default]
+    KeyboardMouse,
+    Gamepad,
 }
 
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Serialize, Deserialize)]
@@ -175,6 +587,9 @@ pub enum GameInputEvent {
     ExitReleased,
     ModifierChanged { modifier: Modifiers },
     KeyboardEvent { event: KeyEvent },
+    GamepadButton { button: Button, state: ElementState },
+    GamepadAxis { axis: Axis, value: Float },
+    GamepadDisconnected,
 }
 
 pub fn convert_input(
@@ -281,12 +696,39 @@ pub struct InputHandler {
     pub alternate_pressed: bool,
     pub tertiary_pressed: bool,
 
-    pub key_map: HashMap<Key, KeyAction>,
+    /// The key bindings, where the first one listed for an action is its primary binding.
+    pub key_map: KeyMap,
+    pub gamepad_map: GamepadMap,
     pub key_states: HashSet<ActionType>,
 
+    /// Where the left stick of the gamepad is pushed, which pans the camera.
+    pub left_stick: Vec2,
+    /// Where the right stick of the gamepad is pushed, which moves the pointing cursor.
+    pub right_stick: Vec2,
+    pub last_device: InputDevice,
+
+    /// The modifiers currently held.
+    modifiers: BTreeSet<Modifier>,
+    /// The action each held key triggered when it was pressed, so that releasing it releases the same action.
+    pressed_keys: HashMap<Key, KeyAction>,
+
+    /// Whether the next key pressed is captured as a binding, instead of triggering its action.
+    capturing: bool,
+    /// The modifier key pressed while capturing, which is captured by itself if it is released before any other key is pressed.
+    capturing_modifier: Option<Key>,
+    captured: Option<KeyBinding>,
+
     to_clear: Vec<KeyAction>,
 }
 
+/// Whether the key is one of the keys that are held as a [Modifier].
+fn is_modifier_key(key: &Key) -> bool {
+    matches!(
+        key,
+        Key::Named(NamedKey::Shift | NamedKey::Control | NamedKey::Alt | NamedKey::Super)
+    )
+}
+
 impl InputHandler {
     pub fn new(options: &GameOptions) -> Self {
         Self {
@@ -302,13 +744,73 @@ impl InputHandler {
             alternate_pressed: false,
             tertiary_pressed: false,
 
-            key_map: options.keymap.clone(),
+            key_map: options.key_bindings.clone(),
+            gamepad_map: options.gamepad_bindings.clone(),
             key_states: Default::default(),
 
+            left_stick: Vec2::ZERO,
+            right_stick: Vec2::ZERO,
+            last_device: Default::default(),
+
+            modifiers: Default::default(),
+            pressed_keys: Default::default(),
+
+            capturing: false,
+            capturing_modifier: None,
+            captured: None,
+
             to_clear: Default::default(),
         }
     }
 
+    /// Captures the next key pressed, along with the modifiers held, as a binding. Escape stops capturing without one.
+    pub fn start_capture(&mut self) {
+        self.clear_held();
+
+        self.capturing = true;
+        self.capturing_modifier = None;
+        self.captured = None;
+    }
+
+    pub fn stop_capture(&mut self) {
+        self.capturing = false;
+        self.capturing_modifier = None;
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.capturing
+    }
+
+    /// Takes the binding captured since capturing started, if a key has been pressed.
+    pub fn take_captured(&mut self) -> Option<KeyBinding> {
+        self.captured.take()
+    }
+
+    fn capture_key(&mut self, state: ElementState, key: Key) {
+        match state {
+            Pressed => {
+                if key == Key::Named(NamedKey::Escape) {
+                    self.stop_capture();
+                } else if is_modifier_key(&key) {
+                    // waits to see if it is held for another key
+                    self.capturing_modifier = Some(key);
+                } else {
+                    let mut binding = KeyBinding::new(key);
+                    binding.modifiers = self.modifiers.clone();
+
+                    self.captured = Some(binding);
+                    self.stop_capture();
+                }
+            }
+            Released => {
+                if self.capturing_modifier.as_ref() == Some(&key) {
+                    self.captured = Some(KeyBinding::new(key));
+                    self.stop_capture();
+                }
+            }
+        }
+    }
+
     pub fn reset(&mut self) {
         self.main_pressed = false;
         self.alternate_pressed = false;
@@ -323,6 +825,19 @@ impl InputHandler {
     }
 
     pub fn update(&mut self, event: GameInputEvent) {
+        // the input hints show the bindings of the device used last
+        match event {
+            GameInputEvent::None => {}
+            GameInputEvent::GamepadButton { .. }
+            | GameInputEvent::GamepadAxis { .. }
+            | GameInputEvent::GamepadDisconnected => {
+                self.last_device = InputDevice::Gamepad;
+            }
+            _ => {
+                self.last_device = InputDevice::KeyboardMouse;
+            }
+        }
+
         match event {
             GameInputEvent::MainPos { pos } => {
                 self.main_pos = pos;
@@ -354,16 +869,82 @@ impl InputHandler {
             GameInputEvent::TertiaryReleased => {
                 self.tertiary_held = false;
             }
+            GameInputEvent::ModifierChanged { modifier } => {
+                self.modifiers = Modifier::held(modifier.state());
+            }
             GameInputEvent::KeyboardEvent { event } => {
                 self.handle_key(event.state, event.key_without_modifiers());
             }
+            GameInputEvent::GamepadButton { button, state } => {
+                self.handle_button(state, button);
+            }
+            GameInputEvent::GamepadAxis { axis, value } => match axis {
+                Axis::LeftStickX => self.left_stick.x = value,
+                Axis::LeftStickY => self.left_stick.y = value,
+                Axis::RightStickX => self.right_stick.x = value,
+                Axis::RightStickY => self.right_stick.y = value,
+                _ => {}
+            },
+            GameInputEvent::GamepadDisconnected => {
+                self.clear_held();
+
+                self.left_stick = Vec2::ZERO;
+                self.right_stick = Vec2::ZERO;
+            }
             _ => {}
         }
     }
 
     pub fn handle_key(&mut self, state: ElementState, key: Key) -> Option<()> {
-        let action = *self.key_map.get(&key)?;
+        if self.capturing {
+            self.capture_key(state, key);
+
+            return Some(());
+        }
+
+        let action = match state {
+            Pressed => {
+                let action = match_binding(&self.key_map, &key, &self.modifiers)?.1;
+                self.pressed_keys.insert(key, action);
+
+                action
+            }
+            Released => self.pressed_keys.remove(&key)?,
+        };
+
+        self.handle_action(state, action);
+
+        Some(())
+    }
+
+    /// Presses or releases the action bound to the gamepad button. The place button acts as the main mouse button.
+    fn handle_button(&mut self, state: ElementState, button: Button) -> Option<()> {
+        if button == PLACE_BUTTON {
+            match state {
+                Pressed => {
+                    self.main_pressed = true;
+                    self.main_held = true;
+                }
+                Released => {
+                    self.main_held = false;
+                }
+            }
+
+            return Some(());
+        }
+
+        let action = self
+            .gamepad_map
+            .iter()
+            .find(|(bound, _)| *bound == button)?
+            .1;
+
+        self.handle_action(state, action);
 
+        Some(())
+    }
+
+    fn handle_action(&mut self, state: ElementState, action: KeyAction) {
         match action.press_type {
             PressType::Tap => match state {
                 Pressed => {
@@ -393,11 +974,58 @@ impl InputHandler {
                 Released => {}
             },
         }
+    }
 
-        Some(())
+    /// Moves the pointing cursor along with the right stick, keeping it in the window.
+    pub fn move_cursor(&mut self, elapsed: Float, (width, height): (Float, Float)) {
+        /// How far the cursor moves in a second, in pixels, with the stick pushed all the way.
+        const CURSOR_SPEED: Float = 800.0;
+
+        if self.right_stick == Vec2::ZERO {
+            return;
+        }
+
+        // the stick is up when positive, while the screen goes down
+        let delta = vec2(self.right_stick.x, -self.right_stick.y) * CURSOR_SPEED * elapsed;
+
+        self.main_pos = (self.main_pos + delta).clamp(Vec2::ZERO, vec2(width, height));
+    }
+
+    pub fn modifier_held(&self, modifier: Modifier) -> bool {
+        self.modifiers.contains(&modifier)
     }
 
     pub fn key_active(&self, action: ActionType) -> bool {
         self.key_states.contains(&action)
     }
+
+    /// Returns whether the action is active, and deactivates it so that nothing else handles it in the same frame.
+    pub fn consume(&mut self, action: ActionType) -> bool {
+        self.key_states.remove(&action)
+    }
+
+    /// Forgets every held button and key, so that presses from before are not picked up again.
+    ///
+    /// Toggled keys are kept, as they do not depend on the key being held.
+    pub fn clear_held(&mut self) {
+        self.main_held = false;
+        self.alternate_held = false;
+        self.tertiary_held = false;
+
+        self.main_move = None;
+        self.scroll = None;
+
+        for action in self
+            .key_map
+            .iter()
+            .map(|(_, action)| action)
+            .chain(self.gamepad_map.iter().map(|(_, action)| action))
+        {
+            if action.press_type != PressType::Toggle {
+                self.key_states.remove(&action.action);
+            }
+        }
+        self.pressed_keys.clear();
+        self.to_clear.clear();
+    }
 }
@@ -0,0 +1,89 @@
+use crate::input::ActionType;
+use std::mem;
+
+/// The priority of the hints that don't ask for one.
+pub const DEFAULT_HINT_PRIORITY: i32 = 0;
+
+/// The priority of the hints of the open screens, which are shown before the world's.
+pub const SCREEN_HINT_PRIORITY: i32 = 1;
+
+/// A hint shown in the info window: the name of the last action, with the keys of all of them pressed together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionHint {
+    pub actions: Vec<ActionType>,
+    /// the hints with a higher priority are shown first
+    pub priority: i32,
+}
+
+impl ActionHint {
+    pub fn new(actions: Vec<ActionType>) -> Self {
+        Self {
+            actions,
+            priority: DEFAULT_HINT_PRIORITY,
+        }
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+
+        self
+    }
+}
+
+/// Adds the hint, unless one for the same actions is already there, which then keeps the higher of the two priorities.
+fn insert(hints: &mut Vec<ActionHint>, hint: ActionHint) {
+    if let Some(existing) = hints.iter_mut().find(|v| v.actions == hint.actions) {
+        existing.priority = existing.priority.max(hint.priority);
+    } else {
+        hints.push(hint);
+    }
+}
+
+/// The input hints shown in the info window, each combination of actions once.
+///
+/// The world's hints are pushed while handling input, and are kept until it is handled again. The screens push theirs
+/// while being drawn, and those are shown for the frame after, as the info window may be drawn before them.
+#[derive(Debug, Clone, Default)]
+pub struct InputHints {
+    world: Vec<ActionHint>,
+    frame: Vec<ActionHint>,
+    last_frame: Vec<ActionHint>,
+}
+
+impl InputHints {
+    /// Adds a hint of the world's input.
+    pub fn push(&mut self, actions: Vec<ActionType>) {
+        insert(&mut self.world, ActionHint::new(actions));
+    }
+
+    /// Adds the hints of a screen drawn in this frame.
+    pub fn push_context(&mut self, hints: Vec<ActionHint>) {
+        for hint in hints {
+            insert(&mut self.frame, hint);
+        }
+    }
+
+    /// Forgets the hints of the world's input, before it is handled again.
+    pub fn clear(&mut self) {
+        self.world.clear();
+    }
+
+    /// Starts a new frame, showing the hints the screens pushed in the last one.
+    pub fn new_frame(&mut self) {
+        self.last_frame = mem::take(&mut self.frame);
+    }
+
+    /// Gets the hints to show, the highest priority first, and otherwise in the order they were added.
+    pub fn hints(&self) -> Vec<ActionHint> {
+        let mut hints = Vec::new();
+
+        for hint in self.last_frame.iter().chain(&self.world) {
+            insert(&mut hints, hint.clone());
+        }
+
+        // the sort is stable, keeping the order within a priority
+        hints.sort_by_key(|hint| -hint.priority);
+
+        hints
+    }
+}
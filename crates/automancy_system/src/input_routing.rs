@@ -0,0 +1,81 @@
+use crate::camera::GameCamera;
+use crate::input::InputHandler;
+use std::mem;
+
+/// What takes the input, which depends on what is open over the world.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InputRoute {
+    /// a screen outside of any map, like the main menu or the map list, which the world has nothing to do with
+    #[default]
+    Menu,
+    /// the world of the map being played
+    World,
+    /// a popup or menu of the map being played, which holds the world still until it closes
+    Modal,
+    /// the camera path playback, which has the camera to itself
+    CameraPath,
+}
+
+/// Moves the input between the world and what is open over it, so that nothing carries over from one to the other.
+#[derive(Debug, Clone, Default)]
+pub struct InputRouter {
+    route: InputRoute,
+    /// the camera as it was when an in-game modal opened, kept while one is open
+    modal_camera: Option<GameCamera>,
+}
+
+impl InputRouter {
+    /// Gets what took the input last.
+    pub fn route(&self) -> InputRoute {
+        self.route
+    }
+
+    /// Gets the camera kept while an in-game modal is open.
+    pub fn modal_camera(&self) -> Option<&GameCamera> {
+        self.modal_camera.as_ref()
+    }
+
+    /// Gives the input to the route, and returns it.
+    ///
+    /// When an in-game modal opens, the camera stops and is kept. When the world gets the input back, the camera goes back
+    /// to where it was, and held keys are forgotten, so that presses made while something else had the input do not carry over.
+    pub fn update(
+        &mut self,
+        route: InputRoute,
+        input_handler: &mut InputHandler,
+        camera: &mut GameCamera,
+    ) -> InputRoute {
+        let from = mem::replace(&mut self.route, route);
+
+        match route {
+            InputRoute::World => {
+                if let Some(kept) = self.modal_camera.take() {
+                    *camera = kept;
+                }
+
+                if from != InputRoute::World {
+                    input_handler.clear_held();
+                }
+            }
+            InputRoute::Modal => {
+                if self.modal_camera.is_none() {
+                    camera.stop();
+                    self.modal_camera = Some(*camera);
+                }
+            }
+            // the camera of a map is of no use out of it
+            InputRoute::Menu => {
+                self.modal_camera = None;
+            }
+            // the playback keeps the camera it puts back itself
+            InputRoute::CameraPath => {}
+        }
+
+        route
+    }
+
+    /// Forgets the camera kept for an in-game modal, as it was in the map loaded before.
+    pub fn map_loaded(&mut self) {
+        self.modal_camera = None;
+    }
+}
@@ -1,32 +1,67 @@
-use automancy_defs::{id::Id, kira::manager::AudioManager, math::Vec2, rendering::Vertex};
-use automancy_resources::{data::DataMap, types::item::ItemDef, ResourceManager};
+use audio::AudioChannels;
+use automancy_defs::{
+    coord::TileCoord,
+    id::{Id, TileId},
+    kira::manager::AudioManager,
+    math::Vec2,
+    rendering::Vertex,
+};
+use automancy_resources::{
+    data::{Data, DataMap},
+    format::Formattable,
+    types::item::ItemDef,
+    ResourceManager,
+};
 use camera::GameCamera;
 use cosmic_text::fontdb::Source;
-use game::GameSystemMessage;
-use hashbrown::HashMap;
-use input::{ActionType, InputHandler};
-use map::{LoadMapOption, MapInfo, MapInfoRaw};
+use flow::FlowGraph;
+use game::{GameSystemMessage, ScriptUsage};
+use gamepad::Gamepads;
+use hashbrown::{HashMap, HashSet};
+use input::InputHandler;
+use input_hints::InputHints;
+use map::{GameMap, LoadMapOption, MapInfo, MapInfoRaw, Region};
 use options::{GameOptions, MiscOptions};
+use production::ProductionStats;
+use ractor::rpc::CallResult;
 use ractor::ActorRef;
+use ron::ser::PrettyConfig;
 use std::{
     sync::{atomic::AtomicBool, Arc},
     time::{Duration, Instant, SystemTime},
 };
 use tile_entity::{TileEntityMsg, TileEntityWithId};
 use tokio::{runtime::Runtime, sync::Mutex, task::JoinHandle};
-use ui_state::UiState;
+use trim::{TrimKeep, TrimPreview, TrimProgress};
+use ui_state::{PopupState, ToastLevel, UiState};
 use wgpu::{Device, Queue};
 use winit::window::Window;
 use yakui::{font::Fonts, ManagedTextureId, Yakui};
 use yakui_wgpu::YakuiWgpu;
 use yakui_winit::YakuiWinit;
 
+pub mod actor_stats;
+pub mod audio;
 pub mod camera;
+pub mod changelog;
+pub mod crash;
+pub mod detail;
+pub mod favorites;
+pub mod flow;
 pub mod game;
+pub mod gamepad;
+pub mod hotbar;
 pub mod input;
+pub mod input_hints;
+pub mod input_routing;
 pub mod map;
+pub mod metrics;
 pub mod options;
+pub mod production;
+pub mod sandbox;
+pub mod terrain;
 pub mod tile_entity;
+pub mod trim;
 pub mod ui_state;
 pub mod util;
 
@@ -79,12 +114,56 @@ pub struct EventLoopStorage {
     pub elapsed: Duration,
 
     pub map_infos_cache: Vec<((MapInfoRaw, Option<SystemTime>), String)>,
+    /// the loaded thumbnails of the maps, along with the save time they were loaded for
+    pub map_thumbnails: HashMap<String, (Option<SystemTime>, ManagedTextureId)>,
     pub map_info: Option<(Arc<Mutex<MapInfo>>, LoadMapOption)>,
+    /// the tiles that can be placed with the current research progress, along with the generation they were found in
+    pub placeable_tiles: Option<(u64, Arc<HashSet<TileId>>)>,
+    /// bumped whenever a research is unlocked, the sandbox is toggled, or another map is loaded, which makes the placeable tiles be found again
+    pub placeable_generation: u64,
+    /// the last fetched script usage, shown in the debug menu
+    pub script_usage: Option<ScriptUsage>,
+    /// the regions each coordinate is in. set to None whenever the regions change
+    pub region_index: Option<HashMap<TileCoord, Vec<usize>>>,
+    /// whether the map folder was already checked for entries that need attention this session
+    pub map_folder_checked: bool,
+    /// whether the resource packs should be loaded again, before the next frame
+    pub reload_resources: bool,
+    /// whether the game should take the next research off the research queue, once the map info isn't locked anymore
+    pub advance_research_queue: bool,
+    /// when the research queue was last tried while nothing was being researched
+    pub research_queue_checked: Option<Instant>,
 
     pub config_open_cache: Arc<Mutex<Option<ActorRef<TileEntityMsg>>>>,
     pub config_open_updating: Arc<AtomicBool>,
     pub pointing_cache: Arc<Mutex<Option<TileEntityWithId>>>,
     pub pointing_updating: Arc<AtomicBool>,
+    /// the last built flow graph. only rebuilt when asked for, as that calls every tile entity
+    pub flow_graph: Arc<Mutex<Option<FlowGraph>>>,
+    pub flow_graph_updating: Arc<AtomicBool>,
+    /// the tiles around the camera shown on the minimap, and when they were last fetched
+    pub minimap_tiles: Arc<Mutex<HashMap<TileCoord, TileId>>>,
+    pub minimap_updating: Arc<AtomicBool>,
+    pub minimap_refreshed: Option<Instant>,
+    /// the production rates of the map added up, and when they were last collected
+    pub production_stats: Arc<Mutex<Option<ProductionStats>>>,
+    pub production_stats_updating: Arc<AtomicBool>,
+    pub production_stats_refreshed: Option<Instant>,
+    /// the tiles the trim window would remove, and what is kept that they were found for
+    pub trim_preview: Option<(TrimKeep, TrimPreview)>,
+    /// how far the running trim has gotten, if one was started
+    pub trim_progress: Arc<Mutex<Option<TrimProgress>>>,
+}
+
+/// Where the screenshot taken of the next frame goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Screenshot {
+    /// onto the clipboard
+    Clipboard,
+    /// into the screenshots folder as a PNG
+    File,
+    /// into the screenshots folder as a PNG, with the UI left out of the frame
+    FileWithoutUi,
 }
 
 pub struct InnerGameState<YakuiResources, Renderer> {
@@ -93,19 +172,21 @@ pub struct InnerGameState<YakuiResources, Renderer> {
     pub misc_options: MiscOptions,
     pub resource_man: Arc<ResourceManager>,
     pub input_handler: InputHandler,
+    pub gamepads: Gamepads,
     pub loop_store: EventLoopStorage,
     pub tokio: Runtime,
     pub game: ActorRef<GameSystemMessage>,
     pub camera: GameCamera,
     pub audio_man: AudioManager,
+    pub audio: AudioChannels,
     pub start_instant: Instant,
 
     pub gui: Option<GameGui<YakuiResources>>,
     pub renderer: Option<Renderer>,
-    pub screenshotting: bool,
+    pub screenshotting: Option<Screenshot>,
 
     pub logo: Option<ManagedTextureId>,
-    pub input_hints: Vec<Vec<ActionType>>,
+    pub input_hints: InputHints,
     pub puzzle_state: Option<(DataMap, bool)>,
 
     pub game_handle: Option<JoinHandle<()>>,
@@ -114,6 +195,38 @@ pub struct InnerGameState<YakuiResources, Renderer> {
     pub indices_init: Option<Vec<u16>>,
 }
 
+impl EventLoopStorage {
+    /// Makes the placeable tiles be found again the next time they are asked for.
+    pub fn invalidate_placeable_tiles(&mut self) {
+        self.placeable_generation += 1;
+    }
+
+    /// Gets the placeable tiles, recomputing them if they have been invalidated since they were last found.
+    pub fn placeable_tiles(
+        &mut self,
+        resource_man: &ResourceManager,
+        game_data: &mut DataMap,
+    ) -> Arc<HashSet<TileId>> {
+        let generation = self.placeable_generation;
+
+        match &self.placeable_tiles {
+            Some((found_in, tiles)) if *found_in == generation => tiles.clone(),
+            _ => {
+                let tiles = Arc::new(util::placeable_tiles(resource_man, game_data));
+                self.placeable_tiles = Some((generation, tiles.clone()));
+
+                tiles
+            }
+        }
+    }
+
+    /// Gets the regions each coordinate is in, recomputing them if they have been invalidated.
+    pub fn region_index(&mut self, regions: &[Region]) -> &HashMap<TileCoord, Vec<usize>> {
+        self.region_index
+            .get_or_insert_with(|| map::index_regions(regions))
+    }
+}
+
 impl<A, B> InnerGameState<A, B> {
     pub fn ui_viewport(&self) -> Vec2 {
         self.gui
@@ -147,17 +260,93 @@ pub fn game_load_map_inner<A, B>(
     };
 
     if success {
+        state.ui_state.input_router.map_loaded();
+        state.loop_store.invalidate_placeable_tiles();
+        state.loop_store.script_usage = None;
+        state.loop_store.region_index = None;
+        *state.loop_store.flow_graph.blocking_lock() = None;
+        state.ui_state.flow_graph_selected = None;
+        state.loop_store.trim_preview = None;
+        state.loop_store.minimap_tiles.blocking_lock().clear();
+        state.loop_store.minimap_refreshed = None;
+        *state.loop_store.production_stats.blocking_lock() = None;
+        state.loop_store.production_stats_refreshed = None;
+        state.loop_store.advance_research_queue = false;
+        state.loop_store.research_queue_checked = None;
         state.loop_store.map_info = state
             .tokio
             .block_on(state.game.call(GameSystemMessage::GetMapInfoAndName, None))
             .unwrap()
             .unwrap();
 
+        if let Ok(CallResult::Success(missing)) = state
+            .tokio
+            .block_on(state.game.call(GameSystemMessage::TakeMissingIds, None))
+        {
+            if !missing.is_empty() {
+                state.ui_state.popup = PopupState::MissingIds(missing.into_iter().collect());
+            }
+        }
+
+        let mut ambience = None;
+
+        if let Some((info, opt)) = &state.loop_store.map_info {
+            let info = state.tokio.block_on(info.lock());
+
+            if let Some(Data::Id(id)) = info.data.get(state.resource_man.registry.data_ids.ambience)
+            {
+                ambience = Some(*id);
+            }
+            let formatted = format!(
+                "Save Time: {:?}\n{}",
+                info.save_time,
+                ron::ser::to_string_pretty(
+                    &info.data.to_raw(&state.resource_man.interner),
+                    PrettyConfig::default()
+                )
+                .unwrap_or_default()
+            );
+
+            if let Ok(mut context) = crash::CRASH_CONTEXT.lock() {
+                context.map = Some((opt.to_string(), formatted));
+            }
+
+            if let Some(backup) = info.restored_from_backup {
+                let text = state.resource_man.gui_fmt(
+                    state.resource_man.registry.gui_ids.lbl_restored_from_backup,
+                    [("backup", Formattable::integer(&backup))],
+                );
+
+                state.ui_state.push_toast(ToastLevel::Warning, text);
+            }
+        }
+
+        let mood = if opt == LoadMapOption::MainMenu {
+            "menu"
+        } else {
+            "game"
+        };
+        state.audio.set_mood(Some(mood.to_string()));
+        state
+            .audio
+            .set_ambience(&mut state.audio_man, &state.resource_man, ambience);
+
         GameLoadResult::Loaded
     } else if opt == LoadMapOption::MainMenu {
         GameLoadResult::Failed
     } else {
-        game_load_map_inner(state, LoadMapOption::MainMenu)
+        let result = match game_load_map_inner(state, LoadMapOption::MainMenu) {
+            GameLoadResult::Loaded => GameLoadResult::LoadedMainMenu,
+            other => other,
+        };
+
+        if let LoadMapOption::FromSave(name) = &opt {
+            if GameMap::autosave_time(&opt).is_some() {
+                state.ui_state.popup = PopupState::AutosaveFallback(name.clone());
+            }
+        }
+
+        result
     }
 }
 
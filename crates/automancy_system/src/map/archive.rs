@@ -0,0 +1,248 @@
+use super::{
+    frame_decoder, sanitize_name, GameMap, LoadMapOption, MapError, MapInfoRaw, MapRaw,
+    AUTOSAVE_PATH, DICTIONARY_EXT, INFO_EXT, MAP_EXT, THUMBNAIL_EXT,
+};
+use automancy_resources::ResourceManager;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use zstd::Encoder;
+
+/// The extension of the files maps are exported to.
+pub static ARCHIVE_EXT: &str = "amap";
+
+/// The zstd level the files of an exported map are compressed with. The tiles are already compressed, so a higher one gains little.
+const ARCHIVE_COMPRESSION_LEVEL: i32 = 3;
+const ARCHIVE_BUFFER_SIZE: usize = 256 * 1024;
+
+/// What an exported map says about itself. It is the first zstd frame of the archive, as RON, and each of the files follows in a frame of its own.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MapArchiveManifest {
+    /// The name the map was exported under, which it is imported as unless a map already has it.
+    pub name: String,
+    /// The namespaces of the packs the map references, with the version of the pack when it was exported, if it has a manifest.
+    pub packs: Vec<(String, Option<String>)>,
+    /// The files of the map, relative to its folder and separated by `/`, in the order they follow in.
+    pub files: Vec<String>,
+}
+
+impl MapArchiveManifest {
+    /// Gets the namespaces the map references that aren't of any loaded pack.
+    pub fn missing_packs(&self, resource_man: &ResourceManager) -> Vec<String> {
+        self.packs
+            .iter()
+            .filter(|(namespace, _)| {
+                !resource_man
+                    .packs
+                    .iter()
+                    .any(|pack| &pack.namespace == namespace)
+            })
+            .map(|(namespace, _)| namespace.clone())
+            .collect()
+    }
+}
+
+/// Gets the namespaces of the IDs the map references, in its tiles, data, and terrain.
+fn referenced_namespaces(info: &MapInfoRaw, map: &MapRaw) -> BTreeSet<String> {
+    let tiles = map
+        .tiles
+        .iter()
+        .chain(map.chunks.iter().flat_map(|chunk| chunk.tiles.iter()));
+
+    info.data
+        .ids()
+        .chain(map.tile_map.values())
+        .chain(map.terrain.palette.iter())
+        .chain(tiles.flat_map(|(_, _, data)| data.ids()))
+        .chain(map.quarantine.iter().flat_map(|(_, data)| data.ids()))
+        .flat_map(|id| {
+            id.split_once(':')
+                .map(|(namespace, _)| namespace.to_string())
+        })
+        .collect()
+}
+
+/// Gets the files of the map's folder that are exported, relative to it. Backups and half written files are left out.
+fn archived_files(path: &Path) -> Vec<String> {
+    let archived = [INFO_EXT, MAP_EXT, DICTIONARY_EXT, THUMBNAIL_EXT];
+
+    let mut files = vec![];
+    for (dir, prefix) in [
+        (path.to_path_buf(), String::new()),
+        (path.join(AUTOSAVE_PATH), format!("{AUTOSAVE_PATH}/")),
+    ] {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let (Some(name), Some(ext)) = (
+                path.file_name().and_then(|v| v.to_str()),
+                path.extension().and_then(|v| v.to_str()),
+            ) else {
+                continue;
+            };
+
+            if path.is_file() && !name.starts_with('.') && archived.contains(&ext) {
+                files.push(format!("{prefix}{name}"));
+            }
+        }
+    }
+    files.sort();
+
+    files
+}
+
+/// Gets where a file of an imported map goes, or none if its path leaves the map's folder or isn't one that is exported.
+fn archived_file_path(path: &Path, file: &str) -> Option<PathBuf> {
+    let parts = file.split('/').collect::<Vec<_>>();
+
+    let valid = |part: &str| {
+        !part.is_empty()
+            && !part.starts_with('.')
+            && part
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '.' || c == '_' || c == '-')
+    };
+
+    match parts.as_slice() {
+        [name] if valid(name) => Some(path.join(name)),
+        [dir, name] if *dir == AUTOSAVE_PATH && valid(name) => {
+            Some(path.join(AUTOSAVE_PATH).join(name))
+        }
+        _ => None,
+    }
+}
+
+/// Writes each file of an exported map out of the frame it is in, in the order of the manifest.
+fn unpack_files<R: BufRead>(mut reader: R, targets: &[PathBuf]) -> io::Result<()> {
+    for target in targets {
+        let mut decoder = frame_decoder(reader, None)?;
+        let mut content = vec![];
+        decoder.read_to_end(&mut content)?;
+        reader = decoder.finish();
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(target, content)?;
+    }
+
+    Ok(())
+}
+
+impl GameMap {
+    /// Exports a saved map, with its autosave and thumbnail, into one file that can be imported elsewhere.
+    ///
+    /// The map is exported as it was last saved. The packs it references are listed in the archive, so that the ones that are missing can be told apart when it is imported.
+    pub fn export(
+        resource_man: &ResourceManager,
+        map_name: &str,
+        dest: &Path,
+    ) -> Result<(), MapError> {
+        let opt = LoadMapOption::FromSave(map_name.to_string());
+
+        let Some(path) = GameMap::path(&opt).filter(|v| v.is_dir()) else {
+            return Err(MapError::Missing(map_name.to_string()));
+        };
+
+        let unreadable = |_| MapError::Unreadable(map_name.to_string());
+        let (info, _) = GameMap::read_info(resource_man, &opt, 0).map_err(unreadable)?;
+        let dictionary =
+            GameMap::read_dictionary(resource_man, &opt, &info, 0).map_err(unreadable)?;
+        let map =
+            GameMap::read_map(resource_man, &opt, dictionary.as_deref(), 0).map_err(unreadable)?;
+
+        let packs = referenced_namespaces(&info, &map)
+            .into_iter()
+            .map(|namespace| {
+                let version = resource_man
+                    .packs
+                    .iter()
+                    .find(|pack| pack.namespace == namespace)
+                    .and_then(|pack| pack.manifest.as_ref())
+                    .map(|manifest| manifest.version.clone());
+
+                (namespace, version)
+            })
+            .collect();
+
+        let manifest = MapArchiveManifest {
+            name: map_name.to_string(),
+            packs,
+            files: archived_files(&path),
+        };
+
+        let mut writer = BufWriter::with_capacity(ARCHIVE_BUFFER_SIZE, File::create(dest)?);
+
+        let mut encoder = Encoder::new(writer, ARCHIVE_COMPRESSION_LEVEL)?;
+        ron::ser::to_writer(&mut encoder, &manifest).map_err(io::Error::other)?;
+        writer = encoder.finish()?;
+
+        for file in &manifest.files {
+            let mut encoder = Encoder::new(writer, ARCHIVE_COMPRESSION_LEVEL)?;
+            encoder.write_all(&fs::read(path.join(file))?)?;
+            writer = encoder.finish()?;
+        }
+
+        writer
+            .into_inner()
+            .map_err(|err| err.into_error())?
+            .sync_all()?;
+
+        log::info!("Exported map {map_name} to {dest:?}");
+
+        Ok(())
+    }
+
+    /// Reads the manifest of an exported map, without reading its files.
+    pub fn read_archive_manifest(src: &Path) -> Result<MapArchiveManifest, MapError> {
+        let reader = BufReader::with_capacity(ARCHIVE_BUFFER_SIZE, File::open(src)?);
+
+        let decoder = frame_decoder(reader, None)?;
+
+        ron::de::from_reader(decoder).map_err(|err| MapError::InvalidArchive(err.to_string()))
+    }
+
+    /// Imports an exported map into the map folder, and returns the name it was imported as.
+    ///
+    /// Maps are never overwritten: if a map already has the name the map was exported under, a number is added to it.
+    pub fn import(src: &Path) -> Result<String, MapError> {
+        let reader = BufReader::with_capacity(ARCHIVE_BUFFER_SIZE, File::open(src)?);
+
+        let mut decoder = frame_decoder(reader, None)?;
+        let manifest: MapArchiveManifest = ron::de::from_reader(&mut decoder)
+            .map_err(|err| MapError::InvalidArchive(err.to_string()))?;
+        let reader = decoder.finish();
+
+        let name = GameMap::unused_name(&sanitize_name(manifest.name.clone()));
+        let path = GameMap::path(&LoadMapOption::FromSave(name.clone()))
+            .ok_or_else(|| MapError::Missing(name.clone()))?;
+
+        let targets = manifest
+            .files
+            .iter()
+            .map(|file| {
+                archived_file_path(&path, file).ok_or_else(|| {
+                    MapError::InvalidArchive(format!("the file {file} is not a map file"))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // a half imported map would show up as a broken one
+        if let Err(err) = unpack_files(reader, &targets) {
+            if path.exists() {
+                fs::remove_dir_all(&path)?;
+            }
+
+            return Err(err.into());
+        }
+
+        log::info!("Imported map {} from {src:?} as {name}", manifest.name);
+
+        Ok(name)
+    }
+}
@@ -0,0 +1,56 @@
+use crate::map::chunk::chunk_raw_tiles;
+use crate::map::MapRaw;
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::TileId;
+use automancy_resources::data::DataMapRaw;
+use automancy_resources::ResourceManager;
+use hashbrown::HashMap;
+
+/// Builds a map as it is saved from tiles given in code, for tools and tests that need maps without running a game.
+pub struct MapBuilder<'a> {
+    resource_man: &'a ResourceManager,
+    tiles: HashMap<TileCoord, (TileId, DataMapRaw)>,
+}
+
+impl<'a> MapBuilder<'a> {
+    /// Creates a builder of an empty map, naming the tile IDs from the resource manager.
+    pub fn new(resource_man: &'a ResourceManager) -> Self {
+        Self {
+            resource_man,
+            tiles: Default::default(),
+        }
+    }
+
+    /// Places the tile at the coordinate, replacing the tile that was there.
+    pub fn tile(mut self, coord: TileCoord, id: TileId, data: DataMapRaw) -> Self {
+        self.tiles.insert(coord, (id, data));
+
+        self
+    }
+
+    /// Builds the map, with the tiles in coordinate order and the names of their IDs in the tile map.
+    pub fn build(self) -> MapRaw {
+        let interner = &self.resource_man.interner;
+        let tile_map = self
+            .tiles
+            .values()
+            .map(|(id, _)| (**id, interner.resolve(**id).unwrap().to_string()))
+            .collect();
+
+        let mut tiles = self
+            .tiles
+            .into_iter()
+            .map(|(coord, (id, data))| (coord, *id, data))
+            .collect::<Vec<_>>();
+        tiles.sort_unstable_by_key(|(coord, ..)| (coord.y, coord.x));
+
+        MapRaw {
+            tiles: vec![],
+            chunks: chunk_raw_tiles(tiles),
+            tile_map,
+            footprints: Default::default(),
+            quarantine: Default::default(),
+            terrain: Default::default(),
+        }
+    }
+}
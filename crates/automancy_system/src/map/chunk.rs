@@ -0,0 +1,84 @@
+use automancy_defs::coord::{TileCoord, TileUnit};
+use automancy_defs::id::{Id, TileId};
+use automancy_resources::data::DataMapRaw;
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How many coordinates a chunk spans along each axial axis.
+pub const CHUNK_SIZE: TileUnit = 16;
+
+/// The coordinate of a chunk, which is the axial coordinate of its tiles divided by [CHUNK_SIZE], rounded down.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct ChunkCoord(pub TileUnit, pub TileUnit);
+
+impl ChunkCoord {
+    /// Gets the chunk the coordinate is in.
+    pub fn of(coord: TileCoord) -> Self {
+        Self(
+            coord.x.div_euclid(CHUNK_SIZE),
+            coord.y.div_euclid(CHUNK_SIZE),
+        )
+    }
+}
+
+impl fmt::Display for ChunkCoord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}, {}]", self.0, self.1)
+    }
+}
+
+/// The tiles of one chunk of a map.
+#[derive(Debug, Default, Clone)]
+pub struct Chunk {
+    tiles: HashMap<TileCoord, TileId>,
+}
+
+impl Chunk {
+    pub fn get(&self, coord: TileCoord) -> Option<TileId> {
+        self.tiles.get(&coord).copied()
+    }
+
+    pub fn insert(&mut self, coord: TileCoord, id: TileId) -> Option<TileId> {
+        self.tiles.insert(coord, id)
+    }
+
+    pub fn remove(&mut self, coord: TileCoord) -> Option<TileId> {
+        self.tiles.remove(&coord)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (TileCoord, TileId)> + '_ {
+        self.tiles.iter().map(|(coord, id)| (*coord, *id))
+    }
+}
+
+/// The tiles of one chunk as they are saved, each chunk in a zstd frame of its own after the rest of the map.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChunkRaw {
+    pub coord: ChunkCoord,
+    /// The tiles, in coordinate order.
+    pub tiles: Vec<(TileCoord, Id, DataMapRaw)>,
+}
+
+/// Splits the tiles into the chunks they are in, in chunk order, keeping the order of the tiles within each chunk.
+pub fn chunk_raw_tiles(tiles: Vec<(TileCoord, Id, DataMapRaw)>) -> Vec<ChunkRaw> {
+    let mut chunks = HashMap::<ChunkCoord, Vec<_>>::new();
+
+    for tile in tiles {
+        chunks.entry(ChunkCoord::of(tile.0)).or_default().push(tile);
+    }
+
+    let mut chunks = chunks
+        .into_iter()
+        .map(|(coord, tiles)| ChunkRaw { coord, tiles })
+        .collect::<Vec<_>>();
+    chunks.sort_unstable_by_key(|chunk| chunk.coord);
+
+    chunks
+}
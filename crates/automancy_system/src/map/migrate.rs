@@ -0,0 +1,39 @@
+use crate::map::chunk::chunk_raw_tiles;
+use crate::map::{MapInfoRaw, MapRaw};
+use std::mem;
+
+/// The version of the map format that is written when saving.
+pub const MAP_VERSION: u32 = 2;
+
+/// Whether maps of the version can be read, which are the ones up to the current format.
+pub fn is_supported(version: u32) -> bool {
+    version <= MAP_VERSION
+}
+
+/// Version 0 is the format from before maps were versioned. It saved the same info and tiles, only without the version.
+fn from_v0(_info: &mut MapInfoRaw, map: MapRaw) -> MapRaw {
+    map
+}
+
+/// Version 1 saved all the tiles in one list, in the same zstd frame as the rest of the map. From version 2 on, they are split into chunks.
+fn from_v1(_info: &mut MapInfoRaw, mut map: MapRaw) -> MapRaw {
+    map.chunks = chunk_raw_tiles(mem::take(&mut map.tiles));
+
+    map
+}
+
+/// Upgrades a map read from disk to the current format in memory, one version at a time. The version has to be supported.
+///
+/// The map is only written in the current format the next time it is saved.
+pub fn migrate(info: &mut MapInfoRaw, mut map: MapRaw) -> MapRaw {
+    if info.version == 0 {
+        map = from_v0(info, map);
+        info.version = 1;
+    }
+    if info.version == 1 {
+        map = from_v1(info, map);
+        info.version = 2;
+    }
+
+    map
+}
@@ -0,0 +1,1650 @@
+use crate::game;
+use crate::game::{GameSystemMessage, TickUnit};
+use crate::options::{SaveOptions, BACKUP_COUNTS};
+use crate::terrain::{Terrain, TerrainRaw};
+use crate::tile_entity::TileEntityMsg;
+use crate::util::actor::multi_call_iter;
+use automancy_defs::id::Id;
+use automancy_defs::{coord::TileCoord, id::TileId};
+use automancy_resources::{
+    data::{DataMap, DataMapRaw},
+    error::push_err,
+    format::Formattable,
+};
+use automancy_resources::{format::FormatContext, ResourceManager};
+use chunk::{chunk_raw_tiles, Chunk, ChunkCoord, ChunkRaw};
+use hashbrown::HashMap;
+use migrate::MAP_VERSION;
+use ractor::rpc::CallResult;
+use ractor::ActorRef;
+use ron::error::SpannedResult;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, BufWriter, Read};
+use std::time::{Duration, Instant, SystemTime};
+use std::{fmt, fs::File};
+use std::{fmt::Debug, io::Write};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use std::{io, sync::Arc};
+use thiserror::Error;
+use tokio::sync::Mutex;
+use zstd::{Decoder, Encoder};
+
+pub mod archive;
+pub mod builder;
+pub mod chunk;
+pub mod migrate;
+pub mod thumbnail;
+
+pub static MAP_PATH: &str = "map";
+pub static MAP_EXT: &str = "zst";
+pub static INFO_EXT: &str = "ron";
+pub static THUMBNAIL_EXT: &str = "png";
+pub static DICTIONARY_EXT: &str = "dict";
+/// The folder within a map's folder its autosave is kept in.
+pub static AUTOSAVE_PATH: &str = "autosave";
+/// The extension added to a map's files while they are written, before they replace the saved ones.
+pub static TEMP_EXT: &str = "tmp";
+
+pub const THUMBNAIL_WIDTH: u32 = 256;
+pub const THUMBNAIL_HEIGHT: u32 = 144;
+
+static MAIN_MENU_INFO: &[u8] = include_bytes!("../assets/main_menu/info.ron");
+static MAIN_MENU_MAP: &[u8] = include_bytes!("../assets/main_menu/map.zst");
+
+const INFO_BUFFER_SIZE: usize = 1024;
+const MAP_BUFFER_SIZE: usize = 256 * 1024;
+/// The largest a trained map dictionary can be, in bytes.
+const MAX_DICTIONARY_SIZE: usize = 112 * 1024;
+
+/// All the tiles of a map at once, as handed out of the game.
+pub type Tiles = HashMap<TileCoord, TileId>;
+pub type TileEntities = HashMap<TileCoord, ActorRef<TileEntityMsg>>;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LoadMapOption {
+    FromSave(String),
+    MainMenu,
+    Debug, // TODO unused rn but can be useful to have a debug map
+}
+
+impl fmt::Display for LoadMapOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadMapOption::FromSave(v) => f.write_fmt(format_args!("Map {}", v)),
+            LoadMapOption::MainMenu => f.write_str("<main menu>"),
+            LoadMapOption::Debug => f.write_str("<debug map>"),
+        }
+    }
+}
+
+/// A named area of a map, defined by the player. Regions can overlap, and are kept as-is when their tiles change.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Region {
+    pub name: String,
+    /// The index of the region's color in the overlay palette.
+    pub color: usize,
+    pub tiles: Vec<TileCoord>,
+}
+
+/// Maps every coordinate to the indices of the regions it is in.
+pub fn index_regions(regions: &[Region]) -> HashMap<TileCoord, Vec<usize>> {
+    let mut index = HashMap::<TileCoord, Vec<usize>>::new();
+
+    for (i, region) in regions.iter().enumerate() {
+        for coord in &region.tiles {
+            index.entry(*coord).or_default().push(i);
+        }
+    }
+
+    index
+}
+
+#[derive(Error, Debug)]
+pub enum MapError {
+    #[error("a map named {0} already exists")]
+    AlreadyExists(String),
+    #[error("there is no map named {0}")]
+    Missing(String),
+    #[error("the map {0} is the one that is loaded")]
+    Loaded(String),
+    #[error("the map {0} could not be read")]
+    Unreadable(String),
+    #[error("the file is not an exported map: {0}")]
+    InvalidArchive(String),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Contains information about a map.
+#[derive(Debug, Clone, Default)]
+pub struct MapInfo {
+    /// The last save time as a UTC Unix timestamp.
+    pub save_time: Option<SystemTime>,
+    /// The map data.
+    pub data: DataMap,
+    /// The number of ticks that have happened in the map.
+    pub tick_count: TickUnit,
+    /// The seed the random streams of the tile entities are derived from.
+    pub seed: u64,
+    /// The regions defined in the map.
+    pub regions: Vec<Region>,
+    /// The ground under the tiles.
+    pub terrain: Arc<Terrain>,
+    /// The backup the map was loaded from, if its last save couldn't be read.
+    pub restored_from_backup: Option<usize>,
+    /// The name the map is shown by. Maps without one are shown by the name of their folder.
+    pub display_name: Option<String>,
+    pub description: Option<String>,
+    /// When the map was created, if it was kept track of then.
+    pub created_time: Option<SystemTime>,
+    /// How long the map has been played for, up to [MapInfo::played_since].
+    pub playtime: Duration,
+    /// Since when the map has been played without it being added to [MapInfo::playtime].
+    pub played_since: Option<Instant>,
+}
+
+impl MapInfo {
+    /// Adds the time the map has been played for since the last time to the playtime.
+    pub fn flush_playtime(&mut self) {
+        let now = Instant::now();
+
+        if let Some(since) = self.played_since.replace(now) {
+            self.playtime += now - since;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MapInfoRaw {
+    /// The number of saved tiles.
+    #[serde(default)]
+    pub tile_count: u32,
+    #[serde(default)]
+    pub data: DataMapRaw,
+    /// The number of ticks that have happened in the map.
+    #[serde(default)]
+    pub tick_count: TickUnit,
+    /// The seed of the map's randomness. Maps saved without one are given one when they are loaded.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// The regions defined in the map.
+    #[serde(default)]
+    pub regions: Vec<Region>,
+    /// Whether the tiles are compressed with the dictionary saved next to them.
+    #[serde(default)]
+    pub dictionary: bool,
+    /// The version of the format the map was saved in. Maps saved before it existed are version 0.
+    #[serde(default)]
+    pub version: u32,
+    /// When the map was saved, in seconds since the Unix epoch. Maps saved without it use the time their info was last modified.
+    #[serde(default)]
+    pub save_time: Option<u64>,
+    /// The name the map is shown by, which unlike the name of its folder can be anything.
+    #[serde(default)]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// When the map was created, in seconds since the Unix epoch. Maps created before it was kept don't have it.
+    #[serde(default)]
+    pub created_time: Option<u64>,
+    /// How long the map has been played for, in seconds.
+    #[serde(default)]
+    pub playtime: u64,
+}
+
+/// A map stores tiles and tile entities to disk.
+#[derive(Debug, Clone)]
+pub struct GameMap {
+    /// The name of the map, or a built-in map.
+    /// Name should be sanitized.
+    pub opt: LoadMapOption,
+    /// The tiles, by the chunk they are in. Tiles covering several coordinates are listed at each of them.
+    /// Only reached through [GameMap::get_tile] and [GameMap::set_tile] and the like, so that the chunks stay out of the rest of the game.
+    chunks: HashMap<ChunkCoord, Chunk>,
+    /// how many coordinates have tiles on them, across all the chunks
+    tile_count: usize,
+    /// The coordinates covered by tiles other than the ones they are placed at, to the ones they are placed at.
+    /// Only the coordinates the tiles are placed at have tile entities.
+    pub footprints: HashMap<TileCoord, TileCoord>,
+    /// The map's info.
+    pub info: Arc<Mutex<MapInfo>>,
+    /// The data entries of each tile that reference IDs which are not loaded, kept as they were saved along with the tile they belong to.
+    /// These are saved again untouched, so that nothing is lost if the missing packs come back.
+    pub quarantine: HashMap<TileCoord, (TileId, DataMapRaw)>,
+    /// The number of references to each unloaded ID found when the map was loaded.
+    pub missing_ids: BTreeMap<String, usize>,
+    /// The zstd dictionary the tiles are compressed with, if the map has one.
+    pub dictionary: Option<Vec<u8>>,
+}
+
+/// A map stores tiles and tile entities to disk.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MapRaw {
+    /// The tiles, in coordinate order. Only maps from before version 2 have them here, as later ones save them in [MapRaw::chunks].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tiles: Vec<(TileCoord, Id, DataMapRaw)>,
+    /// The tiles by chunk, in chunk order. Each chunk is saved in a zstd frame of its own after the rest of the map,
+    /// so that they can be read apart from each other.
+    #[serde(skip)]
+    pub chunks: Vec<ChunkRaw>,
+    pub tile_map: BTreeMap<Id, String>,
+    /// The coordinates covered by tiles other than the ones they are placed at, and the ones they are placed at, in coordinate order.
+    /// Saved rather than worked out again, so that a tile whose footprint changed keeps covering what it did.
+    #[serde(default)]
+    pub footprints: Vec<(TileCoord, TileCoord)>,
+    /// The data entries that referenced unloaded IDs when the map was last loaded, in coordinate order.
+    #[serde(default)]
+    pub quarantine: Vec<(TileCoord, DataMapRaw)>,
+    /// The ground under the tiles. Maps saved without one are all the default terrain.
+    #[serde(default)]
+    pub terrain: TerrainRaw,
+}
+
+/// What [GameMap::read_saved] read of a map.
+struct SavedMap {
+    info: MapInfoRaw,
+    save_time: Option<SystemTime>,
+    dictionary: Option<Vec<u8>>,
+    map: MapRaw,
+    restored_from_backup: Option<usize>,
+}
+
+/// Removes the temporary files of a save when dropped, unless they were all renamed over the saved ones.
+struct TempFiles {
+    paths: Vec<PathBuf>,
+    renamed: bool,
+}
+
+impl TempFiles {
+    fn new(files: &[&Path]) -> Self {
+        Self {
+            paths: files.iter().map(|file| GameMap::temp_file(file)).collect(),
+            renamed: false,
+        }
+    }
+}
+
+impl Drop for TempFiles {
+    fn drop(&mut self) {
+        if self.renamed {
+            return;
+        }
+
+        for path in &self.paths {
+            match fs::remove_file(path) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                    log::warn!("Could not remove the unfinished save file {path:?}: {err}");
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl GameMap {
+    /// Creates a new empty map, with a new seed.
+    pub fn new_empty(opt: LoadMapOption) -> Self {
+        Self {
+            opt,
+            chunks: Default::default(),
+            tile_count: 0,
+            footprints: Default::default(),
+            info: Arc::new(Mutex::new(MapInfo {
+                seed: rand::random(),
+                created_time: Some(SystemTime::now()),
+                played_since: Some(Instant::now()),
+                ..Default::default()
+            })),
+            quarantine: Default::default(),
+            missing_ids: Default::default(),
+            dictionary: None,
+        }
+    }
+
+    /// Gets the tile at the coordinate.
+    pub fn get_tile(&self, coord: TileCoord) -> Option<TileId> {
+        self.chunks.get(&ChunkCoord::of(coord))?.get(coord)
+    }
+
+    /// Checks if there is a tile at the coordinate.
+    pub fn has_tile(&self, coord: TileCoord) -> bool {
+        self.get_tile(coord).is_some()
+    }
+
+    /// Puts the tile at the coordinate, returning the one that was there.
+    pub fn set_tile(&mut self, coord: TileCoord, id: TileId) -> Option<TileId> {
+        let previous = self
+            .chunks
+            .entry(ChunkCoord::of(coord))
+            .or_default()
+            .insert(coord, id);
+
+        if previous.is_none() {
+            self.tile_count += 1;
+        }
+
+        previous
+    }
+
+    /// Takes the tile off the coordinate, returning it. Chunks are dropped once they have no tiles left.
+    pub fn clear_tile(&mut self, coord: TileCoord) -> Option<TileId> {
+        let chunk_coord = ChunkCoord::of(coord);
+        let chunk = self.chunks.get_mut(&chunk_coord)?;
+        let previous = chunk.remove(coord)?;
+
+        if chunk.is_empty() {
+            self.chunks.remove(&chunk_coord);
+        }
+        self.tile_count -= 1;
+
+        Some(previous)
+    }
+
+    /// Gets how many coordinates have tiles on them, including the ones covered by bigger tiles.
+    pub fn tile_count(&self) -> usize {
+        self.tile_count
+    }
+
+    /// Goes through the tiles of every chunk, in no particular order.
+    pub fn tiles(&self) -> impl Iterator<Item = (TileCoord, TileId)> + '_ {
+        self.chunks.values().flat_map(Chunk::iter)
+    }
+
+    /// Collects the tiles of every chunk into one map.
+    pub fn all_tiles(&self) -> Tiles {
+        self.tiles().collect()
+    }
+
+    /// Gets the coordinate the tile covering the coordinate is placed at. That is the coordinate itself, unless a bigger tile covers it.
+    pub fn anchor(&self, coord: TileCoord) -> TileCoord {
+        self.footprints.get(&coord).copied().unwrap_or(coord)
+    }
+
+    /// Frees the other coordinates covered by the tile placed at the coordinate, returning them.
+    pub fn take_footprint(&mut self, anchor: TileCoord) -> Vec<TileCoord> {
+        let covered = self
+            .footprints
+            .iter()
+            .filter(|(_, v)| **v == anchor)
+            .map(|(coord, _)| *coord)
+            .collect::<Vec<_>>();
+
+        for coord in &covered {
+            self.footprints.remove(coord);
+            self.clear_tile(*coord);
+        }
+
+        covered
+    }
+
+    /// Gets the path to a map from its name.
+    pub fn path(opt: &LoadMapOption) -> Option<PathBuf> {
+        match opt {
+            LoadMapOption::FromSave(map_name) => Some(PathBuf::from(MAP_PATH).join(map_name)),
+            _ => None,
+        }
+    }
+
+    /// Gets the path to a map's backup from its name. It's hidden, so that it doesn't show up as a map of its own.
+    pub fn backup_path(opt: &LoadMapOption) -> Option<PathBuf> {
+        match opt {
+            LoadMapOption::FromSave(map_name) => {
+                Some(PathBuf::from(MAP_PATH).join(format!(".{map_name}.backup")))
+            }
+            _ => None,
+        }
+    }
+
+    /// Renames a saved map, along with its backup. Its autosave and the backups of its files are in its folder, and move with it.
+    ///
+    /// The loaded map shouldn't be renamed, as it would be saved under the old name again.
+    pub fn rename(old: &str, new: &str) -> Result<(), MapError> {
+        if old == new {
+            return Ok(());
+        }
+
+        let old_opt = LoadMapOption::FromSave(old.to_string());
+        let new_opt = LoadMapOption::FromSave(new.to_string());
+
+        let (Some(from), Some(to)) = (GameMap::path(&old_opt), GameMap::path(&new_opt)) else {
+            return Err(MapError::Missing(old.to_string()));
+        };
+
+        if !from.is_dir() {
+            return Err(MapError::Missing(old.to_string()));
+        }
+        if to.exists() {
+            return Err(MapError::AlreadyExists(new.to_string()));
+        }
+
+        fs::rename(from, to)?;
+
+        if let (Some(from), Some(to)) = (
+            GameMap::backup_path(&old_opt),
+            GameMap::backup_path(&new_opt),
+        ) {
+            if from.exists() {
+                if to.exists() {
+                    fs::remove_dir_all(&to)?;
+                }
+
+                fs::rename(from, to)?;
+            }
+        }
+
+        log::info!("Renamed map {old} to {new}");
+
+        Ok(())
+    }
+
+    /// Gets the name, or the name with the lowest number after it that no map or backup has.
+    pub fn unused_name(name: &str) -> String {
+        let taken = |name: &str| {
+            let opt = LoadMapOption::FromSave(name.to_string());
+
+            [GameMap::path(&opt), GameMap::backup_path(&opt)]
+                .into_iter()
+                .flatten()
+                .any(|path| path.exists())
+        };
+
+        if !taken(name) {
+            return name.to_string();
+        }
+
+        (2..)
+            .map(|n| format!("{name}_{n}"))
+            .find(|name| !taken(name))
+            .unwrap()
+    }
+
+    /// Deletes a saved map, along with its backup and autosave.
+    ///
+    /// The loaded map shouldn't be deleted, as it would be saved again.
+    pub fn delete(map_name: &str) -> Result<(), MapError> {
+        let opt = LoadMapOption::FromSave(map_name.to_string());
+
+        let Some(path) = GameMap::path(&opt).filter(|v| v.is_dir()) else {
+            return Err(MapError::Missing(map_name.to_string()));
+        };
+
+        fs::remove_dir_all(path)?;
+
+        if let Some(backup) = GameMap::backup_path(&opt).filter(|v| v.exists()) {
+            fs::remove_dir_all(backup)?;
+        }
+
+        log::info!("Deleted map {map_name}!");
+
+        Ok(())
+    }
+
+    /// Gets the path to the folder of a map's autosave from its name.
+    pub fn autosave_path(opt: &LoadMapOption) -> Option<PathBuf> {
+        GameMap::path(opt).map(|v| v.join(AUTOSAVE_PATH))
+    }
+
+    /// Gets when the map was last autosaved, if it has an autosave.
+    pub fn autosave_time(opt: &LoadMapOption) -> Option<SystemTime> {
+        let info = GameMap::autosave_path(opt)?
+            .join("info")
+            .with_extension(INFO_EXT);
+
+        fs::metadata(info).and_then(|v| v.modified()).ok()
+    }
+
+    /// Copies the autosave of the map over its saved files, so that the map loads from the autosave from then on.
+    pub fn restore_autosave(opt: &LoadMapOption) -> io::Result<()> {
+        let (Some(path), Some(autosave)) = (GameMap::path(opt), GameMap::autosave_path(opt)) else {
+            return Ok(());
+        };
+
+        for entry in fs::read_dir(autosave)?.flatten() {
+            if entry.path().is_file() {
+                fs::copy(entry.path(), path.join(entry.file_name()))?;
+            }
+        }
+
+        log::info!("Restored the autosave of {opt}");
+
+        Ok(())
+    }
+
+    /// Gets the path to a map's info from its name.
+    pub fn info(opt: &LoadMapOption) -> Option<PathBuf> {
+        GameMap::path(opt).map(|v| v.join("info").with_extension(INFO_EXT))
+    }
+
+    /// Gets the path to a map's tiles from its name.
+    pub fn map(opt: &LoadMapOption) -> Option<PathBuf> {
+        GameMap::path(opt).map(|v| v.join("map").with_extension(MAP_EXT))
+    }
+
+    /// Gets the path to a map's compression dictionary from its name.
+    pub fn dictionary(opt: &LoadMapOption) -> Option<PathBuf> {
+        GameMap::path(opt).map(|v| v.join("map").with_extension(DICTIONARY_EXT))
+    }
+
+    /// Gets the path to a backup of one of a map's files, numbered from the newest. Backup 0 is the file itself.
+    pub fn backup_file(path: PathBuf, backup: usize) -> PathBuf {
+        if backup == 0 {
+            return path;
+        }
+
+        let mut path = path.into_os_string();
+        path.push(format!(".{backup}"));
+
+        PathBuf::from(path)
+    }
+
+    fn temp_file(path: &Path) -> PathBuf {
+        let mut path = path.as_os_str().to_owned();
+        path.push(format!(".{TEMP_EXT}"));
+
+        PathBuf::from(path)
+    }
+
+    /// Reads the compression dictionary of a map, or of one of its backups, if its info says it has one.
+    ///
+    /// A missing dictionary is reported as such, since the tiles cannot be read without it. Only the saved files are reported to the player, not their backups.
+    pub fn read_dictionary(
+        resource_man: &ResourceManager,
+        opt: &LoadMapOption,
+        info: &MapInfoRaw,
+        backup: usize,
+    ) -> Result<Option<Vec<u8>>, bool> {
+        if !info.dictionary {
+            return Ok(None);
+        }
+
+        let Some(path) = Self::dictionary(opt).map(|v| Self::backup_file(v, backup)) else {
+            return Ok(None);
+        };
+
+        match fs::read(&path) {
+            Ok(dictionary) => Ok(Some(dictionary)),
+            Err(e) => {
+                log::error!(
+                    "Error loading map {opt}, its dictionary at {path:?} could not be read: {e}"
+                );
+
+                if backup == 0 {
+                    push_err(
+                        resource_man.registry.err_ids.missing_map_dictionary,
+                        &FormatContext::from(
+                            [("map_name", Formattable::display(&opt))].into_iter(),
+                        ),
+                        resource_man,
+                    );
+                }
+
+                Err(true)
+            }
+        }
+    }
+
+    /// Gets the path to a map's thumbnail from its name.
+    pub fn thumbnail(opt: &LoadMapOption) -> Option<PathBuf> {
+        GameMap::path(opt).map(|v| v.join("thumbnail").with_extension(THUMBNAIL_EXT))
+    }
+
+    /// Reads the encoded thumbnail of a map, if it has one.
+    pub fn read_thumbnail(opt: &LoadMapOption) -> Option<Vec<u8>> {
+        fs::read(Self::thumbnail(opt)?).ok()
+    }
+
+    /// Reads the info of a map, or of one of its backups. Only the saved info is reported to the player if it can't be read, not its backups.
+    ///
+    /// The tiles aren't read, so this is cheap enough to do for every map in the list.
+    pub fn read_info(
+        resource_man: &ResourceManager,
+        opt: &LoadMapOption,
+        backup: usize,
+    ) -> Result<(MapInfoRaw, Option<SystemTime>), bool> {
+        let mut time = None;
+
+        let decoded: SpannedResult<MapInfoRaw> = match opt {
+            LoadMapOption::FromSave(name) => {
+                log::debug!("Trying to read map info from {name}, backup {backup}");
+
+                let path = Self::backup_file(Self::info(opt).unwrap(), backup);
+
+                let file = File::open(path).map_err(|_| false)?;
+                time = file
+                    .metadata()
+                    .and_then(|v| v.modified().or(v.accessed()))
+                    .ok();
+
+                ron::de::from_reader(BufReader::with_capacity(INFO_BUFFER_SIZE, file))
+            }
+            LoadMapOption::MainMenu => ron::de::from_bytes(MAIN_MENU_INFO),
+            LoadMapOption::Debug => unreachable!(),
+        };
+
+        match decoded {
+            Ok(v) => {
+                // copying a map around changes when its files were modified, so the saved time comes first
+                if let Some(secs) = v.save_time {
+                    time = SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(secs));
+                }
+
+                Ok((v, time))
+            }
+            Err(e) => {
+                log::error!("Error loading map {opt}, in reading info: serde: {e:?}");
+
+                if backup == 0 {
+                    push_err(
+                        resource_man.registry.err_ids.invalid_map_data,
+                        &FormatContext::from(
+                            [("map_name", Formattable::display(&opt))].into_iter(),
+                        ),
+                        resource_man,
+                    );
+                }
+
+                Err(true)
+            }
+        }
+    }
+
+    /// Reads the tiles of a map, or of one of its backups. Only the saved tiles are reported to the player if they can't be read, not their backups.
+    pub fn read_map(
+        resource_man: &ResourceManager,
+        opt: &LoadMapOption,
+        dictionary: Option<&[u8]>,
+        backup: usize,
+    ) -> Result<MapRaw, bool> {
+        let decoded = match opt {
+            LoadMapOption::FromSave(name) => {
+                log::debug!("Trying to read map data from {name}, backup {backup}");
+
+                let path = Self::backup_file(Self::map(opt).unwrap(), backup);
+
+                let file = File::open(path).map_err(|_| false)?;
+
+                read_frames(BufReader::with_capacity(MAP_BUFFER_SIZE, file), dictionary)
+            }
+            LoadMapOption::MainMenu => read_frames(MAIN_MENU_MAP, None),
+            LoadMapOption::Debug => unreachable!(),
+        };
+
+        match decoded {
+            Ok(v) => Ok(v),
+            Err(e) => {
+                log::error!("Error loading map {opt}, in reading map: {e:?}");
+
+                if backup == 0 {
+                    push_err(
+                        resource_man.registry.err_ids.invalid_map_data,
+                        &FormatContext::from(
+                            [("map_name", Formattable::display(&opt))].into_iter(),
+                        ),
+                        resource_man,
+                    );
+                }
+
+                Err(true)
+            }
+        }
+    }
+
+    /// Reads the info, dictionary and tiles of a map, migrated to the current format. If the saved files can't be read, the backups are read instead, from the newest,
+    /// and the backup that was read is returned along with them.
+    fn read_saved(resource_man: &ResourceManager, opt: &LoadMapOption) -> Result<SavedMap, bool> {
+        let backups = if GameMap::path(opt).is_some() {
+            *BACKUP_COUNTS.end() as usize
+        } else {
+            0
+        };
+
+        for backup in 0..=backups {
+            let (mut info, save_time) = match GameMap::read_info(resource_man, opt, backup) {
+                Ok(v) => v,
+                // the map wasn't saved yet
+                Err(false) if backup == 0 => return Err(false),
+                Err(_) => continue,
+            };
+
+            if !migrate::is_supported(info.version) {
+                log::error!(
+                    "Error loading map {opt}, it is of version {} but only up to {MAP_VERSION} can be read",
+                    info.version
+                );
+
+                if backup > 0 {
+                    continue;
+                }
+
+                push_err(
+                    resource_man.registry.err_ids.unsupported_map_version,
+                    &FormatContext::from(
+                        [
+                            ("map_name", Formattable::display(&opt)),
+                            ("version", Formattable::integer(&info.version)),
+                        ]
+                        .into_iter(),
+                    ),
+                    resource_man,
+                );
+
+                // a newer format could be laid out in any way, so nothing more is read, and an older backup isn't loaded over it either
+                return Err(true);
+            }
+
+            let Ok(dictionary) = GameMap::read_dictionary(resource_man, opt, &info, backup) else {
+                continue;
+            };
+
+            let map = match GameMap::read_map(resource_man, opt, dictionary.as_deref(), backup) {
+                Ok(v) => v,
+                Err(false) if backup == 0 => return Err(false),
+                Err(_) => continue,
+            };
+            let map = migrate::migrate(&mut info, map);
+
+            if backup > 0 {
+                log::warn!(
+                    "Loaded map {opt} from its backup {backup}, as its last save couldn't be read"
+                );
+            }
+
+            return Ok(SavedMap {
+                info,
+                save_time,
+                dictionary,
+                map,
+                restored_from_backup: (backup > 0).then_some(backup),
+            });
+        }
+
+        Err(true)
+    }
+
+    /// Loads a map from disk.
+    pub async fn load(
+        game: ActorRef<GameSystemMessage>,
+        resource_man: Arc<ResourceManager>,
+        opt: &LoadMapOption,
+    ) -> Result<(Self, TileEntities), bool> {
+        if let Some(path) = GameMap::path(opt) {
+            fs::create_dir_all(path).map_err(|_| false)?;
+        }
+
+        let SavedMap {
+            info,
+            save_time,
+            dictionary,
+            map,
+            restored_from_backup,
+        } = GameMap::read_saved(&resource_man, opt)?;
+
+        let mut tiles = HashMap::new();
+        let mut tile_entities = HashMap::new();
+        let mut quarantine = HashMap::new();
+        let mut missing_ids = BTreeMap::new();
+
+        let mut previous_quarantine = map.quarantine.into_iter().collect::<HashMap<_, _>>();
+
+        let seed = info.seed.unwrap_or_else(rand::random);
+
+        for (coord, id, data) in map.chunks.into_iter().flat_map(|chunk| chunk.tiles) {
+            if let Some(id) = map
+                .tile_map
+                .get(&id)
+                .and_then(|id| resource_man.interner.get(id))
+            {
+                let tile_entity =
+                    game::new_tile(resource_man.clone(), game.clone(), coord, TileId(id), seed)
+                        .await;
+
+                let (mut data, mut quarantined, mut unresolved) =
+                    data.to_data_quarantined(&resource_man.interner);
+
+                // entries quarantined before come back if their IDs are loaded again, and nothing else took their place
+                if let Some(previous) = previous_quarantine.remove(&coord) {
+                    let (restored, _, still_unresolved) =
+                        previous.to_data_quarantined(&resource_man.interner);
+
+                    let mut previous = previous.into_inner();
+                    for (key, value) in restored {
+                        if data.get(key).is_none() {
+                            data.set(key, value);
+
+                            if let Some(key) = resource_man.interner.resolve(key) {
+                                previous.remove(key);
+                            }
+                        }
+                    }
+
+                    let mut merged = quarantined.into_inner();
+                    for (key, value) in previous {
+                        merged.entry(key).or_insert(value);
+                    }
+                    quarantined = merged.into();
+
+                    unresolved.extend(still_unresolved);
+                }
+
+                for (_, id) in unresolved {
+                    *missing_ids.entry(id).or_insert(0) += 1;
+                }
+
+                if !quarantined.is_empty() {
+                    log::warn!("Some data of the tile at {coord} references unloaded IDs, and is kept aside");
+
+                    quarantine.insert(coord, (TileId(id), quarantined));
+                }
+
+                for (key, value) in data {
+                    tile_entity
+                        .send_message(TileEntityMsg::SetDataValue(key, value))
+                        .unwrap();
+                }
+
+                tiles.insert(coord, TileId(id));
+                tile_entities.insert(coord, tile_entity);
+            }
+        }
+
+        // only the tiles that were placed get their coordinates back, so nothing is covered by a tile that is gone
+        let mut footprints = HashMap::new();
+        for (coord, anchor) in map.footprints {
+            if let Some(id) = tiles.get(&anchor).copied() {
+                if tile_entities.contains_key(&anchor) && !tiles.contains_key(&coord) {
+                    tiles.insert(coord, id);
+                    footprints.insert(coord, anchor);
+                }
+            }
+        }
+
+        let mut game_map = Self {
+            opt: opt.clone(),
+            chunks: Default::default(),
+            tile_count: 0,
+            footprints,
+            info: Arc::new(Mutex::new(MapInfo {
+                save_time,
+                data: info.data.to_data(&resource_man.interner),
+                tick_count: info.tick_count,
+                seed,
+                regions: info.regions,
+                terrain: Arc::new(Terrain::from_raw(&map.terrain, &resource_man)),
+                restored_from_backup,
+                display_name: info.display_name,
+                description: info.description,
+                created_time: info
+                    .created_time
+                    .map(|v| SystemTime::UNIX_EPOCH + Duration::from_secs(v)),
+                playtime: Duration::from_secs(info.playtime),
+                played_since: Some(Instant::now()),
+            })),
+            quarantine,
+            missing_ids,
+            dictionary,
+        };
+        for (coord, id) in tiles {
+            game_map.set_tile(coord, id);
+        }
+
+        Ok((game_map, tile_entities))
+    }
+
+    /// Saves a map to disk, compressing the tiles with the zstd level of the options, and keeping as many of the previous saves as they say.
+    ///
+    /// Tiles and quarantined entries are written in coordinate order, and data entries in key order, so saving an unchanged map twice gives byte-identical files.
+    pub async fn save(
+        &self,
+        resource_man: &ResourceManager,
+        tile_entities: &TileEntities,
+        options: &SaveOptions,
+    ) -> io::Result<()> {
+        if let Some(path) = GameMap::path(&self.opt) {
+            self.write_to(
+                &path,
+                resource_man,
+                tile_entities,
+                MapFormat::of(options),
+                options.compression_level,
+                options.backup_count.max(0) as usize,
+            )
+            .await?;
+
+            log::info!("Saved map {}", self.opt);
+        }
+
+        Ok(())
+    }
+
+    /// Saves the map to its autosave instead of over its saved files. Autosaves have no backups.
+    ///
+    /// Like [GameMap::save], the tile entities only have their data read, so the map keeps running.
+    pub async fn save_autosave(
+        &self,
+        resource_man: &ResourceManager,
+        tile_entities: &TileEntities,
+        options: &SaveOptions,
+    ) -> io::Result<()> {
+        if let Some(path) = GameMap::autosave_path(&self.opt) {
+            self.write_to(
+                &path,
+                resource_man,
+                tile_entities,
+                MapFormat::of(options),
+                options.compression_level,
+                0,
+            )
+            .await?;
+
+            log::info!("Autosaved map {}", self.opt);
+        }
+
+        Ok(())
+    }
+
+    /// Writes the info, tiles and dictionary of the map into the folder, keeping the number of backups of the files that were there.
+    ///
+    /// The files are written next to the saved ones first, and only replace them once they are complete, so a save that is cut short leaves the last one as it was.
+    async fn write_to(
+        &self,
+        path: &Path,
+        resource_man: &ResourceManager,
+        tile_entities: &TileEntities,
+        format: MapFormat,
+        compression_level: i32,
+        backups: usize,
+    ) -> io::Result<()> {
+        let interner = &resource_man.interner;
+
+        fs::create_dir_all(path)?;
+
+        let info_path = path.join("info").with_extension(INFO_EXT);
+        let map_path = path.join("map").with_extension(MAP_EXT);
+        let dictionary_path = path.join("map").with_extension(DICTIONARY_EXT);
+
+        // a save that fails partway leaves nothing behind to be found later
+        let mut temp_files = TempFiles::new(&[&info_path, &map_path, &dictionary_path]);
+
+        let info = File::create(GameMap::temp_file(&info_path))?;
+
+        let mut info_writer = BufWriter::with_capacity(INFO_BUFFER_SIZE, info);
+
+        let map = File::create(GameMap::temp_file(&map_path))?;
+
+        let map_writer = BufWriter::with_capacity(MAP_BUFFER_SIZE, map);
+
+        let mut map_raw = MapRaw {
+            tiles: vec![],
+            chunks: vec![],
+            tile_map: Default::default(),
+            footprints: self
+                .footprints
+                .iter()
+                .map(|(coord, anchor)| (*coord, *anchor))
+                .collect(),
+            terrain: Default::default(),
+            // only kept while the tile it came from is still there
+            quarantine: self
+                .quarantine
+                .iter()
+                .filter(|(coord, (id, _))| self.get_tile(**coord) == Some(*id))
+                .map(|(coord, (_, data))| (*coord, data.clone()))
+                .collect(),
+        };
+        map_raw.quarantine.sort_by_key(|(coord, _)| *coord);
+        map_raw.footprints.sort_by_key(|(coord, _)| *coord);
+
+        // asked of every tile entity at once, rather than waiting on each in turn
+        let mut all_data = multi_call_iter(
+            tile_entities,
+            |reply, _| TileEntityMsg::GetData(reply),
+            None,
+        )
+        .await
+        .map_err(|err| io::Error::other(err.to_string()))?;
+
+        // the tiles are hashed, so they are sorted first to save the same map to the same bytes every time
+        let mut tiles = self.tiles().collect::<Vec<_>>();
+        tiles.sort_unstable_by_key(|(coord, _)| *coord);
+
+        let mut raw_tiles = vec![];
+        // the coordinates covered by bigger tiles are saved with the footprints
+        for (coord, id) in tiles
+            .into_iter()
+            .filter(|(coord, _)| !self.footprints.contains_key(coord))
+        {
+            if let Some(mut data) = all_data.remove(&coord) {
+                if !map_raw.tile_map.contains_key(&*id) {
+                    map_raw
+                        .tile_map
+                        .insert(*id, interner.resolve(*id).unwrap().to_string());
+                }
+
+                let transient = data
+                    .keys()
+                    .filter(|key| !resource_man.registry.is_persisted(**key))
+                    .copied()
+                    .collect::<Vec<_>>();
+                for key in transient {
+                    data.remove(key);
+                }
+                let data = data.to_raw(interner);
+
+                raw_tiles.push((coord, *id, data));
+            } else if tile_entities.contains_key(&coord) {
+                // saving without it would lose the tile for good, so the last save is kept instead
+                return Err(io::Error::other(format!(
+                    "The tile entity at {coord} stopped while the map was being saved"
+                )));
+            }
+        }
+
+        let mut info_lock = self.info.lock().await;
+        info_lock.flush_playtime();
+
+        map_raw.terrain = info_lock.terrain.to_raw(interner);
+
+        ron::ser::to_writer(
+            &mut info_writer,
+            &MapInfoRaw {
+                data: info_lock.data.to_raw(interner),
+                tile_count: (self.tile_count - self.footprints.len()) as u32,
+                tick_count: info_lock.tick_count,
+                seed: Some(info_lock.seed),
+                regions: info_lock.regions.clone(),
+                dictionary: self.dictionary.is_some(),
+                version: MAP_VERSION,
+                save_time: SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .ok()
+                    .map(|v| v.as_secs()),
+                display_name: info_lock.display_name.clone(),
+                description: info_lock.description.clone(),
+                created_time: info_lock
+                    .created_time
+                    .and_then(|v| v.duration_since(SystemTime::UNIX_EPOCH).ok())
+                    .map(|v| v.as_secs()),
+                playtime: info_lock.playtime.as_secs(),
+            },
+        )
+        .map_err(io::Error::other)?;
+        drop(info_lock);
+
+        // the rest of the map comes first, then each chunk of the tiles in a frame of its own
+        let dictionary = self.dictionary.as_deref();
+        let mut map_writer =
+            write_frame(map_writer, &map_raw, format, compression_level, dictionary)?;
+        for chunk in chunk_raw_tiles(raw_tiles) {
+            map_writer = write_frame(map_writer, &chunk, format, compression_level, dictionary)?;
+        }
+
+        info_writer.into_inner()?.sync_all()?;
+        map_writer.into_inner()?.sync_all()?;
+
+        // a map without a thumbnail still loads, so one that can't be written doesn't fail the save
+        let thumbnail_path = path.join("thumbnail").with_extension(THUMBNAIL_EXT);
+        if let Err(err) = thumbnail::write_thumbnail(&thumbnail_path, resource_man, self.tiles()) {
+            log::warn!("Could not write the thumbnail of map {}: {err}", self.opt);
+        }
+
+        // the dictionary is saved with the tiles every time, so that each backup can be read with the dictionary it was compressed with
+        if let Some(dictionary) = &self.dictionary {
+            fs::write(GameMap::temp_file(&dictionary_path), dictionary)?;
+        }
+
+        for file in [&info_path, &map_path, &dictionary_path] {
+            GameMap::rotate_backups(file, backups)?;
+        }
+
+        fs::rename(GameMap::temp_file(&info_path), &info_path)?;
+        fs::rename(GameMap::temp_file(&map_path), &map_path)?;
+        if self.dictionary.is_some() {
+            fs::rename(GameMap::temp_file(&dictionary_path), &dictionary_path)?;
+        }
+        temp_files.renamed = true;
+
+        Ok(())
+    }
+
+    /// Moves every backup of the file one further back and copies the file to the newest, keeping the number of backups.
+    ///
+    /// The file is copied rather than moved, so that it is still there until the new one replaces it. Backups past the number are removed.
+    fn rotate_backups(file: &Path, backups: usize) -> io::Result<()> {
+        for backup in (backups + 1)..=(*BACKUP_COUNTS.end() as usize) {
+            let path = GameMap::backup_file(file.to_path_buf(), backup);
+
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+
+        if backups == 0 {
+            return Ok(());
+        }
+
+        for backup in (1..=backups).rev() {
+            let from = GameMap::backup_file(file.to_path_buf(), backup - 1);
+            let to = GameMap::backup_file(file.to_path_buf(), backup);
+
+            // a file of the map that wasn't saved then, like the dictionary, isn't left over from an even older save
+            if !from.exists() {
+                if to.exists() {
+                    fs::remove_file(to)?;
+                }
+            } else if backup == 1 {
+                fs::copy(from, to)?;
+            } else {
+                fs::rename(from, to)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copies the saved files of the map to its backup, replacing the last one. Maps that aren't saved have nothing to back up.
+    pub fn write_backup(&self) -> io::Result<()> {
+        let (Some(path), Some(backup)) =
+            (GameMap::path(&self.opt), GameMap::backup_path(&self.opt))
+        else {
+            return Ok(());
+        };
+
+        if backup.exists() {
+            fs::remove_dir_all(&backup)?;
+        }
+        fs::create_dir_all(&backup)?;
+
+        for entry in fs::read_dir(path)?.flatten() {
+            if entry.path().is_file() {
+                fs::copy(entry.path(), backup.join(entry.file_name()))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Trains a zstd dictionary on the tiles of the map, to be used from the next save on. It is saved along with the tiles.
+    ///
+    /// Returns the size of the dictionary. The map should be saved right after, so that the dictionary on disk is written.
+    pub async fn train_dictionary(
+        &mut self,
+        resource_man: &ResourceManager,
+        tile_entities: &TileEntities,
+    ) -> io::Result<usize> {
+        if GameMap::dictionary(&self.opt).is_none() {
+            return Err(io::Error::other("built-in maps are not saved"));
+        }
+
+        let mut tiles = self.tiles().collect::<Vec<_>>();
+        tiles.sort_unstable_by_key(|(coord, _)| *coord);
+
+        let mut samples = vec![];
+        for (coord, id) in tiles {
+            if let Some(tile_entity) = tile_entities.get(&coord) {
+                let Ok(CallResult::Success(data)) =
+                    tile_entity.call(TileEntityMsg::GetData, None).await
+                else {
+                    continue;
+                };
+
+                let sample = (coord, *id, data.to_raw(&resource_man.interner));
+
+                // binary, as the tiles are saved by default
+                samples.push(encode_frame(&sample, MapFormat::Binary)?);
+            }
+        }
+
+        let dictionary = zstd::dict::from_samples(&samples, MAX_DICTIONARY_SIZE)?;
+
+        let size = dictionary.len();
+        self.dictionary = Some(dictionary);
+
+        log::info!("Trained a dictionary of {size} bytes for map {}", self.opt);
+
+        Ok(size)
+    }
+}
+
+/// Makes a decoder that stops at the end of the zstd frame it starts at.
+fn frame_decoder<'a, R: BufRead>(
+    reader: R,
+    dictionary: Option<&'a [u8]>,
+) -> io::Result<Decoder<'a, R>> {
+    match dictionary {
+        Some(dictionary) => Decoder::with_dictionary(reader, dictionary),
+        None => Decoder::with_buffer(reader),
+    }
+    .map(Decoder::single_frame)
+}
+
+/// Reads a map out of its zstd frames: the rest of the map in the first, then each chunk of its tiles in one of its own.
+/// Maps from before the tiles were chunked have only the first. Each frame is read in whichever [MapFormat] it was written in.
+pub fn read_frames<R: BufRead>(reader: R, dictionary: Option<&[u8]>) -> anyhow::Result<MapRaw> {
+    let mut decoder = frame_decoder(reader, dictionary)?;
+    let mut map: MapRaw = decode_frame(&mut decoder)?;
+    let mut reader = decoder.finish();
+
+    while !reader.fill_buf()?.is_empty() {
+        let mut decoder = frame_decoder(reader, dictionary)?;
+        map.chunks.push(decode_frame(&mut decoder)?);
+        reader = decoder.finish();
+    }
+
+    Ok(map)
+}
+
+/// Decodes the content of a frame, as MessagePack if it starts with [BINARY_MAGIC] and as RON otherwise.
+fn decode_frame<T: DeserializeOwned>(decoder: &mut impl Read) -> anyhow::Result<T> {
+    let mut content = vec![];
+    decoder.read_to_end(&mut content)?;
+
+    match content.strip_prefix(&BINARY_MAGIC) {
+        Some(binary) => Ok(rmp_serde::from_slice(binary)?),
+        None => Ok(ron::de::from_bytes(&content)?),
+    }
+}
+
+/// Encodes the value as the content of a frame, in the format.
+pub fn encode_frame(value: &impl Serialize, format: MapFormat) -> io::Result<Vec<u8>> {
+    match format {
+        MapFormat::Binary => {
+            let mut content = BINARY_MAGIC.to_vec();
+            // written with the field names, so that the skipped and defaulted fields still line up
+            rmp_serde::encode::write_named(&mut content, value).map_err(io::Error::other)?;
+
+            Ok(content)
+        }
+        MapFormat::Readable => ron::ser::to_string(value)
+            .map(String::into_bytes)
+            .map_err(io::Error::other),
+    }
+}
+
+/// Compresses the value into a zstd frame of its own in the format, after what the writer already has.
+pub fn write_frame<W: Write>(
+    writer: W,
+    value: &impl Serialize,
+    format: MapFormat,
+    compression_level: i32,
+    dictionary: Option<&[u8]>,
+) -> io::Result<W> {
+    let mut encoder = match dictionary {
+        Some(dictionary) => Encoder::with_dictionary(writer, compression_level, dictionary)?,
+        None => Encoder::new(writer, compression_level)?,
+    };
+    encoder.write_all(&encode_frame(value, format)?)?;
+
+    encoder.finish()
+}
+
+/// Sanitizes the name to ensure that the map can be used without problems on all platforms. This includes removing leading/trailing whitespace and periods, replacing non-alphanumeric characters, and replacing Windows disallowed names.
+///
+/// A name with nothing left once trimmed, like `..` or only spaces, becomes `empty`.
+pub fn sanitize_name(name: String) -> String {
+    let name = name.trim_matches(|c: char| c.is_whitespace() || c == '.');
+
+    if name.is_empty() {
+        return "empty".to_string();
+    }
+
+    name.replace(|c: char| !c.is_alphanumeric(), "_")
+}
+
+/// The magic number every zstd frame starts with, which the tiles of a map are saved as.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// What the content of a frame in [MapFormat::Binary] starts with, inside the compression.
+pub const BINARY_MAGIC: [u8; 4] = *b"AMMP";
+
+/// How the frames of a map are encoded, inside the compression. Maps are read in either.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MapFormat {
+    /// MessagePack, after [BINARY_MAGIC]. Quicker to save and load, and smaller.
+    #[default]
+    Binary,
+    /// RON, as maps were saved before the binary format. Slower, but can be read once decompressed.
+    Readable,
+}
+
+impl MapFormat {
+    pub fn of(options: &SaveOptions) -> Self {
+        if options.readable_maps {
+            MapFormat::Readable
+        } else {
+            MapFormat::Binary
+        }
+    }
+}
+
+/// What an entry of the map folder is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MapEntryKind {
+    /// A map with readable info and tiles.
+    Valid,
+    /// A map with its tiles but without its info, as left by an interrupted save. The info can be recreated.
+    MissingInfo,
+    /// A map with its info but without its tiles. It loads as an empty map.
+    MissingTiles,
+    /// A map whose info cannot be read, or whose tiles are not compressed map data. It does not show up in the map list.
+    Corrupted,
+    /// A file of a map written next to the saved one that never replaced it, as left by a save that was cut short.
+    /// It is valid if it reads as the file it was written for, which it can then replace to finish the save.
+    Temp { valid: bool },
+    /// An older copy of a file of a map, kept from one of its previous saves.
+    Backup,
+    /// The autosave of a map, which is newer if it was saved after the map itself.
+    Autosave { newer: bool },
+    /// A map set aside after it failed to load, with [ERROR_RECOVERY_MARKER] in its name.
+    ErrorRecovery,
+    /// Anything that is not a map or a file of one.
+    Unknown,
+}
+
+impl MapEntryKind {
+    /// Whether the player is told about the entry. Backups and autosaves older than their map are left by saving as usual.
+    pub fn needs_attention(self) -> bool {
+        !matches!(
+            self,
+            MapEntryKind::Valid | MapEntryKind::Backup | MapEntryKind::Autosave { newer: false }
+        )
+    }
+}
+
+/// What is in the name of a map set aside after it failed to load.
+pub static ERROR_RECOVERY_MARKER: &str = "-ERR-";
+
+/// What a zstd dictionary trained on map tiles starts with.
+const DICTIONARY_MAGIC: [u8; 4] = [0x37, 0xA4, 0x30, 0xEC];
+
+fn has_header(path: &Path, magic: &[u8]) -> bool {
+    let mut header = vec![0; magic.len()];
+
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok()
+        && header == magic
+}
+
+fn info_readable(path: &Path) -> bool {
+    File::open(path).is_ok_and(|file| {
+        ron::de::from_reader::<_, MapInfoRaw>(BufReader::with_capacity(INFO_BUFFER_SIZE, file))
+            .is_ok()
+    })
+}
+
+/// Gets the file a temporary file of a save was written for, if the path is one.
+fn temp_target(path: &Path) -> Option<PathBuf> {
+    (path.extension()? == TEMP_EXT).then(|| path.with_extension(""))
+}
+
+/// Whether the file reads as the file of a map at the target: the info is read whole, as it is small, and of the rest only the header.
+fn readable_as(path: &Path, target: &Path) -> bool {
+    match target.extension().and_then(|v| v.to_str()) {
+        Some(ext) if ext == INFO_EXT => info_readable(path),
+        Some(ext) if ext == MAP_EXT => has_header(path, &ZSTD_MAGIC),
+        Some(ext) if ext == DICTIONARY_EXT => has_header(path, &DICTIONARY_MAGIC),
+        _ => false,
+    }
+}
+
+/// Whether the file is a numbered backup of the info, tiles or dictionary of a map. See [GameMap::backup_file].
+fn is_backup(path: &Path) -> bool {
+    let numbered = path
+        .extension()
+        .and_then(|v| v.to_str())
+        .and_then(|v| v.parse::<i32>().ok())
+        .is_some_and(|backup| backup > 0 && BACKUP_COUNTS.contains(&backup));
+
+    numbered
+        && path
+            .with_extension("")
+            .extension()
+            .and_then(|v| v.to_str())
+            .is_some_and(|ext| [INFO_EXT, MAP_EXT, DICTIONARY_EXT].contains(&ext))
+}
+
+/// Whether the file is one of the files a map is saved as.
+fn is_save_file(name: &str) -> bool {
+    [
+        ("info", INFO_EXT),
+        ("map", MAP_EXT),
+        ("map", DICTIONARY_EXT),
+        ("thumbnail", THUMBNAIL_EXT),
+    ]
+    .into_iter()
+    .any(|(stem, ext)| name.strip_prefix(stem).and_then(|v| v.strip_prefix('.')) == Some(ext))
+}
+
+/// Classifies the folder a map is saved in, from its info and the header of its tiles.
+fn classify_save(path: &Path) -> MapEntryKind {
+    if !path.is_dir() {
+        return MapEntryKind::Unknown;
+    }
+
+    let info = path.join("info").with_extension(INFO_EXT);
+    let map = path.join("map").with_extension(MAP_EXT);
+
+    if !info.exists() {
+        return if !map.exists() {
+            MapEntryKind::Unknown
+        } else if has_header(&map, &ZSTD_MAGIC) {
+            MapEntryKind::MissingInfo
+        } else {
+            MapEntryKind::Corrupted
+        };
+    }
+
+    if !info_readable(&info) {
+        MapEntryKind::Corrupted
+    } else if !map.exists() {
+        MapEntryKind::MissingTiles
+    } else if !has_header(&map, &ZSTD_MAGIC) {
+        MapEntryKind::Corrupted
+    } else {
+        MapEntryKind::Valid
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|v| v.modified()).ok()
+}
+
+/// Classifies an entry of the map folder, or a file in the folder of a map. Only the map info and the headers of the other files are read.
+pub fn classify_map_entry(path: &Path) -> MapEntryKind {
+    if path.is_file() {
+        return if let Some(target) = temp_target(path) {
+            MapEntryKind::Temp {
+                valid: readable_as(path, &target),
+            }
+        } else if is_backup(path) {
+            MapEntryKind::Backup
+        } else {
+            MapEntryKind::Unknown
+        };
+    }
+
+    let Some(name) = path.file_name().and_then(|v| v.to_str()) else {
+        return MapEntryKind::Unknown;
+    };
+
+    let parent = path.parent().filter(|parent| {
+        name == AUTOSAVE_PATH && path.is_dir() && classify_save(parent) != MapEntryKind::Unknown
+    });
+    if let Some(parent) = parent {
+        let info = |path: &Path| modified(&path.join("info").with_extension(INFO_EXT));
+
+        return MapEntryKind::Autosave {
+            newer: info(path) > info(parent),
+        };
+    }
+
+    let kind = classify_save(path);
+    if kind == MapEntryKind::Unknown {
+        return kind;
+    }
+
+    if name.contains(ERROR_RECOVERY_MARKER) {
+        MapEntryKind::ErrorRecovery
+    } else {
+        kind
+    }
+}
+
+/// Classifies the files in the folder of a map besides the ones it is saved as, along with its autosave and the files in that.
+fn scan_save(path: &Path, prefix: &str, scanned: &mut Vec<(String, MapEntryKind)>) {
+    let Ok(entries) = fs::read_dir(path) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+
+        if name.starts_with('.') || is_save_file(&name) {
+            continue;
+        }
+
+        let kind = classify_map_entry(&entry.path());
+        scanned.push((format!("{prefix}/{name}"), kind));
+
+        if matches!(kind, MapEntryKind::Autosave { .. }) {
+            scan_save(&entry.path(), &format!("{prefix}/{name}"), scanned);
+        }
+    }
+}
+
+/// Classifies every entry of the map folder, and the files in the folders of the maps, sorted by their paths in it. Hidden entries are skipped.
+pub fn scan_map_folder(dir: &Path) -> Vec<(String, MapEntryKind)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+
+    let mut scanned = vec![];
+    for entry in entries.flatten() {
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+
+        if name.starts_with('.') {
+            continue;
+        }
+
+        let kind = classify_map_entry(&entry.path());
+        scanned.push((name.clone(), kind));
+
+        if kind != MapEntryKind::Unknown && entry.path().is_dir() {
+            scan_save(&entry.path(), &name, &mut scanned);
+        }
+    }
+
+    scanned.sort_by(|a, b| a.0.cmp(&b.0));
+
+    scanned
+}
+
+/// How many entries of the map folder there are of each kind, as told to the player.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MapFolderSummary {
+    pub valid: usize,
+    /// maps missing their info or tiles, or that can't be read
+    pub broken: usize,
+    pub valid_temps: usize,
+    pub invalid_temps: usize,
+    pub backups: usize,
+    pub autosaves: usize,
+    /// of the autosaves, the ones saved after their map
+    pub newer_autosaves: usize,
+    pub error_recoveries: usize,
+    pub unknown: usize,
+}
+
+impl MapFolderSummary {
+    pub fn new(entries: &[(String, MapEntryKind)]) -> Self {
+        let mut summary = Self::default();
+
+        for (_, kind) in entries {
+            match kind {
+                MapEntryKind::Valid => summary.valid += 1,
+                MapEntryKind::MissingInfo
+                | MapEntryKind::MissingTiles
+                | MapEntryKind::Corrupted => summary.broken += 1,
+                MapEntryKind::Temp { valid: true } => summary.valid_temps += 1,
+                MapEntryKind::Temp { valid: false } => summary.invalid_temps += 1,
+                MapEntryKind::Backup => summary.backups += 1,
+                MapEntryKind::Autosave { newer } => {
+                    summary.autosaves += 1;
+                    if *newer {
+                        summary.newer_autosaves += 1;
+                    }
+                }
+                MapEntryKind::ErrorRecovery => summary.error_recoveries += 1,
+                MapEntryKind::Unknown => summary.unknown += 1,
+            }
+        }
+
+        summary
+    }
+}
+
+/// What can be done to an entry of the map folder. See [repair_map_entry].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MapRepair {
+    /// writes fresh info next to the tiles of a map missing its info
+    RecreateInfo,
+    /// finishes a save that was cut short, by moving a valid temporary file over the file it was written for
+    PromoteTemp,
+    /// removes a temporary file that can't be read
+    RemoveTemp,
+}
+
+impl MapRepair {
+    /// Gets the repair of the kind of entry, if it has one.
+    pub fn of(kind: MapEntryKind) -> Option<Self> {
+        match kind {
+            MapEntryKind::MissingInfo => Some(MapRepair::RecreateInfo),
+            MapEntryKind::Temp { valid: true } => Some(MapRepair::PromoteTemp),
+            MapEntryKind::Temp { valid: false } => Some(MapRepair::RemoveTemp),
+            _ => None,
+        }
+    }
+
+    /// Whether the repair replaces or removes a file, which the player has to confirm first.
+    pub fn needs_confirmation(self) -> bool {
+        self != MapRepair::RecreateInfo
+    }
+}
+
+/// Repairs the entry at the path if the repair is the one for it, and returns whether it did.
+///
+/// The entry is classified again first, so one that changed since it was scanned is left alone.
+/// This never asks before replacing or removing a file, so the player has to have confirmed the repairs that do. See [MapRepair::needs_confirmation].
+pub fn repair_map_entry(path: &Path, repair: MapRepair) -> io::Result<bool> {
+    if MapRepair::of(classify_map_entry(path)) != Some(repair) {
+        return Ok(false);
+    }
+
+    match repair {
+        MapRepair::RecreateInfo => {
+            let info = File::create(path.join("info").with_extension(INFO_EXT))?;
+            let mut info_writer = BufWriter::with_capacity(INFO_BUFFER_SIZE, info);
+
+            ron::ser::to_writer(&mut info_writer, &MapInfoRaw::default())
+                .map_err(io::Error::other)?;
+            info_writer.flush()?;
+        }
+        MapRepair::PromoteTemp => {
+            if let Some(target) = temp_target(path) {
+                fs::rename(path, target)?;
+            }
+        }
+        MapRepair::RemoveTemp => fs::remove_file(path)?,
+    }
+
+    Ok(true)
+}
@@ -0,0 +1,83 @@
+use super::{THUMBNAIL_HEIGHT, THUMBNAIL_WIDTH};
+use automancy_defs::colors;
+use automancy_defs::coord::TileCoord;
+use automancy_defs::glam::vec2;
+use automancy_defs::id::TileId;
+use automancy_defs::math::{Float, Vec2, HEX_GRID_LAYOUT};
+use automancy_resources::ResourceManager;
+use hashbrown::HashMap;
+use image::{ImageFormat, Rgba, RgbaImage};
+use std::path::Path;
+use yakui::Color;
+
+/// The farthest from the middle of the tiles the thumbnail reaches, in world units, so that the tiles of a big map are still told apart.
+const MAX_THUMBNAIL_EXTENT: Float = 96.0;
+/// How much room is left around the tiles, as a part of the thumbnail.
+const THUMBNAIL_MARGIN: Float = 0.1;
+const THUMBNAIL_BACKGROUND: Color = colors::DARK_GRAY;
+
+fn rgba(color: Color) -> Rgba<u8> {
+    Rgba([color.r, color.g, color.b, 255])
+}
+
+/// Draws the tiles from the top, each hexagon in the color of its category as on the minimap, around the middle of the tiles.
+///
+/// This is done on the CPU into an image of its own, one pixel at a time by the tile under it, so it needs neither the camera nor the window.
+pub fn draw_thumbnail(
+    resource_man: &ResourceManager,
+    tiles: impl Iterator<Item = (TileCoord, TileId)>,
+) -> RgbaImage {
+    let mut colors = HashMap::new();
+    let mut sum = Vec2::ZERO;
+
+    for (coord, id) in tiles {
+        let color = resource_man
+            .registry
+            .tiles
+            .get(&id)
+            .and_then(|tile| tile.category)
+            .map_or(colors::GRAY, |category| {
+                resource_man.category_color(category)
+            });
+
+        colors.insert(coord, rgba(color));
+        sum += HEX_GRID_LAYOUT.hex_to_world_pos(*coord);
+    }
+
+    let background = rgba(THUMBNAIL_BACKGROUND);
+    if colors.is_empty() {
+        return RgbaImage::from_pixel(THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT, background);
+    }
+
+    let center = sum / colors.len() as Float;
+
+    // as far as the farthest tile, within the limit, in each direction
+    let extent = colors
+        .keys()
+        .map(|coord| (HEX_GRID_LAYOUT.hex_to_world_pos(**coord) - center).abs())
+        .fold(Vec2::ONE, Vec2::max)
+        .min(Vec2::splat(MAX_THUMBNAIL_EXTENT))
+        * (1.0 + THUMBNAIL_MARGIN);
+
+    let size = vec2(THUMBNAIL_WIDTH as Float, THUMBNAIL_HEIGHT as Float);
+    // the same scale on both axes, so that the hexagons keep their shape
+    let scale = (size / (extent * 2.0)).min_element();
+
+    RgbaImage::from_fn(THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT, |x, y| {
+        let pixel = vec2(x as Float + 0.5, y as Float + 0.5) - size / 2.0;
+        let world = center + vec2(pixel.x, -pixel.y) / scale;
+
+        let coord = TileCoord::from(HEX_GRID_LAYOUT.world_pos_to_hex(world));
+
+        colors.get(&coord).copied().unwrap_or(background)
+    })
+}
+
+/// Draws the thumbnail of the tiles and writes it to the path, as a PNG.
+pub fn write_thumbnail(
+    path: &Path,
+    resource_man: &ResourceManager,
+    tiles: impl Iterator<Item = (TileCoord, TileId)>,
+) -> image::ImageResult<()> {
+    draw_thumbnail(resource_man, tiles).save_with_format(path, ImageFormat::Png)
+}
@@ -0,0 +1,237 @@
+use automancy_defs::math::Float;
+use std::fmt::Write;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// How often the game actor publishes a new snapshot of its metrics.
+pub const PUBLISH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How many of the items produced the most are reported.
+pub const TOP_PRODUCED: usize = 20;
+
+/// The longest request header that is read before giving up on a connection.
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The last published snapshot. The game actor replaces it as a whole, and the server only clones the [Arc] out,
+/// so neither waits on the other for longer than that.
+static LATEST: RwLock<Option<Arc<MetricsSnapshot>>> = RwLock::new(None);
+
+/// Whether the metrics server was started, so that the game actor only builds snapshots when someone can read them.
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Replaces the snapshot the server serves.
+pub fn publish(snapshot: MetricsSnapshot) {
+    if let Ok(mut latest) = LATEST.write() {
+        *latest = Some(Arc::new(snapshot));
+    }
+}
+
+fn latest() -> Option<Arc<MetricsSnapshot>> {
+    LATEST.read().ok()?.clone()
+}
+
+/// The game's aggregates at one point in time, with every ID already resolved to its string.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsSnapshot {
+    /// the ticks that have happened in the map, carried across sessions
+    pub map_ticks: u64,
+    /// the base ticks the game actor ran this session
+    pub ticks_processed: u64,
+    pub logic_frequency: Float,
+    pub transfer_frequency: Float,
+    pub entity_count: usize,
+    /// the number of tiles of each category, with "none" for the uncategorized ones
+    pub tiles_by_category: Vec<(String, u64)>,
+    /// the items produced by crafting this session, the most produced first, at most [`TOP_PRODUCED`] of them
+    pub produced: Vec<(String, u64)>,
+    /// how long the last save took
+    pub last_save_duration: Option<Duration>,
+}
+
+/// Escapes a label value for the Prometheus text format.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+impl MetricsSnapshot {
+    /// Writes the snapshot in the Prometheus text exposition format.
+    pub fn encode(&self) -> String {
+        let mut out = String::new();
+
+        write_header(
+            &mut out,
+            "automancy_ticks_processed_total",
+            "counter",
+            "Base ticks the game ran this session.",
+        );
+        let _ = writeln!(
+            out,
+            "automancy_ticks_processed_total {}",
+            self.ticks_processed
+        );
+
+        write_header(
+            &mut out,
+            "automancy_map_ticks",
+            "gauge",
+            "Ticks that have happened in the map, across sessions.",
+        );
+        let _ = writeln!(out, "automancy_map_ticks {}", self.map_ticks);
+
+        write_header(
+            &mut out,
+            "automancy_ticks_per_second",
+            "gauge",
+            "Measured tick frequency of each tick stream.",
+        );
+        let _ = writeln!(
+            out,
+            "automancy_ticks_per_second{{stream=\"logic\"}} {}",
+            self.logic_frequency
+        );
+        let _ = writeln!(
+            out,
+            "automancy_ticks_per_second{{stream=\"transfer\"}} {}",
+            self.transfer_frequency
+        );
+
+        write_header(
+            &mut out,
+            "automancy_entities",
+            "gauge",
+            "Tile entities in the map.",
+        );
+        let _ = writeln!(out, "automancy_entities {}", self.entity_count);
+
+        write_header(
+            &mut out,
+            "automancy_tiles",
+            "gauge",
+            "Tiles in the map, by category.",
+        );
+        for (category, count) in &self.tiles_by_category {
+            let _ = writeln!(
+                out,
+                "automancy_tiles{{category=\"{}\"}} {count}",
+                escape_label(category)
+            );
+        }
+
+        write_header(
+            &mut out,
+            "automancy_items_produced_total",
+            "counter",
+            "Items produced by crafting this session, for the most produced items.",
+        );
+        for (item, amount) in &self.produced {
+            let _ = writeln!(
+                out,
+                "automancy_items_produced_total{{item=\"{}\"}} {amount}",
+                escape_label(item)
+            );
+        }
+
+        if let Some(duration) = self.last_save_duration {
+            write_header(
+                &mut out,
+                "automancy_last_save_seconds",
+                "gauge",
+                "How long the last save of the map took.",
+            );
+            let _ = writeln!(
+                out,
+                "automancy_last_save_seconds {}",
+                duration.as_secs_f64()
+            );
+        }
+
+        out
+    }
+}
+
+/// Answers one connection, with the latest snapshot for `GET /metrics` and an error for anything else.
+async fn handle_connection(mut stream: TcpStream) -> anyhow::Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+
+    while !request.windows(4).any(|v| v == b"\r\n\r\n") {
+        let read = stream.read(&mut buf).await?;
+        if read == 0 || request.len() + read > MAX_REQUEST_SIZE {
+            return Ok(());
+        }
+
+        request.extend_from_slice(&buf[..read]);
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let mut parts = request.lines().next().unwrap_or_default().split(' ');
+
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => {
+            ("200 OK", latest().map(|v| v.encode()).unwrap_or_default())
+        }
+        (Some("GET"), _) => ("404 Not Found", String::new()),
+        _ => ("405 Method Not Allowed", String::new()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+
+    Ok(())
+}
+
+/// Serves the metrics on the port of the local machine, until the runtime shuts down.
+///
+/// It only listens on the loopback address, as it is meant for watching long runs from the same machine.
+pub fn spawn_server(tokio: &tokio::runtime::Runtime, port: u16) {
+    ENABLED.store(true, Ordering::Relaxed);
+
+    tokio.spawn(async move {
+        let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+
+        let listener = match TcpListener::bind(addr).await {
+            Ok(listener) => listener,
+            Err(err) => {
+                log::error!("Could not serve the metrics on {addr}! Error: {err}");
+                ENABLED.store(false, Ordering::Relaxed);
+
+                return;
+            }
+        };
+
+        log::info!("Serving the metrics at http://{addr}/metrics");
+
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(async move {
+                        if let Err(err) = handle_connection(stream).await {
+                            log::debug!("Could not answer a metrics request! Error: {err}");
+                        }
+                    });
+                }
+                Err(err) => log::warn!("Could not accept a metrics connection! Error: {err}"),
+            }
+        }
+    });
+}
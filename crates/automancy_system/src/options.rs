@@ -1,30 +1,55 @@
-use crate::input::{get_default_keymap, KeyAction};
+use crate::detail::DetailPreset;
+use crate::gamepad::{get_default_gamepad_map, GamepadMap};
+use crate::input::{find_conflict, get_default_keymap, KeyMap};
+use automancy_defs::math::Float;
+use automancy_resources::types::translate::FALLBACK_LANGUAGE;
 use automancy_resources::ResourceManager;
-use hashbrown::HashMap;
 use ron::ser::PrettyConfig;
 use serde::{Deserialize, Serialize};
 use std::{
+    env,
     fs::{read_to_string, File},
+    ops::RangeInclusive,
     path::Path,
 };
 use std::{io::Write, mem};
-use winit::keyboard::Key;
 
-static OPTIONS_PATH: &str = "options.ron";
+pub static OPTIONS_PATH: &str = "options.ron";
 static MISC_OPTIONS_PATH: &str = "misc_options.ron";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MiscOptions {
     pub language: String,
+    /// the version of the game the changelog was last shown for
+    #[serde(default)]
+    pub last_seen_version: Option<String>,
+    /// the local port the metrics are served on, in the Prometheus text format. not served when unset
+    #[serde(default)]
+    pub metrics_port: Option<u16>,
 
     #[serde(skip)]
     pub synced: bool,
 }
 
+/// Gets the language of the system from its locale, as in `de_DE`, if it is set.
+fn system_language() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .flat_map(env::var)
+        .map(|v| {
+            // the encoding and the modifier, as in `de_DE.UTF-8@euro`, aren't part of the language
+            v.split(['.', '@']).next().unwrap_or_default().to_string()
+        })
+        .find(|v| !v.is_empty() && v != "C" && v != "POSIX")
+}
+
 impl Default for MiscOptions {
+    /// The language is detected from the system, falling back to [FALLBACK_LANGUAGE] if it can't be.
     fn default() -> Self {
         Self {
-            language: String::from("en_US"),
+            language: system_language().unwrap_or_else(|| FALLBACK_LANGUAGE.to_string()),
+            last_seen_version: None,
+            metrics_port: None,
             synced: false,
         }
     }
@@ -75,7 +100,16 @@ pub struct GameOptions {
     pub graphics: GraphicsOptions,
     pub audio: AudioOptions,
     pub gui: GuiOptions,
-    pub keymap: HashMap<Key, KeyAction>,
+    #[serde(default)]
+    pub saving: SaveOptions,
+    #[serde(default)]
+    pub camera: CameraOptions,
+    /// A list of key bindings, where an action can have several. The first one listed for an action is its primary binding.
+    #[serde(default)]
+    pub key_bindings: KeyMap,
+    /// The gamepad buttons bound to actions, alongside the keys.
+    #[serde(default)]
+    pub gamepad_bindings: GamepadMap,
 
     #[serde(skip)]
     pub synced: bool,
@@ -88,7 +122,10 @@ impl Default for GameOptions {
             graphics: Default::default(),
             audio: Default::default(),
             gui: Default::default(),
-            keymap: Default::default(),
+            saving: Default::default(),
+            camera: Default::default(),
+            key_bindings: Default::default(),
+            gamepad_bindings: Default::default(),
             synced: false,
         }
     }
@@ -105,33 +142,77 @@ impl GameOptions {
                 log::warn!("Error parsing options! A fresh one will be created. Error: {err}")
             })
             .unwrap_or_default();
-        let read_keymap = mem::take(&mut this.keymap);
-
-        let mut default = get_default_keymap(resource_man);
-        for (key, read_action) in read_keymap {
-            let mut modified_action = default[&key];
-            modified_action.action = read_action.action;
-
-            default.insert(key, modified_action);
-        }
+        let read_key_bindings = mem::take(&mut this.key_bindings);
+
+        let default = get_default_keymap(resource_man);
+
+        // the press type and the name of an action always come from its default
+        let mut key_bindings = read_key_bindings
+            .into_iter()
+            .flat_map(|(binding, read_action)| {
+                default
+                    .iter()
+                    .find(|(_, action)| action.action == read_action.action)
+                    .map(|(_, action)| (binding, *action))
+            })
+            .collect::<KeyMap>();
+
+        // actions missing from the options are bound to their defaults
+        let missing = default
+            .iter()
+            .filter(|(_, action)| !key_bindings.iter().any(|(_, v)| v.action == action.action))
+            .cloned()
+            .collect::<Vec<_>>();
+        key_bindings.extend(missing);
+
+        let default_gamepad = get_default_gamepad_map(resource_man);
+
+        let gamepad_bindings = mem::take(&mut this.gamepad_bindings)
+            .into_iter()
+            .flat_map(|(button, read_action)| {
+                default_gamepad
+                    .iter()
+                    .map(|(_, action)| action)
+                    .chain(default.iter().map(|(_, action)| action))
+                    .find(|action| action.action == read_action.action)
+                    .map(|action| (button, *action))
+            })
+            .collect::<GamepadMap>();
 
-        for original in &default {
-            if let Some(other) = default
-                .iter()
-                .find(|other| original.0 != other.0 && original.1.action == other.1.action)
-            {
-                log::error!(
-                    "Action {:?} has multiple bound keys! First: {:?}, second: {:?}. Resetting keymap.",
-                    original.1.action,
-                    original.0,
-                    other.0
-                );
-                default = get_default_keymap(resource_man);
-                break;
-            }
+        this.gamepad_bindings = if gamepad_bindings.is_empty() {
+            default_gamepad
+        } else {
+            gamepad_bindings
+        };
+
+        if let Some((binding, first, second)) = find_conflict(&key_bindings) {
+            log::error!(
+                "Key binding {binding:?} is bound to multiple actions! First: {first:?}, second: {second:?}. Resetting keymap."
+            );
+            key_bindings = default;
         }
 
-        this.keymap = default;
+        this.key_bindings = key_bindings;
+        this.saving.compression_level = this
+            .saving
+            .compression_level
+            .clamp(*COMPRESSION_LEVELS.start(), *COMPRESSION_LEVELS.end());
+        this.camera.min_zoom = this
+            .camera
+            .min_zoom
+            .clamp(*ZOOM_LIMITS.start(), *ZOOM_LIMITS.end());
+        this.camera.max_zoom = this
+            .camera
+            .max_zoom
+            .clamp(this.camera.min_zoom, *ZOOM_LIMITS.end());
+        this.graphics.render_scale = this
+            .graphics
+            .render_scale
+            .clamp(*RENDER_SCALES.start(), *RENDER_SCALES.end());
+        this.saving.backup_count = this
+            .saving
+            .backup_count
+            .clamp(*BACKUP_COUNTS.start(), *BACKUP_COUNTS.end());
 
         if let Err(err) = this.save() {
             log::error!("Error saving options! {err}");
@@ -185,12 +266,70 @@ impl UiScale {
     }
 }
 
+/// How many samples each pixel of the world is drawn with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MsaaSamples {
+    #[default]
+    Off,
+    X2,
+    X4,
+}
+
+impl MsaaSamples {
+    pub const ALL: [Self; 3] = [Self::Off, Self::X2, Self::X4];
+
+    pub const fn count(self) -> u32 {
+        match self {
+            MsaaSamples::Off => 1,
+            MsaaSamples::X2 => 2,
+            MsaaSamples::X4 => 4,
+        }
+    }
+}
+
+/// How the finished frames are handed to the screen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PresentMode {
+    /// vsync when the FPS limit is set to it, and the fastest mode otherwise
+    #[default]
+    Auto,
+    /// waits for the screen's refresh, never tearing
+    Fifo,
+    /// doesn't wait, replacing the frame waiting to be shown, never tearing
+    Mailbox,
+    /// doesn't wait, and can tear
+    Immediate,
+}
+
+impl PresentMode {
+    pub const ALL: [Self; 4] = [Self::Auto, Self::Fifo, Self::Mailbox, Self::Immediate];
+}
+
+/// The scales the world can be rendered at, relative to the window.
+pub const RENDER_SCALES: RangeInclusive<Float> = 0.5..=2.0;
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct GraphicsOptions {
     pub fps_limit: i32,
     pub fullscreen: bool,
     pub ui_scale: UiScale,
     pub anti_aliasing: AAType,
+    /// How far from the camera tiles keep their full cosmetic detail.
+    #[serde(default)]
+    pub detail: DetailPreset,
+    /// How many samples the world is drawn with. Counts the adapter doesn't support fall back to the next lower one.
+    #[serde(default)]
+    pub msaa: MsaaSamples,
+    /// The size the world is rendered at relative to the window, before it is scaled to fit. The UI is always drawn at the window's size.
+    #[serde(default = "default_render_scale")]
+    pub render_scale: Float,
+    /// How the frames are presented. Modes the surface doesn't support fall back to [PresentMode::Auto].
+    #[serde(default)]
+    pub present_mode: PresentMode,
+}
+
+fn default_render_scale() -> Float {
+    1.0
 }
 
 impl Default for GraphicsOptions {
@@ -200,16 +339,43 @@ impl Default for GraphicsOptions {
             fullscreen: false,
             ui_scale: UiScale::Normal,
             anti_aliasing: AAType::FXAA,
+            detail: DetailPreset::Medium,
+            msaa: MsaaSamples::Off,
+            render_scale: default_render_scale(),
+            present_mode: PresentMode::Auto,
         }
     }
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GuiOptions {
     font: Option<String>,
+    /// Whether large amounts are shown in compact notation (1.2k, 3.4M, ...).
+    pub compact_amounts: bool,
+    /// The smallest amount that gets compacted.
+    pub compact_amount_threshold: u64,
+    /// Whether windows and tips animate in, instead of showing up instantly.
+    pub animations: bool,
+}
+
+impl Default for GuiOptions {
+    fn default() -> Self {
+        Self {
+            font: None,
+            compact_amounts: true,
+            compact_amount_threshold: 1000,
+            animations: true,
+        }
+    }
 }
 
 impl GuiOptions {
+    pub fn compact_threshold(&self) -> Option<u64> {
+        self.compact_amounts
+            .then_some(self.compact_amount_threshold)
+    }
+
     pub fn get_font(&self, resource_man: &ResourceManager) -> Option<String> {
         self.font
             .clone()
@@ -228,15 +394,74 @@ impl GuiOptions {
     }
 }
 
+/// The zoom levels the limits of the camera can be set to.
+pub const ZOOM_LIMITS: RangeInclusive<Float> = 0.05..=6.0;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraOptions {
+    /// How far in the camera can zoom.
+    pub min_zoom: Float,
+    /// How far out the camera can zoom.
+    pub max_zoom: Float,
+    /// Whether the camera can be turned around, with the rotation keys or by dragging with Alt held.
+    pub rotation: bool,
+}
+
+impl Default for CameraOptions {
+    fn default() -> Self {
+        Self {
+            min_zoom: *ZOOM_LIMITS.start(),
+            max_zoom: *ZOOM_LIMITS.end(),
+            rotation: true,
+        }
+    }
+}
+
+/// The zstd levels maps can be saved with.
+pub const COMPRESSION_LEVELS: RangeInclusive<i32> = 0..=19;
+
+/// How many of the previous saves of a map can be kept.
+pub const BACKUP_COUNTS: RangeInclusive<i32> = 0..=9;
+
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SaveOptions {
+    /// The zstd level the tiles of maps are compressed with. Higher is smaller, but slower to save.
+    pub compression_level: i32,
+    /// How many of the previous saves of a map are kept next to it, to load from if the last save can't be read.
+    pub backup_count: i32,
+    /// Saves maps as RON instead of the binary format, so that they can be looked through once decompressed.
+    pub readable_maps: bool,
+}
+
+impl Default for SaveOptions {
+    fn default() -> Self {
+        Self {
+            compression_level: 3,
+            backup_count: 3,
+            readable_maps: false,
+        }
+    }
+}
+
+/// The volumes of the sound channels, from 0 to 1. The sound effects and music channels are mixed into the master one.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct AudioOptions {
+    #[serde(default = "default_master_volume")]
+    pub master_volume: f64,
     pub sfx_volume: f64,
     pub music_volume: f64,
 }
 
+fn default_master_volume() -> f64 {
+    1.0
+}
+
 impl Default for AudioOptions {
     fn default() -> Self {
         Self {
+            master_volume: default_master_volume(),
             sfx_volume: 0.5,
             music_volume: 0.5,
         }
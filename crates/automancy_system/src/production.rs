@@ -0,0 +1,216 @@
+use crate::game::GameSystemMessage;
+use crate::tile_entity::TileEntityMsg;
+use crate::util::actor::multi_call_iter_tolerant;
+use automancy_defs::{id::Id, math::Float, stack::ItemAmount};
+use hashbrown::HashMap;
+use ractor::{rpc::CallResult, ActorRef};
+use std::time::{Duration, Instant};
+
+/// The windows the production rates are given over, the shortest first.
+pub const PRODUCTION_WINDOWS: [Duration; 3] = [
+    Duration::from_secs(60),
+    Duration::from_secs(5 * 60),
+    Duration::from_secs(15 * 60),
+];
+
+/// How long each bucket of the recorder covers. The windows are this much coarse.
+const BUCKET_LENGTH: Duration = Duration::from_secs(30);
+
+/// The number of buckets the longest window is kept in.
+const PRODUCTION_BUCKETS: usize = 30;
+
+#[derive(Debug, Clone, Default)]
+struct ProductionBucket {
+    /// how many of each item the tile sent out that were taken in
+    produced: HashMap<Id, ItemAmount>,
+    /// how many of each item the tile took in
+    consumed: HashMap<Id, ItemAmount>,
+}
+
+/// Keeps how many items a tile entity took in and sent out over the longest of the [`PRODUCTION_WINDOWS`].
+///
+/// The buckets are only allocated once the tile entity moves an item, as most tiles never do.
+#[derive(Debug, Clone)]
+pub struct ProductionRecorder {
+    start: Instant,
+    /// the index of the latest bucket, counted from the start
+    current: usize,
+    buckets: Option<Box<[ProductionBucket; PRODUCTION_BUCKETS]>>,
+}
+
+impl Default for ProductionRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProductionRecorder {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            current: 0,
+            buckets: None,
+        }
+    }
+
+    /// Moves on to the bucket of now, clearing the ones that were skipped since the last time.
+    fn advance(&mut self) {
+        let index = (self.start.elapsed().as_nanos() / BUCKET_LENGTH.as_nanos()) as usize;
+
+        if let Some(buckets) = &mut self.buckets {
+            for skipped in (self.current + 1)..=index.min(self.current + PRODUCTION_BUCKETS) {
+                buckets[skipped % PRODUCTION_BUCKETS] = ProductionBucket::default();
+            }
+        }
+        self.current = self.current.max(index);
+    }
+
+    fn bucket(&mut self) -> &mut ProductionBucket {
+        self.advance();
+
+        let buckets = self.buckets.get_or_insert_with(Default::default);
+
+        &mut buckets[self.current % PRODUCTION_BUCKETS]
+    }
+
+    /// Counts items sent out by the tile entity and taken in by another.
+    pub fn record_produced(&mut self, item: Id, amount: ItemAmount) {
+        *self.bucket().produced.entry(item).or_insert(0) += amount;
+    }
+
+    /// Counts items taken in by the tile entity.
+    pub fn record_consumed(&mut self, item: Id, amount: ItemAmount) {
+        *self.bucket().consumed.entry(item).or_insert(0) += amount;
+    }
+
+    /// Sums up each window into rates per minute.
+    pub fn sample(&mut self) -> TileProduction {
+        self.advance();
+
+        let Some(buckets) = &self.buckets else {
+            return TileProduction::default();
+        };
+
+        let elapsed = self.start.elapsed();
+        let into_current = elapsed.saturating_sub(BUCKET_LENGTH * self.current as u32);
+
+        let mut rates = HashMap::<Id, ItemRates>::new();
+
+        for (window, length) in PRODUCTION_WINDOWS.iter().enumerate() {
+            let count = (length.as_nanos() / BUCKET_LENGTH.as_nanos()) as usize;
+            // the current bucket is only partly over, and a new tile entity has not been around for the whole window
+            let covered = (BUCKET_LENGTH * (count as u32 - 1) + into_current).min(elapsed);
+            let minutes = covered.as_secs_f64().max(1.0) as Float / 60.0;
+
+            for back in 0..count.min(self.current + 1) {
+                let bucket = &buckets[(self.current - back) % PRODUCTION_BUCKETS];
+
+                for (item, amount) in &bucket.produced {
+                    rates
+                        .entry(*item)
+                        .or_insert_with(|| ItemRates::new(*item))
+                        .produced[window] += *amount as Float / minutes;
+                }
+                for (item, amount) in &bucket.consumed {
+                    rates
+                        .entry(*item)
+                        .or_insert_with(|| ItemRates::new(*item))
+                        .consumed[window] += *amount as Float / minutes;
+                }
+            }
+        }
+
+        let mut items = rates.into_values().collect::<Vec<_>>();
+        items.sort_by_key(|rates| rates.item);
+
+        TileProduction { items }
+    }
+}
+
+/// How many of an item were produced and consumed per minute, over each of the [`PRODUCTION_WINDOWS`].
+#[derive(Debug, Clone, Copy)]
+pub struct ItemRates {
+    pub item: Id,
+    pub produced: [Float; PRODUCTION_WINDOWS.len()],
+    pub consumed: [Float; PRODUCTION_WINDOWS.len()],
+}
+
+impl ItemRates {
+    fn new(item: Id) -> Self {
+        Self {
+            item,
+            produced: Default::default(),
+            consumed: Default::default(),
+        }
+    }
+}
+
+/// The production rates of a tile entity, by item.
+#[derive(Debug, Clone, Default)]
+pub struct TileProduction {
+    pub items: Vec<ItemRates>,
+}
+
+/// The production rates of every tile entity of the map added up, by item.
+#[derive(Debug, Clone, Default)]
+pub struct ProductionStats {
+    pub items: Vec<ItemRates>,
+    /// when the samples were taken
+    pub taken: Option<Instant>,
+}
+
+impl ProductionStats {
+    /// Adds up the rates of the tile entities.
+    pub fn build<'a>(samples: impl IntoIterator<Item = &'a TileProduction>) -> Self {
+        let mut total = HashMap::<Id, ItemRates>::new();
+
+        for sample in samples {
+            for rates in &sample.items {
+                let entry = total
+                    .entry(rates.item)
+                    .or_insert_with(|| ItemRates::new(rates.item));
+
+                for window in 0..PRODUCTION_WINDOWS.len() {
+                    entry.produced[window] += rates.produced[window];
+                    entry.consumed[window] += rates.consumed[window];
+                }
+            }
+        }
+
+        let mut items = total.into_values().collect::<Vec<_>>();
+        items.sort_by_key(|rates| rates.item);
+
+        Self {
+            items,
+            taken: Some(Instant::now()),
+        }
+    }
+}
+
+/// Samples every tile entity of the map and adds up their production rates.
+///
+/// This calls every tile entity, so it is meant to be run as a task of its own rather than waited on.
+pub async fn collect_production_stats(
+    game: ActorRef<GameSystemMessage>,
+) -> Option<ProductionStats> {
+    let Ok(CallResult::Success((_, tile_entities))) =
+        game.call(GameSystemMessage::GetAllTiles, None).await
+    else {
+        return None;
+    };
+
+    match multi_call_iter_tolerant(
+        &tile_entities,
+        |reply, _| TileEntityMsg::GetStats(reply),
+        None,
+    )
+    .await
+    {
+        Ok(samples) => Some(ProductionStats::build(samples.values())),
+        Err(err) => {
+            log::error!("Could not collect the production stats! Error: {err:?}");
+
+            None
+        }
+    }
+}
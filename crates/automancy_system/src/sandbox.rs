@@ -0,0 +1,54 @@
+use automancy_resources::data::{Data, DataMap, DataMapRaw, DataRaw};
+use automancy_resources::registry::DataIds;
+use automancy_resources::ResourceManager;
+
+/// The sandbox settings of a map. In a sandbox, every tile can be placed and every research unlocked without items, for trying out layouts.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Sandbox {
+    pub enabled: bool,
+    /// whether the machines keep the inputs their scripts use up, which only counts while the sandbox is on
+    pub free_inputs: bool,
+}
+
+impl Sandbox {
+    /// Reads the settings from the map data. Maps without them aren't sandboxes.
+    pub fn from_data(data: &DataMap, data_ids: &DataIds) -> Self {
+        Self {
+            enabled: data.get(data_ids.sandbox) == Some(&Data::Bool(true)),
+            free_inputs: data.get(data_ids.sandbox_free_inputs) == Some(&Data::Bool(true)),
+        }
+    }
+
+    /// Writes the settings into the map data, leaving out the ones that are off.
+    pub fn write_data(&self, data: &mut DataMap, data_ids: &DataIds) {
+        for (key, value) in [
+            (data_ids.sandbox, self.enabled),
+            (data_ids.sandbox_free_inputs, self.free_inputs),
+        ] {
+            if value {
+                data.set(key, Data::Bool(true));
+            } else {
+                data.remove(key);
+            }
+        }
+    }
+
+    /// Whether the machines run without using up their inputs.
+    pub fn has_free_inputs(&self) -> bool {
+        self.enabled && self.free_inputs
+    }
+}
+
+/// Whether the map is a sandbox.
+pub fn is_sandbox(resource_man: &ResourceManager, game_data: &DataMap) -> bool {
+    Sandbox::from_data(game_data, &resource_man.registry.data_ids).enabled
+}
+
+/// Whether the map is a sandbox, from the data of its info as it is saved, for the maps that aren't loaded.
+pub fn is_sandbox_raw(resource_man: &ResourceManager, data: &DataMapRaw) -> bool {
+    resource_man
+        .interner
+        .resolve(resource_man.registry.data_ids.sandbox)
+        .and_then(|key| data.get(key))
+        .is_some_and(|value| matches!(value, DataRaw::Bool(true)))
+}
@@ -0,0 +1,208 @@
+use crate::camera::smoothstep;
+use automancy_defs::coord::{TileBounds, TileCoord};
+use automancy_defs::id::{Id, Interner, TileId};
+use automancy_defs::math::{Float, HEX_GRID_LAYOUT};
+use automancy_resources::data::Data;
+use automancy_resources::ResourceManager;
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// How far from the center terrain is generated on new maps, in tiles. Everything further has the default terrain.
+pub const GENERATED_RADIUS: u32 = 48;
+/// The size of the cells of the generation noise, in world units.
+const NOISE_SCALE: Float = 7.0;
+
+/// The ground of a map. Every coordinate has a terrain, and only the ones that differ from the default are stored.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Terrain(HashMap<TileCoord, Id>);
+
+/// The terrain as it is saved. Each row is stored as runs of coordinates with the same terrain, going along q.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TerrainRaw {
+    /// The terrain IDs the runs refer to.
+    pub palette: Vec<String>,
+    /// The runs, as their first coordinate, their length and the index of their terrain in the palette, in coordinate order.
+    pub runs: Vec<(TileCoord, u32, u32)>,
+}
+
+impl Terrain {
+    /// Gets the terrain at the coordinate.
+    pub fn get(&self, resource_man: &ResourceManager, coord: TileCoord) -> Id {
+        self.0
+            .get(&coord)
+            .copied()
+            .unwrap_or(resource_man.registry.default_terrain)
+    }
+
+    /// Sets the terrain at the coordinate.
+    pub fn set(&mut self, resource_man: &ResourceManager, coord: TileCoord, id: Id) {
+        if id == resource_man.registry.default_terrain {
+            self.0.remove(&coord);
+        } else {
+            self.0.insert(coord, id);
+        }
+    }
+
+    /// Generates the terrain of a new map from the seed, using the noise ranges of the loaded terrains.
+    pub fn generate(resource_man: &ResourceManager, seed: u64) -> Self {
+        let mut generated = resource_man
+            .registry
+            .terrains
+            .values()
+            .flat_map(|def| def.generated.map(|range| (def.id, range)))
+            .collect::<Vec<_>>();
+        generated.sort_by_key(|(id, _)| *id);
+
+        let mut terrain = Self::default();
+
+        if generated.is_empty() {
+            return terrain;
+        }
+
+        for coord in TileBounds::new(TileCoord::ZERO, GENERATED_RADIUS) {
+            let value = noise(seed, coord);
+
+            if let Some((id, _)) = generated
+                .iter()
+                .find(|(_, (min, max))| (*min..=*max).contains(&value))
+            {
+                terrain.set(resource_man, coord, *id);
+            }
+        }
+
+        terrain
+    }
+
+    /// Whether the tile can be placed at the coordinate, going by the terrain there and around it.
+    ///
+    /// Tiles list the terrains they can go on in their `terrains` data, and go on any buildable terrain without it.
+    /// Tiles with `terrain_edge` data also need a neighbor with one of the terrains listed in it.
+    pub fn can_place(&self, resource_man: &ResourceManager, id: TileId, coord: TileCoord) -> bool {
+        if *id == resource_man.registry.none {
+            return true;
+        }
+
+        let Some(def) = resource_man.registry.tiles.get(&id) else {
+            return true;
+        };
+
+        let data_ids = &resource_man.registry.data_ids;
+        let terrain = self.get(resource_man, coord);
+
+        let on = match def.data.get(data_ids.terrains) {
+            Some(Data::SetId(allowed)) => allowed.contains(&terrain),
+            _ => resource_man.terrain_buildable(terrain),
+        };
+
+        on && match def.data.get(data_ids.terrain_edge) {
+            Some(Data::SetId(edge)) => coord
+                .neighbors()
+                .into_iter()
+                .any(|neighbor| edge.contains(&self.get(resource_man, neighbor))),
+            _ => true,
+        }
+    }
+
+    pub fn to_raw(&self, interner: &Interner) -> TerrainRaw {
+        let mut cells = self.0.iter().collect::<Vec<_>>();
+        cells.sort_unstable_by_key(|(coord, _)| **coord);
+
+        let mut palette = BTreeMap::<Id, u32>::new();
+        let mut raw = TerrainRaw::default();
+
+        for (coord, id) in cells {
+            let index = match palette.get(id) {
+                Some(index) => *index,
+                None => {
+                    let Some(name) = interner.resolve(*id) else {
+                        continue;
+                    };
+
+                    let index = raw.palette.len() as u32;
+                    raw.palette.push(name.to_string());
+                    palette.insert(*id, index);
+
+                    index
+                }
+            };
+
+            if let Some((start, len, last)) = raw.runs.last_mut() {
+                if *last == index && start.y == coord.y && start.x + *len as i32 == coord.x {
+                    *len += 1;
+                    continue;
+                }
+            }
+
+            raw.runs.push((*coord, 1, index));
+        }
+
+        raw
+    }
+
+    pub fn from_raw(raw: &TerrainRaw, resource_man: &ResourceManager) -> Self {
+        let palette = raw
+            .palette
+            .iter()
+            .map(|name| {
+                let id = resource_man.interner.get(name);
+
+                if id.is_none() {
+                    log::warn!("Terrain {name} is not loaded, and is replaced with the default");
+                }
+
+                id
+            })
+            .collect::<Vec<_>>();
+
+        let mut terrain = Self::default();
+
+        for (start, len, index) in &raw.runs {
+            let Some(Some(id)) = palette.get(*index as usize) else {
+                continue;
+            };
+
+            for offset in 0..*len {
+                terrain.set(
+                    resource_man,
+                    TileCoord::new(start.x + offset as i32, start.y),
+                    *id,
+                );
+            }
+        }
+
+        terrain
+    }
+}
+
+/// Makes a generation seed out of a map name.
+pub fn seed_from_name(name: &str) -> u64 {
+    // FNV-1a, which unlike the std hasher is guaranteed to stay the same
+    name.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn lattice(seed: u64, x: i64, y: i64) -> Float {
+    let mut v = seed
+        ^ (x as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+        ^ (y as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f);
+    v ^= v >> 33;
+    v = v.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    v ^= v >> 33;
+
+    (v >> 40) as Float / (1u64 << 24) as Float
+}
+
+/// Gets the value of the generation noise at the coordinate, from 0 to 100.
+fn noise(seed: u64, coord: TileCoord) -> i32 {
+    let p = HEX_GRID_LAYOUT.hex_to_world_pos(*coord) / NOISE_SCALE;
+    let (x, y) = (p.x.floor(), p.y.floor());
+    let (tx, ty) = (smoothstep(p.x - x), smoothstep(p.y - y));
+    let (x, y) = (x as i64, y as i64);
+
+    let top = lattice(seed, x, y) * (1.0 - tx) + lattice(seed, x + 1, y) * tx;
+    let bottom = lattice(seed, x, y + 1) * (1.0 - tx) + lattice(seed, x + 1, y + 1) * tx;
+
+    ((top * (1.0 - ty) + bottom * ty) * 100.0) as i32
+}
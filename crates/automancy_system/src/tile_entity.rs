@@ -1,24 +1,81 @@
-use crate::game::{GameSystemMessage, TickUnit};
+use crate::actor_stats::MessageCounter;
+use crate::flow::{FlowRecorder, FlowSample};
+use crate::game::{GameSystemMessage, TickUnit, TPS};
+use crate::production::{ProductionRecorder, TileProduction};
 use crate::tile_entity::TileEntityMsg::*;
 use automancy_defs::id::{Id, TileId};
-use automancy_defs::{coord::TileCoord, stack::ItemStack};
+use automancy_defs::{
+    coord::TileCoord,
+    math::Float,
+    stack::{ItemAmount, ItemStack},
+};
 use automancy_resources::types::function::{OnFailAction, TileResult, TileTransactionResult};
+use automancy_resources::types::item::ItemClass;
 use automancy_resources::{
     data::{Data, DataMap},
+    inventory::{Inventory, InventoryLimits},
     FunctionInfo,
 };
 use automancy_resources::{rhai_call_options, rhai_log_err, ResourceManager};
 use automancy_resources::{rhai_render::RenderCommand, rhai_ui::RhaiUiUnit};
 use hashbrown::HashSet;
 use ractor::{Actor, ActorProcessingErr, ActorRef, RpcReplyPort};
-use rand::{thread_rng, RngCore};
+use rand::rngs::StdRng;
+use rand::{thread_rng, RngCore, SeedableRng};
 use rhai::{Dynamic, Scope};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::mem;
 use std::sync::Arc;
 use thiserror::Error;
 
 pub type TileEntityWithId = (TileId, ActorRef<TileEntityMsg>);
 
+/// How many of the latest events a tile entity keeps in its history.
+pub const TILE_HISTORY_SIZE: usize = 100;
+
+/// How many ticks a tile's tick handlers can go without doing something before the status overlay shows it as idle.
+pub const IDLE_AFTER_TICKS: TickUnit = TPS;
+
+/// What a ticking tile is doing, as the status overlay shows it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileActivity {
+    /// the tick handlers did something within the last [IDLE_AFTER_TICKS]
+    Working,
+    Idle,
+    /// the last logic tick's script failed
+    Error,
+}
+
+/// The state of a tile the overlays color it by, sent along with its render commands.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileStatus {
+    pub id: TileId,
+    /// None for the tiles without tick handlers
+    pub activity: Option<TileActivity>,
+    /// how full its buffer is, from 0 to 1, or None if it has no inventory limits
+    pub fullness: Option<Float>,
+    /// how many items it took in over the last minute
+    pub throughput: ItemAmount,
+}
+
+/// A significant event in a tile entity's history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TileHistoryEvent {
+    /// A data value was added, changed, or removed.
+    DataChanged { key: Id },
+    /// The tile sent out what its script makes.
+    CraftFinished { script: Id },
+    /// The tile took in a transfer.
+    TransferIn {
+        item: Id,
+        amount: ItemAmount,
+        from: TileCoord,
+    },
+    /// A transfer sent out by the tile was taken in.
+    TransferOut { item: Id, amount: ItemAmount },
+}
+
 fn run_tile_function<Result: 'static, const SIZE: usize>(
     resource_man: &ResourceManager,
     id: TileId,
@@ -28,14 +85,17 @@ fn run_tile_function<Result: 'static, const SIZE: usize>(
     (ast, metadata): &FunctionInfo,
     args: [(&'static str, Dynamic); SIZE],
     function: &'static str,
-) -> Option<Result> {
-    let tile_def = resource_man.registry.tiles.get(&id)?;
+    random: i32,
+) -> std::result::Result<Option<Result>, ()> {
+    let Some(tile_def) = resource_man.registry.tiles.get(&id) else {
+        return Ok(None);
+    };
     let mut rhai_state = Dynamic::from(data.clone());
 
     let mut input = rhai::Map::from([
         ("coord".into(), Dynamic::from(coord)),
         ("id".into(), Dynamic::from(id)),
-        ("random".into(), Dynamic::from_int(random())),
+        ("random".into(), Dynamic::from_int(random)),
         ("setup".into(), Dynamic::from(tile_def.data.clone())),
     ]);
 
@@ -69,10 +129,10 @@ fn run_tile_function<Result: 'static, const SIZE: usize>(
     }
 
     match result {
-        Ok(result) => result.try_cast::<Result>(),
+        Ok(result) => Ok(result.try_cast::<Result>()),
         Err(err) => {
             rhai_log_err(function, &metadata.str_id, &err, Some(coord));
-            None
+            Err(())
         }
     }
 }
@@ -118,7 +178,10 @@ pub fn collect_render_commands(
                 ("unloading", Dynamic::from_bool(unloading)),
             ],
             "tile_render",
-        ) as Option<rhai::Array>
+            random(),
+        )
+        .ok()
+        .flatten() as Option<rhai::Array>
         {
             return Some(
                 result
@@ -140,6 +203,8 @@ pub struct TileEntity {
     pub coord: TileCoord,
     /// The handle to the Resource Manager
     pub resource_man: Arc<ResourceManager>,
+    /// The counter of the messages it handles, if it is sampled.
+    pub counter: Option<Arc<MessageCounter>>,
 }
 
 /// Represents a tile entity's state. A tile entity is the actor that allows the tile to take, process, and output resources.
@@ -153,24 +218,77 @@ pub struct TileEntityState {
 
     /// The field changed since last render request.
     field_changes: HashSet<Id>,
+
+    /// The tick the tile entity was last ticked at.
+    tick: TickUnit,
+    /// The latest events of the tile entity, along with the tick they happened at. Only kept while recording.
+    history: Option<VecDeque<(TickUnit, TileHistoryEvent)>>,
+    /// what flowed into the tile entity lately, for the flow graph
+    flow: FlowRecorder,
+    /// the items the tile entity took in and sent out lately, for the production stats
+    production: ProductionRecorder,
+    /// the random stream of the tile entity's functions, see [tile_seed]
+    rng: StdRng,
+    /// the latest tick the tick handlers did something at, for the tick activity overlay
+    active_tick: Option<TickUnit>,
+    /// whether the script failed in the last logic tick, for the status overlay
+    failed: bool,
+    /// the script the game was last told the tile is configured with
+    script: Option<Id>,
+    /// the outputs of the script sent out since the last craft was counted, see [TileEntity::record_craft]
+    crafting: Inventory,
 }
 
 impl TileEntityState {
-    fn new(game: ActorRef<GameSystemMessage>) -> Self {
+    fn new(game: ActorRef<GameSystemMessage>, seed: u64) -> Self {
         Self {
             game,
 
             data: Default::default(),
 
             field_changes: HashSet::new(),
+
+            tick: 0,
+            history: None,
+            flow: FlowRecorder::new(),
+            production: ProductionRecorder::new(),
+            rng: StdRng::seed_from_u64(seed),
+            active_tick: None,
+            failed: false,
+            script: None,
+            crafting: Inventory::default(),
         }
     }
+
+    /// Adds the event to the history, if it is being recorded.
+    fn record(&mut self, event: TileHistoryEvent) {
+        let Some(history) = &mut self.history else {
+            return;
+        };
+
+        if history.len() >= TILE_HISTORY_SIZE {
+            history.pop_front();
+        }
+
+        history.push_back((self.tick, event));
+    }
 }
 
 #[derive(Debug)]
 pub enum TileEntityMsg {
+    /// a logic tick, which runs the script's `handle_tick`
     Tick {
         tick_count: TickUnit,
+        /// the count of the tick among the logic ticks, which the script gets
+        stream_tick: TickUnit,
+        /// whether what the script takes out of the buffer is put back, as in a sandbox with free inputs
+        free_inputs: bool,
+    },
+    /// a transfer tick, which runs the script's `handle_transfer_tick`
+    TransferTick {
+        tick_count: TickUnit,
+        /// the count of the tick among the transfer ticks, which the script gets
+        stream_tick: TickUnit,
     },
     Transaction {
         stack: ItemStack,
@@ -187,10 +305,12 @@ pub enum TileEntityMsg {
         requested_from_id: TileId,
         requested_from_coord: TileCoord,
     },
+    /// Collects the render commands, and with them the status the overlays color the tile by if asked for.
     CollectRenderCommands {
-        reply: RpcReplyPort<Option<Vec<RenderCommand>>>,
+        reply: RpcReplyPort<(Option<Vec<RenderCommand>>, Option<TileStatus>)>,
         loading: bool,
         unloading: bool,
+        status: bool,
     },
     SetData(DataMap),
     SetDataValue(Id, Data),
@@ -200,9 +320,233 @@ pub enum TileEntityMsg {
     GetDataValue(Id, RpcReplyPort<Option<Data>>),
     GetDataWithCoord(RpcReplyPort<(TileCoord, DataMap)>),
     GetTileConfigUi(RpcReplyPort<Option<RhaiUiUnit>>),
+    /// Starts or stops recording the history. Stopping clears it.
+    SetHistoryRecording(bool),
+    /// Gets the history, oldest first, or None if it is not being recorded.
+    GetHistory(RpcReplyPort<Option<Vec<(TickUnit, TileHistoryEvent)>>>),
+    /// Runs the script's `handle_ui_event` with the event of a custom context menu action.
+    UiEvent(Id),
+    /// Gets what flowed into the tile entity over the last minute.
+    GetFlowSample(RpcReplyPort<FlowSample>),
+    /// Gets how many items the tile entity took in and sent out per minute, over each of the production windows.
+    GetStats(RpcReplyPort<TileProduction>),
+    /// Gets the latest tick the tick handlers returned something at, or None if they never did.
+    GetActiveTick(RpcReplyPort<Option<TickUnit>>),
+    /// Takes up to the amount of the item out of the inventory under the key, replying with how much was taken.
+    /// It can be less than what was last read, as the tile may have used some of it up since.
+    TakeFromBuffer {
+        buffer: Id,
+        item: Id,
+        amount: ItemAmount,
+        reply: RpcReplyPort<ItemAmount>,
+    },
+    /// Puts up to the amount of the item into the inventory under the key, as much as the tile's limits allow,
+    /// replying with how much was put in.
+    InsertToBuffer {
+        buffer: Id,
+        item: Id,
+        amount: ItemAmount,
+        reply: RpcReplyPort<ItemAmount>,
+    },
 }
 
 impl TileEntity {
+    /// Checks the value against the schema of the key, warning about it if it does not match.
+    fn check_data(&self, key: Id, value: &Data) -> bool {
+        match self.resource_man.registry.check_data(key, value) {
+            Ok(()) => true,
+            Err(expected) => {
+                log::warn!(
+                    "Rejected data {} of the tile at {}: expected {expected:?}, but got {:?}",
+                    self.resource_man.interner.resolve(key).unwrap_or_default(),
+                    self.coord,
+                    value.kind()
+                );
+
+                false
+            }
+        }
+    }
+
+    /// Runs the tick handler of the script for the logic or the transfer tick, if the script has one.
+    ///
+    /// The script gets the count of the tick in its stream.
+    /// With free inputs, the items the logic tick took out of the buffer are put back after it, so the inputs of the machines are never used up.
+    fn tick(
+        &self,
+        state: &mut TileEntityState,
+        stream_tick: TickUnit,
+        transfer: bool,
+        free_inputs: bool,
+    ) -> Result<(), ActorProcessingErr> {
+        let tile_def = self
+            .resource_man
+            .registry
+            .tiles
+            .get(&self.id)
+            .ok_or(Box::new(TileEntityError::NonExistent(self.coord)))?;
+
+        let Some(function @ (_, metadata)) = tile_def
+            .function
+            .as_ref()
+            .and_then(|v| self.resource_man.functions.get(v))
+        else {
+            return Ok(());
+        };
+
+        let name = if transfer {
+            if !metadata.handles_transfer_tick {
+                return Ok(());
+            }
+
+            "handle_transfer_tick"
+        } else {
+            if !metadata.handles_tick {
+                return Ok(());
+            }
+
+            "handle_tick"
+        };
+
+        let buffer_id = self.resource_man.registry.data_ids.buffer;
+        let kept_buffer = (free_inputs && !transfer)
+            .then(|| state.data.get(buffer_id).cloned())
+            .flatten();
+
+        let result: Result<Option<TileResult>, ()> = self.try_run_function(
+            state,
+            function,
+            // wrapped into the script integer range, as scripts cannot hold a u64
+            [(
+                "tick",
+                Dynamic::from_int((stream_tick % rhai::INT::MAX as TickUnit) as rhai::INT),
+            )],
+            name,
+        );
+
+        if !transfer {
+            state.failed = result.is_err();
+        }
+        let result = result.ok().flatten();
+
+        if let Some(Data::Inventory(kept)) = kept_buffer {
+            if let Some(Data::Inventory(buffer)) = state.data.get_mut(buffer_id) {
+                for (id, amount) in kept.iter() {
+                    let used = *amount - buffer.get(*id);
+
+                    if used > 0 {
+                        buffer.add(*id, used);
+                    }
+                }
+            } else {
+                state.data.set(buffer_id, Data::Inventory(kept));
+            }
+        }
+
+        if !transfer {
+            state.flow.record_tick(result.is_some());
+        }
+
+        if let Some(result) = result {
+            state.active_tick = Some(state.tick);
+
+            self.handle_rhai_result(state, result);
+        }
+
+        Ok(())
+    }
+
+    /// Runs a function of the tile's script, recording the data it changed if the history is being recorded.
+    fn run_function<Result: 'static, const SIZE: usize>(
+        &self,
+        state: &mut TileEntityState,
+        function: &FunctionInfo,
+        args: [(&'static str, Dynamic); SIZE],
+        name: &'static str,
+    ) -> Option<Result> {
+        self.try_run_function(state, function, args, name)
+            .ok()
+            .flatten()
+    }
+
+    /// Like [Self::run_function], but errs if the script failed, which has been logged already.
+    fn try_run_function<Result: 'static, const SIZE: usize>(
+        &self,
+        state: &mut TileEntityState,
+        function: &FunctionInfo,
+        args: [(&'static str, Dynamic); SIZE],
+        name: &'static str,
+    ) -> std::result::Result<Option<Result>, ()> {
+        let old_data = state.history.is_some().then(|| state.data.clone());
+
+        let result = run_tile_function(
+            &self.resource_man,
+            self.id,
+            self.coord,
+            &mut state.data,
+            &mut state.field_changes,
+            function,
+            args,
+            name,
+            state.rng.next_u32() as i32,
+        );
+
+        if let Some(old_data) = old_data {
+            let mut changed = state
+                .data
+                .keys()
+                .chain(old_data.keys())
+                .copied()
+                .filter(|k| state.data.get(*k) != old_data.get(*k))
+                .collect::<Vec<_>>();
+            changed.sort();
+            changed.dedup();
+
+            for key in changed {
+                state.record(TileHistoryEvent::DataChanged { key });
+            }
+        }
+
+        result
+    }
+
+    /// Gets what the overlays color the tile by.
+    fn status(&self, state: &mut TileEntityState) -> TileStatus {
+        let ticks = self
+            .resource_man
+            .registry
+            .tiles
+            .get(&self.id)
+            .and_then(|tile| tile.function.as_ref())
+            .and_then(|function| self.resource_man.functions.get(function))
+            .is_some_and(|(_, metadata)| metadata.handles_tick || metadata.handles_transfer_tick);
+
+        let activity = ticks.then(|| {
+            if state.failed {
+                TileActivity::Error
+            } else if state.active_tick.is_some_and(|active_tick| {
+                state.tick.saturating_sub(active_tick) < IDLE_AFTER_TICKS
+            }) {
+                TileActivity::Working
+            } else {
+                TileActivity::Idle
+            }
+        });
+
+        let limits = InventoryLimits::of_tile(&self.resource_man, self.id, &state.data);
+        let fullness = match state.data.get(self.resource_man.registry.data_ids.buffer) {
+            Some(Data::Inventory(buffer)) => buffer.fullness(limits),
+            _ => Inventory::default().fullness(limits),
+        };
+
+        TileStatus {
+            id: self.id,
+            activity,
+            fullness,
+            throughput: state.flow.total_inflow(),
+        }
+    }
+
     fn handle_rhai_transaction_result(
         &self,
         state: &mut TileEntityState,
@@ -263,6 +607,16 @@ impl TileEntity {
                 source_coord,
                 root_coord,
             } => {
+                state.record(TileHistoryEvent::TransferIn {
+                    item: consumed.id,
+                    amount: consumed.amount,
+                    from: source_coord,
+                });
+                state.flow.record_inflow(root_coord, consumed.amount);
+                state
+                    .production
+                    .record_consumed(consumed.id, consumed.amount);
+
                 send_to_tile(
                     state,
                     self.coord,
@@ -276,6 +630,76 @@ impl TileEntity {
         }
     }
 
+    /// Counts the crafts of the tile's script that the sent out items complete.
+    ///
+    /// What was sent out is added up until it makes all of the script's outputs, so an output sent out in parts
+    /// counts once it is all out, and several crafts sent out together count as several.
+    fn record_craft(&self, state: &mut TileEntityState, stack: ItemStack) {
+        let Some(def) = state
+            .script
+            .and_then(|script| self.resource_man.registry.scripts.get(&script))
+        else {
+            return;
+        };
+
+        let mut outputs = Inventory::default();
+        for output in &def.instructions.outputs {
+            outputs.add(output.id, output.amount);
+        }
+
+        if outputs.get(stack.id) <= 0 {
+            return;
+        }
+        state.crafting.add(stack.id, stack.amount);
+
+        let crafts = outputs
+            .iter()
+            .filter(|(_, amount)| **amount > 0)
+            .map(|(id, amount)| state.crafting.get(*id) / amount)
+            .min()
+            .unwrap_or(0);
+        if crafts <= 0 {
+            return;
+        }
+
+        for (id, amount) in outputs.iter() {
+            state.crafting.take(*id, amount * crafts);
+        }
+
+        let _ = state
+            .game
+            .send_message(GameSystemMessage::RecordCraft(def.id, crafts as u64));
+
+        for _ in 0..crafts {
+            state.record(TileHistoryEvent::CraftFinished { script: def.id });
+        }
+    }
+
+    /// Tells the game when the script the tile is configured with changes, so that it can keep count of them.
+    fn report_script(&self, myself: &ActorRef<TileEntityMsg>, state: &mut TileEntityState) {
+        let script = state
+            .data
+            .get(self.resource_man.registry.data_ids.script)
+            .cloned()
+            .and_then(Data::into_id);
+
+        if script == state.script {
+            return;
+        }
+
+        state.script = script;
+        // what was sent out before counts towards the old script only
+        state.crafting = Inventory::default();
+
+        let _ = state
+            .game
+            .send_message(GameSystemMessage::SetConfiguredScript {
+                coord: self.coord,
+                actor: myself.get_id(),
+                script,
+            });
+    }
+
     fn handle_rhai_result(&self, state: &mut TileEntityState, result: TileResult) {
         match result {
             TileResult::MakeTransaction {
@@ -331,18 +755,56 @@ impl TileEntity {
         root_id: TileId,
     ) -> Option<GameSystemMessage> {
         let tile = self.resource_man.registry.tiles.get(&self.id)?;
+        let data_ids = &self.resource_man.registry.data_ids;
+
+        // fluids only flow through the tiles made for them
+        if self.resource_man.item_class(stack.id) == ItemClass::Fluid {
+            let fluid_capable = state
+                .data
+                .get(data_ids.fluid_capable)
+                .or_else(|| tile.data.get(data_ids.fluid_capable))
+                == Some(&Data::Bool(true));
+
+            if !fluid_capable {
+                return None;
+            }
+        }
+
+        let mut stack = stack;
+        if let Some(transfer_rate) = self
+            .resource_man
+            .registry
+            .items
+            .get(&stack.id)
+            .and_then(|item| item.transfer_rate)
+        {
+            stack.amount = stack.amount.min(transfer_rate);
+        }
+
+        // only what fits in the buffer is offered to the script, so the rest stays with the sender
+        let limits = InventoryLimits::of_tile(&self.resource_man, self.id, &state.data)
+            .for_item(&self.resource_man, stack.id)
+            .with_threshold(&self.resource_man, &state.data, stack.id);
+        if !limits.is_unlimited() {
+            let room = match state.data.get(self.resource_man.buffer_of(stack.id)) {
+                Some(Data::Inventory(buffer)) => buffer.room_for(stack.id, limits),
+                _ => Inventory::default().room_for(stack.id, limits),
+            };
+
+            if room <= 0 {
+                return None;
+            }
+
+            stack.amount = stack.amount.min(room);
+        }
 
         if let Some(function) = tile
             .function
             .as_ref()
             .and_then(|v| self.resource_man.functions.get(v))
         {
-            if let Some(result) = run_tile_function(
-                &self.resource_man,
-                self.id,
-                self.coord,
-                &mut state.data,
-                &mut state.field_changes,
+            if let Some(result) = self.run_function(
+                state,
                 function,
                 [
                     ("source_coord", Dynamic::from(source_coord)),
@@ -371,51 +833,42 @@ pub enum TileEntityError {
 impl Actor for TileEntity {
     type Msg = TileEntityMsg;
     type State = TileEntityState;
-    type Arguments = (ActorRef<GameSystemMessage>,);
+    /// the game, and the seed of the map
+    type Arguments = (ActorRef<GameSystemMessage>, u64);
 
     async fn pre_start(
         &self,
         _myself: ActorRef<Self::Msg>,
         args: Self::Arguments,
     ) -> Result<Self::State, ActorProcessingErr> {
-        Ok(TileEntityState::new(args.0))
+        Ok(TileEntityState::new(args.0, tile_seed(args.1, self.coord)))
     }
 
     async fn handle(
         &self,
-        _myself: ActorRef<Self::Msg>,
+        myself: ActorRef<Self::Msg>,
         message: Self::Msg,
         state: &mut Self::State,
     ) -> Result<(), ActorProcessingErr> {
+        let _timer = self.counter.as_deref().map(MessageCounter::time);
+
         match message {
             Tick {
-                tick_count: _tick_count,
+                tick_count,
+                stream_tick,
+                free_inputs,
             } => {
-                let tile_def = self
-                    .resource_man
-                    .registry
-                    .tiles
-                    .get(&self.id)
-                    .ok_or(Box::new(TileEntityError::NonExistent(self.coord)))?;
+                state.tick = tick_count;
 
-                if let Some(function) = tile_def
-                    .function
-                    .as_ref()
-                    .and_then(|v| self.resource_man.functions.get(v))
-                {
-                    if let Some(result) = run_tile_function(
-                        &self.resource_man,
-                        self.id,
-                        self.coord,
-                        &mut state.data,
-                        &mut state.field_changes,
-                        function,
-                        [],
-                        "handle_tick",
-                    ) {
-                        self.handle_rhai_result(state, result);
-                    }
-                }
+                self.tick(state, stream_tick, false, free_inputs)?;
+            }
+            TransferTick {
+                tick_count,
+                stream_tick,
+            } => {
+                state.tick = tick_count;
+
+                self.tick(state, stream_tick, true, false)?;
             }
             Transaction {
                 stack,
@@ -434,6 +887,13 @@ impl Actor for TileEntity {
                 }
             }
             TransactionResult { result } => {
+                state.record(TileHistoryEvent::TransferOut {
+                    item: result.id,
+                    amount: result.amount,
+                });
+                state.production.record_produced(result.id, result.amount);
+                self.record_craft(state, result);
+
                 let tile_def = self
                     .resource_man
                     .registry
@@ -446,12 +906,8 @@ impl Actor for TileEntity {
                     .as_ref()
                     .and_then(|v| self.resource_man.functions.get(v))
                 {
-                    let _: Option<()> = run_tile_function(
-                        &self.resource_man,
-                        self.id,
-                        self.coord,
-                        &mut state.data,
-                        &mut state.field_changes,
+                    let _: Option<()> = self.run_function(
+                        state,
                         function,
                         [("transferred", Dynamic::from(result))],
                         "handle_transaction_result",
@@ -474,12 +930,8 @@ impl Actor for TileEntity {
                     .as_ref()
                     .and_then(|v| self.resource_man.functions.get(v))
                 {
-                    if let Some(result) = run_tile_function(
-                        &self.resource_man,
-                        self.id,
-                        self.coord,
-                        &mut state.data,
-                        &mut state.field_changes,
+                    if let Some(result) = self.run_function(
+                        state,
                         function,
                         [
                             ("requested_from_coord", Dynamic::from(requested_from_coord)),
@@ -504,16 +956,7 @@ impl Actor for TileEntity {
                     .as_ref()
                     .and_then(|v| self.resource_man.functions.get(v))
                 {
-                    if let Some(result) = run_tile_function(
-                        &self.resource_man,
-                        self.id,
-                        self.coord,
-                        &mut state.data,
-                        &mut state.field_changes,
-                        function,
-                        [],
-                        "tile_config",
-                    ) {
+                    if let Some(result) = self.run_function(state, function, [], "tile_config") {
                         reply.send(Some(result))?;
                     } else {
                         reply.send(None)?;
@@ -524,23 +967,40 @@ impl Actor for TileEntity {
                 reply,
                 loading,
                 unloading,
+                status,
             } => {
-                reply.send(collect_render_commands(
-                    &self.resource_man,
-                    self.id,
-                    self.coord,
-                    &mut state.data,
-                    &mut state.field_changes,
-                    loading,
-                    unloading,
+                let status = status.then(|| self.status(state));
+
+                reply.send((
+                    collect_render_commands(
+                        &self.resource_man,
+                        self.id,
+                        self.coord,
+                        &mut state.data,
+                        &mut state.field_changes,
+                        loading,
+                        unloading,
+                    ),
+                    status,
                 ))?;
             }
             SetData(data) => {
                 state.field_changes.extend(data.keys());
-                state.data = data;
+                state.data = DataMap::default();
+
+                for (key, value) in data {
+                    if self.check_data(key, &value) {
+                        state.data.set(key, value);
+                    }
+                }
             }
             SetDataValue(key, value) => {
+                if !self.check_data(key, &value) {
+                    return Ok(());
+                }
+
                 state.field_changes.insert(key);
+                state.record(TileHistoryEvent::DataChanged { key });
                 state.data.set(key, value);
             }
             TakeData(reply) => {
@@ -549,6 +1009,7 @@ impl Actor for TileEntity {
             }
             RemoveData(key) => {
                 state.field_changes.insert(key);
+                state.record(TileHistoryEvent::DataChanged { key });
                 state.data.remove(key);
             }
             GetData(reply) => {
@@ -560,8 +1021,126 @@ impl Actor for TileEntity {
             GetDataWithCoord(reply) => {
                 reply.send((self.coord, state.data.clone()))?;
             }
+            TakeFromBuffer {
+                buffer,
+                item,
+                amount,
+                reply,
+            } => {
+                let taken = match state.data.get_mut(buffer) {
+                    Some(Data::Inventory(inventory)) => inventory.take(item, amount.max(0)),
+                    _ => 0,
+                };
+
+                if taken > 0 {
+                    state.field_changes.insert(buffer);
+                    state.record(TileHistoryEvent::DataChanged { key: buffer });
+                }
+
+                reply.send(taken)?;
+            }
+            InsertToBuffer {
+                buffer,
+                item,
+                amount,
+                reply,
+            } => {
+                let data_ids = &self.resource_man.registry.data_ids;
+                // items and fluids don't mix, so each only goes in the buffer of its class
+                let wrong_class = (buffer == data_ids.buffer || buffer == data_ids.fluid_buffer)
+                    && buffer != self.resource_man.buffer_of(item);
+
+                if wrong_class
+                    || (state.data.get(buffer).is_none()
+                        && !self.check_data(buffer, &Data::Inventory(Default::default())))
+                {
+                    reply.send(0)?;
+
+                    return Ok(());
+                }
+
+                let limits = InventoryLimits::of_tile(&self.resource_man, self.id, &state.data)
+                    .for_item(&self.resource_man, item);
+
+                let inserted = match state
+                    .data
+                    .entry(buffer)
+                    .or_insert_with(|| Data::Inventory(Default::default()))
+                {
+                    Data::Inventory(inventory) => {
+                        let amount = amount.max(0);
+
+                        amount - inventory.add_limited(item, amount, limits)
+                    }
+                    _ => 0,
+                };
+
+                if inserted > 0 {
+                    state.field_changes.insert(buffer);
+                    state.record(TileHistoryEvent::DataChanged { key: buffer });
+                }
+
+                reply.send(inserted)?;
+            }
+            SetHistoryRecording(recording) => {
+                if !recording {
+                    state.history = None;
+                } else if state.history.is_none() {
+                    state.history = Some(VecDeque::with_capacity(TILE_HISTORY_SIZE));
+                }
+            }
+            GetFlowSample(reply) => {
+                let script = state
+                    .data
+                    .get(self.resource_man.registry.data_ids.script)
+                    .cloned()
+                    .and_then(Data::into_id);
+
+                reply.send(state.flow.sample(script))?;
+            }
+            GetStats(reply) => {
+                reply.send(state.production.sample())?;
+            }
+            GetActiveTick(reply) => {
+                reply.send(state.active_tick)?;
+            }
+            GetHistory(reply) => {
+                reply.send(
+                    state
+                        .history
+                        .as_ref()
+                        .map(|history| history.iter().copied().collect()),
+                )?;
+            }
+            UiEvent(event) => {
+                let tile_def = self
+                    .resource_man
+                    .registry
+                    .tiles
+                    .get(&self.id)
+                    .ok_or(Box::new(TileEntityError::NonExistent(self.coord)))?;
+
+                if let Some(function @ (_, metadata)) = tile_def
+                    .function
+                    .as_ref()
+                    .and_then(|v| self.resource_man.functions.get(v))
+                {
+                    if metadata.handles_ui_event {
+                        if let Some(result) = self.run_function(
+                            state,
+                            function,
+                            [("event", Dynamic::from(event))],
+                            "handle_ui_event",
+                        ) {
+                            self.handle_rhai_result(state, result);
+                        }
+                    }
+                }
+            }
         }
 
+        self.report_script(&myself, state);
+
         Ok(())
     }
 }
@@ -588,6 +1167,26 @@ fn send_to_tile(
     }
 }
 
+/// A random value for the functions that don't run as part of the simulation, like rendering.
+/// These don't draw from the tile entity's stream, so that what is on screen never changes what happens in the map.
 fn random() -> i32 {
     thread_rng().next_u32() as i32
 }
+
+/// Mixes the bits of the value, as in SplitMix64.
+fn mix(mut value: u64) -> u64 {
+    value = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+
+    value ^ (value >> 31)
+}
+
+/// Derives the seed of the random stream of the tile entity at the coordinate from the map's seed.
+///
+/// The streams differ from tile to tile, but are the same every time the map is run from the same save.
+pub fn tile_seed(map_seed: u64, coord: TileCoord) -> u64 {
+    let coord = ((coord.x as u32 as u64) << 32) | coord.y as u32 as u64;
+
+    mix(map_seed ^ mix(coord))
+}
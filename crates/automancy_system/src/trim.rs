@@ -0,0 +1,163 @@
+use crate::game::{GameSystemMessage, TICK_INTERVAL};
+use crate::map::{Region, Tiles};
+use automancy_defs::coord::{TileBounds, TileCoord};
+use automancy_defs::id::Id;
+use automancy_resources::ResourceManager;
+use hashbrown::{HashMap, HashSet};
+use ractor::{rpc::CallResult, ActorRef};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// How many tiles are removed per message to the game, so that ticks keep happening while a large map is trimmed.
+pub const TRIM_CHUNK_SIZE: usize = 256;
+
+/// The radius that is kept when the trim window opens.
+pub const DEFAULT_TRIM_RADIUS: u32 = 32;
+
+/// The part of the map that is kept when trimming. Everything outside of it is removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrimKeep {
+    /// the tiles within the radius of the center
+    Radius { center: TileCoord, radius: u32 },
+    /// the tiles of the region, by index
+    Region(usize),
+}
+
+impl Default for TrimKeep {
+    fn default() -> Self {
+        Self::Radius {
+            center: TileCoord::ZERO,
+            radius: DEFAULT_TRIM_RADIUS,
+        }
+    }
+}
+
+/// The tiles a trim would remove, worked out before confirming it.
+#[derive(Debug, Clone, Default)]
+pub struct TrimPreview {
+    pub doomed: Vec<TileCoord>,
+    /// how many of the doomed tiles are of each category, the most first
+    pub by_category: Vec<(Option<Id>, usize)>,
+}
+
+impl TrimPreview {
+    /// Finds the tiles outside of what is kept. A region that doesn't exist keeps nothing.
+    pub fn build(
+        resource_man: &ResourceManager,
+        tiles: &Tiles,
+        regions: &[Region],
+        keep: TrimKeep,
+    ) -> Self {
+        let kept = match keep {
+            TrimKeep::Radius { center, radius } => {
+                let bounds = TileBounds::new(center, radius);
+
+                tiles
+                    .keys()
+                    .filter(|coord| bounds.contains(**coord))
+                    .copied()
+                    .collect::<HashSet<_>>()
+            }
+            TrimKeep::Region(index) => regions
+                .get(index)
+                .map(|region| region.tiles.iter().copied().collect())
+                .unwrap_or_default(),
+        };
+
+        let mut doomed = vec![];
+        let mut categories = HashMap::<Option<Id>, usize>::new();
+
+        for (coord, id) in tiles {
+            if kept.contains(coord) {
+                continue;
+            }
+
+            doomed.push(*coord);
+
+            let category = resource_man
+                .registry
+                .tiles
+                .get(id)
+                .and_then(|tile| tile.category);
+            *categories.entry(category).or_insert(0) += 1;
+        }
+
+        doomed.sort_unstable();
+
+        let mut by_category = categories.into_iter().collect::<Vec<_>>();
+        by_category.sort_by(|(a_id, a), (b_id, b)| b.cmp(a).then_with(|| a_id.cmp(b_id)));
+
+        Self {
+            doomed,
+            by_category,
+        }
+    }
+}
+
+/// How far a trim has gotten.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrimProgress {
+    BackingUp,
+    Removing {
+        removed: usize,
+        total: usize,
+    },
+    Done {
+        removed: usize,
+    },
+    /// the backup couldn't be saved, so nothing was removed
+    BackupFailed,
+}
+
+/// Saves a backup of the map and removes the tiles, a chunk at a time, updating the progress as it goes.
+///
+/// Nothing is removed unless the backup was saved. The removed tiles are undone as one step.
+pub async fn trim_map(
+    game: ActorRef<GameSystemMessage>,
+    doomed: Vec<TileCoord>,
+    progress: Arc<Mutex<Option<TrimProgress>>>,
+) {
+    *progress.lock().await = Some(TrimProgress::BackingUp);
+
+    let Ok(CallResult::Success(true)) = game.call(GameSystemMessage::BackupMap, None).await else {
+        *progress.lock().await = Some(TrimProgress::BackupFailed);
+
+        return;
+    };
+
+    let total = doomed.len();
+    let mut removed = 0;
+
+    *progress.lock().await = Some(TrimProgress::Removing { removed, total });
+
+    let mut chunks = doomed.chunks(TRIM_CHUNK_SIZE).peekable();
+    while let Some(chunk) = chunks.next() {
+        let last = chunks.peek().is_none();
+
+        match game
+            .call(
+                |reply| GameSystemMessage::TrimTiles {
+                    coords: chunk.to_vec(),
+                    last,
+                    reply,
+                },
+                None,
+            )
+            .await
+        {
+            Ok(CallResult::Success(count)) => removed += count,
+            other => {
+                log::error!("Could not trim a part of the map! Result: {other:?}");
+
+                break;
+            }
+        }
+
+        *progress.lock().await = Some(TrimProgress::Removing { removed, total });
+
+        // lets the game tick between the chunks
+        tokio::time::sleep(TICK_INTERVAL).await;
+    }
+
+    *progress.lock().await = Some(TrimProgress::Done { removed });
+}
@@ -1,14 +1,56 @@
+use crate::camera::{CameraPath, GameCamera};
+use crate::changelog::ChangelogEntry;
+use crate::game::PlacementFailures;
+use crate::input::{ActionType, InputHandler};
+use crate::input_routing::{InputRoute, InputRouter};
+use crate::map::{MapEntryKind, MapRepair};
+use crate::sandbox::Sandbox;
+use crate::trim::TrimKeep;
 use automancy_defs::{
     coord::TileCoord,
     glam::vec2,
     id::{Id, ModelId, TileId},
-    math::Vec2,
+    math::{Float, Matrix4, Vec2},
+    stack::ItemAmount,
 };
 use automancy_resources::data::DataMap;
+use automancy_resources::error::take_err_toasts;
 use enum_map::{enum_map, Enum, EnumMap};
 use fuzzy_matcher::skim::SkimMatcherV2;
 use hashbrown::{HashMap, HashSet};
-use std::{fmt::Debug, mem};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::{fmt::Debug, mem, time::Instant};
+
+/// The most toasts shown at once. The oldest one makes room when another is pushed.
+pub const MAX_TOASTS: usize = 5;
+
+/// The toasts queued from outside the UI, such as by the game actor, that are shown from the next frame on.
+static QUEUED_TOASTS: Mutex<Vec<(ToastLevel, String)>> = Mutex::new(Vec::new());
+
+/// Queues a toast to be shown from the next frame on. Unlike [UiState::push_toast], this can be called from any thread.
+pub fn queue_toast(level: ToastLevel, text: String) {
+    if let Ok(mut queued) = QUEUED_TOASTS.lock() {
+        queued.push((level, text));
+    }
+}
+
+/// How important a toast is, which decides its color and how long it stays up.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum ToastLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+/// A short message shown to the player in the corner, that fades away after a few seconds.
+#[derive(Clone, Debug)]
+pub struct Toast {
+    pub level: ToastLevel,
+    pub text: String,
+    pub shown_at: Instant,
+}
 
 /// The state of the main game GUI.
 #[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
@@ -44,13 +86,130 @@ pub enum PopupState {
     MapCreate,
     MapDeleteConfirmation(String),
     InvalidName,
+    /// the unloaded IDs the loaded map references, and how many times
+    MissingIds(Vec<(String, usize)>),
+    /// the entries of the map folder, found when the load screen first opened, some of which need attention
+    MapFolderIssues(Vec<(String, MapEntryKind)>),
+    /// asks before the repair replaces or removes the files of the map folder at the paths
+    MapRepairConfirmation(MapRepair, Vec<String>),
+    /// the changelog entries shown in the "what's new" window
+    Changelog(Vec<ChangelogEntry>),
+    /// asks before trimming the map, with the number of tiles that would be removed
+    TrimConfirmation(usize),
+    /// offers to load the autosave of the map that couldn't be read
+    AutosaveFallback(String),
+    /// asks before importing the exported map at the path, with the namespaces it references that aren't loaded
+    MapImportConfirmation(PathBuf, Vec<String>),
+}
+
+/// What an entry of the tile context menu does.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum ContextMenuEntry {
+    /// a registered action, by index
+    Action(usize),
+    /// a custom action of the tile, sending the event to its script
+    Custom { event: Id },
+}
+
+/// The open context menu of a tile.
+#[derive(Clone, Debug)]
+pub struct ContextMenuState {
+    /// the tile the menu is for
+    pub coord: TileCoord,
+    /// the entries, along with the text they are shown with
+    pub entries: Vec<(String, ContextMenuEntry)>,
+    /// the entry the keyboard navigation is on
+    pub highlighted: usize,
+    /// when the menu was opened
+    pub opened: Instant,
+}
+
+/// A tile that failed to be placed, flashed at its position for a moment.
+#[derive(Clone, Debug)]
+pub struct PlacementFlash {
+    pub coord: TileCoord,
+    pub at: Instant,
+    /// the reason it failed, floating up from where the cursor was. left out for the tiles of bulk placements
+    pub text: Option<(String, Vec2)>,
+}
+
+/// Placing tiles by holding the button down and moving across the map.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlacementDrag {
+    /// where the drag last placed a tile, which is turned toward the next one
+    pub last_placed: Option<TileCoord>,
+    /// set once the player ran out of the item the tile's category needs, so that nothing more is placed until the button is let go
+    pub stopped: bool,
+}
+
+/// The map overlay that recolors the visible tiles.
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
+pub enum OverlayMode {
+    #[default]
+    None,
+    Category,
+    /// whether the machines are working, idle, or failing
+    Status,
+    /// how many items the tiles take in, as a heat map
+    Throughput,
+    /// how full the storages are
+    Fullness,
+}
+
+impl OverlayMode {
+    pub fn next(self) -> Self {
+        match self {
+            OverlayMode::None => OverlayMode::Category,
+            OverlayMode::Category => OverlayMode::Status,
+            OverlayMode::Status => OverlayMode::Throughput,
+            OverlayMode::Throughput => OverlayMode::Fullness,
+            OverlayMode::Fullness => OverlayMode::None,
+        }
+    }
+}
+
+/// The column the script usage table in the debug menu is sorted by.
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
+pub enum ScriptUsageSort {
+    #[default]
+    Name,
+    Configured,
+    Crafted,
+}
+
+/// What the items of the inventory window are sorted by. Items that are the same by it are sorted by name.
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
+pub enum InventorySort {
+    #[default]
+    Name,
+    /// the most first
+    Amount,
+    /// the category the item is needed to place tiles of, by the order of the categories. items without one come last
+    Category,
+}
+
+/// Which scripts the recipe browser lists for an item.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum RecipeLookup {
+    /// the scripts that take the item in
+    Consuming(Id),
+    /// the scripts that put the item out
+    Producing(Id),
 }
 
 #[derive(Eq, PartialEq, Ord, PartialOrd, Enum, Clone, Copy, Debug)]
 pub enum TextField {
     Filter,
+    /// The search of the tile selection, across every category.
+    TileSearch,
     MapRenaming,
     MapName,
+    MapDescription,
+    RegionName,
+    RegionRenaming,
+    BookmarkRenaming,
+    RecipeSearch,
+    InventoryFilter,
 }
 
 pub struct TextFieldState {
@@ -72,8 +231,14 @@ impl Default for TextFieldState {
             fuse: SkimMatcherV2::default().ignore_case(),
             fields: enum_map! {
                 TextField::Filter => Default::default(),
+                TextField::TileSearch => Default::default(),
                 TextField::MapName => Default::default(),
-                TextField::MapRenaming => Default::default()
+                TextField::MapRenaming => Default::default(),
+                TextField::RegionName => Default::default(),
+                TextField::RegionRenaming => Default::default(),
+                TextField::BookmarkRenaming => Default::default(),
+                TextField::RecipeSearch => Default::default(),
+                TextField::InventoryFilter => Default::default()
             },
         }
     }
@@ -95,35 +260,120 @@ pub struct UiState {
     pub previous: Option<Screen>,
     pub substate: SubState,
     pub popup: PopupState,
+    /// what takes the input, see [UiState::input_route]
+    pub input_router: InputRouter,
 
     pub debugger_open: bool,
+    /// the column the script usage table is sorted by
+    pub script_usage_sort: ScriptUsageSort,
+
+    /// the toasts that are up, the oldest first
+    pub toasts: VecDeque<Toast>,
+    /// a message about something that is ongoing, replaced every time it is shown, and when it was last shown
+    pub status: Option<(String, Instant)>,
 
     pub text_field: TextFieldState,
 
+    /// the binding in the key map that the next key pressed replaces, by index
+    pub rebinding: Option<usize>,
+
     pub renaming_map: Option<String>,
+    /// the sandbox settings the next new map is made with
+    pub new_map_sandbox: Sandbox,
 
     pub tile_selection_category: Option<Id>,
+    /// whether the tile selection was drawn last frame, so that its search is reset once it closes
+    pub tile_selection_shown: bool,
+
+    /// the currently active map overlay
+    pub overlay_mode: OverlayMode,
+    /// whether the outlines of the map's regions are drawn
+    pub show_regions: bool,
+    /// whether the coordinates of the tiles in view are drawn over them, from the debug menu
+    pub show_tile_coords: bool,
+    /// whether the ticking tiles are tinted by how long ago they last did something, from the debug menu
+    pub show_tick_activity: bool,
+    /// whether the culling range and the part of the world on the screen are outlined, from the debug menu
+    pub show_culling_bounds: bool,
+    pub regions_open: bool,
+    /// the tiles that will make up the next region, taken from the selection
+    pub region_selection: Option<HashSet<TileCoord>>,
+    /// the region being renamed, by index
+    pub renaming_region: Option<usize>,
+    pub camera_path_open: bool,
+    /// the camera path being played back, when the playback started, and the camera as it was before
+    pub camera_playback: Option<(CameraPath, Instant, GameCamera)>,
+    pub bookmarks_open: bool,
+    /// the bookmark being renamed, by slot
+    pub renaming_bookmark: Option<usize>,
+    pub flow_graph_open: bool,
+    /// the node of the flow graph that has its tiles listed, by index
+    pub flow_graph_selected: Option<usize>,
+    pub production_stats_open: bool,
+    pub recipes_open: bool,
+    /// the item the recipe browser is looking up, or None if it lists every script
+    pub recipe_lookup: Option<RecipeLookup>,
+    pub inventory_open: bool,
+    pub inventory_sort: InventorySort,
+    pub research_tree_open: bool,
+    /// the research whose details are shown under the research tree
+    pub research_tree_selected: Option<Id>,
+    /// how far the research tree is panned, in pixels
+    pub research_tree_pan: Vec2,
+    pub research_tree_zoom: Float,
+    pub trim_open: bool,
+    /// what the trim window keeps of the map
+    pub trim_keep: TrimKeep,
+    pub map_settings_open: bool,
+    pub error_log_open: bool,
+    /// the entries of the error log that show their id and arguments, by number
+    pub error_log_expanded: HashSet<usize>,
 
     /// the currently selected tile.
     pub selected_tile_id: Option<TileId>,
-    /// the currently selected tile's model ids.
-    pub selected_tile_render_cache: Option<(TileId, Vec<ModelId>)>,
+    /// the config the selected tile is placed with, when it was picked from the map
+    pub selected_tile_data: Option<DataMap>,
+    /// the tile hovered in the tile selection, which the hotbar keys assign instead of placing
+    pub hovered_selection_tile: Option<TileId>,
+    /// the models of the ghost of the selected tile and their transforms, by the direction it faces.
+    pub ghost_render_cache: HashMap<Option<TileCoord>, Option<(TileId, Vec<(ModelId, Matrix4)>)>>,
     /// the last placed tile, to prevent repeatedly sending place requests
     pub already_placed_at: Option<TileCoord>,
+    /// the tiles being placed while the button is held, if it was pressed with a tile selected
+    pub placement_drag: Option<PlacementDrag>,
+    /// the placements that failed recently
+    pub placement_flashes: Vec<PlacementFlash>,
+    /// the failed placements made since painting tiles started, told all at once when it stops
+    pub painting_failures: PlacementFailures,
     /// the tile that has its config menu open.
     pub config_open_at: Option<TileCoord>,
+    /// how much of an item is taken out of a tile's inventory per click, or everything if 0
+    pub take_amount: ItemAmount,
     /// tile currently linking
     pub linking_tile: Option<(TileCoord, Id)>,
+    /// the open context menu, if any
+    pub context_menu: Option<ContextMenuState>,
     /// the currently grouped tiles
     pub grouped_tiles: HashSet<TileCoord>,
     /// the stored initial cursor position, for moving/copying tiles
     pub paste_from: Option<TileCoord>,
     pub paste_content: Vec<(TileCoord, TileId, Option<DataMap>)>,
-    pub paste_content_render_cache: HashMap<TileCoord, Option<(TileId, Vec<ModelId>)>>,
+    pub paste_content_render_cache: HashMap<TileCoord, Option<(TileId, Vec<(ModelId, Matrix4)>)>>,
 
     pub tile_config_ui_position: Vec2,
     pub player_ui_position: Vec2,
     pub debugger_ui_position: Vec2,
+    pub regions_ui_position: Vec2,
+    pub camera_path_ui_position: Vec2,
+    pub bookmarks_ui_position: Vec2,
+    pub flow_graph_ui_position: Vec2,
+    pub production_stats_ui_position: Vec2,
+    pub recipes_ui_position: Vec2,
+    pub inventory_ui_position: Vec2,
+    pub research_tree_ui_position: Vec2,
+    pub trim_ui_position: Vec2,
+    pub map_settings_ui_position: Vec2,
+    pub error_log_ui_position: Vec2,
 
     pub force_show_puzzle: bool,
     pub selected_research: Option<Id>,
@@ -138,17 +388,59 @@ impl Default for UiState {
             previous: Default::default(),
             substate: Default::default(),
             popup: Default::default(),
+            input_router: Default::default(),
             debugger_open: Default::default(),
+            script_usage_sort: Default::default(),
+            toasts: Default::default(),
+            status: Default::default(),
             text_field: Default::default(),
+            rebinding: None,
             renaming_map: Default::default(),
+            new_map_sandbox: Default::default(),
+            tile_selection_shown: Default::default(),
             tile_selection_category: Default::default(),
+            overlay_mode: Default::default(),
+            show_regions: false,
+            show_tile_coords: false,
+            show_tick_activity: false,
+            show_culling_bounds: false,
+            regions_open: false,
+            region_selection: Default::default(),
+            renaming_region: Default::default(),
+            camera_path_open: false,
+            camera_playback: Default::default(),
+            bookmarks_open: false,
+            renaming_bookmark: Default::default(),
+            flow_graph_open: false,
+            flow_graph_selected: Default::default(),
+            production_stats_open: false,
+            recipes_open: false,
+            recipe_lookup: None,
+            inventory_open: false,
+            inventory_sort: Default::default(),
+            research_tree_open: false,
+            research_tree_selected: None,
+            research_tree_pan: Vec2::ZERO,
+            research_tree_zoom: 1.0,
+            trim_open: false,
+            trim_keep: Default::default(),
+            map_settings_open: false,
+            error_log_open: false,
+            error_log_expanded: Default::default(),
 
             selected_tile_id: Default::default(),
-            selected_tile_render_cache: Default::default(),
+            selected_tile_data: Default::default(),
+            hovered_selection_tile: Default::default(),
+            ghost_render_cache: Default::default(),
             already_placed_at: Default::default(),
+            placement_drag: Default::default(),
+            placement_flashes: Default::default(),
+            painting_failures: Default::default(),
             config_open_at: Default::default(),
 
+            take_amount: 0,
             linking_tile: Default::default(),
+            context_menu: Default::default(),
             grouped_tiles: Default::default(),
             paste_from: Default::default(),
             paste_content: Default::default(),
@@ -157,6 +449,17 @@ impl Default for UiState {
             tile_config_ui_position: vec2(0.1, 0.1), // TODO make default pos screen center?
             player_ui_position: vec2(0.1, 0.1),
             debugger_ui_position: vec2(0.1, 0.1),
+            regions_ui_position: vec2(0.1, 0.1),
+            camera_path_ui_position: vec2(0.1, 0.1),
+            bookmarks_ui_position: vec2(0.1, 0.1),
+            flow_graph_ui_position: vec2(0.1, 0.1),
+            production_stats_ui_position: vec2(0.1, 0.1),
+            recipes_ui_position: vec2(0.1, 0.1),
+            inventory_ui_position: vec2(0.1, 0.1),
+            research_tree_ui_position: vec2(0.1, 0.1),
+            trim_ui_position: vec2(0.1, 0.1),
+            map_settings_ui_position: vec2(0.1, 0.1),
+            error_log_ui_position: vec2(0.1, 0.1),
 
             force_show_puzzle: false,
             selected_research: Default::default(),
@@ -167,6 +470,36 @@ impl Default for UiState {
 }
 
 impl UiState {
+    /// Returns what should take the input, from the screens that are open.
+    ///
+    /// Only the screens of the map being played are modal. The ones outside of it, like the main menu and the map list,
+    /// and their popups, have nothing to do with the world.
+    pub fn input_route(&self, input_handler: &InputHandler) -> InputRoute {
+        let in_map = match self.screen {
+            Screen::Ingame | Screen::Paused => true,
+            Screen::Options => self.previous == Some(Screen::Paused),
+            Screen::MainMenu | Screen::MapLoad => false,
+        };
+
+        if self.camera_playback.is_some() {
+            InputRoute::CameraPath
+        } else if !in_map {
+            InputRoute::Menu
+        } else if self.screen != Screen::Ingame
+            || self.popup != PopupState::None
+            || input_handler.key_active(ActionType::Player)
+        {
+            InputRoute::Modal
+        } else {
+            InputRoute::World
+        }
+    }
+
+    /// Returns whether a screen that covers the world is open, during which the world does not take input.
+    pub fn is_modal(&self, input_handler: &InputHandler) -> bool {
+        self.input_route(input_handler) != InputRoute::World
+    }
+
     pub fn return_screen(&mut self) {
         if let Some(prev) = self.previous {
             self.screen = prev;
@@ -197,4 +530,39 @@ impl UiState {
             false
         }
     }
+
+    /// Shows a toast above the ones already up. One with the same text that is still up is taken down, so that repeating something doesn't stack it.
+    pub fn push_toast(&mut self, level: ToastLevel, text: String) {
+        self.toasts.retain(|toast| toast.text != text);
+
+        if self.toasts.len() >= MAX_TOASTS {
+            self.toasts.pop_front();
+        }
+
+        self.toasts.push_back(Toast {
+            level,
+            text,
+            shown_at: Instant::now(),
+        });
+    }
+
+    /// Shows the toasts queued with [queue_toast], and the errors that were pushed to be shown as toasts.
+    pub fn take_queued_toasts(&mut self) {
+        let queued = QUEUED_TOASTS
+            .lock()
+            .map(|mut v| mem::take(&mut *v))
+            .unwrap_or_default();
+
+        for (level, text) in queued {
+            self.push_toast(level, text);
+        }
+
+        for (_, text) in take_err_toasts() {
+            self.push_toast(ToastLevel::Error, text);
+        }
+    }
+
+    pub fn show_status(&mut self, text: String) {
+        self.status = Some((text, Instant::now()));
+    }
 }
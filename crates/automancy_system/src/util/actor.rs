@@ -3,11 +3,46 @@ use ractor::rpc::CallResult;
 use ractor::{concurrency, ActorRef, Message, MessagingErr, RpcReplyPort};
 use std::{hash::Hash, time::Duration};
 
+/// Sends the message to every actor at once, then waits for all the replies.
+///
+/// Fails if any of the actors can't be sent to. Actors that don't reply in time are left out of the results.
 pub async fn multi_call_iter<Key, TMessage, TReply, TMsgBuilder>(
     actors: &HashMap<Key, ActorRef<TMessage>>,
     msg_builder: TMsgBuilder,
     timeout_option: Option<Duration>,
 ) -> Result<HashMap<Key, TReply>, MessagingErr<TMessage>>
+where
+    Key: Hash + Eq + Send + Sync + Copy + 'static,
+    TMessage: Message,
+    TReply: Send + 'static,
+    TMsgBuilder: Fn(RpcReplyPort<TReply>, Key) -> TMessage,
+{
+    multi_call(actors, msg_builder, timeout_option, false).await
+}
+
+/// Like [`multi_call_iter`], but actors that stopped since they were listed are left out of the results as well, rather than failing the whole call.
+///
+/// For gathering what is shown to the player, where a tile removed in the meantime has nothing to show anyway.
+pub async fn multi_call_iter_tolerant<Key, TMessage, TReply, TMsgBuilder>(
+    actors: &HashMap<Key, ActorRef<TMessage>>,
+    msg_builder: TMsgBuilder,
+    timeout_option: Option<Duration>,
+) -> Result<HashMap<Key, TReply>, MessagingErr<TMessage>>
+where
+    Key: Hash + Eq + Send + Sync + Copy + 'static,
+    TMessage: Message,
+    TReply: Send + 'static,
+    TMsgBuilder: Fn(RpcReplyPort<TReply>, Key) -> TMessage,
+{
+    multi_call(actors, msg_builder, timeout_option, true).await
+}
+
+async fn multi_call<Key, TMessage, TReply, TMsgBuilder>(
+    actors: &HashMap<Key, ActorRef<TMessage>>,
+    msg_builder: TMsgBuilder,
+    timeout_option: Option<Duration>,
+    skip_stopped: bool,
+) -> Result<HashMap<Key, TReply>, MessagingErr<TMessage>>
 where
     Key: Hash + Eq + Send + Sync + Copy + 'static,
     TMessage: Message,
@@ -24,7 +59,12 @@ where
             Some(duration) => (tx, duration).into(),
             None => tx.into(),
         };
-        actor.cast(msg_builder(port, *k))?;
+        match actor.cast(msg_builder(port, *k)) {
+            Ok(()) => {}
+            // the actor stopped since it was listed, so it has nothing to reply with
+            Err(_) if skip_stopped => continue,
+            Err(err) => return Err(err),
+        }
         rx_ports.insert(*k, rx);
     }
 
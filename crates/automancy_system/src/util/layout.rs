@@ -0,0 +1,170 @@
+use automancy_defs::math::{Float, Vec2};
+use std::collections::VecDeque;
+
+/// How many times the order of the layers is swept over, to untangle the edges.
+const ORDERING_SWEEPS: usize = 8;
+
+/// Finds the edges that make up cycles in the graph, by walking it depth first and taking the edges back to the nodes still being walked.
+///
+/// Leaving them out makes the graph acyclic.
+fn back_edges(node_count: usize, edges: &[(usize, usize)]) -> Vec<bool> {
+    let mut outgoing = vec![vec![]; node_count];
+    for (index, (from, to)) in edges.iter().enumerate() {
+        outgoing[*from].push((*to, index));
+    }
+
+    // 0 is unseen, 1 is being walked, 2 is done
+    let mut visit = vec![0u8; node_count];
+    let mut back = vec![false; edges.len()];
+
+    for root in 0..node_count {
+        if visit[root] != 0 {
+            continue;
+        }
+
+        // walked without recursion, as the graph can be deep
+        let mut stack = vec![(root, 0)];
+        visit[root] = 1;
+
+        while let Some((node, next)) = stack.last_mut() {
+            let node = *node;
+
+            if let Some(&(to, index)) = outgoing[node].get(*next) {
+                *next += 1;
+
+                match visit[to] {
+                    0 => {
+                        visit[to] = 1;
+                        stack.push((to, 0));
+                    }
+                    1 => back[index] = true,
+                    _ => {}
+                }
+            } else {
+                visit[node] = 2;
+                stack.pop();
+            }
+        }
+    }
+
+    back
+}
+
+/// Puts every node on the layer after the furthest of the nodes with edges into it, so that the edges all go forward.
+fn assign_layers(node_count: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+    let mut outgoing = vec![vec![]; node_count];
+    let mut incoming = vec![0usize; node_count];
+    for (from, to) in edges {
+        outgoing[*from].push(*to);
+        incoming[*to] += 1;
+    }
+
+    let mut layers = vec![0; node_count];
+    let mut queue = (0..node_count)
+        .filter(|node| incoming[*node] == 0)
+        .collect::<VecDeque<_>>();
+
+    while let Some(node) = queue.pop_front() {
+        for &to in &outgoing[node] {
+            layers[to] = layers[to].max(layers[node] + 1);
+
+            incoming[to] -= 1;
+            if incoming[to] == 0 {
+                queue.push_back(to);
+            }
+        }
+    }
+
+    layers
+}
+
+/// Sorts the nodes of the layer by the average position of their neighbours, keeping the ones without any where they are.
+fn sort_by_barycenter(layer: &mut [usize], neighbours: &[Vec<usize>], positions: &[Float]) {
+    let mut keyed = layer
+        .iter()
+        .enumerate()
+        .map(|(index, node)| {
+            let around = &neighbours[*node];
+
+            let key = if around.is_empty() {
+                index as Float
+            } else {
+                around.iter().map(|v| positions[*v]).sum::<Float>() / around.len() as Float
+            };
+
+            (key, *node)
+        })
+        .collect::<Vec<_>>();
+
+    keyed.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+    for (slot, (_, node)) in layer.iter_mut().zip(keyed) {
+        *slot = node;
+    }
+}
+
+/// Lays out a directed graph in layers, going from the left to the right.
+///
+/// Every edge goes at least one layer to the right, apart from the ones that close a cycle, which are left out of the layering.
+/// The nodes of a layer are ordered to cross as few edges as a few sweeps manage, and centered around 0 vertically.
+///
+/// Returns the position of each node, where the layers are 1 apart horizontally and the nodes of a layer are 1 apart vertically.
+pub fn layered(node_count: usize, edges: &[(usize, usize)]) -> Vec<Vec2> {
+    let back = back_edges(node_count, edges);
+    let forward = edges
+        .iter()
+        .zip(back)
+        .filter(|(_, back)| !back)
+        .map(|(edge, _)| *edge)
+        .filter(|(from, to)| from != to)
+        .collect::<Vec<_>>();
+
+    let node_layers = assign_layers(node_count, &forward);
+
+    let mut layers = vec![vec![]; node_layers.iter().max().map_or(0, |v| v + 1)];
+    for (node, layer) in node_layers.iter().enumerate() {
+        layers[*layer].push(node);
+    }
+
+    let mut predecessors = vec![vec![]; node_count];
+    let mut successors = vec![vec![]; node_count];
+    for (from, to) in &forward {
+        predecessors[*to].push(*from);
+        successors[*from].push(*to);
+    }
+
+    let mut positions = vec![0.0; node_count];
+    let update_positions = |layers: &[Vec<usize>], positions: &mut [Float]| {
+        for layer in layers {
+            for (index, node) in layer.iter().enumerate() {
+                positions[*node] = index as Float;
+            }
+        }
+    };
+    update_positions(&layers, &mut positions);
+
+    for sweep in 0..ORDERING_SWEEPS {
+        if sweep % 2 == 0 {
+            for layer in 1..layers.len() {
+                sort_by_barycenter(&mut layers[layer], &predecessors, &positions);
+                update_positions(&layers[layer..=layer], &mut positions);
+            }
+        } else {
+            for layer in (0..layers.len().saturating_sub(1)).rev() {
+                sort_by_barycenter(&mut layers[layer], &successors, &positions);
+                update_positions(&layers[layer..=layer], &mut positions);
+            }
+        }
+    }
+
+    let mut result = vec![Vec2::ZERO; node_count];
+    for (x, layer) in layers.iter().enumerate() {
+        let offset = (layer.len() as Float - 1.0) / 2.0;
+
+        for (y, node) in layer.iter().enumerate() {
+            result[*node] = Vec2::new(x as Float, y as Float - offset);
+        }
+    }
+
+    result
+}
@@ -1,9 +1,15 @@
-use automancy_defs::id::Id;
+use crate::game::PlacementFailure;
+use crate::sandbox::is_sandbox;
+use crate::ui_state::{queue_toast, ToastLevel};
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::{Id, TileId};
 use automancy_resources::data::{Data, DataMap};
+use automancy_resources::format::Formattable;
 use automancy_resources::ResourceManager;
 use hashbrown::HashSet;
 
 pub mod actor;
+pub mod layout;
 pub mod num;
 pub mod round;
 
@@ -24,6 +30,126 @@ pub fn is_research_unlocked(
     false
 }
 
+/// Takes the next research off the queue that can be started: the first one whose prerequisite is unlocked, and whose items are in the player inventory.
+/// Its items are taken from the player inventory, as if they were submitted.
+///
+/// The researches that aren't loaded anymore, or that depend on one that isn't, are dropped from the queue with a toast,
+/// and the ones that are already unlocked are dropped without one.
+pub fn pop_research_queue(resource_man: &ResourceManager, game_data: &mut DataMap) -> Option<Id> {
+    let data_ids = &resource_man.registry.data_ids;
+
+    let Some(Data::VecId(queue)) = game_data.get(data_ids.research_queue).cloned() else {
+        return None;
+    };
+
+    let mut next = None;
+    let mut kept = vec![];
+
+    for id in queue {
+        if next.is_some() {
+            kept.push(id);
+            continue;
+        }
+
+        let Some(research) = resource_man.get_research(id) else {
+            drop_queued_research(resource_man, id);
+            continue;
+        };
+
+        if research
+            .depends_on
+            .is_some_and(|prev| resource_man.get_research(prev).is_none())
+        {
+            drop_queued_research(resource_man, id);
+            continue;
+        }
+
+        if is_research_unlocked(id, resource_man, game_data) {
+            continue;
+        }
+
+        let ready = research.depends_on.map_or(true, |prev| {
+            is_research_unlocked(prev, resource_man, game_data)
+        }) && research
+            .required_items
+            .iter()
+            .flatten()
+            .all(|stack| game_data.contains_stack(data_ids.player_inventory, *stack));
+
+        if !ready {
+            kept.push(id);
+            continue;
+        }
+
+        if let Some(stacks) = &research.required_items {
+            if let Some(Data::Inventory(inventory)) = game_data.get_mut(data_ids.player_inventory) {
+                for stack in stacks {
+                    inventory.take(stack.id, stack.amount);
+                }
+            }
+
+            if let Data::SetId(filled) = game_data
+                .entry(data_ids.research_items_filled)
+                .or_insert_with(|| Data::SetId(HashSet::new()))
+            {
+                filled.insert(id);
+            }
+        }
+
+        next = Some(id);
+    }
+
+    game_data.set(data_ids.research_queue, Data::VecId(kept));
+
+    next
+}
+
+fn drop_queued_research(resource_man: &ResourceManager, id: Id) {
+    let name = resource_man
+        .get_research(id)
+        .map(|research| resource_man.research_str(research.name).to_string())
+        .or_else(|| resource_man.interner.resolve(id).map(str::to_string))
+        .unwrap_or_default();
+
+    queue_toast(
+        ToastLevel::Warning,
+        resource_man.gui_fmt(
+            resource_man.registry.gui_ids.lbl_research_dropped,
+            [("research", Formattable::display(&name))],
+        ),
+    );
+}
+
+/// Collects all the tiles that can be placed, which are either default tiles or unlocked by a research. In a sandbox, every tile can be.
+pub fn placeable_tiles(resource_man: &ResourceManager, game_data: &mut DataMap) -> HashSet<TileId> {
+    let sandbox = is_sandbox(resource_man, game_data);
+
+    resource_man
+        .ordered_tiles
+        .iter()
+        .filter(|id| {
+            if sandbox {
+                return true;
+            }
+
+            let is_default_tile = resource_man.registry.tiles[*id]
+                .data
+                .get(resource_man.registry.data_ids.default_tile)
+                .cloned()
+                .and_then(|v| v.into_bool())
+                .unwrap_or(false);
+
+            is_default_tile
+                || resource_man
+                    .get_research_by_unlock(**id)
+                    .is_some_and(|research| {
+                        is_research_unlocked(research.id, resource_man, game_data)
+                    })
+        })
+        .copied()
+        .collect()
+}
+
 pub fn should_category_show(
     category: Id,
     resource_man: &ResourceManager,
@@ -37,6 +163,10 @@ pub fn should_category_show(
         return false;
     };
 
+    if is_sandbox(resource_man, game_data) {
+        return true;
+    }
+
     if tiles.iter().any(|id| {
         resource_man.registry.tiles[id]
             .data
@@ -65,3 +195,102 @@ pub fn should_category_show(
 
     false
 }
+
+/// Returns whether the tile can always be placed, without being unlocked first.
+pub fn is_default_tile(resource_man: &ResourceManager, id: TileId) -> bool {
+    resource_man
+        .registry
+        .tiles
+        .get(&id)
+        .and_then(|tile| tile.data.get(resource_man.registry.data_ids.default_tile))
+        .cloned()
+        .and_then(|v| v.into_bool())
+        .unwrap_or(false)
+}
+
+/// Whether the tile points somewhere, which is when its definition has a direction.
+pub fn is_directional(resource_man: &ResourceManager, id: TileId) -> bool {
+    resource_man.registry.tiles.get(&id).is_some_and(|tile| {
+        tile.data
+            .get(resource_man.registry.data_ids.direction)
+            .is_some()
+    })
+}
+
+/// Gets where the tile points, from its data if it has a direction there, or else from its definition.
+pub fn tile_direction(
+    resource_man: &ResourceManager,
+    id: TileId,
+    data: Option<&DataMap>,
+) -> Option<TileCoord> {
+    data.and_then(|data| data.get(resource_man.registry.data_ids.direction))
+        .or_else(|| {
+            resource_man
+                .registry
+                .tiles
+                .get(&id)
+                .and_then(|tile| tile.data.get(resource_man.registry.data_ids.direction))
+        })
+        .and_then(|direction| match direction {
+            Data::Coord(direction) => Some(*direction),
+            _ => None,
+        })
+}
+
+/// Checks that the player has done the research that unlocks the tile, and has the item its category needs.
+///
+/// Removing a tile is always allowed, and so is placing anything in a sandbox.
+pub fn placement_requirements(
+    resource_man: &ResourceManager,
+    game_data: &mut DataMap,
+    id: TileId,
+) -> Result<(), PlacementFailure> {
+    if id == TileId(resource_man.registry.none)
+        || is_default_tile(resource_man, id)
+        || is_sandbox(resource_man, game_data)
+    {
+        return Ok(());
+    }
+
+    let unlocked = resource_man
+        .get_research_by_unlock(id)
+        .is_some_and(|research| is_research_unlocked(research.id, resource_man, game_data));
+    if !unlocked {
+        return Err(PlacementFailure::Locked);
+    }
+
+    if let Some(item) = resource_man
+        .registry
+        .tiles
+        .get(&id)
+        .and_then(|tile| tile.category)
+        .and_then(|category| missing_category_item(category, resource_man, game_data))
+    {
+        return Err(PlacementFailure::MissingItem(item));
+    }
+
+    Ok(())
+}
+
+/// Gets the item the player needs to have to place the tiles of the category, if they have none of it. Nothing is needed in a sandbox.
+pub fn missing_category_item(
+    category: Id,
+    resource_man: &ResourceManager,
+    game_data: &mut DataMap,
+) -> Option<Id> {
+    if is_sandbox(resource_man, game_data) {
+        return None;
+    }
+
+    let item = resource_man.registry.categories.get(&category)?.item?;
+
+    if let Some(Data::Inventory(inventory)) =
+        game_data.get_mut(resource_man.registry.data_ids.player_inventory)
+    {
+        if inventory.get(item) > 0 {
+            return None;
+        }
+    }
+
+    Some(item)
+}
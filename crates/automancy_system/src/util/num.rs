@@ -21,6 +21,10 @@ pub trait NumTrait:
     fn zero() -> Self;
     fn one() -> Self;
     fn clamp(self, min: Self, max: Self) -> Self;
+    /// Adds, stopping at the bounds of the type rather than overflowing. Floats don't overflow, and just add.
+    fn saturating_add(self, rhs: Self) -> Self;
+    fn saturating_sub(self, rhs: Self) -> Self;
+    fn saturating_mul(self, rhs: Self) -> Self;
 }
 
 impl NumTrait for f32 {
@@ -35,6 +39,18 @@ impl NumTrait for f32 {
     fn clamp(self, min: Self, max: Self) -> Self {
         self.clamp(min, max)
     }
+
+    fn saturating_add(self, rhs: Self) -> Self {
+        self + rhs
+    }
+
+    fn saturating_sub(self, rhs: Self) -> Self {
+        self - rhs
+    }
+
+    fn saturating_mul(self, rhs: Self) -> Self {
+        self * rhs
+    }
 }
 
 impl NumTrait for f64 {
@@ -49,6 +65,18 @@ impl NumTrait for f64 {
     fn clamp(self, min: Self, max: Self) -> Self {
         self.clamp(min, max)
     }
+
+    fn saturating_add(self, rhs: Self) -> Self {
+        self + rhs
+    }
+
+    fn saturating_sub(self, rhs: Self) -> Self {
+        self - rhs
+    }
+
+    fn saturating_mul(self, rhs: Self) -> Self {
+        self * rhs
+    }
 }
 
 impl NumTrait for i32 {
@@ -69,6 +97,18 @@ impl NumTrait for i32 {
             self
         }
     }
+
+    fn saturating_add(self, rhs: Self) -> Self {
+        i32::saturating_add(self, rhs)
+    }
+
+    fn saturating_sub(self, rhs: Self) -> Self {
+        i32::saturating_sub(self, rhs)
+    }
+
+    fn saturating_mul(self, rhs: Self) -> Self {
+        i32::saturating_mul(self, rhs)
+    }
 }
 
 impl NumTrait for i64 {
@@ -89,4 +129,16 @@ impl NumTrait for i64 {
             self
         }
     }
+
+    fn saturating_add(self, rhs: Self) -> Self {
+        i64::saturating_add(self, rhs)
+    }
+
+    fn saturating_sub(self, rhs: Self) -> Self {
+        i64::saturating_sub(self, rhs)
+    }
+
+    fn saturating_mul(self, rhs: Self) -> Self {
+        i64::saturating_mul(self, rhs)
+    }
 }
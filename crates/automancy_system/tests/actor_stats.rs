@@ -0,0 +1,264 @@
+use automancy_defs::coord::TileCoord;
+use automancy_system::actor_stats::{
+    format_stats, ActorStats, CounterSnapshot, MessageCounter, MessageStats, BUSIEST_TILES,
+};
+use ractor::{Actor, ActorProcessingErr, ActorRef};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[test]
+fn handling_times_go_in_power_of_two_buckets() {
+    let counter = MessageCounter::new();
+
+    for micros in [0, 1, 3, 4, 1000, 1024] {
+        counter.record(Duration::from_micros(micros));
+    }
+    // far past the last bucket, still counted in it
+    counter.record(Duration::from_secs(3600));
+
+    let snapshot = counter.snapshot();
+    assert_eq!(snapshot.handled, 7);
+    assert_eq!(snapshot.buckets[0], 1);
+    assert_eq!(snapshot.buckets[1], 1);
+    assert_eq!(snapshot.buckets[2], 1);
+    assert_eq!(snapshot.buckets[3], 1);
+    assert_eq!(snapshot.buckets[10], 1);
+    assert_eq!(snapshot.buckets[11], 1);
+    assert_eq!(snapshot.buckets.last(), Some(&1));
+    assert_eq!(snapshot.buckets.iter().sum::<u64>(), 7);
+}
+
+#[test]
+fn timer_counts_when_dropped() {
+    let counter = MessageCounter::new();
+
+    {
+        let _timer = counter.time();
+        assert_eq!(counter.snapshot().handled, 0);
+    }
+
+    // left early, as by an error in a handler
+    let timed = |early: bool| {
+        let _timer = counter.time();
+        if early {
+            return 0;
+        }
+
+        1
+    };
+    assert_eq!(timed(true) + timed(false), 1);
+
+    assert_eq!(counter.snapshot().handled, 3);
+}
+
+#[test]
+fn stats_between_snapshots() {
+    let earlier = CounterSnapshot {
+        handled: 50,
+        busy_nanos: 1_000_000_000,
+        ..Default::default()
+    };
+    let mut later = CounterSnapshot {
+        handled: 150,
+        busy_nanos: 1_500_000_000,
+        ..Default::default()
+    };
+    later.buckets[3] = 95;
+    later.buckets[10] = 5;
+
+    let stats = MessageStats::between(&earlier, &later, Duration::from_secs(1));
+    assert_eq!(stats.rate, 100.0);
+    assert_eq!(stats.p95, Duration::from_micros(8));
+    // busy half of the time
+    assert!((stats.backlog - 1.0).abs() < 1e-9);
+
+    // one more slow message puts the 95th percentile in the slow bucket
+    later.buckets[3] = 94;
+    later.buckets[10] = 6;
+    let stats = MessageStats::between(&earlier, &later, Duration::from_secs(1));
+    assert_eq!(stats.p95, Duration::from_micros(1024));
+
+    // nothing is made up from an empty window
+    assert_eq!(
+        MessageStats::between(&earlier, &later, Duration::ZERO),
+        MessageStats::default()
+    );
+    let idle = MessageStats::between(&later, &later, Duration::from_secs(1));
+    assert_eq!(idle.rate, 0.0);
+    assert_eq!(idle.p95, Duration::ZERO);
+    assert_eq!(idle.backlog, 0.0);
+}
+
+#[test]
+fn backlog_is_capped_when_always_busy() {
+    let later = CounterSnapshot {
+        handled: 1,
+        busy_nanos: 5_000_000_000,
+        ..Default::default()
+    };
+
+    let stats = MessageStats::between(&CounterSnapshot::default(), &later, Duration::from_secs(1));
+    assert!(stats.backlog.is_finite());
+    assert!(stats.backlog > 900.0);
+}
+
+#[test]
+fn formats_like_the_overlay() {
+    let stats = MessageStats {
+        rate: 1234.0,
+        p95: Duration::from_micros(400),
+        backlog: 12.3,
+    };
+
+    assert_eq!(
+        format_stats(&stats),
+        "1.2k msg/s, p95 handling 0.4ms, est. backlog 12"
+    );
+    assert_eq!(
+        format_stats(&MessageStats::default()),
+        "0 msg/s, p95 handling 0.0ms, est. backlog 0"
+    );
+}
+
+#[test]
+fn only_sampled_tiles_get_counters() {
+    let stats = ActorStats::new();
+
+    assert!(ActorStats::is_sampled(TileCoord::new(0, 0)));
+    assert!(ActorStats::is_sampled(TileCoord::new(-4, 8)));
+    assert!(!ActorStats::is_sampled(TileCoord::new(1, 0)));
+    assert!(!ActorStats::is_sampled(TileCoord::new(4, -3)));
+
+    assert!(stats.tile_counter(TileCoord::new(4, 4)).is_some());
+    assert!(stats.tile_counter(TileCoord::new(2, 4)).is_none());
+}
+
+#[test]
+fn flush_reports_the_busiest_tiles() {
+    let stats = ActorStats::new();
+
+    let counters = (0..8)
+        .map(|i| {
+            let coord = TileCoord::new(i * 4, 0);
+            (coord, stats.tile_counter(coord).unwrap())
+        })
+        .collect::<Vec<_>>();
+
+    // the first flush only sets where the window starts
+    stats.flush();
+    assert_eq!(stats.report().sampled_tiles, 0);
+
+    for (i, (_, counter)) in counters.iter().enumerate() {
+        for _ in 0..(i + 1) * 10 {
+            counter.record(Duration::from_micros(1));
+        }
+    }
+    stats.game.record(Duration::from_micros(1));
+
+    stats.flush();
+    let report = stats.report();
+    assert_eq!(report.sampled_tiles, 8);
+    assert!(report.game.rate > 0.0);
+    assert_eq!(report.busiest_tiles.len(), BUSIEST_TILES);
+    assert_eq!(
+        report
+            .busiest_tiles
+            .iter()
+            .map(|(coord, _)| *coord)
+            .collect::<Vec<_>>(),
+        counters
+            .iter()
+            .rev()
+            .take(BUSIEST_TILES)
+            .map(|(coord, _)| *coord)
+            .collect::<Vec<_>>()
+    );
+
+    // the counters of removed tile entities go away with them
+    drop(counters);
+    stats.flush();
+    assert_eq!(stats.report().sampled_tiles, 0);
+}
+
+/// An actor that takes the given time to handle each message, counting them like the game does.
+struct Slow {
+    counter: Arc<MessageCounter>,
+    handling: Duration,
+}
+
+#[async_trait::async_trait]
+impl Actor for Slow {
+    type Msg = ();
+    type State = ();
+    type Arguments = ();
+
+    async fn pre_start(
+        &self,
+        _myself: ActorRef<Self::Msg>,
+        _args: Self::Arguments,
+    ) -> Result<Self::State, ActorProcessingErr> {
+        Ok(())
+    }
+
+    async fn handle(
+        &self,
+        _myself: ActorRef<Self::Msg>,
+        _message: Self::Msg,
+        _state: &mut Self::State,
+    ) -> Result<(), ActorProcessingErr> {
+        let _timer = self.counter.time();
+
+        std::thread::sleep(self.handling);
+
+        Ok(())
+    }
+}
+
+/// Sends the actor a message every interval for the window, and gets the stats of what it handled in it.
+async fn load(handling: Duration, interval: Duration, window: Duration) -> MessageStats {
+    let counter = Arc::new(MessageCounter::new());
+    let (actor, handle) = Actor::spawn(
+        None,
+        Slow {
+            counter: counter.clone(),
+            handling,
+        },
+        (),
+    )
+    .await
+    .unwrap();
+
+    let earlier = counter.snapshot();
+    let start = Instant::now();
+    while start.elapsed() < window {
+        actor.send_message(()).unwrap();
+        tokio::time::sleep(interval).await;
+    }
+    let stats = MessageStats::between(&earlier, &counter.snapshot(), start.elapsed());
+
+    actor.kill();
+    let _ = handle.await;
+
+    stats
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn backlog_tracks_a_slowed_handler() {
+    let window = Duration::from_millis(500);
+    let interval = Duration::from_millis(5);
+
+    let fast = load(Duration::ZERO, interval, window).await;
+    // about half of the time spent handling
+    let busy = load(Duration::from_micros(2500), interval, window).await;
+    // slower than the messages come in, so they pile up
+    let slowed = load(Duration::from_millis(20), interval, window).await;
+
+    assert!(fast.backlog < 0.2, "{fast:?}");
+    assert!(busy.backlog > fast.backlog, "{busy:?}");
+    assert!(slowed.backlog > 5.0, "{slowed:?}");
+    assert!(slowed.backlog > busy.backlog);
+
+    // the slowed actor handles fewer messages than it is sent
+    assert!(slowed.rate < fast.rate);
+    assert!(slowed.p95 >= Duration::from_millis(16));
+}
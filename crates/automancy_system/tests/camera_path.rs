@@ -0,0 +1,177 @@
+mod common;
+
+use automancy_defs::coord::TileCoord;
+use automancy_defs::glam::vec3;
+use automancy_defs::math::{Float, Vec3};
+use automancy_resources::data::{Data, DataMap};
+use automancy_system::camera::{catmull_rom, smoothstep, CameraPath, CameraWaypoint};
+use common::load_pack;
+
+fn waypoint(coord: TileCoord, zoom: Float, dwell: Float, transition: Float) -> CameraWaypoint {
+    CameraWaypoint {
+        coord,
+        zoom,
+        dwell,
+        transition,
+    }
+}
+
+fn path(looping: bool) -> CameraPath {
+    CameraPath {
+        waypoints: vec![
+            waypoint(TileCoord::new(0, 0), 2.0, 1.0, 2.0),
+            waypoint(TileCoord::new(6, -2), 1.0, 0.5, 3.0),
+            waypoint(TileCoord::new(-4, 5), 3.0, 2.0, 1.0),
+        ],
+        looping,
+    }
+}
+
+fn assert_near(a: Vec3, b: Vec3) {
+    assert!(a.distance(b) < 1e-3, "{a} is not {b}");
+}
+
+#[test]
+fn paths_round_trip_through_the_map_data() {
+    let resource_man = load_pack(&[]);
+    let data_ids = &resource_man.registry.data_ids;
+
+    for looping in [false, true] {
+        let mut data = DataMap::default();
+        path(looping).write_data(&mut data, data_ids);
+
+        assert_eq!(CameraPath::from_data(&data, data_ids), path(looping));
+    }
+
+    // writing an empty path clears what was there
+    let mut data = DataMap::default();
+    path(true).write_data(&mut data, data_ids);
+    CameraPath::default().write_data(&mut data, data_ids);
+    assert!(data.get(data_ids.camera_path).is_none());
+    assert!(data.get(data_ids.camera_path_timings).is_none());
+    assert!(data.get(data_ids.camera_path_looping).is_none());
+    assert_eq!(
+        CameraPath::from_data(&data, data_ids),
+        CameraPath::default()
+    );
+}
+
+#[test]
+fn waypoints_without_timings_get_the_defaults() {
+    let resource_man = load_pack(&[]);
+    let data_ids = &resource_man.registry.data_ids;
+
+    let mut data = DataMap::default();
+    path(false).write_data(&mut data, data_ids);
+    // as written by hand, with the timings of the last waypoint missing
+    let mut read = path(false);
+    read.waypoints
+        .push(CameraWaypoint::new(TileCoord::new(1, 1), 2.0));
+    data.set(
+        data_ids.camera_path,
+        Data::VecCoord(read.waypoints.iter().map(|v| v.coord).collect()),
+    );
+
+    assert_eq!(CameraPath::from_data(&data, data_ids), read);
+}
+
+#[test]
+fn sampling_stops_at_every_waypoint() {
+    let path = path(false);
+    let [a, b, c] = [0, 1, 2].map(|i| path.waypoints[i].pos());
+
+    assert_near(path.sample(0.0).unwrap(), a);
+    assert_near(path.sample(0.9).unwrap(), a);
+    // the dwell of the first, then its transition
+    assert_near(path.sample(1.0 + 2.0 + 0.1).unwrap(), b);
+    assert_near(path.sample(3.5 + 3.0 + 1.9).unwrap(), c);
+
+    // the last waypoint is held for its dwell, and then the playback is over
+    assert_near(path.sample(8.5).unwrap(), c);
+    assert_eq!(path.sample(8.6), None);
+    assert_eq!(CameraPath::default().sample(0.0), None);
+}
+
+#[test]
+fn sampling_is_continuous() {
+    for looping in [false, true] {
+        let path = path(looping);
+        let step = 0.001;
+        let mut last = path.sample(0.0).unwrap();
+        let mut time = step;
+
+        while let Some(pos) = path.sample(time) {
+            // far more than the camera can move in a millisecond along these paths
+            assert!(
+                pos.distance(last) < 0.05,
+                "the camera jumps from {last} to {pos} at {time}"
+            );
+
+            last = pos;
+            time += step;
+
+            if time > 20.0 {
+                break;
+            }
+        }
+    }
+}
+
+#[test]
+fn looping_paths_start_over() {
+    let path = path(true);
+    // every waypoint's dwell and transition, back to the first
+    let total = 1.0 + 2.0 + 0.5 + 3.0 + 2.0 + 1.0;
+
+    for time in [0.0, 0.7, 2.2, 5.1, 7.9] {
+        assert_near(
+            path.sample(time + total).unwrap(),
+            path.sample(time).unwrap(),
+        );
+        assert_near(
+            path.sample(time + total * 3.0).unwrap(),
+            path.sample(time).unwrap(),
+        );
+    }
+
+    // the last waypoint flies back to the first
+    assert_near(
+        path.sample(total - 0.0001).unwrap(),
+        path.waypoints[0].pos(),
+    );
+}
+
+#[test]
+fn single_waypoints_are_held() {
+    let path = CameraPath {
+        waypoints: vec![waypoint(TileCoord::new(3, 3), 2.5, 1.5, 4.0)],
+        looping: false,
+    };
+
+    assert_near(path.sample(1.0).unwrap(), path.waypoints[0].pos());
+    assert_eq!(path.sample(1.6), None);
+}
+
+#[test]
+fn easing() {
+    assert_eq!(smoothstep(0.0), 0.0);
+    assert_eq!(smoothstep(1.0), 1.0);
+    assert_eq!(smoothstep(-1.0), 0.0);
+    assert_eq!(smoothstep(2.0), 1.0);
+    assert!((smoothstep(0.5) - 0.5).abs() < 1e-6);
+
+    let points = [
+        vec3(0.0, 0.0, 1.0),
+        vec3(1.0, 2.0, 2.0),
+        vec3(3.0, 1.0, 3.0),
+        vec3(4.0, 4.0, 1.0),
+    ];
+    assert_near(
+        catmull_rom(points[0], points[1], points[2], points[3], 0.0),
+        points[1],
+    );
+    assert_near(
+        catmull_rom(points[0], points[1], points[2], points[3], 1.0),
+        points[2],
+    );
+}
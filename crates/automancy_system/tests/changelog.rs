@@ -0,0 +1,116 @@
+use automancy_system::changelog::{
+    compare_versions, entries_since, read_changelog, released_entries, ChangelogEntry,
+};
+use std::cmp::Ordering;
+
+fn entry(version: &str) -> ChangelogEntry {
+    ChangelogEntry {
+        version: version.to_string(),
+        date: None,
+        sections: vec![],
+    }
+}
+
+fn versions(entries: &[ChangelogEntry]) -> Vec<&str> {
+    entries.iter().map(|v| v.version.as_str()).collect()
+}
+
+/// A changelog sorted like the bundled one, with an unreleased version on top.
+fn changelog() -> Vec<ChangelogEntry> {
+    ["0.4.0", "0.3.1", "0.3.0", "0.2.0", "0.1.0"]
+        .into_iter()
+        .map(entry)
+        .collect()
+}
+
+#[test]
+fn versions_compare_by_part() {
+    assert_eq!(compare_versions("0.1.0", "0.1.0"), Ordering::Equal);
+    assert_eq!(compare_versions("0.1", "0.1.0"), Ordering::Equal);
+    assert_eq!(compare_versions("v0.2.0", "0.2.0"), Ordering::Equal);
+    assert_eq!(compare_versions("0.2.0", "0.10.0"), Ordering::Less);
+    assert_eq!(compare_versions("1.0.0", "0.99.99"), Ordering::Greater);
+    assert_eq!(compare_versions("0.3.1", "0.3"), Ordering::Greater);
+    // a suffix after the digits doesn't count
+    assert_eq!(compare_versions("0.3.0-beta", "0.3.0"), Ordering::Equal);
+}
+
+#[test]
+fn released_leaves_out_newer_versions() {
+    let changelog = changelog();
+
+    assert_eq!(
+        versions(&released_entries(&changelog, "0.3.1")),
+        ["0.3.1", "0.3.0", "0.2.0", "0.1.0"]
+    );
+    assert_eq!(
+        versions(&released_entries(&changelog, "0.3.0-beta")),
+        ["0.3.0", "0.2.0", "0.1.0"]
+    );
+    assert!(released_entries(&changelog, "0.0.1").is_empty());
+}
+
+#[test]
+fn since_the_last_seen_version() {
+    let changelog = changelog();
+
+    assert_eq!(
+        versions(&entries_since(&changelog, Some("0.2.0"), "0.3.1")),
+        ["0.3.1", "0.3.0"]
+    );
+    // nothing is new when the version didn't change
+    assert!(entries_since(&changelog, Some("0.3.1"), "0.3.1").is_empty());
+    // nor when going back to an older version
+    assert!(entries_since(&changelog, Some("0.4.0"), "0.3.1").is_empty());
+    // a last seen version that isn't in the changelog still limits what is shown
+    assert_eq!(
+        versions(&entries_since(&changelog, Some("0.2.5"), "0.4.0")),
+        ["0.4.0", "0.3.1", "0.3.0"]
+    );
+}
+
+#[test]
+fn first_run_shows_the_newest_release() {
+    let changelog = changelog();
+
+    assert_eq!(
+        versions(&entries_since(&changelog, None, "0.3.1")),
+        ["0.3.1"]
+    );
+    assert_eq!(
+        versions(&entries_since(&changelog, None, "9.0.0")),
+        ["0.4.0"]
+    );
+    assert!(entries_since(&changelog, None, "0.0.1").is_empty());
+}
+
+#[test]
+fn bundled_changelog_parses() {
+    let changelog = read_changelog();
+
+    assert!(!changelog.is_empty());
+    assert!(changelog
+        .windows(2)
+        .all(|v| compare_versions(&v[0].version, &v[1].version).is_gt()));
+    assert!(changelog
+        .iter()
+        .all(|v| v.sections.iter().all(|v| !v.bullets.is_empty())));
+}
+
+#[test]
+fn unknown_fields_are_ignored() {
+    let entries = ron::from_str::<Vec<ChangelogEntry>>(
+        r#"[
+            (
+                version: "0.2.0",
+                codename: "later",
+                sections: [(bullets: [Plain("a")], icon: "star")],
+            ),
+        ]"#,
+    )
+    .unwrap();
+
+    assert_eq!(versions(&entries), ["0.2.0"]);
+    assert_eq!(entries[0].date, None);
+    assert_eq!(entries[0].sections[0].title, None);
+}
@@ -0,0 +1,377 @@
+//! Loads small resource packs written out by the tests, so that the game logic can be tested without the real packs.
+
+#![allow(dead_code)]
+
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::{Id, TileId};
+use automancy_defs::kira::manager::backend::mock::MockBackend;
+use automancy_defs::kira::manager::{AudioManager, AudioManagerSettings};
+use automancy_defs::kira::track::TrackBuilder;
+use automancy_defs::stack::ItemAmount;
+use automancy_resources::data::DataMap;
+use automancy_resources::ResourceManager;
+use automancy_system::game::{GameSystem, GameSystemMessage, PlacementResult, TickUnit};
+use automancy_system::map::{GameMap, LoadMapOption};
+use automancy_system::tile_entity::TileEntityMsg;
+use ractor::{Actor, ActorRef};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// The namespace of the test packs.
+pub const NAMESPACE: &str = "test";
+
+/// A tile file, placeable without research if it is a default tile.
+pub fn tile(name: &str, category: Option<&str>, default_tile: bool) -> (String, String) {
+    let category = category.map_or("None".to_string(), |v| format!("Some(\"{v}\")"));
+
+    (
+        format!("tiles/{name}.ron"),
+        format!(
+            r#"(
+                id: "{name}",
+                function: None,
+                category: {category},
+                data: ({{ "core:default_tile": Bool({default_tile}) }}),
+            )"#
+        ),
+    )
+}
+
+/// An item file, with a model of the same name.
+pub fn item(name: &str) -> (String, String) {
+    (
+        format!("items/{name}.ron"),
+        format!(r#"(id: "{name}", model: "{name}")"#),
+    )
+}
+
+/// A tile file running the function, placeable without research.
+pub fn machine(name: &str, function: &str) -> (String, String) {
+    (
+        format!("tiles/{name}.ron"),
+        format!(
+            r#"(
+                id: "{name}",
+                function: Some("{function}"),
+                data: ({{ "core:default_tile": Bool(true) }}),
+            )"#
+        ),
+    )
+}
+
+/// A source function file, with the IDs it uses given as (ID, constant name) pairs ahead of its body.
+pub fn function(name: &str, id_deps: &[(&str, &str)], body: &str) -> (String, String) {
+    let id_deps = id_deps
+        .iter()
+        .map(|(id, key)| format!("[\"{id}\", \"{key}\"]"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    (
+        format!("functions/src/{name}.rhai"),
+        format!(
+            r#"
+            fn function_id() {{ "{name}" }}
+            fn id_deps() {{ [{id_deps}] }}
+            {body}
+            "#
+        ),
+    )
+}
+
+/// A terrain file, generated on new maps where the noise is in the range, if there is one.
+pub fn terrain(name: &str, buildable: bool, generated: Option<(i32, i32)>) -> (String, String) {
+    let generated = generated.map_or("None".to_string(), |(min, max)| {
+        format!("Some(({min}, {max}))")
+    });
+
+    (
+        format!("terrains/{name}.ron"),
+        format!(r#"(id: "{name}", buildable: {buildable}, generated: {generated})"#),
+    )
+}
+
+/// A data schema file, for a key of the test packs.
+pub fn schema(name: &str, kind: &str, persisted: bool) -> (String, String) {
+    (
+        format!("schemas/{name}.ron"),
+        format!(r#"(id: "{name}", kind: {kind}, persisted: {persisted})"#),
+    )
+}
+
+/// A tag file, matching the entries and those of the included tags, without the excluded ones.
+pub fn tag(name: &str, entries: &[&str], includes: &[&str], excludes: &[&str]) -> (String, String) {
+    let list = |ids: &[&str]| {
+        ids.iter()
+            .map(|v| format!("\"{v}\""))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    (
+        format!("tags/{name}.ron"),
+        format!(
+            r#"(id: "{name}", entries: [{}], includes: [{}], excludes: [{}])"#,
+            list(entries),
+            list(includes),
+            list(excludes)
+        ),
+    )
+}
+
+/// A research file, unlocking the tiles.
+pub fn research(name: &str, unlocks: &[&str], depends_on: Option<&str>) -> (String, String) {
+    let unlocks = unlocks
+        .iter()
+        .map(|v| format!("\"{v}\""))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let depends_on = depends_on.map_or("None".to_string(), |v| format!("Some(\"{v}\")"));
+
+    (
+        format!("researches/{name}.ron"),
+        format!(
+            r#"(
+                id: "{name}",
+                icon: "{name}",
+                icon_mode: Tile,
+                unlocks: [{unlocks}],
+                depends_on: {depends_on},
+                name: "{name}",
+                description: "{name}",
+                completed_description: "{name}",
+                required_items: None,
+                attached_puzzle: None,
+            )"#
+        ),
+    )
+}
+
+/// A script file, making the outputs from the inputs.
+pub fn script(
+    name: &str,
+    inputs: &[(&str, ItemAmount)],
+    outputs: &[(&str, ItemAmount)],
+) -> (String, String) {
+    let stacks = |stacks: &[(&str, ItemAmount)]| {
+        stacks
+            .iter()
+            .map(|(id, amount)| format!("(\"{id}\", {amount})"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    (
+        format!("scripts/{name}.ron"),
+        format!(
+            r#"(
+                id: "{name}",
+                instructions: (
+                    inputs: Some([{}]),
+                    output: [{}],
+                ),
+            )"#,
+            stacks(inputs),
+            stacks(outputs)
+        ),
+    )
+}
+
+/// Writes the files into a pack folder, by their paths in it, and loads it the way the game loads its packs.
+pub fn load_pack(files: &[(String, String)]) -> ResourceManager {
+    static PACKS: AtomicUsize = AtomicUsize::new(0);
+
+    let dir = std::env::temp_dir().join(format!(
+        "automancy_test_pack_{}_{}",
+        std::process::id(),
+        PACKS.fetch_add(1, Ordering::Relaxed)
+    ));
+    for (path, content) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    let mut audio_man = AudioManager::<MockBackend>::new(AudioManagerSettings::default()).unwrap();
+    let track = audio_man.add_sub_track(TrackBuilder::new()).unwrap();
+
+    let mut resource_man = ResourceManager::new(track);
+    resource_man.strict = true;
+
+    load_dir(&mut resource_man, &dir);
+    fs::remove_dir_all(&dir).unwrap();
+
+    resource_man
+}
+
+fn load_dir(resource_man: &mut ResourceManager, dir: &Path) {
+    resource_man.load_tiles(dir, NAMESPACE).unwrap();
+    resource_man.load_terrains(dir, NAMESPACE).unwrap();
+    resource_man.load_items(dir, NAMESPACE).unwrap();
+    resource_man.load_schemas(dir, NAMESPACE).unwrap();
+    resource_man.load_tags(dir, NAMESPACE).unwrap();
+    resource_man.load_categories(dir, NAMESPACE).unwrap();
+    resource_man.load_scripts(dir, NAMESPACE).unwrap();
+    resource_man.load_functions(dir, NAMESPACE).unwrap();
+    resource_man.load_researches(dir, NAMESPACE).unwrap();
+    resource_man.load_start(dir, NAMESPACE).unwrap();
+
+    resource_man.compile_tags();
+    resource_man.compile_researches();
+    resource_man.compile_start();
+    resource_man.ordered_tiles();
+    resource_man.ordered_items();
+    resource_man.compile_scripts();
+    resource_man.compile_categories();
+}
+
+/// Gets the ID of the name in the test packs.
+pub fn id(resource_man: &ResourceManager, name: &str) -> Id {
+    Id::try_parse(&format!("{NAMESPACE}:{name}"), &resource_man.interner).unwrap()
+}
+
+/// A map saved by a test, removed once the test is done with it.
+pub struct TestMap(pub LoadMapOption);
+
+impl TestMap {
+    pub fn new(name: &str) -> Self {
+        Self(LoadMapOption::FromSave(format!(
+            "test_{name}_{}",
+            std::process::id()
+        )))
+    }
+}
+
+impl Drop for TestMap {
+    fn drop(&mut self) {
+        if let Some(path) = GameMap::path(&self.0) {
+            let _ = fs::remove_dir_all(path);
+        }
+    }
+}
+
+/// Starts the game, with the map loaded.
+pub async fn start_game(
+    resource_man: Arc<ResourceManager>,
+    map: &TestMap,
+) -> ActorRef<GameSystemMessage> {
+    let (game, _handle) = Actor::spawn(None, GameSystem { resource_man }, ())
+        .await
+        .unwrap();
+
+    load_map(&game, map).await;
+
+    game
+}
+
+pub async fn load_map(game: &ActorRef<GameSystemMessage>, map: &TestMap) {
+    let opt = map.0.clone();
+
+    assert!(game
+        .call(|reply| GameSystemMessage::LoadMap(opt, reply), None)
+        .await
+        .unwrap()
+        .unwrap());
+}
+
+pub async fn save_map(game: &ActorRef<GameSystemMessage>) {
+    game.call(GameSystemMessage::SaveMap, None)
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+/// Places the tile, skipping the research and item requirements.
+pub async fn place(
+    game: &ActorRef<GameSystemMessage>,
+    coord: TileCoord,
+    id: TileId,
+    data: Option<DataMap>,
+) -> PlacementResult {
+    game.call(
+        |reply| GameSystemMessage::PlaceTile {
+            coord,
+            id,
+            data,
+            record: false,
+            force: true,
+            reply: Some(reply),
+        },
+        None,
+    )
+    .await
+    .unwrap()
+    .unwrap()
+}
+
+pub async fn get_tile(game: &ActorRef<GameSystemMessage>, coord: TileCoord) -> Option<TileId> {
+    game.call(|reply| GameSystemMessage::GetTile(coord, reply), None)
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+/// Gets the data of the loaded map.
+pub async fn get_map_data(game: &ActorRef<GameSystemMessage>) -> DataMap {
+    let (info, _) = game
+        .call(GameSystemMessage::GetMapInfoAndName, None)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+
+    let info = info.lock().await;
+
+    info.data.clone()
+}
+
+pub async fn tile_entity(
+    game: &ActorRef<GameSystemMessage>,
+    coord: TileCoord,
+) -> Option<ActorRef<TileEntityMsg>> {
+    game.call(|reply| GameSystemMessage::GetTileEntity(coord, reply), None)
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+/// Sends the game the number of ticks, waiting until it has handled them.
+pub async fn run_ticks(game: &ActorRef<GameSystemMessage>, ticks: usize) {
+    for _ in 0..ticks {
+        game.send_message(GameSystemMessage::Tick).unwrap();
+    }
+
+    get_tick(game).await;
+}
+
+pub async fn get_tick(game: &ActorRef<GameSystemMessage>) -> TickUnit {
+    game.call(GameSystemMessage::GetTick, None)
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+pub async fn get_data(game: &ActorRef<GameSystemMessage>, coord: TileCoord) -> DataMap {
+    tile_entity(game, coord)
+        .await
+        .unwrap()
+        .call(TileEntityMsg::GetData, None)
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+/// Waits until the tile entities have handled what was sent to them, so that what they send the game in return is ahead of the next message to it.
+pub async fn settle(game: &ActorRef<GameSystemMessage>, coords: &[TileCoord]) {
+    for coord in coords {
+        if let Some(tile_entity) = tile_entity(game, *coord).await {
+            tile_entity
+                .call(TileEntityMsg::GetData, None)
+                .await
+                .unwrap()
+                .unwrap();
+        }
+    }
+}
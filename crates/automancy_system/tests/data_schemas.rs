@@ -0,0 +1,190 @@
+mod common;
+
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::TileId;
+use automancy_resources::data::{Data, DataKind, DataMap};
+use automancy_resources::inventory::Inventory;
+use automancy_system::tile_entity::TileEntityMsg;
+use common::{
+    get_data, id, load_map, load_pack, place, save_map, schema, settle, start_game, tile,
+    tile_entity, TestMap,
+};
+use hashbrown::HashSet;
+use std::sync::Arc;
+
+#[test]
+fn built_in_keys_are_checked() {
+    let resource_man = load_pack(&[]);
+    let registry = &resource_man.registry;
+    let data_ids = &registry.data_ids;
+
+    for (key, schema) in data_ids.schemas() {
+        assert_eq!(registry.data_schemas.get(&key), Some(&schema));
+    }
+
+    assert_eq!(
+        registry.check_data(data_ids.buffer, &Data::Inventory(Inventory::default())),
+        Ok(())
+    );
+    assert_eq!(
+        registry.check_data(data_ids.buffer, &Data::Amount(3)),
+        Err(DataKind::Inventory)
+    );
+    assert_eq!(
+        registry.check_data(data_ids.direction, &Data::Bool(true)),
+        Err(DataKind::Coord)
+    );
+    assert!(!registry.is_persisted(data_ids.variant_model));
+    assert!(registry.is_persisted(data_ids.buffer));
+}
+
+#[test]
+fn every_built_in_key_has_a_schema() {
+    let resource_man = load_pack(&[]);
+    let data_ids = &resource_man.registry.data_ids;
+
+    let schemas = data_ids
+        .schemas()
+        .into_iter()
+        .map(|(key, _)| key)
+        .collect::<Vec<_>>();
+
+    for key in data_ids.all() {
+        // a render tag, never a key in the data
+        if key == data_ids.none_tile_render_tag {
+            continue;
+        }
+
+        assert!(
+            schemas.contains(&key),
+            "{} has no schema",
+            resource_man.interner.resolve(key).unwrap_or_default()
+        );
+    }
+
+    assert_eq!(
+        schemas.iter().collect::<HashSet<_>>().len(),
+        schemas.len(),
+        "a key has more than one schema"
+    );
+}
+
+#[test]
+fn pack_schemas_are_loaded() {
+    let resource_man = load_pack(&[
+        schema("counter", "Amount", true),
+        schema("scratch", "SetId", false),
+        tile("free", None, true),
+    ]);
+    let registry = &resource_man.registry;
+    let counter = id(&resource_man, "counter");
+    let scratch = id(&resource_man, "scratch");
+
+    assert_eq!(registry.check_data(counter, &Data::Amount(1)), Ok(()));
+    assert_eq!(
+        registry.check_data(counter, &Data::Bool(false)),
+        Err(DataKind::Amount)
+    );
+    assert_eq!(
+        registry.check_data(scratch, &Data::SetId(HashSet::new())),
+        Ok(())
+    );
+    assert!(registry.is_persisted(counter));
+    assert!(!registry.is_persisted(scratch));
+
+    // keys without a schema take anything, and are saved
+    let free = id(&resource_man, "free");
+    assert_eq!(registry.check_data(free, &Data::Bool(true)), Ok(()));
+    assert_eq!(registry.check_data(free, &Data::Amount(2)), Ok(()));
+    assert!(registry.is_persisted(free));
+}
+
+#[tokio::test]
+async fn tile_entities_drop_mismatched_values() {
+    let resource_man = Arc::new(load_pack(&[
+        tile("machine", None, true),
+        schema("counter", "Amount", true),
+    ]));
+    let map = TestMap::new("tile_entities_drop_mismatched_values");
+    let game = start_game(resource_man.clone(), &map).await;
+    let data_ids = &resource_man.registry.data_ids;
+    let coord = TileCoord::new(0, 0);
+    let counter = id(&resource_man, "counter");
+
+    let mut data = DataMap::default();
+    data.set(counter, Data::Bool(true));
+    data.set(data_ids.direction, Data::Coord(TileCoord::RIGHT));
+    data.set(data_ids.capacity, Data::Coord(TileCoord::LEFT));
+    place(
+        &game,
+        coord,
+        TileId(id(&resource_man, "machine")),
+        Some(data),
+    )
+    .await;
+    settle(&game, &[coord]).await;
+
+    let data = get_data(&game, coord).await;
+    assert_eq!(data.get(counter), None);
+    assert_eq!(data.get(data_ids.capacity), None);
+    assert_eq!(
+        data.get(data_ids.direction),
+        Some(&Data::Coord(TileCoord::RIGHT))
+    );
+
+    let tile_entity = tile_entity(&game, coord).await.unwrap();
+    tile_entity
+        .send_message(TileEntityMsg::SetDataValue(counter, Data::Amount(4)))
+        .unwrap();
+    tile_entity
+        .send_message(TileEntityMsg::SetDataValue(
+            data_ids.direction,
+            Data::Amount(4),
+        ))
+        .unwrap();
+
+    let data = get_data(&game, coord).await;
+    assert_eq!(data.get(counter), Some(&Data::Amount(4)));
+    assert_eq!(
+        data.get(data_ids.direction),
+        Some(&Data::Coord(TileCoord::RIGHT))
+    );
+}
+
+#[tokio::test]
+async fn unpersisted_keys_are_not_saved() {
+    let resource_man = Arc::new(load_pack(&[
+        tile("machine", None, true),
+        schema("counter", "Amount", true),
+        schema("scratch", "Amount", false),
+    ]));
+    let map = TestMap::new("unpersisted_keys_are_not_saved");
+    let game = start_game(resource_man.clone(), &map).await;
+    let coord = TileCoord::new(1, 1);
+    let counter = id(&resource_man, "counter");
+    let scratch = id(&resource_man, "scratch");
+
+    let mut data = DataMap::default();
+    data.set(counter, Data::Amount(7));
+    data.set(scratch, Data::Amount(9));
+    place(
+        &game,
+        coord,
+        TileId(id(&resource_man, "machine")),
+        Some(data),
+    )
+    .await;
+    settle(&game, &[coord]).await;
+    assert_eq!(
+        get_data(&game, coord).await.get(scratch),
+        Some(&Data::Amount(9))
+    );
+
+    save_map(&game).await;
+    load_map(&game, &map).await;
+    settle(&game, &[coord]).await;
+
+    let data = get_data(&game, coord).await;
+    assert_eq!(data.get(counter), Some(&Data::Amount(7)));
+    assert_eq!(data.get(scratch), None);
+}
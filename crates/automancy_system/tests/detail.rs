@@ -0,0 +1,118 @@
+use automancy_defs::coord::TileCoord;
+use automancy_defs::glam::vec3;
+use automancy_defs::math::{Float, Vec3, FAR, HEX_GRID_LAYOUT};
+use automancy_system::detail::{detail_level, DetailLevel, DetailPreset, DetailThresholds};
+
+const PRESETS: [DetailPreset; 3] = [DetailPreset::Low, DetailPreset::Medium, DetailPreset::High];
+
+/// The coordinates within the radius around the center.
+fn coords(center: TileCoord, radius: i32) -> Vec<TileCoord> {
+    let mut coords = vec![];
+
+    for q in -radius..=radius {
+        for r in (-radius).max(-q - radius)..=radius.min(-q + radius) {
+            coords.push(TileCoord::new(center.x + q, center.y + r));
+        }
+    }
+
+    coords
+}
+
+fn distance(camera_pos: Vec3, coord: TileCoord) -> Float {
+    camera_pos.distance(HEX_GRID_LAYOUT.hex_to_world_pos(*coord).extend(FAR))
+}
+
+/// Checks that no tile gets more detail than a tile nearer to the camera.
+fn check_monotonic(camera_pos: Vec3, thresholds: DetailThresholds) {
+    let mut coords = coords(TileCoord::new(0, 0), 40);
+    coords.sort_by(|a, b| distance(camera_pos, *a).total_cmp(&distance(camera_pos, *b)));
+
+    let levels = coords
+        .iter()
+        .map(|coord| detail_level(camera_pos, *coord, thresholds))
+        .collect::<Vec<_>>();
+
+    for (pair, coords) in levels.windows(2).zip(coords.windows(2)) {
+        assert!(
+            pair[0] >= pair[1],
+            "{} is further than {} from {camera_pos}, but has more detail",
+            coords[1],
+            coords[0]
+        );
+    }
+}
+
+#[test]
+fn detail_falls_off_with_distance() {
+    for preset in PRESETS {
+        for camera_pos in [
+            vec3(0.0, 0.0, 2.0),
+            vec3(0.0, 0.0, 12.0),
+            vec3(13.7, -4.2, 6.5),
+            vec3(-30.0, 22.0, 1.0),
+        ] {
+            check_monotonic(camera_pos, preset.thresholds());
+        }
+    }
+}
+
+#[test]
+fn every_level_is_reached() {
+    for preset in PRESETS {
+        let camera_pos = vec3(0.0, 0.0, 2.0);
+        let levels = coords(TileCoord::new(0, 0), 40)
+            .into_iter()
+            .map(|coord| detail_level(camera_pos, coord, preset.thresholds()))
+            .collect::<Vec<_>>();
+
+        for level in DetailLevel::ALL {
+            assert!(levels.contains(&level), "{preset:?} never gives {level:?}");
+        }
+    }
+}
+
+#[test]
+fn higher_presets_never_give_less_detail() {
+    let camera_pos = vec3(3.0, 1.0, 4.0);
+
+    for coord in coords(TileCoord::new(0, 0), 40) {
+        let levels = PRESETS.map(|preset| detail_level(camera_pos, coord, preset.thresholds()));
+
+        assert!(
+            levels[0] <= levels[1] && levels[1] <= levels[2],
+            "{coord}: {levels:?}"
+        );
+    }
+}
+
+#[test]
+fn medium_below_high_is_skipped() {
+    // a medium threshold nearer than the high one leaves nothing at medium
+    let thresholds = DetailThresholds {
+        high: 10.0,
+        medium: 4.0,
+    };
+    let camera_pos = vec3(0.0, 0.0, 2.0);
+
+    check_monotonic(camera_pos, thresholds);
+    assert!(coords(TileCoord::new(0, 0), 40)
+        .into_iter()
+        .all(|coord| detail_level(camera_pos, coord, thresholds) != DetailLevel::Medium));
+}
+
+#[test]
+fn updates_and_effects_follow_the_level() {
+    for level in DetailLevel::ALL {
+        let updated = (0..64).filter(|frame| level.updates_on(*frame)).count() as u64;
+
+        assert_eq!(updated, 64 / level.update_interval());
+        assert!(level.updates_on(0));
+    }
+
+    assert!(DetailLevel::Low.update_interval() >= DetailLevel::Medium.update_interval());
+    assert!(DetailLevel::Medium.update_interval() >= DetailLevel::High.update_interval());
+
+    assert!(!DetailLevel::Low.shows_effects());
+    assert!(DetailLevel::Medium.shows_effects());
+    assert!(DetailLevel::High.shows_effects());
+}
@@ -0,0 +1,139 @@
+mod common;
+
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::TileId;
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::ResourceManager;
+use automancy_system::game::GameSystemMessage;
+use automancy_system::map::{GameMap, MAP_EXT};
+use common::{id, load_map, load_pack, place, save_map, settle, start_game, tile, TestMap};
+use hashbrown::{HashMap, HashSet};
+use ractor::ActorRef;
+use std::fs;
+use std::sync::Arc;
+
+const NAMES: [&str; 5] = ["a", "b", "c", "d", "e"];
+
+fn saved_tiles(map: &TestMap) -> Vec<u8> {
+    fs::read(
+        GameMap::path(&map.0)
+            .unwrap()
+            .join("map")
+            .with_extension(MAP_EXT),
+    )
+    .unwrap()
+}
+
+fn coords() -> Vec<TileCoord> {
+    (-6..6)
+        .flat_map(|q| (-6..6).map(move |r| TileCoord::new(q * 3 + r, r * 2 - q)))
+        .collect()
+}
+
+/// Data that has hashed collections in it, so that it only saves the same way if they are sorted.
+fn data(resource_man: &ResourceManager, coord: TileCoord) -> DataMap {
+    let ids = NAMES.map(|name| id(resource_man, name));
+
+    let mut data = DataMap::default();
+    data.set(
+        ids[0],
+        Data::SetId(ids.iter().copied().collect::<HashSet<_>>()),
+    );
+    data.set(
+        ids[1],
+        Data::TileMap(
+            coords()
+                .into_iter()
+                .take(24)
+                .zip(ids.iter().copied().cycle())
+                .map(|(v, id)| (v + coord, id))
+                .collect::<HashMap<_, _>>(),
+        ),
+    );
+    data.set(
+        ids[2],
+        Data::MapSetId(
+            ids.iter()
+                .map(|id| (*id, ids.iter().copied().collect::<HashSet<_>>()))
+                .collect(),
+        ),
+    );
+
+    data
+}
+
+async fn place_all(
+    resource_man: &Arc<ResourceManager>,
+    game: &ActorRef<GameSystemMessage>,
+    coords: &[TileCoord],
+) {
+    for (index, coord) in coords.iter().enumerate() {
+        let tile = TileId(id(resource_man, NAMES[index % NAMES.len()]));
+
+        place(game, *coord, tile, Some(data(resource_man, *coord))).await;
+    }
+
+    settle(game, coords).await;
+}
+
+fn pack() -> Arc<ResourceManager> {
+    Arc::new(load_pack(&NAMES.map(|name| tile(name, None, true))))
+}
+
+#[tokio::test]
+async fn saves_do_not_depend_on_the_order_of_hashing() {
+    let resource_man = pack();
+    let map = TestMap::new("saves_do_not_depend_on_the_order_of_hashing");
+    let game = start_game(resource_man.clone(), &map).await;
+
+    place_all(&resource_man, &game, &coords()).await;
+    save_map(&game).await;
+    let saved = saved_tiles(&map);
+
+    save_map(&game).await;
+    assert_eq!(saved_tiles(&map), saved);
+
+    // the tiles and their data are hashed differently every time the map is loaded
+    for _ in 0..3 {
+        load_map(&game, &map).await;
+        save_map(&game).await;
+        assert_eq!(saved_tiles(&map), saved);
+    }
+
+    let game = start_game(resource_man.clone(), &map).await;
+    save_map(&game).await;
+    assert_eq!(saved_tiles(&map), saved);
+}
+
+#[tokio::test]
+async fn saves_do_not_depend_on_the_order_of_placement() {
+    let resource_man = pack();
+    let name = "saves_do_not_depend_on_the_order_of_placement";
+    let coords = coords();
+
+    let placed_in_order = {
+        let map = TestMap::new(name);
+        let game = start_game(resource_man.clone(), &map).await;
+
+        place_all(&resource_man, &game, &coords).await;
+        save_map(&game).await;
+
+        saved_tiles(&map)
+    };
+
+    // the same tiles with the same data, placed from the other end
+    let mut reversed = coords.iter().copied().enumerate().collect::<Vec<_>>();
+    reversed.reverse();
+
+    let map = TestMap::new(name);
+    let game = start_game(resource_man.clone(), &map).await;
+    for (index, coord) in reversed {
+        let tile = TileId(id(&resource_man, NAMES[index % NAMES.len()]));
+
+        place(&game, coord, tile, Some(data(&resource_man, coord))).await;
+    }
+    settle(&game, &coords).await;
+    save_map(&game).await;
+
+    assert_eq!(saved_tiles(&map), placed_in_order);
+}
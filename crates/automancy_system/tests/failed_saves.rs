@@ -0,0 +1,50 @@
+mod common;
+
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::TileId;
+use automancy_system::game::GameSystemMessage;
+use automancy_system::map::{GameMap, MAP_EXT, TEMP_EXT};
+use common::{id, load_pack, place, save_map, start_game, tile, tile_entity, TestMap};
+use ractor::rpc::CallResult;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[tokio::test]
+async fn failed_saves_leave_no_temp_files() {
+    let resource_man = Arc::new(load_pack(&[tile("wall", None, true)]));
+    let map = TestMap::new("failed_saves_leave_no_temp_files");
+    let game = start_game(resource_man.clone(), &map).await;
+
+    let wall = TileId(id(&resource_man, "wall"));
+    let coord = TileCoord::new(0, 0);
+    place(&game, coord, wall, None).await;
+    save_map(&game).await;
+
+    let path = GameMap::path(&map.0).unwrap();
+    let saved = fs::read(path.join("map").with_extension(MAP_EXT)).unwrap();
+
+    // the tile entity can't be asked for its data anymore, so the save stops partway
+    tile_entity(&game, coord)
+        .await
+        .unwrap()
+        .stop_and_wait(None, None)
+        .await
+        .unwrap();
+    assert!(!matches!(
+        game.call(GameSystemMessage::SaveMap, None).await,
+        Ok(CallResult::Success(()))
+    ));
+
+    let temp_files = fs::read_dir(&path)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|file| file.extension().is_some_and(|ext| ext == TEMP_EXT))
+        .collect::<Vec<_>>();
+    assert_eq!(temp_files, Vec::<PathBuf>::new());
+
+    assert_eq!(
+        fs::read(path.join("map").with_extension(MAP_EXT)).unwrap(),
+        saved
+    );
+}
@@ -0,0 +1,161 @@
+mod common;
+
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::TileId;
+use automancy_defs::stack::ItemAmount;
+use automancy_system::flow::{FlowGraph, FlowRecorder, FlowSample};
+use common::{id, load_pack, tile};
+use hashbrown::HashMap;
+
+fn sample(ticks: u64, active: u64, inflow: &[(TileCoord, ItemAmount)]) -> FlowSample {
+    FlowSample {
+        script: None,
+        inflow: inflow.to_vec(),
+        ticks,
+        active,
+    }
+}
+
+#[test]
+fn recorder_sums_up_the_window() {
+    let mut recorder = FlowRecorder::new();
+    let a = TileCoord::new(0, 0);
+    let b = TileCoord::new(3, -1);
+
+    recorder.record_inflow(a, 4);
+    recorder.record_inflow(b, 1);
+    recorder.record_inflow(a, 2);
+    for active in [true, false, true, true] {
+        recorder.record_tick(active);
+    }
+
+    assert_eq!(recorder.total_inflow(), 7);
+
+    let mut sample = recorder.sample(None);
+    sample.inflow.sort_by_key(|(coord, _)| (coord.x, coord.y));
+    assert_eq!(sample.inflow, [(a, 6), (b, 1)]);
+    assert_eq!(sample.ticks, 4);
+    assert_eq!(sample.active, 3);
+}
+
+#[test]
+fn machines_are_grouped_by_tile_and_script() {
+    let mut resource_man = load_pack(&[
+        tile("miner", None, true),
+        tile("smelter", None, true),
+        tile("wall", None, true),
+    ]);
+    let miner = TileId(id(&resource_man, "miner"));
+    let smelter = TileId(id(&resource_man, "smelter"));
+    let wall = TileId(id(&resource_man, "wall"));
+    let copper = resource_man.interner.get_or_intern("test:smelt_copper");
+
+    let miners = [
+        TileCoord::new(0, 0),
+        TileCoord::new(0, 1),
+        TileCoord::new(0, 2),
+    ];
+    let smelters = [TileCoord::new(4, 0), TileCoord::new(4, 1)];
+    let odd_smelter = TileCoord::new(4, 2);
+    let idle_wall = TileCoord::new(9, 9);
+
+    let mut tiles = HashMap::new();
+    for coord in miners {
+        tiles.insert(coord, miner);
+    }
+    for coord in smelters.into_iter().chain([odd_smelter]) {
+        tiles.insert(coord, smelter);
+    }
+    tiles.insert(idle_wall, wall);
+
+    let mut samples = HashMap::new();
+    for coord in miners {
+        samples.insert(coord, sample(10, 10, &[]));
+    }
+    samples.insert(
+        smelters[0],
+        sample(10, 5, &[(miners[0], 6), (miners[1], 2)]),
+    );
+    samples.insert(
+        smelters[1],
+        // from another smelter of the same group, and from a tile that is gone
+        sample(
+            10,
+            0,
+            &[
+                (miners[2], 4),
+                (smelters[0], 3),
+                (TileCoord::new(50, 50), 9),
+            ],
+        ),
+    );
+    samples.insert(
+        odd_smelter,
+        FlowSample {
+            script: Some(copper),
+            ..sample(10, 10, &[(miners[2], 1)])
+        },
+    );
+    samples.insert(idle_wall, sample(0, 0, &[]));
+
+    let graph = FlowGraph::build(&tiles, &samples);
+
+    // the miners, the smelters, and the smelter with another script, but not the idle wall
+    assert_eq!(graph.nodes.len(), 3);
+    assert_eq!(graph.positions.len(), 3);
+
+    let node = |tile, script| {
+        graph
+            .nodes
+            .iter()
+            .position(|v| v.tile == tile && v.script == script)
+            .unwrap()
+    };
+    let miner_node = node(miner, None);
+    let smelter_node = node(smelter, None);
+    let odd_node = node(smelter, Some(copper));
+
+    assert_eq!(graph.nodes[miner_node].coords, miners);
+    assert_eq!(graph.nodes[smelter_node].coords, smelters);
+    assert_eq!(graph.nodes[odd_node].coords, [odd_smelter]);
+
+    assert_eq!(graph.nodes[miner_node].utilization(), Some(1.0));
+    assert_eq!(graph.nodes[smelter_node].utilization(), Some(0.25));
+
+    // the flows between the smelters themselves, and from the gone tile, are left out
+    let edges = graph
+        .edges
+        .iter()
+        .map(|edge| (edge.from, edge.to, edge.amount))
+        .collect::<Vec<_>>();
+    assert_eq!(edges.len(), 2);
+    assert!(edges.contains(&(miner_node, smelter_node, 12)));
+    assert!(edges.contains(&(miner_node, odd_node, 1)));
+    assert_eq!(graph.max_amount(), 12);
+
+    // the machines the items come from are laid out before the ones they go to
+    assert!(graph.positions[miner_node].x < graph.positions[smelter_node].x);
+}
+
+#[test]
+fn machines_that_only_send_are_kept() {
+    let resource_man = load_pack(&[tile("crate", None, true), tile("sink", None, true)]);
+    let storage = TileId(id(&resource_man, "crate"));
+    let sink = TileId(id(&resource_man, "sink"));
+
+    let from = TileCoord::new(0, 0);
+    let to = TileCoord::new(1, 0);
+    let tiles = HashMap::from([(from, storage), (to, sink)]);
+    let samples = HashMap::from([(from, sample(0, 0, &[])), (to, sample(0, 0, &[(from, 5)]))]);
+
+    let graph = FlowGraph::build(&tiles, &samples);
+
+    assert_eq!(graph.nodes.len(), 2);
+    assert!(graph.nodes.iter().all(|node| node.utilization().is_none()));
+    assert_eq!(graph.edges.len(), 1);
+    assert_eq!(graph.edges[0].amount, 5);
+
+    assert!(FlowGraph::build(&HashMap::new(), &HashMap::new())
+        .nodes
+        .is_empty());
+}
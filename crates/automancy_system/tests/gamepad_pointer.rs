@@ -0,0 +1,142 @@
+use automancy_defs::coord::TileCoord;
+use automancy_defs::glam::{vec2, Vec2};
+use automancy_defs::hexx::Hex;
+use automancy_defs::math::{world_to_screen, Float, HEX_GRID_LAYOUT};
+use automancy_system::camera::GameCamera;
+use automancy_system::input::{
+    ActionType, GameInputEvent, InputHandler, KeyAction, KeyBinding, PressType,
+};
+use automancy_system::options::GameOptions;
+use gilrs::Axis;
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI, TAU};
+use winit::event::ElementState;
+use winit::keyboard::{Key, SmolStr};
+
+const SIZE: (Float, Float) = (800.0, 600.0);
+/// How far the cursor moves in a second with the stick pushed all the way, as in [InputHandler::move_cursor].
+const CURSOR_SPEED: Float = 800.0;
+/// How fast the rotation keys turn the camera, in radians per second.
+const KEY_ROTATION_SPEED: Float = 1.5;
+
+fn rotate_key() -> Key {
+    Key::Character(SmolStr::new("q"))
+}
+
+fn input_handler() -> InputHandler {
+    let mut options = GameOptions::default();
+    options.key_bindings.push((
+        KeyBinding::new(rotate_key()),
+        KeyAction {
+            action: ActionType::RotateLeft,
+            press_type: PressType::Hold,
+            name: None,
+        },
+    ));
+
+    InputHandler::new(&options)
+}
+
+/// Turns the camera to the angle with the rotation key, the way the player does.
+fn turn(camera: &mut GameCamera, input_handler: &mut InputHandler, angle: Float) {
+    input_handler.handle_key(ElementState::Pressed, rotate_key());
+    camera.handle_input(input_handler);
+    camera.update_pos(SIZE, angle / KEY_ROTATION_SPEED);
+
+    input_handler.handle_key(ElementState::Released, rotate_key());
+    camera.stop();
+    camera.update_pos(SIZE, 0.0);
+}
+
+/// Pushes the right stick towards the screen position for as long as it takes the cursor to get there.
+fn steer(input_handler: &mut InputHandler, target: Vec2) {
+    let offset = target - input_handler.main_pos;
+    if offset.length() < 0.01 {
+        return;
+    }
+
+    // the stick is up when positive, while the screen goes down
+    let stick = vec2(offset.x, -offset.y).normalize();
+    input_handler.update(GameInputEvent::GamepadAxis {
+        axis: Axis::RightStickX,
+        value: stick.x,
+    });
+    input_handler.update(GameInputEvent::GamepadAxis {
+        axis: Axis::RightStickY,
+        value: stick.y,
+    });
+
+    input_handler.move_cursor(offset.length() / CURSOR_SPEED, SIZE);
+
+    input_handler.update(GameInputEvent::GamepadAxis {
+        axis: Axis::RightStickX,
+        value: 0.0,
+    });
+    input_handler.update(GameInputEvent::GamepadAxis {
+        axis: Axis::RightStickY,
+        value: 0.0,
+    });
+}
+
+#[test]
+fn stick_points_at_the_tile_under_it_at_any_rotation() {
+    let targets = [
+        TileCoord::new(0, 0),
+        TileCoord::new(2, 0),
+        TileCoord::new(-1, 3),
+        TileCoord::new(0, -2),
+        TileCoord::new(-3, 1),
+    ];
+
+    for angle in [0.0, FRAC_PI_4, FRAC_PI_2, 2.0, PI, 4.5] {
+        let mut camera = GameCamera::new(SIZE);
+        let mut input_handler = input_handler();
+        turn(&mut camera, &mut input_handler, angle);
+        assert!((camera.get_rotation() - angle).abs() < 0.001);
+
+        input_handler.main_pos = vec2(SIZE.0, SIZE.1) / 2.0;
+
+        for target in targets {
+            let world = HEX_GRID_LAYOUT.hex_to_world_pos(Hex::from(target));
+            let screen = world_to_screen(
+                SIZE,
+                world.extend(0.0),
+                camera.get_pos(),
+                camera.get_rotation(),
+            );
+
+            steer(&mut input_handler, screen);
+            assert!(input_handler.main_pos.distance(screen) < 0.5);
+
+            camera.update_pointing_at(input_handler.main_pos, SIZE);
+            assert_eq!(camera.pointing_at, target, "at {angle} radians");
+        }
+    }
+}
+
+#[test]
+fn turning_changes_the_tile_under_the_cursor() {
+    let mut camera = GameCamera::new(SIZE);
+    let mut input_handler = input_handler();
+
+    let target = TileCoord::new(2, 0);
+    let world = HEX_GRID_LAYOUT
+        .hex_to_world_pos(Hex::from(target))
+        .extend(0.0);
+    let screen = world_to_screen(SIZE, world, camera.get_pos(), camera.get_rotation());
+
+    input_handler.main_pos = vec2(SIZE.0, SIZE.1) / 2.0;
+    steer(&mut input_handler, screen);
+    camera.update_pointing_at(input_handler.main_pos, SIZE);
+    assert_eq!(camera.pointing_at, target);
+
+    // the cursor stays where it is on the screen, so half a turn puts it over the tile across the center
+    turn(&mut camera, &mut input_handler, PI);
+    camera.update_pointing_at(input_handler.main_pos, SIZE);
+    assert_eq!(camera.pointing_at, TileCoord::new(-2, 0));
+
+    // a full turn brings it back
+    turn(&mut camera, &mut input_handler, PI);
+    assert!(camera.get_rotation() < 0.001 || (TAU - camera.get_rotation()) < 0.001);
+    camera.update_pointing_at(input_handler.main_pos, SIZE);
+    assert_eq!(camera.pointing_at, target);
+}
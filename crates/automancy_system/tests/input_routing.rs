@@ -0,0 +1,162 @@
+use automancy_defs::glam::vec3;
+use automancy_system::camera::GameCamera;
+use automancy_system::input::{
+    ActionType, GameInputEvent, InputHandler, KeyAction, KeyBinding, PressType,
+};
+use automancy_system::input_routing::{InputRoute, InputRouter};
+use automancy_system::options::GameOptions;
+use winit::event::ElementState;
+use winit::keyboard::{Key, SmolStr};
+
+fn rotate_key() -> Key {
+    Key::Character(SmolStr::new("q"))
+}
+
+/// An input handler with a held key bound, which the camera turns with.
+fn input_handler() -> InputHandler {
+    let mut options = GameOptions::default();
+    options.key_bindings.push((
+        KeyBinding::new(rotate_key()),
+        KeyAction {
+            action: ActionType::RotateLeft,
+            press_type: PressType::Hold,
+            name: None,
+        },
+    ));
+
+    InputHandler::new(&options)
+}
+
+fn camera() -> GameCamera {
+    GameCamera::new((800.0, 600.0))
+}
+
+#[test]
+fn keys_held_over_a_modal_are_dropped_in_the_world() {
+    let mut router = InputRouter::default();
+    let mut input_handler = input_handler();
+    let mut camera = camera();
+
+    router.update(InputRoute::World, &mut input_handler, &mut camera);
+    router.update(InputRoute::Modal, &mut input_handler, &mut camera);
+
+    // pressed while the modal had the input, and released after it closed, which the world never sees
+    input_handler.handle_key(ElementState::Pressed, rotate_key());
+    input_handler.update(GameInputEvent::MainPressed);
+    assert!(input_handler.key_active(ActionType::RotateLeft));
+
+    assert_eq!(
+        router.update(InputRoute::World, &mut input_handler, &mut camera),
+        InputRoute::World
+    );
+    assert!(!input_handler.key_active(ActionType::RotateLeft));
+    assert!(!input_handler.main_held);
+
+    // the release of the forgotten key does nothing
+    assert!(input_handler
+        .handle_key(ElementState::Released, rotate_key())
+        .is_none());
+}
+
+#[test]
+fn keys_held_in_the_world_are_kept() {
+    let mut router = InputRouter::default();
+    let mut input_handler = input_handler();
+    let mut camera = camera();
+
+    router.update(InputRoute::World, &mut input_handler, &mut camera);
+    input_handler.handle_key(ElementState::Pressed, rotate_key());
+
+    router.update(InputRoute::World, &mut input_handler, &mut camera);
+    assert!(input_handler.key_active(ActionType::RotateLeft));
+}
+
+#[test]
+fn camera_goes_back_after_a_modal() {
+    let mut router = InputRouter::default();
+    let mut input_handler = input_handler();
+    let mut camera = camera();
+
+    router.update(InputRoute::World, &mut input_handler, &mut camera);
+    camera.set_raw_pos(vec3(3.0, -2.0, 1.5));
+    let before = camera;
+
+    router.update(InputRoute::Modal, &mut input_handler, &mut camera);
+    assert_eq!(router.modal_camera(), Some(&before));
+
+    // the camera is moved by something else while the modal is open
+    camera.set_raw_pos(vec3(-9.0, 9.0, 0.5));
+    router.update(InputRoute::Modal, &mut input_handler, &mut camera);
+
+    router.update(InputRoute::World, &mut input_handler, &mut camera);
+    assert_eq!(camera, before);
+    assert_eq!(router.modal_camera(), None);
+}
+
+#[test]
+fn menus_leave_the_camera_alone() {
+    let mut router = InputRouter::default();
+    let mut input_handler = input_handler();
+    let mut camera = camera();
+
+    router.update(InputRoute::Menu, &mut input_handler, &mut camera);
+    assert_eq!(router.modal_camera(), None);
+
+    input_handler.handle_key(ElementState::Pressed, rotate_key());
+
+    // the map starts where it was loaded, and nothing pressed in the menu reaches it
+    camera.set_raw_pos(vec3(1.0, 1.0, 1.0));
+    let loaded = camera;
+    router.update(InputRoute::World, &mut input_handler, &mut camera);
+    assert_eq!(camera, loaded);
+    assert!(!input_handler.key_active(ActionType::RotateLeft));
+}
+
+#[test]
+fn leaving_to_the_menu_drops_the_kept_camera() {
+    let mut router = InputRouter::default();
+    let mut input_handler = input_handler();
+    let mut camera = camera();
+
+    router.update(InputRoute::World, &mut input_handler, &mut camera);
+    router.update(InputRoute::Modal, &mut input_handler, &mut camera);
+    assert!(router.modal_camera().is_some());
+
+    router.update(InputRoute::Menu, &mut input_handler, &mut camera);
+    assert_eq!(router.modal_camera(), None);
+}
+
+#[test]
+fn loading_a_map_drops_the_kept_camera() {
+    let mut router = InputRouter::default();
+    let mut input_handler = input_handler();
+    let mut camera = camera();
+
+    router.update(InputRoute::World, &mut input_handler, &mut camera);
+    camera.set_raw_pos(vec3(5.0, 5.0, 1.0));
+    // paused, then another map loaded from the pause menu
+    router.update(InputRoute::Modal, &mut input_handler, &mut camera);
+    router.map_loaded();
+
+    camera.set_raw_pos(vec3(0.0, 0.0, 2.0));
+    let loaded = camera;
+    router.update(InputRoute::World, &mut input_handler, &mut camera);
+    assert_eq!(camera, loaded);
+}
+
+#[test]
+fn camera_path_keeps_the_camera() {
+    let mut router = InputRouter::default();
+    let mut input_handler = input_handler();
+    let mut camera = camera();
+
+    router.update(InputRoute::World, &mut input_handler, &mut camera);
+    router.update(InputRoute::CameraPath, &mut input_handler, &mut camera);
+    assert_eq!(router.route(), InputRoute::CameraPath);
+    assert_eq!(router.modal_camera(), None);
+
+    camera.set_raw_pos(vec3(2.0, 2.0, 1.0));
+    let played = camera;
+    router.update(InputRoute::World, &mut input_handler, &mut camera);
+    assert_eq!(camera, played);
+}
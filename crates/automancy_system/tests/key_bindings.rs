@@ -0,0 +1,242 @@
+mod common;
+
+use automancy_system::input::{
+    find_conflict, get_default_keymap, match_binding, ActionType, GameInputEvent, InputHandler,
+    KeyAction, KeyBinding, KeyMap, Modifier, PressType,
+};
+use automancy_system::options::GameOptions;
+use common::load_pack;
+use std::collections::BTreeSet;
+use winit::event::ElementState;
+use winit::keyboard::{Key, ModifiersState, NamedKey, SmolStr};
+
+fn key(c: &str) -> Key {
+    Key::Character(SmolStr::new(c))
+}
+
+fn action(action: ActionType, press_type: PressType) -> KeyAction {
+    KeyAction {
+        action,
+        press_type,
+        name: None,
+    }
+}
+
+fn held(modifiers: &[Modifier]) -> BTreeSet<Modifier> {
+    modifiers.iter().copied().collect()
+}
+
+fn input_handler(key_map: KeyMap) -> InputHandler {
+    InputHandler::new(&GameOptions {
+        key_bindings: key_map,
+        ..Default::default()
+    })
+}
+
+fn hold_modifiers(input_handler: &mut InputHandler, state: ModifiersState) {
+    input_handler.update(GameInputEvent::ModifierChanged {
+        modifier: state.into(),
+    });
+}
+
+#[test]
+fn most_specific_binding_wins() {
+    let key_map = vec![
+        (
+            KeyBinding::new(key("z")),
+            action(ActionType::Undo, PressType::Tap),
+        ),
+        (
+            KeyBinding::new(key("z")).with(Modifier::Control),
+            action(ActionType::Redo, PressType::Tap),
+        ),
+        (
+            KeyBinding::new(key("z")).with(Modifier::Shift),
+            action(ActionType::Delete, PressType::Tap),
+        ),
+        (
+            KeyBinding::new(key("z"))
+                .with(Modifier::Control)
+                .with(Modifier::Shift),
+            action(ActionType::Cancel, PressType::Tap),
+        ),
+    ];
+    let matched = |modifiers: &[Modifier]| {
+        match_binding(&key_map, &key("z"), &held(modifiers)).map(|(_, action)| action.action)
+    };
+
+    assert_eq!(matched(&[]), Some(ActionType::Undo));
+    assert_eq!(matched(&[Modifier::Control]), Some(ActionType::Redo));
+    assert_eq!(
+        matched(&[Modifier::Control, Modifier::Shift]),
+        Some(ActionType::Cancel)
+    );
+    // a modifier that no binding asks for does not stop the others from matching
+    assert_eq!(
+        matched(&[Modifier::Control, Modifier::Alt]),
+        Some(ActionType::Redo)
+    );
+    assert_eq!(match_binding(&key_map, &key("x"), &held(&[])), None);
+}
+
+#[test]
+fn first_listed_wins_among_equals() {
+    let key_map = vec![
+        (
+            KeyBinding::new(key("q")).with(Modifier::Control),
+            action(ActionType::RotateLeft, PressType::Hold),
+        ),
+        (
+            KeyBinding::new(key("q")).with(Modifier::Shift),
+            action(ActionType::RotateRight, PressType::Hold),
+        ),
+    ];
+
+    assert_eq!(
+        match_binding(
+            &key_map,
+            &key("q"),
+            &held(&[Modifier::Control, Modifier::Shift])
+        )
+        .map(|(_, action)| action.action),
+        Some(ActionType::RotateLeft)
+    );
+}
+
+#[test]
+fn conflicts() {
+    let undo = action(ActionType::Undo, PressType::Tap);
+    let redo = action(ActionType::Redo, PressType::Tap);
+
+    // several keys for one action, and one key with different modifiers, are fine
+    let key_map = vec![
+        (KeyBinding::new(key("z")), undo),
+        (KeyBinding::new(key("u")), undo),
+        (KeyBinding::new(key("z")).with(Modifier::Control), redo),
+        (KeyBinding::new(key("z")), undo),
+    ];
+    assert_eq!(find_conflict(&key_map), None);
+
+    let mut key_map = key_map;
+    key_map.push((KeyBinding::new(key("u")), redo));
+    assert_eq!(
+        find_conflict(&key_map),
+        Some((
+            &KeyBinding::new(key("u")),
+            ActionType::Undo,
+            ActionType::Redo
+        ))
+    );
+}
+
+#[test]
+fn default_bindings_do_not_conflict() {
+    let resource_man = load_pack(&[]);
+
+    assert_eq!(find_conflict(&get_default_keymap(&resource_man)), None);
+}
+
+#[test]
+fn every_key_of_an_action_triggers_it() {
+    let rotate = action(ActionType::RotateLeft, PressType::Hold);
+    let mut input_handler = input_handler(vec![
+        (KeyBinding::new(key("q")), rotate),
+        (KeyBinding::new(Key::Named(NamedKey::ArrowLeft)), rotate),
+    ]);
+
+    input_handler.handle_key(ElementState::Pressed, key("q"));
+    assert!(input_handler.key_active(ActionType::RotateLeft));
+    input_handler.handle_key(ElementState::Released, key("q"));
+    assert!(!input_handler.key_active(ActionType::RotateLeft));
+
+    input_handler.handle_key(ElementState::Pressed, Key::Named(NamedKey::ArrowLeft));
+    assert!(input_handler.key_active(ActionType::RotateLeft));
+    input_handler.handle_key(ElementState::Released, Key::Named(NamedKey::ArrowLeft));
+    assert!(!input_handler.key_active(ActionType::RotateLeft));
+}
+
+#[test]
+fn chords_trigger_their_own_action() {
+    let mut input_handler = input_handler(vec![
+        (
+            KeyBinding::new(key("z")),
+            action(ActionType::Undo, PressType::Tap),
+        ),
+        (
+            KeyBinding::new(key("z")).with(Modifier::Control),
+            action(ActionType::Redo, PressType::Tap),
+        ),
+    ]);
+
+    input_handler.handle_key(ElementState::Pressed, key("z"));
+    assert!(input_handler.key_active(ActionType::Undo));
+    assert!(!input_handler.key_active(ActionType::Redo));
+    input_handler.handle_key(ElementState::Released, key("z"));
+    input_handler.reset();
+
+    hold_modifiers(&mut input_handler, ModifiersState::CONTROL);
+    input_handler.handle_key(ElementState::Pressed, key("z"));
+    assert!(input_handler.key_active(ActionType::Redo));
+    assert!(!input_handler.key_active(ActionType::Undo));
+
+    // a tap is only active for the frame it was pressed in
+    input_handler.reset();
+    assert!(!input_handler.key_active(ActionType::Redo));
+}
+
+#[test]
+fn releasing_a_chord_releases_what_it_pressed() {
+    let mut input_handler = input_handler(vec![
+        (
+            KeyBinding::new(key("q")),
+            action(ActionType::RotateLeft, PressType::Hold),
+        ),
+        (
+            KeyBinding::new(key("q")).with(Modifier::Control),
+            action(ActionType::RotateRight, PressType::Hold),
+        ),
+    ]);
+
+    hold_modifiers(&mut input_handler, ModifiersState::CONTROL);
+    input_handler.handle_key(ElementState::Pressed, key("q"));
+    assert!(input_handler.key_active(ActionType::RotateRight));
+
+    // Control is let go of first, but the key still releases the chord's action rather than the plain one's
+    hold_modifiers(&mut input_handler, ModifiersState::empty());
+    input_handler.handle_key(ElementState::Released, key("q"));
+    assert!(!input_handler.key_active(ActionType::RotateRight));
+    assert!(!input_handler.key_active(ActionType::RotateLeft));
+}
+
+#[test]
+fn capturing_bindings() {
+    let mut input_handler = input_handler(vec![]);
+
+    // a key along with the modifiers held
+    input_handler.start_capture();
+    hold_modifiers(&mut input_handler, ModifiersState::CONTROL);
+    input_handler.handle_key(ElementState::Pressed, Key::Named(NamedKey::Control));
+    input_handler.handle_key(ElementState::Pressed, key("k"));
+    assert!(!input_handler.is_capturing());
+    assert_eq!(
+        input_handler.take_captured(),
+        Some(KeyBinding::new(key("k")).with(Modifier::Control))
+    );
+    hold_modifiers(&mut input_handler, ModifiersState::empty());
+
+    // a modifier by itself, once it is released without another key
+    input_handler.start_capture();
+    input_handler.handle_key(ElementState::Pressed, Key::Named(NamedKey::Shift));
+    assert!(input_handler.is_capturing());
+    input_handler.handle_key(ElementState::Released, Key::Named(NamedKey::Shift));
+    assert_eq!(
+        input_handler.take_captured(),
+        Some(KeyBinding::new(Key::Named(NamedKey::Shift)))
+    );
+
+    // escape stops without a binding
+    input_handler.start_capture();
+    input_handler.handle_key(ElementState::Pressed, Key::Named(NamedKey::Escape));
+    assert!(!input_handler.is_capturing());
+    assert_eq!(input_handler.take_captured(), None);
+}
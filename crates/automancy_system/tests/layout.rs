@@ -0,0 +1,135 @@
+use automancy_defs::math::Vec2;
+use automancy_system::util::layout::layered;
+
+/// Checks that no two nodes are put in the same place.
+fn assert_distinct(positions: &[Vec2]) {
+    for (a, pa) in positions.iter().enumerate() {
+        for pb in &positions[a + 1..] {
+            assert_ne!(pa, pb, "{positions:?}");
+        }
+    }
+}
+
+#[test]
+fn empty_graph() {
+    assert!(layered(0, &[]).is_empty());
+    // nodes without edges are all on the first layer
+    assert_eq!(
+        layered(3, &[]),
+        [
+            Vec2::new(0.0, -1.0),
+            Vec2::new(0.0, 0.0),
+            Vec2::new(0.0, 1.0)
+        ]
+    );
+}
+
+#[test]
+fn chain_goes_right() {
+    assert_eq!(
+        layered(3, &[(0, 1), (1, 2)]),
+        [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(2.0, 0.0)
+        ]
+    );
+}
+
+#[test]
+fn layers_are_centered() {
+    let positions = layered(4, &[(0, 1), (0, 2), (1, 3), (2, 3)]);
+
+    assert_eq!(positions[0], Vec2::new(0.0, 0.0));
+    assert_eq!(positions[3], Vec2::new(2.0, 0.0));
+    assert_eq!(positions[1].x, 1.0);
+    assert_eq!(positions[2].x, 1.0);
+    assert_eq!((positions[1].y - positions[2].y).abs(), 1.0);
+    assert_eq!(positions[1].y + positions[2].y, 0.0);
+}
+
+#[test]
+fn longest_path_sets_the_layer() {
+    // 3 is reached directly from 0, and through 1 and 2
+    let positions = layered(4, &[(0, 3), (0, 1), (1, 2), (2, 3)]);
+
+    assert_eq!(
+        positions.iter().map(|v| v.x).collect::<Vec<_>>(),
+        [0.0, 1.0, 2.0, 3.0]
+    );
+}
+
+#[test]
+fn every_edge_goes_forward() {
+    let edges = [
+        (0, 2),
+        (0, 3),
+        (1, 3),
+        (1, 4),
+        (2, 5),
+        (3, 5),
+        (3, 6),
+        (4, 6),
+        (5, 7),
+        (6, 7),
+        (0, 7),
+    ];
+    let positions = layered(8, &edges);
+
+    for (from, to) in edges {
+        assert!(positions[from].x < positions[to].x, "{from} -> {to}");
+    }
+    assert_distinct(&positions);
+}
+
+#[test]
+fn cycles_and_self_loops_are_broken() {
+    let edges = [(0, 1), (1, 2), (2, 0), (2, 2), (2, 3)];
+    let positions = layered(4, &edges);
+
+    assert_distinct(&positions);
+    // only the edge closing the cycle, and the loop, go backwards or nowhere
+    let forward = edges
+        .iter()
+        .filter(|(from, to)| positions[*from].x < positions[*to].x)
+        .count();
+    assert_eq!(forward, 3);
+}
+
+#[test]
+fn ordering_untangles_crossings() {
+    // as given, the edges into the second layer cross
+    let edges = [(0, 3), (1, 2)];
+    let positions = layered(4, &edges);
+
+    assert_eq!(
+        positions[0].y < positions[1].y,
+        positions[3].y < positions[2].y
+    );
+
+    // two chains side by side, given in the crossing order
+    let edges = [(0, 3), (1, 2), (3, 5), (2, 4)];
+    let positions = layered(6, &edges);
+
+    for ((a_from, a_to), (b_from, b_to)) in [((0, 3), (1, 2)), ((3, 5), (2, 4))] {
+        assert_eq!(
+            positions[a_from].y < positions[b_from].y,
+            positions[a_to].y < positions[b_to].y,
+            "{positions:?}"
+        );
+    }
+}
+
+#[test]
+fn deep_graphs_are_walked() {
+    let count = 100_000;
+    let edges = (0..count - 1)
+        .map(|node| (node, node + 1))
+        .chain([(count - 1, 0)])
+        .collect::<Vec<_>>();
+
+    let positions = layered(count, &edges);
+
+    assert_eq!(positions.len(), count);
+    assert_eq!(positions[count - 1].x, (count - 1) as f32);
+}
@@ -0,0 +1,502 @@
+use automancy_system::map::{
+    classify_map_entry, repair_map_entry, sanitize_name, scan_map_folder, MapEntryKind,
+    MapFolderSummary, MapInfoRaw, MapRepair,
+};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+const DICTIONARY_MAGIC: [u8; 4] = [0x37, 0xa4, 0x30, 0xec];
+
+/// A map folder made up by a test, removed once the test is done with it.
+struct MapFolder(PathBuf);
+
+impl MapFolder {
+    fn new(name: &str) -> Self {
+        let dir =
+            std::env::temp_dir().join(format!("automancy_test_maps_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        Self(dir)
+    }
+
+    /// Writes the files into the entry, by their names in it.
+    fn entry(&self, name: &str, files: &[(&str, &[u8])]) -> PathBuf {
+        let path = self.0.join(name);
+        fs::create_dir_all(&path).unwrap();
+
+        for (file, content) in files {
+            fs::write(path.join(file), content).unwrap();
+        }
+
+        path
+    }
+}
+
+impl Drop for MapFolder {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn info() -> Vec<u8> {
+    ron::ser::to_string(&MapInfoRaw::default())
+        .unwrap()
+        .into_bytes()
+}
+
+fn tiles() -> Vec<u8> {
+    let mut tiles = ZSTD_MAGIC.to_vec();
+    tiles.extend([0; 16]);
+
+    tiles
+}
+
+#[test]
+fn entries_are_classified() {
+    let folder = MapFolder::new("classified");
+    let info = info();
+    let tiles = tiles();
+
+    let valid = folder.entry("valid", &[("info.ron", &info), ("map.zst", &tiles)]);
+    let missing_info = folder.entry("missing_info", &[("map.zst", &tiles)]);
+    let missing_tiles = folder.entry("missing_tiles", &[("info.ron", &info)]);
+    let bad_info = folder.entry(
+        "bad_info",
+        &[("info.ron", b"(this is not map info"), ("map.zst", &tiles)],
+    );
+    let bad_tiles = folder.entry(
+        "bad_tiles",
+        &[("info.ron", &info), ("map.zst", b"not compressed")],
+    );
+    let bad_tiles_no_info = folder.entry("bad_tiles_no_info", &[("map.zst", b"not compressed")]);
+    let empty = folder.entry("empty", &[]);
+    let file = folder.0.join("notes.txt");
+    fs::write(&file, "not a map").unwrap();
+
+    assert_eq!(classify_map_entry(&valid), MapEntryKind::Valid);
+    assert_eq!(classify_map_entry(&missing_info), MapEntryKind::MissingInfo);
+    assert_eq!(
+        classify_map_entry(&missing_tiles),
+        MapEntryKind::MissingTiles
+    );
+    assert_eq!(classify_map_entry(&bad_info), MapEntryKind::Corrupted);
+    assert_eq!(classify_map_entry(&bad_tiles), MapEntryKind::Corrupted);
+    assert_eq!(
+        classify_map_entry(&bad_tiles_no_info),
+        MapEntryKind::Corrupted
+    );
+    assert_eq!(classify_map_entry(&empty), MapEntryKind::Unknown);
+    assert_eq!(classify_map_entry(&file), MapEntryKind::Unknown);
+    assert_eq!(
+        classify_map_entry(&folder.0.join("not_there")),
+        MapEntryKind::Unknown
+    );
+}
+
+#[test]
+fn short_tiles_are_corrupted() {
+    let folder = MapFolder::new("short_tiles");
+
+    // shorter than the magic number the tiles start with
+    let path = folder.entry(
+        "short",
+        &[("info.ron", &info()), ("map.zst", &ZSTD_MAGIC[..2])],
+    );
+
+    assert_eq!(classify_map_entry(&path), MapEntryKind::Corrupted);
+}
+
+#[test]
+fn scan_is_sorted_and_skips_hidden_entries() {
+    let folder = MapFolder::new("scanned");
+    let info = info();
+    let tiles = tiles();
+
+    folder.entry("b", &[("info.ron", &info), ("map.zst", &tiles)]);
+    folder.entry("a", &[("map.zst", &tiles)]);
+    folder.entry(".hidden", &[("map.zst", b"not compressed")]);
+    folder.entry("c", &[]);
+
+    assert_eq!(
+        scan_map_folder(&folder.0),
+        vec![
+            ("a".to_string(), MapEntryKind::MissingInfo),
+            ("b".to_string(), MapEntryKind::Valid),
+            ("c".to_string(), MapEntryKind::Unknown),
+        ]
+    );
+    assert_eq!(scan_map_folder(&folder.0.join("not_there")), vec![]);
+}
+
+#[test]
+fn only_missing_info_is_repaired() {
+    let folder = MapFolder::new("repaired");
+    let tiles = tiles();
+
+    let missing_info = folder.entry("missing_info", &[("map.zst", &tiles)]);
+    assert!(repair_map_entry(&missing_info, MapRepair::RecreateInfo).unwrap());
+    assert_eq!(classify_map_entry(&missing_info), MapEntryKind::Valid);
+    // the tiles are left as they were
+    assert_eq!(fs::read(missing_info.join("map.zst")).unwrap(), tiles);
+
+    let bad_info = b"(this is not map info";
+    let corrupted = folder.entry("corrupted", &[("info.ron", bad_info), ("map.zst", &tiles)]);
+    let unknown = folder.entry("unknown", &[]);
+
+    for path in [&corrupted, &unknown] {
+        let before = files(path);
+
+        assert!(!repair_map_entry(path, MapRepair::RecreateInfo).unwrap());
+        assert_eq!(files(path), before);
+    }
+    assert_eq!(fs::read(corrupted.join("info.ron")).unwrap(), bad_info);
+}
+
+/// Sets when the info of the save at the path was last modified.
+fn set_saved(path: &Path, time: SystemTime) {
+    File::options()
+        .write(true)
+        .open(path.join("info.ron"))
+        .unwrap()
+        .set_modified(time)
+        .unwrap();
+}
+
+#[test]
+fn files_of_maps_are_classified() {
+    let folder = MapFolder::new("files_classified");
+    let info = info();
+    let tiles = tiles();
+    let mut dictionary = DICTIONARY_MAGIC.to_vec();
+    dictionary.extend([0; 16]);
+
+    let map = folder.entry(
+        "map",
+        &[
+            ("info.ron", &info),
+            ("map.zst", &tiles),
+            ("info.ron.tmp", &info),
+            ("map.zst.tmp", &tiles),
+            ("map.dict.tmp", &dictionary),
+            ("info.ron.1", &info),
+            ("map.zst.2", &tiles),
+            ("map.dict.9", &dictionary),
+            ("notes.txt", b"not a map"),
+        ],
+    );
+    let cut_short = folder.entry(
+        "cut_short",
+        &[
+            ("info.ron", &info),
+            ("map.zst", &tiles),
+            ("info.ron.tmp", b"(tick_count: 3"),
+            ("map.zst.tmp", b""),
+            ("map.dict.tmp", &tiles),
+            ("thumbnail.png.tmp", b"not a map file"),
+            ("map.zst.10", &tiles),
+            ("map.zst.0", &tiles),
+            ("thumbnail.png.1", b"not backed up"),
+        ],
+    );
+
+    for file in ["info.ron.tmp", "map.zst.tmp", "map.dict.tmp"] {
+        assert_eq!(
+            classify_map_entry(&map.join(file)),
+            MapEntryKind::Temp { valid: true },
+            "{file}"
+        );
+    }
+    for file in [
+        "info.ron.tmp",
+        "map.zst.tmp",
+        "map.dict.tmp",
+        "thumbnail.png.tmp",
+    ] {
+        assert_eq!(
+            classify_map_entry(&cut_short.join(file)),
+            MapEntryKind::Temp { valid: false },
+            "{file}"
+        );
+    }
+
+    for file in ["info.ron.1", "map.zst.2", "map.dict.9"] {
+        assert_eq!(
+            classify_map_entry(&map.join(file)),
+            MapEntryKind::Backup,
+            "{file}"
+        );
+    }
+    // past the most backups kept, not numbered from the first, or not a file that is backed up
+    for file in ["map.zst.10", "map.zst.0", "thumbnail.png.1"] {
+        assert_eq!(
+            classify_map_entry(&cut_short.join(file)),
+            MapEntryKind::Unknown,
+            "{file}"
+        );
+    }
+    assert_eq!(
+        classify_map_entry(&map.join("notes.txt")),
+        MapEntryKind::Unknown
+    );
+}
+
+#[test]
+fn autosaves_and_recovery_copies_are_classified() {
+    let folder = MapFolder::new("autosaves_classified");
+    let info = info();
+    let tiles = tiles();
+    let save: [(&str, &[u8]); 2] = [("info.ron", &info), ("map.zst", &tiles)];
+
+    let now = SystemTime::now();
+    let earlier = now - Duration::from_secs(60);
+
+    let behind = folder.entry("behind", &save);
+    let behind_autosave = folder.entry("behind/autosave", &save);
+    set_saved(&behind, now);
+    set_saved(&behind_autosave, earlier);
+
+    let ahead = folder.entry("ahead", &save);
+    let ahead_autosave = folder.entry("ahead/autosave", &save);
+    set_saved(&ahead, earlier);
+    set_saved(&ahead_autosave, now);
+
+    // only a folder in a map is its autosave
+    let not_autosave = folder.entry("autosave", &save);
+
+    let recovery = folder.entry("factory-ERR-1700000000", &save);
+    let empty_recovery = folder.entry("empty-ERR-1700000000", &[]);
+
+    assert_eq!(
+        classify_map_entry(&behind_autosave),
+        MapEntryKind::Autosave { newer: false }
+    );
+    assert_eq!(
+        classify_map_entry(&ahead_autosave),
+        MapEntryKind::Autosave { newer: true }
+    );
+    assert_eq!(classify_map_entry(&not_autosave), MapEntryKind::Valid);
+    assert_eq!(classify_map_entry(&recovery), MapEntryKind::ErrorRecovery);
+    assert_eq!(classify_map_entry(&empty_recovery), MapEntryKind::Unknown);
+
+    assert!(!MapEntryKind::Autosave { newer: false }.needs_attention());
+    assert!(MapEntryKind::Autosave { newer: true }.needs_attention());
+}
+
+#[test]
+fn scan_goes_into_maps_and_their_autosaves() {
+    let folder = MapFolder::new("scanned_deep");
+    let info = info();
+    let tiles = tiles();
+
+    folder.entry(
+        "map",
+        &[
+            ("info.ron", &info),
+            ("map.zst", &tiles),
+            ("thumbnail.png", b"thumbnail"),
+            ("map.zst.tmp", &tiles),
+            ("map.zst.1", &tiles),
+            (".hidden", b"hidden"),
+        ],
+    );
+    folder.entry(
+        "map/autosave",
+        &[
+            ("info.ron", &info),
+            ("map.zst", &tiles),
+            ("info.ron.tmp", b"(tick_count"),
+        ],
+    );
+    let autosave = folder.0.join("map/autosave");
+    set_saved(&folder.0.join("map"), SystemTime::now());
+    set_saved(&autosave, SystemTime::now() - Duration::from_secs(60));
+
+    // what isn't a map isn't looked into
+    folder.entry("stray", &[("map.zst.tmp", &tiles)]);
+
+    let scanned = scan_map_folder(&folder.0);
+    assert_eq!(
+        scanned,
+        vec![
+            ("map".to_string(), MapEntryKind::Valid),
+            (
+                "map/autosave".to_string(),
+                MapEntryKind::Autosave { newer: false }
+            ),
+            (
+                "map/autosave/info.ron.tmp".to_string(),
+                MapEntryKind::Temp { valid: false }
+            ),
+            ("map/map.zst.1".to_string(), MapEntryKind::Backup),
+            (
+                "map/map.zst.tmp".to_string(),
+                MapEntryKind::Temp { valid: true }
+            ),
+            ("stray".to_string(), MapEntryKind::Unknown),
+        ]
+    );
+
+    assert_eq!(
+        MapFolderSummary::new(&scanned),
+        MapFolderSummary {
+            valid: 1,
+            valid_temps: 1,
+            invalid_temps: 1,
+            backups: 1,
+            autosaves: 1,
+            unknown: 1,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn summary_counts_every_kind() {
+    let entries = [
+        MapEntryKind::Valid,
+        MapEntryKind::Valid,
+        MapEntryKind::MissingInfo,
+        MapEntryKind::MissingTiles,
+        MapEntryKind::Corrupted,
+        MapEntryKind::Temp { valid: true },
+        MapEntryKind::Temp { valid: false },
+        MapEntryKind::Temp { valid: false },
+        MapEntryKind::Backup,
+        MapEntryKind::Autosave { newer: true },
+        MapEntryKind::Autosave { newer: false },
+        MapEntryKind::ErrorRecovery,
+        MapEntryKind::ErrorRecovery,
+        MapEntryKind::Unknown,
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(i, kind)| (i.to_string(), kind))
+    .collect::<Vec<_>>();
+
+    assert_eq!(
+        MapFolderSummary::new(&entries),
+        MapFolderSummary {
+            valid: 2,
+            broken: 3,
+            valid_temps: 1,
+            invalid_temps: 2,
+            backups: 1,
+            autosaves: 2,
+            newer_autosaves: 1,
+            error_recoveries: 2,
+            unknown: 1,
+        }
+    );
+}
+
+#[test]
+fn valid_temp_files_are_promoted() {
+    let folder = MapFolder::new("promoted");
+    let info = info();
+    let tiles = tiles();
+    let mut newer_tiles = ZSTD_MAGIC.to_vec();
+    newer_tiles.extend([1; 16]);
+
+    // cut short after the info was moved into place, but before the tiles were
+    let path = folder.entry(
+        "map",
+        &[
+            ("info.ron", &info),
+            ("map.zst", &tiles),
+            ("map.zst.tmp", &newer_tiles),
+        ],
+    );
+    let temp = path.join("map.zst.tmp");
+
+    // the repair has to be the one for the file
+    assert!(!repair_map_entry(&temp, MapRepair::RemoveTemp).unwrap());
+    assert!(!repair_map_entry(&temp, MapRepair::RecreateInfo).unwrap());
+    assert!(temp.exists());
+
+    assert!(repair_map_entry(&temp, MapRepair::PromoteTemp).unwrap());
+    assert!(!temp.exists());
+    assert_eq!(fs::read(path.join("map.zst")).unwrap(), newer_tiles);
+    assert_eq!(classify_map_entry(&path), MapEntryKind::Valid);
+
+    // the info is written again when it is missing
+    let missing_info = folder.entry(
+        "missing_info",
+        &[("map.zst", &tiles), ("info.ron.tmp", &info)],
+    );
+    assert!(repair_map_entry(&missing_info.join("info.ron.tmp"), MapRepair::PromoteTemp).unwrap());
+    assert_eq!(fs::read(missing_info.join("info.ron")).unwrap(), info);
+    assert_eq!(classify_map_entry(&missing_info), MapEntryKind::Valid);
+
+    // already gone
+    assert!(!repair_map_entry(&temp, MapRepair::PromoteTemp).unwrap());
+}
+
+#[test]
+fn invalid_temp_files_are_removed() {
+    let folder = MapFolder::new("removed");
+    let info = info();
+    let tiles = tiles();
+
+    let path = folder.entry(
+        "map",
+        &[
+            ("info.ron", &info),
+            ("map.zst", &tiles),
+            ("info.ron.tmp", b"(tick_count: 3"),
+        ],
+    );
+    let temp = path.join("info.ron.tmp");
+
+    assert!(!repair_map_entry(&temp, MapRepair::PromoteTemp).unwrap());
+    assert!(temp.exists());
+
+    assert!(repair_map_entry(&temp, MapRepair::RemoveTemp).unwrap());
+    assert!(!temp.exists());
+
+    // the saved files are left as they were
+    assert_eq!(
+        files(&path),
+        vec![
+            ("info.ron".to_string(), info),
+            ("map.zst".to_string(), tiles),
+        ]
+    );
+
+    // nothing else is removed as a temp file
+    let backup = folder.entry("backed_up", &[("map.zst.1", &[][..])]);
+    assert!(!repair_map_entry(&backup.join("map.zst.1"), MapRepair::RemoveTemp).unwrap());
+    assert!(backup.join("map.zst.1").exists());
+}
+
+/// The files in the entry with their contents, sorted by name.
+fn files(path: &Path) -> Vec<(String, Vec<u8>)> {
+    let mut files = fs::read_dir(path)
+        .unwrap()
+        .flatten()
+        .map(|entry| {
+            (
+                entry.file_name().to_string_lossy().to_string(),
+                fs::read(entry.path()).unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+
+    files
+}
+
+#[test]
+fn names_are_trimmed_before_they_are_checked() {
+    assert_eq!(sanitize_name("My Factory".to_string()), "My_Factory");
+    assert_eq!(sanitize_name("  .factory. ".to_string()), "factory");
+    assert_eq!(sanitize_name(". . factory".to_string()), "factory");
+    assert_eq!(sanitize_name("a.b".to_string()), "a_b");
+
+    // nothing is left to name the folder after, so it can't end up as the map folder itself or its parent
+    for name in ["", ".", "..", "   ", " .. ", "\t\n"] {
+        assert_eq!(sanitize_name(name.to_string()), "empty", "{name:?}");
+    }
+}
@@ -0,0 +1,106 @@
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::Interner;
+use automancy_resources::data::DataMapRaw;
+use automancy_system::map::chunk::chunk_raw_tiles;
+use automancy_system::map::{read_frames, write_frame, MapFormat, MapRaw, BINARY_MAGIC};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::time::Instant;
+
+const TILE_DATA: &str = r#"({
+    "core/amount": Amount(12),
+    "core/script": Id("core/iron_plate"),
+    "core/linked": Bool(true),
+    "core/buffer": Inventory(([("core/iron", 3), ("core/copper", 40)])),
+    "core/weights": VecFloat([0.5, 1.25]),
+})"#;
+
+/// Makes a map with the number of tiles in a square around the middle, with some data on every other tile.
+fn synthetic_map(tile_count: i32) -> MapRaw {
+    let mut interner = Interner::new();
+    let machine = interner.get_or_intern("core/machine");
+    let data = ron::from_str::<DataMapRaw>(TILE_DATA).unwrap();
+
+    let side = (tile_count as f64).sqrt().ceil() as i32;
+    let tiles = (0..tile_count)
+        .map(|i| {
+            let coord = TileCoord::new(i % side - side / 2, i / side - side / 2);
+            let data = if i % 2 == 0 {
+                data.clone()
+            } else {
+                DataMapRaw::default()
+            };
+
+            (coord, machine, data)
+        })
+        .collect::<Vec<_>>();
+
+    MapRaw {
+        tiles: vec![],
+        chunks: chunk_raw_tiles(tiles),
+        tile_map: BTreeMap::from([(machine, "core/machine".to_string())]),
+        footprints: vec![(TileCoord::new(1, 0), TileCoord::ZERO)],
+        quarantine: vec![(TileCoord::ZERO, data)],
+        terrain: Default::default(),
+    }
+}
+
+fn write_map(map: &MapRaw, format: MapFormat) -> Vec<u8> {
+    let mut writer = write_frame(vec![], map, format, 3, None).unwrap();
+    for chunk in &map.chunks {
+        writer = write_frame(writer, chunk, format, 3, None).unwrap();
+    }
+
+    writer
+}
+
+/// Everything the map has, chunks included, as RON to compare.
+fn content(map: &MapRaw) -> String {
+    ron::to_string(&(map, &map.chunks)).unwrap()
+}
+
+#[test]
+fn test_map_format_round_trip() {
+    let map = synthetic_map(1000);
+
+    let readable = write_map(&map, MapFormat::Readable);
+    let from_readable = read_frames(readable.as_slice(), None).unwrap();
+    assert_eq!(content(&map), content(&from_readable));
+
+    let binary = write_map(&from_readable, MapFormat::Binary);
+    let mut magic = [0; BINARY_MAGIC.len()];
+    zstd::Decoder::new(binary.as_slice())
+        .unwrap()
+        .single_frame()
+        .read_exact(&mut magic)
+        .unwrap();
+    assert_eq!(magic, BINARY_MAGIC);
+    let from_binary = read_frames(binary.as_slice(), None).unwrap();
+    assert_eq!(content(&map), content(&from_binary));
+
+    let readable_again = write_map(&from_binary, MapFormat::Readable);
+    assert_eq!(readable, readable_again);
+}
+
+/// Compares the time maps take to save and load in each format. Run with `cargo test --release -- --ignored --nocapture`.
+#[test]
+#[ignore]
+fn bench_map_format() {
+    let map = synthetic_map(50_000);
+
+    for format in [MapFormat::Readable, MapFormat::Binary] {
+        let start = Instant::now();
+        let bytes = write_map(&map, format);
+        let saved = start.elapsed();
+
+        let start = Instant::now();
+        let loaded = read_frames(bytes.as_slice(), None).unwrap();
+        let read = start.elapsed();
+
+        assert_eq!(loaded.chunks.len(), map.chunks.len());
+        println!(
+            "{format:?}: {} KiB, saved in {saved:?}, loaded in {read:?}",
+            bytes.len() / 1024
+        );
+    }
+}
@@ -0,0 +1,351 @@
+use automancy_defs::coord::{TileBounds, TileCoord, TileUnit};
+use automancy_defs::glam::IVec2;
+use automancy_defs::id::TileId;
+use automancy_defs::kira::manager::backend::mock::MockBackend;
+use automancy_defs::kira::manager::{AudioManager, AudioManagerSettings};
+use automancy_defs::kira::track::TrackBuilder;
+use automancy_defs::math::Float;
+use automancy_defs::stack::ItemAmount;
+use automancy_resources::builder::ResourceManagerBuilder;
+use automancy_resources::data::{DataMapRaw, DataRaw};
+use automancy_resources::ResourceManager;
+use automancy_system::map::builder::MapBuilder;
+use automancy_system::map::chunk::ChunkRaw;
+use automancy_system::map::migrate::{migrate, MAP_VERSION};
+use automancy_system::map::{read_frames, write_frame, MapFormat, MapInfoRaw, MapRaw};
+use automancy_system::terrain::TerrainRaw;
+use proptest::collection::{btree_map, vec};
+use proptest::prelude::*;
+use proptest::sample::select;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// How many random maps each property is checked with.
+const CASES: u32 = 64;
+
+const NAMESPACES: [&str; 3] = ["core", "test", "missing"];
+const NAMES: [&str; 6] = ["machine", "belt", "iron", "copper", "chest", "script"];
+
+/// The registry the random maps take their tiles from, with a tile of each of [NAMES].
+static RESOURCES: LazyLock<ResourceManager> = LazyLock::new(|| {
+    let mut audio_man = AudioManager::<MockBackend>::new(AudioManagerSettings::default()).unwrap();
+    let track = audio_man.add_sub_track(TrackBuilder::new()).unwrap();
+
+    NAMES
+        .iter()
+        .fold(ResourceManagerBuilder::new(track), |builder, name| {
+            builder.tile(name, "core")
+        })
+        .build()
+});
+
+fn ids() -> impl Strategy<Value = String> {
+    (select(NAMESPACES.as_slice()), select(NAMES.as_slice()))
+        .prop_map(|(namespace, name)| format!("{namespace}:{name}"))
+}
+
+fn id_lists() -> impl Strategy<Value = Vec<String>> {
+    vec(ids(), 0..4)
+}
+
+fn coords() -> impl Strategy<Value = TileCoord> {
+    (-100..100, -100..100).prop_map(|(q, r): (TileUnit, TileUnit)| TileCoord::new(q, r))
+}
+
+fn offsets() -> impl Strategy<Value = IVec2> {
+    (-50..50, -50..50).prop_map(|(x, y): (i32, i32)| IVec2::new(x, y))
+}
+
+/// A float that is written and read back exactly.
+fn floats() -> impl Strategy<Value = Float> {
+    (-1000..1000).prop_map(|v: i32| v as Float / 8.0)
+}
+
+/// An entry of any kind there is.
+fn data() -> impl Strategy<Value = DataRaw> {
+    prop_oneof![
+        vec((ids(), any::<ItemAmount>()), 0..4).prop_map(|v| DataRaw::Inventory(v.into())),
+        coords().prop_map(DataRaw::Coord),
+        vec(coords(), 0..4).prop_map(DataRaw::VecCoord),
+        ids().prop_map(DataRaw::Id),
+        (0..0x1000000u32).prop_map(|v| DataRaw::Color(format!("#{v:06x}"))),
+        id_lists().prop_map(DataRaw::VecId),
+        id_lists().prop_map(DataRaw::SetId),
+        any::<ItemAmount>().prop_map(DataRaw::Amount),
+        any::<bool>().prop_map(DataRaw::Bool),
+        prop_oneof![
+            Just(TileBounds::Empty),
+            (coords(), 0..20u32).prop_map(|(center, radius)| TileBounds::new(center, radius)),
+        ]
+        .prop_map(DataRaw::TileBounds),
+        vec(offsets(), 0..4).prop_map(DataRaw::VecOffsetCoord),
+        vec((coords(), ids()), 0..4).prop_map(DataRaw::TileMap),
+        vec((offsets(), ids()), 0..4).prop_map(DataRaw::TileMapOffsetCoord),
+        vec((ids(), id_lists()), 0..4).prop_map(DataRaw::MapSetId),
+        vec(floats(), 0..4).prop_map(DataRaw::VecFloat),
+        vec(any::<u16>().prop_map(|v| format!("\"quoted\" {v}\n")), 0..4)
+            .prop_map(DataRaw::VecString),
+    ]
+}
+
+fn data_maps() -> impl Strategy<Value = DataMapRaw> {
+    btree_map(ids(), data(), 0..16).prop_map(DataMapRaw::from)
+}
+
+fn tiles() -> impl Strategy<Value = Vec<(TileCoord, TileId, DataMapRaw)>> {
+    vec(
+        (
+            coords(),
+            select(RESOURCES.ordered_tiles.clone()),
+            data_maps(),
+        ),
+        0..200,
+    )
+}
+
+fn terrains() -> impl Strategy<Value = TerrainRaw> {
+    (id_lists(), vec((coords(), 1..100u32, 0..4u32), 0..8))
+        .prop_map(|(palette, runs)| TerrainRaw { palette, runs })
+}
+
+fn build(tiles: Vec<(TileCoord, TileId, DataMapRaw)>) -> MapRaw {
+    tiles
+        .into_iter()
+        .fold(MapBuilder::new(&RESOURCES), |builder, (coord, id, data)| {
+            builder.tile(coord, id, data)
+        })
+        .build()
+}
+
+fn maps() -> impl Strategy<Value = MapRaw> {
+    (
+        tiles(),
+        vec((coords(), coords()), 0..8),
+        vec((coords(), data_maps()), 0..8),
+        terrains(),
+    )
+        .prop_map(|(tiles, footprints, quarantine, terrain)| {
+            let mut map = build(tiles);
+            map.footprints = footprints;
+            map.quarantine = quarantine;
+            map.terrain = terrain;
+
+            map
+        })
+}
+
+fn write_map(map: &MapRaw, format: MapFormat) -> Vec<u8> {
+    let mut writer = write_frame(vec![], map, format, 3, None).unwrap();
+    for chunk in &map.chunks {
+        writer = write_frame(writer, chunk, format, 3, None).unwrap();
+    }
+
+    writer
+}
+
+/// Everything the map has, chunks included, as RON to compare.
+fn content(map: &MapRaw) -> String {
+    ron::to_string(&(map, &map.chunks)).unwrap()
+}
+
+fn all_tiles(chunks: &[ChunkRaw]) -> String {
+    ron::to_string(
+        &chunks
+            .iter()
+            .flat_map(|chunk| &chunk.tiles)
+            .collect::<Vec<_>>(),
+    )
+    .unwrap()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(CASES))]
+
+    #[test]
+    fn built_maps_keep_the_last_tile_at_each_coordinate(tiles in tiles()) {
+        let map = build(tiles.clone());
+        let placed = map
+            .chunks
+            .iter()
+            .flat_map(|chunk| &chunk.tiles)
+            .collect::<Vec<_>>();
+
+        let expected = tiles
+            .iter()
+            .map(|(coord, id, _)| (*coord, *id))
+            .collect::<HashMap<_, _>>();
+        prop_assert_eq!(placed.len(), expected.len());
+        for (coord, id, _) in placed {
+            prop_assert_eq!(expected[coord], TileId(*id));
+        }
+
+        // the tile map names the IDs of the tiles, and nothing else
+        let ids = expected.values().collect::<HashSet<_>>();
+        prop_assert_eq!(map.tile_map.len(), ids.len());
+        for id in ids {
+            prop_assert_eq!(&map.tile_map[&**id], RESOURCES.interner.resolve(**id).unwrap());
+        }
+    }
+
+    #[test]
+    fn random_maps_round_trip_in_every_format(map in maps()) {
+        for format in [MapFormat::Readable, MapFormat::Binary] {
+            let bytes = write_map(&map, format);
+            let read = read_frames(bytes.as_slice(), None).unwrap();
+
+            prop_assert_eq!(content(&map), content(&read), "{:?}", format);
+            // saving what was read gives the same file again
+            prop_assert_eq!(bytes, write_map(&read, format), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn random_maps_survive_format_switches(map in maps()) {
+        let expected = content(&map);
+
+        let mut read = map;
+        for format in [
+            MapFormat::Binary,
+            MapFormat::Readable,
+            MapFormat::Binary,
+            MapFormat::Binary,
+            MapFormat::Readable,
+        ] {
+            read = read_frames(write_map(&read, format).as_slice(), None).unwrap();
+
+            prop_assert_eq!(&expected, &content(&read), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn random_maps_round_trip_with_frames_in_both_formats(map in maps()) {
+        // each frame is read in the format it was written in, so a map can be written in both
+        let mut bytes = write_frame(vec![], &map, MapFormat::Readable, 3, None).unwrap();
+        for (index, chunk) in map.chunks.iter().enumerate() {
+            let format = if index % 2 == 0 {
+                MapFormat::Binary
+            } else {
+                MapFormat::Readable
+            };
+
+            bytes = write_frame(bytes, chunk, format, 3, None).unwrap();
+        }
+
+        let read = read_frames(bytes.as_slice(), None).unwrap();
+        prop_assert_eq!(content(&map), content(&read));
+    }
+
+    #[test]
+    fn random_old_maps_migrate_to_chunks(map in tiles().prop_map(build)) {
+        let expected = all_tiles(&map.chunks);
+        let mut tiles = map
+            .chunks
+            .into_iter()
+            .flat_map(|chunk| chunk.tiles)
+            .collect::<Vec<_>>();
+        tiles.sort_by_key(|(coord, ..)| *coord);
+
+        for version in [0, 1] {
+            // versions before 2 saved the tiles in one list in the one frame, always as RON
+            let old = MapRaw {
+                tiles: tiles.clone(),
+                chunks: vec![],
+                tile_map: map.tile_map.clone(),
+                footprints: vec![],
+                quarantine: vec![],
+                terrain: Default::default(),
+            };
+            let bytes = write_frame(vec![], &old, MapFormat::Readable, 3, None).unwrap();
+
+            let mut info = MapInfoRaw {
+                version,
+                ..Default::default()
+            };
+            let read = read_frames(bytes.as_slice(), None).unwrap();
+            prop_assert!(read.chunks.is_empty());
+
+            let migrated = migrate(&mut info, read);
+            prop_assert_eq!(info.version, MAP_VERSION);
+            prop_assert!(migrated.tiles.is_empty());
+            prop_assert_eq!(&expected, &all_tiles(&migrated.chunks), "v{}", version);
+
+            // once migrated, it round trips like any other map
+            for format in [MapFormat::Readable, MapFormat::Binary] {
+                let again = read_frames(write_map(&migrated, format).as_slice(), None).unwrap();
+
+                prop_assert_eq!(content(&migrated), content(&again), "v{}, {:?}", version, format);
+            }
+        }
+    }
+}
+
+/// The maps saved by each version of the format, kept in the fixtures folder as `v<version>/info.ron` and `v<version>/map.zst`.
+const FIXTURE_VERSIONS: [u32; 3] = [0, 1, 2];
+
+fn read_fixture(version: u32) -> (MapInfoRaw, MapRaw) {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/maps")
+        .join(format!("v{version}"));
+
+    let info = ron::from_str(&fs::read_to_string(dir.join("info.ron")).unwrap()).unwrap();
+    let map = read_frames(fs::read(dir.join("map.zst")).unwrap().as_slice(), None).unwrap();
+
+    (info, map)
+}
+
+#[test]
+fn fixtures_of_every_version_load() {
+    let mut loaded = vec![];
+
+    for version in FIXTURE_VERSIONS {
+        let (mut info, map) = read_fixture(version);
+        assert_eq!(info.version, version);
+
+        let map = migrate(&mut info, map);
+        assert_eq!(info.version, MAP_VERSION);
+        assert!(map.tiles.is_empty());
+        assert_eq!(
+            info.tile_count as usize,
+            map.chunks
+                .iter()
+                .map(|chunk| chunk.tiles.len())
+                .sum::<usize>()
+        );
+
+        for format in [MapFormat::Readable, MapFormat::Binary] {
+            let again = read_frames(write_map(&map, format).as_slice(), None).unwrap();
+
+            assert_eq!(content(&map), content(&again), "v{version}, {format:?}");
+        }
+
+        loaded.push((version, map));
+    }
+
+    // every version keeps the same tiles, with the same data
+    let (_, latest) = loaded.last().unwrap();
+    for (version, map) in &loaded {
+        assert_eq!(
+            all_tiles(&latest.chunks),
+            all_tiles(&map.chunks),
+            "v{version}"
+        );
+        assert_eq!(latest.tile_map, map.tile_map, "v{version}");
+    }
+
+    let tiles = latest
+        .chunks
+        .iter()
+        .flat_map(|chunk| &chunk.tiles)
+        .collect::<Vec<_>>();
+    let (_, _, data) = tiles
+        .iter()
+        .find(|(coord, ..)| *coord == TileCoord::new(20, -3))
+        .unwrap();
+    assert!(matches!(data.get("core:amount"), Some(DataRaw::Amount(12))));
+    assert!(matches!(
+        data.get("core:buffer"),
+        Some(DataRaw::Inventory(_))
+    ));
+}
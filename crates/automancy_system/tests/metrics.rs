@@ -0,0 +1,135 @@
+mod common;
+
+use automancy_system::metrics::{self, MetricsSnapshot, PUBLISH_INTERVAL};
+use common::{load_pack, run_ticks, start_game, TestMap};
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Sends the request line to the server, returning the status line and the body of the response.
+async fn request(port: u16, line: &str) -> (String, String) {
+    let mut stream = loop {
+        // the server is started on a task, so it may not be listening yet
+        match TcpStream::connect(("127.0.0.1", port)).await {
+            Ok(stream) => break stream,
+            Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+        }
+    };
+
+    stream
+        .write_all(format!("{line}\r\nHost: localhost\r\n\r\n").as_bytes())
+        .await
+        .unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+
+    (head.lines().next().unwrap().to_string(), body.to_string())
+}
+
+/// Gets the value of the metric without labels from the scraped text.
+fn value(body: &str, name: &str) -> Option<f64> {
+    body.lines()
+        .filter(|line| !line.starts_with('#'))
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(' ')?.parse().ok())
+}
+
+#[test]
+fn encodes_the_text_format() {
+    let snapshot = MetricsSnapshot {
+        map_ticks: 120,
+        ticks_processed: 60,
+        logic_frequency: 60.0,
+        transfer_frequency: 30.0,
+        entity_count: 3,
+        tiles_by_category: vec![("core:machines".to_string(), 2), ("none".to_string(), 1)],
+        produced: vec![("test:\"odd\\ item\"".to_string(), 7)],
+        last_save_duration: Some(Duration::from_millis(250)),
+    };
+    let body = snapshot.encode();
+
+    assert_eq!(value(&body, "automancy_ticks_processed_total"), Some(60.0));
+    assert_eq!(value(&body, "automancy_map_ticks"), Some(120.0));
+    assert_eq!(value(&body, "automancy_entities"), Some(3.0));
+    assert_eq!(value(&body, "automancy_last_save_seconds"), Some(0.25));
+
+    for line in [
+        "# TYPE automancy_ticks_processed_total counter",
+        "# TYPE automancy_map_ticks gauge",
+        "automancy_ticks_per_second{stream=\"logic\"} 60",
+        "automancy_ticks_per_second{stream=\"transfer\"} 30",
+        "automancy_tiles{category=\"core:machines\"} 2",
+        "automancy_tiles{category=\"none\"} 1",
+        // the quotes and the backslash of the label are escaped
+        "automancy_items_produced_total{item=\"test:\\\"odd\\\\ item\\\"\"} 7",
+    ] {
+        assert!(body.lines().any(|v| v == line), "{line}\n{body}");
+    }
+
+    // every metric has its help and type once, before its samples
+    for name in [
+        "automancy_ticks_processed_total",
+        "automancy_ticks_per_second",
+        "automancy_tiles",
+        "automancy_items_produced_total",
+    ] {
+        let type_line = format!("# TYPE {name} ");
+        assert_eq!(body.matches(&type_line).count(), 1, "{name}");
+    }
+
+    // nothing is reported for a save that didn't happen
+    let body = MetricsSnapshot::default().encode();
+    assert!(!body.contains("automancy_last_save_seconds"));
+    assert_eq!(value(&body, "automancy_ticks_processed_total"), Some(0.0));
+}
+
+#[test]
+fn scraped_ticks_increase() {
+    let tokio = tokio::runtime::Runtime::new().unwrap();
+
+    // a port that was free a moment ago
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    metrics::spawn_server(&tokio, port);
+    assert!(metrics::enabled());
+
+    tokio.block_on(async {
+        let resource_man = Arc::new(load_pack(&[]));
+        let map = TestMap::new("scraped_ticks_increase");
+        let game = start_game(resource_man, &map).await;
+
+        // ticks until a snapshot newer than the wait is published
+        let scrape = |ticks| {
+            let game = game.clone();
+
+            async move {
+                run_ticks(&game, ticks).await;
+                tokio::time::sleep(PUBLISH_INTERVAL + Duration::from_millis(100)).await;
+                run_ticks(&game, 1).await;
+
+                let (status, body) = request(port, "GET /metrics HTTP/1.1").await;
+                assert_eq!(status, "HTTP/1.1 200 OK");
+
+                value(&body, "automancy_ticks_processed_total").unwrap()
+            }
+        };
+
+        let first = scrape(10).await;
+        let second = scrape(25).await;
+
+        assert!(first >= 11.0, "{first}");
+        assert!(second >= first + 26.0, "{first} -> {second}");
+
+        let (status, _) = request(port, "GET /other HTTP/1.1").await;
+        assert_eq!(status, "HTTP/1.1 404 Not Found");
+        let (status, _) = request(port, "POST /metrics HTTP/1.1").await;
+        assert_eq!(status, "HTTP/1.1 405 Method Not Allowed");
+    });
+}
@@ -0,0 +1,115 @@
+use automancy_system::util::actor::{multi_call_iter, multi_call_iter_tolerant};
+use hashbrown::HashMap;
+use ractor::{Actor, ActorProcessingErr, ActorRef, RpcReplyPort};
+use std::time::Duration;
+
+struct Echo(u32);
+
+enum EchoMsg {
+    Get(RpcReplyPort<u32>),
+    /// never replies, as if the actor were stuck
+    Ignore(RpcReplyPort<u32>),
+}
+
+#[async_trait::async_trait]
+impl Actor for Echo {
+    type Msg = EchoMsg;
+    type State = ();
+    type Arguments = ();
+
+    async fn pre_start(
+        &self,
+        _myself: ActorRef<Self::Msg>,
+        _args: Self::Arguments,
+    ) -> Result<Self::State, ActorProcessingErr> {
+        Ok(())
+    }
+
+    async fn handle(
+        &self,
+        _myself: ActorRef<Self::Msg>,
+        message: Self::Msg,
+        _state: &mut Self::State,
+    ) -> Result<(), ActorProcessingErr> {
+        match message {
+            EchoMsg::Get(reply) => reply.send(self.0)?,
+            EchoMsg::Ignore(reply) => std::mem::forget(reply),
+        }
+
+        Ok(())
+    }
+}
+
+async fn spawn(count: u32) -> HashMap<u32, ActorRef<EchoMsg>> {
+    let mut actors = HashMap::new();
+
+    for i in 0..count {
+        let (actor, _handle) = Actor::spawn(None, Echo(i * 10), ()).await.unwrap();
+        actors.insert(i, actor);
+    }
+
+    actors
+}
+
+#[tokio::test]
+async fn every_actor_replies() {
+    let actors = spawn(3).await;
+
+    let expected = HashMap::from([(0, 0), (1, 10), (2, 20)]);
+    assert_eq!(
+        multi_call_iter(&actors, |reply, _| EchoMsg::Get(reply), None)
+            .await
+            .unwrap(),
+        expected
+    );
+    assert_eq!(
+        multi_call_iter_tolerant(&actors, |reply, _| EchoMsg::Get(reply), None)
+            .await
+            .unwrap(),
+        expected
+    );
+}
+
+#[tokio::test]
+async fn stopped_actors_fail_only_the_strict_call() {
+    let actors = spawn(3).await;
+
+    actors[&1].stop_and_wait(None, None).await.unwrap();
+
+    assert!(
+        multi_call_iter(&actors, |reply, _| EchoMsg::Get(reply), None)
+            .await
+            .is_err()
+    );
+    assert_eq!(
+        multi_call_iter_tolerant(&actors, |reply, _| EchoMsg::Get(reply), None)
+            .await
+            .unwrap(),
+        HashMap::from([(0, 0), (2, 20)])
+    );
+}
+
+#[tokio::test]
+async fn actors_that_do_not_reply_in_time_are_left_out() {
+    let actors = spawn(3).await;
+
+    let build = |reply: RpcReplyPort<u32>, key: u32| {
+        if key == 2 {
+            EchoMsg::Ignore(reply)
+        } else {
+            EchoMsg::Get(reply)
+        }
+    };
+
+    let timeout = Some(Duration::from_millis(100));
+    assert_eq!(
+        multi_call_iter(&actors, build, timeout).await.unwrap(),
+        HashMap::from([(0, 0), (1, 10)])
+    );
+    assert_eq!(
+        multi_call_iter_tolerant(&actors, build, timeout)
+            .await
+            .unwrap(),
+        HashMap::from([(0, 0), (1, 10)])
+    );
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use automancy_defs::coord::{TileBounds, TileCoord};
+use automancy_defs::id::TileId;
+use automancy_system::game::{GameSystemMessage, RenderFrame};
+use common::{id, load_pack, place, start_game, tile, TestMap};
+use ractor::ActorRef;
+use std::sync::Arc;
+
+async fn render_frame(
+    game: &ActorRef<GameSystemMessage>,
+    culling_range: TileBounds,
+    statuses: bool,
+) -> RenderFrame {
+    game.call(
+        |reply| GameSystemMessage::GetAllRenderCommands {
+            culling_range,
+            statuses,
+            reply,
+        },
+        None,
+    )
+    .await
+    .unwrap()
+    .unwrap()
+}
+
+#[tokio::test]
+async fn statuses_come_with_the_render_commands_of_the_tiles_in_view() {
+    let resource_man = Arc::new(load_pack(&[tile("wall", None, true)]));
+    let map = TestMap::new("statuses_come_with_the_render_commands_of_the_tiles_in_view");
+    let game = start_game(resource_man.clone(), &map).await;
+
+    let wall = TileId(id(&resource_man, "wall"));
+    let seen = TileCoord::new(1, 0);
+    let unseen = TileCoord::new(40, 0);
+    place(&game, seen, wall, None).await;
+    place(&game, unseen, wall, None).await;
+
+    let in_view = TileBounds::new(TileCoord::new(0, 0), 5);
+
+    let frame = render_frame(&game, in_view, true).await;
+    assert_eq!(
+        frame.statuses.keys().copied().collect::<Vec<_>>(),
+        vec![seen]
+    );
+    assert_eq!(frame.statuses[&seen].id, wall);
+    // walls have no tick handlers
+    assert_eq!(frame.statuses[&seen].activity, None);
+
+    let frame = render_frame(&game, in_view, false).await;
+    assert!(frame.statuses.is_empty());
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::TileId;
+use automancy_resources::data::{Data, DataMap};
+use automancy_system::game::GameSystemMessage;
+use common::{id, load_pack, place, start_game, tile, TestMap};
+use std::sync::Arc;
+
+#[tokio::test]
+async fn picked_tiles_keep_their_protection() {
+    let resource_man = Arc::new(load_pack(&[tile("wall", None, true)]));
+    let map = TestMap::new("picked_tiles_keep_their_protection");
+    let game = start_game(resource_man.clone(), &map).await;
+
+    let data_ids = &resource_man.registry.data_ids;
+    let wall = TileId(id(&resource_man, "wall"));
+    let coord = TileCoord::new(1, 2);
+
+    let mut data = DataMap::default();
+    data.set(data_ids.protected, Data::Bool(true));
+    data.set(data_ids.direction, Data::Coord(TileCoord::RIGHT));
+    place(&game, coord, wall, Some(data)).await;
+
+    let (picked, data) = game
+        .call(|reply| GameSystemMessage::PickTile(coord, reply), None)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(picked, wall);
+    assert!(matches!(
+        data.get(data_ids.protected),
+        Some(Data::Bool(true))
+    ));
+    assert!(matches!(
+        data.get(data_ids.direction),
+        Some(Data::Coord(direction)) if *direction == TileCoord::RIGHT
+    ));
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use automancy_defs::id::{Id, TileId};
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::ResourceManager;
+use automancy_system::sandbox::Sandbox;
+use automancy_system::util::placeable_tiles;
+use automancy_system::EventLoopStorage;
+use common::{load_pack, research, tile, NAMESPACE};
+use hashbrown::HashSet;
+use std::sync::Arc;
+
+fn id(resource_man: &ResourceManager, name: &str) -> Id {
+    Id::try_parse(&format!("{NAMESPACE}:{name}"), &resource_man.interner).unwrap()
+}
+
+fn unlocked(resource_man: &ResourceManager, game_data: &mut DataMap) -> &mut HashSet<Id> {
+    let Data::SetId(unlocked) = game_data
+        .entry(resource_man.registry.data_ids.unlocked_researches)
+        .or_insert_with(|| Data::SetId(HashSet::new()))
+    else {
+        unreachable!()
+    };
+
+    unlocked
+}
+
+/// Invalidates the cache, as is done after unlocking research or toggling the sandbox, and checks that the tiles are found again.
+fn check(store: &mut EventLoopStorage, resource_man: &ResourceManager, game_data: &mut DataMap) {
+    store.invalidate_placeable_tiles();
+
+    let fresh = placeable_tiles(resource_man, game_data);
+    let cached = store.placeable_tiles(resource_man, game_data);
+
+    assert_eq!(*cached, fresh);
+}
+
+#[test]
+fn cache_follows_research_and_sandbox() {
+    let resource_man = load_pack(&[
+        tile("machine", None, true),
+        tile("belt", None, false),
+        tile("smelter", None, false),
+        research("belts", &["belt"], None),
+        research("smelting", &["smelter"], Some("belts")),
+    ]);
+    let data_ids = resource_man.registry.data_ids;
+    let belt = TileId(id(&resource_man, "belt"));
+
+    let mut store = EventLoopStorage::default();
+    let mut game_data = DataMap::default();
+
+    check(&mut store, &resource_man, &mut game_data);
+    assert!(!store
+        .placeable_tiles(&resource_man, &mut game_data)
+        .contains(&belt));
+
+    let belts = id(&resource_man, "belts");
+    unlocked(&resource_man, &mut game_data).insert(belts);
+    check(&mut store, &resource_man, &mut game_data);
+    assert!(store
+        .placeable_tiles(&resource_man, &mut game_data)
+        .contains(&belt));
+
+    unlocked(&resource_man, &mut game_data).insert(id(&resource_man, "smelting"));
+    check(&mut store, &resource_man, &mut game_data);
+
+    unlocked(&resource_man, &mut game_data).clear();
+    check(&mut store, &resource_man, &mut game_data);
+
+    let sandbox = Sandbox {
+        enabled: true,
+        free_inputs: false,
+    };
+    sandbox.write_data(&mut game_data, &data_ids);
+    check(&mut store, &resource_man, &mut game_data);
+    assert_eq!(
+        store.placeable_tiles(&resource_man, &mut game_data).len(),
+        resource_man.ordered_tiles.len()
+    );
+
+    Sandbox::default().write_data(&mut game_data, &data_ids);
+    check(&mut store, &resource_man, &mut game_data);
+}
+
+#[test]
+fn cache_is_kept_until_invalidated() {
+    let resource_man = load_pack(&[
+        tile("belt", None, false),
+        research("belts", &["belt"], None),
+    ]);
+    let belt = TileId(id(&resource_man, "belt"));
+
+    let mut store = EventLoopStorage::default();
+    let mut game_data = DataMap::default();
+
+    let first = store.placeable_tiles(&resource_man, &mut game_data);
+    let second = store.placeable_tiles(&resource_man, &mut game_data);
+    assert!(Arc::ptr_eq(&first, &second));
+
+    // the tiles are only looked for again once invalidated, not on every call
+    unlocked(&resource_man, &mut game_data).insert(id(&resource_man, "belts"));
+    assert!(!store
+        .placeable_tiles(&resource_man, &mut game_data)
+        .contains(&belt));
+
+    store.invalidate_placeable_tiles();
+    assert!(store
+        .placeable_tiles(&resource_man, &mut game_data)
+        .contains(&belt));
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::TileId;
+use automancy_resources::data::{Data, DataMap};
+use automancy_system::game::GameSystemMessage;
+use automancy_system::map::{GameMap, MAP_EXT};
+use common::{
+    get_data, id, item, load_map, load_pack, place, save_map, settle, start_game, tile, TestMap,
+};
+use std::fs;
+use std::sync::Arc;
+
+fn saved_tiles(map: &TestMap) -> Vec<u8> {
+    fs::read(
+        GameMap::path(&map.0)
+            .unwrap()
+            .join("map")
+            .with_extension(MAP_EXT),
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn quarantined_data_survives_saves() {
+    let with_gem = Arc::new(load_pack(&[tile("holder", None, true), item("gem")]));
+    // the pack the gem came from is gone
+    let without_gem = Arc::new(load_pack(&[tile("holder", None, true)]));
+
+    let map = TestMap::new("quarantined_data_survives_saves");
+    let coord = TileCoord::new(2, -1);
+    let item_key = with_gem.registry.data_ids.item;
+
+    let game = start_game(with_gem.clone(), &map).await;
+    let mut data = DataMap::default();
+    data.set(item_key, Data::Id(id(&with_gem, "gem")));
+    place(&game, coord, TileId(id(&with_gem, "holder")), Some(data)).await;
+    settle(&game, &[coord]).await;
+    save_map(&game).await;
+    let saved = saved_tiles(&map);
+
+    let game = start_game(without_gem.clone(), &map).await;
+    assert_eq!(
+        get_data(&game, coord)
+            .await
+            .get(without_gem.registry.data_ids.item),
+        None
+    );
+    let missing = game
+        .call(GameSystemMessage::TakeMissingIds, None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(missing.get("test:gem"), Some(&1));
+
+    // the quarantined entry is written back as it is, save after save
+    save_map(&game).await;
+    let quarantined = saved_tiles(&map);
+    load_map(&game, &map).await;
+    save_map(&game).await;
+    assert_eq!(saved_tiles(&map), quarantined);
+
+    // once the pack is back, the entry is too, and the map saves to what it was before the pack went missing
+    let game = start_game(with_gem.clone(), &map).await;
+    assert_eq!(
+        get_data(&game, coord).await.get(item_key),
+        Some(&Data::Id(id(&with_gem, "gem")))
+    );
+    save_map(&game).await;
+    assert_eq!(saved_tiles(&map), saved);
+}
+
+#[tokio::test]
+async fn quarantine_goes_with_its_tile() {
+    let with_gem = Arc::new(load_pack(&[
+        tile("holder", None, true),
+        tile("other", None, true),
+        item("gem"),
+    ]));
+    let without_gem = Arc::new(load_pack(&[
+        tile("holder", None, true),
+        tile("other", None, true),
+    ]));
+
+    let map = TestMap::new("quarantine_goes_with_its_tile");
+    let coord = TileCoord::new(0, 0);
+    let item_key = with_gem.registry.data_ids.item;
+
+    let game = start_game(with_gem.clone(), &map).await;
+    let mut data = DataMap::default();
+    data.set(item_key, Data::Id(id(&with_gem, "gem")));
+    place(&game, coord, TileId(id(&with_gem, "holder")), Some(data)).await;
+    settle(&game, &[coord]).await;
+    save_map(&game).await;
+
+    // the tile is replaced while the gem is missing, so what was kept for it is dropped
+    let game = start_game(without_gem.clone(), &map).await;
+    place(&game, coord, TileId(id(&without_gem, "other")), None).await;
+    save_map(&game).await;
+
+    let game = start_game(with_gem.clone(), &map).await;
+    assert_eq!(get_data(&game, coord).await.get(item_key), None);
+}
@@ -0,0 +1,197 @@
+mod common;
+
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::{Id, TileId};
+use automancy_defs::stack::ItemStack;
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::ResourceManager;
+use automancy_system::game::{GameSystemMessage, ScriptUsage};
+use automancy_system::tile_entity::TileEntityMsg;
+use common::{
+    id, load_map, load_pack, place, save_map, script, settle, start_game, tile, tile_entity,
+    TestMap,
+};
+use hashbrown::HashMap;
+use ractor::ActorRef;
+use std::sync::Arc;
+
+fn pack() -> Arc<ResourceManager> {
+    Arc::new(load_pack(&[
+        tile("machine", None, true),
+        tile("furnace", None, true),
+        script("smelt", &[("ore", 1)], &[("ingot", 2)]),
+        script("alloy", &[("ingot", 2)], &[("alloy", 1), ("slag", 1)]),
+    ]))
+}
+
+fn configured_with(resource_man: &ResourceManager, script: Id) -> DataMap {
+    let mut data = DataMap::default();
+    data.set(resource_man.registry.data_ids.script, Data::Id(script));
+
+    data
+}
+
+async fn usage(game: &ActorRef<GameSystemMessage>) -> ScriptUsage {
+    game.call(GameSystemMessage::GetScriptUsage, None)
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+async fn configured(game: &ActorRef<GameSystemMessage>) -> HashMap<Id, u64> {
+    usage(game).await.configured
+}
+
+#[tokio::test]
+async fn configured_counts_follow_the_tiles() {
+    let resource_man = pack();
+    let machine = TileId(id(&resource_man, "machine"));
+    let smelt = id(&resource_man, "smelt");
+    let alloy = id(&resource_man, "alloy");
+    let furnace = TileId(id(&resource_man, "furnace"));
+    let coords = [
+        TileCoord::new(0, 0),
+        TileCoord::new(1, 0),
+        TileCoord::new(2, 0),
+    ];
+
+    let map = TestMap::new("configured_counts");
+    let game = start_game(resource_man.clone(), &map).await;
+    assert!(configured(&game).await.is_empty());
+
+    // placed
+    for coord in coords {
+        place(
+            &game,
+            coord,
+            machine,
+            Some(configured_with(&resource_man, smelt)),
+        )
+        .await;
+    }
+    settle(&game, &coords).await;
+    assert_eq!(configured(&game).await, HashMap::from([(smelt, 3)]));
+
+    // reconfigured
+    tile_entity(&game, coords[0])
+        .await
+        .unwrap()
+        .send_message(TileEntityMsg::SetDataValue(
+            resource_man.registry.data_ids.script,
+            Data::Id(alloy),
+        ))
+        .unwrap();
+    tile_entity(&game, coords[1])
+        .await
+        .unwrap()
+        .send_message(TileEntityMsg::RemoveData(
+            resource_man.registry.data_ids.script,
+        ))
+        .unwrap();
+    settle(&game, &coords).await;
+    assert_eq!(
+        configured(&game).await,
+        HashMap::from([(smelt, 1), (alloy, 1)])
+    );
+
+    // replaced by a tile configured with something else, and removed
+    place(
+        &game,
+        coords[2],
+        furnace,
+        Some(configured_with(&resource_man, alloy)),
+    )
+    .await;
+    settle(&game, &coords).await;
+    assert_eq!(configured(&game).await, HashMap::from([(alloy, 2)]));
+
+    place(&game, coords[0], TileId(resource_man.registry.none), None).await;
+    settle(&game, &coords).await;
+    assert_eq!(configured(&game).await, HashMap::from([(alloy, 1)]));
+
+    // loaded again, with nothing left over from before
+    save_map(&game).await;
+    load_map(&game, &map).await;
+    settle(&game, &coords).await;
+    assert_eq!(configured(&game).await, HashMap::from([(alloy, 1)]));
+
+    game.stop(None);
+}
+
+#[tokio::test]
+async fn crafts_count_what_was_sent_out() {
+    let resource_man = pack();
+    let machine = TileId(id(&resource_man, "machine"));
+    let smelt = id(&resource_man, "smelt");
+    let alloy = id(&resource_man, "alloy");
+    let item = |name: &str| ItemStack {
+        id: id(&resource_man, name),
+        amount: 0,
+    };
+    let coord = TileCoord::new(0, 0);
+
+    let map = TestMap::new("crafts");
+    let game = start_game(resource_man.clone(), &map).await;
+
+    place(
+        &game,
+        coord,
+        machine,
+        Some(configured_with(&resource_man, smelt)),
+    )
+    .await;
+    let tile_entity = tile_entity(&game, coord).await.unwrap();
+    let send_out = |stack: ItemStack, amount| {
+        tile_entity
+            .send_message(TileEntityMsg::TransactionResult {
+                result: ItemStack { amount, ..stack },
+            })
+            .unwrap()
+    };
+    let crafted = |usage: ScriptUsage, script: Id| usage.crafted.get(&script).copied().unwrap_or(0);
+
+    // an output sent out in parts counts once it is all out
+    send_out(item("ingot"), 1);
+    settle(&game, &[coord]).await;
+    assert_eq!(crafted(usage(&game).await, smelt), 0);
+
+    send_out(item("ingot"), 1);
+    settle(&game, &[coord]).await;
+    assert_eq!(crafted(usage(&game).await, smelt), 1);
+
+    // several crafts sent out at once count as several, and what is left over counts towards the next
+    send_out(item("ingot"), 5);
+    settle(&game, &[coord]).await;
+    assert_eq!(crafted(usage(&game).await, smelt), 3);
+
+    send_out(item("ingot"), 1);
+    settle(&game, &[coord]).await;
+    assert_eq!(crafted(usage(&game).await, smelt), 4);
+
+    // what isn't made by the script doesn't count
+    send_out(item("ore"), 10);
+    settle(&game, &[coord]).await;
+    assert_eq!(crafted(usage(&game).await, smelt), 4);
+
+    // with several outputs, each has to be sent out
+    tile_entity
+        .send_message(TileEntityMsg::SetDataValue(
+            resource_man.registry.data_ids.script,
+            Data::Id(alloy),
+        ))
+        .unwrap();
+    send_out(item("alloy"), 2);
+    settle(&game, &[coord]).await;
+    assert_eq!(crafted(usage(&game).await, alloy), 0);
+
+    send_out(item("slag"), 1);
+    settle(&game, &[coord]).await;
+    assert_eq!(crafted(usage(&game).await, alloy), 1);
+
+    send_out(item("slag"), 1);
+    settle(&game, &[coord]).await;
+    assert_eq!(crafted(usage(&game).await, alloy), 2);
+    assert_eq!(crafted(usage(&game).await, smelt), 4);
+
+    game.stop(None);
+}
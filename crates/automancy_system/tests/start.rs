@@ -0,0 +1,158 @@
+mod common;
+
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::TileId;
+use automancy_resources::data::Data;
+use automancy_resources::error::error_log;
+use automancy_resources::ResourceManager;
+use common::{
+    get_data, get_map_data, get_tile, id, item, load_map, load_pack, research, save_map,
+    start_game, tile, TestMap,
+};
+use std::fs;
+use std::sync::Arc;
+
+fn pack() -> ResourceManager {
+    load_pack(&[
+        item("gem"),
+        item("ore"),
+        tile("machine", None, true),
+        tile("belt", None, false),
+        research("belts", &["belt"], None),
+        (
+            "start.ron".to_string(),
+            include_str!("fixtures/start/start.ron").to_string(),
+        ),
+    ])
+}
+
+/// Loads the start definition as that of another pack, loaded after the test pack.
+fn load_other_start(resource_man: &mut ResourceManager, start: &str) {
+    let dir = std::env::temp_dir().join(format!("automancy_test_start_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("start.ron"), start).unwrap();
+
+    resource_man.load_start(&dir, "other").unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    resource_man.compile_start();
+}
+
+#[test]
+fn unresolved_entries_are_reported_and_skipped() {
+    let resource_man = pack();
+    let start = &resource_man.registry.start;
+
+    assert_eq!(start.inventory.len(), 2);
+    assert_eq!(start.researches, [id(&resource_man, "belts")]);
+    assert_eq!(start.tiles.len(), 3);
+    // the key that isn't loaded is left out of the tile's data
+    assert_eq!(start.tiles[0].2.keys().count(), 1);
+
+    let reported = error_log()
+        .into_iter()
+        .filter(|v| v.id == resource_man.registry.err_ids.unresolved_start_entry)
+        .flat_map(|v| {
+            v.args
+                .into_iter()
+                .find(|(key, _)| key == "entry")
+                .map(|(_, entry)| entry)
+        })
+        .collect::<Vec<_>>();
+
+    for entry in [
+        "dust",
+        "unknown_research",
+        "unknown_tile",
+        "test:unknown_key",
+    ] {
+        assert!(reported.iter().any(|v| v == entry), "{entry}: {reported:?}");
+    }
+}
+
+#[test]
+fn later_packs_add_and_override() {
+    let mut resource_man = pack();
+
+    load_other_start(
+        &mut resource_man,
+        r#"(
+            inventory: [("test:gem", 3)],
+            researches: ["test:belts"],
+            tiles: [(coord: (0, 0), id: "test:belt")],
+        )"#,
+    );
+
+    let start = &resource_man.registry.start;
+    let gem = id(&resource_man, "gem");
+    let belt = TileId(id(&resource_man, "belt"));
+
+    assert_eq!(
+        start
+            .inventory
+            .iter()
+            .find(|stack| stack.id == gem)
+            .map(|v| v.amount),
+        Some(8)
+    );
+    assert_eq!(start.researches.len(), 1);
+
+    // the other pack's tile takes the place of the test pack's, leaving its data behind
+    let origin = start
+        .tiles
+        .iter()
+        .filter(|(coord, ..)| *coord == TileCoord::new(0, 0))
+        .collect::<Vec<_>>();
+    assert_eq!(origin.len(), 1);
+    assert_eq!(origin[0].1, belt);
+    assert!(origin[0].2.is_empty());
+    assert_eq!(start.tiles.len(), 3);
+}
+
+#[tokio::test]
+async fn new_maps_get_the_start() {
+    let resource_man = Arc::new(pack());
+    let data_ids = resource_man.registry.data_ids;
+    let map = TestMap::new("new_maps_get_the_start");
+    let game = start_game(resource_man.clone(), &map).await;
+
+    let machine = TileId(id(&resource_man, "machine"));
+    let belt = TileId(id(&resource_man, "belt"));
+    let gem = id(&resource_man, "gem");
+    let ore = id(&resource_man, "ore");
+
+    assert_eq!(get_tile(&game, TileCoord::new(0, 0)).await, Some(machine));
+    assert_eq!(get_tile(&game, TileCoord::new(1, -1)).await, Some(belt));
+    assert_eq!(get_tile(&game, TileCoord::new(-2, 1)).await, Some(machine));
+    assert_eq!(get_tile(&game, TileCoord::new(2, 0)).await, None);
+
+    let origin = get_data(&game, TileCoord::new(0, 0)).await;
+    assert_eq!(origin.get(data_ids.item), Some(&Data::Id(gem)));
+    assert_eq!(
+        get_data(&game, TileCoord::new(-2, 1))
+            .await
+            .get(data_ids.direction),
+        Some(&Data::Coord(TileCoord::new(1, 0)))
+    );
+
+    let check_map_data = |data: automancy_resources::data::DataMap| {
+        let Some(Data::Inventory(inventory)) = data.get(data_ids.player_inventory) else {
+            panic!("no player inventory");
+        };
+        let mut inventory = inventory.clone();
+        assert_eq!(inventory.get(gem), 5);
+        assert_eq!(inventory.get(ore), 2);
+
+        let Some(Data::SetId(unlocked)) = data.get(data_ids.unlocked_researches) else {
+            panic!("no unlocked researches");
+        };
+        assert!(unlocked.contains(&id(&resource_man, "belts")));
+    };
+    check_map_data(get_map_data(&game).await);
+
+    // the start is only given once, loading the map again leaves it as it was saved
+    save_map(&game).await;
+    load_map(&game, &map).await;
+    check_map_data(get_map_data(&game).await);
+    assert_eq!(get_tile(&game, TileCoord::new(0, 0)).await, Some(machine));
+}
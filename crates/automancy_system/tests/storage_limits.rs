@@ -0,0 +1,101 @@
+mod common;
+
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::TileId;
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::inventory::Inventory;
+use common::{
+    function, get_data, id, item, load_pack, machine, place, run_ticks, settle, start_game, TestMap,
+};
+use std::sync::Arc;
+
+#[tokio::test]
+async fn overflow_stays_with_the_producer() {
+    let resource_man = Arc::new(load_pack(&[
+        item("widget"),
+        function(
+            "producer",
+            &[("widget", "WIDGET"), ("core:buffer", "BUFFER")],
+            r#"
+            fn handle_tick(input) {
+                let buffer = this[BUFFER];
+                let held = buffer[WIDGET];
+
+                if held > 0 {
+                    return Result::MakeTransaction(input.coord + TileCoord(1, 0), input.id, input.coord, [ItemStack(WIDGET, held)]);
+                }
+            }
+
+            fn handle_transaction_result(input) {
+                let buffer = this[BUFFER];
+                buffer.take(input.transferred.id, input.transferred.amount);
+                this[BUFFER] = buffer;
+            }
+            "#,
+        ),
+        function(
+            "storage",
+            &[("core:buffer", "BUFFER")],
+            r#"
+            fn handle_transaction(input) {
+                let buffer = this.get_or_new_inventory(BUFFER);
+                buffer.add(input.stack.id, input.stack.amount);
+                this[BUFFER] = buffer;
+
+                TransResult::Consume(input.stack, input.source_coord, input.root_coord)
+            }
+            "#,
+        ),
+        machine("producer", "producer"),
+        (
+            "tiles/storage.ron".to_string(),
+            r#"(
+                id: "storage",
+                function: Some("storage"),
+                data: ({ "core:default_tile": Bool(true), "core:capacity": Amount(8) }),
+            )"#
+            .to_string(),
+        ),
+    ]));
+    let map = TestMap::new("overflow_stays_with_the_producer");
+    let game = start_game(resource_man.clone(), &map).await;
+    let buffer_id = resource_man.registry.data_ids.buffer;
+    let widget = id(&resource_man, "widget");
+    let producer = TileCoord::new(0, 0);
+    let storage = TileCoord::new(1, 0);
+
+    let mut held = Inventory::default();
+    held.insert(widget, 20);
+    let mut data = DataMap::default();
+    data.set(buffer_id, Data::Inventory(held));
+
+    place(
+        &game,
+        producer,
+        TileId(id(&resource_man, "producer")),
+        Some(data),
+    )
+    .await;
+    place(&game, storage, TileId(id(&resource_man, "storage")), None).await;
+
+    let buffer = |data: DataMap| match data.get(buffer_id) {
+        Some(Data::Inventory(buffer)) => buffer.clone().get(widget),
+        _ => 0,
+    };
+
+    // the producer, again after the storage, so that it has handled what was taken
+    let order = [producer, storage, producer];
+
+    run_ticks(&game, 1).await;
+    settle(&game, &order).await;
+    assert_eq!(buffer(get_data(&game, storage).await), 8);
+    assert_eq!(buffer(get_data(&game, producer).await), 12);
+
+    // once full, the storage takes nothing more, and the rest is kept
+    for _ in 0..5 {
+        run_ticks(&game, 1).await;
+        settle(&game, &order).await;
+    }
+    assert_eq!(buffer(get_data(&game, storage).await), 8);
+    assert_eq!(buffer(get_data(&game, producer).await), 12);
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use automancy_defs::id::Id;
+use automancy_resources::error::pop_err;
+use automancy_resources::ResourceManager;
+use common::{id, load_pack, tag};
+use hashbrown::HashSet;
+
+fn entries(resource_man: &ResourceManager, name: &str) -> HashSet<Id> {
+    resource_man.registry.tags[&id(resource_man, name)]
+        .entries
+        .clone()
+}
+
+fn ids(resource_man: &ResourceManager, names: &[&str]) -> HashSet<Id> {
+    names.iter().map(|name| id(resource_man, name)).collect()
+}
+
+#[test]
+fn includes_reach_through_every_level() {
+    let resource_man = load_pack(&[
+        tag("metals", &["iron"], &["precious"], &[]),
+        tag("precious", &["gold"], &["rare"], &[]),
+        tag("rare", &["iridium"], &[], &[]),
+    ]);
+
+    assert_eq!(
+        entries(&resource_man, "metals"),
+        ids(&resource_man, &["iron", "gold", "iridium"])
+    );
+    assert_eq!(
+        entries(&resource_man, "precious"),
+        ids(&resource_man, &["gold", "iridium"])
+    );
+    assert_eq!(
+        entries(&resource_man, "rare"),
+        ids(&resource_man, &["iridium"])
+    );
+}
+
+#[test]
+fn excludes_win_over_includes() {
+    let resource_man = load_pack(&[
+        // excluded at the top, while a tag two levels down includes it
+        tag(
+            "metals",
+            &["iron", "tin"],
+            &["precious"],
+            &["iridium", "tin"],
+        ),
+        tag("precious", &["gold"], &["rare"], &[]),
+        tag("rare", &["iridium", "osmium"], &[], &[]),
+        // excluded in the middle, so it is gone from the tags above as well
+        tag("coins", &[], &["minted"], &[]),
+        tag("minted", &["copper"], &["rare"], &["osmium"]),
+        // excluding a tag removes everything it matches, through its own includes
+        tag("common", &["iron", "gold", "osmium"], &[], &["precious"]),
+    ]);
+
+    assert_eq!(
+        entries(&resource_man, "metals"),
+        ids(&resource_man, &["iron", "gold", "osmium"])
+    );
+    assert_eq!(
+        entries(&resource_man, "coins"),
+        ids(&resource_man, &["copper", "iridium"])
+    );
+    assert_eq!(
+        entries(&resource_man, "common"),
+        ids(&resource_man, &["iron"])
+    );
+    // the included tags keep their own entries
+    assert_eq!(
+        entries(&resource_man, "rare"),
+        ids(&resource_man, &["iridium", "osmium"])
+    );
+}
+
+#[test]
+fn cycles_are_reported_and_add_nothing() {
+    let resource_man = load_pack(&[
+        tag("a", &["x"], &["b"], &[]),
+        tag("b", &["y"], &["c"], &[]),
+        tag("c", &["z"], &["a"], &[]),
+    ]);
+
+    let cycle = resource_man.registry.err_ids.tag_include_cycle;
+    let mut reported = 0;
+    while let Some((err, _)) = pop_err() {
+        if err == cycle {
+            reported += 1;
+        }
+    }
+    assert_eq!(reported, 1);
+
+    // the include closing the cycle is skipped, so only the tag the cycle was found from reaches every entry
+    let all = ids(&resource_man, &["x", "y", "z"]);
+    assert_eq!(
+        ["a", "b", "c"]
+            .into_iter()
+            .filter(|name| entries(&resource_man, name) == all)
+            .count(),
+        1
+    );
+    for (name, own) in [("a", "x"), ("b", "y"), ("c", "z")] {
+        assert!(entries(&resource_man, name).contains(&id(&resource_man, own)));
+    }
+}
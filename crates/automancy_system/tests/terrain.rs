@@ -0,0 +1,196 @@
+mod common;
+
+use automancy_defs::coord::{TileBounds, TileCoord};
+use automancy_defs::id::TileId;
+use automancy_resources::ResourceManager;
+use automancy_system::game::{PlacementFailure, PlacementResult};
+use automancy_system::terrain::{seed_from_name, Terrain, TerrainRaw, GENERATED_RADIUS};
+use common::{id, load_map, load_pack, place, save_map, start_game, terrain, tile, TestMap};
+use std::sync::Arc;
+
+/// A tile that can only go on the terrains, or needs a neighbor with one of them if `edge`.
+fn tile_on(name: &str, terrains: &[&str], edge: bool) -> (String, String) {
+    let terrains = terrains
+        .iter()
+        .map(|v| format!("\"{v}\""))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let key = if edge {
+        "core:terrain_edge"
+    } else {
+        "core:terrains"
+    };
+
+    (
+        format!("tiles/{name}.ron"),
+        format!(
+            r#"(
+                id: "{name}",
+                function: None,
+                data: ({{ "core:default_tile": Bool(true), "{key}": SetId([{terrains}]) }}),
+            )"#
+        ),
+    )
+}
+
+fn pack(water: Option<(i32, i32)>) -> ResourceManager {
+    load_pack(&[
+        terrain("water", false, water),
+        terrain("sand", true, None),
+        tile("house", None, true),
+        tile_on("boat", &["water"], false),
+        tile_on("pump", &["water"], true),
+    ])
+}
+
+#[test]
+fn terrain_round_trips() {
+    let resource_man = pack(None);
+    let water = id(&resource_man, "water");
+    let sand = id(&resource_man, "sand");
+
+    let mut terrain = Terrain::default();
+    // a run along a row, broken by another terrain, and single cells on other rows
+    for q in 0..5 {
+        terrain.set(&resource_man, TileCoord::new(q, 0), water);
+    }
+    terrain.set(&resource_man, TileCoord::new(2, 0), sand);
+    terrain.set(&resource_man, TileCoord::new(-3, 1), sand);
+    terrain.set(&resource_man, TileCoord::new(7, -4), water);
+
+    let raw = terrain.to_raw(&resource_man.interner);
+    assert_eq!(raw.palette, vec!["test:water", "test:sand"]);
+    assert_eq!(
+        raw.runs,
+        vec![
+            (TileCoord::new(7, -4), 1, 0),
+            (TileCoord::new(0, 0), 2, 0),
+            (TileCoord::new(2, 0), 1, 1),
+            (TileCoord::new(3, 0), 2, 0),
+            (TileCoord::new(-3, 1), 1, 1),
+        ]
+    );
+    assert_eq!(Terrain::from_raw(&raw, &resource_man), terrain);
+
+    // setting the default terrain clears the coordinate
+    terrain.set(
+        &resource_man,
+        TileCoord::new(7, -4),
+        resource_man.registry.default_terrain,
+    );
+    assert_eq!(terrain.to_raw(&resource_man.interner).runs.len(), 4);
+}
+
+#[test]
+fn unloaded_terrain_is_the_default() {
+    let resource_man = pack(None);
+    let raw = TerrainRaw {
+        palette: vec!["test:lava".to_string(), "test:water".to_string()],
+        runs: vec![(TileCoord::new(0, 0), 3, 0), (TileCoord::new(0, 1), 2, 1)],
+    };
+
+    let terrain = Terrain::from_raw(&raw, &resource_man);
+    let default = resource_man.registry.default_terrain;
+
+    assert_eq!(terrain.get(&resource_man, TileCoord::new(1, 0)), default);
+    assert_eq!(
+        terrain.get(&resource_man, TileCoord::new(1, 1)),
+        id(&resource_man, "water")
+    );
+}
+
+#[test]
+fn generation() {
+    let resource_man = pack(Some((0, 40)));
+    let water = id(&resource_man, "water");
+    let seed = seed_from_name("generation");
+
+    let terrain = Terrain::generate(&resource_man, seed);
+    assert_eq!(Terrain::generate(&resource_man, seed), terrain);
+
+    let bounds = TileBounds::new(TileCoord::ZERO, GENERATED_RADIUS);
+    let generated = bounds
+        .into_iter()
+        .filter(|coord| terrain.get(&resource_man, *coord) == water)
+        .count();
+    assert!(generated > 0);
+    assert!(generated < bounds.into_iter().count());
+
+    // nothing is generated outside the radius
+    let far = TileCoord::new(GENERATED_RADIUS as i32 + 1, 0);
+    assert_eq!(
+        terrain.get(&resource_man, far),
+        resource_man.registry.default_terrain
+    );
+
+    // without terrains to generate, every coordinate is the default
+    let none = pack(None);
+    assert_eq!(Terrain::generate(&none, seed), Terrain::default());
+
+    // the seed of a name never changes
+    assert_eq!(seed_from_name(""), 0xcbf2_9ce4_8422_2325);
+    assert_eq!(seed_from_name("a"), 0xaf63_dc4c_8601_ec8c);
+}
+
+#[test]
+fn placement_rules() {
+    let resource_man = pack(None);
+    let water = id(&resource_man, "water");
+    let [house, boat, pump] = ["house", "boat", "pump"].map(|v| TileId(id(&resource_man, v)));
+
+    let mut terrain = Terrain::default();
+    let lake = TileCoord::new(0, 0);
+    terrain.set(&resource_man, lake, water);
+    let shore = lake + TileCoord::RIGHT;
+    let inland = TileCoord::new(8, 8);
+
+    assert!(terrain.can_place(&resource_man, house, shore));
+    assert!(!terrain.can_place(&resource_man, house, lake));
+
+    assert!(terrain.can_place(&resource_man, boat, lake));
+    assert!(!terrain.can_place(&resource_man, boat, shore));
+
+    assert!(terrain.can_place(&resource_man, pump, shore));
+    assert!(!terrain.can_place(&resource_man, pump, inland));
+    assert!(!terrain.can_place(&resource_man, pump, lake));
+
+    // removing tiles is always allowed
+    assert!(terrain.can_place(&resource_man, TileId(resource_man.registry.none), lake));
+}
+
+#[tokio::test]
+async fn placing_follows_the_saved_terrain() {
+    // water everywhere it is generated
+    let resource_man = Arc::new(pack(Some((0, 100))));
+    let map = TestMap::new("placing_follows_the_saved_terrain");
+    let game = start_game(resource_man.clone(), &map).await;
+    let [house, boat] = ["house", "boat"].map(|v| TileId(id(&resource_man, v)));
+    let far = TileCoord::new(GENERATED_RADIUS as i32 * 2, 0);
+
+    // even forced placements keep to the terrain
+    assert_eq!(
+        place(&game, TileCoord::ZERO, house, None).await,
+        PlacementResult::Failed(PlacementFailure::InvalidTerrain)
+    );
+    assert_eq!(
+        place(&game, TileCoord::ZERO, boat, None).await,
+        PlacementResult::Placed
+    );
+    assert_eq!(
+        place(&game, far, house, None).await,
+        PlacementResult::Placed
+    );
+
+    save_map(&game).await;
+    load_map(&game, &map).await;
+
+    let coord = TileCoord::new(3, -2);
+    assert_eq!(
+        place(&game, coord, house, None).await,
+        PlacementResult::Failed(PlacementFailure::InvalidTerrain)
+    );
+    assert_eq!(
+        place(&game, coord, boat, None).await,
+        PlacementResult::Placed
+    );
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::TileId;
+use automancy_resources::data::Data;
+use automancy_system::game::{GameSystemMessage, TickRates, NORMAL_TICK_SPEED};
+use common::{
+    function, get_data, get_tick, id, load_map, load_pack, machine, place, run_ticks, save_map,
+    settle, start_game, TestMap,
+};
+use std::sync::Arc;
+
+#[tokio::test]
+async fn ticks_carry_across_saves() {
+    let resource_man = Arc::new(load_pack(&[]));
+    let map = TestMap::new("ticks_carry_across_saves");
+    let game = start_game(resource_man.clone(), &map).await;
+
+    assert_eq!(get_tick(&game).await, 0);
+    run_ticks(&game, 25).await;
+    assert_eq!(get_tick(&game).await, 25);
+
+    save_map(&game).await;
+    run_ticks(&game, 5).await;
+
+    // what was ticked after the save is lost with the session
+    let game = start_game(resource_man, &map).await;
+    assert_eq!(get_tick(&game).await, 25);
+
+    run_ticks(&game, 10).await;
+    save_map(&game).await;
+    load_map(&game, &map).await;
+    assert_eq!(get_tick(&game).await, 35);
+}
+
+#[tokio::test]
+async fn tick_count_follows_the_speed() {
+    let resource_man = Arc::new(load_pack(&[]));
+    let map = TestMap::new("tick_count_follows_the_speed");
+    let game = start_game(resource_man, &map).await;
+
+    let rates = |speed| TickRates {
+        speed,
+        ..TickRates::default()
+    };
+
+    game.send_message(GameSystemMessage::SetTickRates(rates(200)))
+        .unwrap();
+    run_ticks(&game, 10).await;
+    assert_eq!(get_tick(&game).await, 20);
+
+    game.send_message(GameSystemMessage::SetTickRates(rates(50)))
+        .unwrap();
+    run_ticks(&game, 10).await;
+    assert_eq!(get_tick(&game).await, 25);
+
+    // every tick stepped while paused is counted, whatever the speed
+    game.send_message(GameSystemMessage::StepTick(3)).unwrap();
+    run_ticks(&game, 10).await;
+    assert_eq!(get_tick(&game).await, 28);
+
+    game.send_message(GameSystemMessage::SetTickPaused(false))
+        .unwrap();
+    game.send_message(GameSystemMessage::SetTickRates(rates(NORMAL_TICK_SPEED)))
+        .unwrap();
+    run_ticks(&game, 4).await;
+    assert_eq!(get_tick(&game).await, 32);
+}
+
+#[tokio::test]
+async fn scripts_get_the_tick() {
+    let resource_man = Arc::new(load_pack(&[
+        function(
+            "ticker",
+            &[("last_tick", "LAST_TICK")],
+            "fn handle_tick(input) { this[LAST_TICK] = input.tick; }",
+        ),
+        machine("ticker", "ticker"),
+    ]));
+    let map = TestMap::new("scripts_get_the_tick");
+    let game = start_game(resource_man.clone(), &map).await;
+    let coord = TileCoord::new(0, 0);
+
+    place(&game, coord, TileId(id(&resource_man, "ticker")), None).await;
+
+    run_ticks(&game, 12).await;
+    settle(&game, &[coord]).await;
+    assert_eq!(
+        get_data(&game, coord)
+            .await
+            .get(id(&resource_man, "last_tick")),
+        Some(&Data::Amount(11))
+    );
+
+    // the script goes on from the saved count too
+    save_map(&game).await;
+    load_map(&game, &map).await;
+    run_ticks(&game, 1).await;
+    settle(&game, &[coord]).await;
+    assert_eq!(
+        get_data(&game, coord)
+            .await
+            .get(id(&resource_man, "last_tick")),
+        Some(&Data::Amount(12))
+    );
+}
@@ -0,0 +1,197 @@
+mod common;
+
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::TileId;
+use automancy_resources::data::Data;
+use automancy_resources::ResourceManager;
+use automancy_system::game::{GameSystemMessage, TickRates, NORMAL_TICK_SPEED, TPS};
+use common::{
+    function, get_data, id, item, load_pack, machine, place, run_ticks, settle, start_game, TestMap,
+};
+use std::sync::Arc;
+
+/// How many items the producer makes, in its first logic ticks.
+const MADE: i32 = 6;
+const BASE_TICKS: u64 = 60;
+
+/// A chain of a producer, which makes items on its logic ticks, a belt, which moves one of them on per transfer tick, and a consumer.
+fn pack() -> ResourceManager {
+    load_pack(&[
+        item("widget"),
+        function(
+            "producer",
+            &[
+                ("widget", "WIDGET"),
+                ("made", "MADE"),
+                ("ticks", "TICKS"),
+                ("last_tick", "LAST_TICK"),
+            ],
+            &format!(
+                r#"
+                fn handle_tick(input) {{
+                    this[TICKS] = (this[TICKS] ?? 0) + 1;
+                    this[LAST_TICK] = input.tick;
+
+                    let made = this[MADE] ?? 0;
+                    if made < {MADE} {{
+                        this[MADE] = made + 1;
+
+                        return Result::MakeTransaction(input.coord + TileCoord(1, 0), input.id, input.coord, [ItemStack(WIDGET, 1)]);
+                    }}
+                }}
+                "#
+            ),
+        ),
+        function(
+            "belt",
+            &[
+                ("widget", "WIDGET"),
+                ("held", "HELD"),
+                ("ticks", "TICKS"),
+                ("last_tick", "LAST_TICK"),
+            ],
+            r#"
+            fn handle_transaction(input) {
+                this[HELD] = (this[HELD] ?? 0) + input.stack.amount;
+
+                TransResult::Consume(input.stack, input.source_coord, input.root_coord)
+            }
+
+            fn handle_transfer_tick(input) {
+                this[TICKS] = (this[TICKS] ?? 0) + 1;
+                this[LAST_TICK] = input.tick;
+
+                let held = this[HELD] ?? 0;
+                if held > 0 {
+                    this[HELD] = held - 1;
+
+                    return Result::MakeTransaction(input.coord + TileCoord(1, 0), input.id, input.coord, [ItemStack(WIDGET, 1)]);
+                }
+            }
+            "#,
+        ),
+        function(
+            "consumer",
+            &[("received", "RECEIVED")],
+            r#"
+            fn handle_transaction(input) {
+                this[RECEIVED] = (this[RECEIVED] ?? 0) + input.stack.amount;
+
+                TransResult::Consume(input.stack, input.source_coord, input.root_coord)
+            }
+            "#,
+        ),
+        machine("producer", "producer"),
+        machine("belt", "belt"),
+        machine("consumer", "consumer"),
+    ])
+}
+
+#[tokio::test]
+async fn chain_at_rate_ratios() {
+    let resource_man = Arc::new(pack());
+    let amount = |v| Some(Data::Amount(v));
+
+    for (logic, transfer) in [(60, 60), (60, 120), (120, 60), (30, 180), (180, 30)] {
+        let map = TestMap::new(&format!("chain_at_rate_ratios_{logic}_{transfer}"));
+        let game = start_game(resource_man.clone(), &map).await;
+        let coords = [
+            TileCoord::new(0, 0),
+            TileCoord::new(1, 0),
+            TileCoord::new(2, 0),
+        ];
+
+        for (coord, name) in coords.into_iter().zip(["producer", "belt", "consumer"]) {
+            place(&game, coord, TileId(id(&resource_man, name)), None).await;
+        }
+
+        game.send_message(GameSystemMessage::SetTickRates(TickRates {
+            logic,
+            transfer,
+            speed: NORMAL_TICK_SPEED,
+        }))
+        .unwrap();
+
+        // settled every base tick, so that what is sent along the chain arrives before the next tick
+        for _ in 0..BASE_TICKS {
+            run_ticks(&game, 1).await;
+            settle(&game, &coords).await;
+        }
+
+        let logic_ticks = (BASE_TICKS * logic / TPS) as i32;
+        let transfer_ticks = (BASE_TICKS * transfer / TPS) as i32;
+
+        let producer = get_data(&game, coords[0]).await;
+        let belt = get_data(&game, coords[1]).await;
+        let consumer = get_data(&game, coords[2]).await;
+        let get = |data: &automancy_resources::data::DataMap, name| {
+            data.get(id(&resource_man, name)).cloned()
+        };
+
+        // every tick of a stream gets its own count, even when several run in one base tick
+        assert_eq!(
+            get(&producer, "ticks"),
+            amount(logic_ticks),
+            "{logic}:{transfer}"
+        );
+        assert_eq!(
+            get(&producer, "last_tick"),
+            amount(logic_ticks - 1),
+            "{logic}:{transfer}"
+        );
+        assert_eq!(
+            get(&belt, "ticks"),
+            amount(transfer_ticks),
+            "{logic}:{transfer}"
+        );
+        assert_eq!(
+            get(&belt, "last_tick"),
+            amount(transfer_ticks - 1),
+            "{logic}:{transfer}"
+        );
+
+        // nothing is lost or made up along the way, whatever the ratio
+        assert_eq!(get(&producer, "made"), amount(MADE), "{logic}:{transfer}");
+        assert_eq!(get(&belt, "held"), amount(0), "{logic}:{transfer}");
+        assert_eq!(
+            get(&consumer, "received"),
+            amount(MADE),
+            "{logic}:{transfer}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn belt_keeps_its_own_pace() {
+    let resource_man = Arc::new(pack());
+    let map = TestMap::new("belt_keeps_its_own_pace");
+    let game = start_game(resource_man.clone(), &map).await;
+    let coords = [
+        TileCoord::new(0, 0),
+        TileCoord::new(1, 0),
+        TileCoord::new(2, 0),
+    ];
+
+    for (coord, name) in coords.into_iter().zip(["producer", "belt", "consumer"]) {
+        place(&game, coord, TileId(id(&resource_man, name)), None).await;
+    }
+
+    // the items are all made long before the belt moves them on, a fast logic rate doesn't speed it up
+    game.send_message(GameSystemMessage::SetTickRates(TickRates {
+        logic: 360,
+        transfer: 20,
+        speed: NORMAL_TICK_SPEED,
+    }))
+    .unwrap();
+
+    for _ in 0..6 {
+        run_ticks(&game, 1).await;
+        settle(&game, &coords).await;
+    }
+
+    let received = get_data(&game, coords[2])
+        .await
+        .get(id(&resource_man, "received"))
+        .cloned();
+    assert_eq!(received, Some(Data::Amount(2)));
+}
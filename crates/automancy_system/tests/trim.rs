@@ -0,0 +1,182 @@
+mod common;
+
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::TileId;
+use automancy_resources::data::{Data, DataMap};
+use automancy_system::game::GameSystemMessage;
+use automancy_system::map::{Region, Tiles};
+use automancy_system::trim::{trim_map, TrimKeep, TrimPreview, TrimProgress};
+use common::{
+    function, get_data, get_tile, id, load_pack, machine, place, run_ticks, start_game, tile,
+    tile_entity, TestMap,
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+#[test]
+fn preview_keeps_the_radius_or_the_region() {
+    let resource_man = load_pack(&[
+        (
+            "categories/machines.ron".to_string(),
+            r#"(id: "machines", ord: 0, icon: "machine", icon_mode: Tile, item: None, color: None)"#
+                .to_string(),
+        ),
+        tile("machine", Some("machines"), true),
+        tile("belt", None, true),
+    ]);
+    let machine = TileId(id(&resource_man, "machine"));
+    let belt = TileId(id(&resource_man, "belt"));
+    let machines = id(&resource_man, "machines");
+
+    let tiles = Tiles::from([
+        (TileCoord::new(0, 0), machine),
+        (TileCoord::new(1, 0), belt),
+        (TileCoord::new(10, 0), machine),
+        (TileCoord::new(20, 0), machine),
+        (TileCoord::new(0, -20), belt),
+    ]);
+    let regions = [Region {
+        name: "outpost".to_string(),
+        color: 0,
+        tiles: vec![TileCoord::new(10, 0), TileCoord::new(30, 30)],
+    }];
+
+    let preview = TrimPreview::build(
+        &resource_man,
+        &tiles,
+        &regions,
+        TrimKeep::Radius {
+            center: TileCoord::ZERO,
+            radius: 5,
+        },
+    );
+    let mut expected = vec![
+        TileCoord::new(10, 0),
+        TileCoord::new(20, 0),
+        TileCoord::new(0, -20),
+    ];
+    expected.sort_unstable();
+    assert_eq!(preview.doomed, expected);
+    assert_eq!(preview.by_category, [(Some(machines), 2), (None, 1)]);
+
+    // the radius can be kept around any center
+    let preview = TrimPreview::build(
+        &resource_man,
+        &tiles,
+        &regions,
+        TrimKeep::Radius {
+            center: TileCoord::new(20, 0),
+            radius: 0,
+        },
+    );
+    assert_eq!(preview.doomed.len(), 4);
+    assert!(!preview.doomed.contains(&TileCoord::new(20, 0)));
+
+    let preview = TrimPreview::build(&resource_man, &tiles, &regions, TrimKeep::Region(0));
+    assert_eq!(preview.doomed.len(), 4);
+    assert!(!preview.doomed.contains(&TileCoord::new(10, 0)));
+    assert_eq!(preview.by_category, [(None, 2), (Some(machines), 2)]);
+
+    // a region that doesn't exist keeps nothing
+    let preview = TrimPreview::build(&resource_man, &tiles, &regions, TrimKeep::Region(3));
+    assert_eq!(preview.doomed.len(), tiles.len());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn trims_past_entities_that_do_not_stop() {
+    let resource_man = Arc::new(load_pack(&[
+        // blocks its tile entity for longer than the game waits for it to answer and to stop
+        function("stuck", &[], "fn handle_tick(input) { sleep(15); }"),
+        machine("stuck", "stuck"),
+        tile("kept", None, true),
+        tile("doomed", None, true),
+    ]));
+    let map = TestMap::new("trims_past_entities_that_do_not_stop");
+    let game = start_game(resource_man.clone(), &map).await;
+    let data_ids = resource_man.registry.data_ids;
+
+    let kept = TileCoord::new(0, 0);
+    let doomed = TileCoord::new(8, 0);
+    let stuck = TileCoord::new(9, 0);
+
+    place(&game, kept, TileId(id(&resource_man, "kept")), None).await;
+    let mut data = DataMap::default();
+    data.set(data_ids.direction, Data::Coord(TileCoord::new(1, 0)));
+    place(
+        &game,
+        doomed,
+        TileId(id(&resource_man, "doomed")),
+        Some(data),
+    )
+    .await;
+    place(&game, stuck, TileId(id(&resource_man, "stuck")), None).await;
+
+    // backed up ahead of the stuck tick, as saving waits on every entity
+    assert!(game
+        .call(GameSystemMessage::BackupMap, None)
+        .await
+        .unwrap()
+        .unwrap());
+
+    game.send_message(GameSystemMessage::SetTickPaused(true))
+        .unwrap();
+    game.send_message(GameSystemMessage::StepTick(1)).unwrap();
+    run_ticks(&game, 1).await;
+    // lets the stuck entity get into its tick
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let start = Instant::now();
+    let removed = game
+        .call(
+            |reply| GameSystemMessage::TrimTiles {
+                coords: vec![doomed, stuck],
+                last: true,
+                reply,
+            },
+            None,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    // the stuck entity is given up on, rather than holding up the trim until it is done
+    assert!(start.elapsed() < Duration::from_secs(10));
+    assert_eq!(removed, 2);
+
+    assert_eq!(
+        get_tile(&game, kept).await,
+        Some(TileId(id(&resource_man, "kept")))
+    );
+    assert_eq!(get_tile(&game, doomed).await, None);
+    assert_eq!(get_tile(&game, stuck).await, None);
+    assert!(tile_entity(&game, stuck).await.is_none());
+
+    // the game goes on as usual
+    game.send_message(GameSystemMessage::StepTick(1)).unwrap();
+    run_ticks(&game, 1).await;
+
+    // undone as one step, with the data of the entities that answered
+    game.send_message(GameSystemMessage::Undo).unwrap();
+    assert_eq!(
+        get_tile(&game, doomed).await,
+        Some(TileId(id(&resource_man, "doomed")))
+    );
+    assert_eq!(
+        get_tile(&game, stuck).await,
+        Some(TileId(id(&resource_man, "stuck")))
+    );
+    assert_eq!(
+        get_data(&game, doomed).await.get(data_ids.direction),
+        Some(&Data::Coord(TileCoord::new(1, 0)))
+    );
+
+    // the whole trim backs up the map first, then reports how many were removed
+    let progress = Arc::new(Mutex::new(None));
+    trim_map(game.clone(), vec![kept], progress.clone()).await;
+    assert_eq!(
+        *progress.lock().await,
+        Some(TrimProgress::Done { removed: 1 })
+    );
+    assert_eq!(get_tile(&game, kept).await, None);
+}
@@ -0,0 +1,65 @@
+use crate::{button, center_row, label, num_input};
+use automancy_system::util::num::NumTrait;
+use std::ops::RangeInclusive;
+use yakui::{draggable, use_state};
+
+/// How far the cursor has to be dragged for the value to be scrubbed by one step.
+const SCRUB_DISTANCE_PER_STEP: f32 = 8.0;
+
+/// Draws an amount input: a text field that is typed into, buttons to step the value down and up,
+/// and a handle that can be dragged sideways to scrub through the values.
+///
+/// The value is always kept within the range. Returns whether the value was changed.
+#[track_caller]
+pub fn amount_input<T: NumTrait>(
+    value: &mut T,
+    range: RangeInclusive<T>,
+    step: T,
+    parse: impl Fn(&str) -> Option<T>,
+    to_string: impl Fn(&T) -> String,
+) -> bool {
+    let (min, max) = (*range.start(), *range.end());
+    let scrub_from = use_state(|| None as Option<T>);
+    // the "+" button comes after the text field, so the field is told of its change the next frame
+    let stepped_up = use_state(|| false);
+
+    let old = *value;
+    let mut changed = stepped_up.get();
+    stepped_up.set(false);
+
+    center_row(|| {
+        if button("-").clicked {
+            *value = T::clamp(value.saturating_sub(step), min, max);
+            changed = true;
+        }
+
+        let res = draggable(|| {
+            label("<>");
+        });
+
+        if let Some(drag) = res.dragging {
+            let from = scrub_from.get().unwrap_or(*value);
+            scrub_from.set(Some(from));
+
+            let steps = ((drag.current.x - drag.start.x) / SCRUB_DISTANCE_PER_STEP).trunc();
+
+            *value = T::clamp(
+                from.saturating_add(step.saturating_mul(T::from_f64(steps as f64))),
+                min,
+                max,
+            );
+            changed = true;
+        } else {
+            scrub_from.set(None);
+        }
+
+        num_input(value, changed, range, parse, to_string);
+
+        if button("+").clicked {
+            *value = T::clamp(value.saturating_add(step), min, max);
+            stepped_up.set(true);
+        }
+    });
+
+    *value != old
+}
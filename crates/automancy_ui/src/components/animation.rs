@@ -0,0 +1,67 @@
+use automancy_defs::glam::FloatExt;
+use automancy_defs::math::Float;
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+use yakui::use_state;
+
+/// How long windows take to animate in.
+pub const WINDOW_OPEN_DURATION: Duration = Duration::from_millis(120);
+/// How long windows take to animate out, see [crate::closing_windows].
+pub const WINDOW_CLOSE_DURATION: Duration = Duration::from_millis(120);
+/// How long the cursor has to rest on something before its hover tip shows up.
+pub const HOVER_TIP_DELAY: Duration = Duration::from_millis(150);
+/// How much smaller a window starts out when it opens.
+const WINDOW_OPEN_SCALE: Float = 0.9;
+
+thread_local! {
+    /// Whether GUI animations are played. If not, everything snaps to its final state immediately.
+    pub static ANIMATIONS_ENABLED: Cell<bool> = const { Cell::new(true) };
+}
+
+/// Eases a linear progress between 0 and 1 so that it starts fast and slows down towards the end.
+pub fn ease_out_cubic(t: Float) -> Float {
+    1.0 - (1.0 - t.clamp(0.0, 1.0)).powi(3)
+}
+
+/// Moves a value stored in the current widget towards the target, by the factor `t` each frame.
+///
+/// Returns the value for this frame.
+#[track_caller]
+pub fn lerp_towards(target: Float, t: Float) -> Float {
+    let s = use_state(move || target);
+
+    if !ANIMATIONS_ENABLED.get() {
+        s.set(target);
+
+        return target;
+    }
+
+    let r = s.get();
+
+    s.modify(|v| {
+        let lerped = v.lerp(target, t);
+
+        lerped.clamp(v.min(target + 0.01), v.max(target - 0.01))
+    });
+
+    r
+}
+
+/// Returns how far the current widget has gotten in its opening animation, eased, from 0 to 1.
+///
+/// The animation starts the first frame this is called for the widget.
+#[track_caller]
+pub fn open_progress(duration: Duration) -> Float {
+    let opened_at = use_state(Instant::now);
+
+    if !ANIMATIONS_ENABLED.get() {
+        return 1.0;
+    }
+
+    ease_out_cubic(opened_at.get().elapsed().as_secs_f32() / duration.as_secs_f32())
+}
+
+/// Returns the scale of a window with the given opening progress.
+pub fn window_open_scale(progress: Float) -> Float {
+    WINDOW_OPEN_SCALE.lerp(1.0, progress)
+}
@@ -1,17 +1,22 @@
 use crate::{
-    center_col, col, heading, pad_y, RoundedRectLerpedColor, PADDING_LARGE, PADDING_MEDIUM,
-    ROUNDED_MEDIUM,
+    center_col, col, ease_out_cubic, heading, open_progress, pad_y, window_open_scale,
+    RoundedRectLerpedColor, ANIMATIONS_ENABLED, PADDING_LARGE, PADDING_MEDIUM, ROUNDED_MEDIUM,
+    WINDOW_CLOSE_DURATION, WINDOW_OPEN_DURATION,
 };
 use automancy_defs::colors;
+use hashbrown::{HashMap, HashSet};
+use std::cell::RefCell;
+use std::mem;
+use std::time::Instant;
 use yakui::geometry::{Color, Constraints, Vec2};
 use yakui::widget::{LayoutContext, PaintContext, Widget};
-use yakui::Response;
 use yakui::{
-    align, colored_box_container,
+    align, colored_box_container, constrained,
     util::{widget, widget_children},
-    widgets::{Layer, Pad},
-    Alignment,
+    widgets::{Absolute, Layer, Pad},
+    Alignment, Dim2, Pivot,
 };
+use yakui::{Rect, Response};
 
 /**
 A colored box with rounded corners that can contain children.
@@ -24,6 +29,10 @@ pub struct RoundRect {
     pub radius: f32,
     pub color: (Color, Color, Color, Color),
     pub min_size: Vec2,
+    /// The scale of the painted box around its center. Does not affect the layout.
+    pub scale: f32,
+    /// The title of the window the box is, whose painted rect is kept for its closing animation.
+    pub window: Option<String>,
 }
 
 impl RoundRect {
@@ -32,6 +41,8 @@ impl RoundRect {
             radius,
             color: (color, color, color, color),
             min_size: Vec2::ZERO,
+            scale: 1.0,
+            window: None,
         }
     }
 
@@ -40,6 +51,8 @@ impl RoundRect {
             radius,
             color,
             min_size: Vec2::ZERO,
+            scale: 1.0,
+            window: None,
         }
     }
 
@@ -48,6 +61,8 @@ impl RoundRect {
             radius,
             color: (x0, x1, x0, x1),
             min_size: Vec2::ZERO,
+            scale: 1.0,
+            window: None,
         }
     }
 
@@ -56,6 +71,8 @@ impl RoundRect {
             radius,
             color: (y0, y0, y1, y1),
             min_size: Vec2::ZERO,
+            scale: 1.0,
+            window: None,
         }
     }
 
@@ -107,10 +124,22 @@ impl Widget for RoundRectWidget {
         let node = ctx.dom.get_current();
         let layout_node = ctx.layout.get(ctx.dom.current()).unwrap();
 
-        let mut rect = RoundedRectLerpedColor::new(layout_node.rect, self.props.radius);
+        let size = layout_node.rect.size() * self.props.scale;
+        let pos = layout_node.rect.pos() + (layout_node.rect.size() - size) / 2.0;
+
+        let mut rect = RoundedRectLerpedColor::new(
+            Rect::from_pos_size(pos, size),
+            self.props.radius * self.props.scale,
+        );
         rect.color = self.props.color;
         rect.add(ctx.paint);
 
+        if let Some(title) = &self.props.window {
+            WINDOWS.with_borrow_mut(|windows| {
+                windows.painted.insert(title.clone(), layout_node.rect);
+            });
+        }
+
         for &child in &node.children {
             ctx.paint(child);
         }
@@ -129,12 +158,57 @@ pub fn group(children: impl FnOnce()) {
     });
 }
 
-pub fn window_box(title: String, children: impl FnOnce()) {
-    RoundRect::new(ROUNDED_MEDIUM, colors::BACKGROUND_1).show_children(|| {
+/// Draws a bar of the width, filled up to the fraction, from 0 to 1.
+pub fn fill_bar(fraction: f32, width: f32, color: Color) {
+    let mut back = RoundRect::new(4.0, colors::BACKGROUND_3);
+    back.min_size = Vec2::new(width, 8.0);
+
+    back.show_children(|| {
+        let mut fill = RoundRect::new(4.0, color);
+        fill.min_size = Vec2::new(width * fraction.clamp(0.0, 1.0), 8.0);
+        fill.show();
+    });
+}
+
+pub(crate) fn fade(color: Color, progress: f32) -> Color {
+    Color {
+        a: (color.a as f32 * progress) as u8,
+        ..color
+    }
+}
+
+/// A window that is no longer drawn, kept until its closing animation is done.
+#[derive(Debug, Clone)]
+struct ClosingWindow {
+    title: String,
+    rect: Rect,
+    closed_at: Instant,
+}
+
+/// The windows drawn lately, to find the ones that were closed.
+#[derive(Debug, Default)]
+struct Windows {
+    /// the windows shown this frame, by title
+    shown: HashSet<String>,
+    /// where the windows were painted in the last frame, by title
+    painted: HashMap<String, Rect>,
+    closing: Vec<ClosingWindow>,
+}
+
+thread_local! {
+    static WINDOWS: RefCell<Windows> = RefCell::default();
+}
+
+fn window_frame(title: &str, progress: f32, window: Option<String>, children: impl FnOnce()) {
+    let mut rect = RoundRect::new(ROUNDED_MEDIUM, fade(colors::BACKGROUND_1, progress));
+    rect.scale = window_open_scale(progress);
+    rect.window = window;
+
+    rect.show_children(|| {
         Pad::all(PADDING_LARGE).show(|| {
             center_col(|| {
                 pad_y(0.0, PADDING_MEDIUM).show(|| {
-                    heading(&title);
+                    heading(title);
                 });
 
                 children();
@@ -143,6 +217,71 @@ pub fn window_box(title: String, children: impl FnOnce()) {
     });
 }
 
+#[track_caller]
+pub fn window_box(title: String, children: impl FnOnce()) {
+    let progress = open_progress(WINDOW_OPEN_DURATION);
+
+    WINDOWS.with_borrow_mut(|windows| {
+        // opened again while it was closing
+        windows.closing.retain(|window| window.title != title);
+        windows.shown.insert(title.clone());
+    });
+
+    window_frame(&title, progress, Some(title.clone()), children);
+}
+
+/// Plays the closing animations of the windows that stopped being drawn since the last frame.
+///
+/// The windows' contents are gone once they stop being drawn, so what closes is their box and title, where they were last painted.
+/// Goes after all the windows of the frame.
+pub fn closing_windows() {
+    let closing = WINDOWS.with_borrow_mut(|windows| {
+        let shown = mem::take(&mut windows.shown);
+        let painted = mem::take(&mut windows.painted);
+
+        if ANIMATIONS_ENABLED.get() {
+            for (title, rect) in painted {
+                if !shown.contains(&title) {
+                    windows.closing.push(ClosingWindow {
+                        title,
+                        rect,
+                        closed_at: Instant::now(),
+                    });
+                }
+            }
+        }
+
+        windows
+            .closing
+            .retain(|window| window.closed_at.elapsed() < WINDOW_CLOSE_DURATION);
+        if !ANIMATIONS_ENABLED.get() {
+            windows.closing.clear();
+        }
+
+        windows.closing.clone()
+    });
+
+    for window in closing {
+        let progress = 1.0
+            - ease_out_cubic(
+                window.closed_at.elapsed().as_secs_f32() / WINDOW_CLOSE_DURATION.as_secs_f32(),
+            );
+
+        Layer::new().show(|| {
+            Absolute::new(
+                Alignment::TOP_LEFT,
+                Pivot::TOP_LEFT,
+                Dim2::pixels(window.rect.pos().x, window.rect.pos().y),
+            )
+            .show(|| {
+                constrained(Constraints::tight(window.rect.size()), || {
+                    window_frame(&window.title, progress, None, || {});
+                });
+            });
+        });
+    }
+}
+
 pub fn window(title: String, children: impl FnOnce()) {
     Layer::new().show(|| {
         align(Alignment::CENTER, || {
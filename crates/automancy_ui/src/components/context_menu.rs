@@ -0,0 +1,124 @@
+use crate::{button_text, clamp_percentage_to_viewport, col, label_text, RoundRect, PADDING_SMALL};
+use automancy_defs::colors;
+use std::cell::Cell;
+use std::time::Instant;
+use yakui::{
+    util::widget_children,
+    widget::{LayoutContext, Widget},
+    widgets::{Layer, Pad},
+    Alignment, Constraints, Dim2, Flow, Response, Vec2,
+};
+
+#[derive(Debug)]
+pub struct ContextMenu {
+    /// when the menu was opened, so that it moves to where the cursor is when it opens again
+    pub opened: Instant,
+}
+
+impl ContextMenu {
+    pub fn new(opened: Instant) -> Self {
+        Self { opened }
+    }
+
+    #[track_caller]
+    pub fn show<F: FnOnce()>(self, children: F) -> Response<ContextMenuResponse> {
+        widget_children::<ContextMenuWidget, F>(children, self)
+    }
+}
+
+/// Stays where the cursor was when the menu was opened, moved back in if it would leave the viewport.
+#[derive(Debug)]
+pub struct ContextMenuWidget {
+    props: ContextMenu,
+    opened: Cell<Option<Instant>>,
+    cursor: Cell<Vec2>,
+    pos: Cell<Vec2>,
+}
+
+pub type ContextMenuResponse = ();
+
+impl Widget for ContextMenuWidget {
+    type Props<'a> = ContextMenu;
+    type Response = ContextMenuResponse;
+
+    fn new() -> Self {
+        Self {
+            props: ContextMenu::new(Instant::now()),
+            opened: Cell::default(),
+            cursor: Cell::default(),
+            pos: Cell::default(),
+        }
+    }
+
+    fn update(&mut self, props: Self::Props<'_>) -> Self::Response {
+        self.props = props;
+    }
+
+    fn flow(&self) -> Flow {
+        Flow::Absolute {
+            anchor: Alignment::new(self.pos.get().x, self.pos.get().y),
+            offset: Dim2::ZERO,
+        }
+    }
+
+    fn layout(&self, mut ctx: LayoutContext<'_>, _constraints: Constraints) -> Vec2 {
+        let node = ctx.dom.get_current();
+
+        let mut size = Vec2::ZERO;
+        for &child in &node.children {
+            size = size.max(ctx.calculate_layout(child, Constraints::none()));
+        }
+
+        if self.opened.get() != Some(self.props.opened) {
+            if let Some(pos) = ctx.input.get_mouse_position(ctx.layout) {
+                self.cursor.set(pos);
+                self.opened.set(Some(self.props.opened));
+            }
+        }
+
+        self.pos.set(clamp_percentage_to_viewport(
+            size,
+            self.cursor.get() / ctx.layout.viewport().size(),
+            ctx.layout.viewport(),
+        ));
+
+        size
+    }
+}
+
+/// Draws a menu of entries at the cursor, with the highlighted one stood out, and returns the index of the clicked one.
+///
+/// What the entries do, and closing the menu, is left to the caller.
+#[track_caller]
+pub fn context_menu<S: AsRef<str>>(
+    opened: Instant,
+    entries: &[S],
+    highlighted: usize,
+) -> Option<usize> {
+    let mut clicked = None;
+
+    Layer::new().show(|| {
+        ContextMenu::new(opened).show(|| {
+            RoundRect::new(8.0, colors::BACKGROUND_1).show_children(|| {
+                Pad::all(PADDING_SMALL).show(|| {
+                    col(|| {
+                        for (index, entry) in entries.iter().enumerate() {
+                            let mut button = button_text(label_text(entry.as_ref()));
+
+                            if index == highlighted {
+                                button.style.fill = colors::LIGHT_BLUE;
+                                button.hover_style.fill = colors::LIGHT_BLUE.adjust(1.2);
+                            }
+
+                            if button.show().clicked {
+                                clicked = Some(index);
+                            }
+                        }
+                    });
+                });
+            });
+        });
+    });
+
+    clicked
+}
@@ -1,3 +1,4 @@
+use crate::RoundedRectLerpedColor;
 use automancy_defs::{
     id::{ModelId, TileId},
     math::Matrix4,
@@ -5,12 +6,12 @@ use automancy_defs::{
 };
 use automancy_resources::data::DataMap;
 use std::cell::{Cell, RefCell};
-use wgpu::{BindGroup, Buffer};
+use std::f32::consts::FRAC_1_SQRT_2;
 use yakui::{
     paint::{CustomPaintCall, PaintCall},
     util::widget,
     widget::Widget,
-    Rect, Response, Vec2,
+    Color, Rect, Response, Vec2,
 };
 
 thread_local! {
@@ -29,6 +30,18 @@ pub enum UiGameObjectType {
     Model(ModelId),
 }
 
+/// What is drawn behind a game object.
+///
+/// The render itself is always blended over what is beneath it by its alpha, so with [`GameObjectBackground::Transparent`] only the object is seen.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum GameObjectBackground {
+    #[default]
+    Transparent,
+    Solid(Color),
+    /// from the top color to the bottom one
+    Gradient(Color, Color),
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct GameObject {
     pub index: usize,
@@ -37,6 +50,9 @@ pub struct GameObject {
     pub size: Vec2,
     pub model_matrix: Matrix4,
     pub world_matrix: Matrix4,
+    pub background: GameObjectBackground,
+    /// the radius of the corners of the slot the object sits in. the render is clipped to stay clear of them
+    pub corner_radius: f32,
 }
 
 pub fn ui_game_object(
@@ -66,6 +82,8 @@ impl GameObject {
             size,
             model_matrix: model_matrix.unwrap_or_default(),
             world_matrix: world_matrix.unwrap_or_default(),
+            background: GameObjectBackground::default(),
+            corner_radius: 0.0,
         };
 
         INDEX_COUNTER.set(index + 1);
@@ -73,6 +91,16 @@ impl GameObject {
         result
     }
 
+    pub fn with_background(mut self, background: GameObjectBackground) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn with_corner_radius(mut self, corner_radius: f32) -> Self {
+        self.corner_radius = corner_radius;
+        self
+    }
+
     #[track_caller]
     pub fn show(self) -> Response<()> {
         widget::<GameElementWidget>(Some(self))
@@ -84,8 +112,8 @@ pub struct GameElementPaint {
     pub props: GameObject,
     pub clip_offset: Vec2,
     pub clip_scale: Vec2,
-    pub present_uniform: Option<Buffer>,
-    pub present_bind_group: Option<BindGroup>,
+    /// where this object is among the ones presented this frame, given once it is prepared
+    pub present_slot: Option<usize>,
 }
 
 #[derive(Debug, Clone)]
@@ -134,26 +162,51 @@ impl Widget for GameElementWidget {
             self.clip.set(clip);
         }
 
+        let (background, corner_radius) = self
+            .props
+            .borrow()
+            .as_ref()
+            .map_or((GameObjectBackground::Transparent, 0.0), |props| {
+                (props.background, props.corner_radius)
+            });
+
         let mut inside = Rect::ZERO;
         let mut clip_scale = Vec2::ONE;
         let mut clip_offset = Vec2::ZERO;
         if let Some(mut rect) = ctx.layout.get(ctx.dom.current()).map(|v| v.rect) {
+            let color = match background {
+                GameObjectBackground::Transparent => None,
+                GameObjectBackground::Solid(color) => Some((color, color, color, color)),
+                GameObjectBackground::Gradient(top, bottom) => Some((top, top, bottom, bottom)),
+            };
+
+            if let Some(color) = color {
+                let mut slot = RoundedRectLerpedColor::new(rect, corner_radius);
+                slot.color = color;
+                slot.add(ctx.paint);
+            }
+
             rect.set_pos(rect.pos() * ctx.layout.scale_factor());
             rect.set_size(rect.size() * ctx.layout.scale_factor());
 
+            // the largest rectangle that stays within the rounded corners
+            let inset = corner_radius * ctx.layout.scale_factor() * (1.0 - FRAC_1_SQRT_2);
+            let slot = Rect::from_pos_size(
+                rect.pos() + Vec2::splat(inset),
+                (rect.size() - Vec2::splat(inset * 2.0)).max(Vec2::ZERO),
+            );
+
             inside = ctx
                 .layout
                 .unscaled_viewport()
                 .constrain(self.clip.get())
-                .constrain(rect);
+                .constrain(slot);
 
             clip_scale = inside.size() / rect.size();
 
-            let inside_center = inside.pos() + inside.size() / 2.0;
-            let rect_center = rect.pos() + rect.size() / 2.0;
-            let sign = (inside_center - rect_center).signum();
-
-            clip_offset = (sign + Vec2::ONE) * (Vec2::ONE - clip_scale) * rect.size();
+            // how much is cut off the top-left, in the render's texture, which is twice the size.
+            // clipped from both sides at once by the corners, so it isn't enough to know which side is clipped
+            clip_offset = (inside.pos() - rect.pos()) * 2.0;
         }
 
         if let Some(layer) = ctx.paint.layers_mut().current_mut() {
@@ -164,8 +217,7 @@ impl Widget for GameElementWidget {
                 props,
                 clip_scale,
                 clip_offset,
-                present_bind_group: None,
-                present_uniform: None,
+                present_slot: None,
             });
 
             layer.calls.push((
@@ -0,0 +1,240 @@
+use crate::{interactive, InteractiveResponse, RoundRect};
+use std::cell::Cell;
+use yakui::{
+    event::{EventInterest, EventResponse, WidgetEvent},
+    input::MouseButton,
+    paint::{PaintMesh, Vertex},
+    util::widget_children,
+    widget::{EventContext, LayoutContext, PaintContext, Widget},
+    Color, Constraints, Response, Vec2,
+};
+
+#[rustfmt::skip]
+const QUAD_INDEX: [u16; 6] = [
+    0, 1, 2,
+    3, 0, 2,
+];
+
+/// A straight line drawn on a [GraphCanvas], with its ends in pixels from the canvas' top-left corner.
+#[derive(Debug, Clone, Copy)]
+pub struct GraphLine {
+    pub from: Vec2,
+    pub to: Vec2,
+    pub thickness: f32,
+    pub color: Color,
+}
+
+/**
+An area of a fixed size that draws lines under its children, and puts each child centered on its point.
+
+The children are matched with the points in order. The ones without a point are put in the top-left corner.
+*/
+#[derive(Debug, Clone)]
+pub struct GraphCanvas {
+    pub size: Vec2,
+    pub lines: Vec<GraphLine>,
+    /// where the children are centered, in pixels from the top-left corner
+    pub points: Vec<Vec2>,
+}
+
+impl GraphCanvas {
+    pub fn new(size: Vec2) -> Self {
+        Self {
+            size,
+            lines: Vec::new(),
+            points: Vec::new(),
+        }
+    }
+
+    #[track_caller]
+    pub fn show<F: FnOnce()>(self, children: F) -> Response<GraphCanvasResponse> {
+        widget_children::<GraphCanvasWidget, F>(children, self)
+    }
+}
+
+#[derive(Debug)]
+pub struct GraphCanvasWidget {
+    props: GraphCanvas,
+}
+
+pub type GraphCanvasResponse = ();
+
+impl Widget for GraphCanvasWidget {
+    type Props<'a> = GraphCanvas;
+    type Response = GraphCanvasResponse;
+
+    fn new() -> Self {
+        Self {
+            props: GraphCanvas::new(Vec2::ZERO),
+        }
+    }
+
+    fn update(&mut self, props: Self::Props<'_>) -> Self::Response {
+        self.props = props;
+    }
+
+    fn layout(&self, mut ctx: LayoutContext<'_>, input: Constraints) -> Vec2 {
+        let node = ctx.dom.get_current();
+
+        for (index, &child) in node.children.iter().enumerate() {
+            let size = ctx.calculate_layout(child, Constraints::none());
+
+            let pos = self
+                .props
+                .points
+                .get(index)
+                .map_or(Vec2::ZERO, |point| *point - size / 2.0);
+            ctx.layout.set_pos(child, pos);
+        }
+
+        input.constrain_min(self.props.size)
+    }
+
+    fn paint(&self, mut ctx: PaintContext<'_>) {
+        let node = ctx.dom.get_current();
+        let origin = ctx.layout.get(ctx.dom.current()).unwrap().rect.pos();
+
+        for line in &self.props.lines {
+            let direction = (line.to - line.from).normalize_or_zero();
+            let side = Vec2::new(-direction.y, direction.x) * line.thickness / 2.0;
+            let color = line.color.to_linear();
+
+            let vertices = [
+                line.from + side,
+                line.from - side,
+                line.to - side,
+                line.to + side,
+            ]
+            .map(|pos| Vertex::new(origin + pos, Vec2::ZERO, color));
+
+            ctx.paint.add_mesh(PaintMesh::new(vertices, QUAD_INDEX));
+        }
+
+        for &child in &node.children {
+            ctx.paint(child);
+        }
+    }
+}
+
+/// Draws a round node of a graph, to be put on a [GraphCanvas].
+#[track_caller]
+pub fn graph_node(radius: f32, color: Color) -> Response<InteractiveResponse> {
+    interactive(|| {
+        let mut node = RoundRect::new(radius, color);
+        node.min_size = Vec2::splat(radius * 2.0);
+        node.show();
+    })
+}
+
+/// An area that is dragged with the mouse and scrolled over, to pan and zoom what it shows. Clicks its children take don't drag it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PanZoom {}
+
+impl PanZoom {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[track_caller]
+    pub fn show<F: FnOnce()>(self, children: F) -> Response<PanZoomResponse> {
+        widget_children::<PanZoomWidget, F>(children, self)
+    }
+}
+
+#[derive(Debug)]
+pub struct PanZoomResponse {
+    /// how far it was dragged since the last frame, in pixels
+    pub dragged: Vec2,
+    /// how far it was scrolled over since the last frame
+    pub scrolled: f32,
+}
+
+#[derive(Debug)]
+pub struct PanZoomWidget {
+    props: PanZoom,
+    hovering: bool,
+    /// where the mouse was when it last moved while dragging
+    dragging_at: Option<Vec2>,
+    dragged: Cell<Vec2>,
+    scrolled: Cell<f32>,
+}
+
+impl Widget for PanZoomWidget {
+    type Props<'a> = PanZoom;
+    type Response = PanZoomResponse;
+
+    fn new() -> Self {
+        Self {
+            props: PanZoom::new(),
+            hovering: false,
+            dragging_at: None,
+            dragged: Cell::default(),
+            scrolled: Cell::default(),
+        }
+    }
+
+    fn update(&mut self, props: Self::Props<'_>) -> Self::Response {
+        self.props = props;
+
+        PanZoomResponse {
+            dragged: self.dragged.take(),
+            scrolled: self.scrolled.take(),
+        }
+    }
+
+    fn event_interest(&self) -> EventInterest {
+        EventInterest::MOUSE_ALL
+    }
+
+    fn event(&mut self, _ctx: EventContext<'_>, event: &WidgetEvent) -> EventResponse {
+        match *event {
+            WidgetEvent::MouseEnter => {
+                self.hovering = true;
+
+                EventResponse::Bubble
+            }
+            WidgetEvent::MouseLeave => {
+                self.hovering = false;
+
+                EventResponse::Bubble
+            }
+            WidgetEvent::MouseButtonChanged {
+                button: MouseButton::One,
+                down,
+                inside,
+                position,
+                ..
+            } => {
+                if inside && down {
+                    self.dragging_at = Some(position);
+
+                    EventResponse::Sink
+                } else {
+                    self.dragging_at = None;
+
+                    EventResponse::Bubble
+                }
+            }
+            WidgetEvent::MouseMoved(Some(position)) => {
+                if let Some(at) = self.dragging_at {
+                    self.dragged.set(self.dragged.get() + position - at);
+                    self.dragging_at = Some(position);
+                }
+
+                EventResponse::Bubble
+            }
+            WidgetEvent::MouseScroll { delta, .. } if self.hovering => {
+                self.scrolled.set(self.scrolled.get() + delta.y);
+
+                EventResponse::Sink
+            }
+            _ => EventResponse::Bubble,
+        }
+    }
+}
+
+/// Draws an area that pans and zooms what it shows, when dragged or scrolled over.
+#[track_caller]
+pub fn pan_zoom(children: impl FnOnce()) -> Response<PanZoomResponse> {
+    PanZoom::new().show(children)
+}
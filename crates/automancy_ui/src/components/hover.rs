@@ -1,8 +1,10 @@
 use crate::clamp_percentage_to_viewport;
-use crate::RoundRect;
+use crate::{RoundRect, ANIMATIONS_ENABLED, HOVER_TIP_DELAY};
 use automancy_defs::colors;
 use std::cell::Cell;
+use std::time::Instant;
 use yakui::{
+    use_state,
     util::widget_children,
     widget::{LayoutContext, Widget},
     Alignment, Constraints, Dim2, Flow, Response, Vec2,
@@ -73,8 +75,31 @@ impl Widget for HoverWidget {
     }
 }
 
+/// Draws a tip next to the cursor, after it has been requested for the same key for a short while.
+///
+/// The key is what the tip is for, like the hovered tile, so that moving onto something else waits again.
 #[track_caller]
-pub fn hover_tip(children: impl FnOnce()) {
+pub fn hover_tip<K: PartialEq + 'static>(key: K, children: impl FnOnce()) {
+    let requested = use_state(|| None::<(K, Instant)>);
+
+    let requested_at = {
+        let mut requested = requested.borrow_mut();
+
+        match &*requested {
+            Some((requested_key, requested_at)) if *requested_key == key => *requested_at,
+            _ => {
+                let now = Instant::now();
+                *requested = Some((key, now));
+
+                now
+            }
+        }
+    };
+
+    if ANIMATIONS_ENABLED.get() && requested_at.elapsed() < HOVER_TIP_DELAY {
+        return;
+    }
+
     Hover::new().show(|| {
         RoundRect::new(8.0, colors::BACKGROUND_1).show_children(|| {
             children();
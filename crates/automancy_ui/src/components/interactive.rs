@@ -26,12 +26,16 @@ pub struct InteractiveWidget {
     hovering: bool,
     mouse_down: bool,
     clicked: bool,
+    secondary_mouse_down: bool,
+    secondary_clicked: bool,
 }
 
 #[derive(Debug)]
 pub struct InteractiveResponse {
     pub hovering: bool,
     pub clicked: bool,
+    /// whether it was clicked with the right mouse button
+    pub secondary_clicked: bool,
 }
 
 impl Widget for InteractiveWidget {
@@ -44,6 +48,8 @@ impl Widget for InteractiveWidget {
             hovering: false,
             mouse_down: false,
             clicked: false,
+            secondary_mouse_down: false,
+            secondary_clicked: false,
         }
     }
 
@@ -53,9 +59,13 @@ impl Widget for InteractiveWidget {
         let clicked = self.clicked;
         self.clicked = false;
 
+        let secondary_clicked = self.secondary_clicked;
+        self.secondary_clicked = false;
+
         Self::Response {
             hovering: self.hovering,
             clicked,
+            secondary_clicked,
         }
     }
 
@@ -74,25 +84,31 @@ impl Widget for InteractiveWidget {
                 EventResponse::Sink
             }
             WidgetEvent::MouseButtonChanged {
-                button: MouseButton::One,
+                button: button @ (MouseButton::One | MouseButton::Two),
                 down,
                 inside,
                 ..
             } => {
+                let (mouse_down, clicked) = if *button == MouseButton::One {
+                    (&mut self.mouse_down, &mut self.clicked)
+                } else {
+                    (&mut self.secondary_mouse_down, &mut self.secondary_clicked)
+                };
+
                 if *inside {
                     if *down {
-                        self.mouse_down = true;
+                        *mouse_down = true;
                         EventResponse::Sink
-                    } else if self.mouse_down {
-                        self.mouse_down = false;
-                        self.clicked = true;
+                    } else if *mouse_down {
+                        *mouse_down = false;
+                        *clicked = true;
                         EventResponse::Sink
                     } else {
                         EventResponse::Bubble
                     }
                 } else {
                     if !*down {
-                        self.mouse_down = false;
+                        *mouse_down = false;
                     }
 
                     EventResponse::Bubble
@@ -107,3 +123,68 @@ impl Widget for InteractiveWidget {
 pub fn interactive(children: impl FnOnce()) -> Response<InteractiveResponse> {
     Interactive::new().show(children)
 }
+
+/// Tracks whether the pointer is over the children, without taking the clicks from the widgets around it.
+#[derive(Debug, Default)]
+pub struct Hoverable {}
+
+impl Hoverable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[track_caller]
+    pub fn show<F: FnOnce()>(self, children: F) -> Response<HoverableResponse> {
+        widget_children::<HoverableWidget, F>(children, self)
+    }
+}
+
+#[derive(Debug)]
+pub struct HoverableWidget {
+    props: Hoverable,
+    hovering: bool,
+}
+
+#[derive(Debug)]
+pub struct HoverableResponse {
+    pub hovering: bool,
+}
+
+impl Widget for HoverableWidget {
+    type Props<'a> = Hoverable;
+    type Response = HoverableResponse;
+
+    fn new() -> Self {
+        Self {
+            props: Hoverable::new(),
+            hovering: false,
+        }
+    }
+
+    fn update(&mut self, props: Self::Props<'_>) -> Self::Response {
+        self.props = props;
+
+        Self::Response {
+            hovering: self.hovering,
+        }
+    }
+
+    fn event_interest(&self) -> EventInterest {
+        EventInterest::MOUSE_INSIDE
+    }
+
+    fn event(&mut self, _ctx: EventContext<'_>, event: &WidgetEvent) -> EventResponse {
+        match event {
+            WidgetEvent::MouseEnter => self.hovering = true,
+            WidgetEvent::MouseLeave => self.hovering = false,
+            _ => {}
+        }
+
+        EventResponse::Bubble
+    }
+}
+
+#[track_caller]
+pub fn hoverable(children: impl FnOnce()) -> Response<HoverableResponse> {
+    Hoverable::new().show(children)
+}
@@ -0,0 +1,186 @@
+use crate::GraphLine;
+use automancy_defs::colors;
+use yakui::{
+    event::{EventInterest, EventResponse, WidgetEvent},
+    input::MouseButton,
+    paint::{PaintMesh, Vertex},
+    widget::{EventContext, LayoutContext, PaintContext, Widget},
+    Color, Constraints, Response, Vec2,
+};
+
+#[rustfmt::skip]
+const QUAD_INDEX: [u16; 6] = [
+    0, 1, 2,
+    3, 0, 2,
+];
+
+/// A square cell drawn on a [Minimap], centered on its position in pixels from the minimap's top-left corner.
+#[derive(Debug, Clone, Copy)]
+pub struct MinimapCell {
+    pub pos: Vec2,
+    pub color: Color,
+}
+
+/**
+An area of a fixed size that draws a square cell for each of its positions, and lines over them.
+
+Clicking it reports where it was clicked, in pixels from its top-left corner.
+*/
+#[derive(Debug, Clone)]
+pub struct Minimap {
+    pub size: Vec2,
+    pub background: Color,
+    /// the width and height of the cells
+    pub cell_size: f32,
+    pub cells: Vec<MinimapCell>,
+    pub lines: Vec<GraphLine>,
+}
+
+impl Minimap {
+    pub fn new(size: Vec2, cell_size: f32) -> Self {
+        Self {
+            size,
+            background: colors::TRANSPARENT,
+            cell_size,
+            cells: Vec::new(),
+            lines: Vec::new(),
+        }
+    }
+
+    #[track_caller]
+    pub fn show(self) -> Response<MinimapResponse> {
+        yakui::util::widget::<MinimapWidget>(self)
+    }
+}
+
+#[derive(Debug)]
+pub struct MinimapWidget {
+    props: Minimap,
+    mouse_down: bool,
+    clicked: Option<Vec2>,
+}
+
+#[derive(Debug)]
+pub struct MinimapResponse {
+    /// where it was clicked since the last frame, in pixels from the top-left corner
+    pub clicked: Option<Vec2>,
+}
+
+fn quad(origin: Vec2, corners: [Vec2; 4], color: Color) -> PaintMesh<[Vertex; 4], [u16; 6]> {
+    let color = color.to_linear();
+
+    PaintMesh::new(
+        corners.map(|pos| Vertex::new(origin + pos, Vec2::ZERO, color)),
+        QUAD_INDEX,
+    )
+}
+
+impl Widget for MinimapWidget {
+    type Props<'a> = Minimap;
+    type Response = MinimapResponse;
+
+    fn new() -> Self {
+        Self {
+            props: Minimap::new(Vec2::ZERO, 0.0),
+            mouse_down: false,
+            clicked: None,
+        }
+    }
+
+    fn update(&mut self, props: Self::Props<'_>) -> Self::Response {
+        self.props = props;
+
+        MinimapResponse {
+            clicked: self.clicked.take(),
+        }
+    }
+
+    fn layout(&self, _ctx: LayoutContext<'_>, input: Constraints) -> Vec2 {
+        input.constrain_min(self.props.size)
+    }
+
+    fn paint(&self, mut ctx: PaintContext<'_>) {
+        let origin = ctx.layout.get(ctx.dom.current()).unwrap().rect.pos();
+        let size = self.props.size;
+
+        ctx.paint.add_mesh(quad(
+            origin,
+            [
+                Vec2::ZERO,
+                Vec2::new(0.0, size.y),
+                size,
+                Vec2::new(size.x, 0.0),
+            ],
+            self.props.background,
+        ));
+
+        let half = self.props.cell_size / 2.0;
+
+        for cell in &self.props.cells {
+            let min = (cell.pos - half).max(Vec2::ZERO);
+            let max = (cell.pos + half).min(size);
+
+            if min.cmpge(max).any() {
+                continue;
+            }
+
+            ctx.paint.add_mesh(quad(
+                origin,
+                [min, Vec2::new(min.x, max.y), max, Vec2::new(max.x, min.y)],
+                cell.color,
+            ));
+        }
+
+        for line in &self.props.lines {
+            let direction = (line.to - line.from).normalize_or_zero();
+            let side = Vec2::new(-direction.y, direction.x) * line.thickness / 2.0;
+
+            ctx.paint.add_mesh(quad(
+                origin,
+                [
+                    line.from + side,
+                    line.from - side,
+                    line.to - side,
+                    line.to + side,
+                ],
+                line.color,
+            ));
+        }
+    }
+
+    fn event_interest(&self) -> EventInterest {
+        EventInterest::MOUSE_INSIDE | EventInterest::MOUSE_OUTSIDE
+    }
+
+    fn event(&mut self, ctx: EventContext<'_>, event: &WidgetEvent) -> EventResponse {
+        match *event {
+            WidgetEvent::MouseButtonChanged {
+                button: MouseButton::One,
+                down,
+                inside,
+                position,
+                ..
+            } => {
+                if !inside {
+                    if !down {
+                        self.mouse_down = false;
+                    }
+
+                    return EventResponse::Bubble;
+                }
+
+                if down {
+                    self.mouse_down = true;
+                } else if self.mouse_down {
+                    self.mouse_down = false;
+
+                    let origin = ctx.layout.get(ctx.dom.current()).unwrap().rect.pos();
+                    self.clicked = Some(position - origin);
+                }
+
+                EventResponse::Sink
+            }
+            _ => EventResponse::Bubble,
+        }
+    }
+}
@@ -13,13 +13,18 @@ pub const PADDING_MEDIUM: f32 = 8.0;
 pub const PADDING_LARGE: f32 = 12.0;
 pub const PADDING_XLARGE: f32 = 16.0;
 
+mod amount;
+mod animation;
 mod button;
 mod checkbox;
 mod container;
+mod context_menu;
 mod game_object;
+mod graph;
 mod hover;
 mod interactive;
 mod layout;
+mod minimap;
 mod movable;
 mod position;
 mod scrollable;
@@ -29,15 +34,21 @@ mod slider;
 mod text;
 mod textbox;
 mod tip;
+mod toast;
 mod util;
 
+pub use self::amount::*;
+pub use self::animation::*;
 pub use self::button::*;
 pub use self::checkbox::*;
 pub use self::container::*;
+pub use self::context_menu::*;
 pub use self::game_object::*;
+pub use self::graph::*;
 pub use self::hover::*;
 pub use self::interactive::*;
 pub use self::layout::*;
+pub use self::minimap::*;
 pub use self::movable::*;
 pub use self::position::*;
 pub use self::scrollable::*;
@@ -47,4 +58,5 @@ pub use self::slider::*;
 pub use self::text::*;
 pub use self::textbox::*;
 pub use self::tip::*;
+pub use self::toast::*;
 pub use self::util::*;
@@ -1,4 +1,5 @@
 use crate::{col, pad_x, pad_y, row, PaintRectLerpedColor, RoundRect};
+use automancy_defs::math::Float;
 use automancy_defs::{
     colors,
     glam::{vec2, Vec2Swizzles},
@@ -10,6 +11,7 @@ use yakui::{
     input::MouseButton,
     reflow,
     widget::{EventContext, LayoutContext, Widget},
+    widgets::Pad,
     Alignment, Dim2, Rect,
 };
 use yakui::{
@@ -398,6 +400,36 @@ pub fn scroll_horizontal(min: Vec2, max: Vec2, children: impl FnOnce()) {
     scroll_horizontal_bar_alignment(min, max, Some(Alignment::BOTTOM_LEFT), children)
 }
 
+/// Lays out `count` items of `cell` size in rows, as many to a row as fit in the max width, and scrolls vertically
+/// once the rows are taller than the max height.
+///
+/// The rows are kept `overhang` away from the edges of the scrolled area, so that items drawn past their cell
+/// (e.g. while animated) aren't cut off by it.
+#[track_caller]
+pub fn scroll_grid(
+    max: Vec2,
+    cell: Float,
+    overhang: Float,
+    count: usize,
+    mut item: impl FnMut(usize),
+) {
+    let columns = (((max.x - overhang * 2.0) / cell).floor() as usize).max(1);
+
+    scroll_vertical(Vec2::ZERO, max, || {
+        Pad::all(overhang).show(|| {
+            col(|| {
+                for start in (0..count).step_by(columns) {
+                    row(|| {
+                        for index in start..(start + columns).min(count) {
+                            item(index);
+                        }
+                    });
+                }
+            });
+        });
+    });
+}
+
 #[track_caller]
 fn scroll_bar(res: ScrollableResponse, alignment: Vec2, dir: ScrollDirection) {
     let ratio = res.size / res.canvas_size;
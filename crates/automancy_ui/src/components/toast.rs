@@ -0,0 +1,20 @@
+use crate::{colored_label, fade, row, RoundRect, LABEL_SIZE, PADDING_MEDIUM, ROUNDED_MEDIUM};
+use automancy_defs::colors;
+use yakui::{widgets::Pad, Color, Vec2};
+
+/// Draws a toast, which is a short message in a box with a bar of the accent color on its side, faded out by the opacity, from 0 to 1.
+pub fn toast(text: &str, accent: Color, opacity: f32) {
+    let rect = RoundRect::new(ROUNDED_MEDIUM, fade(colors::BACKGROUND_1, opacity));
+
+    rect.show_children(|| {
+        Pad::all(PADDING_MEDIUM).show(|| {
+            row(|| {
+                let mut bar = RoundRect::new(2.0, fade(accent, opacity));
+                bar.min_size = Vec2::new(4.0, LABEL_SIZE);
+                bar.show();
+
+                colored_label(text, fade(colors::BLACK, opacity));
+            });
+        });
+    });
+}
@@ -1,16 +1,28 @@
+use crate::frame_timing::CpuStage;
 use crate::GameState;
 use crate::{gui, renderer};
 use automancy_defs::id::Id;
+use automancy_defs::math::{self, Float, Vec2, FAR, HEX_GRID_LAYOUT};
 use automancy_defs::{coord::TileCoord, id::TileId};
 use automancy_defs::{log, window};
-use automancy_resources::data::Data;
-use automancy_system::game::{GameSystemMessage, PlaceTileResponse};
-use automancy_system::input::{self, ActionType};
-use automancy_system::map::{GameMap, LoadMapOption, MAP_PATH};
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::error::has_err;
+use automancy_resources::format::Formattable;
+use automancy_system::camera::{
+    CameraBookmark, CameraBookmarks, CameraPath, CameraWaypoint, BOOKMARK_SLOTS,
+};
+use automancy_system::game::{GameSystemMessage, PlacementFailure, PlacementResult};
+use automancy_system::hotbar::read_hotbar;
+use automancy_system::input::{self, ActionType, GameInputEvent};
+use automancy_system::input_routing::InputRoute;
+use automancy_system::map::{self, GameMap, LoadMapOption, MAP_PATH};
 use automancy_system::tile_entity::{TileEntityMsg, TileEntityWithId};
-use automancy_system::ui_state::{Screen, TextField};
+use automancy_system::ui_state::{PlacementDrag, PopupState, Screen, TextField, ToastLevel};
+use automancy_system::util::{is_directional, tile_direction};
+use automancy_system::Screenshot;
 use ractor::rpc::CallResult;
 use ractor::ActorRef;
+use std::path::Path;
 use std::sync::atomic::Ordering;
 use std::time::{Instant, SystemTime};
 use std::{fs, mem};
@@ -20,6 +32,17 @@ use winit::{
     event::{Event, WindowEvent},
     event_loop::ActiveEventLoop,
 };
+use yakui::paint::{Texture, TextureFilter, TextureFormat};
+use yakui::UVec2;
+
+/// Whether the saved map of the name is the one that is loaded.
+pub fn is_map_loaded(state: &GameState, map_name: &str) -> bool {
+    state
+        .loop_store
+        .map_info
+        .as_ref()
+        .is_some_and(|(_, opt)| *opt == LoadMapOption::FromSave(map_name.to_string()))
+}
 
 /// Refreshes the list of maps on the filesystem. Should be done every time the list of maps could have changed (on map creation/delete and on game load).
 pub fn refresh_maps(state: &mut GameState) {
@@ -31,9 +54,13 @@ pub fn refresh_maps(state: &mut GameState) {
         .map(|f| f.file_name().to_str().unwrap().to_string())
         .filter(|f| !f.starts_with('.'))
         .flat_map(|name| {
-            GameMap::read_info(&state.resource_man, &LoadMapOption::FromSave(name.clone()))
-                .ok()
-                .zip(Some(name))
+            GameMap::read_info(
+                &state.resource_man,
+                &LoadMapOption::FromSave(name.clone()),
+                0,
+            )
+            .ok()
+            .zip(Some(name))
         })
         .collect::<Vec<_>>();
 
@@ -47,6 +74,67 @@ pub fn refresh_maps(state: &mut GameState) {
             .cmp(&b.0 .1.unwrap_or(SystemTime::UNIX_EPOCH))
     });
     state.loop_store.map_infos_cache.reverse();
+
+    load_map_thumbnails(state);
+}
+
+/// Checks the map folder for entries that need attention, and tells the player about them. Only done once per session.
+pub fn check_map_folder(state: &mut GameState) {
+    if mem::replace(&mut state.loop_store.map_folder_checked, true) {
+        return;
+    }
+
+    let entries = map::scan_map_folder(Path::new(MAP_PATH));
+    let issues = entries
+        .iter()
+        .filter(|(_, kind)| kind.needs_attention())
+        .collect::<Vec<_>>();
+
+    if issues.is_empty() {
+        return;
+    }
+
+    log::warn!("Found entries in the map folder that need attention: {issues:?}");
+
+    if state.ui_state.popup == PopupState::None {
+        state.ui_state.popup = PopupState::MapFolderIssues(entries);
+    }
+}
+
+/// Decodes the thumbnails of the listed maps into GUI textures, reusing the ones that haven't changed.
+fn load_map_thumbnails(state: &mut GameState) {
+    let Some(gui) = state.gui.as_mut() else {
+        return;
+    };
+
+    for ((_, save_time), name) in &state.loop_store.map_infos_cache {
+        if let Some((loaded_time, _)) = state.loop_store.map_thumbnails.get(name) {
+            if loaded_time == save_time {
+                continue;
+            }
+        }
+
+        let Some(image) = GameMap::read_thumbnail(&LoadMapOption::FromSave(name.clone()))
+            .and_then(|bytes| image::load_from_memory(&bytes).ok())
+        else {
+            state.loop_store.map_thumbnails.remove(name);
+            continue;
+        };
+
+        let image = image.to_rgba8();
+        let mut texture = Texture::new(
+            TextureFormat::Rgba8Srgb,
+            UVec2::new(image.width(), image.height()),
+            image.into_raw(),
+        );
+        texture.mag_filter = TextureFilter::Linear;
+        texture.min_filter = TextureFilter::Linear;
+
+        state
+            .loop_store
+            .map_thumbnails
+            .insert(name.clone(), (*save_time, gui.yak.add_texture(texture)));
+    }
 }
 
 pub async fn shutdown_graceful(
@@ -72,7 +160,7 @@ pub async fn shutdown_graceful(
 fn render(
     state: &mut GameState,
     event_loop: &ActiveEventLoop,
-    screenshotting: bool,
+    screenshotting: Option<Screenshot>,
 ) -> anyhow::Result<bool> {
     let mut result = Ok(false);
 
@@ -149,11 +237,22 @@ fn render(
 
     {
         {
+            let ui_start = Instant::now();
+
             state.gui.as_mut().unwrap().yak.start();
 
-            gui::render_ui(state, &mut result, event_loop);
+            // the UI is left out for just this frame, so that the screenshot of it is only the world
+            if screenshotting != Some(Screenshot::FileWithoutUi) {
+                gui::render_ui(state, &mut result, event_loop);
+            }
 
             state.gui.as_mut().unwrap().yak.finish();
+
+            if let Some(renderer) = state.renderer.as_mut() {
+                renderer
+                    .frame_timings
+                    .record_cpu(CpuStage::UiBuild, ui_start.elapsed());
+            }
         }
 
         if !matches!(result, Ok(true)) {
@@ -199,8 +298,8 @@ fn link_tile(state: &mut GameState, entity: Option<TileEntityWithId>, link_to: T
         entity.send_message(TileEntityMsg::RemoveData(id)).unwrap();
 
         state
-            .audio_man
-            .play(state.resource_man.audio["click"].clone())
+            .audio
+            .play(&mut state.audio_man, &state.resource_man, "click")
             .unwrap();
         // TODO click2
     } else {
@@ -209,43 +308,730 @@ fn link_tile(state: &mut GameState, entity: Option<TileEntityWithId>, link_to: T
             .unwrap();
 
         state
-            .audio_man
-            .play(state.resource_man.audio["click"].clone())
+            .audio
+            .play(&mut state.audio_man, &state.resource_man, "click")
             .unwrap();
     }
 }
 
-fn place_tile(id: TileId, coord: TileCoord, state: &mut GameState) -> anyhow::Result<()> {
-    let response = state
+/// Links the pointed-at tile if a tile is in link mode, and toggles its config window otherwise.
+fn interact_with_tile(state: &mut GameState, pointing_at_entity: Option<TileEntityWithId>) {
+    if let Some((link_to, id)) = state.ui_state.linking_tile {
+        link_tile(state, pointing_at_entity, link_to, id);
+    } else if Some(state.camera.pointing_at) == state.ui_state.config_open_at {
+        state.ui_state.config_open_at = None;
+        state.ui_state.text_field.get(TextField::Filter).clear();
+    } else {
+        state.ui_state.config_open_at = Some(state.camera.pointing_at);
+        state.ui_state.text_field.get(TextField::Filter).clear();
+    }
+}
+
+/// Places the tile at the coordinate, telling the player how it went.
+///
+/// The failures of tiles placed while painting are only flashed, and told all together once the painting stops.
+pub fn place_tile(
+    id: TileId,
+    coord: TileCoord,
+    data: Option<DataMap>,
+    force: bool,
+    painting: bool,
+    state: &mut GameState,
+) -> anyhow::Result<PlacementResult> {
+    let result = state
         .tokio
         .block_on(state.game.call(
             |reply| GameSystemMessage::PlaceTile {
                 coord,
                 id,
                 record: true,
+                force,
                 reply: Some(reply),
-                data: None,
+                data,
             },
             None,
         ))?
         .unwrap();
 
-    match response {
-        PlaceTileResponse::Placed => {
+    match result {
+        PlacementResult::Placed => {
             state
-                .audio_man
-                .play(state.resource_man.audio["tile_placement"].clone())
+                .audio
+                .play_at(
+                    &mut state.audio_man,
+                    &state.resource_man,
+                    "tile_placement",
+                    coord,
+                    state.camera.get_pos(),
+                )
                 .unwrap();
             state.ui_state.config_open_at = Some(coord);
             state.ui_state.already_placed_at = Some(coord);
+
+            if let Some(renderer) = state.renderer.as_mut() {
+                renderer.animate_placement([coord]);
+            }
         }
-        PlaceTileResponse::Removed => {
+        PlacementResult::Removed => {
             state
-                .audio_man
-                .play(state.resource_man.audio["tile_removal"].clone())
+                .audio
+                .play_at(
+                    &mut state.audio_man,
+                    &state.resource_man,
+                    "tile_removal",
+                    coord,
+                    state.camera.get_pos(),
+                )
                 .unwrap();
+
+            if let Some(renderer) = state.renderer.as_mut() {
+                renderer.animate_removal([coord]);
+            }
         }
-        _ => {}
+        PlacementResult::Failed(failure) => {
+            if painting {
+                // so that the same tile isn't tried again every frame
+                state.ui_state.already_placed_at = Some(coord);
+                state.ui_state.painting_failures.push(coord, failure);
+
+                gui::placement::flash_failures(state, &[coord]);
+            } else {
+                gui::placement::flash_failure(state, coord, failure);
+            }
+        }
+        PlacementResult::Ignored => {}
+    }
+
+    Ok(result)
+}
+
+/// Places the tile at the pointed-at coordinate while the button is held, as a part of the drag.
+///
+/// A directional tile points along the drag, the one placed before it being turned toward it, so that dragging out
+/// a line of them links them up. Running out of the item the tile's category needs stops the drag until the button is let go.
+fn drag_place_tile(state: &mut GameState, id: TileId) -> anyhow::Result<()> {
+    let Some(mut drag) = state.ui_state.placement_drag else {
+        return Ok(());
+    };
+
+    if drag.stopped {
+        return Ok(());
+    }
+
+    let coord = state.camera.pointing_at;
+    let painting = !state.input_handler.main_pressed;
+
+    let direction = drag
+        .last_placed
+        .map(|last| coord - last)
+        .filter(|delta| TileCoord::ZERO.neighbors().contains(delta))
+        .filter(|_| is_directional(&state.resource_man, id));
+
+    // a picked tile keeps its config, other than where it points
+    let mut data = state.ui_state.selected_tile_data.clone();
+    if let Some(direction) = direction {
+        data.get_or_insert_with(DataMap::default).set(
+            state.resource_man.registry.data_ids.direction,
+            Data::Coord(direction),
+        );
+    }
+
+    match place_tile(id, coord, data, false, painting, state)? {
+        PlacementResult::Placed => {
+            if let Some((last, direction)) = drag.last_placed.zip(direction) {
+                if let Ok(CallResult::Success(Some(entity))) = state.tokio.block_on(
+                    state
+                        .game
+                        .call(|reply| GameSystemMessage::GetTileEntity(last, reply), None),
+                ) {
+                    entity.send_message(TileEntityMsg::SetDataValue(
+                        state.resource_man.registry.data_ids.direction,
+                        Data::Coord(direction),
+                    ))?;
+                }
+            }
+
+            drag.last_placed = Some(coord);
+        }
+        PlacementResult::Failed(failure @ PlacementFailure::MissingItem(_)) => {
+            drag.stopped = true;
+
+            let text = gui::placement::failure_text(state, failure);
+            state.ui_state.push_toast(ToastLevel::Warning, text);
+        }
+        _ => {
+            // the tiles that were already there aren't turned
+            drag.last_placed = None;
+        }
+    }
+
+    state.ui_state.placement_drag = Some(drag);
+
+    Ok(())
+}
+
+/// Selects the pointed-at tile for placing, so that what is placed next is configured the same way.
+///
+/// The tile has to be unlocked, or the player is told so instead.
+fn pick_tile(state: &mut GameState) -> anyhow::Result<()> {
+    let coord = state.camera.pointing_at;
+
+    let Some((id, data)) = state
+        .tokio
+        .block_on(
+            state
+                .game
+                .call(|reply| GameSystemMessage::PickTile(coord, reply), None),
+        )?
+        .unwrap()
+    else {
+        let text = state
+            .resource_man
+            .gui_str(state.resource_man.registry.gui_ids.lbl_nothing_to_pick);
+        state
+            .ui_state
+            .push_toast(ToastLevel::Info, text.to_string());
+
+        return Ok(());
+    };
+
+    let Some(info) = state.loop_store.map_info.as_ref().map(|v| v.0.clone()) else {
+        return Ok(());
+    };
+
+    let unlocked = state
+        .loop_store
+        .placeable_tiles(&state.resource_man, &mut info.blocking_lock().data)
+        .contains(&id);
+
+    if !unlocked {
+        let text = gui::placement::failure_text(state, PlacementFailure::Locked);
+        state.ui_state.push_toast(ToastLevel::Warning, text);
+
+        return Ok(());
+    }
+
+    state.ui_state.already_placed_at = None;
+    state.ui_state.selected_tile_id = Some(id);
+    state.ui_state.selected_tile_data = (!data.is_empty()).then_some(data);
+
+    Ok(())
+}
+
+/// Turns the selected tile clockwise, keeping the rest of its config.
+fn rotate_selected_tile(state: &mut GameState) {
+    let Some(id) = state
+        .ui_state
+        .selected_tile_id
+        .filter(|id| is_directional(&state.resource_man, *id))
+    else {
+        return;
+    };
+
+    let direction = tile_direction(
+        &state.resource_man,
+        id,
+        state.ui_state.selected_tile_data.as_ref(),
+    )
+    .unwrap_or(TileCoord::RIGHT);
+
+    state
+        .ui_state
+        .selected_tile_data
+        .get_or_insert_with(DataMap::default)
+        .set(
+            state.resource_man.registry.data_ids.direction,
+            Data::Coord(TileCoord::from(direction.rotate_cw(1))),
+        );
+}
+
+/// Adds the current view to the end of the map's camera path.
+pub fn record_waypoint(state: &mut GameState) {
+    let Some(info) = state.loop_store.map_info.as_ref().map(|v| v.0.clone()) else {
+        return;
+    };
+    let mut info = info.blocking_lock();
+    let data_ids = &state.resource_man.registry.data_ids;
+
+    let mut path = CameraPath::from_data(&info.data, data_ids);
+    path.waypoints.push(CameraWaypoint::new(
+        state.camera.get_tile_coord(),
+        state.camera.get_raw_pos().z,
+    ));
+    path.write_data(&mut info.data, data_ids);
+}
+
+/// Stores the current view in the map's bookmark slot, replacing what was there but keeping its name.
+pub fn set_bookmark(state: &mut GameState, slot: usize) {
+    let Some(info) = state.loop_store.map_info.as_ref().map(|v| v.0.clone()) else {
+        return;
+    };
+    let mut info = info.blocking_lock();
+    let data_ids = &state.resource_man.registry.data_ids;
+
+    let mut bookmarks = CameraBookmarks::from_data(&info.data, data_ids);
+    let Some(bookmark) = bookmarks.slots.get_mut(slot) else {
+        return;
+    };
+
+    let name = bookmark
+        .take()
+        .map_or_else(|| format!("#{}", slot + 1), |v| v.name);
+    *bookmark = Some(CameraBookmark {
+        coord: state.camera.get_tile_coord(),
+        zoom: state.camera.get_raw_pos().z,
+        name,
+    });
+    bookmarks.write_data(&mut info.data, data_ids);
+
+    state.ui_state.push_toast(
+        ToastLevel::Info,
+        state.resource_man.gui_fmt(
+            state.resource_man.registry.gui_ids.lbl_bookmark_set,
+            [("slot", Formattable::integer(&(slot + 1)))],
+        ),
+    );
+}
+
+/// Pans the camera to the map's bookmark in the slot, if the slot is set.
+pub fn go_to_bookmark(state: &mut GameState, slot: usize) {
+    let Some(info) = state.loop_store.map_info.as_ref().map(|v| v.0.clone()) else {
+        return;
+    };
+    let bookmarks = CameraBookmarks::from_data(
+        &info.blocking_lock().data,
+        &state.resource_man.registry.data_ids,
+    );
+
+    if let Some(Some(bookmark)) = bookmarks.slots.get(slot) {
+        state.camera.pan_to(bookmark.pos());
+    }
+}
+
+/// Starts flying the camera along the map's camera path, if it has any waypoints.
+pub fn start_camera_playback(state: &mut GameState) {
+    let Some(info) = state.loop_store.map_info.as_ref().map(|v| v.0.clone()) else {
+        return;
+    };
+    let path = CameraPath::from_data(
+        &info.blocking_lock().data,
+        &state.resource_man.registry.data_ids,
+    );
+
+    if !path.waypoints.is_empty() {
+        state.input_handler.clear_held();
+        state.ui_state.camera_playback = Some((path, Instant::now(), state.camera));
+    }
+}
+
+/// Stops the camera path playback, putting the camera back to where it was before.
+pub fn stop_camera_playback(state: &mut GameState) {
+    if let Some((_, _, camera)) = state.ui_state.camera_playback.take() {
+        state.camera = camera;
+    }
+}
+
+/// Gives the input to the world or to what is open over it, and returns which took it. See [automancy_system::input_routing::InputRouter::update].
+fn route_input(state: &mut GameState) -> InputRoute {
+    let route = state.ui_state.input_route(&state.input_handler);
+
+    if route == InputRoute::Modal {
+        state.ui_state.context_menu = None;
+    }
+
+    state
+        .ui_state
+        .input_router
+        .update(route, &mut state.input_handler, &mut state.camera)
+}
+
+/// Handles the input that acts on the world, which is skipped while a modal screen is open.
+fn on_world_input(
+    state: &mut GameState,
+    pointing_at_entity: Option<TileEntityWithId>,
+) -> anyhow::Result<()> {
+    if !state.input_handler.main_held && !state.ui_state.painting_failures.is_empty() {
+        let failures = mem::take(&mut state.ui_state.painting_failures);
+        let text = gui::placement::summary_text(state, &failures);
+
+        state.ui_state.push_toast(ToastLevel::Warning, text);
+    }
+
+    if gui::context_menu::handle_input(state) {
+        return Ok(());
+    }
+
+    if !state.input_handler.main_held {
+        state.ui_state.placement_drag = None;
+    }
+
+    // TODO hint this
+    if (state.input_handler.main_pressed || state.input_handler.main_held)
+        && state.ui_state.already_placed_at != Some(state.camera.pointing_at)
+    {
+        if let Some(id) = state.ui_state.selected_tile_id {
+            if state.input_handler.main_pressed {
+                state.ui_state.placement_drag = Some(PlacementDrag::default());
+            }
+
+            drag_place_tile(state, id)?;
+        }
+    }
+
+    state.input_hints.push(vec![ActionType::Delete]);
+    if state.input_handler.key_active(ActionType::Delete) {
+        // holding the select mode key deliberately removes protected tiles too
+        place_tile(
+            TileId(state.resource_man.registry.none),
+            state.camera.pointing_at,
+            None,
+            state.input_handler.key_active(ActionType::SelectMode),
+            false,
+            state,
+        )?;
+    }
+
+    if !state.input_handler.key_active(ActionType::SelectMode) {
+        // TODO hint this
+        if state.input_handler.alternate_pressed {
+            // a tile in link mode is linked right away, without the menu
+            if state.ui_state.linking_tile.is_some() {
+                interact_with_tile(state, pointing_at_entity.clone());
+            } else if let Some(entity) = pointing_at_entity.clone() {
+                gui::context_menu::open(state, entity);
+            }
+        }
+    }
+
+    if pointing_at_entity.is_some() {
+        state.input_hints.push(vec![ActionType::PickTile]);
+    }
+    if state.input_handler.key_active(ActionType::PickTile) {
+        pick_tile(state)?;
+    }
+
+    if state
+        .ui_state
+        .selected_tile_id
+        .is_some_and(|id| is_directional(&state.resource_man, id))
+    {
+        state.input_hints.push(vec![ActionType::RotateTile]);
+    }
+    if state.input_handler.key_active(ActionType::RotateTile) {
+        rotate_selected_tile(state);
+    }
+
+    if pointing_at_entity.is_some() {
+        state.input_hints.push(vec![ActionType::OpenConfig]);
+    }
+    if state.input_handler.key_active(ActionType::OpenConfig) {
+        if pointing_at_entity.is_some() {
+            interact_with_tile(state, pointing_at_entity);
+        } else {
+            let text = state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.lbl_nothing_to_configure);
+
+            state
+                .ui_state
+                .push_toast(ToastLevel::Info, text.to_string());
+        }
+    }
+
+    state.input_hints.push(vec![ActionType::SelectMode]);
+    if state.input_handler.key_active(ActionType::SelectMode)
+        && state.ui_state.screen == Screen::Ingame
+    {
+        if state.ui_state.paste_from.is_none() {
+            state
+                .ui_state
+                .grouped_tiles
+                .insert(state.camera.pointing_at);
+        }
+    } else {
+        state.ui_state.grouped_tiles.clear();
+    }
+
+    state.input_hints.push(vec![ActionType::Regions]);
+    if state.input_handler.key_active(ActionType::Regions) {
+        // with a selection, the key turns it into a new region
+        if !state.ui_state.grouped_tiles.is_empty() {
+            state.ui_state.region_selection = Some(state.ui_state.grouped_tiles.clone());
+            state.ui_state.regions_open = true;
+        } else {
+            state.ui_state.regions_open = !state.ui_state.regions_open;
+        }
+    }
+
+    if let Some(info) = state.loop_store.map_info.as_ref().map(|v| v.0.clone()) {
+        let mut info = info.blocking_lock();
+        let hotbar = read_hotbar(&state.resource_man, &info.data);
+
+        for (slot, id) in hotbar.iter().enumerate() {
+            let action = ActionType::Hotbar(slot as u8);
+
+            if id.is_some() {
+                state.input_hints.push(vec![action]);
+            }
+            if state.input_handler.key_active(action) {
+                gui::tile_selection::use_hotbar_slot(state, &mut info.data, slot);
+            }
+        }
+
+        if state.input_handler.key_active(ActionType::PreviousCategory) {
+            gui::tile_selection::cycle_category(state, &mut info.data, false);
+        }
+
+        if state.input_handler.key_active(ActionType::NextCategory) {
+            gui::tile_selection::cycle_category(state, &mut info.data, true);
+        }
+    }
+
+    state.input_hints.push(vec![ActionType::CameraPath]);
+    if state.input_handler.key_active(ActionType::CameraPath) {
+        state.ui_state.camera_path_open = !state.ui_state.camera_path_open;
+    }
+
+    state.input_hints.push(vec![ActionType::FlowGraph]);
+    if state.input_handler.key_active(ActionType::FlowGraph) {
+        state.ui_state.flow_graph_open = !state.ui_state.flow_graph_open;
+
+        if state.ui_state.flow_graph_open {
+            gui::flow_graph::refresh_flow_graph(state);
+        }
+    }
+
+    if state.input_handler.key_active(ActionType::PauseTicks) {
+        let paused = match state
+            .tokio
+            .block_on(state.game.call(GameSystemMessage::GetTickStats, None))
+        {
+            Ok(CallResult::Success(stats)) => stats.paused,
+            _ => false,
+        };
+
+        state
+            .game
+            .send_message(GameSystemMessage::SetTickPaused(!paused))?;
+
+        let text = state.resource_man.gui_str(if paused {
+            state.resource_man.registry.gui_ids.lbl_ticks_resumed
+        } else {
+            state.resource_man.registry.gui_ids.lbl_ticks_paused
+        });
+        state
+            .ui_state
+            .push_toast(ToastLevel::Info, text.to_string());
+    }
+
+    if state.input_handler.key_active(ActionType::StepTick) {
+        state.game.send_message(GameSystemMessage::StepTick(1))?;
+    }
+
+    for (action, faster) in [(ActionType::SlowDown, false), (ActionType::SpeedUp, true)] {
+        if !state.input_handler.key_active(action) {
+            continue;
+        }
+
+        let Ok(CallResult::Success(stats)) = state
+            .tokio
+            .block_on(state.game.call(GameSystemMessage::GetTickStats, None))
+        else {
+            continue;
+        };
+
+        let rates = stats.rates.next_speed(faster);
+        state
+            .game
+            .send_message(GameSystemMessage::SetTickRates(rates))?;
+
+        let text = state.resource_man.gui_fmt(
+            state.resource_man.registry.gui_ids.lbl_tick_speed,
+            [("speed", Formattable::display(&rates.speed_factor()))],
+        );
+        state.ui_state.push_toast(ToastLevel::Info, text);
+    }
+
+    state.input_hints.push(vec![ActionType::ProductionStats]);
+    if state.input_handler.key_active(ActionType::ProductionStats) {
+        state.ui_state.production_stats_open = !state.ui_state.production_stats_open;
+    }
+
+    state.input_hints.push(vec![ActionType::Recipes]);
+    if state.input_handler.key_active(ActionType::Recipes) {
+        state.ui_state.recipes_open = !state.ui_state.recipes_open;
+    }
+
+    state.input_hints.push(vec![ActionType::Inventory]);
+    if state.input_handler.key_active(ActionType::Inventory) {
+        state.ui_state.inventory_open = !state.ui_state.inventory_open;
+    }
+
+    state.input_hints.push(vec![ActionType::Bookmarks]);
+    if state.input_handler.key_active(ActionType::Bookmarks) {
+        state.ui_state.bookmarks_open = !state.ui_state.bookmarks_open;
+    }
+
+    for slot in 0..BOOKMARK_SLOTS {
+        if state
+            .input_handler
+            .key_active(ActionType::SetBookmark(slot as u8))
+        {
+            set_bookmark(state, slot);
+        }
+
+        if state
+            .input_handler
+            .key_active(ActionType::GoToBookmark(slot as u8))
+        {
+            go_to_bookmark(state, slot);
+        }
+    }
+
+    if state.ui_state.camera_path_open {
+        state.input_hints.push(vec![ActionType::RecordWaypoint]);
+    }
+    if state.input_handler.key_active(ActionType::RecordWaypoint) {
+        record_waypoint(state);
+    }
+
+    if state.input_handler.key_active(ActionType::HotkeyActive) {
+        state
+            .input_hints
+            .push(vec![ActionType::HotkeyActive, ActionType::Undo]);
+        state
+            .input_hints
+            .push(vec![ActionType::HotkeyActive, ActionType::Redo]);
+
+        if state.input_handler.key_active(ActionType::Undo) {
+            state.game.send_message(GameSystemMessage::Undo)?;
+        }
+        if state.input_handler.key_active(ActionType::Redo) {
+            state.game.send_message(GameSystemMessage::Redo)?;
+        }
+
+        if state.ui_state.paste_from.is_none() && !state.ui_state.grouped_tiles.is_empty() {
+            state
+                .input_hints
+                .push(vec![ActionType::HotkeyActive, ActionType::Cut]);
+            state
+                .input_hints
+                .push(vec![ActionType::HotkeyActive, ActionType::Copy]);
+
+            if state.input_handler.key_active(ActionType::Cut)
+                || state.input_handler.key_active(ActionType::Copy)
+            {
+                state.ui_state.paste_from = Some(state.camera.pointing_at);
+                state
+                    .audio
+                    .play(&mut state.audio_man, &state.resource_man, "click")?;
+
+                let coords = Vec::from_iter(mem::take(&mut state.ui_state.grouped_tiles));
+
+                if state.input_handler.key_active(ActionType::Cut) {
+                    let none = state.resource_man.registry.none;
+
+                    let (removed, failures) = state
+                        .tokio
+                        .block_on(state.game.call(
+                            |reply| {
+                                GameSystemMessage::PlaceTiles {
+                                    tiles: coords
+                                        .into_iter()
+                                        .map(|coord| (coord, TileId(none), None))
+                                        .collect::<Vec<_>>(),
+                                    reply: Some(reply),
+                                    place_over: true,
+                                    record: true,
+                                    force: false,
+                                }
+                            },
+                            None,
+                        ))?
+                        .unwrap();
+                    state.ui_state.paste_content = removed;
+
+                    gui::placement::report_failures(state, &failures);
+
+                    if let Some(renderer) = state.renderer.as_mut() {
+                        renderer.animate_removal(
+                            state
+                                .ui_state
+                                .paste_content
+                                .iter()
+                                .map(|(coord, ..)| *coord),
+                        );
+                    }
+                } else {
+                    state.ui_state.paste_content = state
+                        .tokio
+                        .block_on(
+                            state
+                                .game
+                                .call(|reply| GameSystemMessage::GetTiles(coords, reply), None),
+                        )?
+                        .unwrap();
+                }
+            }
+        }
+
+        if let Some(start) = state.ui_state.paste_from {
+            state
+                .input_hints
+                .push(vec![ActionType::HotkeyActive, ActionType::Paste]);
+            state.input_hints.push(vec![
+                ActionType::HotkeyActive,
+                ActionType::SelectMode,
+                ActionType::Paste,
+            ]);
+
+            if state.input_handler.key_active(ActionType::Paste) {
+                let direction = state.camera.pointing_at - start;
+                // holding the select key pastes over the tiles in the way, instead of leaving them be
+                let place_over = state.input_handler.key_active(ActionType::SelectMode);
+
+                let tiles = state
+                    .ui_state
+                    .paste_content
+                    .clone()
+                    .into_iter()
+                    .map(|(coord, id, data)| (coord + direction, id, data))
+                    .collect::<Vec<_>>();
+
+                let coords = tiles.iter().map(|(coord, ..)| *coord).collect::<Vec<_>>();
+
+                let (_, failures) = state
+                    .tokio
+                    .block_on(state.game.call(
+                        |reply| GameSystemMessage::PlaceTiles {
+                            tiles,
+                            reply: Some(reply),
+                            place_over,
+                            record: true,
+                            force: false,
+                        },
+                        None,
+                    ))?
+                    .unwrap();
+
+                if let Some(renderer) = state.renderer.as_mut() {
+                    renderer.animate_placement(
+                        coords
+                            .into_iter()
+                            .filter(|coord| !failures.0.iter().any(|(failed, _)| failed == coord)),
+                    );
+                }
+
+                gui::placement::report_failures(state, &failures);
+
+                state
+                    .audio
+                    .play(&mut state.audio_man, &state.resource_man, "click")?;
+                // TODO click2
+            }
+        }
+    } else {
+        state.input_hints.push(vec![ActionType::HotkeyActive]);
     }
 
     Ok(())
@@ -281,22 +1067,44 @@ pub fn on_event(
 
                     state.loop_store.elapsed = now - state.loop_store.frame_start.take().unwrap();
 
+                    state.input_handler.move_cursor(
+                        state.loop_store.elapsed.as_secs_f32(),
+                        window::window_size_double(&state.renderer.as_ref().unwrap().gpu.window),
+                    );
+                    if state.ui_state.camera_playback.is_none() {
+                        state.camera.pan(
+                            state.input_handler.left_stick,
+                            state.loop_store.elapsed.as_secs_f32(),
+                        );
+                    }
+
                     state.camera.update_pointing_at(
                         state.input_handler.main_pos,
                         window::window_size_double(&state.renderer.as_ref().unwrap().gpu.window),
                     );
+                    if let Some((path, start, _)) = &state.ui_state.camera_playback {
+                        // render time rather than ticks, so the flight stays smooth at any tick rate
+                        match path.sample(start.elapsed().as_secs_f32()) {
+                            Some(pos) => state.camera.set_raw_pos(pos),
+                            None => stop_camera_playback(state),
+                        }
+                    }
                     state.camera.update_pos(
                         window::window_size_double(&state.renderer.as_ref().unwrap().gpu.window),
                         state.loop_store.elapsed.as_secs_f32(),
                     );
+                    state.audio.update(state.camera.get_pos());
+                    state.audio.update_music(
+                        &mut state.audio_man,
+                        &state.resource_man,
+                        state.ui_state.screen == Screen::Paused || has_err(),
+                    );
 
                     state.loop_store.frame_start = Some(now);
 
                     let result = render(state, event_loop, state.screenshotting);
 
-                    if state.screenshotting {
-                        state.screenshotting = false;
-                    }
+                    state.screenshotting = None;
 
                     return result;
                 }
@@ -330,203 +1138,160 @@ pub fn on_event(
     };
 
     if window_event.is_some() || device_event.is_some() {
-        let pointing_at_entity = state.loop_store.pointing_cache.blocking_lock().clone();
-
-        state.input_handler.reset();
-
-        state.input_handler.update(input::convert_input(
+        let event = input::convert_input(
             window_event,
             device_event,
             window::window_size_double(&state.renderer.as_ref().unwrap().gpu.window),
             1.0, //TODO sensitivity option
-        ));
+        );
 
-        state.camera.handle_input(&state.input_handler);
+        on_input(state, event)?;
+    }
 
-        state.input_hints.clear();
+    Ok(false)
+}
 
-        state.input_hints.push(vec![ActionType::Cancel]);
-        if state.input_handler.key_active(ActionType::Cancel) {
-            // one by one
-            if state.ui_state.selected_tile_id.take().is_none()
-                && state.ui_state.linking_tile.take().is_none()
-                && state.ui_state.paste_from.take().is_none()
-            {
-                if state
-                    .ui_state
-                    .switch_screen_when(&|s| s.screen == Screen::Ingame, Screen::Paused)
-                {
-                    state
-                        .tokio
-                        .block_on(state.game.call(GameSystemMessage::SaveMap, None))?
-                        .unwrap();
-                } else {
-                    state
-                        .ui_state
-                        .switch_screen_when(&|s| s.screen == Screen::Paused, Screen::Ingame);
-                }
-            }
-        }
+/// Handles the events of the gamepads since they were last polled.
+pub fn on_gamepad_events(state: &mut GameState) -> anyhow::Result<()> {
+    for event in state.gamepads.poll() {
+        on_input(state, event)?;
+    }
 
-        state.input_hints.push(vec![ActionType::Player]);
+    Ok(())
+}
 
-        // TODO hint this
-        if (state.input_handler.main_pressed
-            || (state.input_handler.key_active(ActionType::SelectMode)
-                && state.input_handler.main_held))
-            && state.ui_state.already_placed_at != Some(state.camera.pointing_at)
-        {
-            if let Some(id) = state.ui_state.selected_tile_id {
-                place_tile(id, state.camera.pointing_at, state)?;
-            }
-        }
+/// Moves the pointing cursor onto the neighbor of the tile it points at that is the most in the direction on the screen.
+fn step_cursor(state: &mut GameState, direction: Vec2) {
+    let size = window::window_size_double(&state.renderer.as_ref().unwrap().gpu.window);
+    let camera_pos = state.camera.get_pos();
+    let rotation = state.camera.get_rotation();
 
-        state.input_hints.push(vec![ActionType::Delete]);
-        if state.input_handler.key_active(ActionType::Delete) {
-            place_tile(
-                TileId(state.resource_man.registry.none),
-                state.camera.pointing_at,
-                state,
-            )?;
-        }
+    let to_screen = |coord: TileCoord| {
+        let pos = HEX_GRID_LAYOUT.hex_to_world_pos(*coord);
 
-        if !state.input_handler.key_active(ActionType::SelectMode) {
-            // TODO hint this
-            if state.input_handler.alternate_pressed {
-                if let Some((link_to, id)) = state.ui_state.linking_tile {
-                    link_tile(state, pointing_at_entity, link_to, id);
-                } else if Some(state.camera.pointing_at) == state.ui_state.config_open_at {
-                    state.ui_state.config_open_at = None;
-                    state.ui_state.text_field.get(TextField::Filter).clear();
-                } else {
-                    state.ui_state.config_open_at = Some(state.camera.pointing_at);
-                    state.ui_state.text_field.get(TextField::Filter).clear();
-                }
-            }
-        }
+        math::world_to_screen(size, pos.extend(FAR), camera_pos, rotation)
+    };
 
-        state.input_hints.push(vec![ActionType::SelectMode]);
-        if state.input_handler.key_active(ActionType::SelectMode)
-            && state.ui_state.screen == Screen::Ingame
+    let from = to_screen(state.camera.pointing_at);
+
+    let Some(to) = state
+        .camera
+        .pointing_at
+        .neighbors()
+        .map(to_screen)
+        .into_iter()
+        .max_by(|a, b| {
+            Float::total_cmp(
+                &(*a - from).normalize_or_zero().dot(direction),
+                &(*b - from).normalize_or_zero().dot(direction),
+            )
+        })
+    else {
+        return;
+    };
+
+    state.input_handler.main_pos = to;
+}
+
+/// Handles one input event from any device.
+fn on_input(state: &mut GameState, event: GameInputEvent) -> anyhow::Result<()> {
+    let pointing_at_entity = state.loop_store.pointing_cache.blocking_lock().clone();
+
+    state.input_handler.reset();
+
+    state.input_handler.update(event);
+
+    state.input_hints.clear();
+
+    state.input_hints.push(vec![ActionType::Cancel]);
+    if state.input_handler.consume(ActionType::Cancel) {
+        // one by one, closing a modal screen first so that it does not also pause the game
+        if state.ui_state.camera_playback.is_some() {
+            stop_camera_playback(state);
+        } else if state.ui_state.context_menu.is_some() {
+            state.ui_state.context_menu = None;
+        } else if state.ui_state.popup != PopupState::None {
+            state.ui_state.popup = PopupState::None;
+        } else if state.ui_state.screen == Screen::Ingame
+            && state.input_handler.key_active(ActionType::Player)
+        {
+            state.input_handler.consume(ActionType::Player);
+        } else if state.ui_state.selected_tile_id.take().is_none()
+            && state.ui_state.linking_tile.take().is_none()
+            && state.ui_state.paste_from.take().is_none()
         {
-            if state.ui_state.paste_from.is_none() {
+            if state
+                .ui_state
+                .switch_screen_when(&|s| s.screen == Screen::Ingame, Screen::Paused)
+            {
+                state
+                    .tokio
+                    .block_on(state.game.call(GameSystemMessage::SaveMap, None))?
+                    .unwrap();
+            } else {
                 state
                     .ui_state
-                    .grouped_tiles
-                    .insert(state.camera.pointing_at);
+                    .switch_screen_when(&|s| s.screen == Screen::Paused, Screen::Ingame);
             }
-        } else {
-            state.ui_state.grouped_tiles.clear();
         }
+    }
 
-        if state.input_handler.key_active(ActionType::HotkeyActive) {
-            state
-                .input_hints
-                .push(vec![ActionType::HotkeyActive, ActionType::Undo]);
-            state
-                .input_hints
-                .push(vec![ActionType::HotkeyActive, ActionType::Redo]);
+    // the camera path has the camera to itself while it plays
+    let world_suspended = route_input(state) != InputRoute::World;
+    if !world_suspended {
+        state.camera.handle_input(&state.input_handler);
 
-            if state.input_handler.key_active(ActionType::Undo) {
-                state.game.send_message(GameSystemMessage::Undo)?;
-            }
-            if state.input_handler.key_active(ActionType::Redo) {
-                //TODO state.game.send_message(GameSystemMessage::Redo)?;
+        for (action, direction) in [
+            (ActionType::CursorUp, Vec2::NEG_Y),
+            (ActionType::CursorDown, Vec2::Y),
+            (ActionType::CursorLeft, Vec2::NEG_X),
+            (ActionType::CursorRight, Vec2::X),
+        ] {
+            if state.input_handler.consume(action) {
+                step_cursor(state, direction);
             }
+        }
+    }
 
-            if state.ui_state.paste_from.is_none() && !state.ui_state.grouped_tiles.is_empty() {
-                state
-                    .input_hints
-                    .push(vec![ActionType::HotkeyActive, ActionType::Cut]);
-                state
-                    .input_hints
-                    .push(vec![ActionType::HotkeyActive, ActionType::Copy]);
+    state.input_hints.push(vec![ActionType::Player]);
 
-                if state.input_handler.key_active(ActionType::Cut)
-                    || state.input_handler.key_active(ActionType::Copy)
-                {
-                    state.ui_state.paste_from = Some(state.camera.pointing_at);
-                    state
-                        .audio_man
-                        .play(state.resource_man.audio["click"].clone())?;
-
-                    let coords = Vec::from_iter(mem::take(&mut state.ui_state.grouped_tiles));
-
-                    if state.input_handler.key_active(ActionType::Cut) {
-                        let none = state.resource_man.registry.none;
-
-                        state.ui_state.paste_content = state
-                            .tokio
-                            .block_on(state.game.call(
-                                |reply| {
-                                    GameSystemMessage::PlaceTiles {
-                                        tiles: coords
-                                            .into_iter()
-                                            .map(|coord| (coord, TileId(none), None))
-                                            .collect::<Vec<_>>(),
-                                        reply: Some(reply),
-                                        place_over: true,
-                                        record: true,
-                                    }
-                                },
-                                None,
-                            ))?
-                            .unwrap();
-                    } else {
-                        state.ui_state.paste_content = state
-                            .tokio
-                            .block_on(
-                                state
-                                    .game
-                                    .call(|reply| GameSystemMessage::GetTiles(coords, reply), None),
-                            )?
-                            .unwrap();
-                    }
-                }
-            }
+    if !world_suspended {
+        on_world_input(state, pointing_at_entity)?;
+    }
 
-            if let Some(start) = state.ui_state.paste_from {
-                state
-                    .input_hints
-                    .push(vec![ActionType::HotkeyActive, ActionType::Paste]);
-
-                if state.input_handler.key_active(ActionType::Paste) {
-                    let direction = state.camera.pointing_at - start;
-
-                    let tiles = state
-                        .ui_state
-                        .paste_content
-                        .clone()
-                        .into_iter()
-                        .map(|(coord, id, data)| (coord + direction, id, data))
-                        .collect::<Vec<_>>();
-
-                    state.game.send_message(GameSystemMessage::PlaceTiles {
-                        tiles,
-                        reply: None,
-                        place_over: false,
-                        record: true,
-                    })?;
+    if state.input_handler.key_active(ActionType::Fullscreen) {
+        state.options.graphics.fullscreen = !state.options.graphics.fullscreen;
+        state.options.synced = false
+    }
 
-                    state
-                        .audio_man
-                        .play(state.resource_man.audio["click"].clone())?;
-                    // TODO click2
-                }
-            }
-        } else {
-            state.input_hints.push(vec![ActionType::HotkeyActive]);
-        }
+    if state.input_handler.key_active(ActionType::ReloadResources) {
+        state.loop_store.reload_resources = true;
+    }
 
-        if state.input_handler.key_active(ActionType::Fullscreen) {
-            state.options.graphics.fullscreen = !state.options.graphics.fullscreen;
-            state.options.synced = false
-        }
+    if state.input_handler.key_active(ActionType::SkipTrack) {
+        state
+            .audio
+            .skip_music(&mut state.audio_man, &state.resource_man);
+    }
 
-        state.screenshotting = state.input_handler.key_active(ActionType::Screenshot);
+    // kept until the next frame takes it, as other events may come before that
+    if state
+        .input_handler
+        .key_active(ActionType::SaveScreenshotWithoutUi)
+    {
+        state.screenshotting = Some(Screenshot::FileWithoutUi);
+    } else if state.input_handler.key_active(ActionType::SaveScreenshot) {
+        state.screenshotting = Some(Screenshot::File);
+    } else if state.input_handler.key_active(ActionType::Screenshot) {
+        state.screenshotting = Some(Screenshot::Clipboard);
+    }
+
+    state.input_hints.push(vec![ActionType::ToggleGui]);
 
-        state.input_hints.push(vec![ActionType::ToggleGui]);
+    state.input_hints.push(vec![ActionType::CycleOverlay]);
+    if state.input_handler.key_active(ActionType::CycleOverlay) {
+        state.ui_state.overlay_mode = state.ui_state.overlay_mode.next();
     }
 
-    Ok(false)
+    Ok(())
 }
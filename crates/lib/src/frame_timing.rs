@@ -0,0 +1,292 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use wgpu::{
+    Buffer, BufferDescriptor, BufferUsages, CommandEncoder, Device, Maintain, MapMode, QuerySet,
+    QuerySetDescriptor, QueryType, Queue, RenderPassTimestampWrites, QUERY_SIZE,
+};
+
+/// How many frames the averages are taken over.
+const AVERAGE_FRAMES: usize = 60;
+
+/// How many of the latest frame times are kept, for the sparkline in the debugger.
+pub const FRAME_HISTORY: usize = 120;
+
+/// A part of a frame's work on the CPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuStage {
+    /// building the UI
+    UiBuild,
+    /// gathering the render commands of the tiles into instances
+    Instances,
+    /// writing the changed instances and matrices to the GPU buffers
+    Upload,
+    /// recording the draws of the world
+    Encode,
+    /// drawing the UI, along with the game objects in it
+    UiRender,
+    /// waiting for the surface to give out a texture, and presenting it
+    PresentWait,
+}
+
+impl CpuStage {
+    pub const ALL: [Self; 6] = [
+        Self::UiBuild,
+        Self::Instances,
+        Self::Upload,
+        Self::Encode,
+        Self::UiRender,
+        Self::PresentWait,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::UiBuild => "UI build",
+            Self::Instances => "Instance gathering",
+            Self::Upload => "Buffer upload",
+            Self::Encode => "World encoding",
+            Self::UiRender => "UI rendering",
+            Self::PresentWait => "Present wait",
+        }
+    }
+}
+
+/// A render pass that is timed on the GPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpuPass {
+    Game,
+    Overlay,
+    PostProcessing,
+    Antialiasing,
+    Gui,
+    Combine,
+    Present,
+}
+
+impl GpuPass {
+    pub const ALL: [Self; 7] = [
+        Self::Game,
+        Self::Overlay,
+        Self::PostProcessing,
+        Self::Antialiasing,
+        Self::Gui,
+        Self::Combine,
+        Self::Present,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Game => "Game pass",
+            Self::Overlay => "Overlay pass",
+            Self::PostProcessing => "Post processing pass",
+            Self::Antialiasing => "Antialiasing pass",
+            Self::Gui => "GUI pass",
+            Self::Combine => "Combine pass",
+            Self::Present => "Present pass",
+        }
+    }
+}
+
+/// The average of the latest [AVERAGE_FRAMES] samples of a duration.
+#[derive(Debug, Clone, Default)]
+pub struct RollingAverage {
+    samples: VecDeque<Duration>,
+    total: Duration,
+}
+
+impl RollingAverage {
+    pub fn push(&mut self, sample: Duration) {
+        self.samples.push_back(sample);
+        self.total += sample;
+
+        if self.samples.len() > AVERAGE_FRAMES {
+            if let Some(oldest) = self.samples.pop_front() {
+                self.total -= oldest;
+            }
+        }
+    }
+
+    /// Gets the average, or None if nothing was sampled yet.
+    pub fn average(&self) -> Option<Duration> {
+        (!self.samples.is_empty()).then(|| self.total / self.samples.len() as u32)
+    }
+}
+
+/// Where the time of the latest frames went, on the CPU, and on the GPU if the adapter can time it.
+#[derive(Debug, Clone, Default)]
+pub struct FrameTimings {
+    pub cpu: [RollingAverage; CpuStage::ALL.len()],
+    pub gpu: [RollingAverage; GpuPass::ALL.len()],
+    /// whether the adapter supports timestamp queries. Without them, the GPU passes are never sampled
+    pub gpu_supported: bool,
+    /// the times between the latest frames, the oldest first
+    pub frame_times: VecDeque<Duration>,
+    last_frame: Option<Instant>,
+}
+
+impl FrameTimings {
+    pub fn new(gpu_supported: bool) -> Self {
+        Self {
+            gpu_supported,
+            ..Default::default()
+        }
+    }
+
+    /// Counts the time since the last frame started.
+    pub fn start_frame(&mut self) {
+        let now = Instant::now();
+
+        if let Some(last) = self.last_frame.replace(now) {
+            self.frame_times.push_back(now - last);
+
+            if self.frame_times.len() > FRAME_HISTORY {
+                self.frame_times.pop_front();
+            }
+        }
+    }
+
+    pub fn record_cpu(&mut self, stage: CpuStage, time: Duration) {
+        self.cpu[stage as usize].push(time);
+    }
+
+    pub fn record_gpu(&mut self, pass: GpuPass, time: Duration) {
+        self.gpu[pass as usize].push(time);
+    }
+}
+
+/// The number of timestamps written each frame, one at the start and one at the end of each pass.
+const TIMESTAMP_COUNT: u32 = GpuPass::ALL.len() as u32 * 2;
+
+/// Times the render passes with timestamp queries, reading the times back a few frames later so that the CPU never waits on the GPU for them.
+///
+/// While the times of a frame are still being read back, the frames after it are not timed.
+pub struct GpuTimer {
+    query_set: QuerySet,
+    resolve_buffer: Buffer,
+    readback_buffer: Buffer,
+    /// the nanoseconds each timestamp tick takes
+    period: f32,
+    /// the passes timed in the frame being read back
+    in_flight: Option<[bool; GpuPass::ALL.len()]>,
+    /// whether mapping the readback buffer worked, once it is done
+    mapped: Arc<Mutex<Option<bool>>>,
+    /// the passes timed so far in the frame being recorded, if it is timed
+    recording: Option<[bool; GpuPass::ALL.len()]>,
+}
+
+impl GpuTimer {
+    /// Creates the timer. The device must have been created with [wgpu::Features::TIMESTAMP_QUERY].
+    pub fn new(device: &Device, queue: &Queue) -> Self {
+        let size = TIMESTAMP_COUNT as u64 * QUERY_SIZE as u64;
+
+        Self {
+            query_set: device.create_query_set(&QuerySetDescriptor {
+                label: Some("Frame Timing Query Set"),
+                ty: QueryType::Timestamp,
+                count: TIMESTAMP_COUNT,
+            }),
+            resolve_buffer: device.create_buffer(&BufferDescriptor {
+                label: Some("Frame Timing Resolve Buffer"),
+                size,
+                usage: BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            readback_buffer: device.create_buffer(&BufferDescriptor {
+                label: Some("Frame Timing Readback Buffer"),
+                size,
+                usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            period: queue.get_timestamp_period(),
+            in_flight: None,
+            mapped: Default::default(),
+            recording: None,
+        }
+    }
+
+    /// Records the times of the frame being read back if they arrived. Then this frame is timed, unless that one is still being read.
+    pub fn begin_frame(&mut self, device: &Device, timings: &mut FrameTimings) {
+        if let Some(timed) = self.in_flight {
+            device.poll(Maintain::Poll);
+
+            let Some(ok) = self.mapped.lock().unwrap().take() else {
+                self.recording = None;
+
+                return;
+            };
+
+            if ok {
+                {
+                    let data = self.readback_buffer.slice(..).get_mapped_range();
+                    let ticks = data
+                        .chunks_exact(QUERY_SIZE as usize)
+                        .map(|v| u64::from_le_bytes(v.try_into().unwrap()))
+                        .collect::<Vec<_>>();
+
+                    for (index, pass) in GpuPass::ALL.into_iter().enumerate() {
+                        if timed[index] {
+                            let elapsed = ticks[index * 2 + 1].saturating_sub(ticks[index * 2]);
+
+                            timings.record_gpu(
+                                pass,
+                                Duration::from_nanos((elapsed as f64 * self.period as f64) as u64),
+                            );
+                        }
+                    }
+                }
+
+                self.readback_buffer.unmap();
+            }
+
+            self.in_flight = None;
+        }
+
+        self.recording = Some([false; GpuPass::ALL.len()]);
+    }
+
+    /// Gets where the pass writes its timestamps to, if this frame is timed.
+    pub fn pass_writes(&mut self, pass: GpuPass) -> Option<RenderPassTimestampWrites<'_>> {
+        let recording = self.recording.as_mut()?;
+        recording[pass as usize] = true;
+
+        let index = pass as u32 * 2;
+
+        Some(RenderPassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: Some(index),
+            end_of_pass_write_index: Some(index + 1),
+        })
+    }
+
+    /// Copies the timestamps of this frame to where they are read back from. This goes at the end of the frame's commands.
+    pub fn resolve(&self, encoder: &mut CommandEncoder) {
+        if self.recording.is_none() {
+            return;
+        }
+
+        encoder.resolve_query_set(&self.query_set, 0..TIMESTAMP_COUNT, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.readback_buffer,
+            0,
+            self.resolve_buffer.size(),
+        );
+    }
+
+    /// Starts reading back the timestamps of this frame. This goes after the frame's commands are submitted.
+    pub fn after_submit(&mut self) {
+        let Some(timed) = self.recording.take() else {
+            return;
+        };
+
+        let mapped = self.mapped.clone();
+        self.readback_buffer
+            .slice(..)
+            .map_async(MapMode::Read, move |result| {
+                *mapped.lock().unwrap() = Some(result.is_ok());
+            });
+
+        self.in_flight = Some(timed);
+    }
+}
@@ -1,8 +1,9 @@
 use automancy_defs::rendering::{AnimationMatrixData, GameUBO, GpuInstance, MatrixData, Vertex};
 use automancy_defs::rendering::{PostProcessingUBO, WorldMatrixData};
-use automancy_defs::{rendering::IntermediateUBO, slice_group_by::GroupBy};
+use automancy_defs::{math::Float, rendering::IntermediateUBO, slice_group_by::GroupBy};
 use automancy_macros::OptionGetter;
 use automancy_resources::ResourceManager;
+use automancy_system::options::PresentMode as PresentModeOption;
 use bytemuck::Pod;
 use ordermap::OrderMap;
 use std::mem;
@@ -10,7 +11,8 @@ use std::{num::NonZero, sync::Arc};
 use wgpu::{util::StagingBelt, CommandEncoder};
 use wgpu::{
     util::{backend_bits_from_env, power_preference_from_env, BufferInitDescriptor, DeviceExt},
-    BufferAddress, InstanceFlags, PipelineCompilationOptions, COPY_BUFFER_ALIGNMENT,
+    BufferAddress, BufferDescriptor, InstanceFlags, PipelineCompilationOptions,
+    COPY_BUFFER_ALIGNMENT,
 };
 use wgpu::{AdapterInfo, Face, Surface};
 use wgpu::{
@@ -18,13 +20,13 @@ use wgpu::{
     BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, BlendState,
     Buffer, BufferBindingType, BufferUsages, Color, ColorTargetState, ColorWrites, CompareFunction,
     DepthStencilState, Device, DeviceDescriptor, Extent3d, Features, FilterMode, FragmentState,
-    FrontFace, Instance, InstanceDescriptor, Limits, MultisampleState, PipelineLayoutDescriptor,
-    PowerPreference, PresentMode, PrimitiveState, PrimitiveTopology, Queue, RenderPipeline,
-    RenderPipelineDescriptor, RequestAdapterOptions, Sampler, SamplerBindingType,
-    SamplerDescriptor, ShaderModule, ShaderModuleDescriptor, ShaderSource, ShaderStages,
-    SurfaceConfiguration, Texture, TextureDescriptor, TextureDimension, TextureFormat,
-    TextureSampleType, TextureUsages, TextureView, TextureViewDescriptor, TextureViewDimension,
-    VertexState,
+    FrontFace, Instance, InstanceDescriptor, Limits, MultisampleState, PipelineLayout,
+    PipelineLayoutDescriptor, PowerPreference, PresentMode, PrimitiveState, PrimitiveTopology,
+    Queue, RenderPipeline, RenderPipelineDescriptor, RequestAdapterOptions, Sampler,
+    SamplerBindingType, SamplerDescriptor, ShaderModule, ShaderModuleDescriptor, ShaderSource,
+    ShaderStages, SurfaceConfiguration, Texture, TextureDescriptor, TextureDimension,
+    TextureFormat, TextureSampleType, TextureUsages, TextureView, TextureViewDescriptor,
+    TextureViewDimension, VertexState,
 };
 use winit::dpi::PhysicalSize;
 use winit::window::Window;
@@ -42,6 +44,17 @@ pub const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
 pub const MODEL_DEPTH_FORMAT: TextureFormat = TextureFormat::R32Float;
 pub const SCREENSHOT_FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
 pub const NORMAL_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+/// The model depth format with MSAA on, as [MODEL_DEPTH_FORMAT] can't be resolved.
+pub const MSAA_MODEL_DEPTH_FORMAT: TextureFormat = TextureFormat::R16Float;
+
+/// Gets the format the model depth is drawn in, for targets with the sample count.
+pub fn model_depth_format(samples: u32) -> TextureFormat {
+    if samples > 1 {
+        MSAA_MODEL_DEPTH_FORMAT
+    } else {
+        MODEL_DEPTH_FORMAT
+    }
+}
 
 fn align_to_copy_alignment(add: BufferAddress) -> BufferAddress {
     add + (COPY_BUFFER_ALIGNMENT - (add % COPY_BUFFER_ALIGNMENT))
@@ -149,16 +162,22 @@ where
     if (buffer.size() as usize) < byte_size * size {
         let usage = buffer.usage();
 
-        *buffer = device.create_buffer_init(&BufferInitDescriptor {
+        // grown to the next power of two, so that a buffer that keeps growing isn't made anew every time
+        *buffer = device.create_buffer(&BufferDescriptor {
             label: None,
-            contents: bytemuck::cast_slice(data),
+            size: align_to_copy_alignment(
+                (byte_size * size.max(data.len()).next_power_of_two()) as BufferAddress,
+            ),
             usage,
+            mapped_at_creation: false,
         });
-    } else {
-        return update_buffer_with_changes(encoder, device, buffer, changes, data);
+
+        // everything is written again, as the new buffer starts out empty
+        let all = (0..data.len()).collect::<Vec<_>>();
+        return update_buffer_with_changes(encoder, device, buffer, &all, data);
     }
 
-    None
+    update_buffer_with_changes(encoder, device, buffer, changes, data)
 }
 
 pub fn resize_update_buffer<V>(device: &Device, queue: &Queue, buffer: &mut Buffer, data: &[V])
@@ -476,6 +495,7 @@ pub struct GlobalResources {
     pub index_buffer: Buffer,
 
     pub game_pipeline: RenderPipeline,
+    pub game_pipeline_layout: PipelineLayout,
 
     pub intermediate_bind_group_layout: BindGroupLayout,
     pub screenshot_uniform_buffer: Buffer,
@@ -535,6 +555,53 @@ pub struct SharedResources {
     present_bind_group: Option<BindGroup>,
     #[getters(get)]
     screenshot_bind_group: Option<BindGroup>,
+
+    /// the number of samples the world is drawn with
+    samples: u32,
+    /// the size the world is rendered at, relative to the window
+    render_scale: Float,
+    /// the multisampled targets the world is drawn into, if MSAA is on
+    pub msaa: Option<MsaaTargets>,
+    /// the game pipeline for the multisampled targets, along with the sample count it was made for
+    msaa_pipeline: Option<(u32, RenderPipeline)>,
+}
+
+/// The multisampled targets the world is drawn into when MSAA is on.
+/// They are resolved into the single sampled ones once the overlay objects are drawn, which post processing reads from.
+pub struct MsaaTargets {
+    pub color: (Texture, TextureView),
+    pub normal: (Texture, TextureView),
+    pub model_depth: (Texture, TextureView),
+    pub depth: (Texture, TextureView),
+    pub overlay_depth: (Texture, TextureView),
+}
+
+impl MsaaTargets {
+    fn new(device: &Device, color_format: TextureFormat, size: Extent3d, samples: u32) -> Self {
+        let target = |format| {
+            create_texture_and_view(
+                device,
+                &TextureDescriptor {
+                    label: None,
+                    size,
+                    mip_level_count: 1,
+                    sample_count: samples,
+                    dimension: TextureDimension::D2,
+                    format,
+                    usage: TextureUsages::RENDER_ATTACHMENT,
+                    view_formats: &[],
+                },
+            )
+        };
+
+        Self {
+            color: target(color_format),
+            normal: target(NORMAL_FORMAT),
+            model_depth: target(MSAA_MODEL_DEPTH_FORMAT),
+            depth: target(DEPTH_FORMAT),
+            overlay_depth: target(DEPTH_FORMAT),
+        }
+    }
 }
 
 impl SharedResources {
@@ -549,12 +616,20 @@ impl SharedResources {
             height: config.height,
             depth_or_array_layers: 1,
         };
+        // the world is rendered at the render scale, and scaled to the window when combined with the UI
+        let max_size = device.limits().max_texture_dimension_2d;
+        let world_extent = Extent3d {
+            width: ((config.width as Float * self.render_scale).round() as u32).clamp(1, max_size),
+            height: ((config.height as Float * self.render_scale).round() as u32)
+                .clamp(1, max_size),
+            depth_or_array_layers: 1,
+        };
 
         self.game_texture = Some(create_texture_and_view(
             device,
             &TextureDescriptor {
                 label: None,
-                size: extent,
+                size: world_extent,
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
@@ -593,7 +668,7 @@ impl SharedResources {
             device,
             &TextureDescriptor {
                 label: None,
-                size: extent,
+                size: world_extent,
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
@@ -606,7 +681,7 @@ impl SharedResources {
             device,
             &TextureDescriptor {
                 label: None,
-                size: extent,
+                size: world_extent,
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
@@ -619,11 +694,11 @@ impl SharedResources {
             device,
             &TextureDescriptor {
                 label: None,
-                size: extent,
+                size: world_extent,
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
-                format: MODEL_DEPTH_FORMAT,
+                format: model_depth_format(self.samples),
                 usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
                 view_formats: &[],
             },
@@ -664,11 +739,7 @@ impl SharedResources {
             device,
             &TextureDescriptor {
                 label: None,
-                size: Extent3d {
-                    width: config.width,
-                    height: config.height,
-                    ..Default::default()
-                },
+                size: world_extent,
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
@@ -688,11 +759,7 @@ impl SharedResources {
             device,
             &TextureDescriptor {
                 label: None,
-                size: Extent3d {
-                    width: config.width,
-                    height: config.height,
-                    ..Default::default()
-                },
+                size: world_extent,
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
@@ -706,7 +773,7 @@ impl SharedResources {
             device,
             &TextureDescriptor {
                 label: None,
-                size: extent,
+                size: world_extent,
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
@@ -782,7 +849,130 @@ impl SharedResources {
                 ],
             }),
         );
+
+        self.msaa = (self.samples > 1)
+            .then(|| MsaaTargets::new(device, config.format, world_extent, self.samples));
+
+        if self.samples > 1
+            && !self
+                .msaa_pipeline
+                .as_ref()
+                .is_some_and(|(samples, _)| *samples == self.samples)
+        {
+            self.msaa_pipeline = Some((
+                self.samples,
+                create_game_pipeline(
+                    device,
+                    &global_resources.game_pipeline_layout,
+                    &global_resources.game_shader,
+                    config.format,
+                    self.samples,
+                ),
+            ));
+        }
     }
+
+    /// Changes the sample count and the render scale of the world, recreating its targets if either changed.
+    pub fn set_world_targets(
+        &mut self,
+        device: &Device,
+        config: &SurfaceConfiguration,
+        global_resources: &GlobalResources,
+        samples: u32,
+        render_scale: Float,
+    ) {
+        if self.samples == samples && self.render_scale == render_scale {
+            return;
+        }
+
+        self.samples = samples;
+        self.render_scale = render_scale;
+
+        self.create(device, config, global_resources);
+    }
+
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    pub fn render_scale(&self) -> Float {
+        self.render_scale
+    }
+
+    /// Gets the pipeline the world is drawn with, which has to match the sample count of its targets.
+    pub fn game_pipeline<'a>(
+        &'a self,
+        global_resources: &'a GlobalResources,
+    ) -> &'a RenderPipeline {
+        match &self.msaa_pipeline {
+            Some((samples, pipeline)) if self.msaa.is_some() && *samples == self.samples => {
+                pipeline
+            }
+            _ => &global_resources.game_pipeline,
+        }
+    }
+}
+
+/// Creates the pipeline the world is drawn with, for targets with the sample count.
+fn create_game_pipeline(
+    device: &Device,
+    layout: &PipelineLayout,
+    shader: &ShaderModule,
+    color_format: TextureFormat,
+    samples: u32,
+) -> RenderPipeline {
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("Game Render Pipeline"),
+        layout: Some(layout),
+        vertex: VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[Vertex::desc(), GpuInstance::desc()],
+            compilation_options: PipelineCompilationOptions::default(),
+        },
+        fragment: Some(FragmentState {
+            module: shader,
+            entry_point: "fs_main",
+            targets: &[
+                Some(ColorTargetState {
+                    format: color_format,
+                    blend: Some(BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                }),
+                Some(ColorTargetState {
+                    format: NORMAL_FORMAT,
+                    blend: None,
+                    write_mask: ColorWrites::COLOR,
+                }),
+                Some(ColorTargetState {
+                    format: model_depth_format(samples),
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                }),
+            ],
+            compilation_options: PipelineCompilationOptions::default(),
+        }),
+        primitive: PrimitiveState {
+            topology: PrimitiveTopology::TriangleList,
+            front_face: FrontFace::Ccw,
+            cull_mode: Some(Face::Back),
+            ..Default::default()
+        },
+        depth_stencil: Some(DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: CompareFunction::Less,
+            stencil: Default::default(),
+            bias: Default::default(),
+        }),
+        multisample: MultisampleState {
+            count: samples,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+        cache: None,
+    })
 }
 
 pub fn init_gpu_resources(
@@ -978,58 +1168,13 @@ pub fn init_gpu_resources(
         push_constant_ranges: &[],
     });
 
-    let game_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
-        label: Some("Game Render Pipeline"),
-        layout: Some(&game_pipeline_layout),
-        vertex: VertexState {
-            module: &game_shader,
-            entry_point: "vs_main",
-            buffers: &[Vertex::desc(), GpuInstance::desc()],
-            compilation_options: PipelineCompilationOptions::default(),
-        },
-        fragment: Some(FragmentState {
-            module: &game_shader,
-            entry_point: "fs_main",
-            targets: &[
-                Some(ColorTargetState {
-                    format: config.format,
-                    blend: Some(BlendState::PREMULTIPLIED_ALPHA_BLENDING),
-                    write_mask: ColorWrites::ALL,
-                }),
-                Some(ColorTargetState {
-                    format: NORMAL_FORMAT,
-                    blend: None,
-                    write_mask: ColorWrites::COLOR,
-                }),
-                Some(ColorTargetState {
-                    format: MODEL_DEPTH_FORMAT,
-                    blend: None,
-                    write_mask: ColorWrites::ALL,
-                }),
-            ],
-            compilation_options: PipelineCompilationOptions::default(),
-        }),
-        primitive: PrimitiveState {
-            topology: PrimitiveTopology::TriangleList,
-            front_face: FrontFace::Ccw,
-            cull_mode: Some(Face::Back),
-            ..Default::default()
-        },
-        depth_stencil: Some(DepthStencilState {
-            format: DEPTH_FORMAT,
-            depth_write_enabled: true,
-            depth_compare: CompareFunction::Less,
-            stencil: Default::default(),
-            bias: Default::default(),
-        }),
-        multisample: MultisampleState {
-            count: 1,
-            mask: !0,
-            alpha_to_coverage_enabled: false,
-        },
-        multiview: None,
-        cache: None,
-    });
+    let game_pipeline = create_game_pipeline(
+        device,
+        &game_pipeline_layout,
+        &game_shader,
+        config.format,
+        1,
+    );
 
     let game_resources = {
         let uniform_buffer = device.create_buffer_init(&BufferInitDescriptor {
@@ -1624,6 +1769,11 @@ pub fn init_gpu_resources(
 
         present_bind_group: None,
         screenshot_bind_group: None,
+
+        samples: 1,
+        render_scale: 1.0,
+        msaa: None,
+        msaa_pipeline: None,
     };
 
     let render = RenderResources {
@@ -1642,6 +1792,7 @@ pub fn init_gpu_resources(
         intermediate_shader,
 
         game_pipeline,
+        game_pipeline_layout,
 
         intermediate_bind_group_layout,
         screenshot_uniform_buffer,
@@ -1672,10 +1823,17 @@ pub fn init_gpu_resources(
 
 pub struct Gpu {
     vsync: bool,
+    present_mode: PresentModeOption,
 
     pub window: Arc<Window>,
 
     pub adapter_info: AdapterInfo,
+    /// whether the device was created with timestamp queries, which the adapter might not support
+    pub timestamps: bool,
+    /// the present modes the surface supports
+    pub present_modes: Vec<PresentMode>,
+    /// the sample counts every target of the world can be multisampled with, including 1
+    pub msaa_samples: Vec<u32>,
     pub instance: Instance,
     pub device: Device,
     pub queue: Queue,
@@ -1684,23 +1842,58 @@ pub struct Gpu {
 }
 
 impl Gpu {
-    fn pick_present_mode(vsync: bool) -> PresentMode {
-        if vsync {
-            PresentMode::AutoVsync
-        } else {
-            PresentMode::AutoNoVsync
+    /// Gets the present mode the option asks for, or None if it is left to the FPS limit.
+    fn chosen_present_mode(option: PresentModeOption) -> Option<PresentMode> {
+        match option {
+            PresentModeOption::Auto => None,
+            PresentModeOption::Fifo => Some(PresentMode::Fifo),
+            PresentModeOption::Mailbox => Some(PresentMode::Mailbox),
+            PresentModeOption::Immediate => Some(PresentMode::Immediate),
+        }
+    }
+
+    /// Picks the chosen present mode if the surface supports it, or else one that vsyncs if asked to.
+    fn pick_present_mode(
+        present_modes: &[PresentMode],
+        vsync: bool,
+        option: PresentModeOption,
+    ) -> PresentMode {
+        match Self::chosen_present_mode(option) {
+            Some(mode) if present_modes.contains(&mode) => mode,
+            _ => {
+                if vsync {
+                    PresentMode::AutoVsync
+                } else {
+                    PresentMode::AutoNoVsync
+                }
+            }
         }
     }
 
-    pub fn set_vsync(&mut self, vsync: bool) {
-        if self.vsync != vsync {
+    pub fn set_present_mode(&mut self, vsync: bool, option: PresentModeOption) {
+        if self.vsync != vsync || self.present_mode != option {
             self.vsync = vsync;
-            self.config.present_mode = Self::pick_present_mode(vsync);
+            self.present_mode = option;
+            self.config.present_mode = Self::pick_present_mode(&self.present_modes, vsync, option);
 
             self.surface.configure(&self.device, &self.config);
         }
     }
 
+    pub fn supports_present_mode(&self, option: PresentModeOption) -> bool {
+        Self::chosen_present_mode(option).map_or(true, |mode| self.present_modes.contains(&mode))
+    }
+
+    /// Gets the most samples up to the requested count that the world can be multisampled with.
+    pub fn supported_samples(&self, requested: u32) -> u32 {
+        self.msaa_samples
+            .iter()
+            .copied()
+            .filter(|samples| *samples <= requested)
+            .max()
+            .unwrap_or(1)
+    }
+
     pub fn resize(
         &mut self,
         shared_resources: &mut SharedResources,
@@ -1737,10 +1930,25 @@ impl Gpu {
             .await
             .unwrap();
 
+        let timestamps = adapter.features().contains(Features::TIMESTAMP_QUERY);
+        // without it, only the sample counts every adapter supports can be used
+        let format_features = adapter
+            .features()
+            .contains(Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+
         let (device, queue) = adapter
             .request_device(
                 &DeviceDescriptor {
-                    required_features: Features::INDIRECT_FIRST_INSTANCE,
+                    required_features: {
+                        let mut features = Features::INDIRECT_FIRST_INSTANCE;
+                        if timestamps {
+                            features |= Features::TIMESTAMP_QUERY;
+                        }
+                        if format_features {
+                            features |= Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+                        }
+                        features
+                    },
                     // WebGL doesn't support all of wgpu's features, so if
                     // we're building for the web we'll have to disable some.
                     required_limits: if cfg!(target_arch = "wasm32") {
@@ -1764,12 +1972,34 @@ impl Gpu {
             .find(|f| f.is_srgb())
             .unwrap_or(surface_caps.formats[0]);
 
+        let present_modes = surface_caps.present_modes.clone();
+        let msaa_samples = [1, 2, 4]
+            .into_iter()
+            .filter(|samples| {
+                *samples == 1
+                    || (format_features
+                        && [
+                            surface_format,
+                            NORMAL_FORMAT,
+                            MSAA_MODEL_DEPTH_FORMAT,
+                            DEPTH_FORMAT,
+                        ]
+                        .into_iter()
+                        .all(|format| {
+                            adapter
+                                .get_texture_format_features(format)
+                                .flags
+                                .sample_count_supported(*samples)
+                        }))
+            })
+            .collect::<Vec<_>>();
+
         let config = SurfaceConfiguration {
             usage: TextureUsages::RENDER_ATTACHMENT,
             format: surface_format,
             width: size.width,
             height: size.height,
-            present_mode: Self::pick_present_mode(vsync),
+            present_mode: Self::pick_present_mode(&present_modes, vsync, PresentModeOption::Auto),
             alpha_mode: surface_caps.alpha_modes[0],
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
@@ -1779,10 +2009,14 @@ impl Gpu {
 
         Gpu {
             vsync,
+            present_mode: PresentModeOption::Auto,
 
             window,
 
             adapter_info: adapter.get_info(),
+            timestamps,
+            present_modes,
+            msaa_samples,
             instance,
             device,
             queue,
@@ -0,0 +1,174 @@
+use crate::event::go_to_bookmark;
+use crate::GameState;
+use automancy_resources::format::Formattable;
+use automancy_system::camera::CameraBookmarks;
+use automancy_system::game::GameSystemMessage;
+use automancy_system::ui_state::TextField;
+use automancy_ui::{button, col, label, movable, row, textbox, window};
+use ractor::rpc::CallResult;
+use std::mem;
+use yakui::widgets::Layer;
+
+/// A change made to the map's bookmarks in the bookmarks window.
+enum BookmarkChange {
+    GoTo(usize),
+    Rename(usize, String),
+    Delete(usize),
+}
+
+fn apply_change(state: &mut GameState, change: BookmarkChange) {
+    if let BookmarkChange::GoTo(slot) = change {
+        return go_to_bookmark(state, slot);
+    }
+
+    let Some(info) = state.loop_store.map_info.as_ref().map(|v| v.0.clone()) else {
+        return;
+    };
+
+    let mut info = info.blocking_lock();
+    let data_ids = &state.resource_man.registry.data_ids;
+    let mut bookmarks = CameraBookmarks::from_data(&info.data, data_ids);
+
+    match change {
+        BookmarkChange::Rename(slot, name) => {
+            if let Some(Some(bookmark)) = bookmarks.slots.get_mut(slot) {
+                if !name.trim().is_empty() {
+                    bookmark.name = name.trim().to_string();
+                }
+            }
+        }
+        BookmarkChange::Delete(slot) => {
+            if let Some(bookmark) = bookmarks.slots.get_mut(slot) {
+                *bookmark = None;
+            }
+        }
+        BookmarkChange::GoTo(_) => {}
+    }
+
+    bookmarks.write_data(&mut info.data, data_ids);
+}
+
+/// Draws the window for managing the map's camera bookmarks, along with what tile is at each of them.
+pub fn bookmarks_window(state: &mut GameState) {
+    let Some(info) = state.loop_store.map_info.as_ref().map(|v| v.0.clone()) else {
+        return;
+    };
+
+    let bookmarks = CameraBookmarks::from_data(
+        &info.blocking_lock().data,
+        &state.resource_man.registry.data_ids,
+    );
+
+    // the tile might have been removed since, in which case the bookmark just shows that nothing is there
+    let bookmarks = bookmarks
+        .slots
+        .into_iter()
+        .enumerate()
+        .flat_map(|(slot, bookmark)| bookmark.map(|v| (slot, v)))
+        .map(|(slot, bookmark)| {
+            let tile = match state.tokio.block_on(state.game.call(
+                |reply| GameSystemMessage::GetTile(bookmark.coord, reply),
+                None,
+            )) {
+                Ok(CallResult::Success(id)) => id,
+                _ => None,
+            };
+
+            (slot, bookmark, state.resource_man.try_tile_name(tile))
+        })
+        .collect::<Vec<_>>();
+
+    let mut change = None;
+
+    Layer::new().show(|| {
+        let mut pos = state.ui_state.bookmarks_ui_position;
+        movable(&mut pos, || {
+            window(
+                state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.bookmarks)
+                    .to_string(),
+                || {
+                    col(|| {
+                        if bookmarks.is_empty() {
+                            label(
+                                &state
+                                    .resource_man
+                                    .gui_str(state.resource_man.registry.gui_ids.lbl_no_bookmarks),
+                            );
+                        }
+
+                        for (slot, bookmark, tile) in &bookmarks {
+                            let slot = *slot;
+
+                            row(|| {
+                                if state.ui_state.renaming_bookmark == Some(slot) {
+                                    let renaming =
+                                        state.ui_state.text_field.get(TextField::BookmarkRenaming);
+
+                                    let res = textbox(renaming, None, None);
+                                    if res.lost_focus || res.activated {
+                                        state.ui_state.renaming_bookmark = None;
+
+                                        change =
+                                            Some(BookmarkChange::Rename(slot, mem::take(renaming)));
+                                    }
+                                } else {
+                                    label(&state.resource_man.gui_fmt(
+                                        state.resource_man.registry.gui_ids.lbl_bookmark,
+                                        [
+                                            ("slot", Formattable::integer(&(slot + 1))),
+                                            ("name", Formattable::display(&bookmark.name)),
+                                            ("tile", Formattable::display(tile)),
+                                            ("coord", Formattable::display(&bookmark.coord)),
+                                        ],
+                                    ));
+
+                                    if button(
+                                        &state.resource_man.gui_str(
+                                            state.resource_man.registry.gui_ids.btn_rename,
+                                        ),
+                                    )
+                                    .clicked
+                                    {
+                                        state
+                                            .ui_state
+                                            .text_field
+                                            .get(TextField::BookmarkRenaming)
+                                            .clone_from(&bookmark.name);
+                                        state.ui_state.renaming_bookmark = Some(slot);
+                                    }
+                                }
+
+                                if button(
+                                    &state
+                                        .resource_man
+                                        .gui_str(state.resource_man.registry.gui_ids.btn_go_to),
+                                )
+                                .clicked
+                                {
+                                    change = Some(BookmarkChange::GoTo(slot));
+                                }
+
+                                if button(
+                                    &state
+                                        .resource_man
+                                        .gui_str(state.resource_man.registry.gui_ids.btn_delete),
+                                )
+                                .clicked
+                                {
+                                    change = Some(BookmarkChange::Delete(slot));
+                                }
+                            });
+                        }
+                    });
+                },
+            );
+        });
+        state.ui_state.bookmarks_ui_position = pos;
+    });
+
+    if let Some(change) = change {
+        apply_change(state, change);
+    }
+}
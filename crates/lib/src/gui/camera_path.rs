@@ -0,0 +1,188 @@
+use crate::event::{record_waypoint, start_camera_playback};
+use crate::GameState;
+use automancy_defs::math::Float;
+use automancy_resources::format::Formattable;
+use automancy_system::camera::CameraPath;
+use automancy_ui::{button, checkbox, col, label, movable, row, window};
+use yakui::widgets::Layer;
+
+/// How much the dwell or transition time of a waypoint changes per click, in seconds.
+const TIME_STEP: Float = 0.5;
+
+/// A change made to the map's camera path in the camera path window.
+enum PathChange {
+    Record,
+    Dwell(usize, Float),
+    Transition(usize, Float),
+    Delete(usize),
+    Looping(bool),
+    Play,
+}
+
+fn apply_change(state: &mut GameState, change: PathChange) {
+    match change {
+        PathChange::Record => return record_waypoint(state),
+        PathChange::Play => return start_camera_playback(state),
+        _ => {}
+    }
+
+    let Some(info) = state.loop_store.map_info.as_ref().map(|v| v.0.clone()) else {
+        return;
+    };
+
+    let mut info = info.blocking_lock();
+    let data_ids = &state.resource_man.registry.data_ids;
+    let mut path = CameraPath::from_data(&info.data, data_ids);
+
+    match change {
+        PathChange::Dwell(index, delta) => {
+            if let Some(waypoint) = path.waypoints.get_mut(index) {
+                waypoint.dwell = (waypoint.dwell + delta).max(0.0);
+            }
+        }
+        PathChange::Transition(index, delta) => {
+            if let Some(waypoint) = path.waypoints.get_mut(index) {
+                waypoint.transition = (waypoint.transition + delta).max(0.0);
+            }
+        }
+        PathChange::Delete(index) => {
+            if index < path.waypoints.len() {
+                path.waypoints.remove(index);
+            }
+        }
+        PathChange::Looping(looping) => path.looping = looping,
+        PathChange::Record | PathChange::Play => {}
+    }
+
+    path.write_data(&mut info.data, data_ids);
+}
+
+/// Draws the window for editing the map's camera path.
+pub fn camera_path_window(state: &mut GameState) {
+    let Some(info) = state.loop_store.map_info.as_ref().map(|v| v.0.clone()) else {
+        return;
+    };
+
+    let path = CameraPath::from_data(
+        &info.blocking_lock().data,
+        &state.resource_man.registry.data_ids,
+    );
+
+    let mut change = None;
+
+    Layer::new().show(|| {
+        let mut pos = state.ui_state.camera_path_ui_position;
+        movable(&mut pos, || {
+            window(
+                state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.camera_path)
+                    .to_string(),
+                || {
+                    col(|| {
+                        row(|| {
+                            if button(
+                                &state
+                                    .resource_man
+                                    .gui_str(state.resource_man.registry.gui_ids.btn_add_waypoint),
+                            )
+                            .clicked
+                            {
+                                change = Some(PathChange::Record);
+                            }
+
+                            if !path.waypoints.is_empty()
+                                && button(&state.resource_man.gui_str(
+                                    state.resource_man.registry.gui_ids.btn_start_playback,
+                                ))
+                                .clicked
+                            {
+                                change = Some(PathChange::Play);
+                            }
+                        });
+
+                        row(|| {
+                            let mut looping = path.looping;
+                            checkbox(&mut looping);
+                            if looping != path.looping {
+                                change = Some(PathChange::Looping(looping));
+                            }
+
+                            label(
+                                &state
+                                    .resource_man
+                                    .gui_str(state.resource_man.registry.gui_ids.lbl_loop),
+                            );
+                        });
+
+                        if path.waypoints.is_empty() {
+                            label(
+                                &state
+                                    .resource_man
+                                    .gui_str(state.resource_man.registry.gui_ids.lbl_no_waypoints),
+                            );
+                        }
+
+                        for (index, waypoint) in path.waypoints.iter().enumerate() {
+                            row(|| {
+                                label(&state.resource_man.gui_fmt(
+                                    state.resource_man.registry.gui_ids.lbl_waypoint,
+                                    [
+                                        ("index", Formattable::integer(&(index + 1))),
+                                        ("coord", Formattable::display(&waypoint.coord)),
+                                    ],
+                                ));
+
+                                label(&state.resource_man.gui_fmt(
+                                    state.resource_man.registry.gui_ids.lbl_dwell,
+                                    [(
+                                        "time",
+                                        Formattable::display(&format!("{:.1}", waypoint.dwell)),
+                                    )],
+                                ));
+                                if button("-").clicked {
+                                    change = Some(PathChange::Dwell(index, -TIME_STEP));
+                                }
+                                if button("+").clicked {
+                                    change = Some(PathChange::Dwell(index, TIME_STEP));
+                                }
+
+                                label(&state.resource_man.gui_fmt(
+                                    state.resource_man.registry.gui_ids.lbl_transition,
+                                    [(
+                                        "time",
+                                        Formattable::display(&format!(
+                                            "{:.1}",
+                                            waypoint.transition
+                                        )),
+                                    )],
+                                ));
+                                if button("-").clicked {
+                                    change = Some(PathChange::Transition(index, -TIME_STEP));
+                                }
+                                if button("+").clicked {
+                                    change = Some(PathChange::Transition(index, TIME_STEP));
+                                }
+
+                                if button(
+                                    &state
+                                        .resource_man
+                                        .gui_str(state.resource_man.registry.gui_ids.btn_delete),
+                                )
+                                .clicked
+                                {
+                                    change = Some(PathChange::Delete(index));
+                                }
+                            });
+                        }
+                    });
+                },
+            );
+        });
+        state.ui_state.camera_path_ui_position = pos;
+    });
+
+    if let Some(change) = change {
+        apply_change(state, change);
+    }
+}
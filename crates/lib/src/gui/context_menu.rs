@@ -0,0 +1,332 @@
+use crate::event::place_tile;
+use crate::GameState;
+use automancy_defs::{coord::TileCoord, id::TileId, log};
+use automancy_resources::data::{Data, DataMap};
+use automancy_system::game::{copy_auxiliary_data, GameSystemMessage};
+use automancy_system::input::ActionType;
+use automancy_system::tile_entity::{TileEntityMsg, TileEntityWithId};
+use automancy_system::ui_state::{ContextMenuEntry, ContextMenuState, TextField};
+use automancy_ui::context_menu;
+use ractor::rpc::CallResult;
+use ractor::ActorRef;
+use std::cell::RefCell;
+use std::time::Instant;
+
+/// The tile a context menu is for, as it was when the menu was opened or the entry was chosen.
+pub struct ContextMenuTarget {
+    pub coord: TileCoord,
+    pub id: TileId,
+    pub entity: ActorRef<TileEntityMsg>,
+    pub data: DataMap,
+}
+
+/// An entry of the tile context menu, shown for the tiles it applies to.
+#[derive(Clone, Copy)]
+pub struct ContextMenuAction {
+    /// the text the entry is shown with
+    pub name: fn(&GameState, &ContextMenuTarget) -> String,
+    /// whether the entry is shown for the tile. entries that would do nothing should not be
+    pub applies: fn(&GameState, &ContextMenuTarget) -> bool,
+    pub run: fn(&mut GameState, &ContextMenuTarget) -> anyhow::Result<()>,
+}
+
+thread_local! {
+    static ACTIONS: RefCell<Vec<ContextMenuAction>> = RefCell::new(builtin_actions());
+}
+
+/// Adds an entry to the tile context menu, after the ones already registered.
+pub fn register_action(action: ContextMenuAction) {
+    ACTIONS.with_borrow_mut(|actions| actions.push(action));
+}
+
+fn builtin_actions() -> Vec<ContextMenuAction> {
+    vec![
+        ContextMenuAction {
+            name: |state, _| {
+                state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.btn_open_config)
+                    .to_string()
+            },
+            applies: |state, target| state.ui_state.config_open_at != Some(target.coord),
+            run: |state, target| {
+                state.ui_state.config_open_at = Some(target.coord);
+                state.ui_state.text_field.get(TextField::Filter).clear();
+
+                Ok(())
+            },
+        },
+        ContextMenuAction {
+            name: |state, _| {
+                state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.btn_copy_settings)
+                    .to_string()
+            },
+            applies: |state, target| !settings(state, target).is_empty(),
+            run: |state, target| {
+                // pasted like copied tiles are, so the settings go onto the tile placed with them
+                state.ui_state.paste_from = Some(target.coord);
+                state.ui_state.paste_content =
+                    vec![(target.coord, target.id, Some(settings(state, target)))];
+
+                state
+                    .audio
+                    .play(&mut state.audio_man, &state.resource_man, "click")?;
+
+                Ok(())
+            },
+        },
+        ContextMenuAction {
+            name: |state, target| {
+                let gui_ids = &state.resource_man.registry.gui_ids;
+
+                state
+                    .resource_man
+                    .gui_str(if protected(state, target) {
+                        gui_ids.btn_unprotect
+                    } else {
+                        gui_ids.btn_protect
+                    })
+                    .to_string()
+            },
+            applies: |state, target| {
+                matches!(
+                    state
+                        .tokio
+                        .block_on(target.entity.call(TileEntityMsg::GetTileConfigUi, None)),
+                    Ok(CallResult::Success(Some(_)))
+                )
+            },
+            run: |state, target| {
+                let id = state.resource_man.registry.data_ids.protected;
+
+                if protected(state, target) {
+                    target.entity.send_message(TileEntityMsg::RemoveData(id))?;
+                } else {
+                    target
+                        .entity
+                        .send_message(TileEntityMsg::SetDataValue(id, Data::Bool(true)))?;
+                }
+
+                Ok(())
+            },
+        },
+        ContextMenuAction {
+            name: |state, _| {
+                state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.btn_remove_tile)
+                    .to_string()
+            },
+            // removing a protected tile would be refused
+            applies: |state, target| !protected(state, target),
+            run: |state, target| {
+                place_tile(
+                    TileId(state.resource_man.registry.none),
+                    target.coord,
+                    None,
+                    false,
+                    false,
+                    state,
+                )
+                .map(|_| ())
+            },
+        },
+    ]
+}
+
+fn protected(state: &GameState, target: &ContextMenuTarget) -> bool {
+    matches!(
+        target
+            .data
+            .get(state.resource_man.registry.data_ids.protected),
+        Some(Data::Bool(true))
+    )
+}
+
+/// The settings of the tile that go along when it is copied.
+fn settings(state: &GameState, target: &ContextMenuTarget) -> DataMap {
+    copy_auxiliary_data(&state.resource_man, &mut target.data.clone())
+}
+
+/// Gets the tile at the coordinate, along with its data.
+fn find_target(state: &GameState, coord: TileCoord) -> Option<ContextMenuTarget> {
+    let Ok(CallResult::Success(Some(id))) = state.tokio.block_on(
+        state
+            .game
+            .call(|reply| GameSystemMessage::GetTile(coord, reply), None),
+    ) else {
+        return None;
+    };
+    let Ok(CallResult::Success(Some(entity))) = state.tokio.block_on(
+        state
+            .game
+            .call(|reply| GameSystemMessage::GetTileEntity(coord, reply), None),
+    ) else {
+        return None;
+    };
+    let Ok(CallResult::Success(data)) = state
+        .tokio
+        .block_on(entity.call(TileEntityMsg::GetData, None))
+    else {
+        return None;
+    };
+
+    Some(ContextMenuTarget {
+        coord,
+        id,
+        entity,
+        data,
+    })
+}
+
+/// Opens the context menu of the pointed-at tile, listing the entries that apply to it. Nothing opens if none do.
+pub fn open(state: &mut GameState, (id, entity): TileEntityWithId) {
+    let coord = state.camera.pointing_at;
+
+    let Ok(CallResult::Success(data)) = state
+        .tokio
+        .block_on(entity.call(TileEntityMsg::GetData, None))
+    else {
+        return;
+    };
+
+    let target = ContextMenuTarget {
+        coord,
+        id,
+        entity,
+        data,
+    };
+
+    let actions = ACTIONS.with_borrow(|actions| actions.clone());
+    let mut entries = actions
+        .into_iter()
+        .enumerate()
+        .filter(|(_, action)| (action.applies)(state, &target))
+        .map(|(index, action)| {
+            (
+                (action.name)(state, &target),
+                ContextMenuEntry::Action(index),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(def) = state.resource_man.registry.tiles.get(&id) {
+        let handles_ui_event = def
+            .function
+            .as_ref()
+            .and_then(|v| state.resource_man.functions.get(v))
+            .is_some_and(|(_, metadata)| metadata.handles_ui_event);
+
+        if handles_ui_event {
+            entries.extend(def.actions.iter().map(|action| {
+                (
+                    state.resource_man.gui_str(action.name).to_string(),
+                    ContextMenuEntry::Custom {
+                        event: action.event,
+                    },
+                )
+            }));
+        }
+    }
+
+    state.ui_state.context_menu = (!entries.is_empty()).then(|| ContextMenuState {
+        coord,
+        entries,
+        highlighted: 0,
+        opened: Instant::now(),
+    });
+}
+
+/// Runs the entry of the open context menu, closing it.
+fn choose(state: &mut GameState, index: usize) {
+    let Some(menu) = state.ui_state.context_menu.take() else {
+        return;
+    };
+    let Some((_, entry)) = menu.entries.get(index) else {
+        return;
+    };
+    // the tile may have changed or gone since the menu opened
+    let Some(target) = find_target(state, menu.coord) else {
+        return;
+    };
+
+    let result = match *entry {
+        ContextMenuEntry::Action(index) => {
+            match ACTIONS.with_borrow(|actions| actions.get(index).copied()) {
+                Some(action) if (action.applies)(state, &target) => (action.run)(state, &target),
+                _ => Ok(()),
+            }
+        }
+        ContextMenuEntry::Custom { event } => target
+            .entity
+            .send_message(TileEntityMsg::UiEvent(event))
+            .map_err(Into::into),
+    };
+
+    if let Err(err) = result {
+        log::warn!("Could not run the context menu entry! Error: {err}");
+    }
+}
+
+/// Handles the input for the open context menu, and returns whether it took the world input.
+///
+/// A click anywhere else closes the menu, as clicks on it are taken by the GUI first.
+pub fn handle_input(state: &mut GameState) -> bool {
+    let Some(menu) = state.ui_state.context_menu.as_mut() else {
+        return false;
+    };
+
+    state.input_hints.push(vec![ActionType::MenuUp]);
+    state.input_hints.push(vec![ActionType::MenuDown]);
+    state.input_hints.push(vec![ActionType::MenuConfirm]);
+
+    let len = menu.entries.len();
+
+    if state.input_handler.key_active(ActionType::MenuUp) {
+        menu.highlighted = (menu.highlighted + len - 1) % len;
+    }
+    if state.input_handler.key_active(ActionType::MenuDown) {
+        menu.highlighted = (menu.highlighted + 1) % len;
+    }
+    if state.input_handler.key_active(ActionType::MenuConfirm) {
+        let highlighted = menu.highlighted;
+        choose(state, highlighted);
+
+        return true;
+    }
+
+    if state.input_handler.main_pressed {
+        state.ui_state.context_menu = None;
+
+        return true;
+    }
+
+    // a right-click elsewhere opens the menu there instead
+    if state.input_handler.alternate_pressed {
+        state.ui_state.context_menu = None;
+
+        return false;
+    }
+
+    true
+}
+
+/// Draws the open context menu.
+pub fn context_menu_ui(state: &mut GameState) {
+    let Some(menu) = &state.ui_state.context_menu else {
+        return;
+    };
+
+    let names = menu
+        .entries
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    let clicked = context_menu(menu.opened, &names, menu.highlighted);
+
+    if let Some(index) = clicked {
+        choose(state, index);
+    }
+}
@@ -1,12 +1,302 @@
+use crate::frame_timing::{CpuStage, FrameTimings, GpuPass, FRAME_HISTORY};
 use crate::GameState;
-use automancy_defs::colors::BACKGROUND_3;
-use automancy_ui::{col, label, movable, window, DIVIER_HEIGHT, DIVIER_THICKNESS};
+use automancy_defs::{
+    colors::{self, BACKGROUND_3},
+    id::{Id, SharedStr},
+    log,
+};
+use automancy_resources::data::Data;
+use automancy_resources::types::translate::FALLBACK_LANGUAGE;
+use automancy_system::actor_stats::{format_stats, ACTOR_STATS};
+use automancy_system::detail::DetailLevel;
+use automancy_system::game::{GameSystemMessage, ScriptUsage, TickRates, TICK_SPEEDS, TPS};
+use automancy_system::sandbox::Sandbox;
+use automancy_system::tile_entity::{TileEntityMsg, TileHistoryEvent};
+use automancy_system::ui_state::{ScriptUsageSort, ToastLevel};
+use automancy_ui::{
+    button, col, label, movable, row, scroll_vertical, window, GraphCanvas, GraphLine,
+    DIVIER_HEIGHT, DIVIER_THICKNESS,
+};
+use ractor::rpc::CallResult;
 use ron::ser::PrettyConfig;
-use yakui::{divider, widgets::Layer};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Write};
+use std::time::Duration;
+use yakui::{divider, widgets::Layer, Vec2};
+
+const SCRIPT_USAGE_CSV_PATH: &str = "script_usage.csv";
+
+/// The height of the frame time sparkline.
+const SPARKLINE_HEIGHT: f32 = 40.0;
+/// How far apart the frames are on the frame time sparkline.
+const SPARKLINE_STEP: f32 = 2.0;
+
+/// A row of the script usage table: the translated name, the ID string, the configured count, and the crafted count.
+type ScriptUsageRow = (SharedStr, String, u64, u64);
+
+fn fetch_script_usage(state: &mut GameState) {
+    match state
+        .tokio
+        .block_on(state.game.call(GameSystemMessage::GetScriptUsage, None))
+    {
+        Ok(CallResult::Success(usage)) => state.loop_store.script_usage = Some(usage),
+        _ => log::warn!("Could not fetch script usage!"),
+    }
+}
+
+fn script_usage_rows(state: &GameState, usage: &ScriptUsage) -> Vec<ScriptUsageRow> {
+    let mut rows = state
+        .resource_man
+        .registry
+        .scripts
+        .keys()
+        .map(|id| {
+            (
+                state.resource_man.script_name(*id),
+                state
+                    .resource_man
+                    .interner
+                    .resolve(*id)
+                    .unwrap_or_default()
+                    .to_string(),
+                usage.configured.get(id).copied().unwrap_or(0),
+                usage.crafted.get(id).copied().unwrap_or(0),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    match state.ui_state.script_usage_sort {
+        ScriptUsageSort::Name => {
+            rows.sort_by(|a, b| a.0.as_ref().cmp(b.0.as_ref()).then_with(|| a.1.cmp(&b.1)))
+        }
+        ScriptUsageSort::Configured => {
+            rows.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.1.cmp(&b.1)))
+        }
+        ScriptUsageSort::Crafted => rows.sort_by(|a, b| b.3.cmp(&a.3).then_with(|| a.1.cmp(&b.1))),
+    }
+
+    rows
+}
+
+fn csv_field(field: &str) -> Cow<str> {
+    if field.contains([',', '"', '\n']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+fn export_script_usage(rows: &[ScriptUsageRow]) -> io::Result<()> {
+    let mut file = File::create(SCRIPT_USAGE_CSV_PATH)?;
+
+    writeln!(file, "name,id,configured,crafted")?;
+
+    for (name, id, configured, crafted) in rows {
+        writeln!(
+            file,
+            "{},{},{configured},{crafted}",
+            csv_field(name.as_ref()),
+            csv_field(id)
+        )?;
+    }
+
+    Ok(())
+}
+
+fn history_event_text(state: &GameState, event: TileHistoryEvent) -> String {
+    match event {
+        TileHistoryEvent::DataChanged { key } => format!(
+            "Data {} changed",
+            state.resource_man.interner.resolve(key).unwrap_or_default()
+        ),
+        TileHistoryEvent::CraftFinished { script } => {
+            format!("Crafted {}", state.resource_man.script_name(script))
+        }
+        TileHistoryEvent::TransferIn { item, amount, from } => format!(
+            "Took in {amount} {} from {from}",
+            state.resource_man.item_name(item)
+        ),
+        TileHistoryEvent::TransferOut { item, amount } => {
+            format!("Sent out {amount} {}", state.resource_man.item_name(item))
+        }
+    }
+}
+
+/// Describes a data value of a tile entity, along with what the schema of its key expects.
+fn data_schema_text(state: &GameState, key: Id, value: &Data) -> String {
+    let name = state.resource_man.interner.resolve(key).unwrap_or_default();
+
+    match state.resource_man.registry.data_schemas.get(&key) {
+        Some(schema) => format!(
+            "{name}: {:?}{}{}",
+            value.kind(),
+            if schema.kind == value.kind() {
+                String::new()
+            } else {
+                format!(" (expected {:?})", schema.kind)
+            },
+            if schema.persisted { "" } else { ", not saved" }
+        ),
+        None => format!("{name}: {:?}, no schema", value.kind()),
+    }
+}
+
+fn format_millis(time: Duration) -> String {
+    format!("{:.2} ms", time.as_secs_f64() * 1000.0)
+}
+
+/// Draws the average time of each part of a frame as a table, the CPU stages first.
+fn frame_timing_table(timings: &FrameTimings) {
+    row(|| {
+        col(|| {
+            for stage in CpuStage::ALL {
+                label(stage.name());
+            }
+            for pass in GpuPass::ALL {
+                label(pass.name());
+            }
+        });
+
+        col(|| {
+            for stage in CpuStage::ALL {
+                label(
+                    &timings.cpu[stage as usize]
+                        .average()
+                        .map_or_else(|| "-".to_string(), format_millis),
+                );
+            }
+            for pass in GpuPass::ALL {
+                if !timings.gpu_supported {
+                    label("unsupported");
+                } else {
+                    label(
+                        &timings.gpu[pass as usize]
+                            .average()
+                            .map_or_else(|| "-".to_string(), format_millis),
+                    );
+                }
+            }
+        });
+    });
+}
+
+/// Draws the times of the latest frames as a line, scaled so that the longest one reaches the top.
+fn frame_time_sparkline(frame_times: &VecDeque<Duration>) {
+    let longest = frame_times.iter().max().copied().unwrap_or_default();
+    let scale = SPARKLINE_HEIGHT / longest.as_secs_f32().max(f32::EPSILON);
+
+    let points = frame_times
+        .iter()
+        .enumerate()
+        .map(|(index, time)| {
+            Vec2::new(
+                index as f32 * SPARKLINE_STEP,
+                SPARKLINE_HEIGHT - time.as_secs_f32() * scale,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut canvas = GraphCanvas::new(Vec2::new(
+        FRAME_HISTORY as f32 * SPARKLINE_STEP,
+        SPARKLINE_HEIGHT,
+    ));
+    canvas.lines = points
+        .windows(2)
+        .map(|pair| GraphLine {
+            from: pair[0],
+            to: pair[1],
+            thickness: 1.0,
+            color: colors::INPUT,
+        })
+        .collect();
+
+    label(&format!(
+        "Frame times, last {} (longest {})",
+        frame_times.len(),
+        format_millis(longest)
+    ));
+    canvas.show(|| {});
+}
 
 /// Draws the debug menu (F3).
 pub fn debugger(state: &mut GameState) {
     let fps = 1.0 / state.loop_store.elapsed.as_secs_f64();
+    let frame_timings = state
+        .renderer
+        .as_ref()
+        .map(|renderer| renderer.frame_timings.clone());
+
+    let tick = match state
+        .tokio
+        .block_on(state.game.call(GameSystemMessage::GetTick, None))
+    {
+        Ok(CallResult::Success(tick)) => Some(tick),
+        _ => None,
+    };
+
+    let tick_stats = match state
+        .tokio
+        .block_on(state.game.call(GameSystemMessage::GetTickStats, None))
+    {
+        Ok(CallResult::Success(stats)) => Some(stats),
+        _ => None,
+    };
+    let mut new_tick_rates = None;
+    let mut set_paused = None;
+    let mut step_ticks = None;
+
+    let actor_stats = ACTOR_STATS.report();
+
+    let pointing_at_entity = state.loop_store.pointing_cache.blocking_lock().clone();
+    let history = pointing_at_entity.as_ref().and_then(|(_, entity)| {
+        match state
+            .tokio
+            .block_on(entity.call(TileEntityMsg::GetHistory, None))
+        {
+            Ok(CallResult::Success(history)) => history,
+            _ => None,
+        }
+    });
+    // newest first, with how many ticks ago each event happened
+    let history_rows = history.as_ref().map(|history| {
+        history
+            .iter()
+            .rev()
+            .map(|(at, event)| {
+                format!(
+                    "{} ticks ago: {}",
+                    tick.unwrap_or(*at).saturating_sub(*at),
+                    history_event_text(state, *event)
+                )
+            })
+            .collect::<Vec<_>>()
+    });
+    let mut toggle_history = false;
+    let mut train_dictionary = false;
+    let mut toggle_readable_maps = false;
+    let mut open_error_log = false;
+    let mut toggle_tile_coords = false;
+    let mut toggle_tick_activity = false;
+    let mut toggle_culling_bounds = false;
+    let mut reload_resources = false;
+    let data_rows = pointing_at_entity
+        .as_ref()
+        .and_then(|(_, entity)| {
+            match state
+                .tokio
+                .block_on(entity.call(TileEntityMsg::GetData, None))
+            {
+                Ok(CallResult::Success(data)) => Some(data),
+                _ => None,
+            }
+        })
+        .map(|data| {
+            data.into_iter()
+                .map(|(key, value)| data_schema_text(state, key, &value))
+                .collect::<Vec<_>>()
+        });
 
     let reg_tiles = state.resource_man.registry.tiles.len();
     let reg_items = state.resource_man.registry.items.len();
@@ -15,12 +305,57 @@ pub fn debugger(state: &mut GameState) {
     let scripts = state.resource_man.registry.scripts.len();
     let audio = state.resource_man.audio.len();
     let meshes = state.resource_man.all_meshes_anims.len();
+    let mesh_bytes = state
+        .resource_man
+        .all_index_ranges
+        .keys()
+        .map(|id| state.resource_man.model_mesh_bytes(id))
+        .sum::<u64>();
+    let models_in_use = state
+        .renderer
+        .as_ref()
+        .map(|renderer| renderer.models_in_use())
+        .unwrap_or_default();
+    let detail_counts = state
+        .renderer
+        .as_ref()
+        .map(|renderer| renderer.detail_counts)
+        .unwrap_or_default();
+    let lod_counts = state
+        .renderer
+        .as_ref()
+        .map(|renderer| renderer.lod_counts.clone())
+        .unwrap_or_default();
+    let world_draws = state
+        .renderer
+        .as_ref()
+        .map(|renderer| renderer.world_draws)
+        .unwrap_or_default();
+    let mesh_bytes_in_use = models_in_use
+        .iter()
+        .map(|id| state.resource_man.model_mesh_bytes(id))
+        .sum::<u64>();
+
+    if state.loop_store.script_usage.is_none() {
+        fetch_script_usage(state);
+    }
+
+    let script_usage_rows = state
+        .loop_store
+        .script_usage
+        .as_ref()
+        .map(|usage| script_usage_rows(state, usage))
+        .unwrap_or_default();
+    let mut refresh_script_usage = false;
+    let mut export_script_usage_clicked = false;
+    let mut script_usage_sort = state.ui_state.script_usage_sort;
 
     let Some((info, map_name)) = &state.loop_store.map_info else {
         return;
     };
 
     let map_info = state.tokio.block_on(info.lock()).clone();
+    let sandbox = Sandbox::from_data(&map_info.data, &state.resource_man.registry.data_ids);
 
     Layer::new().show(|| {
         let mut pos = state.ui_state.player_ui_position;
@@ -32,6 +367,30 @@ pub fn debugger(state: &mut GameState) {
                 || {
                     col(|| {
                         label(&format!("FPS: {fps:.1}"));
+                        row(|| {
+                            if let Some(tick) = tick {
+                                label(&format!("Tick: {tick}"));
+                            }
+
+                            if let Some(stats) = &tick_stats {
+                                if stats.paused {
+                                    label(&format!("Paused ({} steps left)", stats.steps));
+
+                                    if button("Resume").clicked {
+                                        set_paused = Some(false);
+                                    }
+                                } else if button("Pause").clicked {
+                                    set_paused = Some(true);
+                                }
+
+                                if button("Step").clicked {
+                                    step_ticks = Some(1);
+                                }
+                                if button("Step 10").clicked {
+                                    step_ticks = Some(10);
+                                }
+                            }
+                        });
                         label(&format!(
                             "WGPU: {}",
                             ron::ser::to_string_pretty(
@@ -40,15 +399,77 @@ pub fn debugger(state: &mut GameState) {
                             )
                             .unwrap_or("could not format wgpu info".to_string())
                         ));
+                        {
+                            let renderer = state.renderer.as_ref().unwrap();
+
+                            label(&format!(
+                                "World Targets: MSAA={}x RenderScale={:.2} PresentMode={:?}",
+                                renderer.shared_resources.samples(),
+                                renderer.shared_resources.render_scale(),
+                                renderer.gpu.config.present_mode
+                            ));
+                        }
+
+                        if let Some(timings) = &frame_timings {
+                            divider(BACKGROUND_3, DIVIER_HEIGHT, DIVIER_THICKNESS);
+
+                            label("Frame Timing (averaged)");
+                            frame_timing_table(timings);
+                            frame_time_sparkline(&timings.frame_times);
+                        }
 
                         divider(BACKGROUND_3, DIVIER_HEIGHT, DIVIER_THICKNESS);
 
                         label(&format!("ResourceMan: Tiles={reg_tiles} Items={reg_items} Tags={tags} Functions={functions} Scripts={scripts} Audio={audio} Meshes={meshes}"));
+                        label(&format!(
+                            "Now Playing: {} Ambience: {}",
+                            state.audio.now_playing().unwrap_or("-"),
+                            state.audio.ambience().unwrap_or("-")
+                        ));
+                        label(&format!(
+                            "Load Order: {}",
+                            state
+                                .resource_man
+                                .packs
+                                .iter()
+                                .map(|pack| match &pack.manifest {
+                                    Some(v) => format!("{} {}", pack.namespace, v.version),
+                                    None => pack.namespace.clone(),
+                                })
+                                .collect::<Vec<_>>()
+                                .join(" -> ")
+                        ));
+                        label(&format!(
+                            "Meshes: InUse={}/{meshes} ({} / {} KiB)",
+                            models_in_use.len(),
+                            mesh_bytes_in_use / 1024,
+                            mesh_bytes / 1024
+                        ));
+                        label(&format!(
+                            "Detail: High={} Medium={} Low={}",
+                            detail_counts[DetailLevel::High as usize],
+                            detail_counts[DetailLevel::Medium as usize],
+                            detail_counts[DetailLevel::Low as usize]
+                        ));
+                        label(&format!("World Draws: {world_draws}"));
+                        label(&format!(
+                            "LOD Instances: {}",
+                            lod_counts
+                                .iter()
+                                .enumerate()
+                                .map(|(level, count)| format!("{level}={count}"))
+                                .collect::<Vec<_>>()
+                                .join(" ")
+                        ));
 
                         divider(BACKGROUND_3, DIVIER_HEIGHT, DIVIER_THICKNESS);
 
                         label(&format!("Map \"{map_name}\"",));
                         label(&format!("Save Time: {:?}", &map_info.save_time));
+                        label(&format!(
+                            "Sandbox: {} (Free Inputs: {})",
+                            sandbox.enabled, sandbox.free_inputs
+                        ));
                         label(&format!(
                             "Info: {}",
                             &ron::ser::to_string_pretty(
@@ -57,10 +478,321 @@ pub fn debugger(state: &mut GameState) {
                             )
                             .unwrap_or("could not format map info".to_string()),
                         ));
+
+                        if let Some(stats) = &tick_stats {
+                            row(|| {
+                                label(&format!("Speed: {}x", stats.rates.speed_factor()));
+
+                                for speed in TICK_SPEEDS {
+                                    let rates = TickRates {
+                                        speed,
+                                        ..stats.rates
+                                    };
+
+                                    if speed != stats.rates.speed
+                                        && button(&format!("{}x", rates.speed_factor())).clicked
+                                    {
+                                        new_tick_rates = Some(rates);
+                                    }
+                                }
+                            });
+
+                            label("Tick Rates");
+
+                            for (name, logic, rate, frequency) in [
+                                ("Logic", true, stats.rates.logic, stats.logic_frequency),
+                                (
+                                    "Transfer",
+                                    false,
+                                    stats.rates.transfer,
+                                    stats.transfer_frequency,
+                                ),
+                            ] {
+                                let with_rate = |rate| {
+                                    if logic {
+                                        TickRates {
+                                            logic: rate,
+                                            ..stats.rates
+                                        }
+                                    } else {
+                                        TickRates {
+                                            transfer: rate,
+                                            ..stats.rates
+                                        }
+                                    }
+                                };
+
+                                row(|| {
+                                    label(&format!(
+                                        "{name}: {rate}/s (measured {frequency:.1}/s)"
+                                    ));
+
+                                    if button("-").clicked {
+                                        new_tick_rates =
+                                            Some(with_rate(rate.saturating_sub(TPS / 4)));
+                                    }
+                                    if button("+").clicked {
+                                        new_tick_rates = Some(with_rate(rate + TPS / 4));
+                                    }
+                                });
+                            }
+                        }
+
+                        label("Actors");
+                        label(&format!("Game actor: {}", format_stats(&actor_stats.game)));
+                        label(&format!(
+                            "Tile entities ({} sampled): {}",
+                            actor_stats.sampled_tiles,
+                            format_stats(&actor_stats.tiles)
+                        ));
+                        for (coord, stats) in &actor_stats.busiest_tiles {
+                            label(&format!("  {coord}: {}", format_stats(stats)));
+                        }
+
+                        row(|| {
+                            label("Save Dictionary");
+
+                            if button("Train").clicked {
+                                train_dictionary = true;
+                            }
+                        });
+
+                        row(|| {
+                            label("Save Format");
+
+                            let text = if state.options.saving.readable_maps {
+                                "Readable"
+                            } else {
+                                "Binary"
+                            };
+                            if button(text).clicked {
+                                toggle_readable_maps = true;
+                            }
+                        });
+
+                        row(|| {
+                            label("Resource Packs");
+
+                            if button("Reload").clicked {
+                                reload_resources = true;
+                            }
+                        });
+
+                        label(&format!(
+                            "Translations ({}): {} fell back to {FALLBACK_LANGUAGE}",
+                            state.misc_options.language,
+                            state.resource_man.translates.fallen_back.len()
+                        ));
+
+                        row(|| {
+                            label("Errors");
+
+                            if button("Error Log").clicked {
+                                open_error_log = true;
+                            }
+                        });
+
+                        row(|| {
+                            label("In-World");
+
+                            let text = if state.ui_state.show_tile_coords {
+                                "Hide Coordinates"
+                            } else {
+                                "Show Coordinates"
+                            };
+                            if button(text).clicked {
+                                toggle_tile_coords = true;
+                            }
+
+                            let text = if state.ui_state.show_tick_activity {
+                                "Hide Tick Activity"
+                            } else {
+                                "Show Tick Activity"
+                            };
+                            if button(text).clicked {
+                                toggle_tick_activity = true;
+                            }
+
+                            let text = if state.ui_state.show_culling_bounds {
+                                "Hide Culling Bounds"
+                            } else {
+                                "Show Culling Bounds"
+                            };
+                            if button(text).clicked {
+                                toggle_culling_bounds = true;
+                            }
+                        });
+
+                        divider(BACKGROUND_3, DIVIER_HEIGHT, DIVIER_THICKNESS);
+
+                        if let Some(data_rows) = &data_rows {
+                            label("Tile Data");
+
+                            for text in data_rows {
+                                label(text);
+                            }
+
+                            divider(BACKGROUND_3, DIVIER_HEIGHT, DIVIER_THICKNESS);
+                        }
+
+                        row(|| {
+                            label("Tile History");
+
+                            if pointing_at_entity.is_some() {
+                                let text = if history_rows.is_some() {
+                                    "Stop Recording"
+                                } else {
+                                    "Record"
+                                };
+
+                                if button(text).clicked {
+                                    toggle_history = true;
+                                }
+                            }
+                        });
+
+                        if let Some(history_rows) = &history_rows {
+                            if history_rows.is_empty() {
+                                label("Nothing recorded yet");
+                            }
+
+                            scroll_vertical(Vec2::ZERO, Vec2::new(f32::INFINITY, 240.0), || {
+                                col(|| {
+                                    for text in history_rows {
+                                        label(text);
+                                    }
+                                });
+                            });
+                        }
+
+                        divider(BACKGROUND_3, DIVIER_HEIGHT, DIVIER_THICKNESS);
+
+                        row(|| {
+                            label("Script Usage");
+
+                            if button("Refresh").clicked {
+                                refresh_script_usage = true;
+                            }
+                            if button("Export CSV").clicked {
+                                export_script_usage_clicked = true;
+                            }
+                        });
+
+                        row(|| {
+                            label("Sort by:");
+
+                            for (sort, name) in [
+                                (ScriptUsageSort::Name, "Name"),
+                                (ScriptUsageSort::Configured, "Configured"),
+                                (ScriptUsageSort::Crafted, "Crafted"),
+                            ] {
+                                if button(name).clicked {
+                                    script_usage_sort = sort;
+                                }
+                            }
+                        });
+
+                        for (name, id, configured, crafted) in &script_usage_rows {
+                            label(&format!(
+                                "{name} ({id}): Configured={configured} Crafted={crafted}"
+                            ));
+                        }
                     });
                 }
             );
         });
         state.ui_state.player_ui_position = pos;
     });
+    state.ui_state.script_usage_sort = script_usage_sort;
+
+    if open_error_log {
+        state.ui_state.error_log_open = true;
+    }
+
+    if reload_resources {
+        state.loop_store.reload_resources = true;
+    }
+
+    if toggle_tile_coords {
+        state.ui_state.show_tile_coords = !state.ui_state.show_tile_coords;
+    }
+    if toggle_tick_activity {
+        state.ui_state.show_tick_activity = !state.ui_state.show_tick_activity;
+    }
+    if toggle_culling_bounds {
+        state.ui_state.show_culling_bounds = !state.ui_state.show_culling_bounds;
+    }
+
+    if toggle_readable_maps {
+        state.options.saving.readable_maps = !state.options.saving.readable_maps;
+
+        if let Err(err) = state
+            .game
+            .send_message(GameSystemMessage::SetSaveOptions(state.options.saving))
+        {
+            log::warn!("Could not set the map save options! Error: {err}");
+        }
+    }
+
+    if train_dictionary {
+        let text = match state
+            .tokio
+            .block_on(state.game.call(GameSystemMessage::TrainMapDictionary, None))
+        {
+            Ok(CallResult::Success(Some(size))) => {
+                format!("Saved the map with a {} KiB dictionary", size / 1024)
+            }
+            _ => "Could not train a dictionary for this map".to_string(),
+        };
+
+        state.ui_state.push_toast(ToastLevel::Info, text);
+    }
+
+    if let Some(paused) = set_paused {
+        if let Err(err) = state
+            .game
+            .send_message(GameSystemMessage::SetTickPaused(paused))
+        {
+            log::warn!("Could not pause or resume the ticks! Error: {err}");
+        }
+    }
+
+    if let Some(steps) = step_ticks {
+        if let Err(err) = state.game.send_message(GameSystemMessage::StepTick(steps)) {
+            log::warn!("Could not step the ticks! Error: {err}");
+        }
+    }
+
+    if let Some(rates) = new_tick_rates {
+        if let Err(err) = state
+            .game
+            .send_message(GameSystemMessage::SetTickRates(rates))
+        {
+            log::warn!("Could not set the tick rates! Error: {err}");
+        }
+    }
+
+    if toggle_history {
+        if let Some((_, entity)) = &pointing_at_entity {
+            if let Err(err) =
+                entity.send_message(TileEntityMsg::SetHistoryRecording(history.is_none()))
+            {
+                log::warn!("Could not toggle the tile history! Error: {err}");
+            }
+        }
+    }
+
+    if refresh_script_usage {
+        fetch_script_usage(state);
+    }
+
+    if export_script_usage_clicked {
+        match export_script_usage(&script_usage_rows) {
+            Ok(()) => state.ui_state.push_toast(
+                ToastLevel::Info,
+                format!("Exported script usage to {SCRIPT_USAGE_CSV_PATH}"),
+            ),
+            Err(err) => log::error!("Could not export script usage! Error: {err}"),
+        }
+    }
 }
@@ -0,0 +1,230 @@
+use crate::gui::overlay::legend_entry;
+use crate::GameState;
+use automancy_defs::{
+    colors,
+    coord::{TileBounds, TileCoord},
+    glam::vec2,
+    math::{self, Float, FAR, HEX_GRID_LAYOUT},
+    window,
+};
+use automancy_system::game::{GameSystemMessage, TickUnit, TPS};
+use automancy_ui::{small, window_box, GraphCanvas, GraphLine, PADDING_LARGE};
+use ractor::rpc::CallResult;
+use std::f32::consts::PI;
+use yakui::{
+    widgets::{Absolute, Layer, Pad},
+    Alignment, Color, Dim2, Pivot, Vec2,
+};
+
+/// The most coordinates drawn at once, the ones closest to the pointed-at tile first.
+const MAX_COORD_LABELS: usize = 256;
+
+/// How far apart the centers of neighboring tiles have to be on the screen for their coordinates to be drawn.
+/// Closer than this, the labels would cover each other, so only the pointed-at tile is labeled.
+const MIN_LABEL_SPACING: Float = 40.0;
+
+const OUTLINE_THICKNESS: Float = 2.0;
+
+/// How many ticks ago the tiles last did something, with the fewest ticks of each color.
+const ACTIVITY_STEPS: [(TickUnit, Color); 3] = [
+    (0, colors::INPUT),
+    (TPS, colors::ORANGE),
+    (TPS * 5, colors::RED),
+];
+
+/// Gets the color of a tile that last did something the number of ticks ago, or never did.
+fn activity_color(age: Option<TickUnit>) -> Color {
+    let Some(age) = age else {
+        return ACTIVITY_STEPS[ACTIVITY_STEPS.len() - 1].1;
+    };
+
+    ACTIVITY_STEPS
+        .iter()
+        .rev()
+        .find(|(min, _)| age >= *min)
+        .map_or(ACTIVITY_STEPS[0].1, |(_, color)| *color)
+}
+
+/// Draws the coordinates of the tiles in view over them, and outlines the pointed-at tile, if they are shown.
+pub fn tile_coords(state: &mut GameState) {
+    if !state.ui_state.show_tile_coords {
+        return;
+    }
+
+    let size = window::window_size_double(&state.renderer.as_ref().unwrap().gpu.window);
+    let camera_pos = state.camera.get_pos();
+    let rotation = state.camera.get_rotation();
+    let scale_factor = state.gui.as_ref().unwrap().yak.layout_dom().scale_factor();
+    let viewport = state.ui_viewport();
+
+    // in the UI's logical pixels, rather than the window's physical ones
+    let to_screen = |pos: Vec2| {
+        math::world_to_screen(size, pos.extend(FAR), camera_pos, rotation) / scale_factor
+    };
+    let center = |coord: TileCoord| HEX_GRID_LAYOUT.hex_to_world_pos(*coord);
+
+    let pointing_at = state.camera.pointing_at;
+    let pointing_center = center(pointing_at);
+
+    let corners = (0..6)
+        .map(|i| {
+            let angle = PI / 6.0 + i as Float * PI / 3.0;
+
+            to_screen(pointing_center + vec2(angle.cos(), angle.sin()))
+        })
+        .collect::<Vec<_>>();
+
+    let mut canvas = GraphCanvas::new(viewport);
+    canvas.lines = (0..corners.len())
+        .map(|i| GraphLine {
+            from: corners[i],
+            to: corners[(i + 1) % corners.len()],
+            thickness: OUTLINE_THICKNESS,
+            color: colors::ORANGE,
+        })
+        .collect();
+
+    let spacing =
+        to_screen(center(pointing_at + TileCoord::RIGHT)).distance(to_screen(pointing_center));
+
+    let mut coords = if spacing < MIN_LABEL_SPACING {
+        vec![pointing_at]
+    } else {
+        state.camera.culling_range.into_iter().collect::<Vec<_>>()
+    };
+    coords.sort_by_key(|coord| coord.unsigned_distance_to(*pointing_at));
+
+    let labels = coords
+        .into_iter()
+        .map(|coord| (coord, to_screen(center(coord))))
+        .filter(|(_, pos)| {
+            pos.x >= 0.0 && pos.y >= 0.0 && pos.x <= viewport.x && pos.y <= viewport.y
+        })
+        .take(MAX_COORD_LABELS)
+        .collect::<Vec<_>>();
+    canvas.points = labels.iter().map(|(_, pos)| *pos).collect();
+
+    Absolute::new(Alignment::TOP_LEFT, Pivot::TOP_LEFT, Dim2::ZERO).show(|| {
+        Layer::new().show(|| {
+            canvas.show(|| {
+                for (coord, _) in &labels {
+                    small(&coord.to_minimal_string());
+                }
+            });
+        });
+    });
+}
+
+/// Outlines the culling range, which the tiles are loaded and drawn in, and the part of the world on the screen, if they are shown.
+///
+/// The gap between the two is the margin that grows as the camera zooms out.
+pub fn culling_bounds(state: &mut GameState) {
+    if !state.ui_state.show_culling_bounds {
+        return;
+    }
+
+    let TileBounds::Hex(_) = state.camera.culling_range else {
+        return;
+    };
+
+    let size = window::window_size_double(&state.renderer.as_ref().unwrap().gpu.window);
+    let camera_pos = state.camera.get_pos();
+    let rotation = state.camera.get_rotation();
+    let scale_factor = state.gui.as_ref().unwrap().yak.layout_dom().scale_factor();
+
+    // in the UI's logical pixels, rather than the window's physical ones
+    let to_screen = |pos: Vec2| {
+        math::world_to_screen(size, pos.extend(FAR), camera_pos, rotation) / scale_factor
+    };
+    let outline = |corners: Vec<Vec2>, color: Color| {
+        (0..corners.len())
+            .map(|i| GraphLine {
+                from: corners[i],
+                to: corners[(i + 1) % corners.len()],
+                thickness: OUTLINE_THICKNESS,
+                color,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let center = state.camera.culling_range.center();
+    let radius = state.camera.culling_range.radius() as i32;
+    let range_corners = [
+        TileCoord::RIGHT,
+        TileCoord::TOP_RIGHT,
+        TileCoord::TOP_LEFT,
+        TileCoord::LEFT,
+        TileCoord::BOTTOM_LEFT,
+        TileCoord::BOTTOM_RIGHT,
+    ]
+    .into_iter()
+    .map(|direction| to_screen(HEX_GRID_LAYOUT.hex_to_world_pos(*(center + direction * radius))))
+    .collect::<Vec<_>>();
+
+    let (min, max) = math::get_screen_world_bounding_vec(size, camera_pos, rotation);
+    let screen_corners = [min, vec2(max.x, min.y), max, vec2(min.x, max.y)]
+        .into_iter()
+        .map(to_screen)
+        .collect::<Vec<_>>();
+
+    let mut canvas = GraphCanvas::new(state.ui_viewport());
+    canvas.lines = outline(range_corners, colors::ORANGE);
+    canvas.lines.extend(outline(screen_corners, colors::INPUT));
+
+    Absolute::new(Alignment::TOP_LEFT, Pivot::TOP_LEFT, Dim2::ZERO).show(|| {
+        Layer::new().show(|| {
+            canvas.show(|| {});
+        });
+    });
+}
+
+/// Tints the ticking tiles in view by how many ticks ago they last did something, and draws the legend of the colors, if it is shown.
+///
+/// A tile did something on a tick if its tick handler returned a result, so a machine that is stuck waiting stands out in red.
+pub fn tick_activity(state: &mut GameState) {
+    if !state.ui_state.show_tick_activity {
+        return;
+    }
+
+    let culling_range = state.camera.culling_range;
+
+    let Ok(CallResult::Success(tick)) = state
+        .tokio
+        .block_on(state.game.call(GameSystemMessage::GetTick, None))
+    else {
+        return;
+    };
+
+    let Ok(CallResult::Success(activity)) = state.tokio.block_on(state.game.call(
+        |reply| GameSystemMessage::GetTickActivity(culling_range, reply),
+        None,
+    )) else {
+        return;
+    };
+
+    let renderer = state.renderer.as_mut().unwrap();
+    for (coord, active_tick) in activity {
+        let age = active_tick.map(|active_tick| tick.saturating_sub(active_tick));
+
+        renderer
+            .tile_tints
+            .insert(coord, activity_color(age).with_alpha(0.6).to_linear());
+    }
+
+    Absolute::new(Alignment::CENTER_LEFT, Pivot::CENTER_LEFT, Dim2::ZERO).show(|| {
+        Layer::new().show(|| {
+            Pad::all(PADDING_LARGE).show(|| {
+                window_box("Tick Activity".to_string(), || {
+                    for (index, (min, color)) in ACTIVITY_STEPS.iter().enumerate() {
+                        let text = match ACTIVITY_STEPS.get(index + 1) {
+                            Some((next, _)) => format!("{min}-{} ticks ago", next - 1),
+                            None => format!("{min}+ ticks ago, or never"),
+                        };
+
+                        legend_entry(*color, &text);
+                    }
+                });
+            });
+        });
+    });
+}
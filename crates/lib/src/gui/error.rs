@@ -1,7 +1,10 @@
 use crate::GameState;
-use automancy_resources::error::{error_to_key, peek_err, pop_err};
-use automancy_ui::{button, label, row_max, window};
-use yakui::{spacer, widgets::Layer};
+use automancy_resources::error::{
+    clear_error_log, error_log, error_to_key, peek_err, pop_err, LoggedError,
+};
+use automancy_resources::format_time;
+use automancy_ui::{button, col, label, movable, row, row_max, scroll_vertical, window};
+use yakui::{spacer, widgets::Layer, Vec2};
 
 /// Draws an error popup. Can only be called when there are errors in the queue!
 pub fn error_popup(state: &mut GameState) {
@@ -35,3 +38,134 @@ pub fn error_popup(state: &mut GameState) {
         });
     }
 }
+
+/// Puts everything there is to know about the error into text, for pasting into a bug report.
+fn report_text(state: &GameState, entry: &LoggedError) -> String {
+    let mut text = format!(
+        "ID: {}\n{}",
+        error_to_key(entry.id, &state.resource_man),
+        entry.text
+    );
+
+    for (key, value) in &entry.args {
+        text.push_str(&format!("\n{key}: {value}"));
+    }
+
+    text
+}
+
+/// Draws the window listing the latest errors, the newest first, which stay there once their popups are dismissed.
+pub fn error_log_window(state: &mut GameState) {
+    let entries = error_log();
+    let time_fmt = state
+        .resource_man
+        .gui_str(state.resource_man.registry.gui_ids.time_fmt)
+        .to_string();
+
+    let mut copied = None;
+
+    Layer::new().show(|| {
+        let mut pos = state.ui_state.error_log_ui_position;
+        movable(&mut pos, || {
+            window(
+                state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.error_log)
+                    .to_string(),
+                || {
+                    col(|| {
+                        row(|| {
+                            if button(
+                                &state
+                                    .resource_man
+                                    .gui_str(state.resource_man.registry.gui_ids.btn_clear),
+                            )
+                            .clicked
+                            {
+                                clear_error_log();
+                                state.ui_state.error_log_expanded.clear();
+                            }
+
+                            if button(
+                                &state
+                                    .resource_man
+                                    .gui_str(state.resource_man.registry.gui_ids.btn_cancel),
+                            )
+                            .clicked
+                            {
+                                state.ui_state.error_log_open = false;
+                            }
+                        });
+
+                        if entries.is_empty() {
+                            label(
+                                &state
+                                    .resource_man
+                                    .gui_str(state.resource_man.registry.gui_ids.lbl_no_errors),
+                            );
+                        }
+
+                        scroll_vertical(Vec2::ZERO, Vec2::new(f32::INFINITY, 320.0), || {
+                            col(|| {
+                                for entry in entries.iter().rev() {
+                                    let expanded =
+                                        state.ui_state.error_log_expanded.contains(&entry.number);
+
+                                    row(|| {
+                                        if button(if expanded { "-" } else { "+" }).clicked {
+                                            if expanded {
+                                                state
+                                                    .ui_state
+                                                    .error_log_expanded
+                                                    .remove(&entry.number);
+                                            } else {
+                                                state
+                                                    .ui_state
+                                                    .error_log_expanded
+                                                    .insert(entry.number);
+                                            }
+                                        }
+
+                                        label(&format!(
+                                            "[{}] {}",
+                                            format_time(entry.time, &time_fmt),
+                                            entry.text
+                                        ));
+
+                                        if button(
+                                            &state.resource_man.gui_str(
+                                                state.resource_man.registry.gui_ids.btn_copy,
+                                            ),
+                                        )
+                                        .clicked
+                                        {
+                                            copied = Some(report_text(state, entry));
+                                        }
+                                    });
+
+                                    if expanded {
+                                        label(&format!(
+                                            "  ID: {}",
+                                            error_to_key(entry.id, &state.resource_man)
+                                        ));
+
+                                        for (key, value) in &entry.args {
+                                            label(&format!("  {key}: {value}"));
+                                        }
+                                    }
+                                }
+                            });
+                        });
+                    });
+                },
+            );
+        });
+        state.ui_state.error_log_ui_position = pos;
+    });
+
+    if let Some(text) = copied {
+        if let Err(err) = state.renderer.as_mut().unwrap().clipboard.set_text(text) {
+            log::warn!("Could not copy the error to the clipboard: {err}");
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use crate::gui::overlay::fullness_color;
+use crate::GameState;
+use automancy_defs::{
+    colors,
+    glam::vec3,
+    math::{Float, HEX_GRID_LAYOUT},
+};
+use automancy_resources::format::Formattable;
+use automancy_system::flow::{collect_flow_graph, FlowGraph};
+use automancy_ui::{button, col, graph_node, label, movable, row, window, GraphCanvas, GraphLine};
+use std::sync::atomic::Ordering;
+use yakui::{widgets::Layer, Vec2};
+
+/// The size of the area the graph is drawn in.
+const CANVAS_SIZE: Vec2 = Vec2::new(480.0, 320.0);
+
+const NODE_RADIUS: Float = 8.0;
+
+/// The thickness of the edge with the most items flowing along it. The others are thinner by how many fewer items they carry.
+const MAX_EDGE_THICKNESS: Float = 8.0;
+
+/// How many of the tiles of the selected node are listed.
+const LISTED_TILES: usize = 8;
+
+/// Starts building the flow graph again in the background, unless it is already being built.
+pub fn refresh_flow_graph(state: &GameState) {
+    if state
+        .loop_store
+        .flow_graph_updating
+        .swap(true, Ordering::Relaxed)
+    {
+        return;
+    }
+
+    let cache = state.loop_store.flow_graph.clone();
+    let updating = state.loop_store.flow_graph_updating.clone();
+    let game = state.game.clone();
+
+    state.tokio.spawn(async move {
+        if let Some(graph) = collect_flow_graph(game).await {
+            *cache.lock().await = Some(graph);
+        }
+
+        updating.store(false, Ordering::Relaxed);
+    });
+}
+
+/// Fits the positions of the layout into the canvas, keeping the nodes clear of its edges.
+fn canvas_points(graph: &FlowGraph) -> Vec<Vec2> {
+    let min = graph
+        .positions
+        .iter()
+        .fold(Vec2::splat(Float::MAX), |acc, v| acc.min(*v));
+    let max = graph
+        .positions
+        .iter()
+        .fold(Vec2::splat(Float::MIN), |acc, v| acc.max(*v));
+
+    let margin = Vec2::splat(NODE_RADIUS * 2.0);
+    let area = CANVAS_SIZE - margin * 2.0;
+    let extent = max - min;
+
+    graph
+        .positions
+        .iter()
+        .map(|pos| {
+            let fraction = Vec2::new(
+                if extent.x > 0.0 {
+                    (pos.x - min.x) / extent.x
+                } else {
+                    0.5
+                },
+                if extent.y > 0.0 {
+                    (pos.y - min.y) / extent.y
+                } else {
+                    0.5
+                },
+            );
+
+            margin + fraction * area
+        })
+        .collect()
+}
+
+fn graph_lines(graph: &FlowGraph, points: &[Vec2], selected: Option<usize>) -> Vec<GraphLine> {
+    let max_amount = graph.max_amount().max(1) as Float;
+
+    graph
+        .edges
+        .iter()
+        .map(|edge| GraphLine {
+            from: points[edge.from],
+            to: points[edge.to],
+            thickness: 1.0 + (MAX_EDGE_THICKNESS - 1.0) * edge.amount as Float / max_amount,
+            color: if selected == Some(edge.from) {
+                colors::OUTPUT
+            } else if selected == Some(edge.to) {
+                colors::INPUT
+            } else {
+                colors::GRAY
+            },
+        })
+        .collect()
+}
+
+/// Draws the window with the graph of how items flowed between the machines over the last minute.
+///
+/// The machines of the same tile and script are one node, colored by how busy they were. The graph is only built when the window opens or is refreshed.
+pub fn flow_graph_window(state: &mut GameState) {
+    let cache = state.loop_store.flow_graph.clone();
+    let graph = cache.blocking_lock();
+    let updating = state.loop_store.flow_graph_updating.load(Ordering::Relaxed);
+
+    let mut refresh = false;
+    let mut go_to = None;
+
+    Layer::new().show(|| {
+        let mut pos = state.ui_state.flow_graph_ui_position;
+        movable(&mut pos, || {
+            window(
+                state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.flow_graph)
+                    .to_string(),
+                || {
+                    col(|| {
+                        row(|| {
+                            if updating {
+                                label(&state.resource_man.gui_str(
+                                    state.resource_man.registry.gui_ids.lbl_collecting_flows,
+                                ));
+                            } else if button(
+                                &state
+                                    .resource_man
+                                    .gui_str(state.resource_man.registry.gui_ids.btn_refresh),
+                            )
+                            .clicked
+                            {
+                                refresh = true;
+                            }
+                        });
+
+                        let Some(graph) = graph.as_ref().filter(|graph| !graph.nodes.is_empty())
+                        else {
+                            if !updating {
+                                label(
+                                    &state
+                                        .resource_man
+                                        .gui_str(state.resource_man.registry.gui_ids.lbl_no_flows),
+                                );
+                            }
+
+                            return;
+                        };
+
+                        let points = canvas_points(graph);
+
+                        let mut canvas = GraphCanvas::new(CANVAS_SIZE);
+                        canvas.lines =
+                            graph_lines(graph, &points, state.ui_state.flow_graph_selected);
+                        canvas.points = points;
+                        canvas.show(|| {
+                            for (index, node) in graph.nodes.iter().enumerate() {
+                                let mut color = node
+                                    .utilization()
+                                    .map_or(colors::LIGHT_GRAY, fullness_color);
+                                if state.ui_state.flow_graph_selected == Some(index) {
+                                    color = color.adjust(1.3);
+                                }
+
+                                if graph_node(NODE_RADIUS, color).clicked {
+                                    state.ui_state.flow_graph_selected = Some(index);
+                                }
+                            }
+                        });
+
+                        let Some(node) = state
+                            .ui_state
+                            .flow_graph_selected
+                            .and_then(|index| graph.nodes.get(index))
+                        else {
+                            return;
+                        };
+
+                        let name = match node.script {
+                            Some(script) => format!(
+                                "{} ({})",
+                                state.resource_man.tile_name(node.tile),
+                                state.resource_man.script_name(script)
+                            ),
+                            None => state.resource_man.tile_name(node.tile).to_string(),
+                        };
+                        label(&name);
+
+                        label(&state.resource_man.gui_fmt(
+                            state.resource_man.registry.gui_ids.lbl_flow_node,
+                            [
+                                ("count", Formattable::integer(&node.coords.len())),
+                                (
+                                    "utilization",
+                                    Formattable::integer(
+                                        &((node.utilization().unwrap_or(0.0) * 100.0).round()
+                                            as u32),
+                                    ),
+                                ),
+                            ],
+                        ));
+
+                        for coord in node.coords.iter().take(LISTED_TILES) {
+                            row(|| {
+                                label(&coord.to_string());
+
+                                if button(
+                                    &state
+                                        .resource_man
+                                        .gui_str(state.resource_man.registry.gui_ids.btn_go_to),
+                                )
+                                .clicked
+                                {
+                                    go_to = Some(*coord);
+                                }
+                            });
+                        }
+
+                        if node.coords.len() > LISTED_TILES {
+                            label(&format!("+{}", node.coords.len() - LISTED_TILES));
+                        }
+                    });
+                },
+            );
+        });
+        state.ui_state.flow_graph_ui_position = pos;
+    });
+
+    drop(graph);
+
+    if refresh {
+        refresh_flow_graph(state);
+    }
+
+    if let Some(coord) = go_to {
+        let pos = HEX_GRID_LAYOUT.hex_to_world_pos(*coord);
+
+        state
+            .camera
+            .set_raw_pos(vec3(pos.x, pos.y, state.camera.get_raw_pos().z));
+    }
+}
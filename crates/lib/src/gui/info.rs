@@ -1,113 +1,221 @@
+use crate::gui::util::{fluid_levels, storage_fill};
 use crate::GameState;
-use automancy_defs::{colors, glam::vec2, id::TileId, rendering::InstanceData};
-use automancy_resources::{data::DataMap, types::IconMode};
+use automancy_defs::{colors, glam::vec2, id::TileId, math::Float, rendering::InstanceData};
+use automancy_resources::{
+    data::{Data, DataMap},
+    format::Formattable,
+    inventory::InventoryLimits,
+    types::IconMode,
+};
+use automancy_system::gamepad::button_name;
+use automancy_system::input::{
+    get_default_keymap, ActionType, InputDevice, KeyBinding, KeyMap, Modifier,
+};
+use automancy_system::input_hints::ActionHint;
+use automancy_system::production::TileProduction;
+use automancy_system::sandbox::is_sandbox;
+use automancy_system::tile_entity::TileEntityMsg;
 use automancy_ui::{
-    col, col_align_end, colored_label, colored_sized_text, group, label, row, ui_game_object,
-    window_box, UiGameObjectType, LABEL_SIZE, LARGE_ICON_SIZE, PADDING_LARGE,
+    col, colored_label, colored_sized_text, group, label, row, ui_game_object, window_box,
+    UiGameObjectType, LABEL_SIZE, LARGE_ICON_SIZE, PADDING_LARGE,
 };
+use ractor::rpc::CallResult;
 use winit::keyboard::{Key, NamedKey};
 use yakui::{
+    constrained,
     widgets::{Absolute, Layer, Pad},
-    Alignment, Dim2, Pivot,
+    Alignment, Constraints, Dim2, Pivot, Vec2,
 };
 
-#[track_caller]
-fn input_hint_names(state: &mut GameState) {
-    for hint in &state.input_hints {
-        let name = hint
-            .last()
-            .and_then(|action| {
-                state
-                    .input_handler
-                    .key_map
-                    .values()
-                    .find(|v| v.action == *action)
-            })
-            .and_then(|v| v.name);
-
-        if let Some(name) = name.and_then(|name| state.resource_man.translates.keys.get(&name)) {
-            label(name);
-        } else {
-            label(&state.resource_man.translates.unnamed);
+/// How wide the names of the input hints are at most, so that the keys line up next to them.
+const HINT_NAME_WIDTH: Float = 160.0;
+
+/// Gets the name of the hint, which is the name of its last action.
+///
+/// An action that isn't bound to any key is still named, by its default binding.
+fn input_hint_name(state: &GameState, default: &KeyMap, hint: &ActionHint) -> String {
+    let name = hint.actions.last().and_then(|action| {
+        state
+            .input_handler
+            .key_map
+            .iter()
+            .chain(default)
+            .find(|(_, v)| v.action == *action)
+            .and_then(|(_, v)| v.name)
+    });
+
+    name.and_then(|name| state.resource_man.translates.keys.get(&name))
+        .unwrap_or(&state.resource_man.translates.unnamed)
+        .to_string()
+}
+
+/// Gets the name of the key as it is shown to the player.
+pub fn key_display_name(key: &Key) -> String {
+    if let Key::Character(c) = key {
+        c.to_uppercase()
+    } else if let Key::Named(n) = key {
+        match n {
+            NamedKey::Alt => "Alt".to_string(),
+            NamedKey::Control => "Ctrl".to_string(),
+            NamedKey::Shift => "Shift".to_string(),
+            NamedKey::Super => "Super".to_string(),
+            NamedKey::Space => "Space".to_string(),
+            NamedKey::Delete => "Del".to_string(),
+            NamedKey::Backspace => "Backspace".to_string(),
+            NamedKey::Enter => "Enter".to_string(),
+            NamedKey::Escape => "Esc".to_string(),
+            NamedKey::Tab => "Tab".to_string(),
+            NamedKey::F1 => "F1".to_string(),
+            NamedKey::F2 => "F2".to_string(),
+            NamedKey::F3 => "F3".to_string(),
+            NamedKey::F4 => "F4".to_string(),
+            NamedKey::F5 => "F5".to_string(),
+            NamedKey::F6 => "F6".to_string(),
+            NamedKey::F7 => "F7".to_string(),
+            NamedKey::F8 => "F8".to_string(),
+            NamedKey::F9 => "F9".to_string(),
+            NamedKey::F10 => "F10".to_string(),
+            NamedKey::F11 => "F11".to_string(),
+            NamedKey::F12 => "F12".to_string(),
+            NamedKey::Pause => "Pause".to_string(),
+            NamedKey::ArrowLeft => "Left".to_string(),
+            NamedKey::ArrowUp => "Up".to_string(),
+            NamedKey::ArrowDown => "Down".to_string(),
+            NamedKey::ArrowRight => "Right".to_string(),
+            _ => "<?>".to_string(),
         }
+    } else {
+        "<?>".to_string()
+    }
+}
+
+fn modifier_name(modifier: Modifier) -> &'static str {
+    match modifier {
+        Modifier::Shift => "Shift",
+        Modifier::Control => "Ctrl",
+        Modifier::Alt => "Alt",
+        Modifier::Super => "Super",
     }
 }
 
-#[track_caller]
-fn input_hint_keys(state: &mut GameState) {
-    for hint in &state.input_hints {
-        let hint_text = hint
+/// Gets the text of the binding, with its modifiers first.
+pub fn binding_display_name(binding: &KeyBinding) -> String {
+    binding
+        .modifiers
+        .iter()
+        .map(|modifier| modifier_name(*modifier).to_string())
+        .chain([key_display_name(&binding.key)])
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+/// Gets the text of the primary binding of the action.
+pub fn binding_text(state: &GameState, action: ActionType) -> Option<String> {
+    let (binding, _key_action) = state
+        .input_handler
+        .key_map
+        .iter()
+        .find(|(_, v)| v.action == action)?;
+
+    Some(binding_display_name(binding))
+}
+
+/// Gets the button the action is bound to when the gamepad was used last, or else its primary key binding.
+///
+/// An action that has no button is still shown with its key.
+fn action_binding_text(state: &GameState, action: ActionType) -> Option<String> {
+    if state.input_handler.last_device == InputDevice::Gamepad {
+        if let Some((button, _)) = state
+            .input_handler
+            .gamepad_map
             .iter()
-            .flat_map(|action| {
-                if let Some((key, _key_action)) = state
-                    .input_handler
-                    .key_map
-                    .iter()
-                    .find(|(_, v)| v.action == *action)
-                {
-                    if let Key::Character(c) = key {
-                        Some(c.to_uppercase())
-                    } else if let Key::Named(n) = key {
-                        match n {
-                            NamedKey::Alt => Some("Alt".to_string()),
-                            NamedKey::Control => Some("Ctrl".to_string()),
-                            NamedKey::Shift => Some("Shift".to_string()),
-                            NamedKey::Delete => Some("Del".to_string()),
-                            NamedKey::Backspace => Some("Backspace".to_string()),
-                            NamedKey::Enter => Some("Enter".to_string()),
-                            NamedKey::Escape => Some("Esc".to_string()),
-                            NamedKey::Tab => Some("Tab".to_string()),
-                            NamedKey::F1 => Some("F1".to_string()),
-                            NamedKey::F2 => Some("F2".to_string()),
-                            NamedKey::F3 => Some("F3".to_string()),
-                            NamedKey::F4 => Some("F4".to_string()),
-                            NamedKey::F5 => Some("F5".to_string()),
-                            NamedKey::F6 => Some("F6".to_string()),
-                            NamedKey::F7 => Some("F7".to_string()),
-                            NamedKey::F8 => Some("F8".to_string()),
-                            NamedKey::F9 => Some("F9".to_string()),
-                            NamedKey::F10 => Some("F10".to_string()),
-                            NamedKey::F11 => Some("F11".to_string()),
-                            NamedKey::F12 => Some("F12".to_string()),
-                            NamedKey::ArrowLeft => Some("Left".to_string()),
-                            NamedKey::ArrowUp => Some("Up".to_string()),
-                            NamedKey::ArrowDown => Some("Down".to_string()),
-                            NamedKey::ArrowRight => Some("Right".to_string()),
-                            _ => Some("<?>".to_string()),
-                        }
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>()
-            .join(" + ");
-
-        colored_sized_text(&hint_text, colors::GRAY, LABEL_SIZE).show();
+            .find(|(_, v)| v.action == action)
+        {
+            return Some(button_name(*button).to_string());
+        }
     }
+
+    binding_text(state, action)
 }
 
-fn rest_of_the_info(state: &mut GameState) {
-    group(|| {
-        row(|| {
-            col(|| {
-                input_hint_names(state);
-            });
+/// Gets the keys of the hint pressed together, or None if one of its actions isn't bound.
+fn input_hint_keys(state: &GameState, hint: &ActionHint) -> Option<String> {
+    hint.actions
+        .iter()
+        // only the primary binding is shown
+        .map(|action| action_binding_text(state, *action))
+        .collect::<Option<Vec<_>>>()
+        .map(|keys| keys.join(" + "))
+}
+
+/// Draws the input hints, each name next to its keys.
+fn input_hints(state: &GameState) {
+    let default = get_default_keymap(&state.resource_man);
+
+    col(|| {
+        for hint in state.input_hints.hints() {
+            row(|| {
+                constrained(
+                    Constraints {
+                        min: Vec2::new(HINT_NAME_WIDTH, 0.0),
+                        max: Vec2::new(HINT_NAME_WIDTH, Float::INFINITY),
+                    },
+                    || {
+                        label(&input_hint_name(state, &default, &hint));
+                    },
+                );
 
-            col_align_end(|| {
-                input_hint_keys(state);
+                match input_hint_keys(state, &hint) {
+                    Some(keys) => colored_sized_text(&keys, colors::GRAY, LABEL_SIZE).show(),
+                    None => colored_sized_text(
+                        &state
+                            .resource_man
+                            .gui_str(state.resource_man.registry.gui_ids.lbl_unbound_key),
+                        colors::TEXT_INACTIVE,
+                        LABEL_SIZE,
+                    )
+                    .show(),
+                };
             });
-        });
+        }
     });
 }
 
-fn tile_icon(id: TileId) {
+/// Draws how many of each item the tile took in and sent out per minute, over the shortest production window.
+fn tile_rates(state: &GameState, production: &TileProduction) {
+    for rates in &production.items {
+        label(&state.resource_man.gui_fmt(
+            state.resource_man.registry.gui_ids.lbl_tile_rates,
+            [
+                (
+                    "item",
+                    Formattable::display(&state.resource_man.item_name(rates.item)),
+                ),
+                (
+                    "produced",
+                    Formattable::display(&format!("{:.1}", rates.produced[0])),
+                ),
+                (
+                    "consumed",
+                    Formattable::display(&format!("{:.1}", rates.consumed[0])),
+                ),
+            ],
+        ));
+    }
+}
+
+fn rest_of_the_info(state: &mut GameState) {
+    group(|| {
+        input_hints(state);
+    });
+}
+
+/// Draws a tile's icon, the way the info GUI does.
+pub fn tile_icon(id: TileId, size: Float, instance: InstanceData) {
     ui_game_object(
-        InstanceData::default(),
+        instance,
         UiGameObjectType::Tile(id, DataMap::default()),
-        vec2(LARGE_ICON_SIZE, LARGE_ICON_SIZE),
+        vec2(size, size),
         Some(IconMode::Tile.model_matrix()),
         Some(IconMode::Tile.world_matrix()),
     );
@@ -126,7 +234,18 @@ pub fn info_ui(state: &mut GameState) {
                     || {
                         colored_label(&state.camera.pointing_at.to_string(), colors::DARK_GRAY);
 
-                        let Some((tile, _entity)) =
+                        if state.loop_store.map_info.as_ref().is_some_and(|(info, _)| {
+                            is_sandbox(&state.resource_man, &info.blocking_lock().data)
+                        }) {
+                            colored_label(
+                                &state
+                                    .resource_man
+                                    .gui_str(state.resource_man.registry.gui_ids.lbl_sandbox),
+                                colors::ORANGE,
+                            );
+                        }
+
+                        let Some((tile, entity)) =
                             state.loop_store.pointing_cache.blocking_lock().clone()
                         else {
                             label(
@@ -135,7 +254,11 @@ pub fn info_ui(state: &mut GameState) {
                                     .tile_name(TileId(state.resource_man.registry.none)),
                             );
 
-                            tile_icon(TileId(state.resource_man.registry.none));
+                            tile_icon(
+                                TileId(state.resource_man.registry.none),
+                                LARGE_ICON_SIZE,
+                                InstanceData::default(),
+                            );
 
                             rest_of_the_info(state);
 
@@ -144,7 +267,35 @@ pub fn info_ui(state: &mut GameState) {
 
                         label(&state.resource_man.tile_name(tile));
 
-                        tile_icon(tile);
+                        tile_icon(tile, LARGE_ICON_SIZE, InstanceData::default());
+
+                        if let Ok(CallResult::Success(data)) = state
+                            .tokio
+                            .block_on(entity.call(TileEntityMsg::GetData, None))
+                        {
+                            if let Some(Data::Inventory(buffer)) =
+                                data.get(state.resource_man.registry.data_ids.buffer)
+                            {
+                                storage_fill(
+                                    state,
+                                    buffer,
+                                    InventoryLimits::of_tile(&state.resource_man, tile, &data),
+                                );
+                            }
+
+                            if let Some(Data::Inventory(fluids)) =
+                                data.get(state.resource_man.registry.data_ids.fluid_buffer)
+                            {
+                                fluid_levels(state, fluids);
+                            }
+                        }
+
+                        if let Ok(CallResult::Success(production)) = state
+                            .tokio
+                            .block_on(entity.call(TileEntityMsg::GetStats, None))
+                        {
+                            tile_rates(state, &production);
+                        }
 
                         rest_of_the_info(state);
                     },
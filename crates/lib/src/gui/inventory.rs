@@ -0,0 +1,183 @@
+use crate::gui::item::draw_item;
+use crate::GameState;
+use automancy_defs::{
+    id::Id,
+    stack::{ItemAmount, ItemStack},
+};
+use automancy_resources::{data::Data, format::Formattable, ResourceManager};
+use automancy_system::ui_state::{InventorySort, TextField};
+use automancy_ui::{
+    col, interactive, label, movable, radio, row, scroll_vertical, textbox, window,
+    MEDIUM_ICON_SIZE,
+};
+use fuzzy_matcher::FuzzyMatcher;
+use yakui::{widgets::Layer, Vec2};
+
+/// Gets the category whose tiles need the item to be placed, if there is one.
+fn item_category(resource_man: &ResourceManager, item: Id) -> Option<Id> {
+    resource_man
+        .registry
+        .categories
+        .values()
+        .filter(|category| category.item == Some(item))
+        .min_by_key(|category| (category.ord, category.id))
+        .map(|category| category.id)
+}
+
+/// Sorts the items by the sort. Items that are the same by it are sorted by name, then by ID,
+/// so that they don't move around between frames when only their amounts change.
+fn sort_items(resource_man: &ResourceManager, items: &mut [(Id, ItemAmount)], sort: InventorySort) {
+    items.sort_by_cached_key(|(id, _)| (resource_man.item_name(*id).to_string(), *id));
+
+    match sort {
+        InventorySort::Name => {}
+        InventorySort::Amount => items.sort_by(|a, b| b.1.cmp(&a.1)),
+        InventorySort::Category => items.sort_by_cached_key(|(id, _)| {
+            item_category(resource_man, *id)
+                .map(|category| resource_man.registry.categories[&category].ord)
+                .map_or((1, 0), |ord| (0, ord))
+        }),
+    }
+}
+
+/// Draws the window listing every item in the player inventory, filtered by name and sorted by the chosen sort.
+///
+/// Clicking an item that is needed to place the tiles of a category switches the tile selection to that category.
+pub fn inventory_window(state: &mut GameState) {
+    let mut items = match state.loop_store.map_info.as_ref().map(|v| v.0.clone()) {
+        Some(info) => match info
+            .blocking_lock()
+            .data
+            .get(state.resource_man.registry.data_ids.player_inventory)
+        {
+            Some(Data::Inventory(inventory)) => inventory
+                .iter()
+                .filter(|(_, amount)| **amount != 0)
+                .map(|(id, amount)| (*id, *amount))
+                .collect::<Vec<_>>(),
+            _ => vec![],
+        },
+        None => vec![],
+    };
+    let count = items.len();
+
+    let filter = state
+        .ui_state
+        .text_field
+        .get(TextField::InventoryFilter)
+        .clone();
+    if !filter.is_empty() {
+        items.retain(|(id, _)| {
+            state
+                .ui_state
+                .text_field
+                .fuse
+                .fuzzy_match(&state.resource_man.item_name(*id), &filter)
+                .is_some()
+        });
+    }
+
+    sort_items(
+        &state.resource_man,
+        &mut items,
+        state.ui_state.inventory_sort,
+    );
+
+    let mut clicked = None;
+
+    Layer::new().show(|| {
+        let mut pos = state.ui_state.inventory_ui_position;
+        movable(&mut pos, || {
+            window(
+                state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.inventory)
+                    .to_string(),
+                || {
+                    col(|| {
+                        textbox(
+                            state.ui_state.text_field.get(TextField::InventoryFilter),
+                            None,
+                            Some(
+                                &state.resource_man.gui_str(
+                                    state.resource_man.registry.gui_ids.lbl_inventory_filter,
+                                ),
+                            ),
+                        );
+
+                        row(|| {
+                            label(
+                                &state.resource_man.gui_str(
+                                    state.resource_man.registry.gui_ids.lbl_inventory_sort,
+                                ),
+                            );
+
+                            for (sort, name) in [
+                                (
+                                    InventorySort::Name,
+                                    state.resource_man.registry.gui_ids.btn_sort_name,
+                                ),
+                                (
+                                    InventorySort::Amount,
+                                    state.resource_man.registry.gui_ids.btn_sort_amount,
+                                ),
+                                (
+                                    InventorySort::Category,
+                                    state.resource_man.registry.gui_ids.btn_sort_category,
+                                ),
+                            ] {
+                                radio(&mut state.ui_state.inventory_sort, sort, || {
+                                    label(&state.resource_man.gui_str(name));
+                                });
+                            }
+                        });
+
+                        label(&state.resource_man.gui_fmt(
+                            state.resource_man.registry.gui_ids.lbl_inventory_count,
+                            [("count", Formattable::integer(&count))],
+                        ));
+
+                        if items.is_empty() {
+                            label(
+                                &state
+                                    .resource_man
+                                    .gui_str(state.resource_man.registry.gui_ids.lbl_no_items),
+                            );
+
+                            return;
+                        }
+
+                        scroll_vertical(Vec2::ZERO, Vec2::new(f32::INFINITY, 320.0), || {
+                            col(|| {
+                                for (id, amount) in &items {
+                                    let interact = interactive(|| {
+                                        draw_item(
+                                            state,
+                                            || {},
+                                            ItemStack {
+                                                id: *id,
+                                                amount: *amount,
+                                            },
+                                            MEDIUM_ICON_SIZE,
+                                            true,
+                                            true,
+                                        );
+                                    });
+
+                                    if interact.clicked {
+                                        clicked = Some(*id);
+                                    }
+                                }
+                            });
+                        });
+                    });
+                },
+            );
+        });
+        state.ui_state.inventory_ui_position = pos;
+    });
+
+    if let Some(category) = clicked.and_then(|id| item_category(&state.resource_man, id)) {
+        state.ui_state.tile_selection_category = Some(category);
+    }
+}
@@ -1,23 +1,126 @@
 use automancy_defs::math::Float;
 use automancy_defs::rendering::InstanceData;
-use automancy_defs::{glam::vec2, stack::ItemStack};
-use automancy_resources::{types::IconMode, ResourceManager};
-use automancy_ui::{center_row, label, ui_game_object, UiGameObjectType};
+use automancy_defs::{glam::vec2, id::Id, stack::ItemStack};
+use automancy_resources::format::Formattable;
+use automancy_resources::types::IconMode;
+use automancy_ui::{
+    center_row, hoverable, label, label_text, ui_game_object, UiGameObjectType, HOVER_TIP,
+};
 
-/// Draws an Item's icon.
+use crate::GameState;
+
+use super::util::{amount_label, format_amount};
+
+/// How many of the scripts putting out an item its tooltip lists.
+const TOOLTIP_PRODUCERS: usize = 3;
+
+/// Gets the text of the item's tooltip: its name, its ID, the tags it is in, and the first few scripts putting it out.
+fn item_tooltip(state: &GameState, id: Id) -> String {
+    let resource_man = &state.resource_man;
+
+    let mut lines = vec![
+        resource_man.item_name(id).to_string(),
+        resource_man
+            .interner
+            .resolve(id)
+            .unwrap_or_default()
+            .to_string(),
+    ];
+
+    let mut tags = resource_man
+        .registry
+        .tags
+        .values()
+        .filter(|tag| tag.entries.contains(&id))
+        .map(|tag| resource_man.interner.resolve(tag.id).unwrap_or_default())
+        .collect::<Vec<_>>();
+    tags.sort();
+
+    if !tags.is_empty() {
+        lines.push(resource_man.gui_fmt(
+            resource_man.registry.gui_ids.lbl_item_tags,
+            [("tags", Formattable::display(&tags.join(", ")))],
+        ));
+    }
+
+    if let Some(producers) = resource_man.get_scripts_producing(id) {
+        let mut scripts = producers
+            .iter()
+            .take(TOOLTIP_PRODUCERS)
+            .map(|script| resource_man.script_name(*script).to_string())
+            .collect::<Vec<_>>();
+        if producers.len() > TOOLTIP_PRODUCERS {
+            scripts.push("...".to_string());
+        }
+
+        lines.push(resource_man.gui_fmt(
+            resource_man.registry.gui_ids.lbl_item_produced_by,
+            [("scripts", Formattable::display(&scripts.join(", ")))],
+        ));
+    }
+
+    lines.join("\n")
+}
+
+/// Draws an Item's icon. If `tooltip` is set, hovering it shows the item's tooltip.
 pub fn draw_item(
-    resource_man: &ResourceManager,
+    state: &GameState,
     prefix: impl FnOnce(),
     stack: ItemStack,
     size: Float,
     add_label: bool,
+    tooltip: bool,
+) {
+    draw_item_with(
+        state,
+        prefix,
+        stack,
+        size,
+        add_label,
+        tooltip,
+        InstanceData::default(),
+    );
+}
+
+/// Draws an Item's icon with the instance data, such as to dim it.
+pub fn draw_item_with(
+    state: &GameState,
+    prefix: impl FnOnce(),
+    stack: ItemStack,
+    size: Float,
+    add_label: bool,
+    tooltip: bool,
+    instance: InstanceData,
+) {
+    if !tooltip {
+        draw_item_row(state, prefix, stack, size, add_label, instance);
+
+        return;
+    }
+
+    let hover = hoverable(|| {
+        draw_item_row(state, prefix, stack, size, add_label, instance);
+    });
+
+    if hover.hovering {
+        HOVER_TIP.set(Some(label_text(&item_tooltip(state, stack.id))));
+    }
+}
+
+fn draw_item_row(
+    state: &GameState,
+    prefix: impl FnOnce(),
+    stack: ItemStack,
+    size: Float,
+    add_label: bool,
+    instance: InstanceData,
 ) {
     center_row(|| {
         prefix();
 
         ui_game_object(
-            InstanceData::default(),
-            UiGameObjectType::Model(resource_man.item_model_or_missing(&stack.id)),
+            instance,
+            UiGameObjectType::Model(state.resource_man.item_model_or_missing(&stack.id)),
             vec2(size, size),
             Some(IconMode::Item.model_matrix()),
             Some(IconMode::Item.world_matrix()),
@@ -25,13 +128,18 @@ pub fn draw_item(
 
         if add_label {
             if stack.amount > 0 {
-                label(&format!(
-                    "{} ({})",
-                    resource_man.item_name(stack.id),
-                    stack.amount
-                ));
+                let name = state.resource_man.item_name(stack.id);
+
+                match state.resource_man.item_stack_size(stack.id) {
+                    Some(max) => amount_label(state, stack.amount, |amount| {
+                        format!("{name} ({amount} / {})", format_amount(state, max))
+                    }),
+                    None => {
+                        amount_label(state, stack.amount, |amount| format!("{name} ({amount})"))
+                    }
+                }
             } else {
-                label(&resource_man.item_name(stack.id));
+                label(&state.resource_man.item_name(stack.id));
             }
         }
     });
@@ -0,0 +1,82 @@
+use crate::GameState;
+use automancy_defs::log;
+use automancy_system::game::GameSystemMessage;
+use automancy_system::sandbox::Sandbox;
+use automancy_ui::{button, checkbox, col, label, movable, row, window};
+use yakui::widgets::Layer;
+
+/// Sets the sandbox settings of the loaded map, waiting until the map data has them, so that what can be placed is found again from them.
+pub fn set_sandbox(state: &mut GameState, sandbox: Sandbox) {
+    if let Err(err) = state.tokio.block_on(
+        state
+            .game
+            .call(|reply| GameSystemMessage::SetSandbox(sandbox, reply), None),
+    ) {
+        log::warn!("Could not set the sandbox settings! Error: {err}");
+    }
+
+    state.loop_store.invalidate_placeable_tiles();
+}
+
+/// Draws the window for changing the settings of the loaded map.
+pub fn map_settings_window(state: &mut GameState) {
+    let Some(info) = state.loop_store.map_info.as_ref().map(|v| v.0.clone()) else {
+        return;
+    };
+
+    let sandbox = Sandbox::from_data(
+        &info.blocking_lock().data,
+        &state.resource_man.registry.data_ids,
+    );
+    let mut changed = sandbox;
+
+    Layer::new().show(|| {
+        let mut pos = state.ui_state.map_settings_ui_position;
+        movable(&mut pos, || {
+            window(
+                state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.map_settings)
+                    .to_string(),
+                || {
+                    col(|| {
+                        row(|| {
+                            checkbox(&mut changed.enabled);
+
+                            label(
+                                &state
+                                    .resource_man
+                                    .gui_str(state.resource_man.registry.gui_ids.lbl_sandbox),
+                            );
+                        });
+
+                        if changed.enabled {
+                            row(|| {
+                                checkbox(&mut changed.free_inputs);
+
+                                label(&state.resource_man.gui_str(
+                                    state.resource_man.registry.gui_ids.lbl_sandbox_free_inputs,
+                                ));
+                            });
+                        }
+
+                        if button(
+                            &state
+                                .resource_man
+                                .gui_str(state.resource_man.registry.gui_ids.btn_cancel),
+                        )
+                        .clicked
+                        {
+                            state.ui_state.map_settings_open = false;
+                        }
+                    });
+                },
+            );
+        });
+        state.ui_state.map_settings_ui_position = pos;
+    });
+
+    if changed != sandbox {
+        set_sandbox(state, changed);
+    }
+}
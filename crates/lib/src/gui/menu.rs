@@ -1,24 +1,44 @@
-use crate::event::{refresh_maps, shutdown_graceful};
+use crate::event::{check_map_folder, is_map_loaded, refresh_maps, shutdown_graceful};
+use crate::gui::info::binding_display_name;
+use crate::gui::popup;
 use crate::{GameState, VERSION};
-use automancy_defs::{colors::BACKGROUND_3, glam::vec2, log};
+use automancy_defs::{
+    colors::{self, BACKGROUND_3},
+    glam::vec2,
+    log,
+    math::Float,
+};
 use automancy_resources::{
-    error::push_err,
+    error::push_err_toast,
     format::{FormatContext, Formattable},
     format_time,
 };
-use automancy_system::map::{GameMap, LoadMapOption};
-use automancy_system::ui_state::{OptionsMenuState, PopupState, Screen, SubState, TextField};
+use automancy_system::changelog::{read_changelog, released_entries};
+use automancy_system::detail::DetailPreset;
+use automancy_system::input::{find_conflict, get_default_keymap, ActionType, KeyAction, KeyMap};
+use automancy_system::map::archive::ARCHIVE_EXT;
+use automancy_system::map::{GameMap, LoadMapOption, MapError, THUMBNAIL_HEIGHT, THUMBNAIL_WIDTH};
+use automancy_system::sandbox::is_sandbox_raw;
+use automancy_system::trim::{TrimKeep, DEFAULT_TRIM_RADIUS};
+use automancy_system::ui_state::{
+    OptionsMenuState, PopupState, Screen, SubState, TextField, ToastLevel,
+};
 use automancy_system::{
     game::{GameSystemMessage, COULD_NOT_LOAD_ANYTHING},
-    options::UiScale,
+    options::{
+        MsaaSamples, PresentMode, UiScale, BACKUP_COUNTS, COMPRESSION_LEVELS, RENDER_SCALES,
+        ZOOM_LIMITS,
+    },
 };
 use automancy_system::{game_load_map, game_load_map_inner, GameLoadResult};
 use automancy_ui::{
-    button, center_col, center_row, checkbox, col, group, heading, label, pad_x, row,
-    scroll_horizontal_bar_alignment, scroll_vertical, selection_box, slider, stretch_col, textbox,
-    window, DIVIER_HEIGHT, DIVIER_THICKNESS, PADDING_LARGE, PADDING_MEDIUM, PADDING_SMALL,
+    button, center_col, center_row, checkbox, col, colored_label, group, heading, label, pad_x,
+    row, scroll_horizontal_bar_alignment, scroll_vertical, selection_box, slider, stretch_col,
+    textbox, window, RoundRect, DIVIER_HEIGHT, DIVIER_THICKNESS, PADDING_LARGE, PADDING_MEDIUM,
+    PADDING_SMALL, ROUNDED_MEDIUM,
 };
-use std::{fs, mem};
+use rfd::FileDialog;
+use std::mem;
 use winit::event_loop::ActiveEventLoop;
 use yakui::{constrained, divider, image, spacer, widgets::Pad, Constraints, Vec2};
 
@@ -37,6 +57,7 @@ pub fn main_menu(state: &mut GameState, event_loop: &ActiveEventLoop) -> anyhow:
         .clicked
         {
             refresh_maps(state);
+            check_map_folder(state);
             state.ui_state.switch_screen(Screen::MapLoad)
         };
 
@@ -113,6 +134,77 @@ pub fn pause_menu(state: &mut GameState) {
             state.ui_state.switch_screen(Screen::Options)
         };
 
+        if button(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.btn_changelog),
+        )
+        .clicked
+        {
+            state.ui_state.popup =
+                PopupState::Changelog(released_entries(&read_changelog(), VERSION));
+        };
+
+        if button(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.btn_error_log),
+        )
+        .clicked
+        {
+            state.ui_state.error_log_open = true;
+            state.ui_state.switch_screen(Screen::Ingame)
+        };
+
+        if button(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.btn_recipes),
+        )
+        .clicked
+        {
+            state.ui_state.recipes_open = true;
+            state.ui_state.switch_screen(Screen::Ingame)
+        };
+
+        if button(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.btn_inventory),
+        )
+        .clicked
+        {
+            state.ui_state.inventory_open = true;
+            state.ui_state.switch_screen(Screen::Ingame)
+        };
+
+        if button(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.btn_trim_map),
+        )
+        .clicked
+        {
+            state.ui_state.trim_keep = TrimKeep::Radius {
+                center: state.camera.pointing_at,
+                radius: DEFAULT_TRIM_RADIUS,
+            };
+            state.loop_store.trim_preview = None;
+            state.ui_state.trim_open = true;
+            state.ui_state.switch_screen(Screen::Ingame)
+        };
+
+        if button(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.btn_map_settings),
+        )
+        .clicked
+        {
+            state.ui_state.map_settings_open = true;
+            state.ui_state.switch_screen(Screen::Ingame)
+        };
+
         if button(
             &state
                 .resource_man
@@ -139,6 +231,103 @@ pub fn pause_menu(state: &mut GameState) {
     });
 }
 
+/// Draws the thumbnail of a map, or a placeholder if it doesn't have one.
+fn map_thumbnail(state: &GameState, map_name: &str) {
+    let size = vec2(
+        THUMBNAIL_WIDTH as Float / 2.0,
+        THUMBNAIL_HEIGHT as Float / 2.0,
+    );
+
+    if let Some((_, texture)) = state.loop_store.map_thumbnails.get(map_name) {
+        image(*texture, size);
+    } else {
+        let mut placeholder = RoundRect::new(ROUNDED_MEDIUM, BACKGROUND_3);
+        placeholder.min_size = size;
+        placeholder.show();
+    }
+}
+
+/// Formats how long a map has been played for, in hours and minutes.
+fn playtime_text(state: &GameState, playtime: u64) -> String {
+    let minutes = playtime / 60;
+
+    state.resource_man.gui_fmt(
+        state.resource_man.registry.gui_ids.lbl_map_playtime,
+        [
+            ("hours", Formattable::integer(&(minutes / 60))),
+            ("minutes", Formattable::integer(&(minutes % 60))),
+        ],
+    )
+}
+
+/// Asks where to export the map to, and exports it there.
+fn export_map(state: &mut GameState, map_name: &str) {
+    let Some(dest) = FileDialog::new()
+        .add_filter(ARCHIVE_EXT, &[ARCHIVE_EXT])
+        .set_file_name(format!("{map_name}.{ARCHIVE_EXT}"))
+        .save_file()
+    else {
+        return;
+    };
+
+    match GameMap::export(&state.resource_man, map_name, &dest) {
+        Ok(()) => {
+            let text = state.resource_man.gui_fmt(
+                state.resource_man.registry.gui_ids.lbl_map_exported,
+                [("map_name", Formattable::display(&map_name))],
+            );
+
+            state
+                .ui_state
+                .push_toast(ToastLevel::Info, text.to_string());
+        }
+        Err(err) => {
+            log::error!("Could not export map {map_name} to {dest:?}! Error: {err}");
+
+            state.ui_state.push_toast(
+                ToastLevel::Error,
+                state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.lbl_map_export_failed)
+                    .to_string(),
+            );
+        }
+    }
+}
+
+/// Asks for an exported map to import. Maps that reference packs that aren't loaded are asked about before they are imported.
+fn pick_map_import(state: &mut GameState) {
+    let Some(src) = FileDialog::new()
+        .add_filter(ARCHIVE_EXT, &[ARCHIVE_EXT])
+        .pick_file()
+    else {
+        return;
+    };
+
+    let missing = match GameMap::read_archive_manifest(&src) {
+        Ok(manifest) => manifest.missing_packs(&state.resource_man),
+        Err(err) => {
+            log::error!("Could not read the map at {src:?}! Error: {err}");
+
+            state.ui_state.push_toast(
+                ToastLevel::Error,
+                state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.lbl_map_import_failed)
+                    .to_string(),
+            );
+
+            return;
+        }
+    };
+
+    if missing.is_empty() {
+        popup::import_map(state, &src);
+    } else {
+        state.ui_state.popup = PopupState::MapImportConfirmation(src, missing);
+    }
+}
+
 /// Draws the map loading menu.
 pub fn map_menu(state: &mut GameState) {
     window(
@@ -156,8 +345,24 @@ pub fn map_menu(state: &mut GameState) {
 
                         {
                             let infos = mem::take(&mut state.loop_store.map_infos_cache);
-                            for ((_, save_time), map_name) in &infos {
+                            for ((info, save_time), map_name) in &infos {
                                 group(|| {
+                                    map_thumbnail(state, map_name);
+
+                                    if let Some(display_name) = &info.display_name {
+                                        heading(display_name);
+                                    }
+
+                                    // so that a sandbox is never mistaken for a real save
+                                    if is_sandbox_raw(&state.resource_man, &info.data) {
+                                        colored_label(
+                                            &state.resource_man.gui_str(
+                                                state.resource_man.registry.gui_ids.lbl_sandbox,
+                                            ),
+                                            colors::ORANGE,
+                                        );
+                                    }
+
                                     row(|| {
                                         Pad::vertical(PADDING_SMALL).show(|| {
                                             if Some(map_name)
@@ -177,26 +382,21 @@ pub fn map_menu(state: &mut GameState) {
                                                         .filter(|v| v.is_alphanumeric())
                                                         .collect::<String>();
 
-                                                    if fs::rename(
-                                                        GameMap::path(&LoadMapOption::FromSave(
-                                                            map_name.clone(),
-                                                        ))
-                                                        .unwrap(),
-                                                        GameMap::path(&LoadMapOption::FromSave(
-                                                            new_name.clone(),
-                                                        ))
-                                                        .unwrap(),
-                                                    )
-                                                    .is_ok()
+                                                    let result = if is_map_loaded(state, map_name)
                                                     {
-                                                        log::info!(
-                                                            "Renamed map {map_name} to {new_name}"
-                                                        );
-
-                                                        dirty = true;
+                                                        Err(MapError::Loaded(map_name.clone()))
                                                     } else {
-                                                        state.ui_state.popup =
-                                                            PopupState::InvalidName;
+                                                        GameMap::rename(map_name, &new_name)
+                                                    };
+
+                                                    match result {
+                                                        Ok(()) => dirty = true,
+                                                        Err(err) => {
+                                                            log::warn!("Could not rename map {map_name}! Error: {err}");
+
+                                                            state.ui_state.popup =
+                                                                PopupState::InvalidName;
+                                                        }
                                                     }
                                                 }
                                             } else if button(map_name).clicked {
@@ -212,14 +412,40 @@ pub fn map_menu(state: &mut GameState) {
                                     });
 
                                     row(|| {
-                                        if let Some(save_time) = save_time {
-                                            label(&format_time(
-                                                *save_time,
-                                                &state.resource_man.gui_str(
-                                                    state.resource_man.registry.gui_ids.time_fmt,
+                                        col(|| {
+                                            if let Some(save_time) = save_time {
+                                                label(&format_time(
+                                                    *save_time,
+                                                    &state.resource_man.gui_str(
+                                                        state
+                                                            .resource_man
+                                                            .registry
+                                                            .gui_ids
+                                                            .time_fmt,
+                                                    ),
+                                                ));
+                                            }
+
+                                            label(
+                                                &state.resource_man.gui_fmt(
+                                                    state
+                                                        .resource_man
+                                                        .registry
+                                                        .gui_ids
+                                                        .lbl_map_tile_count,
+                                                    [(
+                                                        "count",
+                                                        Formattable::integer(&info.tile_count),
+                                                    )],
                                                 ),
-                                            ));
-                                        }
+                                            );
+
+                                            label(&playtime_text(state, info.playtime));
+
+                                            if let Some(description) = &info.description {
+                                                label(description);
+                                            }
+                                        });
 
                                         spacer(1);
 
@@ -245,6 +471,13 @@ pub fn map_menu(state: &mut GameState) {
                                                     }
                                                 }
                                             }
+                                            if button(&state.resource_man.gui_str(
+                                                state.resource_man.registry.gui_ids.btn_export,
+                                            ))
+                                            .clicked
+                                            {
+                                                export_map(state, map_name);
+                                            }
                                             if button(&state.resource_man.gui_str(
                                                 state.resource_man.registry.gui_ids.btn_delete,
                                             ))
@@ -290,6 +523,16 @@ pub fn map_menu(state: &mut GameState) {
                     state.ui_state.popup = PopupState::MapCreate
                 }
 
+                if button(
+                    &state
+                        .resource_man
+                        .gui_str(state.resource_man.registry.gui_ids.btn_import),
+                )
+                .clicked
+                {
+                    pick_map_import(state);
+                }
+
                 if button(
                     &state
                         .resource_man
@@ -358,6 +601,42 @@ pub fn options_menu_item(state: &mut GameState, menu: OptionsMenuState) {
                 }
             });
 
+            center_row(|| {
+                label(
+                    &state
+                        .resource_man
+                        .gui_str(state.resource_man.registry.gui_ids.options_graphics_detail),
+                );
+
+                state.options.graphics.detail = selection_box(
+                    [DetailPreset::Low, DetailPreset::Medium, DetailPreset::High],
+                    state.options.graphics.detail,
+                    &|v| match v {
+                        DetailPreset::Low => state.resource_man.gui_str(
+                            state
+                                .resource_man
+                                .registry
+                                .gui_ids
+                                .options_graphics_detail_low,
+                        ),
+                        DetailPreset::Medium => state.resource_man.gui_str(
+                            state
+                                .resource_man
+                                .registry
+                                .gui_ids
+                                .options_graphics_detail_medium,
+                        ),
+                        DetailPreset::High => state.resource_man.gui_str(
+                            state
+                                .resource_man
+                                .registry
+                                .gui_ids
+                                .options_graphics_detail_high,
+                        ),
+                    },
+                );
+            });
+
             center_col(|| {
                 label(&format!(
                     "Max FPS: {: >3}",
@@ -385,6 +664,52 @@ pub fn options_menu_item(state: &mut GameState, menu: OptionsMenuState) {
                 checkbox(&mut state.options.graphics.fullscreen);
             });
 
+            center_row(|| {
+                label("MSAA: ");
+
+                let gpu = &state.renderer.as_ref().unwrap().gpu;
+                let supported = MsaaSamples::ALL
+                    .into_iter()
+                    .filter(|v| gpu.msaa_samples.contains(&v.count()))
+                    .collect::<Vec<_>>();
+
+                state.options.graphics.msaa =
+                    selection_box(supported, state.options.graphics.msaa, &|v| match v {
+                        MsaaSamples::Off => "Off".to_string(),
+                        v => format!("{}x", v.count()),
+                    });
+            });
+
+            center_col(|| {
+                label(&format!(
+                    "Render Scale: {: >3}%",
+                    (state.options.graphics.render_scale * 100.0).round() as i32
+                ));
+
+                slider(
+                    &mut state.options.graphics.render_scale,
+                    RENDER_SCALES,
+                    Some(0.05),
+                    |v| v.parse().ok(),
+                    |v| format!("{:.2}", v),
+                );
+            });
+
+            center_row(|| {
+                label("Present Mode: ");
+
+                let gpu = &state.renderer.as_ref().unwrap().gpu;
+                let supported = PresentMode::ALL
+                    .into_iter()
+                    .filter(|v| gpu.supports_present_mode(*v))
+                    .collect::<Vec<_>>();
+
+                state.options.graphics.present_mode =
+                    selection_box(supported, state.options.graphics.present_mode, &|v| {
+                        format!("{v:?}")
+                    });
+            });
+
             /*
             row(|| {
                 label("Antialiasing: ");
@@ -402,61 +727,293 @@ pub fn options_menu_item(state: &mut GameState, menu: OptionsMenuState) {
             }); */
         }
         OptionsMenuState::Audio => {
+            let old = state.options.audio;
+
+            for (name, volume) in [
+                ("Master Volume", &mut state.options.audio.master_volume),
+                ("SFX Volume", &mut state.options.audio.sfx_volume),
+                ("Music Volume", &mut state.options.audio.music_volume),
+            ] {
+                center_col(|| {
+                    label(&format!("{name}: {: >3}%", (*volume * 100.0) as i32));
+
+                    slider(
+                        volume,
+                        0.0..=1.0,
+                        Some(0.01),
+                        |v| v.parse::<f64>().ok().map(|v| v / 100.0),
+                        |v| format!("{: >3}", (v * 100.0) as i32),
+                    );
+                });
+            }
+
+            // heard right away, rather than once the options are confirmed
+            if state.options.audio != old {
+                state
+                    .audio
+                    .apply_options(&mut state.audio_man, &state.options.audio);
+            }
+        }
+        OptionsMenuState::Gui => {
+            center_col(|| {
+                label("Font:");
+
+                let new_font = selection_box(
+                    state.resource_man.fonts.keys().cloned().map(Some),
+                    state.options.gui.get_font(&state.resource_man),
+                    &|font| font.clone().unwrap_or_default(),
+                );
+
+                state.options.gui.set_font(&state.resource_man, new_font);
+            });
+
+            center_col(|| {
+                label("Compact Amounts: ");
+
+                checkbox(&mut state.options.gui.compact_amounts);
+            });
+
+            center_col(|| {
+                label("Animations: ");
+
+                checkbox(&mut state.options.gui.animations);
+            });
+
             center_col(|| {
                 label(&format!(
-                    "SFX Volume: {: >3}%",
-                    (state.options.audio.sfx_volume * 100.0) as i32
+                    "Map Compression: {: >2}",
+                    state.options.saving.compression_level
                 ));
 
                 slider(
-                    &mut state.options.audio.sfx_volume,
-                    0.0..=1.0,
-                    Some(0.01),
-                    |v| v.parse::<f64>().ok().map(|v| v / 100.0),
-                    |v| format!("{: >3}", (v * 100.0) as i32),
+                    &mut state.options.saving.compression_level,
+                    COMPRESSION_LEVELS,
+                    None,
+                    |v| v.parse().ok(),
+                    |v| format!("{: >2}", v),
                 );
             });
 
             center_col(|| {
                 label(&format!(
-                    "Music Volume: {: >3}%",
-                    (state.options.audio.music_volume * 100.0) as i32
+                    "Map Backups: {}",
+                    state.options.saving.backup_count
                 ));
 
                 slider(
-                    &mut state.options.audio.music_volume,
-                    0.0..=1.0,
-                    Some(0.01),
-                    |v| v.parse::<f64>().ok().map(|v| v / 100.0),
-                    |v| format!("{: >3}", (v * 100.0) as i32),
+                    &mut state.options.saving.backup_count,
+                    BACKUP_COUNTS,
+                    None,
+                    |v| v.parse().ok(),
+                    |v| format!("{}", v),
                 );
             });
-        }
-        OptionsMenuState::Gui => {
+
             center_col(|| {
-                label("Font:");
+                label("Language:");
 
-                let new_font = selection_box(
-                    state.resource_man.fonts.keys().cloned().map(Some),
-                    state.options.gui.get_font(&state.resource_man),
-                    &|font| font.clone().unwrap_or_default(),
+                let new_language = selection_box(
+                    state.resource_man.available_languages(),
+                    state.misc_options.language.clone(),
+                    &|language| language.clone(),
                 );
 
-                state.options.gui.set_font(&state.resource_man, new_font);
+                // the translated names, and everything ordered by them, are loaded along with the resources
+                if new_language != state.misc_options.language {
+                    state.misc_options.language = new_language;
+                    state.loop_store.reload_resources = true;
+                }
             });
+        }
+        OptionsMenuState::Controls => {
+            controls_menu(state);
+        }
+    }
+}
 
-            center_col(|| {
-                label("Language:");
+/// How wide the names of the actions in the controls menu are, so that the bindings line up next to them.
+const ACTION_NAME_WIDTH: Float = 180.0;
 
-                label("TODO: UNIMPLEMENTED");
-            });
+/// Gets the name of the action, numbered if several actions share it, like the hotbar slots do.
+fn action_name(state: &GameState, key_action: &KeyAction) -> String {
+    let name = match key_action.name {
+        Some(name) => state
+            .resource_man
+            .translates
+            .keys
+            .get(&name)
+            .unwrap_or(&state.resource_man.translates.unnamed)
+            .to_string(),
+        None => format!("{:?}", key_action.action),
+    };
+
+    match key_action.action {
+        ActionType::Hotbar(index)
+        | ActionType::SelectTile(index)
+        | ActionType::SetBookmark(index)
+        | ActionType::GoToBookmark(index) => {
+            format!("{name} {}", index + 1)
         }
-        OptionsMenuState::Controls => {}
+        _ => name,
+    }
+}
+
+/// Uses the key map for both the input handler and the options, which are saved once the options are confirmed.
+fn apply_key_map(state: &mut GameState, key_map: KeyMap) {
+    state.input_handler.key_map = key_map.clone();
+    state.options.key_bindings = key_map;
+}
+
+/// Replaces the binding being rebound with the key that was pressed, unless the key is already bound to another action.
+fn finish_rebinding(state: &mut GameState) {
+    let Some(index) = state.ui_state.rebinding else {
+        return;
+    };
+
+    let Some(binding) = state.input_handler.take_captured() else {
+        if !state.input_handler.is_capturing() {
+            state.ui_state.rebinding = None;
+        }
+
+        return;
+    };
+
+    state.ui_state.rebinding = None;
+
+    let mut key_map = state.input_handler.key_map.clone();
+    let Some(action) = key_map.get(index).map(|(_, action)| action.action) else {
+        return;
+    };
+    key_map[index].0 = binding;
+
+    if let Some((binding, first, second)) = find_conflict(&key_map) {
+        let other = if first == action { second } else { first };
+        let other_name = key_map
+            .iter()
+            .find(|(_, v)| v.action == other)
+            .map(|(_, v)| action_name(state, v))
+            .unwrap_or_default();
+
+        let text = state.resource_man.gui_fmt(
+            state.resource_man.registry.gui_ids.lbl_binding_conflict,
+            [
+                ("key", Formattable::display(&binding_display_name(binding))),
+                ("action", Formattable::display(&other_name)),
+            ],
+        );
+        state.ui_state.push_toast(ToastLevel::Warning, text);
+
+        return;
+    }
+
+    apply_key_map(state, key_map);
+}
+
+/// Draws the bindings of every action, which are rebound by clicking them and pressing the new key.
+fn controls_menu(state: &mut GameState) {
+    finish_rebinding(state);
+
+    let camera = &mut state.options.camera;
+
+    center_col(|| {
+        label(&state.resource_man.gui_fmt(
+            state.resource_man.registry.gui_ids.options_camera_min_zoom,
+            [(
+                "zoom",
+                Formattable::display(&format!("{:.2}", camera.min_zoom)),
+            )],
+        ));
+
+        slider(
+            &mut camera.min_zoom,
+            *ZOOM_LIMITS.start()..=camera.max_zoom,
+            Some(0.05),
+            |v| v.parse().ok(),
+            |v| format!("{:.2}", v),
+        );
+    });
+
+    center_col(|| {
+        label(&state.resource_man.gui_fmt(
+            state.resource_man.registry.gui_ids.options_camera_max_zoom,
+            [(
+                "zoom",
+                Formattable::display(&format!("{:.2}", camera.max_zoom)),
+            )],
+        ));
+
+        slider(
+            &mut camera.max_zoom,
+            camera.min_zoom..=*ZOOM_LIMITS.end(),
+            Some(0.05),
+            |v| v.parse().ok(),
+            |v| format!("{:.2}", v),
+        );
+    });
+
+    center_col(|| {
+        label(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.options_camera_rotation),
+        );
+
+        checkbox(&mut camera.rotation);
+    });
+
+    let key_map = state.input_handler.key_map.clone();
+
+    for (index, (binding, key_action)) in key_map.iter().enumerate() {
+        row(|| {
+            constrained(
+                Constraints {
+                    min: Vec2::new(ACTION_NAME_WIDTH, 0.0),
+                    max: Vec2::new(ACTION_NAME_WIDTH, Float::INFINITY),
+                },
+                || {
+                    label(&action_name(state, key_action));
+                },
+            );
+
+            let text = if state.ui_state.rebinding == Some(index) {
+                state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.lbl_press_key)
+                    .to_string()
+            } else {
+                binding_display_name(binding)
+            };
+
+            if button(&text).clicked {
+                state.ui_state.rebinding = Some(index);
+                state.input_handler.start_capture();
+            }
+        });
+    }
+
+    if button(
+        &state
+            .resource_man
+            .gui_str(state.resource_man.registry.gui_ids.btn_reset_defaults),
+    )
+    .clicked
+    {
+        state.ui_state.rebinding = None;
+        state.input_handler.stop_capture();
+
+        apply_key_map(state, get_default_keymap(&state.resource_man));
     }
 }
 
 /// Draws the options menu.
 pub fn options_menu(state: &mut GameState) {
+    // the key pressed next is only captured while the controls are shown
+    if state.ui_state.substate != SubState::Options(OptionsMenuState::Controls)
+        && state.ui_state.rebinding.take().is_some()
+    {
+        state.input_handler.stop_capture();
+    }
+
     window(
         state
             .resource_man
@@ -558,7 +1115,7 @@ pub fn options_menu(state: &mut GameState) {
                     .clicked
                     {
                         if state.options.save().is_err() {
-                            push_err(
+                            push_err_toast(
                                 state.resource_man.registry.err_ids.unwritable_options,
                                 &FormatContext::from([].into_iter()),
                                 &state.resource_man,
@@ -566,13 +1123,16 @@ pub fn options_menu(state: &mut GameState) {
                         }
 
                         if state.misc_options.save().is_err() {
-                            push_err(
+                            push_err_toast(
                                 state.resource_man.registry.err_ids.unwritable_options,
                                 &FormatContext::from([].into_iter()),
                                 &state.resource_man,
                             );
                         }
 
+                        state.ui_state.rebinding = None;
+                        state.input_handler.stop_capture();
+
                         state.ui_state.return_screen();
                     }
                 });
@@ -0,0 +1,157 @@
+use crate::GameState;
+use automancy_defs::{
+    colors,
+    coord::TileBounds,
+    glam::{vec2, vec3},
+    math::{self, Float, HEX_GRID_LAYOUT, SQRT_3},
+    window,
+};
+use automancy_system::game::GameSystemMessage;
+use automancy_ui::{GraphLine, Minimap, MinimapCell, PADDING_LARGE};
+use ractor::rpc::CallResult;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use yakui::{
+    widgets::{Absolute, Layer, Pad},
+    Alignment, Dim2, Pivot, Vec2,
+};
+
+/// The width and height of the minimap.
+const MINIMAP_SIZE: Float = 200.0;
+
+/// How many tiles out from the center of the screen the minimap shows.
+const MINIMAP_RADIUS: u32 = 48;
+
+/// How often the tiles on the minimap are fetched again.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+const OUTLINE_THICKNESS: Float = 1.5;
+
+/// Fetches the tiles around the center in the background, unless they were fetched recently or are being fetched.
+fn refresh_tiles(state: &mut GameState, bounds: TileBounds) {
+    if state
+        .loop_store
+        .minimap_refreshed
+        .is_some_and(|v| v.elapsed() < REFRESH_INTERVAL)
+        || state
+            .loop_store
+            .minimap_updating
+            .swap(true, Ordering::Relaxed)
+    {
+        return;
+    }
+
+    state.loop_store.minimap_refreshed = Some(Instant::now());
+
+    let cache = state.loop_store.minimap_tiles.clone();
+    let updating = state.loop_store.minimap_updating.clone();
+    let game = state.game.clone();
+
+    state.tokio.spawn(async move {
+        if let Ok(CallResult::Success(tiles)) = game
+            .call(
+                |reply| GameSystemMessage::GetTilesInBounds(bounds, reply),
+                None,
+            )
+            .await
+        {
+            *cache.lock().await = tiles;
+        }
+
+        updating.store(false, Ordering::Relaxed);
+    });
+}
+
+/// Draws the tiles around the center of the screen in the corner, colored by their category, along with the outline of what the screen shows.
+///
+/// The minimap is turned the same way as the screen. Clicking it moves the camera there.
+pub fn minimap(state: &mut GameState) {
+    let size = window::window_size_double(&state.renderer.as_ref().unwrap().gpu.window);
+    let camera_pos = state.camera.get_pos();
+    let rotation = state.camera.get_rotation();
+
+    let to_world = |normalized: Vec2| {
+        math::normalized_to_world(size, normalized, camera_pos, rotation).truncate()
+    };
+
+    let center = to_world(Vec2::ZERO);
+    let top_left = to_world(vec2(-1.0, -1.0));
+    let top_right = to_world(vec2(1.0, -1.0));
+    let bottom_right = to_world(vec2(1.0, 1.0));
+    let bottom_left = to_world(vec2(-1.0, 1.0));
+
+    // which way the screen's right and down are in the world, so that the minimap is turned the same way
+    let right = (top_right - top_left).normalize_or_zero();
+    let down = (bottom_left - top_left).normalize_or_zero();
+
+    let scale = MINIMAP_SIZE / 2.0 / (MINIMAP_RADIUS as Float * SQRT_3);
+    let half = Vec2::splat(MINIMAP_SIZE / 2.0);
+
+    let to_minimap = |world: Vec2| {
+        let offset = world - center;
+
+        half + vec2(offset.dot(right), offset.dot(down)) * scale
+    };
+
+    refresh_tiles(
+        state,
+        TileBounds::new(
+            HEX_GRID_LAYOUT.world_pos_to_hex(center).into(),
+            MINIMAP_RADIUS,
+        ),
+    );
+
+    let mut minimap = Minimap::new(Vec2::splat(MINIMAP_SIZE), (scale * SQRT_3).max(1.0));
+    minimap.background = colors::BACKGROUND_1;
+
+    minimap.cells = state
+        .loop_store
+        .minimap_tiles
+        .blocking_lock()
+        .iter()
+        .map(|(coord, id)| MinimapCell {
+            pos: to_minimap(HEX_GRID_LAYOUT.hex_to_world_pos(**coord)),
+            color: state
+                .resource_man
+                .registry
+                .tiles
+                .get(id)
+                .and_then(|tile| tile.category)
+                .map_or(colors::GRAY, |category| {
+                    state.resource_man.category_color(category)
+                }),
+        })
+        .collect();
+
+    // the corners are clamped to the minimap, which cuts the outline down to the part that is on it
+    let corners = [top_left, top_right, bottom_right, bottom_left]
+        .map(|corner| to_minimap(corner).clamp(Vec2::ZERO, Vec2::splat(MINIMAP_SIZE)));
+
+    minimap.lines = (0..corners.len())
+        .map(|index| GraphLine {
+            from: corners[index],
+            to: corners[(index + 1) % corners.len()],
+            thickness: OUTLINE_THICKNESS,
+            color: colors::WHITE,
+        })
+        .collect();
+
+    let mut clicked = None;
+
+    Absolute::new(Alignment::BOTTOM_LEFT, Pivot::BOTTOM_LEFT, Dim2::ZERO).show(|| {
+        Layer::new().show(|| {
+            Pad::all(PADDING_LARGE).show(|| {
+                clicked = minimap.show().clicked;
+            });
+        });
+    });
+
+    if let Some(local) = clicked {
+        let offset = (local - half) / scale;
+        let pos = center + right * offset.x + down * offset.y;
+
+        state
+            .camera
+            .set_raw_pos(vec3(pos.x, pos.y, state.camera.get_raw_pos().z));
+    }
+}
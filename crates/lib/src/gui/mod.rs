@@ -4,25 +4,45 @@ use automancy_defs::id::ModelId;
 use automancy_defs::rendering::InstanceData;
 use automancy_defs::{colors, math, rendering::make_line, window};
 use automancy_defs::{
-    math::{Float, Matrix4, FAR, HEX_GRID_LAYOUT},
+    math::{Matrix4, FAR, HEX_GRID_LAYOUT},
     rendering::GameMatrix,
 };
-use automancy_resources::data::DataMap;
+use automancy_system::favorites::toggle_favorite;
+use automancy_system::game::GameSystemMessage;
+use automancy_system::hotbar::{read_hotbar, write_hotbar};
 use automancy_system::input::ActionType;
-use automancy_system::ui_state::{PopupState, Screen};
-use tokio::sync::oneshot;
+use automancy_system::ui_state::{PopupState, Screen, TextField};
+use automancy_ui::{closing_windows, ANIMATIONS_ENABLED};
+use ractor::rpc::CallResult;
+use std::mem;
+use tile_selection::SelectionResponse;
 use util::render_overlay_cached;
 use winit::event_loop::ActiveEventLoop;
 
+pub mod bookmarks;
+pub mod camera_path;
+pub mod context_menu;
 pub mod debug;
+pub mod debug_overlay;
 pub mod error;
+pub mod flow_graph;
 pub mod info;
+pub mod inventory;
 pub mod item;
+pub mod map_settings;
 pub mod menu;
+pub mod minimap;
+pub mod overlay;
+pub mod placement;
 pub mod player;
 pub mod popup;
+pub mod production;
+pub mod recipes;
+pub mod regions;
+pub mod research_tree;
 pub mod tile_config;
 pub mod tile_selection;
+pub mod trim;
 pub mod util;
 
 pub fn render_ui(
@@ -30,31 +50,60 @@ pub fn render_ui(
     result: &mut anyhow::Result<bool>,
     event_loop: &ActiveEventLoop,
 ) {
+    ANIMATIONS_ENABLED.set(state.options.gui.animations);
+    state.input_hints.new_frame();
+    // kept only while the tile selection is drawn
+    state.ui_state.hovered_selection_tile = None;
+    if !state.ui_state.tile_selection_shown {
+        state.ui_state.text_field.get(TextField::TileSearch).clear();
+    }
+    state.ui_state.tile_selection_shown = false;
+
+    // only the world is shown while the camera flies along a path
+    if state.ui_state.camera_playback.is_some() {
+        return;
+    }
+
     if state.ui_state.popup == PopupState::None {
         match state.ui_state.screen {
             Screen::Ingame => {
                 // tile_info
                 info::info_ui(state);
 
+                // the map overlay's legend, as the renderer colors the tiles for it under the other tints
+                overlay::overlay(state);
+
+                // region outlines
+                overlay::regions(state);
+
+                // tick activity tints over the region outlines, and the tile coordinates and culling bounds over the world
+                debug_overlay::tick_activity(state);
+                debug_overlay::tile_coords(state);
+                debug_overlay::culling_bounds(state);
+
                 if !state.input_handler.key_active(ActionType::ToggleGui) {
                     if let Some(map_info) = state.loop_store.map_info.as_ref().map(|v| v.0.clone())
                     {
                         let mut lock = map_info.blocking_lock();
                         let game_data = &mut lock.data;
 
-                        let (selection_send, selection_recv) = oneshot::channel();
-
                         // tile_selections
-                        tile_selection::tile_selections(state, game_data, selection_send);
-
-                        if let Ok(id) = selection_recv.blocking_recv() {
-                            state.ui_state.already_placed_at = None;
-
-                            if state.ui_state.selected_tile_id == Some(id) {
-                                state.ui_state.selected_tile_id = None;
-                            } else {
-                                state.ui_state.selected_tile_id = Some(id);
+                        match tile_selection::tile_selections(state, game_data) {
+                            Some(SelectionResponse::Select(id)) => {
+                                tile_selection::select_tile(state, id)
+                            }
+                            Some(SelectionResponse::Hotbar(slot)) => {
+                                tile_selection::use_hotbar_slot(state, game_data, slot)
                             }
+                            Some(SelectionResponse::ClearHotbar(slot)) => {
+                                let mut hotbar = read_hotbar(&state.resource_man, game_data);
+                                hotbar[slot] = None;
+                                write_hotbar(&state.resource_man, game_data, &hotbar);
+                            }
+                            Some(SelectionResponse::ToggleFavorite(id)) => {
+                                toggle_favorite(&state.resource_man, game_data, id)
+                            }
+                            None => {}
                         }
 
                         player::player(state, game_data);
@@ -63,25 +112,29 @@ pub fn render_ui(
                         tile_config::tile_config_ui(state, game_data);
                     }
 
+                    // the game locks the map info to take the next research, so this waits until it's unlocked here
+                    if mem::take(&mut state.loop_store.advance_research_queue) {
+                        if let Ok(CallResult::Success(Some(id))) = state.tokio.block_on(
+                            state
+                                .game
+                                .call(GameSystemMessage::AdvanceResearchQueue, None),
+                        ) {
+                            player::select_research(state, id);
+                        }
+                    }
+
                     let cursor_pos = math::screen_to_world(
                         window::window_size_double(&state.renderer.as_ref().unwrap().gpu.window),
                         state.input_handler.main_pos,
                         state.camera.get_pos(),
+                        state.camera.get_rotation(),
                     );
 
-                    render_overlay_cached(
-                        &state.resource_man,
-                        state.renderer.as_mut().unwrap(),
-                        state.ui_state.selected_tile_id,
-                        DataMap::default(),
-                        &mut state.ui_state.selected_tile_render_cache,
-                        Matrix4::from_translation(vec3(
-                            cursor_pos.x as Float,
-                            cursor_pos.y as Float,
-                            FAR,
-                        )),
-                        state.camera.get_matrix(),
-                    );
+                    if state.input_handler.key_active(ActionType::Minimap) {
+                        minimap::minimap(state);
+                    }
+
+                    context_menu::context_menu_ui(state);
 
                     if let Some((coord, ..)) = state.ui_state.linking_tile {
                         state.renderer.as_mut().unwrap().overlay_instances.push((
@@ -123,15 +176,58 @@ pub fn render_ui(
         PopupState::InvalidName => {
             popup::invalid_name_popup(state);
         }
+        PopupState::MissingIds(missing) => {
+            popup::missing_ids_popup(state, &missing);
+        }
+        PopupState::MapFolderIssues(entries) => {
+            popup::map_folder_issues_popup(state, &entries);
+        }
+        PopupState::MapRepairConfirmation(repair, paths) => {
+            popup::map_repair_popup(state, repair, &paths);
+        }
+        PopupState::Changelog(entries) => {
+            popup::changelog_popup(state, &entries);
+        }
+        PopupState::TrimConfirmation(count) => {
+            trim::trim_confirmation_popup(state, count);
+        }
+        PopupState::AutosaveFallback(map_name) => {
+            popup::autosave_fallback_popup(state, &map_name);
+        }
+        PopupState::MapImportConfirmation(src, missing) => {
+            popup::map_import_popup(state, &src, &missing);
+        }
     }
 
+    closing_windows();
+
     util::render_info_tip(state);
 
+    util::render_toasts(state);
+
+    let terrain = state
+        .loop_store
+        .map_info
+        .as_ref()
+        .map(|v| v.0.blocking_lock().terrain.clone())
+        .unwrap_or_default();
+
+    // the preview is shown in a deeper red where the terrain doesn't allow the tile
+    let invalid_terrain = state
+        .ui_state
+        .selected_tile_id
+        .is_some_and(|id| !terrain.can_place(&state.resource_man, id, state.camera.pointing_at));
     state.renderer.as_mut().unwrap().tile_tints.insert(
         state.camera.pointing_at,
-        colors::RED.with_alpha(0.2).to_linear(),
+        colors::RED
+            .with_alpha(if invalid_terrain { 0.6 } else { 0.2 })
+            .to_linear(),
     );
 
+    placement::placement_ghost(state);
+
+    placement::placement_flashes(state);
+
     for coord in &state.ui_state.grouped_tiles {
         state
             .renderer
@@ -141,6 +237,8 @@ pub fn render_ui(
             .insert(*coord, colors::ORANGE.with_alpha(0.4).to_linear());
     }
 
+    trim::trim_feedback(state);
+
     if let Some(start) = state.ui_state.paste_from {
         if start != state.camera.pointing_at {
             state.renderer.as_mut().unwrap().overlay_instances.push((
@@ -162,6 +260,15 @@ pub fn render_ui(
         let diff = state.camera.pointing_at - start;
 
         for (coord, id, data) in &state.ui_state.paste_content {
+            if !terrain.can_place(&state.resource_man, *id, *coord + diff) {
+                state
+                    .renderer
+                    .as_mut()
+                    .unwrap()
+                    .tile_tints
+                    .insert(*coord + diff, colors::RED.with_alpha(0.6).to_linear());
+            }
+
             let model_matrix = {
                 let coord = *coord + diff;
                 let p = HEX_GRID_LAYOUT.hex_to_world_pos(*coord);
@@ -180,15 +287,60 @@ pub fn render_ui(
                 Some(*id),
                 data.clone().unwrap_or_default(),
                 cache,
+                InstanceData::default().with_alpha(0.6),
                 model_matrix,
                 state.camera.get_matrix(),
             );
         }
     }
 
+    if state.ui_state.screen == Screen::Ingame && state.ui_state.regions_open {
+        regions::regions_window(state);
+    }
+
+    if state.ui_state.screen == Screen::Ingame && state.ui_state.camera_path_open {
+        camera_path::camera_path_window(state);
+    }
+
+    if state.ui_state.screen == Screen::Ingame && state.ui_state.bookmarks_open {
+        bookmarks::bookmarks_window(state);
+    }
+
+    if state.ui_state.screen == Screen::Ingame && state.ui_state.flow_graph_open {
+        flow_graph::flow_graph_window(state);
+    }
+
+    if state.ui_state.screen == Screen::Ingame && state.ui_state.production_stats_open {
+        production::production_stats_window(state);
+    }
+
+    if state.ui_state.screen == Screen::Ingame && state.ui_state.recipes_open {
+        recipes::recipes_window(state);
+    }
+
+    if state.ui_state.screen == Screen::Ingame && state.ui_state.inventory_open {
+        inventory::inventory_window(state);
+    }
+
+    if state.ui_state.screen == Screen::Ingame && state.ui_state.research_tree_open {
+        research_tree::research_tree_window(state);
+    }
+
+    if state.ui_state.screen == Screen::Ingame && state.ui_state.trim_open {
+        trim::trim_window(state);
+    }
+
+    if state.ui_state.screen == Screen::Ingame && state.ui_state.map_settings_open {
+        map_settings::map_settings_window(state);
+    }
+
     if state.input_handler.key_active(ActionType::Debug) {
         debug::debugger(state);
     }
 
+    if state.ui_state.error_log_open {
+        error::error_log_window(state);
+    }
+
     error::error_popup(state);
 }
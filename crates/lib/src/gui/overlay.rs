@@ -0,0 +1,242 @@
+use crate::GameState;
+use automancy_defs::{colors, id::TileId, math::Float, stack::ItemAmount};
+use automancy_resources::ResourceManager;
+use automancy_system::tile_entity::{TileActivity, TileStatus};
+use automancy_system::ui_state::OverlayMode;
+use automancy_ui::{label, row, window_box, RoundRect, PADDING_LARGE, SMALL_ICON_SIZE};
+use std::collections::BTreeMap;
+use yakui::{
+    widgets::{Absolute, Layer, Pad},
+    Alignment, Color, Dim2, Pivot, Vec2,
+};
+
+pub fn palette_color(index: usize) -> Color {
+    colors::OVERLAY_PALETTE[index % colors::OVERLAY_PALETTE.len()]
+}
+
+/// The fullness the storages are colored by in the overlay, with the lowest fullness of each color.
+const FULLNESS_STEPS: [(Float, Color); 3] = [
+    (0.0, colors::INPUT),
+    (0.5, colors::ORANGE),
+    (0.9, colors::RED),
+];
+
+/// Gets the color of a storage that is full by the fraction, from 0 to 1.
+pub fn fullness_color(fullness: Float) -> Color {
+    FULLNESS_STEPS
+        .iter()
+        .rev()
+        .find(|(min, _)| fullness >= *min)
+        .map_or(FULLNESS_STEPS[0].1, |(_, color)| *color)
+}
+
+/// The items taken in per minute the tiles are colored by in the throughput overlay, with the lowest amount of each color.
+const THROUGHPUT_STEPS: [(ItemAmount, Color); 4] = [
+    (0, colors::DARK_GRAY),
+    (1, colors::INPUT),
+    (60, colors::ORANGE),
+    (600, colors::RED),
+];
+
+/// Gets the color of a tile that took in the amount over the last minute.
+fn throughput_color(throughput: ItemAmount) -> Color {
+    THROUGHPUT_STEPS
+        .iter()
+        .rev()
+        .find(|(min, _)| throughput >= *min)
+        .map_or(THROUGHPUT_STEPS[0].1, |(_, color)| *color)
+}
+
+/// The statuses in the order of the legend, with their colors.
+const ACTIVITY_COLORS: [(TileActivity, Color); 3] = [
+    (TileActivity::Working, colors::INPUT),
+    (TileActivity::Idle, colors::GRAY),
+    (TileActivity::Error, colors::RED),
+];
+
+fn activity_color(activity: TileActivity) -> Color {
+    ACTIVITY_COLORS
+        .iter()
+        .find(|(v, _)| *v == activity)
+        .map_or(colors::GRAY, |(_, color)| *color)
+}
+
+/// Gets the position of the tile's category in the overlay legend, which also decides its color.
+fn category_index(resource_man: &ResourceManager, id: TileId) -> Option<usize> {
+    let category = resource_man.registry.tiles.get(&id)?.category?;
+
+    resource_man
+        .ordered_categories
+        .iter()
+        .position(|v| *v == category)
+}
+
+/// Gets the color the overlay mode gives the tile, or None if it leaves the tile as it is.
+pub fn overlay_color(
+    resource_man: &ResourceManager,
+    mode: OverlayMode,
+    status: &TileStatus,
+) -> Option<Color> {
+    match mode {
+        OverlayMode::None => None,
+        OverlayMode::Category => category_index(resource_man, status.id).map(palette_color),
+        OverlayMode::Status => status.activity.map(activity_color),
+        OverlayMode::Throughput => Some(throughput_color(status.throughput)),
+        OverlayMode::Fullness => status.fullness.map(fullness_color),
+    }
+}
+
+/// Gets the color of the tile's flat hexagon in the strategic view, by the overlay mode or by category otherwise.
+pub fn strategic_color(
+    resource_man: &ResourceManager,
+    mode: OverlayMode,
+    status: &TileStatus,
+) -> Color {
+    let mode = match mode {
+        OverlayMode::None => OverlayMode::Category,
+        mode => mode,
+    };
+
+    overlay_color(resource_man, mode, status).unwrap_or(colors::GRAY)
+}
+
+pub fn legend_entry(color: Color, name: &str) {
+    row(|| {
+        let mut swatch = RoundRect::new(4.0, color);
+        swatch.min_size = Vec2::new(SMALL_ICON_SIZE, SMALL_ICON_SIZE);
+        swatch.show();
+
+        label(name);
+    });
+}
+
+/// Tints the edges of the map's regions, if they are shown.
+pub fn regions(state: &mut GameState) {
+    if !state.ui_state.show_regions {
+        return;
+    }
+
+    let Some(info) = state.loop_store.map_info.as_ref().map(|v| v.0.clone()) else {
+        return;
+    };
+    let info = info.blocking_lock();
+
+    let culling_range = state.camera.culling_range;
+    let index = state.loop_store.region_index(&info.regions);
+
+    let renderer = state.renderer.as_mut().unwrap();
+
+    for (coord, regions) in index {
+        if !culling_range.contains(*coord) {
+            continue;
+        }
+
+        // only the tiles on the edge of a region are tinted, so that the region reads as an outline
+        let edge = regions.iter().copied().find(|region| {
+            coord
+                .neighbors()
+                .iter()
+                .any(|neighbor| !index.get(neighbor).is_some_and(|v| v.contains(region)))
+        });
+
+        if let Some(region) = edge {
+            let color = palette_color(info.regions[region].color);
+
+            renderer
+                .tile_tints
+                .entry(*coord)
+                .or_insert(color.with_alpha(0.35).to_linear());
+        }
+    }
+}
+
+/// Draws the legend of the active overlay mode. The tiles are recolored by the renderer, from the statuses it gets along with their render commands.
+pub fn overlay(state: &mut GameState) {
+    let gui_ids = &state.resource_man.registry.gui_ids;
+
+    let (title, legend) = match state.ui_state.overlay_mode {
+        OverlayMode::None => return,
+        OverlayMode::Category => {
+            let mut categories = BTreeMap::new();
+
+            for status in state.renderer.as_ref().unwrap().tile_statuses.values() {
+                let Some(index) = category_index(&state.resource_man, status.id) else {
+                    continue;
+                };
+
+                categories.entry(index).or_insert_with(|| {
+                    state
+                        .resource_man
+                        .category_name(state.resource_man.ordered_categories[index])
+                        .to_string()
+                });
+            }
+
+            (
+                gui_ids.overlay_category,
+                categories
+                    .into_iter()
+                    .map(|(index, name)| (palette_color(index), name))
+                    .collect::<Vec<_>>(),
+            )
+        }
+        OverlayMode::Status => (
+            gui_ids.overlay_status,
+            ACTIVITY_COLORS
+                .iter()
+                .map(|(activity, color)| {
+                    let name = match activity {
+                        TileActivity::Working => gui_ids.lbl_status_working,
+                        TileActivity::Idle => gui_ids.lbl_status_idle,
+                        TileActivity::Error => gui_ids.lbl_status_error,
+                    };
+
+                    (*color, state.resource_man.gui_str(name).to_string())
+                })
+                .collect(),
+        ),
+        OverlayMode::Throughput => (
+            gui_ids.overlay_throughput,
+            THROUGHPUT_STEPS
+                .iter()
+                .enumerate()
+                .map(|(index, (min, color))| {
+                    let text = match THROUGHPUT_STEPS.get(index + 1) {
+                        Some((next, _)) => format!("{min}-{}/min", next - 1),
+                        None => format!("{min}+/min"),
+                    };
+
+                    (*color, text)
+                })
+                .collect(),
+        ),
+        OverlayMode::Fullness => (
+            gui_ids.overlay_fullness,
+            FULLNESS_STEPS
+                .iter()
+                .enumerate()
+                .map(|(index, (min, color))| {
+                    let max = FULLNESS_STEPS
+                        .get(index + 1)
+                        .map_or(100.0, |(next, _)| next * 100.0 - 1.0);
+
+                    (*color, format!("{:.0}-{max:.0}%", min * 100.0))
+                })
+                .collect(),
+        ),
+    };
+
+    let title = state.resource_man.gui_str(title).to_string();
+
+    Absolute::new(Alignment::TOP_LEFT, Pivot::TOP_LEFT, Dim2::ZERO).show(|| {
+        Layer::new().show(|| {
+            Pad::all(PADDING_LARGE).show(|| {
+                window_box(title, || {
+                    for (color, name) in &legend {
+                        legend_entry(*color, name);
+                    }
+                });
+            });
+        });
+    });
+}
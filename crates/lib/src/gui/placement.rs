@@ -0,0 +1,235 @@
+use crate::gui::util::render_overlay_cached;
+use crate::GameState;
+use automancy_defs::glam::vec3;
+use automancy_defs::math::{Float, Matrix4, FAR, HEX_GRID_LAYOUT};
+use automancy_defs::rendering::InstanceData;
+use automancy_defs::{colors, coord::TileCoord};
+use automancy_resources::format::Formattable;
+use automancy_system::game::{PlacementFailure, PlacementFailures};
+use automancy_system::ui_state::{PlacementFlash, ToastLevel};
+use automancy_system::util::{placement_requirements, tile_direction};
+use automancy_ui::colored_label;
+use std::time::{Duration, Instant};
+use yakui::{
+    widgets::{Absolute, Layer},
+    Alignment, Dim2, Pivot,
+};
+
+/// How long a failed placement is flashed for.
+const FLASH_DURATION: Duration = Duration::from_millis(800);
+
+/// How far the reason floats up while it is shown, in pixels.
+const FLOAT_DISTANCE: f32 = 24.0;
+
+/// How opaque the ghost of the selected tile is.
+const GHOST_ALPHA: Float = 0.5;
+
+/// Gets the translated reason of the failure.
+pub fn failure_text(state: &GameState, failure: PlacementFailure) -> String {
+    let gui_ids = &state.resource_man.registry.gui_ids;
+
+    match failure {
+        PlacementFailure::Occupied => state.resource_man.gui_str(gui_ids.lbl_tile_occupied),
+        PlacementFailure::Protected => state.resource_man.gui_str(gui_ids.lbl_tile_protected),
+        PlacementFailure::InvalidTerrain => state.resource_man.gui_str(gui_ids.lbl_invalid_terrain),
+        PlacementFailure::Locked => state.resource_man.gui_str(gui_ids.lbl_tile_locked),
+        PlacementFailure::MissingItem(item) => {
+            return state.resource_man.gui_fmt(
+                gui_ids.lbl_cannot_place_missing_item,
+                [(
+                    "item_name",
+                    Formattable::display(&state.resource_man.item_name(item)),
+                )],
+            );
+        }
+    }
+    .to_string()
+}
+
+/// Sums up the failures of a bulk placement in one line, with how many tiles failed for each reason.
+pub fn summary_text(state: &GameState, failures: &PlacementFailures) -> String {
+    let reasons = failures
+        .counts()
+        .into_iter()
+        .map(|(failure, count)| format!("{} ({count})", failure_text(state, failure)))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    state.resource_man.gui_fmt(
+        state.resource_man.registry.gui_ids.lbl_placement_failed,
+        [
+            ("count", Formattable::integer(&failures.len())),
+            ("reasons", Formattable::display(&reasons)),
+        ],
+    )
+}
+
+/// Flashes the tile that failed to be placed, with the reason floating up from the cursor.
+pub fn flash_failure(state: &mut GameState, coord: TileCoord, failure: PlacementFailure) {
+    let text = failure_text(state, failure);
+    let pos =
+        state.input_handler.main_pos / state.gui.as_ref().unwrap().yak.layout_dom().scale_factor();
+
+    state
+        .ui_state
+        .placement_flashes
+        .retain(|flash| flash.coord != coord);
+    state.ui_state.placement_flashes.push(PlacementFlash {
+        coord,
+        at: Instant::now(),
+        text: Some((text, pos)),
+    });
+}
+
+/// Flashes the tiles of a bulk placement that failed to be placed, without their reasons.
+pub fn flash_failures(state: &mut GameState, coords: &[TileCoord]) {
+    let now = Instant::now();
+
+    state
+        .ui_state
+        .placement_flashes
+        .retain(|flash| !coords.contains(&flash.coord));
+    state
+        .ui_state
+        .placement_flashes
+        .extend(coords.iter().map(|coord| PlacementFlash {
+            coord: *coord,
+            at: now,
+            text: None,
+        }));
+}
+
+/// Tells the player about the failures of a bulk placement, flashing the tiles and summing up the reasons in a toast.
+pub fn report_failures(state: &mut GameState, failures: &PlacementFailures) {
+    if failures.is_empty() {
+        return;
+    }
+
+    let coords = failures
+        .0
+        .iter()
+        .map(|(coord, _)| *coord)
+        .collect::<Vec<_>>();
+    flash_failures(state, &coords);
+
+    let text = summary_text(state, failures);
+    state.ui_state.push_toast(ToastLevel::Warning, text);
+}
+
+/// Draws the flashes of the failed placements, dropping the ones that are over.
+pub fn placement_flashes(state: &mut GameState) {
+    state
+        .ui_state
+        .placement_flashes
+        .retain(|flash| flash.at.elapsed() < FLASH_DURATION);
+
+    for flash in &state.ui_state.placement_flashes {
+        let t = flash.at.elapsed().as_secs_f32() / FLASH_DURATION.as_secs_f32();
+
+        state.renderer.as_mut().unwrap().tile_tints.insert(
+            flash.coord,
+            colors::RED.with_alpha(0.8 * (1.0 - t)).to_linear(),
+        );
+
+        if let Some((text, pos)) = &flash.text {
+            Layer::new().show(|| {
+                Absolute::new(
+                    Alignment::TOP_LEFT,
+                    Pivot::BOTTOM_CENTER,
+                    Dim2::pixels(pos.x, pos.y - FLOAT_DISTANCE * t),
+                )
+                .show(|| {
+                    colored_label(text, colors::RED);
+                });
+            });
+        }
+    }
+}
+
+/// Draws a see-through ghost of the selected tile where it would be placed, facing the way it would be placed.
+///
+/// The ghost is tinted red when the tile can't be placed there, and hidden while something else has the input.
+pub fn placement_ghost(state: &mut GameState) {
+    let Some(id) = state.ui_state.selected_tile_id else {
+        return;
+    };
+
+    if state.ui_state.is_modal(&state.input_handler)
+        || state.ui_state.research_tree_open
+        || state.ui_state.hovered_selection_tile.is_some()
+        || state.ui_state.paste_from.is_some()
+    {
+        return;
+    }
+
+    let Some(info) = state.loop_store.map_info.as_ref().map(|v| v.0.clone()) else {
+        return;
+    };
+
+    let coord = state.camera.pointing_at;
+    let data = state
+        .ui_state
+        .selected_tile_data
+        .clone()
+        .unwrap_or_default();
+    let direction = tile_direction(&state.resource_man, id, Some(&data));
+    let covered = state
+        .resource_man
+        .registry
+        .tiles
+        .get(&id)
+        .map(|tile| tile.footprint_at(coord, direction))
+        .unwrap_or_default();
+
+    let failed = {
+        let mut lock = info.blocking_lock();
+
+        placement_requirements(&state.resource_man, &mut lock.data, id).is_err()
+            || [coord]
+                .iter()
+                .chain(&covered)
+                .any(|coord| !lock.terrain.can_place(&state.resource_man, id, *coord))
+    };
+
+    let (instance, tint) = if failed {
+        (
+            InstanceData::default()
+                .with_color_offset(colors::RED.to_linear())
+                .with_alpha(GHOST_ALPHA),
+            colors::RED,
+        )
+    } else {
+        (
+            InstanceData::default().with_alpha(GHOST_ALPHA),
+            colors::LIGHT_BLUE,
+        )
+    };
+
+    // the other coordinates a bigger tile would cover
+    for coord in covered {
+        state
+            .renderer
+            .as_mut()
+            .unwrap()
+            .tile_tints
+            .insert(coord, tint.with_alpha(0.4).to_linear());
+    }
+
+    let p = HEX_GRID_LAYOUT.hex_to_world_pos(*coord);
+    let cache = state
+        .ui_state
+        .ghost_render_cache
+        .entry(direction)
+        .or_default();
+
+    render_overlay_cached(
+        &state.resource_man,
+        state.renderer.as_mut().unwrap(),
+        Some(id),
+        data,
+        cache,
+        instance,
+        Matrix4::from_translation(vec3(p.x, p.y, FAR)),
+        state.camera.get_matrix(),
+    );
+}
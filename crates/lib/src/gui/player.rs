@@ -1,5 +1,5 @@
 use automancy_defs::rendering::InstanceData;
-use automancy_defs::stack::ItemStack;
+use automancy_defs::stack::{ItemAmount, ItemStack};
 use automancy_defs::{colors, glam::Vec2};
 use automancy_defs::{colors::BACKGROUND_3, id::Id};
 use automancy_defs::{coord::TileCoord, glam::vec2};
@@ -8,20 +8,24 @@ use automancy_defs::{
     id::ModelId,
 };
 use automancy_resources::data::{Data, DataMap};
+use automancy_resources::format::Formattable;
 use automancy_resources::petgraph::visit::Topo;
 use automancy_resources::types::IconMode;
 use automancy_resources::{rhai_call_options, rhai_log_err};
 use automancy_system::input::ActionType;
+use automancy_system::input_hints::{ActionHint, SCREEN_HINT_PRIORITY};
+use automancy_system::sandbox::is_sandbox;
 use automancy_system::util::is_research_unlocked;
 use automancy_ui::{
-    button, centered_horizontal, col, group, heading, inactive_button, interactive, label,
-    list_row, movable, row, scroll_horizontal, scroll_horizontal_bar_alignment, scroll_vertical,
-    scroll_vertical_bar_alignment, ui_game_object, window_box, PositionRecord, RoundRect,
-    UiGameObjectType, DIVIER_HEIGHT, DIVIER_THICKNESS, MEDIUM_ICON_SIZE, PADDING_MEDIUM,
-    SMALL_ICON_SIZE, TINY_ICON_SIZE,
+    button, center_row, centered_horizontal, col, fill_bar, group, heading, inactive_button,
+    interactive, label, list_row, movable, row, scroll_horizontal, scroll_horizontal_bar_alignment,
+    scroll_vertical, scroll_vertical_bar_alignment, small, symbol_button, ui_game_object,
+    window_box, PositionRecord, RoundRect, UiGameObjectType, DIVIER_HEIGHT, DIVIER_THICKNESS,
+    MEDIUM_ICON_SIZE, PADDING_LARGE, PADDING_MEDIUM, SMALL_ICON_SIZE, TINY_ICON_SIZE,
 };
 use rhai::{Array, Dynamic, Scope};
 use std::mem;
+use std::time::{Duration, Instant};
 use yakui::{
     constrained, divider, reflow,
     widgets::{Absolute, Layer, Pad},
@@ -64,11 +68,12 @@ fn player_inventory(state: &mut GameState, game_data: &mut DataMap) {
                         let pos = PositionRecord::new()
                             .show(|| {
                                 draw_item(
-                                    &state.resource_man,
+                                    state,
                                     || {},
                                     ItemStack { id: *id, amount },
                                     MEDIUM_ICON_SIZE,
                                     true,
+                                    true,
                                 );
                             })
                             .into_inner();
@@ -90,13 +95,275 @@ fn player_inventory(state: &mut GameState, game_data: &mut DataMap) {
     });
 }
 
-fn research_selection(state: &mut GameState, game_data: &mut DataMap) {
-    heading(
-        &state
+/// Makes the research the one being researched, starting its puzzle over.
+pub fn select_research(state: &mut GameState, id: Id) {
+    state.ui_state.selected_research = Some(id);
+    state.ui_state.selected_research_puzzle_tile = None;
+    state.ui_state.research_puzzle_selections = None;
+    state.puzzle_state = None; // TODO have a better save system for this
+    state.ui_state.force_show_puzzle = false;
+}
+
+/// How often the research queue is tried again while nothing is being researched, in case the items for the next research came in.
+const QUEUE_RETRY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Gets the researches lined up after the current one, in order.
+pub fn research_queue(state: &GameState, game_data: &DataMap) -> Vec<Id> {
+    match game_data.get(state.resource_man.registry.data_ids.research_queue) {
+        Some(Data::VecId(queue)) => queue.clone(),
+        _ => vec![],
+    }
+}
+
+/// Checks if there is no research going on, either because none was selected or because the selected one was completed.
+fn research_idle(state: &GameState, game_data: &mut DataMap) -> bool {
+    state.ui_state.selected_research.map_or(true, |id| {
+        is_research_unlocked(id, &state.resource_man, game_data)
+    })
+}
+
+/// Unlocks the research, forgetting the items submitted and the puzzle completed for it.
+fn unlock_research(state: &mut GameState, game_data: &mut DataMap, id: Id) {
+    if let Some(Data::SetId(set)) =
+        game_data.get_mut(state.resource_man.registry.data_ids.research_items_filled)
+    {
+        set.remove(&id);
+    }
+
+    if let Some(Data::SetId(set)) = game_data.get_mut(
+        state
             .resource_man
-            .gui_str(state.resource_man.registry.gui_ids.research_menu_title),
+            .registry
+            .data_ids
+            .research_puzzle_completed,
+    ) {
+        set.remove(&id);
+    }
+
+    if let Data::SetId(set) = game_data
+        .entry(state.resource_man.registry.data_ids.unlocked_researches)
+        .or_insert_with(|| Data::SetId(Default::default()))
+    {
+        set.insert(id);
+    }
+
+    state.loop_store.invalidate_placeable_tiles();
+    state.loop_store.advance_research_queue = true;
+}
+
+/// Lines the research up after the queued ones, unless it is already queued or unlocked.
+/// If there is no research going on, the queue is advanced right away.
+pub fn queue_research(state: &mut GameState, game_data: &mut DataMap, id: Id) {
+    if is_research_unlocked(id, &state.resource_man, game_data) {
+        return;
+    }
+
+    let mut queue = research_queue(state, game_data);
+    if !queue.contains(&id) {
+        queue.push(id);
+    }
+    game_data.set(
+        state.resource_man.registry.data_ids.research_queue,
+        Data::VecId(queue),
+    );
+
+    if research_idle(state, game_data) {
+        state.loop_store.advance_research_queue = true;
+    }
+}
+
+/// Draws the queued researches, with the buttons to move them up or down the queue, or to take them off it.
+fn queued_researches(state: &mut GameState, game_data: &mut DataMap) {
+    let mut queue = research_queue(state, game_data);
+
+    label(&state.resource_man.gui_fmt(
+        state.resource_man.registry.gui_ids.lbl_research_queue,
+        [("count", Formattable::display(&queue.len()))],
+    ));
+
+    if queue.is_empty() {
+        return;
+    }
+
+    let mut moved = None;
+    let mut removed = None;
+
+    scroll_vertical(Vec2::ZERO, Vec2::new(f32::INFINITY, 120.0), || {
+        group(|| {
+            col(|| {
+                for (index, id) in queue.iter().enumerate() {
+                    center_row(|| {
+                        if symbol_button("\u{f47b}", colors::GRAY).clicked && index > 0 {
+                            moved = Some((index, index - 1));
+                        }
+
+                        if symbol_button("\u{f47c}", colors::GRAY).clicked
+                            && index + 1 < queue.len()
+                        {
+                            moved = Some((index, index + 1));
+                        }
+
+                        if symbol_button("\u{f467}", colors::RED).clicked {
+                            removed = Some(index);
+                        }
+
+                        match state.resource_man.get_research(*id) {
+                            Some(research) => {
+                                label(&state.resource_man.research_str(research.name));
+                            }
+                            None => {
+                                label(
+                                    &state.resource_man.interner.resolve(*id).unwrap_or_default(),
+                                );
+                            }
+                        }
+                    });
+                }
+            });
+        });
+    });
+
+    if moved.is_none() && removed.is_none() {
+        return;
+    }
+
+    if let Some((from, to)) = moved {
+        queue.swap(from, to);
+    }
+
+    if let Some(index) = removed {
+        queue.remove(index);
+    }
+
+    game_data.set(
+        state.resource_man.registry.data_ids.research_queue,
+        Data::VecId(queue),
     );
+}
+
+/// Gets how far along the research is, from 0 to 1, with the items and the puzzle each counting for the same part of it.
+///
+/// The items count as far as the player inventory has them, even if they weren't submitted yet.
+fn research_progress(state: &GameState, game_data: &mut DataMap, id: Id) -> f32 {
+    let Some(research) = state.resource_man.get_research(id) else {
+        return 0.0;
+    };
+    let data_ids = &state.resource_man.registry.data_ids;
+
+    let mut parts = vec![];
+
+    if let Some(stacks) = &research.required_items {
+        if game_data.contains_id(data_ids.research_items_filled, id) {
+            parts.push(1.0);
+        } else if let Some(Data::Inventory(inventory)) =
+            game_data.get_mut(data_ids.player_inventory)
+        {
+            let required = stacks.iter().map(|stack| stack.amount).sum::<ItemAmount>();
+            let had = stacks
+                .iter()
+                .map(|stack| inventory.get(stack.id).min(stack.amount))
+                .sum::<ItemAmount>();
+
+            parts.push(had as f32 / required.max(1) as f32);
+        } else {
+            parts.push(0.0);
+        }
+    }
+
+    if research.attached_puzzle.is_some() {
+        parts.push(
+            if game_data.contains_id(data_ids.research_puzzle_completed, id) {
+                1.0
+            } else {
+                0.0
+            },
+        );
+    }
+
+    if parts.is_empty() {
+        return 0.0;
+    }
+
+    parts.iter().sum::<f32>() / parts.len() as f32
+}
 
+/// Draws the research going on and how far along it is, along with how many researches are queued after it.
+///
+/// While nothing is being researched and something is queued, this also tries the queue again every so often.
+fn research_hud(state: &mut GameState, game_data: &mut DataMap) {
+    let queued = research_queue(state, game_data).len();
+    let idle = research_idle(state, game_data);
+
+    if idle
+        && queued > 0
+        && state
+            .loop_store
+            .research_queue_checked
+            .map_or(true, |v| v.elapsed() >= QUEUE_RETRY_INTERVAL)
+    {
+        state.loop_store.research_queue_checked = Some(Instant::now());
+        state.loop_store.advance_research_queue = true;
+    }
+
+    let current = state
+        .ui_state
+        .selected_research
+        .filter(|_| !idle)
+        .and_then(|id| state.resource_man.get_research(id))
+        .map(|research| (research.id, research.name));
+    let progress = current.map(|(id, _)| research_progress(state, game_data, id));
+
+    Absolute::new(Alignment::TOP_CENTER, Pivot::TOP_CENTER, Dim2::ZERO).show(|| {
+        Layer::new().show(|| {
+            Pad::all(PADDING_LARGE).show(|| {
+                group(|| {
+                    col(|| {
+                        match current.zip(progress) {
+                            Some(((_, name), progress)) => {
+                                label(&state.resource_man.research_str(name));
+
+                                fill_bar(progress, 160.0, colors::INPUT);
+                            }
+                            None => {
+                                label(
+                                    &state.resource_man.gui_str(
+                                        state.resource_man.registry.gui_ids.lbl_no_research,
+                                    ),
+                                );
+                            }
+                        }
+
+                        small(&state.resource_man.gui_fmt(
+                            state.resource_man.registry.gui_ids.lbl_research_queued,
+                            [("count", Formattable::display(&queued))],
+                        ));
+                    });
+                });
+            });
+        });
+    });
+}
+
+fn research_selection(state: &mut GameState, game_data: &mut DataMap) {
+    row(|| {
+        heading(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.research_menu_title),
+        );
+
+        if button(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.btn_research_tree),
+        )
+        .clicked
+        {
+            state.ui_state.research_tree_open = !state.ui_state.research_tree_open;
+        }
+    });
+
+    let sandbox = is_sandbox(&state.resource_man, game_data);
     let mut visitor = Topo::new(&state.resource_man.registry.researches);
 
     scroll_vertical(Vec2::ZERO, Vec2::new(f32::INFINITY, 200.0), || {
@@ -109,7 +376,7 @@ fn research_selection(state: &mut GameState, game_data: &mut DataMap) {
                         IconMode::Item => state.resource_man.model_or_missing_item(&research.icon),
                     };
 
-                    if let Some(prev) = research.depends_on {
+                    if let Some(prev) = research.depends_on.filter(|_| !sandbox) {
                         if !is_research_unlocked(prev, &state.resource_man, game_data) {
                             continue;
                         }
@@ -129,17 +396,19 @@ fn research_selection(state: &mut GameState, game_data: &mut DataMap) {
                         });
                     });
 
+                    let id = research.id;
+
                     if interact.clicked {
-                        state.ui_state.selected_research = Some(research.id);
-                        state.ui_state.selected_research_puzzle_tile = None;
-                        state.ui_state.research_puzzle_selections = None;
-                        state.puzzle_state = None; // TODO have a better save system for this
-                        state.ui_state.force_show_puzzle = false;
-                    };
+                        select_research(state, id);
+                    } else if interact.secondary_clicked {
+                        queue_research(state, game_data, id);
+                    }
                 }
             });
         });
     });
+
+    queued_researches(state, game_data);
 }
 
 fn current_research(state: &mut GameState, game_data: &mut DataMap) {
@@ -170,7 +439,7 @@ fn current_research(state: &mut GameState, game_data: &mut DataMap) {
             col(|| {
                 if let Some(stacks) = &research.required_items {
                     for stack in stacks {
-                        draw_item(&state.resource_man, || {}, *stack, SMALL_ICON_SIZE, true);
+                        draw_item(state, || {}, *stack, SMALL_ICON_SIZE, true, true);
                     }
                 }
             });
@@ -209,6 +478,19 @@ fn current_research(state: &mut GameState, game_data: &mut DataMap) {
                 }
             }
         }
+
+        // a sandbox skips the items and the puzzle
+        if is_sandbox(&state.resource_man, game_data)
+            && button(
+                &state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.btn_unlock_research),
+            )
+            .clicked
+        {
+            let id = research.id;
+            unlock_research(state, game_data, id);
+        }
     }
 }
 
@@ -460,28 +742,8 @@ pub fn player(state: &mut GameState, game_data: &mut DataMap) {
         }
 
         if a || b || ab {
-            if let Some(Data::SetId(set)) =
-                game_data.get_mut(state.resource_man.registry.data_ids.research_items_filled)
-            {
-                set.remove(&research.id);
-            }
-
-            if let Some(Data::SetId(set)) = game_data.get_mut(
-                state
-                    .resource_man
-                    .registry
-                    .data_ids
-                    .research_puzzle_completed,
-            ) {
-                set.remove(&research.id);
-            }
-
-            if let Data::SetId(set) = game_data
-                .entry(state.resource_man.registry.data_ids.unlocked_researches)
-                .or_insert_with(|| Data::SetId(Default::default()))
-            {
-                set.insert(research.id);
-            }
+            let id = research.id;
+            unlock_research(state, game_data, id);
 
             state.ui_state.selected_research_puzzle_tile = None;
             state.ui_state.research_puzzle_selections = None;
@@ -489,6 +751,8 @@ pub fn player(state: &mut GameState, game_data: &mut DataMap) {
         }
     }
 
+    research_hud(state, game_data);
+
     let mut board_pos = None;
 
     Layer::new().show(|| {
@@ -496,6 +760,11 @@ pub fn player(state: &mut GameState, game_data: &mut DataMap) {
             return;
         }
 
+        // the same key closes it
+        state.input_hints.push_context(vec![
+            ActionHint::new(vec![ActionType::Player]).with_priority(SCREEN_HINT_PRIORITY)
+        ]);
+
         let mut pos = state.ui_state.player_ui_position;
         movable(&mut pos, || {
             window_box(
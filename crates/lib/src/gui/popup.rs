@@ -1,12 +1,19 @@
+use automancy_system::changelog::ChangelogEntry;
 use automancy_system::game::COULD_NOT_LOAD_ANYTHING;
-use automancy_system::map::{self, GameMap, LoadMapOption};
-use automancy_system::ui_state::{PopupState, Screen, TextField};
+use automancy_system::map::{
+    self, GameMap, LoadMapOption, MapEntryKind, MapError, MapFolderSummary, MapRepair, MAP_PATH,
+};
+use automancy_system::ui_state::{PopupState, Screen, TextField, ToastLevel};
 use automancy_system::{game_load_map, GameLoadResult};
 
-use crate::event::refresh_maps;
+use crate::event::{is_map_loaded, refresh_maps};
+use crate::gui::map_settings::set_sandbox;
 use crate::GameState;
-use automancy_ui::{button, label, row, textbox, window};
-use std::fs;
+use automancy_resources::{format::Formattable, format_time};
+use automancy_ui::{button, checkbox, heading, label, row, scroll_vertical, textbox, window};
+use std::mem;
+use std::path::Path;
+use yakui::Vec2;
 
 pub fn invalid_name_popup(state: &mut GameState) {
     window(
@@ -34,6 +41,269 @@ pub fn invalid_name_popup(state: &mut GameState) {
     );
 }
 
+/// Draws the "what's new" window, with the changes in each of the entries.
+pub fn changelog_popup(state: &mut GameState, entries: &[ChangelogEntry]) {
+    window(
+        state
+            .resource_man
+            .gui_str(state.resource_man.registry.gui_ids.changelog)
+            .to_string(),
+        || {
+            scroll_vertical(Vec2::ZERO, Vec2::new(f32::INFINITY, 360.0), || {
+                for entry in entries {
+                    match &entry.date {
+                        Some(date) => heading(&format!("{} ({date})", entry.version)),
+                        None => heading(&entry.version),
+                    };
+
+                    for section in &entry.sections {
+                        if let Some(title) = &section.title {
+                            label(&title.resolve(&state.resource_man));
+                        }
+
+                        for bullet in &section.bullets {
+                            label(&format!(
+                                "  \u{2022} {}",
+                                bullet.resolve(&state.resource_man)
+                            ));
+                        }
+                    }
+                }
+            });
+
+            if button(
+                &state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.btn_confirm),
+            )
+            .clicked
+            {
+                state.ui_state.popup = PopupState::None;
+            }
+        },
+    );
+}
+
+/// Draws the summary of the unloaded IDs found in the map that was just loaded.
+pub fn missing_ids_popup(state: &mut GameState, missing: &[(String, usize)]) {
+    window(
+        state
+            .resource_man
+            .gui_str(state.resource_man.registry.gui_ids.missing_ids)
+            .to_string(),
+        || {
+            label(
+                &state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.lbl_missing_ids),
+            );
+
+            for (id, count) in missing {
+                label(&format!("{id}: {count}"));
+            }
+
+            if button(
+                &state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.btn_confirm),
+            )
+            .clicked
+            {
+                state.ui_state.popup = PopupState::None;
+            }
+        },
+    );
+}
+
+/// Gets the paths of the entries the repair is for.
+fn repairable(entries: &[(String, MapEntryKind)], repair: MapRepair) -> Vec<String> {
+    entries
+        .iter()
+        .filter(|(_, kind)| MapRepair::of(*kind) == Some(repair))
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Shows the map folder again once it was repaired, if anything in it still needs attention.
+fn rescan_map_folder(state: &mut GameState) {
+    let entries = map::scan_map_folder(Path::new(MAP_PATH));
+
+    state.ui_state.popup = if entries.iter().any(|(_, kind)| kind.needs_attention()) {
+        PopupState::MapFolderIssues(entries)
+    } else {
+        PopupState::None
+    };
+}
+
+/// Draws the summary of the map folder, with the entries that need attention.
+///
+/// Maps missing their info can be repaired from here. Temporary files can be finished or removed once the player confirms it, and the rest are left for the player to review.
+pub fn map_folder_issues_popup(state: &mut GameState, entries: &[(String, MapEntryKind)]) {
+    let mut dirty = false;
+
+    window(
+        state
+            .resource_man
+            .gui_str(state.resource_man.registry.gui_ids.map_folder_issues)
+            .to_string(),
+        || {
+            let gui_ids = state.resource_man.registry.gui_ids;
+            let summary = MapFolderSummary::new(entries);
+
+            label(&state.resource_man.gui_str(gui_ids.lbl_map_folder_issues));
+            label(&state.resource_man.gui_fmt(
+                gui_ids.lbl_map_folder_summary,
+                [
+                    ("saves", Formattable::display(&summary.valid)),
+                    ("broken", Formattable::display(&summary.broken)),
+                    (
+                        "temps",
+                        Formattable::display(&(summary.valid_temps + summary.invalid_temps)),
+                    ),
+                    ("backups", Formattable::display(&summary.backups)),
+                    ("autosaves", Formattable::display(&summary.autosaves)),
+                    (
+                        "recoveries",
+                        Formattable::display(&summary.error_recoveries),
+                    ),
+                    ("unknown", Formattable::display(&summary.unknown)),
+                ],
+            ));
+
+            for (name, kind) in entries.iter().filter(|(_, kind)| kind.needs_attention()) {
+                let id = match kind {
+                    MapEntryKind::MissingInfo => gui_ids.lbl_map_missing_info,
+                    MapEntryKind::MissingTiles => gui_ids.lbl_map_missing_tiles,
+                    MapEntryKind::Corrupted => gui_ids.lbl_map_corrupted,
+                    MapEntryKind::Temp { valid: true } => gui_ids.lbl_map_temp_valid,
+                    MapEntryKind::Temp { valid: false } => gui_ids.lbl_map_temp_invalid,
+                    MapEntryKind::Autosave { .. } => gui_ids.lbl_map_autosave_newer,
+                    MapEntryKind::ErrorRecovery => gui_ids.lbl_map_error_recovery,
+                    MapEntryKind::Valid | MapEntryKind::Backup | MapEntryKind::Unknown => {
+                        gui_ids.lbl_map_unknown
+                    }
+                };
+
+                label(
+                    &state
+                        .resource_man
+                        .gui_fmt(id, [("map_name", Formattable::display(name))]),
+                );
+            }
+
+            row(|| {
+                let missing_info = repairable(entries, MapRepair::RecreateInfo);
+                if !missing_info.is_empty()
+                    && button(&state.resource_man.gui_str(gui_ids.btn_repair)).clicked
+                {
+                    for name in &missing_info {
+                        match map::repair_map_entry(
+                            &Path::new(MAP_PATH).join(name),
+                            MapRepair::RecreateInfo,
+                        ) {
+                            Ok(true) => log::info!("Repaired map {name}"),
+                            Ok(false) => {}
+                            Err(err) => log::error!("Could not repair map {name}! Error: {err}"),
+                        }
+                    }
+
+                    dirty = true;
+                    rescan_map_folder(state);
+                }
+
+                // these replace or remove files, so they are only done once the player confirms them
+                for (repair, id) in [
+                    (MapRepair::PromoteTemp, gui_ids.btn_promote_temps),
+                    (MapRepair::RemoveTemp, gui_ids.btn_remove_temps),
+                ] {
+                    let paths = repairable(entries, repair);
+
+                    if !paths.is_empty()
+                        && button(
+                            &state
+                                .resource_man
+                                .gui_fmt(id, [("count", Formattable::display(&paths.len()))]),
+                        )
+                        .clicked
+                    {
+                        state.ui_state.popup = PopupState::MapRepairConfirmation(repair, paths);
+                    }
+                }
+
+                if button(&state.resource_man.gui_str(gui_ids.btn_open_folder)).clicked {
+                    if let Err(err) = open::that(MAP_PATH) {
+                        log::error!("Could not open the map folder! Error: {err}");
+                    }
+                }
+
+                if button(&state.resource_man.gui_str(gui_ids.btn_confirm)).clicked {
+                    state.ui_state.popup = PopupState::None;
+                }
+            });
+        },
+    );
+
+    if dirty {
+        refresh_maps(state);
+    }
+}
+
+/// Asks before the repair replaces or removes the files of the map folder at the paths, listing them.
+pub fn map_repair_popup(state: &mut GameState, repair: MapRepair, paths: &[String]) {
+    let mut dirty = false;
+
+    window(
+        state
+            .resource_man
+            .gui_str(state.resource_man.registry.gui_ids.map_repair_confirm)
+            .to_string(),
+        || {
+            let gui_ids = state.resource_man.registry.gui_ids;
+            let id = match repair {
+                MapRepair::PromoteTemp => gui_ids.lbl_promote_temps_confirm,
+                MapRepair::RecreateInfo | MapRepair::RemoveTemp => gui_ids.lbl_remove_temps_confirm,
+            };
+
+            label(
+                &state
+                    .resource_man
+                    .gui_fmt(id, [("count", Formattable::display(&paths.len()))]),
+            );
+
+            scroll_vertical(Vec2::ZERO, Vec2::new(f32::INFINITY, 240.0), || {
+                for path in paths {
+                    label(path);
+                }
+            });
+
+            row(|| {
+                if button(&state.resource_man.gui_str(gui_ids.btn_confirm)).clicked {
+                    for path in paths {
+                        match map::repair_map_entry(&Path::new(MAP_PATH).join(path), repair) {
+                            Ok(true) => log::info!("Repaired {path} of the map folder"),
+                            Ok(false) => {}
+                            Err(err) => log::error!(
+                                "Could not repair {path} of the map folder! Error: {err}"
+                            ),
+                        }
+                    }
+
+                    dirty = true;
+                    rescan_map_folder(state);
+                }
+
+                if button(&state.resource_man.gui_str(gui_ids.btn_cancel)).clicked {
+                    rescan_map_folder(state);
+                }
+            });
+        },
+    );
+
+    if dirty {
+        refresh_maps(state);
+    }
+}
+
 pub fn map_delete_popup(state: &mut GameState, map_name: &str) {
     let mut dirty = false;
 
@@ -56,13 +326,26 @@ pub fn map_delete_popup(state: &mut GameState, map_name: &str) {
             )
             .clicked
             {
-                fs::remove_dir_all(
-                    GameMap::path(&LoadMapOption::FromSave(map_name.to_string())).unwrap(),
-                )
-                .unwrap();
+                let result = if is_map_loaded(state, map_name) {
+                    Err(MapError::Loaded(map_name.to_string()))
+                } else {
+                    GameMap::delete(map_name)
+                };
+
+                if let Err(err) = result {
+                    log::error!("Could not delete map {map_name}! Error: {err}");
+
+                    state.ui_state.push_toast(
+                        ToastLevel::Error,
+                        state
+                            .resource_man
+                            .gui_str(state.resource_man.registry.gui_ids.lbl_map_delete_failed)
+                            .to_string(),
+                    );
+                }
+
                 dirty = true;
                 state.ui_state.popup = PopupState::None;
-                log::info!("Deleted map {map_name}!");
             }
 
             if button(
@@ -82,6 +365,149 @@ pub fn map_delete_popup(state: &mut GameState, map_name: &str) {
     }
 }
 
+/// Draws the popup offering to load the autosave of a map that couldn't be read.
+pub fn autosave_fallback_popup(state: &mut GameState, map_name: &str) {
+    let opt = LoadMapOption::FromSave(map_name.to_string());
+
+    window(
+        state
+            .resource_man
+            .gui_str(state.resource_man.registry.gui_ids.load_map)
+            .to_string(),
+        || {
+            let time = GameMap::autosave_time(&opt)
+                .map(|time| {
+                    format_time(
+                        time,
+                        &state
+                            .resource_man
+                            .gui_str(state.resource_man.registry.gui_ids.time_fmt),
+                    )
+                })
+                .unwrap_or_default();
+
+            label(&state.resource_man.gui_fmt(
+                state.resource_man.registry.gui_ids.lbl_autosave_fallback,
+                [
+                    ("map_name", Formattable::display(&map_name)),
+                    ("time", Formattable::display(&time)),
+                ],
+            ));
+
+            if button(
+                &state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.btn_load_autosave),
+            )
+            .clicked
+            {
+                state.ui_state.popup = PopupState::None;
+
+                if let Err(err) = GameMap::restore_autosave(&opt) {
+                    log::error!("Could not restore the autosave of {map_name}! Error: {err}");
+                    return;
+                }
+
+                match game_load_map(state, map_name.to_string()) {
+                    GameLoadResult::Loaded => {
+                        state.ui_state.switch_screen(Screen::Ingame);
+                    }
+                    GameLoadResult::LoadedMainMenu => {
+                        state.ui_state.switch_screen(Screen::MainMenu);
+                    }
+                    GameLoadResult::Failed => {
+                        panic!("{}", COULD_NOT_LOAD_ANYTHING)
+                    }
+                }
+            }
+
+            if button(
+                &state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.btn_cancel),
+            )
+            .clicked
+            {
+                state.ui_state.popup = PopupState::None;
+            }
+        },
+    );
+}
+
+/// Imports the exported map at the path, telling the player what it was imported as.
+pub fn import_map(state: &mut GameState, src: &Path) {
+    match GameMap::import(src) {
+        Ok(map_name) => {
+            let text = state.resource_man.gui_fmt(
+                state.resource_man.registry.gui_ids.lbl_map_imported,
+                [("map_name", Formattable::display(&map_name))],
+            );
+
+            state
+                .ui_state
+                .push_toast(ToastLevel::Info, text.to_string());
+        }
+        Err(err) => {
+            log::error!("Could not import the map at {src:?}! Error: {err}");
+
+            state.ui_state.push_toast(
+                ToastLevel::Error,
+                state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.lbl_map_import_failed)
+                    .to_string(),
+            );
+        }
+    }
+
+    refresh_maps(state);
+}
+
+/// Draws the popup asking before importing an exported map that references packs that aren't loaded.
+pub fn map_import_popup(state: &mut GameState, src: &Path, missing: &[String]) {
+    window(
+        state
+            .resource_man
+            .gui_str(state.resource_man.registry.gui_ids.import_map)
+            .to_string(),
+        || {
+            label(
+                &state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.lbl_import_missing_packs),
+            );
+
+            for namespace in missing {
+                label(namespace);
+            }
+
+            row(|| {
+                if button(
+                    &state
+                        .resource_man
+                        .gui_str(state.resource_man.registry.gui_ids.btn_import),
+                )
+                .clicked
+                {
+                    state.ui_state.popup = PopupState::None;
+
+                    import_map(state, src);
+                }
+
+                if button(
+                    &state
+                        .resource_man
+                        .gui_str(state.resource_man.registry.gui_ids.btn_cancel),
+                )
+                .clicked
+                {
+                    state.ui_state.popup = PopupState::None;
+                }
+            });
+        },
+    );
+}
+
 /// Draws the map creation popup.
 pub fn map_create_popup(state: &mut GameState) {
     window(
@@ -90,14 +516,52 @@ pub fn map_create_popup(state: &mut GameState) {
             .gui_str(state.resource_man.registry.gui_ids.create_map)
             .to_string(),
         || {
-            let name = state.ui_state.text_field.get(TextField::MapName);
-
             row(|| {
                 label("Name:"); //TODO add this to translation
 
-                textbox(name, None, Some("Name your world here..."));
+                textbox(
+                    state.ui_state.text_field.get(TextField::MapName),
+                    None,
+                    Some("Name your world here..."),
+                );
             });
 
+            row(|| {
+                label(
+                    &state
+                        .resource_man
+                        .gui_str(state.resource_man.registry.gui_ids.lbl_map_description),
+                );
+
+                textbox(
+                    state.ui_state.text_field.get(TextField::MapDescription),
+                    None,
+                    None,
+                );
+            });
+
+            row(|| {
+                checkbox(&mut state.ui_state.new_map_sandbox.enabled);
+
+                label(
+                    &state
+                        .resource_man
+                        .gui_str(state.resource_man.registry.gui_ids.lbl_sandbox),
+                );
+            });
+
+            if state.ui_state.new_map_sandbox.enabled {
+                row(|| {
+                    checkbox(&mut state.ui_state.new_map_sandbox.free_inputs);
+
+                    label(
+                        &state
+                            .resource_man
+                            .gui_str(state.resource_man.registry.gui_ids.lbl_sandbox_free_inputs),
+                    );
+                });
+            }
+
             if button(
                 &state
                     .resource_man
@@ -105,13 +569,30 @@ pub fn map_create_popup(state: &mut GameState) {
             )
             .clicked
             {
-                let name = map::sanitize_name(name.clone());
-
-                state.ui_state.text_field.get(TextField::MapName).clear();
+                let display_name = mem::take(state.ui_state.text_field.get(TextField::MapName));
+                let description =
+                    mem::take(state.ui_state.text_field.get(TextField::MapDescription));
+                let sandbox = mem::take(&mut state.ui_state.new_map_sandbox);
                 state.ui_state.popup = PopupState::None;
 
+                // the folder is named after the map, but a new map never takes the folder of another
+                let name = GameMap::unused_name(&map::sanitize_name(display_name.clone()));
+
                 match game_load_map(state, name) {
                     GameLoadResult::Loaded => {
+                        if let Some((info, _)) = &state.loop_store.map_info {
+                            let mut info = info.blocking_lock();
+
+                            info.display_name =
+                                Some(display_name.trim().to_string()).filter(|v| !v.is_empty());
+                            info.description =
+                                Some(description.trim().to_string()).filter(|v| !v.is_empty());
+                        }
+
+                        if sandbox.enabled {
+                            set_sandbox(state, sandbox);
+                        }
+
                         state.ui_state.switch_screen(Screen::Ingame);
                     }
                     GameLoadResult::LoadedMainMenu => {
@@ -0,0 +1,133 @@
+use crate::gui::item::draw_item;
+use crate::GameState;
+use automancy_defs::{math::Float, stack::ItemStack};
+use automancy_resources::format::Formattable;
+use automancy_system::production::{collect_production_stats, ItemRates};
+use automancy_ui::{col, label, movable, scroll_vertical, window, SMALL_ICON_SIZE};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use yakui::{widgets::Layer, Vec2};
+
+/// How often the production stats are collected again while the window is open.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Collects the production stats in the background, unless they were collected recently or are being collected.
+fn refresh_production_stats(state: &mut GameState) {
+    if state
+        .loop_store
+        .production_stats_refreshed
+        .is_some_and(|v| v.elapsed() < REFRESH_INTERVAL)
+        || state
+            .loop_store
+            .production_stats_updating
+            .swap(true, Ordering::Relaxed)
+    {
+        return;
+    }
+
+    state.loop_store.production_stats_refreshed = Some(Instant::now());
+
+    let cache = state.loop_store.production_stats.clone();
+    let updating = state.loop_store.production_stats_updating.clone();
+    let game = state.game.clone();
+
+    state.tokio.spawn(async move {
+        if let Some(stats) = collect_production_stats(game).await {
+            *cache.lock().await = Some(stats);
+        }
+
+        updating.store(false, Ordering::Relaxed);
+    });
+}
+
+/// Formats the rates of each window, the shortest first.
+fn rates_text(rates: &[Float]) -> String {
+    rates
+        .iter()
+        .map(|rate| format!("{rate:.1}"))
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
+
+/// Draws the item's icon and name, along with how many were produced and consumed per minute over each window.
+fn item_rates(state: &GameState, rates: &ItemRates) {
+    draw_item(
+        state,
+        || {},
+        ItemStack {
+            id: rates.item,
+            amount: 0,
+        },
+        SMALL_ICON_SIZE,
+        true,
+        true,
+    );
+
+    label(&state.resource_man.gui_fmt(
+        state.resource_man.registry.gui_ids.lbl_production_rates,
+        [
+            (
+                "produced",
+                Formattable::display(&rates_text(&rates.produced)),
+            ),
+            (
+                "consumed",
+                Formattable::display(&rates_text(&rates.consumed)),
+            ),
+        ],
+    ));
+}
+
+/// Draws the window listing how many of each item the map produces and consumes.
+pub fn production_stats_window(state: &mut GameState) {
+    refresh_production_stats(state);
+
+    let mut items = state
+        .loop_store
+        .production_stats
+        .blocking_lock()
+        .as_ref()
+        .map(|stats| stats.items.clone())
+        .unwrap_or_default();
+    items.sort_by_cached_key(|rates| state.resource_man.item_name(rates.item).to_string());
+
+    Layer::new().show(|| {
+        let mut pos = state.ui_state.production_stats_ui_position;
+        movable(&mut pos, || {
+            window(
+                state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.production_stats)
+                    .to_string(),
+                || {
+                    col(|| {
+                        if items.is_empty() {
+                            label(
+                                &state
+                                    .resource_man
+                                    .gui_str(state.resource_man.registry.gui_ids.lbl_no_production),
+                            );
+
+                            return;
+                        }
+
+                        label(
+                            &state.resource_man.gui_str(
+                                state.resource_man.registry.gui_ids.lbl_production_windows,
+                            ),
+                        );
+
+                        scroll_vertical(Vec2::ZERO, Vec2::new(f32::INFINITY, 320.0), || {
+                            col(|| {
+                                for rates in &items {
+                                    item_rates(state, rates);
+                                }
+                            });
+                        });
+                    });
+                },
+            );
+        });
+        state.ui_state.production_stats_ui_position = pos;
+    });
+}
@@ -0,0 +1,362 @@
+use crate::gui::info::tile_icon;
+use crate::gui::item::draw_item_with;
+use crate::GameState;
+use automancy_defs::{
+    colors,
+    id::{Id, TileId},
+    math::Float,
+    rendering::InstanceData,
+    stack::ItemStack,
+};
+use automancy_resources::{data::Data, format::Formattable, item_match, ResourceManager};
+use automancy_system::ui_state::{RecipeLookup, TextField};
+use automancy_ui::{
+    button, center_row, col, colored_label, interactive, label, label_text, movable, row,
+    scroll_vertical, symbol, textbox, window, HOVER_TIP, MEDIUM_ICON_SIZE, SMALL_ICON_SIZE,
+};
+use fuzzy_matcher::FuzzyMatcher;
+use hashbrown::HashSet;
+use yakui::{widgets::Layer, Vec2};
+
+/// How opaque the icons of the scripts locked behind a research are.
+const LOCKED_ALPHA: Float = 0.35;
+
+/// Groups the scripts by the tiles that list them in their `scripts` data, in the order of the tiles.
+/// The scripts no tile lists come last, in a group of their own.
+fn script_groups(resource_man: &ResourceManager) -> Vec<(Option<TileId>, Vec<Id>)> {
+    let mut listed = HashSet::new();
+
+    let mut groups = resource_man
+        .ordered_tiles
+        .iter()
+        .flat_map(|id| {
+            let Some(Data::VecId(scripts)) = resource_man.registry.tiles[id]
+                .data
+                .get(resource_man.registry.data_ids.scripts)
+            else {
+                return None;
+            };
+
+            let scripts = scripts
+                .iter()
+                .filter(|script| resource_man.registry.scripts.contains_key(*script))
+                .copied()
+                .collect::<Vec<_>>();
+            listed.extend(scripts.iter().copied());
+
+            (!scripts.is_empty()).then_some((Some(*id), scripts))
+        })
+        .collect::<Vec<_>>();
+
+    let mut other = resource_man
+        .registry
+        .scripts
+        .keys()
+        .filter(|id| !listed.contains(*id))
+        .copied()
+        .collect::<Vec<_>>();
+    other.sort_by_cached_key(|id| resource_man.script_name(*id).to_string());
+
+    if !other.is_empty() {
+        groups.push((None, other));
+    }
+
+    groups
+}
+
+/// Checks if the script takes in or puts out the item that is looked up.
+fn matches_lookup(resource_man: &ResourceManager, script: Id, lookup: RecipeLookup) -> bool {
+    let instructions = &resource_man.registry.scripts[&script].instructions;
+
+    match lookup {
+        RecipeLookup::Consuming(item) => instructions
+            .inputs
+            .iter()
+            .flatten()
+            .any(|stack| item_match(resource_man, item, stack.id)),
+        RecipeLookup::Producing(item) => instructions.outputs.iter().any(|stack| stack.id == item),
+    }
+}
+
+/// Checks if the search matches the name of the script, or of any item it takes in or puts out.
+fn matches_search(state: &GameState, script: Id, search: &str) -> bool {
+    if search.is_empty() {
+        return true;
+    }
+
+    let resource_man = &state.resource_man;
+    let fuse = &state.ui_state.text_field.fuse;
+    let instructions = &resource_man.registry.scripts[&script].instructions;
+
+    fuse.fuzzy_match(&resource_man.script_name(script), search)
+        .is_some()
+        || instructions
+            .inputs
+            .iter()
+            .flatten()
+            .chain(&instructions.outputs)
+            .any(|stack| {
+                fuse.fuzzy_match(&resource_man.item_name(stack.id), search)
+                    .is_some()
+            })
+}
+
+/// Draws an item of a script. Clicking it looks up the scripts producing it, and right clicking the scripts consuming it.
+fn recipe_item(
+    state: &mut GameState,
+    prefix: impl FnOnce(),
+    stack: ItemStack,
+    instance: InstanceData,
+) {
+    let interact = interactive(|| {
+        draw_item_with(state, prefix, stack, SMALL_ICON_SIZE, true, true, instance);
+    });
+
+    if interact.clicked {
+        state.ui_state.recipe_lookup = Some(RecipeLookup::Producing(stack.id));
+    } else if interact.secondary_clicked {
+        state.ui_state.recipe_lookup = Some(RecipeLookup::Consuming(stack.id));
+    }
+}
+
+/// Draws the script's name, inputs and outputs. Locked scripts are dimmed, with the research unlocking them in the tooltip.
+fn draw_recipe(state: &mut GameState, id: Id, locked: bool, research: Option<Id>) {
+    let Some(script) = state.resource_man.registry.scripts.get(&id).cloned() else {
+        return;
+    };
+
+    let instance = if locked {
+        InstanceData::default().with_alpha(LOCKED_ALPHA)
+    } else {
+        InstanceData::default()
+    };
+
+    col(|| {
+        let name = state.resource_man.script_name(id);
+
+        if locked {
+            let hover = interactive(|| {
+                colored_label(&name, colors::TEXT_INACTIVE);
+            });
+
+            if let Some(research) = research.filter(|_| hover.hovering) {
+                let text = state.resource_man.gui_fmt(
+                    state.resource_man.registry.gui_ids.lbl_recipes_locked,
+                    [(
+                        "research",
+                        Formattable::display(&state.resource_man.research_str(research)),
+                    )],
+                );
+
+                HOVER_TIP.set(Some(label_text(&text)));
+            }
+        } else {
+            label(&name);
+        }
+
+        for input in script.instructions.inputs.iter().flatten() {
+            recipe_item(
+                state,
+                || symbol("\u{f44d}", colors::INPUT),
+                *input,
+                instance,
+            );
+        }
+
+        for output in &script.instructions.outputs {
+            recipe_item(
+                state,
+                || symbol("\u{f460}", colors::OUTPUT),
+                *output,
+                instance,
+            );
+        }
+    });
+}
+
+/// Draws the item that is looked up, along with the buttons to switch between its consumers and producers, or to stop looking it up.
+fn lookup_header(state: &mut GameState, lookup: RecipeLookup) {
+    let (item, title) = match lookup {
+        RecipeLookup::Consuming(item) => (
+            item,
+            state.resource_man.registry.gui_ids.lbl_recipes_consuming,
+        ),
+        RecipeLookup::Producing(item) => (
+            item,
+            state.resource_man.registry.gui_ids.lbl_recipes_producing,
+        ),
+    };
+
+    center_row(|| {
+        draw_item_with(
+            state,
+            || {},
+            ItemStack {
+                id: item,
+                amount: 0,
+            },
+            MEDIUM_ICON_SIZE,
+            false,
+            true,
+            InstanceData::default(),
+        );
+
+        label(&state.resource_man.gui_fmt(
+            title,
+            [(
+                "item",
+                Formattable::display(&state.resource_man.item_name(item)),
+            )],
+        ));
+    });
+
+    row(|| {
+        if button(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.btn_recipes_consuming),
+        )
+        .clicked
+        {
+            state.ui_state.recipe_lookup = Some(RecipeLookup::Consuming(item));
+        }
+
+        if button(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.btn_recipes_producing),
+        )
+        .clicked
+        {
+            state.ui_state.recipe_lookup = Some(RecipeLookup::Producing(item));
+        }
+
+        if button(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.btn_clear),
+        )
+        .clicked
+        {
+            state.ui_state.recipe_lookup = None;
+        }
+    });
+}
+
+/// Draws the window listing every script by the tiles that run them, filtered by the search and the item that is looked up.
+pub fn recipes_window(state: &mut GameState) {
+    let placeable = match state.loop_store.map_info.as_ref().map(|v| v.0.clone()) {
+        Some(info) => state
+            .loop_store
+            .placeable_tiles(&state.resource_man, &mut info.blocking_lock().data),
+        None => Default::default(),
+    };
+
+    let lookup = state.ui_state.recipe_lookup;
+    let search = state
+        .ui_state
+        .text_field
+        .get(TextField::RecipeSearch)
+        .clone();
+
+    let groups = script_groups(&state.resource_man)
+        .into_iter()
+        .map(|(tile, scripts)| {
+            let scripts = scripts
+                .into_iter()
+                .filter(|script| {
+                    lookup.map_or(true, |lookup| {
+                        matches_lookup(&state.resource_man, *script, lookup)
+                    }) && matches_search(state, *script, &search)
+                })
+                .collect::<Vec<_>>();
+
+            (tile, scripts)
+        })
+        .filter(|(_, scripts)| !scripts.is_empty())
+        .collect::<Vec<_>>();
+
+    Layer::new().show(|| {
+        let mut pos = state.ui_state.recipes_ui_position;
+        movable(&mut pos, || {
+            window(
+                state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.recipes)
+                    .to_string(),
+                || {
+                    col(|| {
+                        textbox(
+                            state.ui_state.text_field.get(TextField::RecipeSearch),
+                            None,
+                            Some(
+                                &state.resource_man.gui_str(
+                                    state.resource_man.registry.gui_ids.lbl_recipes_search,
+                                ),
+                            ),
+                        );
+
+                        if let Some(lookup) = lookup {
+                            lookup_header(state, lookup);
+                        }
+
+                        if groups.is_empty() {
+                            label(
+                                &state
+                                    .resource_man
+                                    .gui_str(state.resource_man.registry.gui_ids.lbl_no_recipes),
+                            );
+
+                            return;
+                        }
+
+                        scroll_vertical(Vec2::ZERO, Vec2::new(f32::INFINITY, 320.0), || {
+                            col(|| {
+                                for (tile, scripts) in &groups {
+                                    let (locked, research) = match tile {
+                                        Some(tile) => (
+                                            !placeable.contains(tile),
+                                            state
+                                                .resource_man
+                                                .get_research_by_unlock(*tile)
+                                                .map(|research| research.name),
+                                        ),
+                                        None => (false, None),
+                                    };
+
+                                    center_row(|| match tile {
+                                        Some(tile) => {
+                                            tile_icon(
+                                                *tile,
+                                                MEDIUM_ICON_SIZE,
+                                                InstanceData::default(),
+                                            );
+
+                                            label(&state.resource_man.tile_name(*tile));
+                                        }
+                                        None => {
+                                            label(
+                                                &state.resource_man.gui_str(
+                                                    state
+                                                        .resource_man
+                                                        .registry
+                                                        .gui_ids
+                                                        .lbl_recipes_other,
+                                                ),
+                                            );
+                                        }
+                                    });
+
+                                    for script in scripts {
+                                        draw_recipe(state, *script, locked, research);
+                                    }
+                                }
+                            });
+                        });
+                    });
+                },
+            );
+        });
+        state.ui_state.recipes_ui_position = pos;
+    });
+}
@@ -0,0 +1,206 @@
+use crate::gui::overlay::palette_color;
+use crate::GameState;
+use automancy_resources::format::Formattable;
+use automancy_system::map::Region;
+use automancy_system::ui_state::TextField;
+use automancy_ui::{
+    button, checkbox, col, label, movable, row, textbox, window, RoundRect, SMALL_ICON_SIZE,
+};
+use std::mem;
+use yakui::{widgets::Layer, Vec2};
+
+/// A change made to the map's regions in the regions window.
+enum RegionChange {
+    Create(String),
+    Rename(usize, String),
+    Recolor(usize),
+    Delete(usize),
+}
+
+fn apply_change(state: &mut GameState, change: RegionChange) {
+    let Some(info) = state.loop_store.map_info.as_ref().map(|v| v.0.clone()) else {
+        return;
+    };
+    let mut info = info.blocking_lock();
+    let regions = &mut info.regions;
+
+    match change {
+        RegionChange::Create(name) => {
+            let Some(tiles) = state.ui_state.region_selection.take() else {
+                return;
+            };
+            // the selection is hashed, so it's sorted to keep the saved region the same every time
+            let mut tiles = tiles.into_iter().collect::<Vec<_>>();
+            tiles.sort_unstable();
+
+            let name = if name.trim().is_empty() {
+                format!("#{}", regions.len() + 1)
+            } else {
+                name.trim().to_string()
+            };
+
+            regions.push(Region {
+                name,
+                color: regions.len(),
+                tiles,
+            });
+        }
+        RegionChange::Rename(index, name) => {
+            if let Some(region) = regions.get_mut(index) {
+                if !name.trim().is_empty() {
+                    region.name = name.trim().to_string();
+                }
+            }
+        }
+        RegionChange::Recolor(index) => {
+            if let Some(region) = regions.get_mut(index) {
+                region.color = region.color.wrapping_add(1);
+            }
+        }
+        RegionChange::Delete(index) => {
+            if index < regions.len() {
+                regions.remove(index);
+            }
+        }
+    }
+
+    state.loop_store.region_index = None;
+}
+
+/// Draws the window for managing the map's regions.
+pub fn regions_window(state: &mut GameState) {
+    let Some(info) = state.loop_store.map_info.as_ref().map(|v| v.0.clone()) else {
+        return;
+    };
+
+    let regions = info
+        .blocking_lock()
+        .regions
+        .iter()
+        .map(|region| (region.name.clone(), region.color, region.tiles.len()))
+        .collect::<Vec<_>>();
+
+    let mut change = None;
+
+    Layer::new().show(|| {
+        let mut pos = state.ui_state.regions_ui_position;
+        movable(&mut pos, || {
+            window(
+                state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.regions)
+                    .to_string(),
+                || {
+                    col(|| {
+                        row(|| {
+                            checkbox(&mut state.ui_state.show_regions);
+                            label(
+                                &state
+                                    .resource_man
+                                    .gui_str(state.resource_man.registry.gui_ids.lbl_show_regions),
+                            );
+                        });
+
+                        if let Some(count) =
+                            state.ui_state.region_selection.as_ref().map(|v| v.len())
+                        {
+                            label(&state.resource_man.gui_fmt(
+                                state.resource_man.registry.gui_ids.lbl_new_region,
+                                [("count", Formattable::integer(&count))],
+                            ));
+
+                            row(|| {
+                                let name = state.ui_state.text_field.get(TextField::RegionName);
+
+                                textbox(name, None, None);
+
+                                if button(
+                                    &state.resource_man.gui_str(
+                                        state.resource_man.registry.gui_ids.btn_create_region,
+                                    ),
+                                )
+                                .clicked
+                                {
+                                    change = Some(RegionChange::Create(
+                                        state.ui_state.text_field.take(TextField::RegionName),
+                                    ));
+                                }
+                            });
+                        }
+
+                        if regions.is_empty() {
+                            label(
+                                &state
+                                    .resource_man
+                                    .gui_str(state.resource_man.registry.gui_ids.lbl_no_regions),
+                            );
+                        }
+
+                        for (index, (name, color, tiles)) in regions.iter().enumerate() {
+                            row(|| {
+                                let mut swatch = RoundRect::new(4.0, palette_color(*color));
+                                swatch.min_size = Vec2::new(SMALL_ICON_SIZE, SMALL_ICON_SIZE);
+                                swatch.show();
+
+                                if state.ui_state.renaming_region == Some(index) {
+                                    let renaming =
+                                        state.ui_state.text_field.get(TextField::RegionRenaming);
+
+                                    let res = textbox(renaming, None, None);
+                                    if res.lost_focus || res.activated {
+                                        state.ui_state.renaming_region = None;
+
+                                        change =
+                                            Some(RegionChange::Rename(index, mem::take(renaming)));
+                                    }
+                                } else {
+                                    label(&format!("{name} ({tiles})"));
+
+                                    if button(
+                                        &state.resource_man.gui_str(
+                                            state.resource_man.registry.gui_ids.btn_rename,
+                                        ),
+                                    )
+                                    .clicked
+                                    {
+                                        state
+                                            .ui_state
+                                            .text_field
+                                            .get(TextField::RegionRenaming)
+                                            .clone_from(name);
+                                        state.ui_state.renaming_region = Some(index);
+                                    }
+                                }
+
+                                if button(
+                                    &state
+                                        .resource_man
+                                        .gui_str(state.resource_man.registry.gui_ids.btn_recolor),
+                                )
+                                .clicked
+                                {
+                                    change = Some(RegionChange::Recolor(index));
+                                }
+
+                                if button(
+                                    &state
+                                        .resource_man
+                                        .gui_str(state.resource_man.registry.gui_ids.btn_delete),
+                                )
+                                .clicked
+                                {
+                                    change = Some(RegionChange::Delete(index));
+                                }
+                            });
+                        }
+                    });
+                },
+            );
+        });
+        state.ui_state.regions_ui_position = pos;
+    });
+
+    if let Some(change) = change {
+        apply_change(state, change);
+    }
+}
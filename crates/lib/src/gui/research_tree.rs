@@ -0,0 +1,426 @@
+use crate::gui::info::tile_icon;
+use crate::gui::item::draw_item;
+use crate::gui::overlay::legend_entry;
+use crate::gui::player::{queue_research, research_queue, select_research};
+use crate::GameState;
+use automancy_defs::{colors, glam::vec2, id::Id, math::Float, rendering::InstanceData};
+use automancy_resources::data::DataMap;
+use automancy_resources::types::research::{ResearchDef, ResearchLayout};
+use automancy_resources::types::IconMode;
+use automancy_resources::ResourceManager;
+use automancy_system::util::is_research_unlocked;
+use automancy_ui::{
+    button, col, heading, interactive, label, label_text, movable, pan_zoom, row, ui_game_object,
+    window, GraphCanvas, GraphLine, PositionRecord, RoundRect, UiGameObjectType, HOVER_TIP,
+    SMALL_ICON_SIZE,
+};
+use hashbrown::HashMap;
+use yakui::{
+    constrained,
+    widgets::{Layer, Pad},
+    Color, Constraints, Vec2,
+};
+
+/// The size of the area the research tree is drawn in.
+const CANVAS_SIZE: Vec2 = Vec2::new(560.0, 360.0);
+
+/// How far apart the layers are, unzoomed.
+const LAYER_SPACING: Float = 96.0;
+
+/// How far apart the researches of a layer are, unzoomed.
+const ROW_SPACING: Float = 64.0;
+
+const NODE_ICON_SIZE: Float = 32.0;
+const NODE_PADDING: Float = 6.0;
+
+const MIN_ZOOM: Float = 0.25;
+const MAX_ZOOM: Float = 3.0;
+
+/// How much the zoom changes for each pixel scrolled.
+const ZOOM_SPEED: Float = 0.004;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResearchState {
+    Unlocked,
+    /// the research it depends on is unlocked, so it can be researched
+    Available,
+    Locked,
+}
+
+impl ResearchState {
+    fn of(resource_man: &ResourceManager, game_data: &mut DataMap, research: &ResearchDef) -> Self {
+        if is_research_unlocked(research.id, resource_man, game_data) {
+            Self::Unlocked
+        } else if research.depends_on.map_or(true, |prev| {
+            is_research_unlocked(prev, resource_man, game_data)
+        }) {
+            Self::Available
+        } else {
+            Self::Locked
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Self::Unlocked => colors::INPUT,
+            Self::Available => colors::ORANGE,
+            Self::Locked => colors::GRAY,
+        }
+    }
+}
+
+/// Gets where the research is on the canvas, panned and zoomed. The first layer starts on the left, with each layer centered vertically.
+fn node_point(layout: &ResearchLayout, id: Id, pan: Vec2, zoom: Float) -> Option<Vec2> {
+    let (layer, order) = *layout.positions.get(&id)?;
+    let count = layout.layers[layer].len();
+
+    let pos = vec2(
+        layer as Float * LAYER_SPACING,
+        (order as Float - (count - 1) as Float / 2.0) * ROW_SPACING,
+    );
+
+    Some(vec2(LAYER_SPACING / 2.0, CANVAS_SIZE.y / 2.0) + pos * zoom + pan)
+}
+
+/// Cuts the line down to the part of it inside the canvas, if any of it is.
+fn clip_line(from: Vec2, to: Vec2) -> Option<(Vec2, Vec2)> {
+    let delta = to - from;
+    let mut start: Float = 0.0;
+    let mut end: Float = 1.0;
+
+    for (p, q) in [
+        (-delta.x, from.x),
+        (delta.x, CANVAS_SIZE.x - from.x),
+        (-delta.y, from.y),
+        (delta.y, CANVAS_SIZE.y - from.y),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else if p < 0.0 {
+            start = start.max(q / p);
+        } else {
+            end = end.min(q / p);
+        }
+    }
+
+    (start <= end).then(|| (from + delta * start, from + delta * end))
+}
+
+/// Draws the details of the research: what it costs, the tiles it unlocks, and the buttons to start it if it can be researched,
+/// or to queue it if it isn't queued yet. Returns whether it should be queued.
+fn research_details(
+    state: &mut GameState,
+    id: Id,
+    research_state: ResearchState,
+    queued: bool,
+) -> bool {
+    let Some(research) = state.resource_man.get_research(id).cloned() else {
+        return false;
+    };
+
+    heading(&state.resource_man.research_str(research.name));
+
+    constrained(
+        Constraints::loose(Vec2::new(CANVAS_SIZE.x, f32::INFINITY)),
+        || {
+            label(&state.resource_man.research_str(research.description));
+        },
+    );
+
+    if let Some(stacks) = &research.required_items {
+        label(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.lbl_research_cost),
+        );
+
+        for stack in stacks {
+            draw_item(state, || {}, *stack, SMALL_ICON_SIZE, true, true);
+        }
+    }
+
+    let unlocks = state
+        .resource_man
+        .ordered_tiles
+        .iter()
+        .filter(|tile| {
+            state
+                .resource_man
+                .get_research_by_unlock(**tile)
+                .is_some_and(|research| research.id == id)
+        })
+        .copied()
+        .collect::<Vec<_>>();
+
+    if !unlocks.is_empty() {
+        label(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.lbl_research_unlocks),
+        );
+
+        for tile in unlocks {
+            row(|| {
+                tile_icon(tile, SMALL_ICON_SIZE, InstanceData::default());
+
+                label(&state.resource_man.tile_name(tile));
+            });
+        }
+    }
+
+    if state.ui_state.selected_research == Some(id) {
+        label(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.lbl_research_current),
+        );
+    } else if research_state == ResearchState::Available
+        && button(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.btn_start_research),
+        )
+        .clicked
+    {
+        select_research(state, id);
+    }
+
+    research_state != ResearchState::Unlocked
+        && !queued
+        && button(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.btn_queue_research),
+        )
+        .clicked
+}
+
+/// Draws the window with every research laid out by what it depends on, colored by whether it is unlocked, available, or locked.
+///
+/// The tree is dragged to pan it, and scrolled over to zoom it. Clicking a research shows its details under the tree.
+pub fn research_tree_window(state: &mut GameState) {
+    let resource_man = state.resource_man.clone();
+    let layout = &resource_man.registry.research_layout;
+
+    let (states, queue) = match state.loop_store.map_info.as_ref().map(|v| v.0.clone()) {
+        Some(info) => {
+            let mut lock = info.blocking_lock();
+
+            let states = layout
+                .positions
+                .keys()
+                .flat_map(|id| resource_man.get_research(*id))
+                .map(|research| {
+                    (
+                        research.id,
+                        ResearchState::of(&resource_man, &mut lock.data, research),
+                    )
+                })
+                .collect::<HashMap<_, _>>();
+
+            (states, research_queue(state, &lock.data))
+        }
+        None => Default::default(),
+    };
+    let state_of = |id: Id| states.get(&id).copied().unwrap_or(ResearchState::Locked);
+
+    let pan = state.ui_state.research_tree_pan;
+    let zoom = state.ui_state.research_tree_zoom;
+    let selected = state.ui_state.research_tree_selected;
+    let node_size = (NODE_ICON_SIZE + NODE_PADDING * 2.0) * zoom;
+
+    // the researches are only drawn if they are entirely inside the canvas, as nothing is cut off at its edges
+    let nodes = layout
+        .layers
+        .iter()
+        .flatten()
+        .flat_map(|id| node_point(layout, *id, pan, zoom).map(|point| (*id, point)))
+        .filter(|(_, point)| {
+            let half = node_size / 2.0;
+
+            point.x >= half
+                && point.y >= half
+                && point.x <= CANVAS_SIZE.x - half
+                && point.y <= CANVAS_SIZE.y - half
+        })
+        .collect::<Vec<_>>();
+
+    let lines = layout
+        .positions
+        .keys()
+        .flat_map(|id| resource_man.get_research(*id))
+        .flat_map(|research| {
+            let prev = research.depends_on?;
+            let (from, to) = clip_line(
+                node_point(layout, prev, pan, zoom)?,
+                node_point(layout, research.id, pan, zoom)?,
+            )?;
+
+            Some(GraphLine {
+                from,
+                to,
+                thickness: 2.0,
+                color: if selected == Some(prev) || selected == Some(research.id) {
+                    colors::OUTPUT
+                } else {
+                    colors::GRAY
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut clicked = None;
+    let mut queued = None;
+    let mut canvas_pos = None;
+    let mut view = None;
+
+    Layer::new().show(|| {
+        let mut pos = state.ui_state.research_tree_ui_position;
+        movable(&mut pos, || {
+            window(
+                resource_man
+                    .gui_str(resource_man.registry.gui_ids.research_tree)
+                    .to_string(),
+                || {
+                    col(|| {
+                        row(|| {
+                            for research_state in [
+                                ResearchState::Unlocked,
+                                ResearchState::Available,
+                                ResearchState::Locked,
+                            ] {
+                                let name = match research_state {
+                                    ResearchState::Unlocked => {
+                                        resource_man.registry.gui_ids.lbl_research_unlocked
+                                    }
+                                    ResearchState::Available => {
+                                        resource_man.registry.gui_ids.lbl_research_available
+                                    }
+                                    ResearchState::Locked => {
+                                        resource_man.registry.gui_ids.lbl_research_locked
+                                    }
+                                };
+
+                                legend_entry(research_state.color(), &resource_man.gui_str(name));
+                            }
+
+                            if button(
+                                &resource_man.gui_str(resource_man.registry.gui_ids.btn_reset_view),
+                            )
+                            .clicked
+                            {
+                                view = Some((Vec2::ZERO, 1.0));
+                            }
+                        });
+
+                        let response =
+                            pan_zoom(|| {
+                                canvas_pos =
+                                    PositionRecord::new()
+                                        .show(|| {
+                                            let mut canvas = GraphCanvas::new(CANVAS_SIZE);
+                                            canvas.lines = lines;
+                                            canvas.points =
+                                                nodes.iter().map(|(_, point)| *point).collect();
+                                            canvas.show(|| {
+                                                for (id, _) in &nodes {
+                                                    let Some(research) =
+                                                        resource_man.get_research(*id)
+                                                    else {
+                                                        continue;
+                                                    };
+
+                                                    let icon = match research.icon_mode {
+                                                        IconMode::Tile => resource_man
+                                                            .model_or_missing_tile(&research.icon),
+                                                        IconMode::Item => resource_man
+                                                            .model_or_missing_item(&research.icon),
+                                                    };
+
+                                                    let mut color = state_of(*id).color();
+                                                    if selected == Some(*id) {
+                                                        color = color.adjust(1.3);
+                                                    }
+
+                                                    let interact =
+                                                        interactive(|| {
+                                                            RoundRect::new(8.0 * zoom, color)
+                                                                .show_children(|| {
+                                                                    Pad::all(NODE_PADDING * zoom)
+                                                                        .show(|| {
+                                                                            ui_game_object(
+                                                            InstanceData::default(),
+                                                            UiGameObjectType::Model(icon),
+                                                            Vec2::splat(NODE_ICON_SIZE * zoom),
+                                                            Some(research.icon_mode.model_matrix()),
+                                                            Some(research.icon_mode.world_matrix()),
+                                                        );
+                                                                        });
+                                                                });
+                                                        });
+
+                                                    if interact.hovering {
+                                                        HOVER_TIP.set(Some(label_text(
+                                                            &resource_man
+                                                                .research_str(research.name),
+                                                        )));
+                                                    }
+
+                                                    if interact.clicked {
+                                                        clicked = Some(*id);
+                                                    }
+                                                }
+                                            });
+                                        })
+                                        .into_inner();
+                            })
+                            .into_inner();
+
+                        if response.dragged != Vec2::ZERO || response.scrolled != 0.0 {
+                            let new_zoom = (zoom * (-response.scrolled * ZOOM_SPEED).exp())
+                                .clamp(MIN_ZOOM, MAX_ZOOM);
+                            let mut new_pan = pan + response.dragged;
+
+                            // zooms in on the pointer, so that what is under it stays there
+                            if let Some(canvas_pos) = canvas_pos.filter(|_| new_zoom != zoom) {
+                                let scale_factor =
+                                    state.gui.as_ref().unwrap().yak.layout_dom().scale_factor();
+                                let pointer = state.input_handler.main_pos / scale_factor
+                                    - canvas_pos
+                                    - vec2(LAYER_SPACING / 2.0, CANVAS_SIZE.y / 2.0);
+
+                                new_pan = pointer - (pointer - new_pan) * (new_zoom / zoom);
+                            }
+
+                            view = Some((new_pan, new_zoom));
+                        }
+
+                        if let Some(id) = clicked.or(selected) {
+                            if research_details(state, id, state_of(id), queue.contains(&id)) {
+                                queued = Some(id);
+                            }
+                        }
+                    });
+                },
+            );
+        });
+        state.ui_state.research_tree_ui_position = pos;
+    });
+
+    if let Some(id) = clicked {
+        state.ui_state.research_tree_selected = Some(id);
+    }
+
+    if let Some(id) = queued {
+        if let Some(info) = state.loop_store.map_info.as_ref().map(|v| v.0.clone()) {
+            queue_research(state, &mut info.blocking_lock().data, id);
+        }
+    }
+
+    if let Some((pan, zoom)) = view {
+        state.ui_state.research_tree_pan = pan;
+        state.ui_state.research_tree_zoom = zoom;
+    }
+}
@@ -1,18 +1,25 @@
 use crate::GameState;
 use automancy_defs::id::Id;
-use automancy_defs::{colors, coord::TileCoord, stack::ItemStack};
+use automancy_defs::{
+    colors,
+    coord::TileCoord,
+    stack::{ItemAmount, ItemStack},
+};
 use automancy_resources::rhai_ui::RhaiUiUnit;
 use automancy_resources::{
     data::{Data, DataMap},
-    inventory::Inventory,
+    inventory::{Inventory, InventoryLimits},
 };
+use automancy_system::game::GameSystemMessage;
+use automancy_system::input::ActionType;
+use automancy_system::input_hints::{ActionHint, SCREEN_HINT_PRIORITY};
 use automancy_system::tile_entity::TileEntityMsg;
 use automancy_system::ui_state::TextField;
 use automancy_ui::{
-    button, center_col, center_row, col, group, info_tip, interactive, label, list_col, movable,
-    num_input, row, scroll_vertical_bar_alignment, selectable_symbol_button, selection_button,
-    slider, spaced_col, spaced_row, symbol, symbol_button, window_box, PositionRecord,
-    MEDIUM_ICON_SIZE, PADDING_MEDIUM, PADDING_XSMALL, SMALL_ICON_SIZE,
+    amount_input, button, center_col, center_row, checkbox, col, group, info_tip, interactive,
+    label, list_col, movable, row, scroll_vertical_bar_alignment, selectable_symbol_button,
+    selection_button, slider, spaced_col, spaced_row, symbol, symbol_button, window_box,
+    PositionRecord, MEDIUM_ICON_SIZE, PADDING_MEDIUM, PADDING_XSMALL, SMALL_ICON_SIZE,
 };
 use ractor::rpc::CallResult;
 use ractor::ActorRef;
@@ -24,7 +31,7 @@ use yakui::{
 };
 
 use super::item::draw_item;
-use super::util::searchable_id;
+use super::util::{amount_label, amount_step, searchable_id, storage_fill};
 
 /// Draws the direction selector.
 fn add_direction(target_coord: &mut Option<TileCoord>, n: u8) {
@@ -55,10 +62,21 @@ fn add_direction(target_coord: &mut Option<TileCoord>, n: u8) {
     });
 }
 
+/// Gets how much of the stack a click moves: up to the take amount, or all of it with the select mode key held.
+fn moved_amount(state: &GameState, stored: ItemAmount) -> ItemAmount {
+    let take_amount = state.ui_state.take_amount;
+
+    if take_amount > 0 && !state.input_handler.key_active(ActionType::SelectMode) {
+        stored.min(take_amount)
+    } else {
+        stored
+    }
+}
+
 fn takeable_items(
     state: &mut GameState,
     game_data: &mut DataMap,
-    mut buffer: Inventory,
+    buffer: Inventory,
     buffer_id: Id,
     tile_entity: ActorRef<TileEntityMsg>,
 ) {
@@ -69,66 +87,172 @@ fn takeable_items(
         return;
     };
 
-    let mut dirty = false;
+    let is_buffer = buffer_id == state.resource_man.registry.data_ids.buffer;
+
+    if is_buffer {
+        if let Some((id, data)) = state.ui_state.config_open_at.and_then(|coord| {
+            let Ok(CallResult::Success(Some(id))) = state.tokio.block_on(
+                state
+                    .game
+                    .call(|reply| GameSystemMessage::GetTile(coord, reply), None),
+            ) else {
+                return None;
+            };
+            let Ok(CallResult::Success(data)) = state
+                .tokio
+                .block_on(tile_entity.call(TileEntityMsg::GetData, None))
+            else {
+                return None;
+            };
+
+            Some((id, data))
+        }) {
+            storage_fill(
+                state,
+                &buffer,
+                InventoryLimits::of_tile(&state.resource_man, id, &data),
+            );
+        }
+    }
+
+    center_row(|| {
+        label(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.lbl_take_amount),
+        );
+
+        let step = amount_step(state);
+        amount_input(
+            &mut state.ui_state.take_amount,
+            0..=ItemAmount::MAX,
+            step,
+            |v| v.parse().ok(),
+            |v| v.to_string(),
+        );
+    });
 
-    for (id, amount) in buffer.clone().into_inner() {
+    for (id, amount) in buffer.into_inner() {
         let mut pos = None;
 
         let interact = interactive(|| {
             pos = PositionRecord::new()
                 .show(|| {
                     draw_item(
-                        &state.resource_man,
+                        state,
                         || {},
                         ItemStack { id, amount },
                         MEDIUM_ICON_SIZE,
                         true,
+                        true,
                     );
                 })
                 .into_inner();
         });
 
-        if interact.clicked {
-            let amount = buffer.take(id, amount);
-
-            if amount > 0 {
-                dirty = true;
-                inventory.add(id, amount);
-
-                if let Some(pos) = pos {
-                    state
-                        .renderer
-                        .as_mut()
-                        .unwrap()
-                        .take_item_animations
-                        .entry(id)
-                        .or_default()
-                        .push_back((
-                            Instant::now(),
-                            Rect::from_pos_size(pos, Vec2::new(MEDIUM_ICON_SIZE, MEDIUM_ICON_SIZE)),
-                        ));
-                }
+        if !interact.clicked {
+            continue;
+        }
+
+        let moving = moved_amount(state, amount).min(inventory.room_for(
+            id,
+            InventoryLimits::default().for_item(&state.resource_man, id),
+        ));
+
+        if moving <= 0 {
+            continue;
+        }
+
+        // the tile may have used some of it up since it was read, so only what it replies with is moved
+        let Ok(CallResult::Success(taken)) = state.tokio.block_on(tile_entity.call(
+            |reply| TileEntityMsg::TakeFromBuffer {
+                buffer: buffer_id,
+                item: id,
+                amount: moving,
+                reply,
+            },
+            None,
+        )) else {
+            continue;
+        };
+
+        if taken > 0 {
+            inventory.add(id, taken);
+
+            if let Some(pos) = pos {
+                state
+                    .renderer
+                    .as_mut()
+                    .unwrap()
+                    .take_item_animations
+                    .entry(id)
+                    .or_default()
+                    .push_back((
+                        Instant::now(),
+                        Rect::from_pos_size(pos, Vec2::new(MEDIUM_ICON_SIZE, MEDIUM_ICON_SIZE)),
+                    ));
             }
         }
     }
 
-    if dirty {
-        tile_entity
-            .send_message(TileEntityMsg::SetDataValue(
-                buffer_id,
-                Data::Inventory(buffer),
-            ))
-            .unwrap();
+    // only the main buffer is filled from the player inventory, as the others are the script's own
+    if !is_buffer || inventory.values().all(|amount| *amount <= 0) {
+        return;
+    }
+
+    label(
+        &state
+            .resource_man
+            .gui_str(state.resource_man.registry.gui_ids.lbl_put_in),
+    );
+
+    for (id, amount) in inventory.clone().into_inner() {
+        if amount <= 0 {
+            continue;
+        }
+
+        let interact = interactive(|| {
+            draw_item(
+                state,
+                || {},
+                ItemStack { id, amount },
+                SMALL_ICON_SIZE,
+                true,
+                true,
+            );
+        });
+
+        if !interact.clicked {
+            continue;
+        }
+
+        let moving = moved_amount(state, amount);
+
+        let Ok(CallResult::Success(inserted)) = state.tokio.block_on(tile_entity.call(
+            |reply| TileEntityMsg::InsertToBuffer {
+                buffer: buffer_id,
+                item: id,
+                amount: moving,
+                reply,
+            },
+            None,
+        )) else {
+            continue;
+        };
+
+        inventory.take(id, inserted);
     }
 }
 
+// the search lists are too dense for the items in them to have tooltips
 fn draw_item_plain(state: &mut GameState, id: Id) {
     draw_item(
-        &state.resource_man,
+        state,
         || {},
         ItemStack { id, amount: 0 },
         SMALL_ICON_SIZE,
         true,
+        false,
     );
 }
 
@@ -141,7 +265,7 @@ fn draw_item_script(state: &mut GameState, id: Id) {
         .map(|script| script.instructions.outputs.as_slice())
     {
         for stack in stacks {
-            draw_item(&state.resource_man, || {}, *stack, SMALL_ICON_SIZE, false);
+            draw_item(state, || {}, *stack, SMALL_ICON_SIZE, false, false);
         }
     }
 
@@ -159,22 +283,24 @@ fn draw_script_info(state: &mut GameState, data: &DataMap, id: Id) {
         if let Some(inputs) = &script.instructions.inputs {
             for input in inputs {
                 draw_item(
-                    &state.resource_man,
+                    state,
                     || symbol("\u{f44d}", colors::INPUT),
                     *input,
                     SMALL_ICON_SIZE,
                     true,
+                    true,
                 );
             }
         }
 
         for output in &script.instructions.outputs {
             draw_item(
-                &state.resource_man,
+                state,
                 || symbol("\u{f460}", colors::OUTPUT),
                 *output,
                 SMALL_ICON_SIZE,
                 true,
+                true,
             );
         }
     });
@@ -195,7 +321,7 @@ fn rhai_ui(
             info_tip(&state.resource_man.gui_str(id));
         }
         RhaiUiUnit::LabelAmount { amount } => {
-            label(&amount.to_string());
+            amount_label(state, amount, str::to_string);
         }
         RhaiUiUnit::InputAmount { id, max } => {
             let Data::Amount(current_amount) = data.get(id).cloned().unwrap_or(Data::Amount(0))
@@ -206,10 +332,10 @@ fn rhai_ui(
             let mut new_amount = current_amount;
             let max_digit_count = (max.checked_ilog10().unwrap_or(0) + 1) as usize;
 
-            num_input(
+            amount_input(
                 &mut new_amount,
-                false,
                 0..=max,
+                amount_step(state),
                 |v| v.parse().ok(),
                 |v| {
                     let n = v.to_string();
@@ -321,6 +447,12 @@ fn rhai_ui(
                         .unwrap();
                 }
             }
+
+            if data_id == state.resource_man.registry.data_ids.item {
+                if let Some(item) = new_id {
+                    item_threshold(state, &tile_entity, data, item);
+                }
+            }
         }
         RhaiUiUnit::SelectableScripts {
             data_id,
@@ -395,6 +527,81 @@ fn rhai_ui(
     }
 }
 
+/// Draws the input for the most of the filtered item the tile takes in, or any amount if 0.
+fn item_threshold(
+    state: &mut GameState,
+    tile_entity: &ActorRef<TileEntityMsg>,
+    data: &DataMap,
+    item: Id,
+) {
+    let id = state.resource_man.registry.data_ids.item_thresholds;
+
+    let mut thresholds = match data.get(id) {
+        Some(Data::Inventory(thresholds)) => thresholds.clone(),
+        _ => Inventory::default(),
+    };
+    let current = thresholds.get(item).max(0);
+    let mut new = current;
+
+    center_row(|| {
+        label(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.lbl_item_threshold),
+        );
+
+        amount_input(
+            &mut new,
+            0..=ItemAmount::MAX,
+            amount_step(state),
+            |v| v.parse().ok(),
+            |v| v.to_string(),
+        );
+    });
+
+    if new != current {
+        if new > 0 {
+            thresholds.insert(item, new);
+        } else {
+            thresholds.remove(&item);
+        }
+
+        tile_entity
+            .send_message(TileEntityMsg::SetDataValue(id, Data::Inventory(thresholds)))
+            .unwrap();
+    }
+}
+
+/// Draws the toggle for protecting the tile from removal.
+fn protection_toggle(state: &mut GameState, tile_entity: &ActorRef<TileEntityMsg>, data: &DataMap) {
+    let id = state.resource_man.registry.data_ids.protected;
+
+    let current = matches!(data.get(id), Some(Data::Bool(true)));
+    let mut new = current;
+
+    center_row(|| {
+        label(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.lbl_protected),
+        );
+
+        checkbox(&mut new);
+    });
+
+    if new != current {
+        if new {
+            tile_entity
+                .send_message(TileEntityMsg::SetDataValue(id, Data::Bool(true)))
+                .unwrap();
+        } else {
+            tile_entity
+                .send_message(TileEntityMsg::RemoveData(id))
+                .unwrap();
+        }
+    }
+}
+
 /// Draws the tile configuration menu.
 pub fn tile_config_ui(state: &mut GameState, game_data: &mut DataMap) {
     Layer::new().show(|| {
@@ -409,6 +616,11 @@ pub fn tile_config_ui(state: &mut GameState, game_data: &mut DataMap) {
             return;
         };
 
+        // with the select mode key held, clicking an item moves all of it
+        state.input_hints.push_context(vec![
+            ActionHint::new(vec![ActionType::SelectMode]).with_priority(SCREEN_HINT_PRIORITY)
+        ]);
+
         let tile_config_ui;
         if let Ok(CallResult::Success(ui)) = state
             .tokio
@@ -435,6 +647,8 @@ pub fn tile_config_ui(state: &mut GameState, game_data: &mut DataMap) {
                             group(|| {
                                 Pad::horizontal(PADDING_MEDIUM).show(|| {
                                     col(|| {
+                                        protection_toggle(state, &tile_entity, &data);
+
                                         if let Some(ui) = tile_config_ui {
                                             rhai_ui(
                                                 state,
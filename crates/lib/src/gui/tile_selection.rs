@@ -1,168 +1,429 @@
+use crate::gui::info::binding_text;
+use crate::gui::placement::failure_text;
 use crate::GameState;
-use automancy_defs::glam::{vec2, FloatExt};
+use automancy_defs::glam::vec2;
 use automancy_defs::id::{Id, ModelId};
 use automancy_defs::math::{Float, Matrix4};
 use automancy_defs::rendering::InstanceData;
 use automancy_defs::{colors, id::TileId};
-use automancy_resources::{
-    data::{Data, DataMap},
-    format::Formattable,
-    types::IconMode,
+use automancy_resources::{data::DataMap, format::Formattable, types::IconMode};
+use automancy_system::favorites::read_favorites;
+use automancy_system::hotbar::{read_hotbar, write_hotbar, HOTBAR_SIZE};
+use automancy_system::input::{ActionType, TILE_SELECTION_KEYS};
+use automancy_system::input_hints::{ActionHint, SCREEN_HINT_PRIORITY};
+use automancy_system::ui_state::{TextField, ToastLevel};
+use automancy_system::util::{
+    is_default_tile, missing_category_item, placement_requirements, should_category_show,
 };
-use automancy_system::util::{is_research_unlocked, should_category_show};
 use automancy_ui::{
-    center_col, col, hover_tip, interactive, label, row, scroll_horizontal_bar_alignment,
-    ui_game_object, viewport_constrained, RoundRect, UiGameObjectType, LARGE_ICON_SIZE,
-    MEDIUM_ICON_SIZE,
+    center_col, col, colored_sized_text, hover_tip, interactive, label, lerp_towards, row,
+    scroll_grid, scroll_horizontal_bar_alignment, textbox, ui_game_object, viewport_constrained,
+    GameObject, RoundRect, UiGameObjectType, LARGE_ICON_SIZE, MEDIUM_ICON_SIZE, PADDING_MEDIUM,
+    PADDING_XSMALL, SMALL_SIZE,
 };
-use tokio::sync::oneshot;
+use hashbrown::HashSet;
 use yakui::{
-    use_state,
-    widgets::{Absolute, Layer},
+    reflow, use_state,
+    widgets::{Absolute, Layer, Pad},
     Alignment, Dim2, Pivot, Vec2,
 };
 
+/// How much of the window's width the tile selection takes up at most.
+const SELECTION_WIDTH: Float = 0.6;
+
+/// How many rows of tiles are shown before the selection scrolls.
+const SELECTION_ROWS: Float = 2.5;
+
 fn tile_hover_z_angle(elapsed: Float, hovered: bool) -> Float {
-    fn angle(hovered: bool) -> Float {
-        if hovered {
-            0.5
-        } else {
-            0.0
-        }
+    lerp_towards(if hovered { 0.5 } else { 0.0 }, elapsed)
+}
+
+/// What the player picked in the tile selection.
+pub enum SelectionResponse {
+    /// A tile was clicked in the selection.
+    Select(TileId),
+    /// A hotbar slot was clicked.
+    Hotbar(usize),
+    /// A hotbar slot was clicked with the hotkey held, emptying it.
+    ClearHotbar(usize),
+    /// A tile was right-clicked, adding it to or removing it from the favorites.
+    ToggleFavorite(TileId),
+}
+
+/// Starts or stops placing the tile, as when it is clicked in the selection.
+pub fn select_tile(state: &mut GameState, id: TileId) {
+    state.ui_state.already_placed_at = None;
+    state.ui_state.selected_tile_data = None;
+
+    if state.ui_state.selected_tile_id == Some(id) {
+        state.ui_state.selected_tile_id = None;
+    } else {
+        state.ui_state.selected_tile_id = Some(id);
     }
+}
 
-    //TODO extract this
-    let s = use_state(move || angle(hovered));
+/// Shows the category before or after the current one in the tile selection, skipping the hidden ones and wrapping around.
+pub fn cycle_category(state: &mut GameState, game_data: &mut DataMap, forward: bool) {
+    let shown = state
+        .resource_man
+        .ordered_categories
+        .iter()
+        .filter(|id| should_category_show(**id, &state.resource_man, game_data))
+        .copied()
+        .collect::<Vec<_>>();
+
+    if shown.is_empty() {
+        return;
+    }
 
-    let target = angle(hovered);
+    let current = state
+        .ui_state
+        .tile_selection_category
+        .and_then(|id| shown.iter().position(|v| *v == id));
 
-    let r = s.get();
+    let index = match (current, forward) {
+        (Some(index), true) => (index + 1) % shown.len(),
+        (Some(index), false) => (index + shown.len() - 1) % shown.len(),
+        (None, true) => 0,
+        (None, false) => shown.len() - 1,
+    };
 
-    s.modify(|v| {
-        let lerped = v.lerp(target, elapsed);
+    state.ui_state.tile_selection_category = Some(shown[index]);
+}
 
-        lerped.clamp(v.min(target + 0.01), v.max(target - 0.01))
-    });
+/// Uses the hotbar slot: it gets the tile hovered in the selection if there is one, otherwise its tile is placed from then on.
+///
+/// The tile has to be placeable right now, or the player is told why not.
+pub fn use_hotbar_slot(state: &mut GameState, game_data: &mut DataMap, slot: usize) {
+    let mut hotbar = read_hotbar(&state.resource_man, game_data);
 
-    r
+    if let Some(id) = state.ui_state.hovered_selection_tile {
+        hotbar[slot] = Some(id);
+        write_hotbar(&state.resource_man, game_data, &hotbar);
+
+        return;
+    }
+
+    let Some(id) = hotbar[slot] else {
+        return;
+    };
+
+    if let Err(failure) = placement_requirements(&state.resource_man, game_data, id) {
+        let text = failure_text(state, failure);
+        state.ui_state.push_toast(ToastLevel::Warning, text);
+
+        return;
+    }
+
+    state.ui_state.already_placed_at = None;
+    state.ui_state.selected_tile_id = Some(id);
+    state.ui_state.selected_tile_data = None;
 }
 
-fn has_category_item(state: &mut GameState, game_data: &mut DataMap, id: Id) -> bool {
-    let category = state.resource_man.registry.categories[&id];
+/// Draws the hotbar, with the number key of each slot under it.
+fn draw_hotbar(state: &mut GameState, game_data: &mut DataMap) -> Option<SelectionResponse> {
+    let world_matrix = IconMode::Tile.world_matrix();
+    let model_matrix = IconMode::Tile.model_matrix();
+
+    let hotbar = read_hotbar(&state.resource_man, game_data);
+    let mut response = None;
+
+    for (slot, id) in hotbar.into_iter().enumerate() {
+        let clicked = interactive(|| {
+            col(|| {
+                if let Some(id) = id {
+                    // in the same rounded slot as the empty ones, with the panel showing through
+                    GameObject::new(
+                        InstanceData::default(),
+                        UiGameObjectType::Tile(id, DataMap::default()),
+                        vec2(MEDIUM_ICON_SIZE, MEDIUM_ICON_SIZE),
+                        Some(model_matrix),
+                        Some(world_matrix),
+                    )
+                    .with_corner_radius(4.0)
+                    .show();
+                } else {
+                    let mut empty = RoundRect::new(4.0, colors::BACKGROUND_3);
+                    empty.min_size = vec2(MEDIUM_ICON_SIZE, MEDIUM_ICON_SIZE);
+                    empty.show();
+                }
+
+                label(&((slot + 1) % HOTBAR_SIZE).to_string());
+            });
+        })
+        .clicked;
 
-    if let Some(item) = category.item {
-        if let Some(Data::Inventory(inventory)) =
-            game_data.get_mut(state.resource_man.registry.data_ids.player_inventory)
-        {
-            inventory.get(item) > 0
-        } else {
-            false
+        if clicked && id.is_some() {
+            response = if state.input_handler.key_active(ActionType::HotkeyActive) {
+                Some(SelectionResponse::ClearHotbar(slot))
+            } else {
+                Some(SelectionResponse::Hotbar(slot))
+            };
         }
-    } else {
-        true
     }
+
+    response
 }
 
-/// Draws the tile selection.
-fn draw_tile_selection(
+/// Whether the tile can be placed from the selection, when shown under the category. Tiles outside of it need its category's item.
+fn tile_active(
+    state: &GameState,
+    game_data: &mut DataMap,
+    id: TileId,
+    current_category: Option<Id>,
+) -> bool {
+    let category = state.resource_man.registry.tiles[&id]
+        .category
+        .or(current_category);
+
+    is_default_tile(&state.resource_man, id)
+        || category.map_or(true, |category| {
+            missing_category_item(category, &state.resource_man, game_data).is_none()
+        })
+}
+
+/// Finds the tiles shown in the selection. While searching, they are the tiles matching the search in every category.
+fn shown_tiles(
     state: &mut GameState,
     game_data: &mut DataMap,
-    selection_send: &mut Option<oneshot::Sender<TileId>>,
+    placeable: &HashSet<TileId>,
     current_category: Option<Id>,
+    search: &str,
+) -> Vec<TileId> {
+    state
+        .resource_man
+        .ordered_tiles
+        .iter()
+        .filter(|id| placeable.contains(*id))
+        .filter(|id| {
+            if !search.is_empty() {
+                let name = state.resource_man.tile_name(**id);
+
+                return state
+                    .ui_state
+                    .text_field
+                    .fuse
+                    .fuzzy_match(&name, search)
+                    .is_some();
+            }
+
+            state.resource_man.registry.tiles[*id]
+                .category
+                .map_or(true, |category| Some(category) == current_category)
+        })
+        .copied()
+        .collect()
+}
+
+/// Draws a tile that can be picked, tilting it while hovered. Clicking it selects it if it is active, and right-clicking it
+/// toggles it as a favorite.
+///
+/// Returns whether it is hovered.
+fn draw_selection_tile(
+    state: &GameState,
+    selected: &mut Option<SelectionResponse>,
+    id: TileId,
+    active: bool,
     size: Float,
-) -> Option<(TileId, bool)> {
+    badge: Option<&str>,
+) -> bool {
     let world_matrix = IconMode::Tile.world_matrix();
 
-    let has_item = if let Some(category) = current_category {
-        has_category_item(state, game_data, category)
+    let hover_anim_active = use_state(|| false);
+
+    let rotate = Matrix4::from_rotation_x(tile_hover_z_angle(
+        state.loop_store.elapsed.as_secs_f32() * 5.0,
+        hover_anim_active.get(),
+    ));
+
+    let color_offset = if active {
+        Default::default()
     } else {
-        true
+        colors::INACTIVE.to_linear()
     };
 
+    let response = interactive(|| {
+        ui_game_object(
+            InstanceData::default().with_color_offset(color_offset),
+            UiGameObjectType::Tile(id, DataMap::default()),
+            vec2(size, size),
+            Some(rotate),
+            Some(world_matrix),
+        );
+
+        // the key that picks it, in the corner
+        if let Some(badge) = badge {
+            reflow(Alignment::TOP_LEFT, Pivot::TOP_LEFT, Dim2::ZERO, || {
+                RoundRect::new(4.0, colors::BACKGROUND_3).show_children(|| {
+                    Pad::all(PADDING_XSMALL).show(|| {
+                        colored_sized_text(badge, colors::BLACK, SMALL_SIZE).show();
+                    });
+                });
+            });
+        }
+    });
+
+    hover_anim_active.set(response.hovering);
+
+    if response.secondary_clicked {
+        *selected = Some(SelectionResponse::ToggleFavorite(id));
+    } else if active && response.clicked {
+        *selected = Some(SelectionResponse::Select(id));
+    }
+
+    response.hovering
+}
+
+/// Draws the tile selection, wrapping into rows that fit the window and scrolling once there are too many of them.
+fn draw_tile_selection(
+    state: &mut GameState,
+    game_data: &mut DataMap,
+    selected: &mut Option<SelectionResponse>,
+    tiles: &[TileId],
+    current_category: Option<Id>,
+    size: Float,
+) -> Option<(TileId, bool)> {
+    let max = Vec2::new(
+        state.ui_viewport().x * SELECTION_WIDTH,
+        size * SELECTION_ROWS + PADDING_MEDIUM * 2.0,
+    );
+
     let mut hovered = None;
 
-    for id in &state.resource_man.ordered_tiles {
-        if let Some(category) = state.resource_man.registry.tiles[id].category {
-            if Some(category) != current_category {
-                continue;
-            }
-        }
+    scroll_grid(max, size, PADDING_MEDIUM, tiles.len(), |index| {
+        let id = tiles[index];
+        let active = tile_active(state, game_data, id, current_category);
 
-        let is_default_tile = match state.resource_man.registry.tiles[id]
-            .data
-            .get(state.resource_man.registry.data_ids.default_tile)
-        {
-            Some(Data::Bool(v)) => *v,
-            _ => false,
-        };
-
-        if !is_default_tile {
-            if let Some(research) = state.resource_man.get_research_by_unlock(*id) {
-                if !is_research_unlocked(research.id, &state.resource_man, game_data) {
-                    continue;
-                }
-            } else {
-                continue;
-            }
+        let badge = (index < TILE_SELECTION_KEYS)
+            .then(|| binding_text(state, ActionType::SelectTile(index as u8)))
+            .flatten();
+
+        if draw_selection_tile(state, selected, id, active, size, badge.as_deref()) {
+            hovered = Some((id, active));
         }
+    });
 
-        let active = is_default_tile || has_item;
-
-        let hover_anim_active = use_state(|| false);
-
-        let rotate = Matrix4::from_rotation_x(tile_hover_z_angle(
-            state.loop_store.elapsed.as_secs_f32() * 5.0,
-            hover_anim_active.get(),
-        ));
-
-        let color_offset = if active {
-            Default::default()
-        } else {
-            colors::INACTIVE.to_linear()
-        };
-
-        let response = interactive(|| {
-            ui_game_object(
-                InstanceData::default().with_color_offset(color_offset),
-                UiGameObjectType::Tile(*id, DataMap::default()),
-                vec2(size, size),
-                Some(rotate),
-                Some(world_matrix),
-            );
-        });
+    hovered
+}
 
-        hover_anim_active.set(response.hovering);
+/// Draws the favorite tiles, whichever category is shown. The ones that can't be placed right now are grayed out.
+fn draw_favorites(
+    state: &mut GameState,
+    game_data: &mut DataMap,
+    selected: &mut Option<SelectionResponse>,
+    placeable: &HashSet<TileId>,
+    favorites: &[TileId],
+) -> Option<(TileId, bool)> {
+    let mut hovered = None;
 
-        if response.hovering {
-            hovered = Some((*id, active));
-        }
+    for id in favorites {
+        let active = placeable.contains(id) && tile_active(state, game_data, *id, None);
 
-        if active && response.clicked {
-            if let Some(send) = selection_send.take() {
-                send.send(*id).unwrap();
-            }
+        if draw_selection_tile(state, selected, *id, active, MEDIUM_ICON_SIZE, None) {
+            hovered = Some((*id, active));
         }
     }
 
     hovered
 }
 
-/// Creates the tile selection GUI.
+/// Creates the tile selection GUI, returning what the player picked in it.
 pub fn tile_selections(
     state: &mut GameState,
     game_data: &mut DataMap,
-    selection_send: oneshot::Sender<TileId>,
-) {
+) -> Option<SelectionResponse> {
     let world_matrix = IconMode::Tile.world_matrix();
     let model_matrix = IconMode::Tile.model_matrix();
 
     let mut hovered_category = None;
     let mut hovered_tile = None;
+    let mut selected = None;
+
+    state.ui_state.tile_selection_shown = true;
+    state.input_hints.push_context(
+        [
+            ActionType::SelectTile(0),
+            ActionType::PreviousCategory,
+            ActionType::NextCategory,
+        ]
+        .into_iter()
+        .map(|action| ActionHint::new(vec![action]).with_priority(SCREEN_HINT_PRIORITY))
+        .collect(),
+    );
+
+    let favorites = read_favorites(&state.resource_man, game_data);
+    let placeable = state
+        .loop_store
+        .placeable_tiles(&state.resource_man, game_data);
+
+    let search = state.ui_state.text_field.get(TextField::TileSearch).clone();
+    let tiles = shown_tiles(
+        state,
+        game_data,
+        &placeable,
+        state.ui_state.tile_selection_category,
+        &search,
+    );
 
     Layer::new().show(|| {
         Absolute::new(Alignment::BOTTOM_CENTER, Pivot::BOTTOM_CENTER, Dim2::ZERO).show(|| {
             viewport_constrained(|| {
                 center_col(|| {
+                    RoundRect::new(8.0, colors::BACKGROUND_1).show_children(|| {
+                        row(|| {
+                            selected = draw_hotbar(state, game_data);
+                        });
+                    });
+
+                    RoundRect::new(8.0, colors::BACKGROUND_1).show_children(|| {
+                        let response = textbox(
+                            state.ui_state.text_field.get(TextField::TileSearch),
+                            None,
+                            Some(
+                                &state
+                                    .resource_man
+                                    .gui_str(state.resource_man.registry.gui_ids.lbl_search_tiles),
+                            ),
+                        );
+
+                        // the only match is picked right away
+                        if response.activated {
+                            if let [id] = tiles[..] {
+                                if tile_active(
+                                    state,
+                                    game_data,
+                                    id,
+                                    state.ui_state.tile_selection_category,
+                                ) {
+                                    selected = Some(SelectionResponse::Select(id));
+                                }
+                            }
+                        }
+                    });
+
+                    if !favorites.is_empty() {
+                        RoundRect::new(8.0, colors::BACKGROUND_1).show_children(|| {
+                            scroll_horizontal_bar_alignment(
+                                Vec2::ZERO,
+                                Vec2::INFINITY,
+                                None,
+                                || {
+                                    row(|| {
+                                        if let Some(hovered) = draw_favorites(
+                                            state,
+                                            game_data,
+                                            &mut selected,
+                                            &placeable,
+                                            &favorites,
+                                        ) {
+                                            hovered_tile = Some(hovered);
+                                        }
+                                    });
+                                },
+                            );
+                        });
+                    }
+
                     RoundRect::new(8.0, colors::BACKGROUND_1).show_children(|| {
                         scroll_horizontal_bar_alignment(Vec2::ZERO, Vec2::INFINITY, None, || {
                             row(|| {
@@ -208,39 +469,56 @@ pub fn tile_selections(
                     });
 
                     RoundRect::new(8.0, colors::BACKGROUND_1).show_children(|| {
-                        scroll_horizontal_bar_alignment(Vec2::ZERO, Vec2::INFINITY, None, || {
-                            row(|| {
-                                hovered_tile = draw_tile_selection(
-                                    state,
-                                    game_data,
-                                    &mut Some(selection_send),
-                                    state.ui_state.tile_selection_category,
-                                    LARGE_ICON_SIZE,
-                                );
-                            });
-                        });
+                        hovered_tile = draw_tile_selection(
+                            state,
+                            game_data,
+                            &mut selected,
+                            &tiles,
+                            state.ui_state.tile_selection_category,
+                            LARGE_ICON_SIZE,
+                        );
                     });
                 });
             });
         });
     });
 
+    // the keys pick the first tiles as if they were clicked
+    if selected.is_none() {
+        for (index, id) in tiles.iter().take(TILE_SELECTION_KEYS).enumerate() {
+            if state
+                .input_handler
+                .key_active(ActionType::SelectTile(index as u8))
+                && tile_active(
+                    state,
+                    game_data,
+                    *id,
+                    state.ui_state.tile_selection_category,
+                )
+            {
+                selected = Some(SelectionResponse::Select(*id));
+            }
+        }
+    }
+
+    state.ui_state.hovered_selection_tile = hovered_tile.map(|(id, _)| id);
+
     Layer::new().show(|| {
         if let Some(id) = hovered_category {
-            hover_tip(|| {
+            hover_tip(id, || {
                 label(&state.resource_man.category_name(id));
             });
         }
 
         if let Some((id, active)) = hovered_tile {
-            hover_tip(|| {
+            hover_tip(id, || {
                 col(|| {
                     label(&state.resource_man.tile_name(id));
 
                     if !active {
-                        if let Some(item) = state
-                            .ui_state
-                            .tile_selection_category
+                        if let Some(item) = state.resource_man.registry.tiles[&id]
+                            .category
+                            .or(state.ui_state.tile_selection_category)
                             .and_then(|id| state.resource_man.registry.categories[&id].item)
                         {
                             label(
@@ -262,4 +540,6 @@ pub fn tile_selections(
             });
         }
     });
+
+    selected
 }
@@ -0,0 +1,278 @@
+use crate::GameState;
+use automancy_defs::colors;
+use automancy_resources::format::Formattable;
+use automancy_system::game::GameSystemMessage;
+use automancy_system::trim::{trim_map, TrimKeep, TrimPreview, TrimProgress, DEFAULT_TRIM_RADIUS};
+use automancy_system::ui_state::{PopupState, ToastLevel};
+use automancy_ui::{button, col, label, movable, radio, slider, window};
+use ractor::rpc::CallResult;
+use yakui::widgets::Layer;
+
+/// How far out the radius that is kept can be set.
+const MAX_TRIM_RADIUS: i32 = 512;
+
+/// Finds the tiles the trim would remove again, if what is kept changed since the last time.
+fn update_preview(state: &mut GameState) {
+    let keep = state.ui_state.trim_keep;
+
+    if state
+        .loop_store
+        .trim_preview
+        .as_ref()
+        .is_some_and(|(last, _)| *last == keep)
+    {
+        return;
+    }
+
+    let Some(info) = state.loop_store.map_info.as_ref().map(|v| v.0.clone()) else {
+        return;
+    };
+
+    let Ok(CallResult::Success((tiles, _))) = state
+        .tokio
+        .block_on(state.game.call(GameSystemMessage::GetAllTiles, None))
+    else {
+        return;
+    };
+
+    let preview = TrimPreview::build(
+        &state.resource_man,
+        &tiles,
+        &info.blocking_lock().regions,
+        keep,
+    );
+
+    state.loop_store.trim_preview = Some((keep, preview));
+}
+
+/// Draws the window for picking what is kept when trimming the map, and showing what would be removed.
+pub fn trim_window(state: &mut GameState) {
+    update_preview(state);
+
+    let regions = state
+        .loop_store
+        .map_info
+        .as_ref()
+        .map(|v| {
+            v.0.blocking_lock()
+                .regions
+                .iter()
+                .map(|region| region.name.clone())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    let running = matches!(
+        *state.loop_store.trim_progress.blocking_lock(),
+        Some(TrimProgress::BackingUp | TrimProgress::Removing { .. })
+    );
+
+    Layer::new().show(|| {
+        let mut pos = state.ui_state.trim_ui_position;
+        movable(&mut pos, || {
+            window(
+                state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.trim_map)
+                    .to_string(),
+                || {
+                    col(|| {
+                        let radius_option = match state.ui_state.trim_keep {
+                            TrimKeep::Radius { .. } => state.ui_state.trim_keep,
+                            TrimKeep::Region(_) => TrimKeep::Radius {
+                                center: state.camera.pointing_at,
+                                radius: DEFAULT_TRIM_RADIUS,
+                            },
+                        };
+
+                        radio(&mut state.ui_state.trim_keep, radius_option, || {
+                            if let TrimKeep::Radius { center, radius } = radius_option {
+                                label(&state.resource_man.gui_fmt(
+                                    state.resource_man.registry.gui_ids.lbl_trim_radius,
+                                    [
+                                        ("radius", Formattable::integer(&radius)),
+                                        ("center", Formattable::display(&center)),
+                                    ],
+                                ));
+                            }
+                        });
+
+                        if let TrimKeep::Radius { radius, .. } = &mut state.ui_state.trim_keep {
+                            // the slider only takes signed numbers
+                            let mut value = *radius as i32;
+
+                            slider(
+                                &mut value,
+                                0..=MAX_TRIM_RADIUS,
+                                None,
+                                |v| v.parse().ok(),
+                                |v| format!("{: >3}", v),
+                            );
+
+                            *radius = value as u32;
+                        }
+
+                        for (index, name) in regions.iter().enumerate() {
+                            radio(
+                                &mut state.ui_state.trim_keep,
+                                TrimKeep::Region(index),
+                                || {
+                                    label(name);
+                                },
+                            );
+                        }
+
+                        if button(
+                            &state
+                                .resource_man
+                                .gui_str(state.resource_man.registry.gui_ids.btn_cancel),
+                        )
+                        .clicked
+                        {
+                            state.ui_state.trim_open = false;
+                        }
+
+                        let Some((_, preview)) = &state.loop_store.trim_preview else {
+                            return;
+                        };
+
+                        label(&state.resource_man.gui_fmt(
+                            state.resource_man.registry.gui_ids.lbl_trim_preview,
+                            [("count", Formattable::integer(&preview.doomed.len()))],
+                        ));
+
+                        for (category, count) in &preview.by_category {
+                            label(&format!(
+                                "{}: {count}",
+                                state.resource_man.try_category_name(*category)
+                            ));
+                        }
+
+                        if !running
+                            && !preview.doomed.is_empty()
+                            && button(
+                                &state
+                                    .resource_man
+                                    .gui_str(state.resource_man.registry.gui_ids.btn_trim),
+                            )
+                            .clicked
+                        {
+                            state.ui_state.popup =
+                                PopupState::TrimConfirmation(preview.doomed.len());
+                        }
+                    });
+                },
+            );
+        });
+        state.ui_state.trim_ui_position = pos;
+    });
+}
+
+/// Draws the popup asking before the map is trimmed, and starts the trim once confirmed.
+pub fn trim_confirmation_popup(state: &mut GameState, count: usize) {
+    window(
+        state
+            .resource_man
+            .gui_str(state.resource_man.registry.gui_ids.trim_map)
+            .to_string(),
+        || {
+            label(&state.resource_man.gui_fmt(
+                state.resource_man.registry.gui_ids.lbl_trim_confirm,
+                [("count", Formattable::integer(&count))],
+            ));
+
+            if button(
+                &state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.btn_confirm),
+            )
+            .clicked
+            {
+                if let Some((_, preview)) = state.loop_store.trim_preview.take() {
+                    state.tokio.spawn(trim_map(
+                        state.game.clone(),
+                        preview.doomed,
+                        state.loop_store.trim_progress.clone(),
+                    ));
+                }
+
+                state.ui_state.popup = PopupState::None;
+            }
+
+            if button(
+                &state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.btn_cancel),
+            )
+            .clicked
+            {
+                state.ui_state.popup = PopupState::None;
+            }
+        },
+    );
+}
+
+/// Keeps the status up with how far the running trim has gotten, shows a toast once it is over, and tints the tiles the trim window would remove.
+pub fn trim_feedback(state: &mut GameState) {
+    let progress = *state.loop_store.trim_progress.blocking_lock();
+
+    if let Some(progress) = progress {
+        let text = match progress {
+            TrimProgress::BackingUp => state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.lbl_trim_backing_up)
+                .to_string(),
+            TrimProgress::Removing { removed, total } => state.resource_man.gui_fmt(
+                state.resource_man.registry.gui_ids.lbl_trimming,
+                [
+                    ("removed", Formattable::integer(&removed)),
+                    ("total", Formattable::integer(&total)),
+                ],
+            ),
+            TrimProgress::Done { removed } => state.resource_man.gui_fmt(
+                state.resource_man.registry.gui_ids.lbl_trim_done,
+                [("count", Formattable::integer(&removed))],
+            ),
+            TrimProgress::BackupFailed => state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.lbl_trim_backup_failed)
+                .to_string(),
+        };
+
+        match progress {
+            TrimProgress::BackingUp | TrimProgress::Removing { .. } => {
+                state.ui_state.show_status(text);
+            }
+            TrimProgress::Done { .. } => {
+                state.ui_state.status = None;
+                state.ui_state.push_toast(ToastLevel::Info, text);
+            }
+            TrimProgress::BackupFailed => {
+                state.ui_state.status = None;
+                state.ui_state.push_toast(ToastLevel::Error, text);
+            }
+        }
+
+        if matches!(
+            progress,
+            TrimProgress::Done { .. } | TrimProgress::BackupFailed
+        ) {
+            *state.loop_store.trim_progress.blocking_lock() = None;
+            state.loop_store.trim_preview = None;
+            state.loop_store.region_index = None;
+            *state.loop_store.flow_graph.blocking_lock() = None;
+        }
+    }
+
+    if !state.ui_state.trim_open {
+        return;
+    }
+
+    if let Some((_, preview)) = &state.loop_store.trim_preview {
+        let tints = &mut state.renderer.as_mut().unwrap().tile_tints;
+
+        for coord in &preview.doomed {
+            tints.insert(*coord, colors::RED.with_alpha(0.5).to_linear());
+        }
+    }
+}
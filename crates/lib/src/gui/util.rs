@@ -1,46 +1,54 @@
+use crate::gui::overlay::fullness_color;
 use crate::renderer::GameRenderer;
 use crate::GameState;
+use automancy_defs::colors;
 use automancy_defs::coord::TileCoord;
 use automancy_defs::id::{ModelId, TileId};
-use automancy_defs::math::Matrix4;
+use automancy_defs::math::{Float, Matrix4};
 use automancy_defs::rendering::GameMatrix;
+use automancy_defs::stack::ItemAmount;
 use automancy_defs::{
     id::{Id, SharedStr},
     rendering::InstanceData,
 };
 use automancy_resources::data::DataMap;
+use automancy_resources::format::Formattable;
+use automancy_resources::inventory::{Inventory, InventoryLimits};
 use automancy_resources::rhai_render::RenderCommand;
 use automancy_resources::types::IconMode;
 use automancy_resources::ResourceManager;
 use automancy_system::game::TAKE_ITEM_ANIMATION_SPEED;
+use automancy_system::input::ActionType;
 use automancy_system::tile_entity::collect_render_commands;
-use automancy_system::ui_state::TextField;
+use automancy_system::ui_state::{TextField, ToastLevel};
 use automancy_ui::{
-    col, group, hover_tip, radio, scroll_vertical, textbox, ui_game_object, UiGameObjectType,
-    HOVER_TIP,
+    col, fill_bar, group, hover_tip, interactive, label, label_text, radio, scroll_vertical,
+    textbox, toast, ui_game_object, UiGameObjectType, HOVER_TIP, PADDING_LARGE,
 };
 use fuzzy_matcher::FuzzyMatcher;
 use hashbrown::{HashMap, HashSet};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use yakui::{constrained, Constraints};
 use yakui::{
-    widgets::{Absolute, Layer},
-    Alignment, Dim2, Pivot, Rect, Vec2,
+    widgets::{Absolute, Layer, Pad},
+    Alignment, Color, Dim2, Pivot, Rect, Vec2,
 };
 
+/// The width of the storage fill bars.
+const STORAGE_FILL_WIDTH: f32 = 160.0;
+
 pub fn render_overlay_cached(
     resource_man: &ResourceManager,
     renderer: &mut GameRenderer,
     tile_id: Option<TileId>,
     mut data: DataMap,
-    cache: &mut Option<(TileId, Vec<ModelId>)>,
+    cache: &mut Option<(TileId, Vec<(ModelId, Matrix4)>)>,
+    instance: InstanceData,
     model_matrix: Matrix4,
     world_matrix: Matrix4,
 ) {
     if let Some(tile_id) = tile_id {
-        let mut transforms = HashMap::new();
-
         let cached_tile_id = cache.as_ref().map(|v| v.0);
 
         if cached_tile_id != Some(tile_id) {
@@ -53,7 +61,7 @@ pub fn render_overlay_cached(
                 true,
                 false,
             ) {
-                transforms = commands
+                let mut transforms = commands
                     .iter()
                     .flat_map(|v| match v {
                         RenderCommand::Transform {
@@ -68,7 +76,10 @@ pub fn render_overlay_cached(
                 let models = commands
                     .into_iter()
                     .flat_map(|v| match v {
-                        RenderCommand::Track { model, .. } => Some(model),
+                        // kept along with the models, so that the tile is drawn turned the same way every frame
+                        RenderCommand::Track { model, .. } => {
+                            Some((model, transforms.remove(&model).unwrap_or_default()))
+                        }
                         _ => None,
                     })
                     .collect::<Vec<_>>();
@@ -78,17 +89,15 @@ pub fn render_overlay_cached(
         }
 
         if let Some((.., models)) = &cache {
-            for model in models {
-                let transform = transforms.remove(model).unwrap_or_default();
-
+            for (model, transform) in models {
                 let (model, (meshes, ..)) = resource_man.mesh_or_missing_tile_mesh(model);
 
                 for mesh in meshes.iter().flatten() {
                     renderer.overlay_instances.push((
-                        InstanceData::default().with_alpha(0.6),
+                        instance,
                         model,
                         GameMatrix::<true>::new(
-                            transform * model_matrix,
+                            *transform * model_matrix,
                             world_matrix,
                             mesh.matrix,
                         ),
@@ -152,6 +161,92 @@ pub fn searchable_id(
     });
 }
 
+/// Formats an amount according to the user's amount display options.
+pub fn format_amount(state: &GameState, amount: ItemAmount) -> String {
+    state
+        .resource_man
+        .format_amount(amount.into(), state.options.gui.compact_threshold())
+}
+
+/// Draws a label containing a formatted amount. If the amount got compacted, the exact amount is shown when hovered.
+pub fn amount_label(state: &GameState, amount: ItemAmount, text: impl FnOnce(&str) -> String) {
+    let formatted = format_amount(state, amount);
+    let full = state.resource_man.format_amount(amount.into(), None);
+
+    let hover = interactive(|| {
+        label(&text(&formatted));
+    });
+
+    if hover.hovering && formatted != full {
+        HOVER_TIP.set(Some(label_text(&full)));
+    }
+}
+
+/// Draws how much of its limits the inventory uses, as "used/total" along with a fill bar. Draws nothing without limits.
+pub fn storage_fill(state: &GameState, inventory: &Inventory, limits: InventoryLimits) {
+    let Some(fullness) = inventory.fullness(limits) else {
+        return;
+    };
+
+    let gui_ids = &state.resource_man.registry.gui_ids;
+
+    if let Some(slots) = limits.slots {
+        label(&state.resource_man.gui_fmt(
+            gui_ids.lbl_storage_slots,
+            [
+                ("used", Formattable::integer(&inventory.used_slots())),
+                ("total", Formattable::integer(&slots)),
+            ],
+        ));
+    }
+    if let Some(capacity) = limits.capacity {
+        label(&state.resource_man.gui_fmt(
+            gui_ids.lbl_storage_capacity,
+            [
+                (
+                    "used",
+                    Formattable::display(&format_amount(state, inventory.total())),
+                ),
+                (
+                    "total",
+                    Formattable::display(&format_amount(state, capacity)),
+                ),
+            ],
+        ));
+    }
+
+    fill_bar(fullness, STORAGE_FILL_WIDTH, fullness_color(fullness));
+}
+
+/// Draws each fluid in the buffer as its name over a bar of how full its stack is.
+/// The fluids without a stack size are measured against the most of any fluid in the buffer.
+pub fn fluid_levels(state: &GameState, fluids: &Inventory) {
+    let most = fluids.values().copied().max().unwrap_or(0).max(1);
+
+    for (id, amount) in fluids.iter() {
+        if *amount <= 0 {
+            continue;
+        }
+
+        let max = state.resource_man.item_stack_size(*id).unwrap_or(most);
+        let fraction = (*amount as Float / max.max(1) as Float).clamp(0.0, 1.0);
+
+        label(&state.resource_man.item_name(*id));
+        fill_bar(fraction, STORAGE_FILL_WIDTH, colors::INPUT);
+    }
+}
+
+/// Gets the step of amount inputs, which holding the select mode key (x10) or the hotkey (x100) multiplies.
+pub fn amount_step(state: &GameState) -> ItemAmount {
+    if state.input_handler.key_active(ActionType::HotkeyActive) {
+        100
+    } else if state.input_handler.key_active(ActionType::SelectMode) {
+        10
+    } else {
+        1
+    }
+}
+
 pub fn take_item_animation(state: &mut GameState, id: Id, dst_rect: Rect) {
     let now = Instant::now();
 
@@ -216,10 +311,81 @@ pub fn take_item_animation(state: &mut GameState, id: Id, dst_rect: Rect) {
     }
 }
 
+const TOAST_DURATION: Duration = Duration::from_secs(3);
+/// Errors stay up for longer, as they are more likely to need reading.
+const ERROR_TOAST_DURATION: Duration = Duration::from_secs(6);
+/// How long before the toast is taken down it starts fading out.
+const TOAST_FADE: Duration = Duration::from_millis(500);
+
+fn toast_duration(level: ToastLevel) -> Duration {
+    match level {
+        ToastLevel::Info | ToastLevel::Warning => TOAST_DURATION,
+        ToastLevel::Error => ERROR_TOAST_DURATION,
+    }
+}
+
+fn toast_accent(level: ToastLevel) -> Color {
+    match level {
+        ToastLevel::Info => colors::INPUT,
+        ToastLevel::Warning => colors::ORANGE,
+        ToastLevel::Error => colors::RED,
+    }
+}
+
+/// How far the toast is from being faded out, from 1 when it is fully shown to 0 when it is gone.
+fn toast_opacity(shown_at: Instant, duration: Duration) -> f32 {
+    let left = duration.saturating_sub(shown_at.elapsed());
+
+    (left.as_secs_f32() / TOAST_FADE.as_secs_f32()).min(1.0)
+}
+
+/// Draws the status and the toasts that are up, the newest at the bottom, and takes down the ones that have been shown long enough.
+pub fn render_toasts(state: &mut GameState) {
+    state.ui_state.take_queued_toasts();
+
+    state
+        .ui_state
+        .toasts
+        .retain(|toast| toast.shown_at.elapsed() < toast_duration(toast.level));
+
+    if state
+        .ui_state
+        .status
+        .as_ref()
+        .is_some_and(|(_, shown_at)| shown_at.elapsed() >= TOAST_DURATION)
+    {
+        state.ui_state.status = None;
+    }
+
+    if state.ui_state.toasts.is_empty() && state.ui_state.status.is_none() {
+        return;
+    }
+
+    Layer::new().show(|| {
+        Absolute::new(Alignment::BOTTOM_RIGHT, Pivot::BOTTOM_RIGHT, Dim2::ZERO).show(|| {
+            Pad::all(PADDING_LARGE).show(|| {
+                col(|| {
+                    if let Some((text, shown_at)) = &state.ui_state.status {
+                        toast(text, colors::GRAY, toast_opacity(*shown_at, TOAST_DURATION));
+                    }
+
+                    for item in &state.ui_state.toasts {
+                        toast(
+                            &item.text,
+                            toast_accent(item.level),
+                            toast_opacity(item.shown_at, toast_duration(item.level)),
+                        );
+                    }
+                });
+            });
+        });
+    });
+}
+
 pub fn render_info_tip(state: &mut GameState) {
     if let Some(tip) = HOVER_TIP.take() {
         Layer::new().show(|| {
-            hover_tip(|| {
+            hover_tip(tip.text.to_string(), || {
                 constrained(
                     Constraints::loose(state.ui_viewport().min(Vec2::new(500.0, f32::INFINITY))),
                     || {
@@ -11,6 +11,7 @@ pub use bytemuck;
 pub use cosmic_text;
 pub use hashbrown;
 pub use log;
+pub use open;
 pub use ractor;
 pub use rhai;
 pub use ron;
@@ -26,6 +27,7 @@ pub use yakui_wgpu;
 pub use yakui_winit;
 
 pub mod event;
+pub mod frame_timing;
 pub mod gpu;
 pub mod gui;
 pub mod renderer;
@@ -1,11 +1,17 @@
+use crate::frame_timing::{CpuStage, FrameTimings, GpuPass, GpuTimer};
 use crate::gpu;
 use crate::gpu::{
     GlobalResources, Gpu, GuiResources, RenderResources, SharedResources, MODEL_DEPTH_CLEAR,
     NORMAL_CLEAR, SCREENSHOT_FORMAT,
 };
+use crate::gui::overlay::{overlay_color, strategic_color};
+use crate::ui_game_object::PresentSlots;
 use crate::GameState;
 use arboard::{Clipboard, ImageData};
-use automancy_defs::math::Matrix4;
+use automancy_defs::chrono::Local;
+use automancy_defs::glam::FloatExt;
+use automancy_defs::log;
+use automancy_defs::math::{Float, Matrix4};
 use automancy_defs::rendering::{GameUBO, InstanceData};
 use automancy_defs::{
     coord::TileCoord,
@@ -19,24 +25,32 @@ use automancy_defs::{
     rendering::{GpuInstance, MatrixData, WorldMatrixData},
     slice_group_by::GroupBy,
 };
+use automancy_resources::format::Formattable;
 use automancy_resources::rhai_render::RenderCommand;
 use automancy_resources::ResourceManager;
-use automancy_system::game::GameSystemMessage;
-use automancy_system::GameGui;
-use automancy_ui::{GameElementPaint, UiGameObjectType};
+use automancy_system::detail::{detail_level, DetailLevel, DetailPreset, DetailThresholds};
+use automancy_system::game::{GameSystemMessage, RenderFrame};
+use automancy_system::options::MsaaSamples;
+use automancy_system::tile_entity::TileStatus;
+use automancy_system::ui_state::{queue_toast, OverlayMode, ToastLevel};
+use automancy_system::{GameGui, Screenshot};
+use automancy_ui::{ease_out_cubic, GameElementPaint, UiGameObjectType, ANIMATIONS_ENABLED};
 use hashbrown::{HashMap, HashSet};
-use image::{EncodableLayout, RgbaImage};
+use image::{EncodableLayout, ImageError, ImageFormat, RgbaImage};
 use ordermap::OrderMap;
 use range_set_blaze::RangeSetBlaze;
 use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::mem;
-use std::sync::Arc;
-use std::time::Instant;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use std::{collections::VecDeque, ops::Mul};
+use std::{fs, thread};
 use tokio::sync::oneshot;
 use wgpu::{
-    util::DrawIndexedIndirectArgs, BufferAddress, BufferDescriptor, BufferUsages, Color,
+    util::DrawIndexedIndirectArgs, Buffer, BufferAddress, BufferDescriptor, BufferUsages, Color,
     CommandEncoderDescriptor, ImageCopyBuffer, ImageDataLayout, IndexFormat, LoadOp, Maintain,
     MapMode, Operations, RenderPassColorAttachment, RenderPassDepthStencilAttachment,
     RenderPassDescriptor, SurfaceError, TextureDescriptor, TextureDimension, TextureFormat,
@@ -71,12 +85,100 @@ pub struct YakuiRenderResources {
 
     pub packed_size: Option<UVec2>,
     pub rects: Vec<Option<crunch::Rect>>,
+    pub present_slots: PresentSlots,
 
     pub animation_cache: AnimationCache,
 }
 
 const WE_ONLY_USE_1_WORLD_MATRIX_IN_GAME_LOL: u32 = 0;
 
+/// How long a tile takes to scale in after being placed, or to shrink away after being removed.
+pub const TILE_ANIMATION_DURATION: Duration = Duration::from_millis(150);
+/// How much later each tile of a bulk placement starts animating than the one before it.
+const TILE_ANIMATION_STAGGER: Duration = Duration::from_millis(8);
+/// The longest a bulk placement is staggered over.
+const MAX_TILE_ANIMATION_STAGGER: Duration = Duration::from_millis(400);
+/// The most tile animations kept at once. Past this, tiles simply appear and disappear.
+const MAX_TILE_ANIMATIONS: usize = 4096;
+/// How big a tile starts out when placed, and ends up when removed.
+const TILE_ANIMATION_SCALE: Float = 0.7;
+
+type RenderObjectKey = (TileCoord, RenderTagId, ModelId, usize);
+type TransformCommands = BTreeMap<ModelId, Vec<(TileCoord, RenderTagId, Matrix4)>>;
+
+/// The folder screenshots are saved into.
+pub static SCREENSHOTS_PATH: &str = "screenshots";
+
+/// A screenshot being read back from the GPU. It is saved once the buffer is mapped, a few frames later.
+struct PendingScreenshot {
+    buffer: Buffer,
+    width: u32,
+    height: u32,
+    block_size: u32,
+    /// the length of each row in the buffer, padded to [COPY_BYTES_PER_ROW_ALIGNMENT]
+    padded_width: u32,
+    /// whether mapping the buffer worked, once it is done
+    mapped: Arc<Mutex<Option<bool>>>,
+}
+
+/// Takes the pixels out of the padded rows of a screenshot buffer, making them opaque.
+fn screenshot_image(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    block_size: u32,
+    padded_width: u32,
+) -> Option<RgbaImage> {
+    let texture_width = (width * block_size) as usize;
+
+    let mut result = Vec::<u8>::new();
+    for chunk in data.chunks_exact(padded_width as usize) {
+        for pixel in chunk[..texture_width].chunks_exact(4) {
+            result.extend(&[pixel[0], pixel[1], pixel[2], 255]);
+        }
+    }
+
+    RgbaImage::from_vec(width, height, result)
+}
+
+/// Saves the screenshot into the [SCREENSHOTS_PATH] folder, named after the time, and shows a toast with where it went.
+///
+/// The encoding is done on a thread of its own, as it takes long enough to be noticed in the frame time.
+fn save_screenshot(resource_man: Arc<ResourceManager>, image: RgbaImage) {
+    thread::spawn(move || {
+        let path = PathBuf::from(SCREENSHOTS_PATH).join(format!(
+            "{}.png",
+            Local::now().format("%Y-%m-%d_%H-%M-%S%.3f")
+        ));
+
+        let result = fs::create_dir_all(SCREENSHOTS_PATH)
+            .map_err(ImageError::IoError)
+            .and_then(|_| image.save_with_format(&path, ImageFormat::Png));
+
+        let gui_ids = &resource_man.registry.gui_ids;
+        match result {
+            Ok(()) => queue_toast(
+                ToastLevel::Info,
+                resource_man.gui_fmt(
+                    gui_ids.lbl_screenshot_saved,
+                    [("path", Formattable::display(&path.display()))],
+                ),
+            ),
+            Err(err) => {
+                log::error!("Could not save the screenshot to {path:?}: {err}");
+
+                queue_toast(
+                    ToastLevel::Error,
+                    resource_man.gui_fmt(
+                        gui_ids.lbl_screenshot_failed,
+                        [("error", Formattable::display(&err))],
+                    ),
+                );
+            }
+        }
+    });
+}
+
 pub struct GameRenderer {
     pub gpu: Gpu,
     pub shared_resources: SharedResources,
@@ -88,13 +190,50 @@ pub struct GameRenderer {
     pub tile_tints: HashMap<TileCoord, Vec4>,
     last_tile_tints: HashMap<TileCoord, Vec4>,
 
+    /// the colors of the flat hexagons drawn in place of the tiles in the strategic view
+    pub strategic_tiles: HashMap<TileCoord, Vec4>,
+    /// the statuses of the tiles in view as of the last frame, if the overlays needed them
+    pub tile_statuses: HashMap<TileCoord, TileStatus>,
+    last_strategic_blend: Float,
+
     pub take_item_animations: HashMap<Id, VecDeque<(Instant, Rect)>>,
 
-    object_ids: OrderMap<(TileCoord, RenderTagId, ModelId, usize), ()>,
+    /// when each recently placed tile started scaling in
+    placed_animations: HashMap<TileCoord, Instant>,
+    /// when each recently removed tile started shrinking away, until its render objects are gone
+    removed_animations: HashMap<TileCoord, Instant>,
+    /// what is left of removed tiles while they shrink away: the start time, the coordinate, the model, the mesh index, and the full model matrix
+    ghosts: Vec<(Instant, TileCoord, ModelId, usize, Matrix4)>,
+    /// the unscaled matrices of the render objects that are scaling in
+    animated_base_matrices: HashMap<RenderObjectKey, MatrixData>,
+
+    /// the number of frames rendered so far
+    frame: u64,
+    /// the camera position and the detail thresholds of the last frame, which detail levels are computed from
+    camera_pos: Vec3,
+    detail_thresholds: DetailThresholds,
+    /// the latest transforms of the tiles that are not updated every frame, waiting for their next update
+    deferred_transforms: HashMap<(TileCoord, RenderTagId, ModelId), Matrix4>,
+    /// how many tiles were drawn at each detail level in the last frame, indexed by [DetailLevel]
+    pub detail_counts: [usize; DetailLevel::ALL.len()],
+
+    object_ids: OrderMap<RenderObjectKey, ()>,
     coord_to_keys: HashMap<TileCoord, HashSet<(RenderTagId, ModelId, usize)>>,
 
     instance_ranges: BTreeMap<(ModelId, usize), RangeSetBlaze<usize>>,
     instances: Vec<GpuInstance>,
+    /// the instances grouped by model and mesh, which is what the world is drawn from, with one draw per group
+    batched_instances: Vec<GpuInstance>,
+    /// where each instance is in the batched instances, by its index in the instances. usize::MAX for the unused ones
+    batch_positions: Vec<usize>,
+    /// the range of the batched instances each model and mesh is drawn from
+    batches: Vec<((ModelId, usize), Range<u32>)>,
+    /// whether anything was tracked or untracked since the instances were last grouped
+    batches_dirty: bool,
+    /// how many draws the world took in the last frame
+    pub world_draws: usize,
+    /// how many instances were drawn at each level of detail of their models in the last frame, 0 being the models themselves
+    pub lod_counts: Vec<usize>,
     matrix_data_map: Vec<MatrixData>,
     animation_matrix_data_map: AnimationMatrixDataMap,
 
@@ -104,9 +243,19 @@ pub struct GameRenderer {
 
     gui_packed_size: Option<UVec2>,
     gui_rects: Vec<Option<crunch::Rect>>,
+    gui_present_slots: PresentSlots,
 
     animation_cache: AnimationCache,
-    screenshot_clipboard: Clipboard,
+    /// the clipboard screenshots and copied text are put on. It is kept around, since the clipboard can lose what was put on it once it is dropped
+    pub clipboard: Clipboard,
+
+    /// the screenshots being read back from the GPU, to be saved once they are
+    pending_screenshots: Vec<PendingScreenshot>,
+
+    /// where the time of the latest frames went, for the debugger
+    pub frame_timings: FrameTimings,
+    /// times the render passes, if the adapter supports timestamp queries
+    gpu_timer: Option<GpuTimer>,
 }
 
 impl GameRenderer {
@@ -116,6 +265,11 @@ impl GameRenderer {
         render_resources: RenderResources,
         global_resources: Arc<GlobalResources>,
     ) -> Self {
+        let frame_timings = FrameTimings::new(gpu.timestamps);
+        let gpu_timer = gpu
+            .timestamps
+            .then(|| GpuTimer::new(&gpu.device, &gpu.queue));
+
         Self {
             gpu,
             shared_resources,
@@ -124,15 +278,35 @@ impl GameRenderer {
 
             tile_tints: Default::default(),
             last_tile_tints: Default::default(),
+            strategic_tiles: Default::default(),
+            tile_statuses: Default::default(),
+            last_strategic_blend: 0.0,
             overlay_instances: Default::default(),
 
             take_item_animations: Default::default(),
 
+            placed_animations: Default::default(),
+            removed_animations: Default::default(),
+            ghosts: Default::default(),
+            animated_base_matrices: Default::default(),
+
+            frame: 0,
+            camera_pos: Vec3::ZERO,
+            detail_thresholds: DetailPreset::default().thresholds(),
+            deferred_transforms: Default::default(),
+            detail_counts: Default::default(),
+
             object_ids: Default::default(),
             coord_to_keys: Default::default(),
 
             instance_ranges: Default::default(),
             instances: Default::default(),
+            batched_instances: Default::default(),
+            batch_positions: Default::default(),
+            batches: Default::default(),
+            batches_dirty: false,
+            world_draws: 0,
+            lod_counts: Default::default(),
             matrix_data_map: Default::default(),
             animation_matrix_data_map: Default::default(),
 
@@ -142,9 +316,135 @@ impl GameRenderer {
 
             gui_packed_size: Default::default(),
             gui_rects: Default::default(),
+            gui_present_slots: Default::default(),
 
             animation_cache: AnimationCache::new(),
-            screenshot_clipboard: Clipboard::new().unwrap(),
+            clipboard: Clipboard::new().unwrap(),
+
+            pending_screenshots: Default::default(),
+
+            frame_timings,
+            gpu_timer,
+        }
+    }
+}
+
+impl GameRenderer {
+    /// Gives each coordinate a start time, staggered across the coordinates so that bulk operations ripple.
+    fn staggered(
+        coords: impl IntoIterator<Item = TileCoord>,
+        len: usize,
+    ) -> impl Iterator<Item = (TileCoord, Instant)> {
+        let now = Instant::now();
+        let room = MAX_TILE_ANIMATIONS.saturating_sub(len);
+
+        coords
+            .into_iter()
+            .take(room)
+            .enumerate()
+            .map(move |(i, coord)| {
+                let delay = TILE_ANIMATION_STAGGER
+                    .saturating_mul(i as u32)
+                    .min(MAX_TILE_ANIMATION_STAGGER);
+
+                (coord, now + delay)
+            })
+    }
+
+    /// Draws the world with the sample count, or the most the GPU supports below it, and at the render scale.
+    pub fn set_world_targets(&mut self, msaa: MsaaSamples, render_scale: Float) {
+        let samples = self.gpu.supported_samples(msaa.count());
+
+        self.shared_resources.set_world_targets(
+            &self.gpu.device,
+            &self.gpu.config,
+            &self.global_resources,
+            samples,
+            render_scale,
+        );
+    }
+
+    /// Gets the detail level of the tile at the coordinate, as of the last frame.
+    pub fn detail_level(&self, coord: TileCoord) -> DetailLevel {
+        detail_level(self.camera_pos, coord, self.detail_thresholds)
+    }
+
+    /// Makes the tiles at the coordinates scale in, as they were just placed.
+    pub fn animate_placement(&mut self, coords: impl IntoIterator<Item = TileCoord>) {
+        if !ANIMATIONS_ENABLED.get() {
+            return;
+        }
+
+        let coords = coords
+            .into_iter()
+            .filter(|coord| self.detail_level(*coord).shows_effects())
+            .collect::<Vec<_>>();
+        let animations = Self::staggered(coords, self.placed_animations.len()).collect::<Vec<_>>();
+        self.placed_animations.extend(animations);
+    }
+
+    /// Makes the tiles at the coordinates shrink away once they are gone, as they were just removed.
+    pub fn animate_removal(&mut self, coords: impl IntoIterator<Item = TileCoord>) {
+        if !ANIMATIONS_ENABLED.get() {
+            return;
+        }
+
+        let coords = coords
+            .into_iter()
+            .filter(|coord| self.detail_level(*coord).shows_effects())
+            .collect::<Vec<_>>();
+        let animations = Self::staggered(coords, self.removed_animations.len()).collect::<Vec<_>>();
+        self.removed_animations.extend(animations);
+    }
+
+    /// Returns the models that currently have instances in the world.
+    pub fn models_in_use(&self) -> HashSet<ModelId> {
+        self.instance_ranges
+            .iter()
+            .filter(|(_, ranges)| !ranges.is_empty())
+            .map(|((model, _), _)| *model)
+            .collect()
+    }
+}
+
+/// Scales around the center of the tile.
+fn scale_about(coord: TileCoord, scale: Float) -> Matrix4 {
+    let translation = coord.as_translation();
+
+    translation * Matrix4::from_scale(Vec3::splat(scale)) * translation.inverse()
+}
+
+/// How far along a tile animation started at `start` is, from 0 to 1.
+fn tile_animation_progress(now: Instant, start: Instant) -> Float {
+    (now.saturating_duration_since(start).as_secs_f32() / TILE_ANIMATION_DURATION.as_secs_f32())
+        .min(1.0)
+}
+
+/// Applies the transforms to the tracked render objects.
+fn apply_transforms(
+    renderer: &mut GameRenderer,
+    resource_man: &ResourceManager,
+    transform_commands: TransformCommands,
+    matrix_data_changes: &mut HashSet<usize>,
+    transformed: &mut HashSet<RenderObjectKey>,
+) {
+    for (model, commands) in transform_commands {
+        let (model, (meshes, ..)) = resource_man.mesh_or_missing_tile_mesh(&model);
+
+        for mesh in meshes.iter().flatten() {
+            for (coord, tag, model_matrix) in commands.iter().cloned() {
+                if let Some(index) = renderer
+                    .object_ids
+                    .get_index_of(&(coord, tag, model, mesh.index))
+                {
+                    if let Some(matrix) = renderer.matrix_data_map.get_mut(index) {
+                        *matrix = MatrixData::new(model_matrix, mesh.matrix);
+
+                        matrix_data_changes.insert(index);
+                        transformed.insert((coord, tag, model, mesh.index));
+                    }
+                }
+            }
         }
     }
 }
@@ -188,7 +488,10 @@ pub fn try_add_animation(
     }
 }
 
-pub fn render(state: &mut GameState, screenshotting: bool) -> Result<(), SurfaceError> {
+pub fn render(
+    state: &mut GameState,
+    screenshotting: Option<Screenshot>,
+) -> Result<(), SurfaceError> {
     let Some(renderer) = state.renderer.as_mut() else {
         return Ok(());
     };
@@ -199,15 +502,28 @@ pub fn render(state: &mut GameState, screenshotting: bool) -> Result<(), Surface
         return Ok(());
     }
 
+    renderer.frame_timings.start_frame();
+    let instances_start = Instant::now();
+
     renderer.animation_cache.clear();
 
     let last_tile_tints = mem::take(&mut renderer.last_tile_tints);
-    let tile_tints = mem::take(&mut renderer.tile_tints);
+    let mut tile_tints = mem::take(&mut renderer.tile_tints);
 
     let camera_pos = state.camera.get_pos();
     let culling_range = state.camera.culling_range;
+    let strategic_blend = state.camera.strategic_blend();
+
+    renderer.frame = renderer.frame.wrapping_add(1);
+    renderer.camera_pos = camera_pos;
+    renderer.detail_thresholds = state.options.graphics.detail.thresholds();
+
+    let overlay_mode = state.ui_state.overlay_mode;
 
-    let render_commands = {
+    let RenderFrame {
+        commands: render_commands,
+        statuses,
+    } = {
         let game = state.game.clone();
 
         state
@@ -216,6 +532,7 @@ pub fn render(state: &mut GameState, screenshotting: bool) -> Result<(), Surface
                 |reply| GameSystemMessage::GetAllRenderCommands {
                     reply,
                     culling_range,
+                    statuses: overlay_mode != OverlayMode::None || strategic_blend > 0.0,
                 },
                 None,
             ))
@@ -223,16 +540,34 @@ pub fn render(state: &mut GameState, screenshotting: bool) -> Result<(), Surface
             .unwrap()
     };
 
+    // the overlay colors go under the tints the gui put in
+    for (coord, status) in &statuses {
+        if let Some(color) = overlay_color(&state.resource_man, overlay_mode, status) {
+            tile_tints
+                .entry(*coord)
+                .or_insert(color.with_alpha(0.6).to_linear());
+        }
+
+        if strategic_blend > 0.0 {
+            renderer.strategic_tiles.insert(
+                *coord,
+                strategic_color(&state.resource_man, overlay_mode, status).to_linear(),
+            );
+        }
+    }
+    renderer.tile_statuses = statuses;
+
     let mut instances_changes = HashSet::new();
     let mut matrix_data_changes = HashSet::new();
+    let mut transformed = HashSet::new();
 
     for batch in render_commands {
         let mut batch = batch.into_iter().collect::<Vec<_>>();
         batch.sort_by_key(|v| v.0.ulength());
 
         let mut untrack_commands = BTreeMap::new();
-        let mut track_commands = BTreeMap::new();
-        let mut transform_commands = BTreeMap::new();
+        let mut track_commands = BTreeMap::<_, Vec<_>>::new();
+        let mut transform_commands = TransformCommands::new();
 
         for (coord, commands) in batch {
             for command in commands {
@@ -254,6 +589,24 @@ pub fn render(state: &mut GameState, screenshotting: bool) -> Result<(), Surface
                         model,
                         model_matrix,
                     } => {
+                        // the moving parts of tiles with less detail are only updated every few frames, with their latest transform.
+                        // objects that are not tracked yet are always transformed right away, so that they never show up untransformed
+                        if !renderer.detail_level(coord).updates_on(renderer.frame)
+                            && renderer.coord_to_keys.get(&coord).is_some_and(|keys| {
+                                keys.iter().any(|(t, m, _)| *t == tag && *m == model)
+                            })
+                            && !track_commands
+                                .get(&model)
+                                .is_some_and(|v| v.contains(&(coord, tag)))
+                        {
+                            renderer
+                                .deferred_transforms
+                                .insert((coord, tag, model), model_matrix);
+
+                            continue;
+                        }
+                        renderer.deferred_transforms.remove(&(coord, tag, model));
+
                         transform_commands
                             .entry(model)
                             .or_insert_with(Vec::new)
@@ -275,9 +628,31 @@ pub fn render(state: &mut GameState, screenshotting: bool) -> Result<(), Surface
                         .object_ids
                         .swap_remove_full(&(coord, tag, model, mesh.index))
                         .expect("render object id wasn't tracked");
+                    renderer.batches_dirty = true;
 
                     let swapping_index = swapping_index.unwrap_or(removed_index);
 
+                    {
+                        let key = (coord, tag, model, mesh.index);
+                        let base = renderer.animated_base_matrices.remove(&key);
+
+                        if let Some(start) = renderer.removed_animations.get(&coord) {
+                            if renderer.ghosts.len() < MAX_TILE_ANIMATIONS {
+                                let matrix = base
+                                    .unwrap_or(renderer.matrix_data_map[removed_index])
+                                    .model_matrix;
+
+                                renderer.ghosts.push((
+                                    *start,
+                                    coord,
+                                    model,
+                                    mesh.index,
+                                    Matrix4::from_cols_array_2d(&matrix),
+                                ));
+                            }
+                        }
+                    }
+
                     if let Some(keys) = renderer.coord_to_keys.get_mut(&coord) {
                         assert!(
                             keys.remove(&(tag, model, mesh.index)),
@@ -355,6 +730,7 @@ pub fn render(state: &mut GameState, screenshotting: bool) -> Result<(), Surface
                         prev_id_slot.is_none(),
                         "render object id was already tracked"
                     );
+                    renderer.batches_dirty = true;
 
                     assert!(
                         renderer
@@ -377,7 +753,7 @@ pub fn render(state: &mut GameState, screenshotting: bool) -> Result<(), Surface
                         animation_matrix_index: animation_matrix_index as u32,
                         world_matrix_index: WE_ONLY_USE_1_WORLD_MATRIX_IN_GAME_LOL,
                         color_offset: [0.0; 4],
-                        alpha: 1.0,
+                        alpha: 1.0 - strategic_blend,
                     };
                     instances_changes.insert(index);
 
@@ -390,24 +766,159 @@ pub fn render(state: &mut GameState, screenshotting: bool) -> Result<(), Surface
             }
         }
 
-        for (model, commands) in transform_commands {
-            let (model, (meshes, ..)) = state.resource_man.mesh_or_missing_tile_mesh(&model);
+        apply_transforms(
+            renderer,
+            &state.resource_man,
+            transform_commands,
+            &mut matrix_data_changes,
+            &mut transformed,
+        );
+    }
 
-            for mesh in meshes.iter().flatten() {
-                for (coord, tag, model_matrix) in commands.iter().cloned() {
-                    if let Some(index) = renderer
-                        .object_ids
-                        .get_index_of(&(coord, tag, model, mesh.index))
-                    {
-                        if let Some(matrix) = renderer.matrix_data_map.get_mut(index) {
-                            *matrix = MatrixData::new(model_matrix, mesh.matrix);
+    {
+        let due = renderer
+            .deferred_transforms
+            .keys()
+            .filter(|(coord, ..)| renderer.detail_level(*coord).updates_on(renderer.frame))
+            .copied()
+            .collect::<Vec<_>>();
+
+        let mut deferred_commands = TransformCommands::new();
+        for key @ (coord, tag, model) in due {
+            if let Some(model_matrix) = renderer.deferred_transforms.remove(&key) {
+                deferred_commands
+                    .entry(model)
+                    .or_default()
+                    .push((coord, tag, model_matrix));
+            }
+        }
 
-                            matrix_data_changes.insert(index);
-                        }
-                    }
+        apply_transforms(
+            renderer,
+            &state.resource_man,
+            deferred_commands,
+            &mut matrix_data_changes,
+            &mut transformed,
+        );
+
+        let mut detail_counts = [0; DetailLevel::ALL.len()];
+        for (coord, keys) in &renderer.coord_to_keys {
+            if !keys.is_empty() {
+                detail_counts[renderer.detail_level(*coord) as usize] += 1;
+            }
+        }
+        renderer.detail_counts = detail_counts;
+    }
+
+    let now = Instant::now();
+
+    // the removed tiles that had their ghosts made are done with, as are the ones that never got removed in time
+    renderer.removed_animations.retain(|coord, start| {
+        now < *start + TILE_ANIMATION_DURATION
+            && renderer
+                .coord_to_keys
+                .get(coord)
+                .is_some_and(|v| !v.is_empty())
+    });
+
+    {
+        let mut finished = vec![];
+
+        for (coord, start) in &renderer.placed_animations {
+            let progress = tile_animation_progress(now, *start);
+            let done = progress >= 1.0;
+            if done {
+                finished.push(*coord);
+            }
+
+            let Some(keys) = renderer.coord_to_keys.get(coord) else {
+                continue;
+            };
+
+            let scale = scale_about(
+                *coord,
+                TILE_ANIMATION_SCALE.lerp(1.0, ease_out_cubic(progress)),
+            );
+
+            for &(tag, model, mesh_index) in keys {
+                let key = (*coord, tag, model, mesh_index);
+                let Some(index) = renderer.object_ids.get_index_of(&key) else {
+                    continue;
+                };
+
+                // a fresh transform replaces the unscaled matrix
+                let base = match renderer.animated_base_matrices.get(&key) {
+                    Some(base) if !transformed.contains(&key) => *base,
+                    _ => renderer.matrix_data_map[index],
+                };
+
+                if done {
+                    renderer.animated_base_matrices.remove(&key);
+                    renderer.matrix_data_map[index] = base;
+                } else {
+                    renderer.animated_base_matrices.insert(key, base);
+                    renderer.matrix_data_map[index] =
+                        MatrixData::new(scale, Matrix4::from_cols_array_2d(&base.model_matrix));
                 }
+
+                matrix_data_changes.insert(index);
             }
         }
+
+        for coord in finished {
+            renderer.placed_animations.remove(&coord);
+        }
+    }
+
+    if strategic_blend != renderer.last_strategic_blend {
+        for (index, instance) in renderer.instances.iter_mut().enumerate() {
+            instance.alpha = 1.0 - strategic_blend;
+            instances_changes.insert(index);
+        }
+
+        renderer.last_strategic_blend = strategic_blend;
+    }
+
+    let strategic_tiles = mem::take(&mut renderer.strategic_tiles);
+    if strategic_blend > 0.0 {
+        let (model, (meshes, ..)) = state
+            .resource_man
+            .mesh_or_missing_tile_mesh(&ModelId(state.resource_man.registry.model_ids.tile_none));
+
+        for (coord, color) in strategic_tiles {
+            for mesh in meshes.iter().flatten() {
+                renderer.overlay_instances.push((
+                    InstanceData::default()
+                        .with_color_offset(color.to_array())
+                        .with_alpha(strategic_blend),
+                    model,
+                    GameMatrix::<true>::new(
+                        coord.as_translation(),
+                        state.camera.get_matrix(),
+                        mesh.matrix,
+                    ),
+                    mesh.index,
+                ));
+            }
+        }
+    }
+
+    renderer
+        .ghosts
+        .retain(|(start, ..)| now < *start + TILE_ANIMATION_DURATION);
+    for &(start, coord, model, mesh_index, matrix) in &renderer.ghosts {
+        let eased = ease_out_cubic(tile_animation_progress(now, start));
+
+        renderer.overlay_instances.push((
+            InstanceData::default().with_alpha((1.0 - eased) * (1.0 - strategic_blend)),
+            model,
+            GameMatrix::<true>::new(
+                scale_about(coord, TILE_ANIMATION_SCALE.lerp(1.0, 1.0 - eased)),
+                state.camera.get_matrix(),
+                matrix,
+            ),
+            mesh_index,
+        ));
     }
 
     let overlay_instances = mem::take(&mut renderer.overlay_instances);
@@ -480,11 +991,14 @@ pub fn render(state: &mut GameState, screenshotting: bool) -> Result<(), Surface
         }
     }
 
-    let mut instances_changes = instances_changes.into_iter().collect::<Vec<_>>();
-    instances_changes.sort();
+    let instances_changes = renderer.update_batches(instances_changes);
     let mut matrix_data_changes = matrix_data_changes.into_iter().collect::<Vec<_>>();
     matrix_data_changes.sort();
 
+    renderer
+        .frame_timings
+        .record_cpu(CpuStage::Instances, instances_start.elapsed());
+
     let r = renderer.inner_render(
         state.resource_man.clone(),
         state.gui.as_mut().unwrap(),
@@ -503,6 +1017,92 @@ pub fn render(state: &mut GameState, screenshotting: bool) -> Result<(), Surface
 }
 
 impl GameRenderer {
+    /// Groups the instances by model and mesh if anything was tracked or untracked, or else copies the changed ones over.
+    ///
+    /// Gives the sorted positions in the batched instances that changed, which are all of them when they were grouped again.
+    fn update_batches(&mut self, instances_changes: HashSet<usize>) -> Vec<usize> {
+        if !self.batches_dirty {
+            let mut changes = instances_changes
+                .into_iter()
+                .filter_map(|index| {
+                    let position = *self.batch_positions.get(index)?;
+                    let instance = self.batched_instances.get_mut(position)?;
+                    *instance = self.instances[index];
+
+                    Some(position)
+                })
+                .collect::<Vec<_>>();
+            changes.sort();
+
+            return changes;
+        }
+
+        self.batches_dirty = false;
+
+        // cleared rather than made anew, so that the memory is kept between frames
+        self.batched_instances.clear();
+        self.batches.clear();
+        self.batch_positions.clear();
+        self.batch_positions
+            .resize(self.instances.len(), usize::MAX);
+
+        for (&key, ranges) in &self.instance_ranges {
+            let start = self.batched_instances.len() as u32;
+
+            for index in ranges.iter() {
+                self.batch_positions[index] = self.batched_instances.len();
+                self.batched_instances.push(self.instances[index]);
+            }
+
+            let end = self.batched_instances.len() as u32;
+            if end > start {
+                self.batches.push((key, start..end));
+            }
+        }
+
+        (0..self.batched_instances.len()).collect()
+    }
+
+    /// Saves the screenshots that were read back since the last frame.
+    fn save_pending_screenshots(&mut self, resource_man: &Arc<ResourceManager>) {
+        if self.pending_screenshots.is_empty() {
+            return;
+        }
+
+        self.gpu.device.poll(Maintain::Poll);
+
+        self.pending_screenshots.retain(|pending| {
+            let Some(ok) = pending.mapped.lock().unwrap().take() else {
+                return true;
+            };
+
+            if !ok {
+                log::warn!("Could not read the screenshot back from the GPU");
+
+                return false;
+            }
+
+            let image = {
+                let data = pending.buffer.slice(..).get_mapped_range();
+
+                screenshot_image(
+                    &data,
+                    pending.width,
+                    pending.height,
+                    pending.block_size,
+                    pending.padded_width,
+                )
+            };
+            pending.buffer.unmap();
+
+            if let Some(image) = image {
+                save_screenshot(resource_man.clone(), image);
+            }
+
+            false
+        });
+    }
+
     fn inner_render(
         &mut self,
         resource_man: Arc<ResourceManager>,
@@ -512,11 +1112,15 @@ impl GameRenderer {
         instances_changes: Vec<usize>,
         matrix_data_changes: Vec<usize>,
         overlay_instances: Vec<OverlayInstance>,
-        screenshotting: bool,
+        screenshotting: Option<Screenshot>,
     ) -> Result<(), SurfaceError> {
         let size = self.gpu.window.inner_size();
 
+        self.save_pending_screenshots(&resource_man);
+
+        let present_start = Instant::now();
         let output = self.gpu.surface.get_current_texture()?;
+        let mut present_wait = present_start.elapsed();
 
         {
             let output_size = output.texture.size();
@@ -533,16 +1137,22 @@ impl GameRenderer {
                 label: Some("Render Encoder"),
             });
 
+        if let Some(timer) = &mut self.gpu_timer {
+            timer.begin_frame(&self.gpu.device, &mut self.frame_timings);
+        }
+
         let mut game_staging_belts = [None, None];
+        let mut upload = Duration::ZERO;
 
         {
             if !self.instances.is_empty() {
+                let upload_start = Instant::now();
                 game_staging_belts[0] = gpu::resize_update_buffer_with_changes(
                     &mut encoder,
                     &self.gpu.device,
                     &mut self.render_resources.game_resources.instance_buffer,
                     &instances_changes,
-                    &self.instances,
+                    &self.batched_instances,
                 );
                 game_staging_belts[1] = gpu::update_buffer_with_changes(
                     &mut encoder,
@@ -572,13 +1182,17 @@ impl GameRenderer {
                     0,
                     bytemuck::cast_slice(&[GameUBO::new(camera_pos, None)]),
                 );
+                upload += upload_start.elapsed();
 
                 {
+                    let msaa = self.shared_resources.msaa.as_ref();
                     let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
                         label: Some("Game Render Pass"),
                         color_attachments: &[
                             Some(RenderPassColorAttachment {
-                                view: &self.shared_resources.game_texture().1,
+                                view: msaa.map_or(&self.shared_resources.game_texture().1, |v| {
+                                    &v.color.1
+                                }),
                                 resolve_target: None,
                                 ops: Operations {
                                     load: LoadOp::Clear(Color::BLACK),
@@ -586,7 +1200,9 @@ impl GameRenderer {
                                 },
                             }),
                             Some(RenderPassColorAttachment {
-                                view: &self.shared_resources.normal_texture().1,
+                                view: msaa.map_or(&self.shared_resources.normal_texture().1, |v| {
+                                    &v.normal.1
+                                }),
                                 resolve_target: None,
                                 ops: Operations {
                                     load: LoadOp::Clear(NORMAL_CLEAR),
@@ -594,7 +1210,10 @@ impl GameRenderer {
                                 },
                             }),
                             Some(RenderPassColorAttachment {
-                                view: &self.shared_resources.model_depth_texture().1,
+                                view: msaa
+                                    .map_or(&self.shared_resources.model_depth_texture().1, |v| {
+                                        &v.model_depth.1
+                                    }),
                                 resolve_target: None,
                                 ops: Operations {
                                     load: LoadOp::Clear(MODEL_DEPTH_CLEAR),
@@ -603,7 +1222,8 @@ impl GameRenderer {
                             }),
                         ],
                         depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
-                            view: &self.shared_resources.depth_texture().1,
+                            view: msaa
+                                .map_or(&self.shared_resources.depth_texture().1, |v| &v.depth.1),
                             depth_ops: Some(Operations {
                                 load: LoadOp::Clear(1.0),
                                 store: StoreOp::Store,
@@ -611,10 +1231,14 @@ impl GameRenderer {
                             stencil_ops: None,
                         }),
                         occlusion_query_set: None,
-                        timestamp_writes: None,
+                        timestamp_writes: self
+                            .gpu_timer
+                            .as_mut()
+                            .and_then(|timer| timer.pass_writes(GpuPass::Game)),
                     });
 
-                    render_pass.set_pipeline(&self.global_resources.game_pipeline);
+                    render_pass
+                        .set_pipeline(self.shared_resources.game_pipeline(&self.global_resources));
                     render_pass.set_bind_group(
                         0,
                         &self.render_resources.game_resources.bind_group,
@@ -633,70 +1257,74 @@ impl GameRenderer {
                         IndexFormat::Uint16,
                     );
 
-                    for (&(model, mesh_index), ranges) in &self.instance_ranges {
-                        let (meshes, ..) = resource_man.all_meshes_anims.get(&model).unwrap();
+                    let encode_start = Instant::now();
+                    let mut draws = 0;
+                    self.lod_counts.clear();
 
-                        if let Some(mesh) = &meshes[mesh_index] {
-                            if mesh.opaque {
-                                let index_range =
-                                    &resource_man.all_index_ranges[&model][&mesh.index];
+                    // the opaque meshes first, then the see-through ones over them
+                    for opaque in [true, false] {
+                        for ((model, mesh_index), range) in &self.batches {
+                            // the meshes a level of detail leaves out are not drawn at it
+                            let (model, level) = resource_man.model_lod(*model, camera_pos.z);
+                            let (meshes, ..) = resource_man.all_meshes_anims.get(&model).unwrap();
 
-                                for range in ranges.ranges() {
-                                    render_pass.draw_indexed(
-                                        index_range.pos..(index_range.pos + index_range.count),
-                                        index_range.base_vertex,
-                                        (*range.start() as u32)..(*range.end() as u32 + 1),
-                                    );
-                                }
-                            }
-                        }
-                    }
-
-                    for (&(model, mesh_index), ranges) in &self.instance_ranges {
-                        let (meshes, ..) = resource_man.all_meshes_anims.get(&model).unwrap();
-
-                        if let Some(mesh) = &meshes[mesh_index] {
-                            if !mesh.opaque {
-                                let index_range =
-                                    &resource_man.all_index_ranges[&model][&mesh.index];
+                            if let Some(Some(mesh)) = meshes.get(*mesh_index) {
+                                if mesh.opaque == opaque {
+                                    let index_range =
+                                        &resource_man.all_index_ranges[&model][&mesh.index];
 
-                                for range in ranges.ranges() {
                                     render_pass.draw_indexed(
                                         index_range.pos..(index_range.pos + index_range.count),
                                         index_range.base_vertex,
-                                        (*range.start() as u32)..(*range.end() as u32 + 1),
+                                        range.clone(),
                                     );
+                                    draws += 1;
+
+                                    if self.lod_counts.len() <= level {
+                                        self.lod_counts.resize(level + 1, 0);
+                                    }
+                                    self.lod_counts[level] += range.len();
                                 }
                             }
                         }
                     }
+
+                    self.world_draws = draws;
+                    self.frame_timings
+                        .record_cpu(CpuStage::Encode, encode_start.elapsed());
                 }
             }
         }
 
         {
+            let msaa = self.shared_resources.msaa.as_ref();
             let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
                 label: Some("Overlay Objects Render Pass"),
+                // with MSAA, this is the last pass into the multisampled targets, so it resolves them
                 color_attachments: &[
                     Some(RenderPassColorAttachment {
-                        view: &self.shared_resources.game_texture().1,
-                        resolve_target: None,
+                        view: msaa.map_or(&self.shared_resources.game_texture().1, |v| &v.color.1),
+                        resolve_target: msaa.map(|_| &self.shared_resources.game_texture().1),
                         ops: Operations {
                             load: LoadOp::Load,
                             store: StoreOp::Store,
                         },
                     }),
                     Some(RenderPassColorAttachment {
-                        view: &self.shared_resources.normal_texture().1,
-                        resolve_target: None,
+                        view: msaa
+                            .map_or(&self.shared_resources.normal_texture().1, |v| &v.normal.1),
+                        resolve_target: msaa.map(|_| &self.shared_resources.normal_texture().1),
                         ops: Operations {
                             load: LoadOp::Load,
                             store: StoreOp::Store,
                         },
                     }),
                     Some(RenderPassColorAttachment {
-                        view: &self.shared_resources.model_depth_texture().1,
-                        resolve_target: None,
+                        view: msaa.map_or(&self.shared_resources.model_depth_texture().1, |v| {
+                            &v.model_depth.1
+                        }),
+                        resolve_target: msaa
+                            .map(|_| &self.shared_resources.model_depth_texture().1),
                         ops: Operations {
                             load: LoadOp::Load,
                             store: StoreOp::Store,
@@ -704,7 +1332,9 @@ impl GameRenderer {
                     }),
                 ],
                 depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
-                    view: &self.shared_resources.overlay_depth_texture().1,
+                    view: msaa.map_or(&self.shared_resources.overlay_depth_texture().1, |v| {
+                        &v.overlay_depth.1
+                    }),
                     depth_ops: Some(Operations {
                         load: LoadOp::Clear(1.0),
                         store: StoreOp::Store,
@@ -712,10 +1342,15 @@ impl GameRenderer {
                     stencil_ops: None,
                 }),
                 occlusion_query_set: None,
-                timestamp_writes: None,
+                timestamp_writes: self
+                    .gpu_timer
+                    .as_mut()
+                    .and_then(|timer| timer.pass_writes(GpuPass::Overlay)),
             });
 
             if !overlay_instances.is_empty() {
+                let upload_start = Instant::now();
+
                 gpu::resize_update_buffer(
                     &self.gpu.device,
                     &self.gpu.queue,
@@ -776,8 +1411,10 @@ impl GameRenderer {
                     0,
                     bytemuck::cast_slice(&[GameUBO::new(camera_pos, None)]),
                 );
+                upload += upload_start.elapsed();
 
-                render_pass.set_pipeline(&self.global_resources.game_pipeline);
+                render_pass
+                    .set_pipeline(self.shared_resources.game_pipeline(&self.global_resources));
                 render_pass.set_bind_group(
                     0,
                     &self.render_resources.overlay_objects_resources.bind_group,
@@ -834,7 +1471,10 @@ impl GameRenderer {
                 })],
                 depth_stencil_attachment: None,
                 occlusion_query_set: None,
-                timestamp_writes: None,
+                timestamp_writes: self
+                    .gpu_timer
+                    .as_mut()
+                    .and_then(|timer| timer.pass_writes(GpuPass::PostProcessing)),
             });
 
             render_pass.set_pipeline(&self.global_resources.post_processing_pipeline);
@@ -867,7 +1507,10 @@ impl GameRenderer {
                 })],
                 depth_stencil_attachment: None,
                 occlusion_query_set: None,
-                timestamp_writes: None,
+                timestamp_writes: self
+                    .gpu_timer
+                    .as_mut()
+                    .and_then(|timer| timer.pass_writes(GpuPass::Antialiasing)),
             });
 
             render_pass.set_pipeline(&self.global_resources.fxaa_pipeline);
@@ -911,15 +1554,21 @@ impl GameRenderer {
 
                 packed_size: self.gui_packed_size.take(),
                 rects: mem::take(&mut self.gui_rects),
+                present_slots: mem::take(&mut self.gui_present_slots),
 
                 animation_cache: mem::take(&mut self.animation_cache),
             };
 
+            let gui_start = Instant::now();
             {
                 let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
                     label: Some("yakui Render Pass"),
                     color_attachments: &surface.color_attachments,
                     depth_stencil_attachment: None,
+                    timestamp_writes: self
+                        .gpu_timer
+                        .as_mut()
+                        .and_then(|timer| timer.pass_writes(GpuPass::Gui)),
                     ..Default::default()
                 });
 
@@ -941,6 +1590,9 @@ impl GameRenderer {
 
             self.gui_packed_size = resources.packed_size;
             self.gui_rects = resources.rects;
+            self.gui_present_slots = resources.present_slots;
+            self.frame_timings
+                .record_cpu(CpuStage::UiRender, gui_start.elapsed());
 
             self.animation_cache = resources.animation_cache;
         };
@@ -958,7 +1610,10 @@ impl GameRenderer {
                 })],
                 depth_stencil_attachment: None,
                 occlusion_query_set: None,
-                timestamp_writes: None,
+                timestamp_writes: self
+                    .gpu_timer
+                    .as_mut()
+                    .and_then(|timer| timer.pass_writes(GpuPass::Combine)),
             });
 
             render_pass.set_pipeline(&self.global_resources.combine_pipeline);
@@ -983,7 +1638,10 @@ impl GameRenderer {
                 })],
                 depth_stencil_attachment: None,
                 occlusion_query_set: None,
-                timestamp_writes: None,
+                timestamp_writes: self
+                    .gpu_timer
+                    .as_mut()
+                    .and_then(|timer| timer.pass_writes(GpuPass::Present)),
             });
 
             render_pass.set_pipeline(&self.global_resources.present_pipeline);
@@ -1000,7 +1658,7 @@ impl GameRenderer {
         let buffer_dim = texture_dim.physical_size(output.texture.format());
         let padded_width = size_align(buffer_dim.width * block_size, COPY_BYTES_PER_ROW_ALIGNMENT);
 
-        let screenshot_buffer = if screenshotting {
+        let screenshot_buffer = if screenshotting.is_some() {
             let intermediate_texture = self.gpu.device.create_texture(&TextureDescriptor {
                 label: Some("Screenshot Intermediate Texture"),
                 size: texture_dim,
@@ -1064,14 +1722,53 @@ impl GameRenderer {
             None
         };
 
+        if let Some(timer) = &self.gpu_timer {
+            timer.resolve(&mut encoder);
+        }
+
         self.gpu
             .queue
             .submit([custom_gui_commands, encoder.finish()]);
 
+        if let Some(timer) = &mut self.gpu_timer {
+            timer.after_submit();
+        }
+        self.frame_timings.record_cpu(CpuStage::Upload, upload);
+
         for mut belt in game_staging_belts.into_iter().flatten() {
             belt.recall();
         }
 
+        // a screenshot that only goes to a file is read back without waiting on it, as nothing else needs it this frame
+        let screenshot_buffer = match screenshot_buffer {
+            Some(buffer)
+                if matches!(
+                    screenshotting,
+                    Some(Screenshot::File | Screenshot::FileWithoutUi)
+                ) =>
+            {
+                let mapped = Arc::new(Mutex::new(None));
+                {
+                    let mapped = mapped.clone();
+                    buffer.slice(..).map_async(MapMode::Read, move |result| {
+                        *mapped.lock().unwrap() = Some(result.is_ok());
+                    });
+                }
+
+                self.pending_screenshots.push(PendingScreenshot {
+                    buffer,
+                    width: texture_dim.width,
+                    height: texture_dim.height,
+                    block_size,
+                    padded_width,
+                    mapped,
+                });
+
+                None
+            }
+            buffer => buffer,
+        };
+
         if let Some(buffer) = screenshot_buffer {
             {
                 let slice = buffer.slice(..);
@@ -1084,25 +1781,31 @@ impl GameRenderer {
                 self.gpu.device.poll(Maintain::Wait);
                 rx.blocking_recv().unwrap().unwrap();
 
-                let texture_width = (texture_dim.width * block_size) as usize;
                 let data = slice.get_mapped_range();
-                let mut result = Vec::<u8>::new();
-                for chunk in data.chunks_exact(padded_width as usize) {
-                    for pixel in chunk[..texture_width].chunks_exact(4) {
-                        result.extend(&[pixel[0], pixel[1], pixel[2], 255]);
+
+                if let Some(image) = screenshot_image(
+                    &data,
+                    texture_dim.width,
+                    texture_dim.height,
+                    block_size,
+                    padded_width,
+                ) {
+                    if matches!(
+                        screenshotting,
+                        Some(Screenshot::File | Screenshot::FileWithoutUi)
+                    ) {
+                        save_screenshot(resource_man.clone(), image.clone());
                     }
-                }
 
-                if let Some(image) =
-                    RgbaImage::from_vec(texture_dim.width, texture_dim.height, result)
-                {
-                    self.screenshot_clipboard
-                        .set_image(ImageData {
-                            width: image.width() as usize,
-                            height: image.height() as usize,
-                            bytes: Cow::from(image.as_bytes()),
-                        })
-                        .unwrap();
+                    if screenshotting == Some(Screenshot::Clipboard) {
+                        self.clipboard
+                            .set_image(ImageData {
+                                width: image.width() as usize,
+                                height: image.height() as usize,
+                                bytes: Cow::from(image.as_bytes()),
+                            })
+                            .unwrap();
+                    }
                 }
             }
 
@@ -1111,7 +1814,12 @@ impl GameRenderer {
 
         self.gpu.window.pre_present_notify();
 
+        let present_start = Instant::now();
         output.present();
+        present_wait += present_start.elapsed();
+
+        self.frame_timings
+            .record_cpu(CpuStage::PresentWait, present_wait);
 
         Ok(())
     }
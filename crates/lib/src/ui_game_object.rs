@@ -1,4 +1,4 @@
-use crate::gpu::{self, MODEL_DEPTH_CLEAR, NORMAL_CLEAR};
+use crate::gpu::{self, GlobalResources, GuiResources, MODEL_DEPTH_CLEAR, NORMAL_CLEAR};
 use crate::renderer::{try_add_animation, YakuiRenderResources};
 use automancy_defs::coord::TileCoord;
 use automancy_defs::rendering::{
@@ -9,15 +9,17 @@ use automancy_resources::rhai_render::RenderCommand;
 use automancy_system::tile_entity::collect_render_commands;
 use automancy_ui::{GameElementPaint, UiGameObjectType, SHOULD_RERENDER};
 use core::cell::Cell;
+use std::mem;
+use std::num::NonZeroU64;
 use std::time::Instant;
-use wgpu::util::{BufferInitDescriptor, DeviceExt, DrawIndexedIndirectArgs};
-use wgpu::BindGroupEntry;
+use wgpu::util::DrawIndexedIndirectArgs;
 use wgpu::{
-    BindGroupDescriptor, BindingResource, BufferUsages, Color, IndexFormat, LoadOp, Operations,
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindingResource, Buffer, BufferBinding,
+    BufferDescriptor, BufferUsages, Color, Device, IndexFormat, LoadOp, Operations, Queue,
     RenderPassColorAttachment, RenderPassDepthStencilAttachment, RenderPassDescriptor, StoreOp,
     TextureViewDescriptor,
 };
-use yakui::UVec2;
+use yakui::{UVec2, Vec2};
 use yakui_wgpu::CallbackTrait;
 
 thread_local! {
@@ -28,10 +30,134 @@ pub fn init_custom_paint_state(start_instant: Instant) {
     START_INSTANT.set(Some(start_instant));
 }
 
+/// The uniforms that present the GUI game objects out of the texture they are all drawn into, in one buffer
+/// with a bind group per object. Kept between frames, so that the objects don't each make their own every frame.
+#[derive(Debug, Default)]
+pub struct PresentSlots {
+    buffer: Option<Buffer>,
+    /// how far apart the uniforms of the objects are in the buffer
+    stride: u64,
+    bind_groups: Vec<BindGroup>,
+    /// the rect index, the clip scale and the clip offset of each object this frame, in the order they were prepared
+    clips: Vec<(usize, Vec2, Vec2)>,
+    /// whether the present texture was made anew since the bind groups were made
+    pub stale: bool,
+}
+
+impl PresentSlots {
+    /// Gives the object the next slot.
+    fn push(&mut self, rect_index: usize, clip_scale: Vec2, clip_offset: Vec2) -> usize {
+        self.clips.push((rect_index, clip_scale, clip_offset));
+
+        self.clips.len() - 1
+    }
+
+    /// Writes the uniforms of the objects of this frame in one go, making the buffer and the bind groups anew if they no longer fit.
+    fn upload(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        global_resources: &GlobalResources,
+        gui_resources: &GuiResources,
+        rects: &[Option<crunch::Rect>],
+        packed_size: Option<UVec2>,
+    ) {
+        let clips = mem::take(&mut self.clips);
+        let Some(packed_size) = packed_size.filter(|_| !clips.is_empty()) else {
+            return;
+        };
+
+        if self.stale || self.bind_groups.len() < clips.len() {
+            let alignment = device.limits().min_uniform_buffer_offset_alignment as u64;
+            let size = mem::size_of::<IntermediateUBO>() as u64;
+
+            self.stride = size.div_ceil(alignment) * alignment;
+            let count = clips.len().next_power_of_two();
+
+            let buffer = device.create_buffer(&BufferDescriptor {
+                label: Some("UI Model Present Uniform Buffer"),
+                size: self.stride * count as u64,
+                usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let view = gui_resources
+                .present_texture()
+                .create_view(&TextureViewDescriptor::default());
+
+            self.bind_groups = (0..count)
+                .map(|slot| {
+                    device.create_bind_group(&BindGroupDescriptor {
+                        label: None,
+                        layout: &global_resources.intermediate_bind_group_layout,
+                        entries: &[
+                            BindGroupEntry {
+                                binding: 0,
+                                resource: BindingResource::TextureView(&view),
+                            },
+                            BindGroupEntry {
+                                binding: 1,
+                                resource: BindingResource::Sampler(
+                                    &global_resources.nonfiltering_sampler,
+                                ),
+                            },
+                            BindGroupEntry {
+                                binding: 2,
+                                resource: BindingResource::Buffer(BufferBinding {
+                                    buffer: &buffer,
+                                    offset: self.stride * slot as u64,
+                                    size: NonZeroU64::new(size),
+                                }),
+                            },
+                        ],
+                    })
+                })
+                .collect();
+            self.buffer = Some(buffer);
+            self.stale = false;
+        }
+
+        let mut data = vec![0; self.stride as usize * clips.len()];
+        for (slot, (rect_index, clip_scale, clip_offset)) in clips.into_iter().enumerate() {
+            let ubo = match rects.get(rect_index).copied().flatten() {
+                Some(rect) => IntermediateUBO {
+                    viewport_size: [
+                        (rect.w as f32 * clip_scale.x) / packed_size.x as f32,
+                        (rect.h as f32 * clip_scale.y) / packed_size.y as f32,
+                    ],
+                    viewport_pos: [
+                        (rect.x as f32 + clip_offset.x) / packed_size.x as f32,
+                        (rect.y as f32 + clip_offset.y) / packed_size.y as f32,
+                    ],
+                },
+                None => IntermediateUBO {
+                    viewport_size: [0.0; 2],
+                    viewport_pos: [0.0; 2],
+                },
+            };
+
+            let start = self.stride as usize * slot;
+            data[start..start + mem::size_of::<IntermediateUBO>()]
+                .copy_from_slice(bytemuck::bytes_of(&ubo));
+        }
+
+        queue.write_buffer(self.buffer.as_ref().unwrap(), 0, &data);
+    }
+}
+
 impl CallbackTrait<YakuiRenderResources> for GameElementPaint {
-    fn prepare(&mut self, YakuiRenderResources { instances, .. }: &mut YakuiRenderResources) {
+    fn prepare(
+        &mut self,
+        YakuiRenderResources {
+            instances,
+            present_slots,
+            ..
+        }: &mut YakuiRenderResources,
+    ) {
         let props = &self.props;
 
+        self.present_slot =
+            Some(present_slots.push(props.index, self.clip_scale, self.clip_offset));
+
         instances.as_mut().unwrap().push((
             props.ty.clone(),
             props.instance,
@@ -57,6 +183,7 @@ impl CallbackTrait<YakuiRenderResources> for GameElementPaint {
             instances,
             packed_size,
             rects,
+            present_slots,
         }: &mut YakuiRenderResources,
     ) {
         let gui_resources = gui_resources.as_mut().unwrap();
@@ -191,6 +318,7 @@ impl CallbackTrait<YakuiRenderResources> for GameElementPaint {
 
                 if *packed_size != Some(size) {
                     gui_resources.resize(device, *surface_format, global_resources, size);
+                    present_slots.stale = true;
                 }
                 *packed_size = Some(size);
 
@@ -378,64 +506,14 @@ impl CallbackTrait<YakuiRenderResources> for GameElementPaint {
                 render_pass.set_bind_group(0, gui_resources.antialiasing_bind_group(), &[]);
                 render_pass.draw(0..3, 0..1);
             }
-        }
 
-        if self.present_uniform.is_none() {
-            self.present_uniform = Some(device.create_buffer_init(&BufferInitDescriptor {
-                label: Some("UI Model Present Uniform Buffer"),
-                contents: bytemuck::cast_slice(&[IntermediateUBO {
-                    viewport_size: [0.0; 2],
-                    viewport_pos: [0.0; 2],
-                }]),
-                usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
-            }));
-        }
-
-        if let Some((Some(rect), packed_size)) =
-            rects.get(self.props.index).cloned().zip(*packed_size)
-        {
-            queue.write_buffer(
-                self.present_uniform.as_ref().unwrap(),
-                0,
-                bytemuck::cast_slice(&[IntermediateUBO {
-                    viewport_size: [
-                        (rect.w as f32 * self.clip_scale.x) / packed_size.x as f32,
-                        (rect.h as f32 * self.clip_scale.y) / packed_size.y as f32,
-                    ],
-                    viewport_pos: [
-                        (rect.x as f32 + self.clip_offset.x) / packed_size.x as f32,
-                        (rect.y as f32 + self.clip_offset.y) / packed_size.y as f32,
-                    ],
-                }]),
-            );
-        }
-
-        if self.present_bind_group.is_none() {
-            self.present_bind_group = Some(
-                device.create_bind_group(&BindGroupDescriptor {
-                    label: None,
-                    layout: &global_resources.intermediate_bind_group_layout,
-                    entries: &[
-                        BindGroupEntry {
-                            binding: 0,
-                            resource: BindingResource::TextureView(
-                                &gui_resources
-                                    .present_texture()
-                                    .create_view(&TextureViewDescriptor::default()),
-                            ),
-                        },
-                        BindGroupEntry {
-                            binding: 1,
-                            resource: BindingResource::Sampler(
-                                &global_resources.nonfiltering_sampler,
-                            ),
-                        },
-                        BindGroupEntry {
-                            binding: 2,
-                            resource: self.present_uniform.as_ref().unwrap().as_entire_binding(),
-                        },
-                    ],
-                }),
+            present_slots.upload(
+                device,
+                queue,
+                global_resources,
+                gui_resources,
+                rects,
+                *packed_size,
             );
         }
     }
@@ -446,10 +524,15 @@ impl CallbackTrait<YakuiRenderResources> for GameElementPaint {
         _device: &wgpu::Device,
         _queue: &wgpu::Queue,
         YakuiRenderResources {
-            global_resources, ..
+            global_resources,
+            present_slots,
+            ..
         }: &'a YakuiRenderResources,
     ) {
-        if let Some(present_bind_group) = self.present_bind_group.as_ref() {
+        if let Some(present_bind_group) = self
+            .present_slot
+            .and_then(|slot| present_slots.bind_groups.get(slot))
+        {
             render_pass.set_pipeline(&global_resources.multisampled_present_pipeline);
             render_pass.set_bind_group(0, present_bind_group, &[]);
             render_pass.draw(0..3, 0..1);
@@ -0,0 +1,201 @@
+use crate::{load_resources, spawn_game};
+use automancy_lib::*;
+
+use actor_stats::{format_rate, CounterSnapshot, ACTOR_STATS};
+use anyhow::{anyhow, bail};
+use game::GameSystemMessage;
+use kira::manager::backend::mock::MockBackend;
+use kira::manager::{AudioManager, AudioManagerSettings};
+use kira::track::TrackBuilder;
+use map::{GameMap, LoadMapOption};
+use options::MiscOptions;
+use ractor::rpc::CallResult;
+use ractor::ActorRef;
+use std::time::{Duration, Instant};
+use tile_entity::TileEntityMsg;
+use tokio::runtime::Runtime;
+use util::actor::multi_call_iter;
+
+/// How many ticks are sent before waiting for the game and the tile entities to go through them, so that their mailboxes stay short.
+const BATCH_TICKS: u32 = 100;
+
+/// What to run in headless mode, from `--headless <map> --ticks <n> [--save]`.
+#[derive(Debug, Clone)]
+pub struct HeadlessArgs {
+    /// the name of the save to run
+    pub map: String,
+    /// the number of base ticks to run
+    pub ticks: u32,
+    /// whether the map is saved once the ticks are done
+    pub save: bool,
+}
+
+impl HeadlessArgs {
+    /// Reads the headless arguments, or returns None if `--headless` isn't one of them.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> anyhow::Result<Option<Self>> {
+        let mut map = None;
+        let mut ticks = None;
+        let mut save = false;
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => {
+                    map = Some(
+                        args.next()
+                            .ok_or_else(|| anyhow!("--headless needs the name of a map"))?,
+                    );
+                }
+                "--ticks" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--ticks needs a number of ticks"))?;
+
+                    ticks = Some(
+                        value
+                            .parse::<u32>()
+                            .map_err(|err| anyhow!("Invalid number of ticks {value:?}: {err}"))?,
+                    );
+                }
+                "--save" => save = true,
+                _ => {}
+            }
+        }
+
+        let Some(map) = map else {
+            if ticks.is_some() || save {
+                bail!("--ticks and --save are only used with --headless <map>");
+            }
+
+            return Ok(None);
+        };
+
+        let Some(ticks) = ticks else {
+            bail!("--headless needs --ticks <n>");
+        };
+
+        Ok(Some(Self { map, ticks, save }))
+    }
+}
+
+/// Waits until every tile entity went through the messages sent to it so far.
+async fn wait_for_tiles(game: &ActorRef<GameSystemMessage>) -> anyhow::Result<usize> {
+    let CallResult::Success((_, tile_entities)) =
+        game.call(GameSystemMessage::GetAllTiles, None).await?
+    else {
+        bail!("The game did not reply with its tiles");
+    };
+
+    multi_call_iter(
+        &tile_entities,
+        |reply, _| TileEntityMsg::GetStats(reply),
+        None,
+    )
+    .await?;
+
+    Ok(tile_entities.len())
+}
+
+fn print_summary(
+    args: &HeadlessArgs,
+    tile_entities: usize,
+    elapsed: Duration,
+    before: CounterSnapshot,
+) {
+    let seconds = elapsed.as_secs_f64().max(f64::EPSILON);
+    let after = ACTOR_STATS.game.snapshot();
+    let handled = after.handled - before.handled;
+    let busy = Duration::from_nanos(after.busy_nanos - before.busy_nanos);
+
+    println!("Ran {} ticks of {} in {elapsed:.2?}", args.ticks, args.map);
+    println!(
+        "  {} ticks/s, {tile_entities} tile entities",
+        format_rate(args.ticks as f64 / seconds)
+    );
+    println!(
+        "  the game actor handled {} messages, {} msg/s, busy for {:.2?}",
+        handled,
+        format_rate(handled as f64 / seconds),
+        busy
+    );
+
+    println!("Game actor messages by kind:");
+    for (kind, count) in ACTOR_STATS.kind_counts() {
+        println!("  {kind}: {count}");
+    }
+}
+
+/// Loads the map and runs its tiles for the number of ticks as fast as they go, without a window, then prints how long it took.
+///
+/// The ticks are stepped rather than run at the map's speed, so every tick message runs exactly one base tick.
+pub fn run(args: HeadlessArgs) -> anyhow::Result<()> {
+    if find_resource_packs().is_empty() {
+        bail!("No resource packs found in {RESOURCES_PATH}");
+    }
+
+    let opt = LoadMapOption::FromSave(args.map.clone());
+    // loading a map that doesn't exist starts a new one, which isn't what is being measured
+    if !GameMap::path(&opt).is_some_and(|path| path.exists()) {
+        bail!("There is no map named {}", args.map);
+    }
+
+    let tokio = Runtime::new()?;
+
+    // there is nothing to hear without a window, so the audio goes nowhere instead of needing a device
+    let mut audio_man = AudioManager::<MockBackend>::new(AudioManagerSettings::default())?;
+    let track = audio_man.add_sub_track(TrackBuilder::new())?;
+
+    log::info!("Loading resources...");
+    let (resource_man, _, _) =
+        load_resources(ResourceManager::new(track), &MiscOptions::load().language)?;
+    RESOURCE_MAN.write().unwrap().replace(resource_man.clone());
+    log::info!("Loaded resources.");
+
+    let (game, game_handle) = spawn_game(&tokio, resource_man)?;
+
+    tokio.block_on(async {
+        match game
+            .call(|reply| GameSystemMessage::LoadMap(opt, reply), None)
+            .await?
+        {
+            CallResult::Success(true) => {}
+            _ => bail!("Could not load the map {}", args.map),
+        }
+        wait_for_tiles(&game).await?;
+
+        ACTOR_STATS.count_kinds();
+        let before = ACTOR_STATS.game.snapshot();
+        let start = Instant::now();
+
+        game.send_message(GameSystemMessage::StepTick(args.ticks))?;
+
+        let mut left = args.ticks;
+        while left > 0 {
+            let batch = left.min(BATCH_TICKS);
+
+            for _ in 0..batch {
+                game.send_message(GameSystemMessage::Tick)?;
+            }
+            wait_for_tiles(&game).await?;
+
+            left -= batch;
+        }
+
+        let elapsed = start.elapsed();
+        let tile_entities = wait_for_tiles(&game).await?;
+
+        print_summary(&args, tile_entities, elapsed, before);
+
+        if args.save {
+            match game.call(GameSystemMessage::SaveMap, None).await? {
+                CallResult::Success(()) => println!("Saved {}", args.map),
+                _ => bail!("Could not save the map {}", args.map),
+            }
+        }
+
+        game.stop(Some("Headless run done".to_string()));
+        game_handle.await?;
+
+        Ok(())
+    })
+}
@@ -1,30 +1,38 @@
 #![windows_subsystem = "windows"]
 use automancy_lib::*;
 
+use anyhow::Context;
+use audio::AudioChannels;
 use camera::GameCamera;
 use color_eyre::config::HookBuilder;
 use cosmic_text::fontdb::Source;
-use game::{GameSystem, GameSystemMessage, TICK_INTERVAL};
+use crash::{write_crash_bundle, HistoryLogger, CRASH_CONTEXT};
+use format::Formattable;
+use game::{GameSystem, GameSystemMessage, AUTOSAVE_CHECK_INTERVAL, TICK_INTERVAL};
+use gamepad::Gamepads;
 use glam::uvec2;
 use gpu::Gpu;
 use input::InputHandler;
 use kira::manager::{AudioManager, AudioManagerSettings};
-use kira::track::{TrackBuilder, TrackHandle};
-use kira::tween::Tween;
+use kira::track::TrackBuilder;
 use map::LoadMapOption;
 use options::{GameOptions, MiscOptions};
-use ractor::Actor;
+use ractor::{Actor, ActorRef};
 use renderer::GameRenderer;
 use rendering::Vertex;
-use rfd::{MessageButtons, MessageDialog, MessageLevel};
+use rfd::{MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+use ron::ser::PrettyConfig;
 use std::fmt::Write;
 use std::fs::File;
+use std::mem;
 use std::path::Path;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use std::{env, fs, panic};
 use tokio::runtime::Runtime;
-use ui_state::UiState;
+use tokio::task::JoinHandle;
+use types::pack::resolve_load_order;
+use ui_state::{PopupState, ToastLevel, UiState};
 use uuid::Uuid;
 use winit::{
     application::ApplicationHandler,
@@ -39,69 +47,101 @@ use winit::{
 };
 use yakui::paint::{Texture, TextureFilter};
 
+mod headless;
+
 pub static LOGO: &[u8] = include_bytes!("logo.png");
 
+/// Whether `--strict-resources` was passed. Then any resource file failing to load stops the resources from loading,
+/// rather than being skipped, which is what pack developers want when checking their packs.
+fn strict_resources() -> bool {
+    env::args().skip(1).any(|arg| arg == "--strict-resources")
+}
+
 /// Initialize the Resource Manager system, and loads all the resources in all namespaces.
+///
+/// The resource manager can be an empty one over an existing interner, to load the resources again with the IDs staying the same.
 fn load_resources(
+    mut resource_man: ResourceManager,
     selected_language: &str,
-    track: TrackHandle,
-) -> (Arc<ResourceManager>, Vec<Vertex>, Vec<u16>) {
-    let mut resource_man = ResourceManager::new(track);
-
-    fs::read_dir(RESOURCES_PATH)
-        .expect("The resources folder doesn't exist- this is very wrong")
-        .flatten()
-        .map(|v| v.path())
-        .filter(|v| v.is_dir())
-        .for_each(|dir| {
-            let namespace = dir.file_name().unwrap().to_str().unwrap().trim();
-            log::info!("Loading namespace {namespace}...");
-
-            resource_man
-                .load_models(&dir, namespace)
-                .expect("Error loading models");
-
-            resource_man.load_audio(&dir).expect("Error loading audio");
-
-            resource_man
-                .load_tiles(&dir, namespace)
-                .expect("Error loading tiles");
-
-            resource_man
-                .load_items(&dir, namespace)
-                .expect("Error loading items");
-
-            resource_man
-                .load_tags(&dir, namespace)
-                .expect("Error loading tags");
-            resource_man
-                .load_categories(&dir, namespace)
-                .expect("Error loading categories");
-
-            resource_man
-                .load_scripts(&dir, namespace)
-                .expect("Error loading scripts");
-
-            resource_man
-                .load_translates(&dir, namespace, selected_language)
-                .expect("Error loading translates");
-
-            resource_man
-                .load_shaders(&dir)
-                .expect("Error loading shaders");
-
-            resource_man.load_fonts(&dir).expect("Error loading fonts");
-
-            resource_man
-                .load_functions(&dir, namespace)
-                .expect("Error loading functions");
-
-            resource_man
-                .load_researches(&dir, namespace)
-                .expect("Error loading researches");
-
-            log::info!("Loaded namespace {namespace}.");
-        });
+) -> anyhow::Result<(Arc<ResourceManager>, Vec<Vertex>, Vec<u16>)> {
+    if let Ok(mut context) = CRASH_CONTEXT.lock() {
+        context.packs.clear();
+    }
+
+    resource_man.strict = strict_resources();
+
+    let (packs, pack_errors) = resolve_load_order(find_resource_packs());
+
+    for pack in &packs {
+        let dir = &pack.dir;
+        let namespace = pack.namespace.as_str();
+        log::info!("Loading namespace {namespace}...");
+
+        if let Ok(mut context) = CRASH_CONTEXT.lock() {
+            context.packs.push(namespace.to_string());
+        }
+
+        resource_man
+            .load_models(dir, namespace)
+            .context("Error loading models")?;
+
+        resource_man
+            .load_audio(dir)
+            .context("Error loading audio")?;
+
+        resource_man
+            .load_tiles(dir, namespace)
+            .context("Error loading tiles")?;
+
+        resource_man
+            .load_terrains(dir, namespace)
+            .context("Error loading terrains")?;
+
+        resource_man
+            .load_items(dir, namespace)
+            .context("Error loading items")?;
+
+        resource_man
+            .load_schemas(dir, namespace)
+            .context("Error loading data schemas")?;
+
+        resource_man
+            .load_tags(dir, namespace)
+            .context("Error loading tags")?;
+        resource_man
+            .load_categories(dir, namespace)
+            .context("Error loading categories")?;
+
+        resource_man
+            .load_scripts(dir, namespace)
+            .context("Error loading scripts")?;
+
+        resource_man
+            .load_translates(dir, namespace, selected_language)
+            .context("Error loading translates")?;
+
+        resource_man
+            .load_shaders(dir)
+            .context("Error loading shaders")?;
+
+        resource_man
+            .load_fonts(dir)
+            .context("Error loading fonts")?;
+
+        resource_man
+            .load_functions(dir, namespace)
+            .context("Error loading functions")?;
+
+        resource_man
+            .load_researches(dir, namespace)
+            .context("Error loading researches")?;
+
+        resource_man
+            .load_start(dir, namespace)
+            .context("Error loading start definitions")?;
+
+        log::info!("Loaded namespace {namespace}.");
+    }
 
     resource_man
         .engine
@@ -109,16 +149,98 @@ fn load_resources(
         .with_headers(true)
         .include_standard_packages(false)
         .write_to_dir("rhai")
-        .unwrap();
+        .context("Error writing the script definitions")?;
 
+    resource_man.compile_tags();
     resource_man.compile_researches();
+    resource_man.compile_start();
     resource_man.ordered_tiles();
     resource_man.ordered_items();
+    resource_man.compile_scripts();
     resource_man.compile_categories();
 
+    resource_man.report_pack_errors(&pack_errors);
+    resource_man.report_load_failures();
+    resource_man.packs = packs;
+
     let (vertices, indices) = resource_man.compile_models();
 
-    (Arc::new(resource_man), vertices, indices)
+    Ok((Arc::new(resource_man), vertices, indices))
+}
+
+/// Spawns the game actor, which runs the map and its tile entities. Nothing about it needs a window.
+fn spawn_game(
+    tokio: &Runtime,
+    resource_man: Arc<ResourceManager>,
+) -> anyhow::Result<(ActorRef<GameSystemMessage>, JoinHandle<()>)> {
+    log::info!("Creating game...");
+    let spawned = tokio.block_on(Actor::spawn(
+        Some("game".to_string()),
+        GameSystem { resource_man },
+        (),
+    ))?;
+    log::info!("Game created.");
+
+    Ok(spawned)
+}
+
+/// Starts sending the game its ticks and autosave checks. They stop once the game does.
+fn start_game_intervals(tokio: &Runtime, game: &ActorRef<GameSystemMessage>) {
+    let game = game.clone();
+    tokio.spawn(async move {
+        game.send_interval(TICK_INTERVAL, || GameSystemMessage::Tick);
+        game.send_interval(AUTOSAVE_CHECK_INTERVAL, || GameSystemMessage::Autosave);
+    });
+}
+
+/// Makes sure there are resource packs to load, and if there are none, tells the player where they are expected.
+///
+/// This runs before anything is loaded, so it uses native dialogs instead of the game's GUI, which needs the packs' shaders and fonts.
+/// Returns false if the player chose to quit.
+fn ensure_resource_packs() -> bool {
+    const OPEN_FOLDER: &str = "Open Folder";
+    const RETRY: &str = "Retry";
+    const QUIT: &str = "Quit";
+
+    loop {
+        if !find_resource_packs().is_empty() {
+            return true;
+        }
+
+        let searched = env::current_dir()
+            .map(|dir| dir.join(RESOURCES_PATH))
+            .unwrap_or_else(|_| RESOURCES_PATH.into());
+
+        log::error!("No resource packs found in {}", searched.display());
+
+        let result = MessageDialog::new()
+            .set_level(MessageLevel::Warning)
+            .set_buttons(MessageButtons::YesNoCancelCustom(
+                OPEN_FOLDER.to_string(),
+                RETRY.to_string(),
+                QUIT.to_string(),
+            ))
+            .set_title("automancy - no resource packs found")
+            .set_description(format!(
+                "automancy could not find any resource packs, so there is nothing to load.\n\n\
+                Every resource pack is a folder inside the \"resources\" folder of the directory the game is started from. The game comes with the \"core\" pack.\n\n\
+                Searched in:\n{}\n\n\
+                Put the packs there, then press \"{RETRY}\".",
+                searched.display()
+            ))
+            .show();
+
+        match result {
+            MessageDialogResult::Custom(button) if button == OPEN_FOLDER => {
+                if let Err(err) = fs::create_dir_all(&searched).and_then(|_| open::that(&searched))
+                {
+                    log::error!("Could not open the resources folder: {err}");
+                }
+            }
+            MessageDialogResult::Custom(button) if button == RETRY => {}
+            _ => return false,
+        }
+    }
 }
 
 /// Gets the game icon.
@@ -169,6 +291,128 @@ struct Automancy {
 }
 
 impl Automancy {
+    /// Loads the resource packs again if it was asked for, keeping the same interner so that the IDs stay valid.
+    ///
+    /// The game and its tile entities hold on to the resource manager they were spawned with, so the map is saved,
+    /// and the game is spawned again to load it back. The renderer is set up again too, as the models are uploaded in one buffer.
+    /// If the packs fail to load, everything is left as it was.
+    fn try_reload_resources(&mut self) {
+        if !mem::take(&mut self.state.loop_store.reload_resources) {
+            return;
+        }
+
+        log::info!("Reloading resources...");
+
+        let loaded = self
+            .state
+            .audio_man
+            .add_sub_track(TrackBuilder::new())
+            .map_err(anyhow::Error::from)
+            .and_then(|track| {
+                load_resources(
+                    ResourceManager::with_interner(track, self.state.resource_man.interner.clone()),
+                    &self.state.misc_options.language,
+                )
+            });
+
+        let (resource_man, vertices, indices) = match loaded {
+            Ok(loaded) => loaded,
+            Err(err) => {
+                log::error!("Could not reload the resources! Error: {err:?}");
+
+                let text = self.state.resource_man.gui_fmt(
+                    self.state
+                        .resource_man
+                        .registry
+                        .gui_ids
+                        .lbl_resources_reload_failed,
+                    [("error", Formattable::display(&err))],
+                );
+                self.state.ui_state.push_toast(ToastLevel::Error, text);
+
+                return;
+            }
+        };
+
+        let opt = self
+            .state
+            .loop_store
+            .map_info
+            .as_ref()
+            .map(|(_, opt)| opt.clone())
+            .unwrap_or(LoadMapOption::MainMenu);
+
+        let stopped = self.state.tokio.block_on(async {
+            self.state
+                .game
+                .call(GameSystemMessage::SaveMap, None)
+                .await?;
+            self.state
+                .game
+                .stop(Some("Reloading resources".to_string()));
+
+            if let Some(handle) = self.state.game_handle.take() {
+                handle.await?;
+            }
+
+            anyhow::Ok(())
+        });
+        if let Err(err) = stopped {
+            // the game may or may not be running at this point, so the new one is spawned regardless
+            log::error!("Could not stop the game to reload the resources! Error: {err:?}");
+        }
+
+        RESOURCE_MAN.write().unwrap().replace(resource_man.clone());
+        self.state.resource_man = resource_man;
+        self.state.loop_store.tag_cache.clear();
+
+        let (game, game_handle) = spawn_game(&self.state.tokio, self.state.resource_man.clone())
+            .expect("Could not spawn the game again after reloading the resources");
+        start_game_intervals(&self.state.tokio, &game);
+        self.state.game = game;
+        self.state.game_handle = Some(game_handle);
+
+        if let Some(renderer) = self.state.renderer.take() {
+            let GameRenderer { gpu, .. } = renderer;
+
+            let (shared_resources, render_resources, global_resources) = gpu::init_gpu_resources(
+                &gpu.device,
+                &gpu.config,
+                &self.state.resource_man,
+                vertices,
+                indices,
+            );
+
+            self.state.renderer = Some(GameRenderer::new(
+                gpu,
+                shared_resources,
+                render_resources,
+                Arc::new(global_resources),
+            ));
+        }
+
+        game_load_map_inner(&mut self.state, opt);
+
+        // sends the save options to the new game, and sets the world targets up on the new renderer
+        self.state.options.synced = false;
+        self.try_sync_options();
+
+        log::info!("Reloaded resources.");
+
+        let text = self
+            .state
+            .resource_man
+            .gui_str(
+                self.state
+                    .resource_man
+                    .registry
+                    .gui_ids
+                    .lbl_resources_reloaded,
+            )
+            .to_string();
+        self.state.ui_state.push_toast(ToastLevel::Info, text);
+    }
+
     fn try_sync_options(&mut self) {
         if !self.state.options.synced {
             {
@@ -202,19 +446,28 @@ impl Automancy {
             }
 
             self.state
-                .audio_man
-                .main_track()
-                .set_volume(self.state.options.audio.sfx_volume, Tween::default());
+                .audio
+                .apply_options(&mut self.state.audio_man, &self.state.options.audio);
 
-            self.state
-                .renderer
-                .as_mut()
-                .unwrap()
+            let graphics = &self.state.options.graphics;
+            let renderer = self.state.renderer.as_mut().unwrap();
+            renderer
                 .gpu
-                .set_vsync(self.state.options.graphics.fps_limit == 0);
+                .set_present_mode(graphics.fps_limit == 0, graphics.present_mode);
+            renderer.set_world_targets(graphics.msaa, graphics.render_scale);
 
             self.fps_limit = Some(self.state.options.graphics.fps_limit);
 
+            self.state.camera.apply_options(&self.state.options.camera);
+
+            if let Err(err) = self
+                .state
+                .game
+                .send_message(GameSystemMessage::SetSaveOptions(self.state.options.saving))
+            {
+                log::warn!("Could not set the map save options! Error: {err}");
+            }
+
             if self.state.options.graphics.fullscreen {
                 self.state
                     .renderer
@@ -266,6 +519,11 @@ impl ApplicationHandler for Automancy {
             self.state.options.graphics.fps_limit == 0,
         ));
 
+        if let Ok(mut context) = CRASH_CONTEXT.lock() {
+            context.adapter_info =
+                ron::ser::to_string_pretty(&gpu.adapter_info, PrettyConfig::default()).ok();
+        }
+
         log::info!("Setting up rendering...");
         let (shared_resources, render_resources, global_resources) = gpu::init_gpu_resources(
             &gpu.device,
@@ -322,6 +580,19 @@ impl ApplicationHandler for Automancy {
         self.state.gui = Some(gui);
         self.state.renderer = Some(renderer);
 
+        if let Some(path) = crash::take_last_crash() {
+            let text = self.state.resource_man.gui_fmt(
+                self.state
+                    .resource_man
+                    .registry
+                    .gui_ids
+                    .lbl_crash_report_saved,
+                [("path", Formattable::display(&path))],
+            );
+
+            self.state.ui_state.push_toast(ToastLevel::Info, text);
+        }
+
         self.try_sync_options();
     }
 
@@ -354,6 +625,7 @@ impl ApplicationHandler for Automancy {
                 }
             }
 
+            self.try_reload_resources();
             self.try_sync_options();
         }
     }
@@ -381,6 +653,12 @@ impl ApplicationHandler for Automancy {
     }
 
     fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        if !self.closed {
+            if let Err(e) = event::on_gamepad_events(&mut self.state) {
+                log::warn!("Gamepad event error: {e}");
+            }
+        }
+
         let fps_limit = self.fps_limit.unwrap_or(0);
 
         if fps_limit != 0 {
@@ -411,7 +689,13 @@ fn main() -> anyhow::Result<()> {
     {
         let filter = "info,wgpu_core::device::resource=warn";
 
-        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(filter)).init();
+        let logger =
+            env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(filter))
+                .build();
+        let max_level = logger.filter();
+
+        log::set_boxed_logger(Box::new(HistoryLogger { inner: logger }))?;
+        log::set_max_level(max_level);
 
         #[cfg(debug_assertions)]
         {
@@ -427,18 +711,22 @@ fn main() -> anyhow::Result<()> {
         }
     }
 
+    let headless = headless::HeadlessArgs::parse(env::args().skip(1))?;
+
     {
         let eyre = HookBuilder::blank()
             .capture_span_trace_by_default(true)
             .display_env_section(false);
 
         let (panic_hook, eyre_hook) = eyre.into_hooks();
+        let headless_run = headless.is_some();
 
         eyre_hook.install()?;
 
         panic::set_hook(Box::new(move |info| {
             let file_path = {
                 let report = panic_hook.panic_report(info);
+                let bundle = write_crash_bundle(&strip_ansi_escapes::strip_str(report.to_string()));
 
                 let uuid = Uuid::new_v4().hyphenated().to_string();
                 let tmp_dir = env::temp_dir();
@@ -455,9 +743,14 @@ fn main() -> anyhow::Result<()> {
                 }
                 eprintln!("{}", report);
 
-                file_path
+                bundle.unwrap_or(file_path)
             };
 
+            // there is no one to click through a dialog in headless mode
+            if headless_run {
+                return;
+            }
+
             if let Some(location) = info.location() {
                 if !["src/game.rs", "src/tile_entity.rs"].contains(&location.file()) {
                     let message = {
@@ -482,6 +775,14 @@ fn main() -> anyhow::Result<()> {
         }));
     }
 
+    if let Some(args) = headless {
+        return headless::run(args);
+    }
+
+    if !ensure_resource_packs() {
+        return Ok(());
+    }
+
     let event_loop = EventLoop::new()?;
 
     let mut state = {
@@ -498,54 +799,70 @@ fn main() -> anyhow::Result<()> {
             builder
         })?;
 
-        let misc_options = MiscOptions::load();
+        let mut misc_options = MiscOptions::load();
 
-        let (resource_man, vertices, indices) = load_resources(&misc_options.language, track);
+        let (resource_man, vertices, indices) =
+            load_resources(ResourceManager::new(track), &misc_options.language)
+                .expect("Error loading resources");
         RESOURCE_MAN.write().unwrap().replace(resource_man.clone());
         log::info!("Loaded resources.");
 
         let options = GameOptions::load(&resource_man);
+        let audio = AudioChannels::new(&mut audio_man, &options.audio)?;
         let input_handler = InputHandler::new(&options);
 
         let mut loop_store = EventLoopStorage::default();
         let camera = GameCamera::new((1.0, 1.0)); // dummy value
 
-        log::info!("Creating game...");
-        let (game, game_handle) = tokio.block_on(Actor::spawn(
-            Some("game".to_string()),
-            GameSystem {
-                resource_man: resource_man.clone(),
-            },
-            (),
-        ))?;
-        {
-            let game = game.clone();
-            tokio.spawn(async move {
-                game.send_interval(TICK_INTERVAL, || GameSystemMessage::Tick);
-            });
+        let (game, game_handle) = spawn_game(&tokio, resource_man.clone())?;
+        start_game_intervals(&tokio, &game);
+        actor_stats::spawn_flush_task(&tokio);
+        if let Some(port) = misc_options.metrics_port {
+            metrics::spawn_server(&tokio, port);
         }
-        log::info!("Game created.");
 
         let start_instant = Instant::now();
         ui_game_object::init_custom_paint_state(start_instant);
         loop_store.frame_start = Some(start_instant);
 
+        let mut ui_state = UiState::default();
+
+        // show what changed since the version the game was last run on
+        if misc_options.last_seen_version.as_deref() != Some(VERSION) {
+            let entries = changelog::entries_since(
+                &changelog::read_changelog(),
+                misc_options.last_seen_version.as_deref(),
+                VERSION,
+            );
+
+            if !entries.is_empty() {
+                ui_state.popup = PopupState::Changelog(entries);
+            }
+
+            misc_options.last_seen_version = Some(VERSION.to_string());
+            if let Err(err) = misc_options.save() {
+                log::error!("Error saving misc options! {err}");
+            }
+        }
+
         GameState {
-            ui_state: UiState::default(),
+            ui_state,
             options,
             misc_options,
             resource_man,
             input_handler,
+            gamepads: Gamepads::new(),
             loop_store,
             tokio,
             game,
             camera,
             audio_man,
+            audio,
             start_instant,
 
             gui: None,
             renderer: None,
-            screenshotting: false,
+            screenshotting: None,
 
             logo: Default::default(),
             input_hints: Default::default(),